@@ -53,6 +53,7 @@ interface OrderDto {
   chain_id?: number;
 }
 
+// Activity times are ISO-8601 UTC strings; `timestamp` is the unix seconds the timeline is sorted by
 interface TradeActivity {
   type: 'trade';
   trade_id: string;
@@ -64,7 +65,8 @@ interface TradeActivity {
   cny_amount: string;
   cny_amount_formatted: string;
   settlement_tx: string | null;
-  settled_at: number;
+  settled_at: string;
+  timestamp: number;
 }
 
 interface PendingTradeActivity {
@@ -75,8 +77,9 @@ interface PendingTradeActivity {
   token_amount_formatted: string;
   cny_amount: string;
   cny_amount_formatted: string;
-  created_at: number;
-  expires_at: number;
+  created_at: string;
+  expires_at: string;
+  timestamp: number;
 }
 
 interface ExpiredTradeActivity {
@@ -87,8 +90,9 @@ interface ExpiredTradeActivity {
   token_amount_formatted: string;
   cny_amount: string;
   cny_amount_formatted: string;
-  created_at: number;
-  expired_at: number;
+  created_at: string;
+  expired_at: string;
+  timestamp: number;
 }
 
 interface WithdrawalActivity {
@@ -99,6 +103,7 @@ interface WithdrawalActivity {
   remaining_after_formatted: string;
   tx_hash: string | null;
   created_at: string;
+  timestamp: number;
 }
 
 type Activity = TradeActivity | PendingTradeActivity | ExpiredTradeActivity | WithdrawalActivity;
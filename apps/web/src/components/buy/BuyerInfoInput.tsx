@@ -156,7 +156,8 @@ export function BuyerInfoInput({ flowData, updateFlowData, goToNextStep, onPriva
 
   // Handle private code lookup
   const handlePrivateCodeLookup = async () => {
-    if (privateCode.length !== 6) {
+    // 22-character base62 codes; orders made private earlier may still have a 6-digit one
+    if (privateCode.length !== 22 && privateCode.length !== 6) {
       setPrivateCodeError(t('privateCode.invalid'));
       return;
     }
@@ -316,18 +317,18 @@ export function BuyerInfoInput({ flowData, updateFlowData, goToNextStep, onPriva
                   <div className="flex gap-3">
                     <Input
                       type="text"
-                      inputMode="numeric"
-                      pattern="[0-9]*"
-                      maxLength={6}
+                      autoComplete="off"
+                      spellCheck={false}
+                      maxLength={22}
                       placeholder={t('privateCode.placeholder')}
                       value={privateCode}
                       onChange={(e) => {
-                        const value = e.target.value.replace(/\D/g, '').slice(0, 6);
+                        const value = e.target.value.replace(/[^0-9A-Za-z]/g, '').slice(0, 22);
                         setPrivateCode(value);
                         setPrivateCodeError(null);
                         setPrivateOrder(null);
                       }}
-                      className="flex-1 h-12 text-center text-2xl font-mono tracking-[0.5em] rounded-xl 
+                      className="flex-1 h-12 text-center text-lg font-mono tracking-wide rounded-xl 
                                  bg-transparent backdrop-blur-sm border border-indigo-300/20 
                                  focus:border-indigo-400/40 focus:ring-1 focus:ring-indigo-300/20
                                  placeholder:text-slate-400 placeholder:tracking-normal placeholder:text-base"
@@ -74,11 +74,13 @@ export function PaymentInstructions({ trades, onAllSettled }: PaymentInstruction
   }, [tradeStatuses, trades.length, onAllSettled]);
 
   // Poll for settlement status (backend handles proof generation automatically)
-  const startPollingForSettlement = (tradeId: string) => {
+  const startPollingForSettlement = (tradeId: string, jobId?: number) => {
     let proofGeneratedTime: number | null = null;
     
     const pollInterval = setInterval(async () => {
       try {
+        // A failed proof job won't settle; the trade's settlement_error (if any) is more specific
+        const job = jobId !== undefined ? await api.getProofJob(jobId) : null;
         const trade = await api.getTrade(tradeId);
         
         // Check if trade is now settled (status === 1)
@@ -119,6 +121,22 @@ export function PaymentInstructions({ trades, onAllSettled }: PaymentInstruction
           return;
         }
         
+        if (job?.status === 'failed') {
+          console.error('❌ Proof job failed:', job.error);
+          clearInterval(pollInterval);
+          
+          setTradeStatuses((prev) => {
+            const updated = new Map(prev);
+            updated.set(tradeId, {
+              ...prev.get(tradeId)!,
+              status: 'proof_failed',
+              error: t('errors.settlementFailed'),
+            });
+            return updated;
+          });
+          return;
+        }
+        
         // Check if proof was generated (proof_generated_at is set)
         if (trade.proof_generated_at && !proofGeneratedTime) {
           proofGeneratedTime = Date.now();
@@ -244,7 +262,7 @@ export function PaymentInstructions({ trades, onAllSettled }: PaymentInstruction
         
         // Start polling for trade status (settlement happens in background)
         // The polling will detect when status changes to 'settled'
-        startPollingForSettlement(tradeId);
+        startPollingForSettlement(tradeId, validationResponse.job_id);
         
       } else {
         // PDF is invalid - translate error based on validation_code
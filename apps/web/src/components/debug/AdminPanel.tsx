@@ -1,6 +1,7 @@
 'use client';
 
 import { useState, useEffect } from 'react';
+import { getAuthToken } from '@/hooks/useAuth';
 import { Card, CardHeader, CardTitle, CardContent } from '@/components/ui/card';
 import { Alert, AlertDescription } from '@/components/ui/alert';
 
@@ -50,8 +51,15 @@ export default function AdminPanel() {
       setLoading(true);
       const API_URL = process.env.NEXT_PUBLIC_API_URL || 'http://localhost:3001';
       const params = forceRefresh ? '?refresh=true' : '';
-      const response = await fetch(`${API_URL}/api/admin/config${params}`);
+      // Admin routes need an admin wallet's JWT
+      const token = getAuthToken();
+      const response = await fetch(`${API_URL}/api/admin/config${params}`, {
+        headers: token ? { Authorization: `Bearer ${token}` } : {},
+      });
       
+      if (response.status === 401) {
+        throw new Error('sign in with an admin wallet');
+      }
       if (!response.ok) {
         throw new Error(`HTTP error! status: ${response.status}`);
       }
@@ -169,7 +169,7 @@ export function CreateOrderForm({ onSwitchToManage }: CreateOrderFormProps = {})
       return;
     }
     
-    if (!amount || !exchangeRate || !accountId || !accountName) {
+    if (!amount || !exchangeRate || !accountId || !accountName.trim()) {
       return;
     }
 
@@ -183,7 +183,8 @@ export function CreateOrderForm({ onSwitchToManage }: CreateOrderFormProps = {})
       exchangeRate: rateInCents,
       rail: paymentRail,
       accountId,
-      accountName,
+      // Trimmed so the stored name matches the one hashed on-chain
+      accountName: accountName.trim(),
       isPublic: isPublicListing,  // v4: public/private flag
       chainId: selectedChainId,   // multi-chain support
     };
@@ -205,7 +206,7 @@ export function CreateOrderForm({ onSwitchToManage }: CreateOrderFormProps = {})
   const isFormValid = () => {
     // Block form if not on correct chain or wallet not on selected chain
     if (!canInteract || !isOnSelectedChain) return false;
-    if (!amount || !exchangeRate || !accountId || !accountName) return false;
+    if (!amount || !exchangeRate || !accountId || !accountName.trim()) return false;
     if (parseFloat(amount) <= 0 || parseFloat(exchangeRate) <= 0) return false;
     if (tokenBalance && parseUnits(amount, tokenInfo.decimals) > tokenBalance.value) return false;
     return true;
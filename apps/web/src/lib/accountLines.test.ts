@@ -0,0 +1,27 @@
+// Cross-checks the frontend account lines hash against the relay's vectors in
+// services/relay/src/crypto/hash.rs (test_account_lines_known_vectors). If either
+// side changes how it normalizes input, both tests must be updated together.
+//
+// Run with: npm test (Node 22.6+, uses --experimental-strip-types)
+import { test } from 'node:test';
+import assert from 'node:assert/strict';
+import { computeAccountLinesHash, maskAlipayAccountId } from './accountLines.ts';
+
+const VECTORS: [name: string, id: string, hash: string][] = [
+  ['张三', '13800138000', '0x4905c4dcca29e1ac9a9522f98e7bc8afc54f8cb79e6f11557b3664cd592169dd'],
+  ['TestName', 'test@example.com', '0xce7bdc250e8ab9a62b66309c474ca6b3f4ebfd3a3b777c8a4f48fdb8201eab48'],
+  // Padded names hash like their trimmed form, as the relay trims before hashing
+  [' 张三\t', '13800138000', '0x4905c4dcca29e1ac9a9522f98e7bc8afc54f8cb79e6f11557b3664cd592169dd'],
+  ['  TestName ', 'test@example.com', '0xce7bdc250e8ab9a62b66309c474ca6b3f4ebfd3a3b777c8a4f48fdb8201eab48'],
+];
+
+test('account lines hash matches the relay vectors', async () => {
+  for (const [name, id, hash] of VECTORS) {
+    assert.equal(await computeAccountLinesHash(name, id), hash, JSON.stringify(name));
+  }
+});
+
+test('account id masking', () => {
+  assert.equal(maskAlipayAccountId('13800138000'), '138******00');
+  assert.equal(maskAlipayAccountId('test@example.com'), 'tes***@example.com');
+});
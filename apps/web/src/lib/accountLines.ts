@@ -0,0 +1,109 @@
+// ============================================================================
+// Account Lines Hash (v4 - Privacy)
+// ============================================================================
+//
+// Kept free of imports so it runs under plain Node for the cross-check against
+// the relay's vectors (services/relay/src/crypto/hash.rs).
+
+/**
+ * Mask an Alipay account ID the same way Alipay masks it in PDF receipts.
+ * This ensures our hash matches what the ZK circuit reads from the PDF.
+ * 
+ * Examples:
+ *   - "test@example.com" -> "tes***@example.com" (email)
+ *   - "13800138000" -> "138******00" (Chinese phone - first 3 + 6 asterisks + last 2)
+ *   - "1-3125551212" -> "1-312*****12" (international phone - first 5 + 5 asterisks + last 2)
+ * 
+ * Pattern:
+ *   - Email: Keep first 3 chars + "***" + "@" + domain
+ *   - Chinese phone (11 digits): Keep first 3 + "******" + last 2
+ *   - International phone (has dash): Keep first 5 + "*****" + last 2
+ *   - Other: Keep first 3 + "***" (fallback)
+ */
+export function maskAlipayAccountId(accountId: string): string {
+  const trimmed = accountId.trim();
+  
+  // Check if it's an email
+  const atIndex = trimmed.indexOf('@');
+  if (atIndex > 0) {
+    const localPart = trimmed.substring(0, atIndex);
+    const domain = trimmed.substring(atIndex); // includes @
+    if (localPart.length <= 3) {
+      return localPart + '***' + domain;
+    }
+    return localPart.substring(0, 3) + '***' + domain;
+  }
+  
+  // Check if it's a Chinese phone number (11 digits, all numeric)
+  // Alipay masks as: first 3 + 6 asterisks + last 2
+  if (/^\d{11}$/.test(trimmed)) {
+    return trimmed.substring(0, 3) + '******' + trimmed.substring(9);
+  }
+  
+  // Check if it's an international phone number (starts with digit, contains dash)
+  // Pattern: first 5 + 5 asterisks + last 2
+  // Example: "1-3125551212" -> "1-312*****12"
+  if (trimmed.length >= 10 && /^\d/.test(trimmed) && trimmed.includes('-')) {
+    if (trimmed.length >= 7) {
+      return trimmed.substring(0, 5) + '*****' + trimmed.substring(trimmed.length - 2);
+    }
+  }
+  
+  // Fallback: keep first 3 chars + ***
+  if (trimmed.length <= 3) {
+    return trimmed + '***';
+  }
+  return trimmed.substring(0, 3) + '***';
+}
+
+/**
+ * Compute accountLinesHash = SHA256(20 || line20 || 21 || line21)
+ * 
+ * IMPORTANT: Must match exactly what the ZK circuit reads from the PDF!
+ * 
+ * In Alipay PDFs:
+ *   - Line 20 = "账户名：" + accountName (e.g., "账户名：张三")
+ *   - Line 21 = "账号：" + maskedAccountId (e.g., "账号：138******88")
+ * 
+ * The ZK circuit hashes the FULL line text including prefixes,
+ * so we must build the same format here.
+ */
+export async function computeAccountLinesHash(accountName: string, accountId: string): Promise<`0x${string}`> {
+  // Build full line text as it appears in Alipay PDF
+  // NOTE: Alipay converts English names to UPPERCASE in their receipts, so we must match
+  // Chinese names are left as-is (Chinese characters don't have uppercase/lowercase)
+  // Leading/trailing whitespace is trimmed (inner spaces kept), like the relay does
+  const name = accountName.trim();
+  const isAsciiName = /^[\x00-\x7F]*$/.test(name);
+  const formattedName = isAsciiName ? name.toUpperCase() : name;
+  const line20 = '账户名：' + formattedName;
+  const line21 = '账号：' + maskAlipayAccountId(accountId);
+  
+  // Build the data buffer: 20 (LE u32) || line20 bytes || 21 (LE u32) || line21 bytes
+  const encoder = new TextEncoder();
+  const line20Bytes = encoder.encode(line20);
+  const line21Bytes = encoder.encode(line21);
+  
+  // Create buffer: 4 bytes (20 as LE u32) + line20 + 4 bytes (21 as LE u32) + line21
+  const buffer = new Uint8Array(4 + line20Bytes.length + 4 + line21Bytes.length);
+  
+  // Write 20 as little-endian u32
+  const view = new DataView(buffer.buffer);
+  view.setUint32(0, 20, true); // true = little-endian
+  buffer.set(line20Bytes, 4);
+  
+  // Write 21 as little-endian u32
+  view.setUint32(4 + line20Bytes.length, 21, true);
+  buffer.set(line21Bytes, 4 + line20Bytes.length + 4);
+  
+  // Compute SHA256 hash
+  const hashBuffer = await crypto.subtle.digest('SHA-256', buffer);
+  const hashArray = new Uint8Array(hashBuffer);
+  
+  // Convert to hex string with 0x prefix
+  const hashHex = Array.from(hashArray)
+    .map(b => b.toString(16).padStart(2, '0'))
+    .join('');
+  
+  return `0x${hashHex}` as `0x${string}`;
+}
@@ -1,5 +1,5 @@
 import axios from 'axios';
-// import { getAuthToken } from '@/hooks/useAuth';  // TODO: re-enable when auth is restored
+import { getAuthToken } from '@/hooks/useAuth';
 
 // Default to Railway backend
 const API_BASE = process.env.NEXT_PUBLIC_API_URL || 'https://lyncz-web-production.up.railway.app';
@@ -161,6 +161,7 @@ export const api = {
     message?: string;
     transaction_id?: string;
     payment_time?: string;
+    job_id?: number; // Proof job queued when valid (poll getProofJob)
   }> {
     const formData = new FormData();
     formData.append('pdf', pdfFile);
@@ -179,6 +180,18 @@ export const api = {
     };
   },
 
+  // Step 2: Poll the queued proof job (proof generation + settlement)
+  async getProofJob(jobId: number): Promise<{
+    job_id: number;
+    trade_id: string;
+    status: 'queued' | 'running' | 'done' | 'failed';
+    result: { settlement_tx_hash: string | null } | null;
+    error: string | null;
+  }> {
+    const response = await axios.get(`${API_BASE}/api/proofs/${jobId}`);
+    return response.data;
+  },
+
   getPdfUrl(tradeId: string): string {
     return `${API_BASE}/api/trades/${tradeId}/pdf`;
   },
@@ -205,7 +218,11 @@ export const api = {
     app_vm_commit?: string;
   }> {
     const params = forceRefresh ? '?refresh=true' : '';
-    const response = await axios.get(`${API_BASE}/api/admin/config${params}`);
+    // Admin routes need an admin wallet's JWT
+    const token = getAuthToken();
+    const response = await axios.get(`${API_BASE}/api/admin/config${params}`, {
+      headers: token ? { Authorization: `Bearer ${token}` } : {},
+    });
     return response.data;
   },
 
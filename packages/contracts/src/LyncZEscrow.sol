@@ -568,6 +568,21 @@ contract LyncZEscrow is ReentrancyGuard, Ownable, Pausable {
      * @param tradeId Trade identifier
      */
     function cancelExpiredTrade(bytes32 tradeId) external nonReentrant {
+        _cancelExpiredTrade(tradeId);
+    }
+    
+    /**
+     * @notice Cancel several expired trades in one transaction
+     * @dev Reverts as a whole if any trade cannot be cancelled
+     * @param tradeIds Trade identifiers
+     */
+    function cancelExpiredTradesBatch(bytes32[] calldata tradeIds) external nonReentrant {
+        for (uint256 i = 0; i < tradeIds.length; i++) {
+            _cancelExpiredTrade(tradeIds[i]);
+        }
+    }
+    
+    function _cancelExpiredTrade(bytes32 tradeId) internal {
         Trade storage trade = trades[tradeId];
         Order storage order = orders[trade.orderId];
         
@@ -0,0 +1,193 @@
+// SPDX-License-Identifier: MIT
+pragma solidity ^0.8.20;
+
+import "forge-std/Test.sol";
+import "../src/LyncZEscrow.sol";
+import "../src/mocks/MockUSDC.sol";
+
+/// @dev Flat fee in token units, independent of the token
+contract FlatFeeCalculator is IFeeCalculator {
+    uint256 public immutable fee;
+
+    constructor(uint256 _fee) {
+        fee = _fee;
+    }
+
+    function calculateFee(uint256, uint256, address, address, bool) external view returns (uint256) {
+        return fee;
+    }
+
+    function getFeeRate() external view returns (uint256) {
+        return fee;
+    }
+
+    function getPrivateFeeRate() external view returns (uint256) {
+        return fee;
+    }
+}
+
+/// @dev Accepts every proof
+contract AcceptAllVerifier is ILyncZVerifier {
+    function verifyPayment(bytes32, bytes calldata, bytes calldata, bytes32, bytes32, uint256, string calldata)
+        external
+        pure
+        returns (bool)
+    {
+        return true;
+    }
+}
+
+/**
+ * @title LyncZEscrowBatchCancelTest
+ * @notice cancelExpiredTradesBatch: expired, mixed and empty batches
+ */
+contract LyncZEscrowBatchCancelTest is Test {
+    uint256 constant PAYMENT_WINDOW = 900;
+    uint256 constant FEE = 20000; // 0.02 USDC
+    uint256 constant ORDER_AMOUNT = 10_000e6; // 10,000 USDC
+    uint256 constant EXCHANGE_RATE = 720; // 7.20 CNY per USDC, in fiat cents
+    uint256 constant FIAT_AMOUNT = 72000; // 720 CNY -> 100 USDC
+
+    event TradeExpired(bytes32 indexed tradeId, bytes32 indexed orderId, uint256 totalReturned);
+
+    LyncZEscrow escrow;
+    MockUSDC usdc;
+    bytes32 orderId;
+
+    address seller = makeAddr("seller");
+    address buyer = makeAddr("buyer");
+
+    function setUp() public {
+        usdc = new MockUSDC();
+        escrow = new LyncZEscrow(70000, 7200000, PAYMENT_WINDOW, address(new FlatFeeCalculator(FEE)));
+        escrow.setVerifier(LyncZEscrow.PaymentRail.ALIPAY, address(new AcceptAllVerifier()));
+
+        usdc.mint(seller, ORDER_AMOUNT);
+        vm.startPrank(seller);
+        usdc.approve(address(escrow), ORDER_AMOUNT);
+        orderId = escrow.createOrder(
+            address(usdc), ORDER_AMOUNT, EXCHANGE_RATE, LyncZEscrow.PaymentRail.ALIPAY, keccak256("account lines"), true
+        );
+        vm.stopPrank();
+    }
+
+    function _fill() internal returns (bytes32) {
+        return escrow.fillOrder(orderId, buyer, FIAT_AMOUNT);
+    }
+
+    function _remaining() internal view returns (uint256 remaining) {
+        (,,,, remaining,,,,,,) = escrow.orders(orderId);
+    }
+
+    function _batch(bytes32 a, bytes32 b) internal pure returns (bytes32[] memory ids) {
+        ids = new bytes32[](2);
+        ids[0] = a;
+        ids[1] = b;
+    }
+
+    function test_ExpiredBatchCancelsEveryTrade() public {
+        bytes32 first = _fill();
+        bytes32 second = _fill();
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+
+        uint256 reserved = escrow.getTrade(first).tokenAmount + FEE;
+        vm.expectEmit(true, true, false, true, address(escrow));
+        emit TradeExpired(first, orderId, reserved);
+        vm.expectEmit(true, true, false, true, address(escrow));
+        emit TradeExpired(second, orderId, reserved);
+        escrow.cancelExpiredTradesBatch(_batch(first, second));
+
+        assertEq(escrow.getTradeStatus(first), uint8(LyncZEscrow.TradeStatus.EXPIRED));
+        assertEq(escrow.getTradeStatus(second), uint8(LyncZEscrow.TradeStatus.EXPIRED));
+    }
+
+    function test_ExpiredBatchRestoresRemainingAmountAndFees() public {
+        bytes32 first = _fill();
+        bytes32 second = _fill();
+        uint256 tokenAmount = escrow.getTrade(first).tokenAmount;
+        assertEq(tokenAmount, 100e6);
+        assertEq(_remaining(), ORDER_AMOUNT - 2 * (tokenAmount + FEE));
+
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+        escrow.cancelExpiredTradesBatch(_batch(first, second));
+
+        // Both the buyer's amount and the fee go back to the order
+        assertEq(_remaining(), ORDER_AMOUNT);
+        assertEq(escrow.getTradeFee(first), 0);
+        assertEq(escrow.getTradeFee(second), 0);
+        // No tokens left the escrow
+        assertEq(usdc.balanceOf(address(escrow)), ORDER_AMOUNT);
+    }
+
+    function test_BatchWithUnexpiredTradeRevertsAsAWhole() public {
+        bytes32 expired = _fill();
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+        bytes32 fresh = _fill();
+        uint256 remainingBefore = _remaining();
+
+        vm.expectRevert(LyncZEscrow.TradeNotExpired.selector);
+        escrow.cancelExpiredTradesBatch(_batch(expired, fresh));
+
+        // The expired trade in the batch was not cancelled either
+        assertEq(escrow.getTradeStatus(expired), uint8(LyncZEscrow.TradeStatus.PENDING));
+        assertEq(escrow.getTradeFee(expired), FEE);
+        assertEq(_remaining(), remainingBefore);
+    }
+
+    function test_BatchWithSettledTradeRevertsAsAWhole() public {
+        bytes32 settled = _fill();
+        bytes32 expired = _fill();
+        escrow.submitProof(settled, keccak256("tx"), "2025-01-01 12:00:00", bytes32(0), "", "");
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+        uint256 remainingBefore = _remaining();
+
+        vm.expectRevert(LyncZEscrow.TradeNotPending.selector);
+        escrow.cancelExpiredTradesBatch(_batch(expired, settled));
+
+        assertEq(escrow.getTradeStatus(settled), uint8(LyncZEscrow.TradeStatus.SETTLED));
+        assertEq(escrow.getTradeStatus(expired), uint8(LyncZEscrow.TradeStatus.PENDING));
+        assertEq(_remaining(), remainingBefore);
+
+        // Without the settled trade the batch goes through
+        bytes32[] memory ids = new bytes32[](1);
+        ids[0] = expired;
+        escrow.cancelExpiredTradesBatch(ids);
+        assertEq(escrow.getTradeStatus(expired), uint8(LyncZEscrow.TradeStatus.EXPIRED));
+    }
+
+    function test_BatchWithAlreadyExpiredTradeReverts() public {
+        bytes32 first = _fill();
+        bytes32 second = _fill();
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+        escrow.cancelExpiredTrade(first);
+        uint256 remainingBefore = _remaining();
+
+        vm.expectRevert(LyncZEscrow.TradeNotPending.selector);
+        escrow.cancelExpiredTradesBatch(_batch(second, first));
+
+        // The first trade's reserve isn't returned twice
+        assertEq(escrow.getTradeStatus(second), uint8(LyncZEscrow.TradeStatus.PENDING));
+        assertEq(_remaining(), remainingBefore);
+    }
+
+    function test_BatchWithUnknownTradeReverts() public {
+        bytes32 expired = _fill();
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+
+        vm.expectRevert(LyncZEscrow.TradeNotFound.selector);
+        escrow.cancelExpiredTradesBatch(_batch(expired, keccak256("unknown")));
+    }
+
+    function test_EmptyBatchIsANoOp() public {
+        bytes32 tradeId = _fill();
+        vm.warp(block.timestamp + PAYMENT_WINDOW + 1);
+        uint256 remainingBefore = _remaining();
+
+        vm.recordLogs();
+        escrow.cancelExpiredTradesBatch(new bytes32[](0));
+
+        assertEq(vm.getRecordedLogs().length, 0);
+        assertEq(escrow.getTradeStatus(tradeId), uint8(LyncZEscrow.TradeStatus.PENDING));
+        assertEq(_remaining(), remainingBefore);
+    }
+}
@@ -0,0 +1,17 @@
+-- ============================================================================
+-- Migration 003: Auto-Cancel Failure Tracking
+-- Purpose: Flag trades whose cancelExpiredTrade keeps failing for manual review
+-- ============================================================================
+--
+-- The trade stays PENDING (status mirrors the contract), but once flagged the
+-- auto-cancel service stops retrying it. Clear cancel_failed_at to re-enable.
+--
+-- ============================================================================
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS cancel_failed_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS cancel_error TEXT;
+
+CREATE INDEX IF NOT EXISTS idx_trades_cancel_failed ON trades(cancel_failed_at) WHERE cancel_failed_at IS NOT NULL;
+
+COMMENT ON COLUMN trades.cancel_failed_at IS 'Set when auto-cancel gave up after repeated failures (needs manual review)';
+COMMENT ON COLUMN trades.cancel_error IS 'Last cancelExpiredTrade error before auto-cancel gave up';
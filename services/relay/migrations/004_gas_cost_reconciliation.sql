@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Migration 004: Gas Cost Reconciliation
+-- Purpose: Track which gas_costs rows were corrected against on-chain receipts
+-- ============================================================================
+--
+-- Gas costs recorded at send time may be estimates. A background job fetches
+-- the receipt for each recent row, overwrites gasUsed/gasPriceGwei/costWei/
+-- costEth with the actual values, and sets "reconciledAt".
+--
+-- ============================================================================
+
+ALTER TABLE gas_costs ADD COLUMN IF NOT EXISTS "reconciledAt" TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS "idx_gas_costs_unreconciled" ON gas_costs("createdAt") WHERE "reconciledAt" IS NULL;
+
+COMMENT ON COLUMN gas_costs."reconciledAt" IS 'When gas values were replaced with on-chain receipt values (NULL = estimate)';
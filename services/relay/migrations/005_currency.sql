@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Migration 005: Fiat Currency
+-- Purpose: Record which fiat currency an order is quoted in (and its trades)
+-- ============================================================================
+--
+-- fiatAmount / exchangeRate stay integers in the currency's minor unit.
+-- Existing rows were all quoted in CNY, which is also the default for new rows.
+--
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS currency VARCHAR(3) NOT NULL DEFAULT 'CNY';
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS currency VARCHAR(3) NOT NULL DEFAULT 'CNY';
+
+COMMENT ON COLUMN orders.currency IS 'ISO 4217 fiat currency code the order is quoted in';
+COMMENT ON COLUMN trades.currency IS 'ISO 4217 fiat currency code of cnyAmount (copied from the order)';
@@ -0,0 +1,28 @@
+-- ============================================================================
+-- Migration 006: Email Outbox
+-- Purpose: Keep notification emails that failed to send so they can be retried
+-- ============================================================================
+--
+-- Emails are stored already rendered (subject + html in the account's language).
+-- The outbox worker retries pending rows with backoff until they are delivered
+-- or give up after the maximum number of attempts (status = 'failed').
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS email_outbox (
+    id BIGSERIAL PRIMARY KEY,
+    to_email VARCHAR(255) NOT NULL,
+    subject TEXT NOT NULL,
+    html TEXT NOT NULL,
+    event VARCHAR(64) NOT NULL,                         -- EmailEvent name (for monitoring)
+    status VARCHAR(16) NOT NULL DEFAULT 'pending',      -- pending | delivered | failed
+    attempts INTEGER NOT NULL DEFAULT 1,                -- includes the original failed send
+    last_error TEXT,
+    next_attempt_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW()
+);
+
+CREATE INDEX IF NOT EXISTS idx_email_outbox_due ON email_outbox(next_attempt_at) WHERE status = 'pending';
+
+COMMENT ON TABLE email_outbox IS 'Notification emails awaiting retry after a failed send';
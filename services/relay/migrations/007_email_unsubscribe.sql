@@ -0,0 +1,8 @@
+-- ============================================================================
+-- Migration 007: Email Unsubscribe Links
+-- Purpose: Keep the List-Unsubscribe URL with queued emails so retries carry it
+-- ============================================================================
+
+ALTER TABLE email_outbox ADD COLUMN IF NOT EXISTS unsubscribe_url TEXT;
+
+COMMENT ON COLUMN email_outbox.unsubscribe_url IS 'One-click unsubscribe link, sent as the List-Unsubscribe header';
@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migration 008: PDF Object Storage
+-- Purpose: Allow receipt PDFs to live in an S3-compatible bucket
+-- ============================================================================
+--
+-- When a bucket is configured, pdf_storage_key holds the object key
+-- (trades/{chainId}/{tradeId}.pdf) and pdf_file stays NULL. Without a bucket,
+-- pdf_file keeps the bytes as before.
+--
+-- ============================================================================
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS pdf_storage_key TEXT;
+
+COMMENT ON COLUMN trades.pdf_storage_key IS 'Object key of the receipt PDF in the PDF bucket (NULL = stored in pdf_file or not uploaded)';
@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Migration 009: Trade Expiry Reminders
+-- Purpose: Remember which pending trades already got their expiry reminder
+-- ============================================================================
+--
+-- The reminder job claims a trade by setting expiry_reminder_sent_at (only
+-- while it is NULL), so each buyer is reminded at most once per trade, even
+-- across restarts or with several API instances running.
+--
+-- ============================================================================
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS expiry_reminder_sent_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_trades_pending_expiry ON trades("expiresAt") WHERE status = 0 AND expiry_reminder_sent_at IS NULL;
+
+COMMENT ON COLUMN trades.expiry_reminder_sent_at IS 'When the buyer was reminded that the trade is about to expire (NULL = not yet)';
@@ -0,0 +1,22 @@
+-- ============================================================================
+-- Migration 010: Order-Created Email Fallback
+-- Purpose: Remember which orders already had their order-created email
+-- ============================================================================
+--
+-- The order-created email is normally sent when the frontend sets the order's
+-- visibility. If that call never happens (tab closed), a background job makes
+-- the order private and sends the email once ORDER_CREATED_EMAIL_GRACE_SECS
+-- have passed. Both paths set created_email_sent_at; the job only claims rows
+-- where it is still NULL.
+--
+-- Existing orders are marked as handled so the job doesn't email old orders.
+--
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS created_email_sent_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE orders SET created_email_sent_at = "syncedAt" WHERE created_email_sent_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_orders_created_email_pending ON orders("createdAt") WHERE created_email_sent_at IS NULL;
+
+COMMENT ON COLUMN orders.created_email_sent_at IS 'When the order-created email was handled (visibility set or fallback job); NULL = not yet';
@@ -0,0 +1,21 @@
+-- ============================================================================
+-- Migration 011: Order Closed Timestamp
+-- Purpose: Keep fully filled / withdrawn orders out of active listings cheaply
+-- ============================================================================
+--
+-- closed_at is set when an order's remaining amount reaches zero (last trade
+-- taken or everything withdrawn) and cleared again if a trade expires and
+-- returns funds to it. Active listings filter on closed_at IS NULL, which the
+-- index below serves without scanning the closed history.
+--
+-- Existing orders with nothing left are backfilled from their last sync time.
+--
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS closed_at TIMESTAMP WITH TIME ZONE;
+
+UPDATE orders SET closed_at = "syncedAt" WHERE "remainingAmount" = 0 AND closed_at IS NULL;
+
+CREATE INDEX IF NOT EXISTS idx_orders_isPublic_closed_at ON orders("isPublic", closed_at);
+
+COMMENT ON COLUMN orders.closed_at IS 'When remainingAmount reached 0 (filled or withdrawn); NULL = open';
@@ -0,0 +1,19 @@
+-- ============================================================================
+-- Migration 012: Longer Private Codes
+-- Purpose: Room for 22-character base62 private codes (128+ bits)
+-- ============================================================================
+--
+-- Private codes were 6 digits, few enough to guess. New codes are 22 base62
+-- characters from a CSPRNG (see crypto::private_code). Existing 6-digit codes
+-- keep working until the seller re-lists the order, which rotates the code.
+--
+-- Codes stay unique across orders: the relay retries on a clash with the
+-- unique index, which is (re)asserted here.
+--
+-- ============================================================================
+
+ALTER TABLE orders ALTER COLUMN "privateCode" TYPE VARCHAR(32);
+
+CREATE UNIQUE INDEX IF NOT EXISTS "idx_orders_privateCode" ON orders("privateCode") WHERE "privateCode" IS NOT NULL;
+
+COMMENT ON COLUMN orders."privateCode" IS 'Code for accessing private orders (22 base62 chars; legacy rows: 6 digits)';
@@ -0,0 +1,26 @@
+-- ============================================================================
+-- Migration 013: Daily Activity Digest
+-- Purpose: Opt-in daily summary email instead of one email per event
+-- ============================================================================
+--
+-- digest_enabled is the account's opt-in (off by default). The digest job
+-- claims an account for a day by moving digest_sent_for forward (only while it
+-- is older), so restarts and multiple API instances send at most one digest
+-- per account per day.
+--
+-- trades.settled_at records when a trade was marked settled, so a digest can
+-- count settlements by when they happened rather than when the trade started.
+-- Trades settled before this migration keep NULL and are not counted.
+--
+-- ============================================================================
+
+ALTER TABLE account_emails ADD COLUMN IF NOT EXISTS digest_enabled BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE account_emails ADD COLUMN IF NOT EXISTS digest_sent_for DATE;
+
+CREATE INDEX IF NOT EXISTS idx_account_emails_digest ON account_emails(wallet) WHERE enabled = TRUE AND digest_enabled = TRUE;
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS settled_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN account_emails.digest_enabled IS 'Opted in to the daily activity digest email';
+COMMENT ON COLUMN account_emails.digest_sent_for IS 'UTC day of the last digest sent (NULL = never)';
+COMMENT ON COLUMN trades.settled_at IS 'When the trade was marked settled (NULL = not settled, or settled before migration 013)';
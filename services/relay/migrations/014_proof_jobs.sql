@@ -0,0 +1,37 @@
+-- ============================================================================
+-- Migration 014: Proof Jobs
+-- Purpose: Queue Axiom proof generation + settlement instead of running it
+--          in a detached task per validate request
+-- ============================================================================
+--
+-- Validate enqueues a job and returns its id; clients poll GET /api/proofs/:id.
+-- A worker in the API server claims queued jobs (FOR UPDATE SKIP LOCKED, so
+-- several instances can share the queue) with a bounded number in flight.
+--
+-- Lifecycle: queued -> running -> done | failed. Jobs left running by a dead
+-- process are put back to queued once older than MAX_PROOF_DURATION_SECS.
+-- A trade has at most one active (queued or running) job.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS proofs (
+    id BIGSERIAL PRIMARY KEY,
+    trade_id VARCHAR(66) NOT NULL REFERENCES trades("tradeId") ON DELETE CASCADE,
+    transaction_id TEXT NOT NULL,                       -- Alipay transaction ID (hashed for submission)
+    payment_time TEXT NOT NULL,                         -- Payment timestamp from the receipt
+    status VARCHAR(16) NOT NULL DEFAULT 'queued',       -- queued | running | done | failed
+    attempts INTEGER NOT NULL DEFAULT 0,                -- times a worker picked the job up
+    settlement_tx_hash VARCHAR(66),                     -- set when done
+    error TEXT,                                         -- set when failed
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    started_at TIMESTAMP WITH TIME ZONE,
+    finished_at TIMESTAMP WITH TIME ZONE,
+    updated_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+
+    CONSTRAINT "proofs_status_valid" CHECK (status IN ('queued', 'running', 'done', 'failed'))
+);
+
+CREATE INDEX IF NOT EXISTS idx_proofs_queued ON proofs(id) WHERE status = 'queued';
+CREATE UNIQUE INDEX IF NOT EXISTS idx_proofs_active_trade ON proofs(trade_id) WHERE status IN ('queued', 'running');
+
+COMMENT ON TABLE proofs IS 'Proof generation + settlement jobs queued by validate';
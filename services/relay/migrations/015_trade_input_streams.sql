@@ -0,0 +1,23 @@
+-- ============================================================================
+-- Migration 015: Persisted Input Streams
+-- Purpose: Keep a trade's Axiom input streams across API restarts
+-- ============================================================================
+--
+-- Validate builds the OpenVM input streams from the receipt PDF; the proof
+-- job needs them later. They used to live only in memory, so a restart in
+-- between lost them. The relay now writes them here too and reads through its
+-- in-memory cache to this table. Rows are deleted once the trade settles and
+-- ignored (then purged) after expires_at.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS trade_input_streams (
+    trade_id VARCHAR(66) PRIMARY KEY REFERENCES trades("tradeId") ON DELETE CASCADE,
+    streams TEXT[] NOT NULL,                            -- hex-encoded OpenVM input streams
+    created_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    expires_at TIMESTAMP WITH TIME ZONE NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_trade_input_streams_expires ON trade_input_streams(expires_at);
+
+COMMENT ON TABLE trade_input_streams IS 'Axiom input streams per trade, between validation and settlement';
@@ -0,0 +1,19 @@
+-- ============================================================================
+-- Migration 016: Stale Order Delisting
+-- Purpose: Drop abandoned orders from the public book without touching chain state
+-- ============================================================================
+--
+-- delisted_at is set by the optional delisting job (ORDER_DELIST_AFTER_DAYS)
+-- on open public orders with no trade or withdrawal in that long. Active
+-- listings filter on delisted_at IS NULL; the order itself is untouched,
+-- still resolves by private code and still shows in the seller's own view.
+-- Any new on-chain activity for the order (trade, withdrawal, rate change,
+-- re-sync) or a visibility change by the seller clears it again.
+--
+-- ============================================================================
+
+ALTER TABLE orders ADD COLUMN IF NOT EXISTS delisted_at TIMESTAMP WITH TIME ZONE;
+
+CREATE INDEX IF NOT EXISTS idx_orders_delisted_at ON orders(delisted_at) WHERE delisted_at IS NOT NULL;
+
+COMMENT ON COLUMN orders.delisted_at IS 'When the order was dropped from listings for inactivity (NULL = listed)';
@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Migration 017: Gas Estimates
+-- Purpose: Keep the pre-send gas estimate next to the actual gas used
+-- ============================================================================
+--
+-- Auto-cancel estimates each cancellation (eth_estimateGas) before sending it,
+-- skipping trades whose estimate reverts. The estimate is stored with the
+-- confirmed transaction's row so it can be compared against "gasUsed".
+-- NULL for rows sent without an estimate (batches, other operations).
+--
+-- ============================================================================
+
+ALTER TABLE gas_costs ADD COLUMN IF NOT EXISTS "gasEstimate" BIGINT;
+
+COMMENT ON COLUMN gas_costs."gasEstimate" IS 'eth_estimateGas result before sending (NULL = not estimated)';
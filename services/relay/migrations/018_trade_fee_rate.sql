@@ -0,0 +1,20 @@
+-- ============================================================================
+-- Migration 018: Trade Fee Rate Snapshot
+-- Purpose: Estimate missing trade fees with the rate in force when the trade was created
+-- ============================================================================
+--
+-- "feeAmount" comes from the TradeCreated event (or, for trades indexed
+-- before fees were recorded, from the escrow's per-trade fee once the trade
+-- settles). "feeRateBps" is the escrow's fee rate (its contract config) when
+-- the listener indexed the TradeCreated event. When the fee is missing it is
+-- estimated from that rate, never from today's fee calculator, whose rate may
+-- have changed since.
+--
+-- Existing trades keep a NULL rate: the rate they were created at isn't
+-- known, and deriving one from "feeAmount" would only restate the fee.
+--
+-- ============================================================================
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS "feeRateBps" INTEGER;
+
+COMMENT ON COLUMN trades."feeRateBps" IS 'Escrow fee rate (bps) when the trade was indexed; estimates feeAmount when it is NULL';
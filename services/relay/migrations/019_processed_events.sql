@@ -0,0 +1,28 @@
+-- ============================================================================
+-- Migration 019: Processed Events
+-- Purpose: Make re-reading a block range a no-op for the event listener
+-- ============================================================================
+--
+-- The listener records every log it applies, keyed by where the log sits on
+-- chain, and skips logs it has already recorded. Restarts, backfills and
+-- overlapping ranges therefore apply each event once, however the handler's
+-- own upserts are keyed. A log whose handler fails is removed again, so the
+-- next pass over its range retries it.
+--
+-- Rows are only needed while a range can still be re-read; the listener
+-- prunes rows older than its retention window (7 days).
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS processed_events (
+    chain_id INTEGER NOT NULL,
+    tx_hash VARCHAR(66) NOT NULL,
+    log_index BIGINT NOT NULL,
+    block_number BIGINT NOT NULL,
+    processed_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    PRIMARY KEY (chain_id, tx_hash, log_index)
+);
+
+CREATE INDEX IF NOT EXISTS idx_processed_events_processed_at ON processed_events(processed_at);
+
+COMMENT ON TABLE processed_events IS 'Logs applied by the event listener (chain, tx, log index); pruned after 7 days';
@@ -0,0 +1,16 @@
+-- ============================================================================
+-- Migration 020: PDF Content Hash
+-- Purpose: Store receipt PDFs content-addressed so identical uploads share one object
+-- ============================================================================
+--
+-- pdf_sha256 is the hex SHA-256 of the uploaded receipt. Bucket objects are
+-- keyed by it (trades/sha256/{hash}.pdf), so a re-uploaded or duplicate
+-- receipt reuses the existing object, and bytes read back are checked
+-- against it. Rows uploaded before this migration keep their per-trade
+-- pdf_storage_key and have no hash.
+--
+-- ============================================================================
+
+ALTER TABLE trades ADD COLUMN IF NOT EXISTS pdf_sha256 TEXT;
+
+COMMENT ON COLUMN trades.pdf_sha256 IS 'Hex SHA-256 of the receipt PDF (content address in the PDF bucket; NULL = legacy upload or none)';
@@ -0,0 +1,24 @@
+-- ============================================================================
+-- Migration 021: Account Email Verification
+-- Purpose: Only notify addresses the wallet owner has confirmed they control
+-- ============================================================================
+--
+-- Storing an email (or changing it) clears `verified` and sets a fresh
+-- verification_nonce. The confirmation link carries a signed, time-bounded
+-- token naming that nonce; confirming sets verified and clears the nonce, so
+-- each link works once and a link for a replaced address never does.
+--
+-- Existing addresses are marked verified on purpose: they were already
+-- receiving notifications, and making every current user re-confirm would
+-- silently stop their emails until they did. Only addresses stored from now
+-- on have to be confirmed.
+--
+-- ============================================================================
+
+ALTER TABLE account_emails ADD COLUMN IF NOT EXISTS verified BOOLEAN NOT NULL DEFAULT FALSE;
+ALTER TABLE account_emails ADD COLUMN IF NOT EXISTS verification_nonce TEXT;
+
+UPDATE account_emails SET verified = TRUE WHERE verification_nonce IS NULL;
+
+COMMENT ON COLUMN account_emails.verified IS 'Owner confirmed the address via the emailed link (notifications only go to verified addresses)';
+COMMENT ON COLUMN account_emails.verification_nonce IS 'Nonce of the outstanding confirmation link (NULL = none pending)';
@@ -0,0 +1,18 @@
+-- ============================================================================
+-- Migration 022: Withdrawal Block Number
+-- Purpose: Record where each withdrawal happened on chain
+-- ============================================================================
+--
+-- The event listener now records every withdrawal with the hash and block of
+-- the transaction that emitted its OrderWithdrawn event. Rows recorded before
+-- that can lack "txHash"; the backfill-withdrawals tool fills both columns
+-- from the chain's logs.
+--
+-- ============================================================================
+
+ALTER TABLE withdrawals ADD COLUMN IF NOT EXISTS "blockNumber" BIGINT;
+
+CREATE INDEX IF NOT EXISTS "idx_withdrawals_missing_txHash" ON withdrawals("orderId") WHERE "txHash" IS NULL;
+
+COMMENT ON COLUMN withdrawals."txHash" IS 'Transaction that emitted the OrderWithdrawn event (NULL only for rows not yet backfilled)';
+COMMENT ON COLUMN withdrawals."blockNumber" IS 'Block of that transaction';
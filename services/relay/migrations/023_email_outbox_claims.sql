@@ -0,0 +1,15 @@
+-- ============================================================================
+-- Migration 023: Email Outbox Claims
+-- Purpose: Send each queued email from one worker only
+-- ============================================================================
+--
+-- A worker claims the rows it is about to send (SELECT ... FOR UPDATE SKIP
+-- LOCKED in the claiming UPDATE) by setting claimed_until. Other workers and
+-- replicas skip claimed rows until the claim runs out, so a worker that dies
+-- mid-batch only delays its emails. Recording the attempt clears the claim.
+--
+-- ============================================================================
+
+ALTER TABLE email_outbox ADD COLUMN IF NOT EXISTS claimed_until TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN email_outbox.claimed_until IS 'Set while a worker is sending the row (NULL = unclaimed)';
@@ -0,0 +1,21 @@
+-- ============================================================================
+-- Migration 024: Gas Cost Trades
+-- Purpose: Link batch transactions' gas costs to the trades they covered
+-- ============================================================================
+--
+-- A batch cancellation is one transaction (one gas_costs row, "tradeId" NULL)
+-- for many trades. Each trade in the batch gets a row here, so a trade's gas
+-- costs include the batch it was cancelled in. The cost itself stays on the
+-- single gas_costs row, so budgets and summaries don't count it twice.
+--
+-- ============================================================================
+
+CREATE TABLE IF NOT EXISTS gas_cost_trades (
+    "gasCostId" INTEGER NOT NULL REFERENCES gas_costs("id") ON DELETE CASCADE,
+    "tradeId" VARCHAR(66) NOT NULL,                      -- Lowercase trade ID
+    PRIMARY KEY ("gasCostId", "tradeId")
+);
+
+CREATE INDEX IF NOT EXISTS "idx_gas_cost_trades_tradeId" ON gas_cost_trades("tradeId");
+
+COMMENT ON TABLE gas_cost_trades IS 'Trades covered by a batch transaction recorded in gas_costs';
@@ -0,0 +1,14 @@
+-- ============================================================================
+-- Migration 025: Proof Job Retry
+-- Purpose: Let the worker put a job back in the queue for later
+-- ============================================================================
+--
+-- A job whose trade is already being proven elsewhere (its proof_in_progress
+-- entry is held) isn't failed: it goes back to queued with retry_at set, and
+-- claim_next skips it until then. NULL means claimable right away.
+--
+-- ============================================================================
+
+ALTER TABLE proofs ADD COLUMN IF NOT EXISTS retry_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN proofs.retry_at IS 'Queued job is not claimed before this (NULL = claimable now)';
@@ -0,0 +1,110 @@
+//! Per-endpoint concurrency limits
+//!
+//! Each limited route owns a semaphore. When every permit is taken the request is
+//! rejected straight away with 503 instead of queueing, so a burst on an expensive
+//! endpoint (validate) sheds its own load without slowing down the rest of the relay.
+
+use axum::{
+    extract::{Request, State},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    routing::MethodRouter,
+};
+use std::sync::Arc;
+use tokio::sync::Semaphore;
+
+use crate::api::ApiError;
+
+/// Semaphore guarding one endpoint
+#[derive(Clone)]
+pub struct ConcurrencyLimit {
+    name: &'static str,
+    semaphore: Arc<Semaphore>,
+}
+
+impl ConcurrencyLimit {
+    pub fn new(name: &'static str, max_in_flight: usize) -> Self {
+        Self {
+            name,
+            semaphore: Arc::new(Semaphore::new(max_in_flight)),
+        }
+    }
+}
+
+/// Wrap a route so at most `max_in_flight` requests run at once; the rest get 503
+pub fn limit_concurrency<S>(route: MethodRouter<S>, name: &'static str, max_in_flight: usize) -> MethodRouter<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    route.layer(middleware::from_fn_with_state(
+        ConcurrencyLimit::new(name, max_in_flight),
+        shed_when_saturated,
+    ))
+}
+
+async fn shed_when_saturated(
+    State(limit): State<ConcurrencyLimit>,
+    request: Request,
+    next: Next,
+) -> Response {
+    match limit.semaphore.clone().try_acquire_owned() {
+        Ok(permit) => {
+            let response = next.run(request).await;
+            drop(permit);
+            response
+        }
+        Err(_) => {
+            tracing::warn!("🚦 {} at concurrency limit, shedding request", limit.name);
+            ApiError::ServiceUnavailable(format!("{} is busy, please retry shortly", limit.name))
+                .into_response()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, routing::post, Router};
+    use tokio::sync::{mpsc, Notify};
+    use tower::ServiceExt;
+
+    #[tokio::test]
+    async fn test_excess_concurrent_validates_are_shed() {
+        let (entered_tx, mut entered_rx) = mpsc::unbounded_channel::<()>();
+        let release = Arc::new(Notify::new());
+
+        let handler = {
+            let release = release.clone();
+            move || async move {
+                let released = release.notified();
+                entered_tx.send(()).unwrap();
+                released.await;
+                "validated"
+            }
+        };
+        let app: Router = Router::new().route(
+            "/api/trades/:trade_id/validate",
+            limit_concurrency(post(handler), "validate", 2),
+        );
+
+        let request = || {
+            Request::post("/api/trades/0x01/validate")
+                .body(Body::empty())
+                .unwrap()
+        };
+
+        // Occupy both permits
+        let first = tokio::spawn(app.clone().oneshot(request()));
+        let second = tokio::spawn(app.clone().oneshot(request()));
+        entered_rx.recv().await.unwrap();
+        entered_rx.recv().await.unwrap();
+
+        // Third request is shed immediately
+        let shed = app.clone().oneshot(request()).await.unwrap();
+        assert_eq!(shed.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+        release.notify_waiters();
+        assert_eq!(first.await.unwrap().unwrap().status(), StatusCode::OK);
+        assert_eq!(second.await.unwrap().unwrap().status(), StatusCode::OK);
+    }
+}
@@ -4,6 +4,10 @@ use axum::{
     Json,
 };
 use serde_json::json;
+use crate::api::request_id;
+use crate::blockchain::chain_clients::ChainClientError;
+use crate::blockchain::client::EthereumClientError;
+use crate::currency::InvalidAmount;
 use crate::db::DbError;
 
 /// API error type that can be converted to HTTP responses
@@ -12,9 +16,18 @@ pub enum ApiError {
     /// Database errors
     Database(String),
     
-    /// Blockchain errors
+    /// Blockchain errors not covered below (bad contract state, failed send) (502)
     BlockchainError(String),
     
+    /// RPC endpoint down, timing out or rate limited - safe to retry (503)
+    RpcUnavailable(String),
+    
+    /// The contract reverted; retrying the same call won't help (400)
+    ContractRevert(String),
+    
+    /// Order, trade or transaction doesn't exist on-chain (404)
+    OnChainNotFound(String),
+    
     /// Invalid request (validation errors)
     BadRequest(String),
     
@@ -24,9 +37,24 @@ pub enum ApiError {
     /// Resource not found
     NotFound(String),
     
+    /// Request conflicts with the resource's current state (409)
+    Conflict(String),
+    
+    /// Upload or aggregate response exceeds the configured size limit (413)
+    PayloadTooLarge(String),
+    
+    /// On-chain verification still failing after retries (400, body reports `attempts`)
+    VerificationFailed { message: String, attempts: u32 },
+    
+    /// Client exceeded its request rate (429)
+    TooManyRequests(String),
+    
     /// Service unavailable (e.g., blockchain integration disabled)
     ServiceUnavailable(String),
     
+    /// Slow dependency (RPC, proving) kept the request past its time limit (504)
+    GatewayTimeout(String),
+    
     /// Internal server error
     Internal(String),
 }
@@ -41,8 +69,33 @@ impl From<DbError> for ApiError {
     }
 }
 
+impl From<EthereumClientError> for ApiError {
+    fn from(err: EthereumClientError) -> Self {
+        match err {
+            EthereumClientError::ProviderError(msg) => ApiError::RpcUnavailable(msg),
+            EthereumClientError::Revert(reason) => ApiError::ContractRevert(reason),
+            EthereumClientError::NotFound(what) => ApiError::OnChainNotFound(what),
+            other => ApiError::BlockchainError(other.to_string()),
+        }
+    }
+}
+
+/// A stored amount that can't be parsed is corrupt data, not a bad request
+impl From<InvalidAmount> for ApiError {
+    fn from(err: InvalidAmount) -> Self {
+        ApiError::Internal(err.to_string())
+    }
+}
+
+impl From<ChainClientError> for ApiError {
+    fn from(err: ChainClientError) -> Self {
+        ApiError::ServiceUnavailable(err.to_string())
+    }
+}
+
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        let mut attempts = None;
         let (status, error_message) = match self {
             ApiError::Database(err) => {
                 // Log the actual database error for debugging
@@ -53,6 +106,16 @@ impl IntoResponse for ApiError {
                 tracing::error!("Blockchain error: {}", err);
                 (StatusCode::BAD_GATEWAY, format!("Blockchain error: {}", err))
             }
+            ApiError::RpcUnavailable(err) => {
+                tracing::warn!("RPC unavailable: {}", err);
+                (StatusCode::SERVICE_UNAVAILABLE, "Blockchain RPC temporarily unavailable, please retry".to_string())
+            }
+            ApiError::ContractRevert(reason) => {
+                (StatusCode::BAD_REQUEST, format!("Transaction reverted: {}", reason))
+            }
+            ApiError::OnChainNotFound(what) => {
+                (StatusCode::NOT_FOUND, format!("Not found on-chain: {}", what))
+            }
             ApiError::BadRequest(msg) => {
                 (StatusCode::BAD_REQUEST, msg)
             }
@@ -62,23 +125,72 @@ impl IntoResponse for ApiError {
             ApiError::NotFound(msg) => {
                 (StatusCode::NOT_FOUND, msg)
             }
+            ApiError::Conflict(msg) => {
+                (StatusCode::CONFLICT, msg)
+            }
+            ApiError::PayloadTooLarge(msg) => {
+                (StatusCode::PAYLOAD_TOO_LARGE, msg)
+            }
+            ApiError::VerificationFailed { message, attempts: made } => {
+                attempts = Some(made);
+                (StatusCode::BAD_REQUEST, message)
+            }
+            ApiError::TooManyRequests(msg) => {
+                (StatusCode::TOO_MANY_REQUESTS, msg)
+            }
             ApiError::ServiceUnavailable(msg) => {
                 (StatusCode::SERVICE_UNAVAILABLE, msg)
             }
+            ApiError::GatewayTimeout(msg) => {
+                (StatusCode::GATEWAY_TIMEOUT, msg)
+            }
             ApiError::Internal(msg) => {
                 tracing::error!("Internal error: {}", msg);
                 (StatusCode::INTERNAL_SERVER_ERROR, "Internal server error".to_string())
             }
         };
 
-        let body = Json(json!({
+        let mut body = json!({
             "error": error_message,
             "status": status.as_u16(),
-        }));
+        });
+        if let Some(attempts) = attempts {
+            body["attempts"] = json!(attempts);
+        }
+        if let Some(request_id) = request_id::current() {
+            body["request_id"] = json!(request_id);
+        }
 
-        (status, body).into_response()
+        (status, Json(body)).into_response()
     }
 }
 
 pub type ApiResult<T> = Result<T, ApiError>;
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_blockchain_errors_map_to_distinct_statuses() {
+        let cases = [
+            (ApiError::RpcUnavailable("connection refused".to_string()), StatusCode::SERVICE_UNAVAILABLE),
+            (ApiError::ContractRevert("TradeNotPending".to_string()), StatusCode::BAD_REQUEST),
+            (ApiError::OnChainNotFound("order 0x01".to_string()), StatusCode::NOT_FOUND),
+            (ApiError::BlockchainError("no event logs".to_string()), StatusCode::BAD_GATEWAY),
+        ];
+        for (error, status) in cases {
+            assert_eq!(error.into_response().status(), status);
+        }
+    }
+
+    #[test]
+    fn test_from_ethereum_client_error() {
+        let status = |err: EthereumClientError| ApiError::from(err).into_response().status();
+        assert_eq!(status(EthereumClientError::ProviderError("timeout".to_string())), StatusCode::SERVICE_UNAVAILABLE);
+        assert_eq!(status(EthereumClientError::Revert("execution reverted: 0x5f3f6cfc".to_string())), StatusCode::BAD_REQUEST);
+        assert_eq!(status(EthereumClientError::NotFound("transaction receipt".to_string())), StatusCode::NOT_FOUND);
+        assert_eq!(status(EthereumClientError::TransactionFailed("No receipt returned".to_string())), StatusCode::BAD_GATEWAY);
+    }
+}
@@ -13,13 +13,14 @@ use crate::api::{
     error::{ApiError, ApiResult},
     state::AppState,
 };
+use crate::email::{language, unsubscribe, verification};
 
 /// Request to set account email
 #[derive(Debug, Deserialize)]
 pub struct SetAccountEmailRequest {
     pub wallet: String,        // Wallet address
     pub email: String,         // Email address
-    pub language: Option<String>, // Language preference: 'en', 'zh-CN', 'zh-TW'
+    pub language: Option<String>, // Language preference: 'en', 'zh-CN', 'zh-TW' (others normalized or replaced by the fallback)
 }
 
 /// Response for account email operations
@@ -29,6 +30,10 @@ pub struct AccountEmailResponse {
     pub email: String,
     pub language: String,
     pub enabled: bool,
+    pub digest_enabled: bool,
+    /// False until the confirmation link emailed to the address is opened;
+    /// no notifications are sent before that
+    pub verified: bool,
 }
 
 /// Query params for GET/DELETE
@@ -38,33 +43,52 @@ pub struct AccountQuery {
 }
 
 /// POST /api/account/email - Set or update account email
+///
+/// A new or changed address is stored unverified and sent a confirmation link
+/// (see email::verification); re-saving a verified address keeps it verified.
 pub async fn set_account_email(
     State(state): State<AppState>,
     Json(request): Json<SetAccountEmailRequest>,
 ) -> ApiResult<Json<AccountEmailResponse>> {
     // Validate email format (basic check)
-    if !request.email.contains('@') || !request.email.contains('.') {
+    if !request.email.contains('@') || !request.email.contains('.')
+        || request.email.chars().any(|c| c.is_whitespace() || matches!(c, '<' | '>' | '"'))
+    {
         return Err(ApiError::BadRequest("Invalid email format".to_string()));
     }
     
-    // Validate language
-    let language = request.language.unwrap_or_else(|| "en".to_string());
-    if !["en", "zh-CN", "zh-TW"].contains(&language.as_str()) {
-        return Err(ApiError::BadRequest("Invalid language. Use 'en', 'zh-CN', or 'zh-TW'".to_string()));
-    }
+    // Store a language we have templates for
+    let fallback = language::fallback_language();
+    let language = request.language
+        .map(|code| language::resolve_language(&code, fallback))
+        .unwrap_or(fallback);
     
     // Validate address format
     if !request.wallet.starts_with("0x") || request.wallet.len() != 42 {
         return Err(ApiError::BadRequest("Invalid wallet address".to_string()));
     }
     
-    let result = state.db.upsert_account_email(&request.wallet, &request.email, &language).await?;
+    let nonce = verification::new_nonce();
+    let result = state.db.upsert_account_email(&request.wallet, &request.email, language, &nonce).await?;
+    
+    if !result.verified {
+        match state.email_service.as_ref() {
+            Some(email_service) => {
+                if let Err(e) = email_service.send_verification(&result.wallet, &result.email, &result.language, &nonce).await {
+                    tracing::warn!("📧 Failed to send verification email to {} for {}: {}", result.email, result.wallet, e);
+                }
+            }
+            None => tracing::warn!("📧 Email not configured, cannot send verification link for {}", result.wallet),
+        }
+    }
     
     Ok(Json(AccountEmailResponse {
         wallet: result.wallet,
         email: result.email,
         language: result.language,
         enabled: result.enabled,
+        digest_enabled: result.digest_enabled,
+        verified: result.verified,
     }))
 }
 
@@ -80,6 +104,8 @@ pub async fn get_account_email(
         email: r.email,
         language: r.language,
         enabled: r.enabled,
+        digest_enabled: r.digest_enabled,
+        verified: r.verified,
     })))
 }
 
@@ -117,3 +143,72 @@ pub async fn toggle_account_email(
     })))
 }
 
+/// POST /api/account/email/digest - Opt in to/out of the daily activity digest
+pub async fn toggle_account_digest(
+    State(state): State<AppState>,
+    Json(request): Json<ToggleAccountEmailRequest>,
+) -> ApiResult<Json<serde_json::Value>> {
+    state.db.set_account_digest_enabled(&request.wallet, request.enabled).await?;
+    
+    Ok(Json(serde_json::json!({
+        "message": if request.enabled { "Daily digest enabled" } else { "Daily digest disabled" },
+        "wallet": request.wallet,
+        "digest_enabled": request.enabled
+    })))
+}
+
+/// Query params for the unsubscribe link
+#[derive(Debug, Deserialize)]
+pub struct UnsubscribeQuery {
+    pub token: String,
+}
+
+/// GET|POST /api/account/email/unsubscribe?token=... - One-click unsubscribe from an email link
+///
+/// No JWT: the signed token (see email::unsubscribe) names the wallet. It can only
+/// disable notifications, so a replayed link never turns them back on.
+pub async fn unsubscribe_account_email(
+    State(state): State<AppState>,
+    Query(query): Query<UnsubscribeQuery>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let wallet = unsubscribe::verify_token(&query.token).map_err(ApiError::BadRequest)?;
+    state.db.set_account_email_enabled(&wallet, false).await?;
+    tracing::info!("📧 Notifications disabled via unsubscribe link for {}", wallet);
+    
+    Ok(Json(serde_json::json!({
+        "message": "Notifications disabled",
+        "wallet": wallet,
+        "enabled": false
+    })))
+}
+
+
+/// Query params for the confirmation link
+#[derive(Debug, Deserialize)]
+pub struct ConfirmEmailQuery {
+    pub token: String,
+}
+
+/// GET /api/account/email/confirm?token=... - Verify an address from its confirmation email
+///
+/// No JWT: the signed, expiring token (see email::verification) names the wallet
+/// and the pending nonce, which confirming consumes.
+pub async fn confirm_account_email(
+    State(state): State<AppState>,
+    Query(query): Query<ConfirmEmailQuery>,
+) -> ApiResult<Json<serde_json::Value>> {
+    let token = verification::verify_token(&query.token, chrono::Utc::now().timestamp())
+        .map_err(ApiError::BadRequest)?;
+    if !state.db.confirm_account_email(&token.wallet, &token.nonce).await? {
+        return Err(ApiError::BadRequest(
+            "Confirmation link already used or replaced by a newer one".to_string()
+        ));
+    }
+    tracing::info!("📧 Notification email verified for {}", token.wallet);
+    
+    Ok(Json(serde_json::json!({
+        "message": "Email verified",
+        "wallet": token.wallet,
+        "verified": true
+    })))
+}
@@ -12,125 +12,303 @@ pub mod trades;
 pub mod settlement;
 
 use axum::{
-    extract::{Path, Query, State},
-    http::{header, StatusCode},
-    response::{IntoResponse, Response},
+    extract::{Path, Query, Request, State},
+    http::{header, HeaderMap, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Redirect, Response},
     Json,
 };
-use chrono::Utc;
 // Note: serde::Deserialize removed - all admin request structs removed for security
 
 use crate::api::{
     error::{ApiError, ApiResult},
+    response_size::capped_json,
     state::AppState,
-    types::HealthResponse,
+    types::{
+        ChainSummary, ChainSyncStatus, ChainsResponse, ConfigCacheStatus, DebugChainConfig, DebugConfigValue, DebugDatabaseQuery, DebugResponse,
+        DebugSummary, DecodedId, GasCostSummaryQuery, GasCostSummaryResponse, HealthResponse, InvalidateConfigQuery,
+        InvalidateConfigResponse, ListenerStatusResponse, MarketStatsQuery, MarketStatsResponse, RelayerStatusResponse, SellerStatsResponse, SellerWithdrawalsQuery,
+        SellerWithdrawalsResponse, StatsQuery, StuckTradesResponse, TokensQuery, TokensResponse, TradeStatsResponse, VersionResponse,
+    },
 };
+use crate::auth;
+use crate::blockchain::{chain_clients::ChainClientError, relayer_balance};
+use crate::config::ApiConfig;
+use crate::crypto;
+use crate::db::{email_outbox::EmailOutboxCounts, models::{DbOrder, DbTrade}, DbError};
+use crate::email::EmailService;
+use crate::storage::{self, PdfServeMode, StorageError};
+use crate::tokens;
+use std::collections::BTreeMap;
 
 // Re-export handlers
-pub use orders::{get_active_orders, get_order_activities, get_order_by_private_code, set_order_visibility, submit_payment_info};
-pub use trades::{get_trade_handler, get_trades_by_buyer_handler, get_trades_by_seller_handler, create_trade_handler};
-pub use settlement::validate_handler;
+pub use orders::{get_active_orders, get_order_activities, get_order_by_private_code, get_orders_batch, set_order_visibility, submit_payment_info, verify_order_remaining};
+pub use trades::{get_trade_handler, get_trade_events, get_trade_gas_costs, get_trade_receipt, get_trades_by_buyer_handler, get_trades_by_seller_handler, create_trade_handler};
+pub use settlement::{get_proof_job, get_settlement_status, regenerate_input_streams, validate_handler};
 
 /// Health check endpoint
+#[utoipa::path(
+    get, path = "/health", tag = "health",
+    responses(
+        (status = 200, description = "Service status", body = HealthResponse),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 pub async fn health_check(State(state): State<AppState>) -> ApiResult<Json<HealthResponse>> {
     let db_status = match state.db.health_check().await {
         Ok(_) => "healthy",
         Err(_) => "unhealthy",
     };
+    let email_status = email_health(state.email_service.as_deref()).await;
 
-    Ok(Json(HealthResponse {
-        status: "ok".to_string(),
-        database: db_status.to_string(),
-        orderbook: "read-only".to_string(),
-        timestamp: Utc::now().to_rfc3339(),
-    }))
+    Ok(Json(HealthResponse::new(db_status, email_status, state.started_at)))
 }
 
-/// Debug database endpoint - returns all orders and trades with chain info
-/// GET /api/debug/database
-pub async fn debug_database(State(state): State<AppState>) -> ApiResult<Json<serde_json::Value>> {
-    // Get all active orders (no limit, all chains)
-    let orders = state.db.get_active_orders(None, None).await?;
+/// GET /version - Crate version, git commit and build time of this binary, plus its chains
+#[utoipa::path(
+    get, path = "/version", tag = "health",
+    responses(
+        (status = 200, description = "Build metadata", body = VersionResponse),
+    )
+)]
+pub async fn get_version(State(state): State<AppState>) -> Json<VersionResponse> {
+    Json(VersionResponse::new(&state.supported_chains))
+}
+
+/// Resend status for /health: "disabled" without an API key (never degrades health)
+async fn email_health(email_service: Option<&EmailService>) -> &'static str {
+    match email_service {
+        None => "disabled",
+        Some(service) if service.health_check().await => "healthy",
+        Some(_) => "unhealthy",
+    }
+}
+
+/// How long clients may cache GET /api/chains (it only changes on redeploy)
+const CHAINS_CACHE_MAX_AGE_SECS: u64 = 300;
+
+/// GET /api/chains - Configured chains (id, name, escrow) with their supported tokens
+#[utoipa::path(
+    get, path = "/api/chains", tag = "health",
+    responses(
+        (status = 200, description = "Configured chains and their tokens", body = ChainsResponse),
+    )
+)]
+pub async fn get_chains(State(state): State<AppState>) -> impl IntoResponse {
+    (
+        [(header::CACHE_CONTROL, format!("public, max-age={}", CHAINS_CACHE_MAX_AGE_SECS))],
+        Json(ChainsResponse::clone(&state.chains_response)),
+    )
+}
+
+/// GET /api/chains/:chain_id/sync - Indexer progress for one chain (last indexed block, head, lag)
+pub async fn get_chain_sync_status(
+    State(state): State<AppState>,
+    Path(chain_id): Path<u64>,
+) -> ApiResult<Json<ChainSyncStatus>> {
+    let client = state.get_blockchain_client(chain_id).map_err(|e| match e {
+        ChainClientError::NotConfigured(_) => ApiError::NotFound(format!("Chain {} not configured", chain_id)),
+        unavailable => unavailable.into(),
+    })?;
     
-    // Get all trades
-    let trades = state.db.get_all_trades().await.unwrap_or_default();
+    let escrow_address = format!("{:#x}", client.escrow_address());
+    let last_indexed_block = state.db.get_last_synced_block(&escrow_address).await?;
+    let head_block = client.get_block_number().await?;
     
-    // Build per-chain summary
-    let mut base_orders = 0u32;
-    let mut eth_orders = 0u32;
-    for order in &orders {
-        match order.chain_id {
-            8453 => base_orders += 1,
-            1 => eth_orders += 1,
-            _ => {}
-        }
-    }
+    Ok(Json(ChainSyncStatus::new(
+        chain_id,
+        escrow_address,
+        last_indexed_block.map(|block| block as u64),
+        head_block,
+    )))
+}
+
+/// GET /api/tokens?chain_id= - Supported tokens (address, symbol, decimals, logo) for token selectors
+#[utoipa::path(
+    get, path = "/api/tokens", tag = "health",
+    params(TokensQuery),
+    responses(
+        (status = 200, description = "Supported tokens", body = TokensResponse),
+    )
+)]
+pub async fn get_tokens(Query(query): Query<TokensQuery>) -> Json<TokensResponse> {
+    let tokens = match query.chain_id {
+        Some(chain_id) => tokens::tokens_for_chain(chain_id).copied().collect(),
+        None => tokens::TOKENS.to_vec(),
+    };
+    Json(TokensResponse { tokens })
+}
+
+/// Debug database endpoint - one page of active orders and trades with chain info
+/// GET /api/debug/database?limit=&offset=
+///
+/// At most DEBUG_MAX_ROWS orders and as many trades per page; `truncated` says
+/// whether either list continues past it. Summary counts cover every row.
+pub async fn debug_database(
+    State(state): State<AppState>,
+    Query(query): Query<DebugDatabaseQuery>,
+) -> ApiResult<Response> {
+    let (limit, offset) = debug_page(&query, state.api_config.debug_max_rows)?;
     
-    let mut base_trades = 0u32;
-    let mut eth_trades = 0u32;
-    let mut base_pending = 0u32;
-    let mut eth_pending = 0u32;
-    let mut base_settled = 0u32;
-    let mut eth_settled = 0u32;
-    for trade in &trades {
-        match trade.chain_id {
-            8453 => {
-                base_trades += 1;
-                match trade.status { 0 => base_pending += 1, 1 => base_settled += 1, _ => {} }
-            }
-            1 => {
-                eth_trades += 1;
-                match trade.status { 0 => eth_pending += 1, 1 => eth_settled += 1, _ => {} }
-            }
-            _ => {}
-        }
-    }
+    // One extra row per list tells whether there's more past this page
+    let orders = state.db.get_active_orders_page(limit + 1, offset).await?;
+    let trades = state.db.get_all_trades(limit + 1, offset).await.unwrap_or_default();
+    let (orders, orders_truncated) = take_page(orders, limit);
+    let (trades, trades_truncated) = take_page(trades, limit);
     
     // Get chain configs
-    let mut chain_configs = serde_json::Map::new();
-    for (&chain_id, _) in state.blockchain_clients.iter() {
-        let chain_name = match chain_id { 8453 => "Base", 1 => "Ethereum", _ => "Unknown" };
-        if let Ok(config) = state.get_config_for_chain(chain_id, false).await {
-            chain_configs.insert(chain_name.to_string(), serde_json::json!({
-                "chain_id": chain_id,
-                "config": config,
-            }));
-        } else {
-            chain_configs.insert(chain_name.to_string(), serde_json::json!({
-                "chain_id": chain_id,
-                "config": "failed to fetch",
-            }));
-        }
+    let mut chain_configs = BTreeMap::new();
+    for chain_id in state.blockchain_clients.chain_ids() {
+        let chain_name = state.chain_name(chain_id);
+        let config = match state.get_config_for_chain(chain_id, false).await {
+            Ok(config) => DebugConfigValue::Loaded(Box::new(config)),
+            Err(_) if state.get_blockchain_client(chain_id).is_err() => DebugConfigValue::Failed("chain temporarily unavailable"),
+            Err(_) => DebugConfigValue::Failed("failed to fetch"),
+        };
+        chain_configs.insert(chain_name.to_string(), DebugChainConfig { chain_id, config });
     }
     
-    // Get gas cost summaries
-    let gas_summary_base = state.db.get_gas_cost_summary(8453).await.ok();
-    let gas_summary_eth = state.db.get_gas_cost_summary(1).await.ok();
+    // Per-chain counts and gas cost summaries; per-trade costs only for this page's trades
+    let trade_ids: Vec<String> = trades.iter().map(|t| t.trade_id.clone()).collect();
+    let trade_gas_costs = state.db.get_gas_costs_by_trades(&trade_ids).await.unwrap_or_default();
+    let dump = DebugResponse {
+        summary: DebugSummary {
+            base: ChainSummary::new(8453, state.db.chain_counts(8453).await?, state.db.get_gas_cost_summary(8453).await.ok()),
+            ethereum: ChainSummary::new(1, state.db.chain_counts(1).await?, state.db.get_gas_cost_summary(1).await.ok()),
+        },
+        chain_configs,
+        limit,
+        offset,
+        truncated: orders_truncated || trades_truncated,
+        orders,
+        trades,
+        trade_gas_costs,
+    };
+    capped_json(
+        &dump,
+        state.api_config.max_aggregate_response_bytes,
+        "use the paginated /api/orders/active and /api/trades/{buyer,seller}/:addr endpoints instead of the full dump",
+    )
+}
+
+/// (limit, offset) of a debug dump page: limit defaults to and is capped at `max_rows`
+fn debug_page(query: &DebugDatabaseQuery, max_rows: i64) -> ApiResult<(i64, i64)> {
+    let limit = query.limit.unwrap_or(max_rows);
+    if limit < 1 || query.offset < 0 {
+        return Err(ApiError::BadRequest("limit must be positive and offset non-negative".to_string()));
+    }
+    Ok((limit.min(max_rows), query.offset))
+}
+
+/// Keep the first `limit` rows of a `limit + 1` fetch; true if the extra row was there
+fn take_page<T>(mut rows: Vec<T>, limit: i64) -> (Vec<T>, bool) {
+    let limit = limit as usize;
+    let truncated = rows.len() > limit;
+    rows.truncate(limit);
+    (rows, truncated)
+}
+
+/// GET /api/debug/decode/:id - Classify a raw bytes32 as a known trade, order, or unknown
+/// Support tool: reports the DB state and tx hash so staff don't have to query by hand.
+pub async fn debug_decode_id(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> ApiResult<Json<DecodedId>> {
+    let id = normalize_bytes32(&id)
+        .ok_or_else(|| ApiError::BadRequest(format!("Not a bytes32 hex id: {}", id)))?;
     
-    Ok(Json(serde_json::json!({
-        "summary": {
-            "base": {
-                "chain_id": 8453,
-                "orders": base_orders,
-                "trades": base_trades,
-                "trades_pending": base_pending,
-                "trades_settled": base_settled,
-                "gas_costs": gas_summary_base,
-            },
-            "ethereum": {
-                "chain_id": 1,
-                "orders": eth_orders,
-                "trades": eth_trades,
-                "trades_pending": eth_pending,
-                "trades_settled": eth_settled,
-                "gas_costs": gas_summary_eth,
-            },
+    let trade = match state.db.get_trade(&id).await {
+        Ok(trade) => Some(trade),
+        Err(DbError::TradeNotFound(_)) => None,
+        Err(e) => return Err(e.into()),
+    };
+    let order = match trade {
+        Some(_) => None,
+        None => match state.db.get_order(&id).await {
+            Ok(order) => Some(order),
+            Err(DbError::OrderNotFound(_)) => None,
+            Err(e) => return Err(e.into()),
         },
-        "chain_configs": chain_configs,
-        "orders": orders,
-        "trades": trades,
-        "trade_gas_costs": state.db.get_gas_costs_by_trades().await.unwrap_or_default(),
-    })))
+    };
+    
+    Ok(Json(classify_id(id, trade.as_ref(), order.as_ref())))
+}
+
+/// Lowercase 0x-prefixed 32-byte hex, or None if the input isn't one
+fn normalize_bytes32(raw: &str) -> Option<String> {
+    let hex = raw.trim();
+    let hex = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")).unwrap_or(hex);
+    if hex.len() == 64 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        Some(format!("0x{}", hex.to_lowercase()))
+    } else {
+        None
+    }
+}
+
+/// Canonical (lowercase) form of an address param, or 400 naming the param
+pub(crate) fn parse_address(name: &str, raw: &str) -> ApiResult<String> {
+    crypto::normalize_address(raw).map_err(|e| ApiError::BadRequest(format!("Invalid {} {:?}: {}", name, raw, e)))
+}
+
+/// Wallet of the caller when its bearer token belongs to one of ADMIN_WALLETS, else 401
+pub(crate) fn require_admin(config: &ApiConfig, headers: &HeaderMap) -> ApiResult<String> {
+    let auth_header = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|h| h.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Admin authentication required".to_string()))?;
+    let wallet = auth::verify_jwt(auth_header)
+        .map_err(|e| ApiError::Unauthorized(format!("Invalid authentication: {}", e)))?;
+    if !config.admin_wallets.contains(&wallet) {
+        return Err(ApiError::Unauthorized(format!("{} is not an admin wallet", wallet)));
+    }
+    Ok(wallet)
+}
+
+/// Middleware in front of every /api/admin route: 401 unless `require_admin` passes
+pub(crate) async fn admin_only(State(state): State<AppState>, request: Request, next: Next) -> ApiResult<Response> {
+    require_admin(&state.api_config, request.headers())?;
+    Ok(next.run(request).await)
+}
+
+/// Describe an id given whichever record it matched (trade takes precedence)
+fn classify_id(id: String, trade: Option<&DbTrade>, order: Option<&DbOrder>) -> DecodedId {
+    if let Some(trade) = trade {
+        let state = match trade.status {
+            0 => "pending",
+            1 => "settled",
+            2 => "expired",
+            _ => "unknown",
+        };
+        return DecodedId {
+            id,
+            kind: "trade".to_string(),
+            chain_id: Some(trade.chain_id),
+            state: Some(state.to_string()),
+            order_id: Some(trade.order_id.clone()),
+            tx_hash: trade.settlement_tx_hash.clone().or_else(|| trade.escrow_tx_hash.clone()),
+        };
+    }
+    
+    if let Some(order) = order {
+        let active = order.remaining_amount.parse::<u128>().map(|r| r > 0).unwrap_or(false);
+        return DecodedId {
+            id,
+            kind: "order".to_string(),
+            chain_id: Some(order.chain_id),
+            state: Some(if active { "active" } else { "filled" }.to_string()),
+            order_id: None,
+            tx_hash: None,
+        };
+    }
+    
+    DecodedId {
+        id,
+        kind: "unknown".to_string(),
+        chain_id: None,
+        state: None,
+        order_id: None,
+        tx_hash: None,
+    }
 }
 
 // ============ Admin Endpoints ============
@@ -148,8 +326,8 @@ pub async fn get_contract_config(
     // If specific chain_id requested, return just that chain's config
     if let Some(chain_id_str) = params.get("chain_id") {
         if let Ok(chain_id) = chain_id_str.parse::<u64>() {
-            let config = state.get_config_for_chain(chain_id, force_refresh).await
-                .map_err(|e| ApiError::BlockchainError(e))?;
+            state.require_supported_chain(chain_id)?;
+            let config = state.get_config_for_chain(chain_id, force_refresh).await?;
             return Ok(Json(serde_json::json!({
                 "chain_id": chain_id,
                 "config": config,
@@ -159,8 +337,8 @@ pub async fn get_contract_config(
     
     // Otherwise return configs for ALL chains
     let mut configs = serde_json::Map::new();
-    for (&chain_id, _) in state.blockchain_clients.iter() {
-        let chain_name = match chain_id { 8453 => "Base", 1 => "Ethereum", _ => "Unknown" };
+    for chain_id in state.blockchain_clients.chain_ids() {
+        let chain_name = state.chain_name(chain_id);
         match state.get_config_for_chain(chain_id, force_refresh).await {
             Ok(config) => {
                 configs.insert(chain_name.to_string(), serde_json::json!({
@@ -171,7 +349,7 @@ pub async fn get_contract_config(
             Err(e) => {
                 configs.insert(chain_name.to_string(), serde_json::json!({
                     "chain_id": chain_id,
-                    "error": e,
+                    "error": e.to_string(),
                 }));
             }
         }
@@ -180,6 +358,185 @@ pub async fn get_contract_config(
     Ok(Json(serde_json::json!(configs)))
 }
 
+/// GET /api/admin/config/cache - Inspect config cache state per chain (read-only, never fetches)
+pub async fn get_config_cache_status(
+    State(state): State<AppState>,
+) -> Json<Vec<ConfigCacheStatus>> {
+    Json(state.config_cache_status().await)
+}
+
+/// GET /api/stats?chain_id=&from=&to= - Settled volume per token and settlement success rate
+pub async fn get_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> ApiResult<Response> {
+    if let Some(chain_id) = query.chain_id {
+        state.require_supported_chain(chain_id)?;
+    }
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(ApiError::BadRequest(format!("from ({}) must not be after to ({})", from, to)));
+        }
+    }
+    let rows = state.db.trade_stats(query.chain_id.map(|id| id as i32), query.from, query.to).await?;
+    capped_json(
+        &TradeStatsResponse::from_rows(&query, &rows)?,
+        state.api_config.max_aggregate_response_bytes,
+        "pass chain_id and a narrower from/to range",
+    )
+}
+
+/// GET /api/market/stats?chain_id= - Active liquidity per token, public orders only
+pub async fn get_market_stats(
+    State(state): State<AppState>,
+    Query(query): Query<MarketStatsQuery>,
+) -> ApiResult<Json<MarketStatsResponse>> {
+    market_stats(&state, query, false).await
+}
+
+/// GET /api/admin/market-stats?chain_id= - Active liquidity per token, private orders
+/// included (admin wallets only, since private orders aren't otherwise listed)
+pub async fn get_admin_market_stats(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<MarketStatsQuery>,
+) -> ApiResult<Json<MarketStatsResponse>> {
+    require_admin(&state.api_config, &headers)?;
+    market_stats(&state, query, true).await
+}
+
+async fn market_stats(state: &AppState, query: MarketStatsQuery, include_private: bool) -> ApiResult<Json<MarketStatsResponse>> {
+    if let Some(chain_id) = query.chain_id {
+        state.require_supported_chain(chain_id)?;
+    }
+    let rows = state.db.market_liquidity(query.chain_id.map(|id| id as i32)).await?;
+    Ok(Json(MarketStatsResponse::from_rows(query.chain_id, &rows, include_private)?))
+}
+
+/// GET /api/sellers/:address/stats - Seller reputation summary (orders, trades, completion rate)
+#[utoipa::path(
+    get, path = "/api/sellers/{address}/stats", tag = "sellers",
+    params(("address" = String, Path, description = "Seller wallet address")),
+    responses(
+        (status = 200, description = "Seller reputation summary", body = SellerStatsResponse),
+        (status = 400, description = "Invalid address", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
+pub async fn get_seller_stats(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+) -> ApiResult<Json<SellerStatsResponse>> {
+    let seller = parse_address("seller address", &address)?;
+    let rows = state.db.seller_stats(&seller).await?;
+    Ok(Json(SellerStatsResponse::from_rows(seller, &rows)?))
+}
+
+/// GET /api/sellers/:address/withdrawals?limit=&offset= - A seller's withdrawals
+/// across all their orders, newest first (for accounting views)
+#[utoipa::path(
+    get, path = "/api/sellers/{address}/withdrawals", tag = "sellers",
+    params(("address" = String, Path, description = "Seller wallet address"), SellerWithdrawalsQuery),
+    responses(
+        (status = 200, description = "One page of the seller's withdrawals", body = SellerWithdrawalsResponse),
+        (status = 400, description = "Invalid address or paging", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
+pub async fn get_seller_withdrawals(
+    State(state): State<AppState>,
+    Path(address): Path<String>,
+    Query(query): Query<SellerWithdrawalsQuery>,
+) -> ApiResult<Json<SellerWithdrawalsResponse>> {
+    let seller = parse_address("seller address", &address)?;
+    let limit = state.api_config.page_limit(query.limit);
+    let offset = query.offset.unwrap_or(0);
+    if limit <= 0 || offset < 0 {
+        return Err(ApiError::BadRequest("limit must be > 0 and offset >= 0".to_string()));
+    }
+    let (rows, total) = state.db.get_withdrawals_by_seller(&seller, limit, offset).await?;
+    Ok(Json(SellerWithdrawalsResponse::from_rows(seller, rows, total, limit, offset)))
+}
+
+/// POST /api/admin/config/invalidate - Drop cached contract config (?chain_id= for one chain)
+///
+/// For after the owner changes contract settings with cast: the next read
+/// fetches fresh instead of serving the old config until the TTL runs out.
+/// Only touches the relay's read cache, never the contract. Admin wallets only.
+pub async fn invalidate_config_cache(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Query(query): Query<InvalidateConfigQuery>,
+) -> ApiResult<Json<InvalidateConfigResponse>> {
+    let admin = require_admin(&state.api_config, &headers)?;
+    if let Some(chain_id) = query.chain_id {
+        state.require_supported_chain(chain_id)?;
+    }
+    let invalidated = state.invalidate_config_cache(query.chain_id).await;
+    tracing::info!("🗑️ Contract config cache invalidated for chains {:?} by {}", invalidated, admin);
+    Ok(Json(InvalidateConfigResponse { invalidated }))
+}
+
+/// GET /api/admin/email-outbox - Queued notification emails by status
+pub async fn get_email_outbox_status(
+    State(state): State<AppState>,
+) -> Result<Json<EmailOutboxCounts>, ApiError> {
+    Ok(Json(state.db.get_email_outbox_counts().await?))
+}
+
+/// GET /api/admin/gas-costs/summary?chain_id= - Relayer gas spend by operation,
+/// per chain and rolled up across chains (all chains when chain_id is omitted)
+pub async fn get_gas_cost_summary(
+    State(state): State<AppState>,
+    Query(query): Query<GasCostSummaryQuery>,
+) -> ApiResult<Json<GasCostSummaryResponse>> {
+    let chains = match query.chain_id {
+        Some(chain_id) => {
+            state.require_supported_chain(chain_id)?;
+            vec![(chain_id as i32, state.db.get_gas_cost_summary(chain_id as i32).await?)]
+        }
+        None => state.db.get_gas_cost_summary_all_chains().await?,
+    };
+    Ok(Json(GasCostSummaryResponse::new(chains)))
+}
+
+/// Most stuck trades listed by GET /api/admin/stuck-trades (oldest first)
+const STUCK_TRADES_LIMIT: i64 = 200;
+
+/// GET /api/admin/stuck-trades - Pending trades already past expiry
+///
+/// Auto-cancel normally clears these within a minute; entries that stay here
+/// (or have `cancel_failed` set) mean the cancel pipeline needs attention.
+pub async fn get_stuck_trades(
+    State(state): State<AppState>,
+) -> Result<Json<StuckTradesResponse>, ApiError> {
+    let trades = state.db.get_stuck_trades(STUCK_TRADES_LIMIT).await?;
+    Ok(Json(StuckTradesResponse::new(trades, chrono::Utc::now().timestamp())))
+}
+
+/// GET /api/admin/listener-status - Event listener progress per configured chain
+///
+/// Served from what the listeners last reported (no RPC or DB calls).
+pub async fn get_listener_status(State(state): State<AppState>) -> Json<ListenerStatusResponse> {
+    let stall_after_secs = state.api_config.listener_stall_secs;
+    Json(ListenerStatusResponse {
+        stall_after_secs,
+        chains: state.listener_progress.snapshot(&state.supported_chains, stall_after_secs),
+    })
+}
+
+/// GET /api/admin/relayer - Relayer address and native balance per chain, with a
+/// `low_balance` flag below the chain's threshold (see blockchain::relayer_balance),
+/// plus each chain's client status (chains still reconnecting have no balance)
+pub async fn get_relayer_status(State(state): State<AppState>) -> Json<RelayerStatusResponse> {
+    let chains = relayer_balance::check_balances(&state.blockchain_clients.ready(), &state.relayer_balance_thresholds).await;
+    Json(RelayerStatusResponse {
+        any_low_balance: chains.iter().any(|c| c.low_balance),
+        chains,
+        client_status: state.blockchain_clients.statuses(),
+    })
+}
+
 // ============ Admin Write Endpoints REMOVED for Security ============
 // All contract modifications must be done directly via cast/forge with the owner wallet.
 // This prevents public API from being exploited to modify contract state.
@@ -199,19 +556,55 @@ pub async fn get_contract_config(
 // ============ Trade File Endpoints ============
 
 /// GET /api/trades/:trade_id/pdf - Download the PDF for a trade
+///
+/// Bucket-stored PDFs are resolved by content hash and proxied (checked against
+/// the hash) or redirected to a presigned URL (PDF_SERVE_MODE).
+/// A key whose object is gone from the bucket is reported separately from
+/// "no PDF uploaded", since it means the stored receipt was lost.
+#[utoipa::path(
+    get, path = "/api/trades/{trade_id}/pdf", tag = "trades",
+    params(("trade_id" = String, Path, description = "bytes32 trade id (0x-prefixed)")),
+    responses(
+        (status = 200, description = "The receipt PDF", content_type = "application/pdf", body = String),
+        (status = 307, description = "Redirect to a presigned bucket URL (PDF_SERVE_MODE=redirect)"),
+        (status = 404, description = "No PDF uploaded, or missing from storage", body = ErrorBody),
+        (status = 500, description = "Stored PDF failed its integrity check", body = ErrorBody),
+        (status = 503, description = "Object storage not configured", body = ErrorBody),
+    )
+)]
 pub async fn get_trade_pdf(
     State(state): State<AppState>,
     Path(trade_id): Path<String>,
 ) -> Result<Response, ApiError> {
-    // Get the trade from the database
-    let trade = state.db.get_trade(&trade_id).await?;
-    
-    // Check if PDF exists
-    let pdf_file = trade.pdf_file.ok_or_else(|| {
-        ApiError::NotFound(format!("No PDF uploaded for trade {}", trade_id))
-    })?;
+    let pdf = state.db.get_trade_pdf(&trade_id).await?;
+    let object_key = pdf.object_key();
+    let filename = pdf.pdf_filename.unwrap_or_else(|| "receipt.pdf".to_string());
     
-    let filename = trade.pdf_filename.unwrap_or_else(|| "receipt.pdf".to_string());
+    let pdf_file = match (object_key, pdf.pdf_file) {
+        (Some(key), _) => {
+            let storage = state.pdf_storage.as_ref().ok_or_else(|| {
+                ApiError::ServiceUnavailable(format!("PDF for trade {} is in object storage, which is not configured", trade_id))
+            })?;
+            let missing = |e: StorageError| match e {
+                StorageError::NotFound(key) => {
+                    tracing::error!("❌ PDF object {} for trade {} is missing from the bucket", key, trade_id);
+                    ApiError::NotFound(format!("PDF for trade {} is missing from storage", trade_id))
+                }
+                StorageError::Backend(msg) => ApiError::Internal(format!("PDF storage error: {}", msg)),
+            };
+            if storage.serve_mode == PdfServeMode::Redirect {
+                let url = storage.store.presigned_get_url(&key, storage.presign_ttl).await.map_err(missing)?;
+                return Ok(Redirect::temporary(&url).into_response());
+            }
+            storage.store.get(&key).await.map_err(missing)?
+        }
+        (None, Some(bytes)) => bytes,
+        (None, None) => return Err(ApiError::NotFound(format!("No PDF uploaded for trade {}", trade_id))),
+    };
+    if !storage::matches_content_hash(&pdf_file, pdf.pdf_sha256.as_deref()) {
+        tracing::error!("❌ PDF for trade {} doesn't match its recorded hash", trade_id);
+        return Err(ApiError::Internal(format!("PDF for trade {} failed its integrity check", trade_id)));
+    }
     
     // Return the PDF with proper headers
     let response = (
@@ -225,3 +618,135 @@ pub async fn get_trade_pdf(
     
     Ok(response)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures;
+
+    #[test]
+    fn test_admin_requires_an_allowlisted_wallet_token() {
+        let config = ApiConfig { admin_wallets: vec!["0xadmin".to_string()], ..ApiConfig::default() };
+
+        assert_eq!(require_admin(&config, &bearer("0xADMIN")).unwrap(), "0xadmin");
+        assert!(matches!(require_admin(&config, &bearer("0xseller")), Err(ApiError::Unauthorized(_))));
+        assert!(matches!(require_admin(&config, &HeaderMap::new()), Err(ApiError::Unauthorized(_))));
+        // No admin wallets configured: admin actions are off
+        assert!(require_admin(&ApiConfig::default(), &bearer("0xadmin")).is_err());
+    }
+
+    fn bearer(address: &str) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        headers.insert(header::AUTHORIZATION, format!("Bearer {}", auth::test_token(address)).parse().unwrap());
+        headers
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_config_invalidation_is_admin_only(pool: sqlx::PgPool) {
+        let state = AppState::from_database(crate::db::Database::from_pool(pool))
+            .with_api_config(ApiConfig { admin_wallets: vec!["0xadmin".to_string()], ..ApiConfig::default() });
+        let invalidate = |headers: HeaderMap| {
+            invalidate_config_cache(State(state.clone()), headers, Query(InvalidateConfigQuery { chain_id: None }))
+        };
+
+        assert!(matches!(invalidate(HeaderMap::new()).await, Err(ApiError::Unauthorized(_))));
+        assert!(matches!(invalidate(bearer("0xseller")).await, Err(ApiError::Unauthorized(_))));
+        assert!(invalidate(bearer("0xadmin")).await.is_ok());
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_admin_market_stats_are_admin_only(pool: sqlx::PgPool) {
+        let state = AppState::from_database(crate::db::Database::from_pool(pool))
+            .with_api_config(ApiConfig { admin_wallets: vec!["0xadmin".to_string()], ..ApiConfig::default() });
+        let stats = |headers: HeaderMap| {
+            get_admin_market_stats(State(state.clone()), headers, Query(MarketStatsQuery { chain_id: None }))
+        };
+
+        assert!(matches!(stats(HeaderMap::new()).await, Err(ApiError::Unauthorized(_))));
+        assert!(matches!(stats(bearer("0xseller")).await, Err(ApiError::Unauthorized(_))));
+        assert!(stats(bearer("0xadmin")).await.is_ok());
+    }
+
+    #[test]
+    fn test_debug_page_bounds() {
+        let page = |limit, offset| debug_page(&DebugDatabaseQuery { limit, offset }, 1000);
+        assert_eq!(page(None, 0).unwrap(), (1000, 0));
+        assert_eq!(page(Some(50), 100).unwrap(), (50, 100));
+        // Capped, not rejected
+        assert_eq!(page(Some(1_000_000), 0).unwrap(), (1000, 0));
+        assert!(page(Some(0), 0).is_err());
+        assert!(page(None, -1).is_err());
+
+        assert_eq!(take_page(vec![1, 2, 3], 2), (vec![1, 2], true));
+        assert_eq!(take_page(vec![1, 2], 2), (vec![1, 2], false));
+    }
+
+    fn trade(trade_id: &str, status: i32) -> DbTrade {
+        DbTrade {
+            order_id: format!("0x{}", "ab".repeat(32)),
+            created_at: 0,
+            expires_at: 0,
+            escrow_tx_hash: Some("0xescrow".to_string()),
+            ..fixtures::trade(trade_id, status)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_email_health_disabled_without_key() {
+        let status = email_health(None).await;
+        assert_eq!(status, "disabled");
+
+        let health = HealthResponse::new("healthy", status, tokio::time::Instant::now());
+        assert_eq!((health.status.as_str(), health.email.as_str()), ("ok", "disabled"));
+        assert_eq!(HealthResponse::new("healthy", "unhealthy", tokio::time::Instant::now()).status, "degraded");
+    }
+
+    #[tokio::test]
+    async fn test_tokens_endpoint_lists_chain_tokens() {
+        let Json(base) = get_tokens(Query(TokensQuery { chain_id: Some(8453) })).await;
+        let symbols: Vec<&str> = base.tokens.iter().map(|t| t.symbol).collect();
+        assert_eq!(symbols, vec!["USDC", "USDbC", "DAI", "WETH", "cbBTC"]);
+        assert!(base.tokens.iter().all(|t| t.chain_id == 8453));
+
+        let Json(all) = get_tokens(Query(TokensQuery { chain_id: None })).await;
+        assert_eq!(all.tokens.len(), tokens::TOKENS.len());
+
+        let Json(unknown) = get_tokens(Query(TokensQuery { chain_id: Some(10) })).await;
+        assert!(unknown.tokens.is_empty());
+    }
+
+    #[test]
+    fn test_known_trade_id_is_classified_as_trade() {
+        let raw = format!("0X{}", "CD".repeat(32));
+        let id = normalize_bytes32(&raw).unwrap();
+        assert_eq!(id, format!("0x{}", "cd".repeat(32)));
+
+        let decoded = classify_id(id.clone(), Some(&trade(&id, 0)), None);
+        assert_eq!(decoded.kind, "trade");
+        assert_eq!(decoded.state.as_deref(), Some("pending"));
+        assert_eq!(decoded.chain_id, Some(8453));
+        assert_eq!(decoded.tx_hash.as_deref(), Some("0xescrow"));
+
+        let unknown = classify_id(id, None, None);
+        assert_eq!(unknown.kind, "unknown");
+    }
+
+    #[test]
+    fn test_bad_address_param_is_bad_request() {
+        assert_eq!(parse_address("seller", "0xABCDEF0000000000000000000000000000000001").unwrap(), "0xabcdef0000000000000000000000000000000001");
+        match parse_address("seller", "0xabc") {
+            Err(ApiError::BadRequest(msg)) => assert_eq!(msg, "Invalid seller \"0xabc\": expected 40 hex characters, got 3"),
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_normalize_bytes32_rejects_bad_input() {
+        assert!(normalize_bytes32("0x1234").is_none());
+        assert!(normalize_bytes32(&format!("0x{}", "zz".repeat(32))).is_none());
+    }
+}
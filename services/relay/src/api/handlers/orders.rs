@@ -5,61 +5,52 @@
 
 use axum::{
     extract::{Path, Query, State},
-    // http::HeaderMap,  // TODO: re-enable when auth is restored
+    http::{header, HeaderMap},
     Json,
 };
 use chrono::{DateTime, Utc};
+use ethers::types::U256;
+use rust_decimal::Decimal;
+use std::future::Future;
+use std::str::FromStr;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 
 use crate::api::{
     error::{ApiError, ApiResult},
-    state::AppState,
+    handlers::{parse_address, require_admin},
+    live::LiveEventKind,
+    state::{AppState, VerifiedHashCache},
+    types::{OrderVerifyResponse, VerifyOrderQuery},
 };
-// use crate::auth;  // TODO: re-enable when auth is restored
-use crate::email::format_token_amount;
+use crate::blockchain::chain_clients::ChainClientError;
+use crate::blockchain::client::EthereumClientError;
+use crate::auth;
+use crate::config::ApiConfig;
+use crate::db::{models::{DbOrder, DbTrade, DbWithdrawal}, DbError};
+use crate::db::orders::OrderSearch;
+use crate::currency::{format_fiat, parse_amount, Currency, InvalidAmount};
+use crate::email::{format_token_amount, format_token_decimal_scaled, order_created::order_created_info};
+use crate::rail::PaymentRail;
+use crate::retry::RetryPolicy;
+use crate::price_oracle::PriceOracle;
+use crate::tokens;
+use crate::webhooks::OrderWebhookPayload;
 
 // ================================================================
 // TOKEN HELPERS
 // ================================================================
 
-/// Get token symbol from address (Base Mainnet + Ethereum Mainnet)
-fn get_token_symbol(token_address: &str) -> String {
-    let addr = token_address.to_lowercase();
-    match addr.as_str() {
-        // Base Mainnet
-        "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913" => "USDC".to_string(),
-        "0x4200000000000000000000000000000000000006" => "WETH".to_string(),
-        "0xcbb7c0000ab88b473b1f5afd9ef808440eed33bf" => "cbBTC".to_string(),
-        // Ethereum Mainnet
-        "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" => "USDC".to_string(),  // ETH USDC
-        "0xdac17f958d2ee523a2206206994597c13d831ec7" => "USDT".to_string(),  // ETH USDT
-        "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2" => "WETH".to_string(),  // ETH WETH
-        "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599" => "WBTC".to_string(),  // ETH WBTC
-        _ => "TOKEN".to_string(),
-    }
-}
-
-/// Get token decimals from address (Base Mainnet + Ethereum Mainnet)
+/// Get token decimals from address (see crate::tokens)
 fn get_token_decimals(token_address: &str) -> u8 {
-    let addr = token_address.to_lowercase();
-    match addr.as_str() {
-        // Base Mainnet
-        "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913" => 6,  // USDC
-        "0x4200000000000000000000000000000000000006" => 18, // WETH
-        "0xcbb7c0000ab88b473b1f5afd9ef808440eed33bf" => 8,  // cbBTC
-        // Ethereum Mainnet
-        "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" => 6,  // USDC
-        "0xdac17f958d2ee523a2206206994597c13d831ec7" => 6,  // USDT
-        "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2" => 18, // WETH
-        "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599" => 8,  // WBTC
-        _ => 18,
-    }
+    tokens::symbol_and_decimals(token_address).1
 }
 
 /// Query parameters for listing orders
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct OrderQueryParams {
-    /// Maximum number of orders to return
+    /// Maximum number of orders to return (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)
     pub limit: Option<i64>,
     
     /// Filter by seller address (optional)
@@ -71,45 +62,111 @@ pub struct OrderQueryParams {
     /// Filter by chain ID (optional, None = all chains)
     /// 8453 = Base, 1 = Ethereum
     pub chain_id: Option<i32>,
+    
+    /// Minimum exchange rate, inclusive (CNY cents per token, same unit as `exchange_rate`)
+    pub min_rate: Option<String>,
+    
+    /// Maximum exchange rate, inclusive (CNY cents per token)
+    pub max_rate: Option<String>,
+    
+    /// Minimum remaining amount, inclusive (token base units, same as `remaining_amount`)
+    pub min_amount: Option<String>,
+    
+    /// Maximum remaining amount, inclusive (token base units)
+    pub max_amount: Option<String>,
+    
+    /// Match any of these token addresses (comma-separated, e.g. USDC,USDT addresses)
+    #[serde(default, deserialize_with = "deserialize_comma_separated")]
+    #[param(value_type = Option<String>)]
+    pub tokens: Option<Vec<String>>,
+    
+    /// Filter by payment rail: 0 = Alipay, 1 = WeChat (optional)
+    pub rail: Option<i32>,
+    
+    /// Resolve each seller's primary ENS name into `seller_ens` (default false)
+    #[serde(default)]
+    pub include_ens: bool,
 }
 
 /// Order response DTO
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct OrderDto {
     pub order_id: String,
     pub seller: String,
     pub token: String,
     pub total_amount: String,
     pub remaining_amount: String,
+    /// total_amount / remaining_amount in whole tokens (decimal string, e.g. "1.5")
+    pub total_amount_decimal: String,
+    pub remaining_amount_decimal: String,
     pub exchange_rate: String,
-    pub rail: i32,  // PaymentRail: 0=ALIPAY, 1=WECHAT
+    #[schema(value_type = i32)]
+    pub rail: PaymentRail,  // 0=ALIPAY, 1=WECHAT
     pub alipay_id: String,
     pub alipay_name: String,
     pub created_at: i64,
     pub chain_id: i32,  // Chain ID: 8453=Base, 1=Ethereum
     pub is_public: bool,
+    /// Unlisted orders only; sent to the order's seller (bearer token) or to a
+    /// lookup by the code itself, never to anyone else
     #[serde(skip_serializing_if = "Option::is_none")]
     pub private_code: Option<String>,
+    /// False until the seller submits payment info (order can't be traded yet)
+    pub payment_info_set: bool,
+    /// ISO 4217 code of exchange_rate (e.g. "CNY")
+    pub currency: String,
+    /// The order's chain isn't configured on this relay: it can't be traded or
+    /// verified here (only listed when HIDE_UNSUPPORTED_CHAIN_ORDERS=false, or to its seller)
+    pub unsupported_chain: bool,
+    /// Seller's primary ENS name (only resolved with `include_ens=true`; null without a reverse record)
+    pub seller_ens: Option<String>,
+    /// Set when exchange_rate is far from the reference market price (price oracle
+    /// enabled only); informational, the order is still listed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub warning: Option<String>,
 }
 
 /// List of orders response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct OrderListResponse {
     pub orders: Vec<OrderDto>,
     pub total: usize,
+    /// Page size applied to the public book (`limit` after clamping to MAX_PAGE_SIZE);
+    /// null for seller listings, which aren't paged
+    pub limit: Option<i64>,
 }
 
 /// GET /api/orders/active
 /// Get list of active sell orders (remaining_amount > 0)
 /// 
+/// Optional `min_rate` / `max_rate` bound `exchange_rate` inclusively. Rates are
+/// CNY per token expressed in cents, NOT token per CNY: ¥7.15 per USDC is stored
+/// and returned as 715, so a ¥7.00–¥7.30 band is `min_rate=700&max_rate=730`.
+/// The column is NUMERIC(78,0), so every row compares cleanly (no text parsing).
+/// `min_amount` / `max_amount` bound `remaining_amount` inclusively in token base
+/// units, and `tokens` matches any of a comma-separated list of addresses.
+/// min > max for either range is rejected with 400.
+/// `include_ens=true` fills `seller_ens` (resolved on Ethereum mainnet, cached).
+/// 
 /// NOTE: Authentication temporarily disabled. When re-enabling, uncomment the
 /// JWT verification block below and the `headers` parameter.
+#[utoipa::path(
+    get, path = "/api/orders/active", tag = "orders",
+    params(OrderQueryParams),
+    responses(
+        (status = 200, description = "Active orders", body = OrderListResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 pub async fn get_active_orders(
     State(state): State<AppState>,
-    // headers: HeaderMap,  // TODO: re-enable when auth is restored
+    headers: HeaderMap,
     Query(params): Query<OrderQueryParams>,
 ) -> ApiResult<Json<OrderListResponse>> {
-    let orders = if let Some(seller) = params.seller {
+    let rail = parse_rail_param(params.rail)?;
+    let include_ens = params.include_ens;
+    let (orders, limit) = if let Some(seller) = params.seller {
         // TODO: Re-enable authentication when ready
         // AUTHENTICATED: Seller-specific query requires JWT proof of wallet ownership
         // let auth_header = headers.get(axum::http::header::AUTHORIZATION)
@@ -125,47 +182,179 @@ pub async fn get_active_orders(
         // }
         
         // Get orders by seller (includes private orders)
-        state.db.get_orders_by_seller(&seller).await?
-    } else if let Some(token) = params.token {
-        // Get orders by token (optionally filtered by chain)
-        state.db.get_active_orders_by_token(&token, params.limit, params.chain_id).await?
+        let seller = parse_address("seller", &seller)?;
+        let orders = state.db.get_orders_by_seller(&seller, rail).await?;
+        (orders, None)
     } else {
-        // Get all active orders (optionally filtered by chain)
-        state.db.get_active_orders(params.limit, params.chain_id).await?
+        // Public book, optionally filtered by chain, token(s), rate and amount range
+        if let Some(chain_id) = params.chain_id {
+            let chain_id = u64::try_from(chain_id)
+                .map_err(|_| ApiError::BadRequest(format!("unsupported chain_id {}", chain_id)))?;
+            state.require_supported_chain(chain_id)?;
+        }
+        let search = OrderSearch {
+            chain_id: params.chain_id,
+            token: params.token.as_deref().map(|t| parse_address("token", t)).transpose()?,
+            tokens: params.tokens.unwrap_or_default().iter().map(|t| parse_address("tokens", t)).collect::<ApiResult<_>>()?,
+            min_rate: parse_decimal_param("min_rate", params.min_rate.as_deref())?,
+            max_rate: parse_decimal_param("max_rate", params.max_rate.as_deref())?,
+            min_amount: parse_amount_param("min_amount", params.min_amount.as_deref())?,
+            max_amount: parse_amount_param("max_amount", params.max_amount.as_deref())?,
+            rail,
+            require_payment_info: state.api_config.hide_orders_without_payment_info,
+            chain_ids: state.api_config.hide_unsupported_chain_orders.then(|| {
+                state.supported_chains.iter().filter_map(|&id| i32::try_from(id).ok()).collect()
+            }),
+        };
+        search.validate().map_err(|e| ApiError::BadRequest(e.to_string()))?;
+        let limit = state.api_config.page_limit(params.limit);
+        if limit <= 0 {
+            return Err(ApiError::BadRequest("limit must be > 0".to_string()));
+        }
+        let orders = state.db.search_orders(&search, Some(limit)).await?;
+        (orders, Some(limit))
     };
     
-    let order_dtos: Vec<OrderDto> = orders
+    let mut order_dtos: Vec<OrderDto> = orders
         .into_iter()
-        .map(|o| order_to_dto(o))
+        .map(|o| order_to_dto(o, &state.api_config, &state.supported_chains))
         .collect();
+    redact_private_codes(&mut order_dtos, caller_wallet(&headers).as_deref());
+    
+    flag_rate_deviations(state.price_oracle.as_deref(), &mut order_dtos).await;
+    
+    if include_ens {
+        let names = state.resolve_ens_names(order_dtos.iter().map(|o| o.seller.as_str())).await;
+        for order in &mut order_dtos {
+            order.seller_ens = names.get(&order.seller.to_lowercase()).cloned();
+        }
+    }
     
     let total = order_dtos.len();
     
     Ok(Json(OrderListResponse {
         orders: order_dtos,
         total,
+        limit,
     }))
 }
 
 /// GET /api/orders/private/:code
 /// Get order by private code (for unlisted orders)
+///
+/// Any code that doesn't resolve gets the same 404, without echoing the code;
+/// the rate limiter counts these misses per IP (see api::rate_limit).
+#[utoipa::path(
+    get, path = "/api/orders/private/{code}", tag = "orders",
+    params(("code" = String, Path, description = "Private code of an unlisted order")),
+    responses(
+        (status = 200, description = "The order", body = OrderDto),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 #[axum::debug_handler]
 pub async fn get_order_by_private_code(
     State(state): State<AppState>,
     Path(code): Path<String>,
 ) -> ApiResult<Json<OrderDto>> {
-    let order = state.db.get_order_by_private_code(&code).await?;
-    Ok(Json(order_to_dto(order)))
+    let order = state.db.get_order_by_private_code(&code).await.map_err(|e| match e {
+        DbError::OrderNotFound(_) => ApiError::NotFound("Order not found".to_string()),
+        other => other.into(),
+    })?;
+    let mut dto = order_to_dto(order, &state.api_config, &state.supported_chains);
+    flag_rate_deviations(state.price_oracle.as_deref(), std::slice::from_mut(&mut dto)).await;
+    Ok(Json(dto))
+}
+
+/// Most order IDs accepted by POST /api/orders/batch
+const MAX_BATCH_ORDER_IDS: usize = 100;
+
+/// Request body for POST /api/orders/batch
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct BatchOrdersRequest {
+    pub order_ids: Vec<String>,
+}
+
+/// Response for POST /api/orders/batch
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchOrdersResponse {
+    /// Found orders, in request order (duplicates collapsed)
+    pub orders: Vec<OrderDto>,
+    /// Requested IDs with no order, in request order
+    pub missing: Vec<String>,
+}
+
+/// POST /api/orders/batch
+/// Fetch up to MAX_BATCH_ORDER_IDS orders in one query (e.g. the orders behind a
+/// list of trades), instead of one request per order. Private codes are only
+/// included for orders of the wallet in the bearer token.
+#[utoipa::path(
+    post, path = "/api/orders/batch", tag = "orders",
+    request_body = BatchOrdersRequest,
+    responses(
+        (status = 200, description = "Found orders and missing ids", body = BatchOrdersResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
+pub async fn get_orders_batch(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Json(req): Json<BatchOrdersRequest>,
+) -> ApiResult<Json<BatchOrdersResponse>> {
+    if req.order_ids.len() > MAX_BATCH_ORDER_IDS {
+        return Err(ApiError::BadRequest(format!(
+            "Too many order IDs: {} (max {})",
+            req.order_ids.len(),
+            MAX_BATCH_ORDER_IDS
+        )));
+    }
+    let order_ids = dedup_ids(req.order_ids);
+    let found = if order_ids.is_empty() {
+        Vec::new()
+    } else {
+        state.db.get_orders_by_ids(&order_ids).await?
+    };
+    let (orders, missing) = in_request_order(&order_ids, found);
+    let mut orders: Vec<OrderDto> = orders
+        .into_iter()
+        .map(|o| order_to_dto(o, &state.api_config, &state.supported_chains))
+        .collect();
+    redact_private_codes(&mut orders, caller_wallet(&headers).as_deref());
+    flag_rate_deviations(state.price_oracle.as_deref(), &mut orders).await;
+    Ok(Json(BatchOrdersResponse { orders, missing }))
+}
+
+/// Drop repeated IDs, keeping the first occurrence
+fn dedup_ids(ids: Vec<String>) -> Vec<String> {
+    let mut seen = std::collections::HashSet::new();
+    ids.into_iter().filter(|id| seen.insert(id.clone())).collect()
+}
+
+/// Arrange `found` in the order of `order_ids`, and list the IDs not found
+fn in_request_order(order_ids: &[String], found: Vec<DbOrder>) -> (Vec<DbOrder>, Vec<String>) {
+    let mut by_id: std::collections::HashMap<String, DbOrder> =
+        found.into_iter().map(|o| (o.order_id.clone(), o)).collect();
+    let mut orders = Vec::with_capacity(by_id.len());
+    let mut missing = Vec::new();
+    for id in order_ids {
+        match by_id.remove(id) {
+            Some(order) => orders.push(order),
+            None => missing.push(id.clone()),
+        }
+    }
+    (orders, missing)
 }
 
 /// Request body for setting order visibility
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct SetVisibilityRequest {
     pub is_public: bool,
 }
 
 /// Response for visibility update
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SetVisibilityResponse {
     pub success: bool,
     pub is_public: bool,
@@ -175,43 +364,58 @@ pub struct SetVisibilityResponse {
 
 /// POST /api/orders/:order_id/visibility
 /// Set order visibility (public/private) - only the seller should call this
+/// Making an order private always rotates its private code; making it public clears it
 /// Also sends the order creation email (since we wait for visibility to be set before emailing)
+/// and, the first time visibility is chosen, fires the `order.ready` webhook; the two are
+/// independent, neither waits on the other
+#[utoipa::path(
+    post, path = "/api/orders/{order_id}/visibility", tag = "orders",
+    params(("order_id" = String, Path, description = "bytes32 order id (0x-prefixed)")),
+    request_body = SetVisibilityRequest,
+    responses(
+        (status = 200, description = "Visibility updated", body = SetVisibilityResponse),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 #[axum::debug_handler]
 pub async fn set_order_visibility(
     State(state): State<AppState>,
     Path(order_id): Path<String>,
     Json(req): Json<SetVisibilityRequest>,
 ) -> ApiResult<Json<SetVisibilityResponse>> {
-    let private_code = state.db.set_order_visibility(&order_id, req.is_public).await?;
+    let change = state.db.set_order_visibility(&order_id, req.is_public).await?;
+    let private_code = change.private_code;
     
-    // Get order details for email
-    if let Ok(order) = state.db.get_order(&order_id).await {
+    // Get order details for email (from the primary: the replica may not have the new visibility yet)
+    if let Ok(order) = state.db.primary().get_order(&order_id).await {
+        // A private order only shows up on the feed to be dropped from books that listed it
+        if req.is_public {
+            state.live_feed.publish(LiveEventKind::OrderUpdated, &order, None);
+        } else if change.was_listed {
+            state.live_feed.publish(LiveEventKind::OrderHidden, &order, None);
+        }
+        
+        // Delivered in the background, so a slow or failing endpoint never delays the email.
+        // Later toggles aren't announced: the order has been ready since the first choice
+        if let Some(webhooks) = state.webhook_sender.as_ref().filter(|_| change.first_choice) {
+            webhooks.dispatch(OrderWebhookPayload::ready(&order));
+        }
+        
         // Get seller's email (returns Result<Option<...>>)
         if let Ok(Some(account_email)) = state.db.get_account_email(&order.seller).await {
-            // Get token info
-            let token_symbol = get_token_symbol(&order.token);
-            let token_decimals = get_token_decimals(&order.token);
-            
             // Send order created email with visibility info
             if let Some(email_service) = crate::email::EmailService::from_env() {
                 let language = &account_email.language;
                 let is_private = !req.is_public;
                 
-                let _ = email_service.send_notification(
+                email_service.send_or_enqueue(
+                    state.db.pool(),
+                    &account_email.wallet,
                     &account_email.email,
                     &language,
                     crate::email::EmailEvent::OrderCreated,
-                    &crate::email::EmailInfo::OrderCreated {
-                        order_id: order_id.clone(),
-                        token_amount: format_token_amount(&order.total_amount, token_decimals, ""),
-                        token_symbol,
-                        exchange_rate: order.exchange_rate.clone(),
-                        account_id: order.alipay_id.clone(),
-                        account_name: order.alipay_name.clone(),
-                        rail: order.rail,  // Pass rail number, template will localize
-                        is_private,
-                        private_code: private_code.clone(),
-                    },
+                    &order_created_info(&order, is_private, private_code.clone()),
                 ).await;
                 
                 let visibility = if is_private { "private" } else { "public" };
@@ -227,11 +431,128 @@ pub async fn set_order_visibility(
     }))
 }
 
-/// Helper to convert DbOrder to OrderDto
-fn order_to_dto(o: crate::db::models::DbOrder) -> OrderDto {
+/// GET /api/orders/:order_id/verify?resync= - Compare the indexed remaining amount
+/// with the escrow contract's, to detect missed or reordered events
+///
+/// With `resync=true` (admin wallets only) a drifted order is overwritten with the
+/// on-chain value and published to the live feed, unless an event changed it since
+/// it was read; otherwise nothing is written.
+#[utoipa::path(
+    get, path = "/api/orders/{order_id}/verify", tag = "orders",
+    params(("order_id" = String, Path, description = "bytes32 order id (0x-prefixed)"), VerifyOrderQuery),
+    responses(
+        (status = 200, description = "Indexed vs on-chain remaining amount", body = OrderVerifyResponse),
+        (status = 401, description = "resync=true without an admin token", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 503, description = "RPC or service unavailable", body = ErrorBody),
+    )
+)]
+pub async fn verify_order_remaining(
+    State(state): State<AppState>,
+    Path(order_id): Path<String>,
+    Query(query): Query<VerifyOrderQuery>,
+    headers: HeaderMap,
+) -> ApiResult<Json<OrderVerifyResponse>> {
+    if query.resync {
+        require_admin(&state.api_config, &headers)?;
+    }
+    let order = state.db.primary().get_order(&order_id).await?;
+    let chain_id = order.chain_id as u64;
+    let client = state.get_blockchain_client(chain_id)?;
+    
+    let mut report = compare_with_chain(&order, client.get_order_remaining(&order.order_id)).await?;
+    if !report.in_sync {
+        tracing::warn!(
+            "⚠️ Order {} remaining amount drifted: db={} chain={}",
+            order.order_id, report.db_value, report.chain_value
+        );
+        if query.resync {
+            if state.db.resync_remaining_amount(&order.order_id, &report.db_value, &report.chain_value).await? {
+                if let Ok(order) = state.db.primary().get_order(&order.order_id).await {
+                    state.live_feed.publish_remaining(&order);
+                }
+                tracing::info!("🔄 Order {} remaining amount resynced to {}", order.order_id, report.chain_value);
+                report.resynced = true;
+            } else {
+                tracing::info!("🔄 Order {} changed while verifying - not resynced, verify again", order.order_id);
+            }
+        }
+    }
+    Ok(Json(report))
+}
+
+/// Compare `order` with the remaining amount `chain_remaining` reads on-chain
+async fn compare_with_chain(
+    order: &DbOrder,
+    chain_remaining: impl Future<Output = Result<U256, EthereumClientError>>,
+) -> ApiResult<OrderVerifyResponse> {
+    let chain_value = chain_remaining.await?;
+    Ok(OrderVerifyResponse::compare(order, &chain_value.to_string()))
+}
+
+/// Parse an optional decimal query param (rates)
+fn parse_decimal_param(name: &str, value: Option<&str>) -> ApiResult<Option<Decimal>> {
+    value
+        .map(|v| {
+            Decimal::from_str(v.trim())
+                .map_err(|_| ApiError::BadRequest(format!("Invalid {}: {}", name, v)))
+        })
+        .transpose()
+}
+
+/// Parse an optional uint256 amount query param (token base units); 400 if malformed
+fn parse_amount_param(name: &str, value: Option<&str>) -> ApiResult<Option<U256>> {
+    value
+        .map(|v| {
+            U256::from_dec_str(v.trim())
+                .map_err(|_| ApiError::BadRequest(format!("Invalid {}: {}", name, v)))
+        })
+        .transpose()
+}
+
+/// Deserialize `a,b,c` into a list, dropping empty entries (None if nothing left)
+fn deserialize_comma_separated<'de, D>(deserializer: D) -> Result<Option<Vec<String>>, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let raw: Option<String> = Option::deserialize(deserializer)?;
+    Ok(raw
+        .map(|s| {
+            s.split(',')
+                .map(|t| t.trim().to_string())
+                .filter(|t| !t.is_empty())
+                .collect::<Vec<_>>()
+        })
+        .filter(|tokens| !tokens.is_empty()))
+}
+
+/// Whether the seller has submitted payment info (required before buyers can trade)
+fn has_payment_info(o: &DbOrder) -> bool {
+    !o.alipay_id.is_empty() && !o.alipay_name.is_empty()
+}
+
+fn is_supported_chain(order: &DbOrder, supported_chains: &[u64]) -> bool {
+    u64::try_from(order.chain_id).is_ok_and(|chain_id| supported_chains.contains(&chain_id))
+}
+
+/// `?rail=` as a PaymentRail; anything but 0 or 1 is a 400
+fn parse_rail_param(rail: Option<i32>) -> ApiResult<Option<PaymentRail>> {
+    rail.map(PaymentRail::try_from).transpose().map_err(ApiError::BadRequest)
+}
+
+/// Helper to convert DbOrder to OrderDto (`*_decimal` amounts use the configured scale)
+fn order_to_dto(o: DbOrder, config: &ApiConfig, supported_chains: &[u64]) -> OrderDto {
+    let payment_info_set = has_payment_info(&o);
+    let unsupported_chain = !is_supported_chain(&o, supported_chains);
+    let token_decimals = get_token_decimals(&o.token);
+    let scale = config.decimal_scale(&o.token);
     OrderDto {
         order_id: o.order_id,
         seller: o.seller,
+        total_amount_decimal: format_token_decimal_scaled(&o.total_amount, token_decimals, scale)
+            .unwrap_or_else(InvalidAmount::into_raw),
+        remaining_amount_decimal: format_token_decimal_scaled(&o.remaining_amount, token_decimals, scale)
+            .unwrap_or_else(InvalidAmount::into_raw),
         token: o.token,
         total_amount: o.total_amount,
         remaining_amount: o.remaining_amount,
@@ -243,6 +564,40 @@ fn order_to_dto(o: crate::db::models::DbOrder) -> OrderDto {
         chain_id: o.chain_id,
         is_public: o.is_public,
         private_code: o.private_code,
+        payment_info_set,
+        currency: o.currency,
+        unsupported_chain,
+        seller_ens: None,
+        warning: None,
+    }
+}
+
+/// Wallet of a valid bearer token on the request, if any
+fn caller_wallet(headers: &HeaderMap) -> Option<String> {
+    let auth_header = headers.get(header::AUTHORIZATION)?.to_str().ok()?;
+    auth::verify_jwt(auth_header).ok()
+}
+
+/// Clear `private_code` on every order not owned by `caller`: the code is what
+/// grants access to an unlisted order, so only its seller gets it back
+fn redact_private_codes(orders: &mut [OrderDto], caller: Option<&str>) {
+    for order in orders {
+        if caller.is_none_or(|wallet| !wallet.eq_ignore_ascii_case(&order.seller)) {
+            order.private_code = None;
+        }
+    }
+}
+
+/// Fill `warning` on orders whose exchange rate deviates from the reference price
+/// (no-op when the price oracle is disabled)
+async fn flag_rate_deviations(oracle: Option<&PriceOracle>, orders: &mut [OrderDto]) {
+    let Some(oracle) = oracle else { return };
+    let rates: Vec<(&str, &str, &str)> = orders.iter()
+        .map(|o| (o.token.as_str(), o.currency.as_str(), o.exchange_rate.as_str()))
+        .collect();
+    let warnings = oracle.check_rates(&rates).await;
+    for (order, warning) in orders.iter_mut().zip(warnings) {
+        order.warning = warning;
     }
 }
 
@@ -251,7 +606,12 @@ fn order_to_dto(o: crate::db::models::DbOrder) -> OrderDto {
 // ============================================================================
 
 /// Activity types for order timeline
-#[derive(Debug, Serialize)]
+///
+/// Every time field is an ISO-8601 UTC string (see `serialize_iso8601`), and
+/// every variant also carries `timestamp`: unix seconds of the time the timeline
+/// is sorted by (settlement, creation or withdrawal). See `into_timeline` for
+/// the order.
+#[derive(Debug, Serialize, ToSchema)]
 #[serde(tag = "type")]
 pub enum OrderActivity {
     /// A successful trade settlement
@@ -261,12 +621,19 @@ pub enum OrderActivity {
         buyer: String,
         token_amount: String,
         token_amount_formatted: String,
+        token_amount_decimal: String,
         fee_amount: String,
         fee_amount_formatted: String,
+        fee_amount_decimal: String,
         cny_amount: String,
         cny_amount_formatted: String,
+        /// ISO 4217 code of cny_amount (e.g. "CNY")
+        currency: String,
         settlement_tx: Option<String>,
-        settled_at: i64,  // Unix timestamp (use created_at since that's when the trade happened)
+        /// Trade creation time (that's when the trade happened)
+        #[serde(serialize_with = "serialize_iso8601")]
+        settled_at: DateTime<Utc>,
+        timestamp: i64,
     },
     /// A pending trade (buyer has initiated but not yet paid/settled)
     #[serde(rename = "pending_trade")]
@@ -275,10 +642,16 @@ pub enum OrderActivity {
         buyer: String,
         token_amount: String,
         token_amount_formatted: String,
+        token_amount_decimal: String,
         cny_amount: String,
         cny_amount_formatted: String,
-        created_at: i64,
-        expires_at: i64,
+        /// ISO 4217 code of cny_amount (e.g. "CNY")
+        currency: String,
+        #[serde(serialize_with = "serialize_iso8601")]
+        created_at: DateTime<Utc>,
+        #[serde(serialize_with = "serialize_iso8601")]
+        expires_at: DateTime<Utc>,
+        timestamp: i64,
     },
     /// An expired trade (buyer failed to pay in time)
     #[serde(rename = "expired_trade")]
@@ -287,43 +660,156 @@ pub enum OrderActivity {
         buyer: String,
         token_amount: String,
         token_amount_formatted: String,
+        token_amount_decimal: String,
         cny_amount: String,
         cny_amount_formatted: String,
-        created_at: i64,
-        expired_at: i64,
+        /// ISO 4217 code of cny_amount (e.g. "CNY")
+        currency: String,
+        #[serde(serialize_with = "serialize_iso8601")]
+        created_at: DateTime<Utc>,
+        #[serde(serialize_with = "serialize_iso8601")]
+        expired_at: DateTime<Utc>,
+        timestamp: i64,
     },
     /// A withdrawal from the order
     #[serde(rename = "withdrawal")]
     Withdrawal {
         amount: String,
         amount_formatted: String,
+        amount_decimal: String,
         remaining_after: String,
         remaining_after_formatted: String,
+        remaining_after_decimal: String,
         tx_hash: Option<String>,
+        #[serde(serialize_with = "serialize_iso8601")]
         created_at: DateTime<Utc>,
+        timestamp: i64,
     },
 }
 
+impl OrderActivity {
+    /// Unix seconds the timeline is sorted by
+    pub fn timestamp(&self) -> i64 {
+        match self {
+            OrderActivity::Trade { timestamp, .. }
+            | OrderActivity::PendingTrade { timestamp, .. }
+            | OrderActivity::ExpiredTrade { timestamp, .. }
+            | OrderActivity::Withdrawal { timestamp, .. } => *timestamp,
+        }
+    }
+
+    /// What identifies the activity: the trade ID, or the withdrawal's
+    /// transaction hash (None for withdrawals recorded without one)
+    pub fn id(&self) -> Option<&str> {
+        match self {
+            OrderActivity::Trade { trade_id, .. }
+            | OrderActivity::PendingTrade { trade_id, .. }
+            | OrderActivity::ExpiredTrade { trade_id, .. } => Some(trade_id),
+            OrderActivity::Withdrawal { tx_hash, .. } => tx_hash.as_deref(),
+        }
+    }
+}
+
+/// Timeline order: most recent first; activities in the same second by `id`
+/// ascending, withdrawals without a tx hash after the rest (in fetch order).
+/// The same history therefore always comes back in the same order. An id that
+/// shows up twice (e.g. a trade indexed twice) is kept once, the most recent.
+fn into_timeline(mut activities: Vec<OrderActivity>) -> Vec<OrderActivity> {
+    let key = |activity: &OrderActivity| (std::cmp::Reverse(activity.timestamp()), activity.id().is_none());
+    activities.sort_by(|a, b| key(a).cmp(&key(b)).then_with(|| a.id().cmp(&b.id())));
+    let mut seen = std::collections::HashSet::new();
+    activities.retain(|activity| activity.id().is_none_or(|id| seen.insert(id.to_string())));
+    activities
+}
+
+/// Unix seconds as UTC (out-of-range values clamp to the epoch)
+fn unix_to_utc(secs: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp(secs, 0).unwrap_or_default()
+}
+
+/// Shared format of every activity time: whole seconds, `Z` suffix
+/// (e.g. "2024-05-01T08:30:00Z"), whatever precision the source had
+fn serialize_iso8601<S: serde::Serializer>(time: &DateTime<Utc>, serializer: S) -> Result<S::Ok, S::Error> {
+    serializer.serialize_str(&time.to_rfc3339_opts(chrono::SecondsFormat::Secs, true))
+}
+
 /// Order activities response
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct OrderActivitiesResponse {
     pub order: OrderDto,
+    /// Most recent first, ties broken by trade ID / tx hash, no repeats (see `into_timeline`)
     pub activities: Vec<OrderActivity>,
     pub token_symbol: String,
     pub token_decimals: u8,
+    /// Set when the trades and withdrawals don't add up to the order's current
+    /// remaining amount (missed or double-applied event)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining_warning: Option<String>,
+}
+
+/// Check the order's remaining amount against its history: total minus the
+/// reserve (token + fee) of every pending or settled trade minus withdrawals.
+/// Expired trades returned their reserve, so they don't count.
+/// `fee_rate_bps` only prices trades with neither a fee nor a creation-time rate.
+/// A malformed amount is an error: counting it as zero would misreport the check.
+fn reconcile_remaining(
+    order: &DbOrder,
+    trades: &[DbTrade],
+    withdrawals: &[DbWithdrawal],
+    fee_rate_bps: u128,
+) -> Result<Option<String>, InvalidAmount> {
+    let mut reserved: u128 = 0;
+    for trade in trades.iter().filter(|t| t.status == 0 || t.status == 1) {
+        reserved += parse_amount::<u128>(&trade.token_amount)? + parse_amount::<u128>(&trade.fee_or_estimate(fee_rate_bps)?)?;
+    }
+    let mut withdrawn: u128 = 0;
+    for withdrawal in withdrawals {
+        withdrawn += parse_amount::<u128>(&withdrawal.amount)?;
+    }
+    let remaining: u128 = parse_amount(&order.remaining_amount)?;
+
+    Ok(match parse_amount::<u128>(&order.total_amount)?.checked_sub(reserved + withdrawn) {
+        Some(implied) if implied == remaining => None,
+        Some(implied) => Some(format!(
+            "remaining amount {} does not match trades and withdrawals (implied {})",
+            remaining, implied
+        )),
+        None => Some(format!(
+            "trades ({}) and withdrawals ({}) exceed the order total {}",
+            reserved, withdrawn, order.total_amount
+        )),
+    })
 }
 
 /// GET /api/orders/:order_id/activities
 /// Get order with all activities (trades + withdrawals) for timeline display
+#[utoipa::path(
+    get, path = "/api/orders/{order_id}/activities", tag = "orders",
+    params(("order_id" = String, Path, description = "bytes32 order id (0x-prefixed)")),
+    responses(
+        (status = 200, description = "Order with its activity timeline", body = OrderActivitiesResponse),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 pub async fn get_order_activities(
     State(state): State<AppState>,
     Path(order_id): Path<String>,
+    headers: HeaderMap,
 ) -> ApiResult<Json<OrderActivitiesResponse>> {
     // Get the order
     let order = state.db.get_order(&order_id).await?;
     
     // Get token info
     let (token_symbol, token_decimals) = get_token_info(&order.token);
+    let scale = state.api_config.decimal_scale(&order.token);
+    let format_token_decimal = |amount: &str| {
+        format_token_decimal_scaled(amount, token_decimals, scale).unwrap_or_else(InvalidAmount::into_raw)
+    };
+    let format_token = |amount: &str| format_token_amount(amount, token_decimals, "").unwrap_or_else(InvalidAmount::into_raw);
+    let format_trade_fiat = |minor_units: &str, currency: &str| {
+        format_fiat(minor_units, Currency::from_code_or_default(currency)).unwrap_or_else(InvalidAmount::into_raw)
+    };
     
     // Get ALL trades for this order (including pending and expired)
     let trades = state.db.get_all_trades_by_order(&order_id).await?;
@@ -334,12 +820,25 @@ pub async fn get_order_activities(
     // Build activities list
     let mut activities: Vec<OrderActivity> = Vec::new();
     
-    // Get fee rate from blockchain config (cached) - used only as fallback
-    let fee_rate_bps: u128 = match state.get_config_for_chain(order.chain_id as u64, false).await {
-        Ok(config) => config.fee_rate_bps.parse().unwrap_or(100), // Default 1% if parsing fails
-        Err(_) => 100, // Default 1% if config fetch fails
+    // Fee rate from blockchain config (cached) - only needed for legacy trades with
+    // neither a recorded fee nor the rate they were created at. Trades with a rate
+    // snapshot are priced at that rate: today's rate may have changed since.
+    let fee_rate_bps: u128 = if trades.iter().any(DbTrade::needs_fee_rate) {
+        let config = state.get_config_for_chain(order.chain_id as u64, false).await?;
+        config.fee_rate_bps().map_err(|e| {
+            tracing::error!("❌ Can't estimate legacy trade fees for order {}: {}", order_id, e);
+            ApiError::BlockchainError(format!("Invalid contract config for chain {}: {}", order.chain_id, e))
+        })?.into()
+    } else {
+        0
     };
     
+    let remaining_warning = reconcile_remaining(&order, &trades, &withdrawals, fee_rate_bps)
+        .unwrap_or_else(|e| Some(format!("can't reconcile the remaining amount: {}", e)));
+    if let Some(warning) = &remaining_warning {
+        tracing::warn!("⚠️ Order {} activities don't reconcile: {}", order_id, warning);
+    }
+    
     // Add trades based on status
     // Status: 0=PENDING, 1=SETTLED, 2=EXPIRED
     for trade in trades {
@@ -350,34 +849,35 @@ pub async fn get_order_activities(
                     trade_id: trade.trade_id,
                     buyer: trade.buyer,
                     token_amount: trade.token_amount.clone(),
-                    token_amount_formatted: format_token_amount(&trade.token_amount, token_decimals, ""),
+                    token_amount_formatted: format_token(&trade.token_amount),
+                    token_amount_decimal: format_token_decimal(&trade.token_amount),
                     cny_amount: trade.cny_amount.clone(),
-                    cny_amount_formatted: format_cny(&trade.cny_amount),
-                    created_at: trade.created_at,
-                    expires_at: trade.expires_at,
+                    cny_amount_formatted: format_trade_fiat(&trade.cny_amount, &trade.currency),
+                    currency: trade.currency.clone(),
+                    created_at: unix_to_utc(trade.created_at),
+                    expires_at: unix_to_utc(trade.expires_at),
+                    timestamp: trade.created_at,
                 });
             }
             1 => {
                 // Settled trade
-                let fee_amount = match &trade.fee_amount {
-                    Some(fee) => fee.clone(),
-                    None => {
-                        let token_amount_u128: u128 = trade.token_amount.parse().unwrap_or(0);
-                        ((token_amount_u128 * fee_rate_bps) / 10000).to_string()
-                    }
-                };
+                let fee_amount = trade.fee_or_estimate(fee_rate_bps).unwrap_or_else(InvalidAmount::into_raw);
                 
                 activities.push(OrderActivity::Trade {
                     trade_id: trade.trade_id,
                     buyer: trade.buyer,
                     token_amount: trade.token_amount.clone(),
-                    token_amount_formatted: format_token_amount(&trade.token_amount, token_decimals, ""),
+                    token_amount_formatted: format_token(&trade.token_amount),
+                    token_amount_decimal: format_token_decimal(&trade.token_amount),
                     fee_amount: fee_amount.clone(),
-                    fee_amount_formatted: format_token_amount(&fee_amount, token_decimals, ""),
+                    fee_amount_formatted: format_token(&fee_amount),
+                    fee_amount_decimal: format_token_decimal(&fee_amount),
                     cny_amount: trade.cny_amount.clone(),
-                    cny_amount_formatted: format_cny(&trade.cny_amount),
+                    cny_amount_formatted: format_trade_fiat(&trade.cny_amount, &trade.currency),
+                    currency: trade.currency.clone(),
                     settlement_tx: trade.settlement_tx_hash,
-                    settled_at: trade.created_at,
+                    settled_at: unix_to_utc(trade.created_at),
+                    timestamp: trade.created_at,
                 });
             }
             2 => {
@@ -386,11 +886,14 @@ pub async fn get_order_activities(
                     trade_id: trade.trade_id,
                     buyer: trade.buyer,
                     token_amount: trade.token_amount.clone(),
-                    token_amount_formatted: format_token_amount(&trade.token_amount, token_decimals, ""),
+                    token_amount_formatted: format_token(&trade.token_amount),
+                    token_amount_decimal: format_token_decimal(&trade.token_amount),
                     cny_amount: trade.cny_amount.clone(),
-                    cny_amount_formatted: format_cny(&trade.cny_amount),
-                    created_at: trade.created_at,
-                    expired_at: trade.expires_at,
+                    cny_amount_formatted: format_trade_fiat(&trade.cny_amount, &trade.currency),
+                    currency: trade.currency.clone(),
+                    created_at: unix_to_utc(trade.created_at),
+                    expired_at: unix_to_utc(trade.expires_at),
+                    timestamp: trade.created_at,
                 });
             }
             _ => {
@@ -403,66 +906,33 @@ pub async fn get_order_activities(
     for w in withdrawals {
         activities.push(OrderActivity::Withdrawal {
             amount: w.amount.clone(),
-            amount_formatted: format_token_amount(&w.amount, token_decimals, ""),
+            amount_formatted: format_token(&w.amount),
+            amount_decimal: format_token_decimal(&w.amount),
             remaining_after: w.remaining_after.clone(),
-            remaining_after_formatted: format_token_amount(&w.remaining_after, token_decimals, ""),
+            remaining_after_formatted: format_token(&w.remaining_after),
+            remaining_after_decimal: format_token_decimal(&w.remaining_after),
             tx_hash: w.tx_hash,
             created_at: w.created_at,
+            timestamp: w.created_at.timestamp(),
         });
     }
     
-    // Sort activities by timestamp (most recent first)
-    // We need to extract a common timestamp for sorting
-    activities.sort_by(|a, b| {
-        let ts_a = match a {
-            OrderActivity::Trade { settled_at, .. } => *settled_at,
-            OrderActivity::PendingTrade { created_at, .. } => *created_at,
-            OrderActivity::ExpiredTrade { created_at, .. } => *created_at,
-            OrderActivity::Withdrawal { created_at, .. } => created_at.timestamp(),
-        };
-        let ts_b = match b {
-            OrderActivity::Trade { settled_at, .. } => *settled_at,
-            OrderActivity::PendingTrade { created_at, .. } => *created_at,
-            OrderActivity::ExpiredTrade { created_at, .. } => *created_at,
-            OrderActivity::Withdrawal { created_at, .. } => created_at.timestamp(),
-        };
-        ts_b.cmp(&ts_a) // Descending order (most recent first)
-    });
+    let mut order = order_to_dto(order, &state.api_config, &state.supported_chains);
+    redact_private_codes(std::slice::from_mut(&mut order), caller_wallet(&headers).as_deref());
     
     Ok(Json(OrderActivitiesResponse {
-        order: order_to_dto(order),
-        activities,
+        order,
+        activities: into_timeline(activities),
         token_symbol,
         token_decimals,
+        remaining_warning,
     }))
 }
 
-/// Get token symbol and decimals from address (Base Mainnet + Ethereum Mainnet)
+/// Get token symbol and decimals from address (see crate::tokens)
 fn get_token_info(token_address: &str) -> (String, u8) {
-    let addr = token_address.to_lowercase();
-    match addr.as_str() {
-        // Base Mainnet
-        "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913" => ("USDC".to_string(), 6),
-        "0xd9aaec86b65d86f6a7b5b1b0c42ffa531710b6ca" => ("USDbC".to_string(), 6),
-        "0x50c5725949a6f0c72e6c4a641f24049a917db0cb" => ("DAI".to_string(), 18),
-        "0x4200000000000000000000000000000000000006" => ("WETH".to_string(), 18),
-        "0xcbb7c0000ab88b473b1f5afd9ef808440eed33bf" => ("cbBTC".to_string(), 8),
-        // Ethereum Mainnet
-        "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48" => ("USDC".to_string(), 6),
-        "0xdac17f958d2ee523a2206206994597c13d831ec7" => ("USDT".to_string(), 6),
-        "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2" => ("WETH".to_string(), 18),
-        "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599" => ("WBTC".to_string(), 8),
-        // Default: assume 18 decimals (ERC20 standard)
-        _ => ("TOKEN".to_string(), 18),
-    }
-}
-
-/// Format CNY amount (stored as cents)
-fn format_cny(cents: &str) -> String {
-    let cents_u64: u64 = cents.parse().unwrap_or(0);
-    let yuan = cents_u64 / 100;
-    let fen = cents_u64 % 100;
-    format!("¥{}.{:02}", yuan, fen)
+    let (symbol, decimals) = tokens::symbol_and_decimals(token_address);
+    (symbol.to_string(), decimals)
 }
 
 // ============================================================================
@@ -479,7 +949,7 @@ fn format_cny(cents: &str) -> String {
 //
 
 /// Request body for submitting payment info
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct PaymentInfoRequest {
     pub account_id: String,
     pub account_name: String,
@@ -488,13 +958,59 @@ pub struct PaymentInfoRequest {
 }
 
 /// Response for payment info submission
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PaymentInfoResponse {
     pub success: bool,
     pub message: String,
     pub computed_hash: String,
 }
 
+/// Whether payment-info should resolve the real orderId from tx_hash after
+/// reading an all-zero hash on-chain (PAYMENT_INFO_TX_FALLBACK_ENABLED)
+fn use_tx_fallback(config: &ApiConfig, verified: bool, got_zero_hash: bool) -> bool {
+    config.payment_info_tx_fallback && !verified && got_zero_hash
+}
+
+/// Payment-info hash check retries (PAYMENT_INFO_VERIFY_ATTEMPTS / PAYMENT_INFO_RETRY_DELAY_MS).
+/// No jitter: the waits exist to let the RPC catch up with the order tx.
+fn verify_retry_policy(config: &ApiConfig) -> RetryPolicy {
+    RetryPolicy::new(config.payment_info_verify_attempts, std::time::Duration::from_millis(config.payment_info_retry_delay_ms))
+        .with_max_delay(std::time::Duration::MAX)
+}
+
+/// Wait before payment-info retry number `retry` (1-based): base, 2x base, 4x base, ...
+fn verify_retry_delay(config: &ApiConfig, retry: u32) -> std::time::Duration {
+    verify_retry_policy(config).delay(retry)
+}
+
+/// Resolve the order id a submission's hash is verified for: from the cache when
+/// this exact (order_id, hash) pair was verified recently, otherwise by awaiting
+/// `on_chain_check` (which is never polled on a cache hit)
+async fn verify_with_cache(
+    cache: &VerifiedHashCache,
+    order_id: &str,
+    computed_hash: &str,
+    on_chain_check: impl Future<Output = ApiResult<String>>,
+) -> ApiResult<String> {
+    if let Some(verified_order_id) = cache.get(order_id, computed_hash) {
+        // Same submission verified moments ago (e.g. a retrying frontend) - skip the RPC round-trips
+        tracing::info!("✅ Hash for order {} already verified on-chain (cached)", verified_order_id);
+        return Ok(verified_order_id);
+    }
+    let verified_order_id = on_chain_check.await?;
+    cache.insert(order_id, computed_hash, &verified_order_id);
+    Ok(verified_order_id)
+}
+
+/// 409 for a payment-info submission to an order that already has it
+fn payment_info_already_set(state: &AppState, order_id: &str) -> ApiError {
+    tracing::warn!("❌ Payment info update rejected for order {} - updates not allowed", order_id);
+    state.verified_hashes.invalidate_order(order_id);
+    ApiError::Conflict(
+        "Payment info already set. Updates are not allowed. Please create a new order if you need different payment details.".to_string()
+    )
+}
+
 /// POST /api/orders/:order_id/payment-info
 /// Submit plain text payment info for an order (seller only)
 /// 
@@ -502,6 +1018,22 @@ pub struct PaymentInfoResponse {
 /// 1. Computes account_lines_hash from the submitted plain text
 /// 2. Queries blockchain to verify the hash matches on-chain
 /// 3. Stores the plain text in the database if verified
+///
+/// 404 when the order isn't on-chain (zero hash), 503 when the RPC never answered,
+/// 400 with `attempts` when the on-chain hash doesn't match, 409 when the order
+/// already has payment info (it can't be changed).
+#[utoipa::path(
+    post, path = "/api/orders/{order_id}/payment-info", tag = "orders",
+    params(("order_id" = String, Path, description = "bytes32 order id, or the order tx hash")),
+    request_body = PaymentInfoRequest,
+    responses(
+        (status = 200, description = "Payment info verified and stored", body = PaymentInfoResponse),
+        (status = 400, description = "Hash mismatch after retries (body reports `attempts`)", body = ErrorBody),
+        (status = 404, description = "Order not on-chain", body = ErrorBody),
+        (status = 409, description = "Payment info already set", body = ErrorBody),
+        (status = 503, description = "RPC unavailable", body = ErrorBody),
+    )
+)]
 #[axum::debug_handler]
 pub async fn submit_payment_info(
     State(state): State<AppState>,
@@ -509,15 +1041,17 @@ pub async fn submit_payment_info(
     Json(req): Json<PaymentInfoRequest>,
 ) -> ApiResult<Json<PaymentInfoResponse>> {
     use crate::api::error::ApiError;
-    use crate::crypto::compute_account_lines_hash;
+    use crate::crypto::{compute_account_lines_hash, AccountLinesDiagnostic};
     
-    // Validate input
-    if req.account_id.trim().is_empty() || req.account_name.trim().is_empty() {
+    // Validate input (whitespace is trimmed the same way for the check, the hash and storage)
+    let account_id = req.account_id.trim();
+    let account_name = req.account_name.trim();
+    if account_id.is_empty() || account_name.is_empty() {
         return Err(ApiError::BadRequest("account_id and account_name cannot be empty".to_string()));
     }
     
-    // Compute account_lines_hash = SHA256(20 || account_name || 21 || account_id)
-    let computed_hash = compute_account_lines_hash(&req.account_name, &req.account_id);
+    // Compute account_lines_hash = SHA256(20 || line20(account_name) || 21 || line21(account_id))
+    let computed_hash = compute_account_lines_hash(account_name, account_id);
     let computed_hash_hex = format!("0x{}", hex::encode(computed_hash));
     
     tracing::info!(
@@ -526,28 +1060,28 @@ pub async fn submit_payment_info(
         account_name: {}\n  \
         computed_hash: {}",
         order_id,
-        req.account_id,
-        req.account_name,
+        account_id,
+        account_name,
         computed_hash_hex
     );
     
-    // Try to fetch order from DB (may not exist yet if payment-info arrives before event listener)
-    let order = state.db.get_order(&order_id).await.ok();
+    // Try to fetch order from DB (may not exist yet if payment-info arrives before event listener).
+    // From the primary: a lagging replica could miss payment info that was just stored
+    let order = state.db.primary().get_order(&order_id).await.ok();
     
-    // Check if payment info already exists (updates not allowed)
-    if let Some(ref order) = order {
-        if !order.alipay_id.is_empty() && !order.alipay_name.is_empty() {
-            tracing::warn!("❌ Payment info update rejected for order {} - updates not allowed", order_id);
-            return Err(ApiError::BadRequest(
-                "Payment info already set. Updates are not allowed. Please create a new order if you need different payment details.".to_string()
-            ));
-        }
+    // Check if payment info already exists (updates not allowed); the write below
+    // re-checks, for a submission that lands in between
+    if order.as_ref().is_some_and(has_payment_info) {
+        return Err(payment_info_already_set(&state, &order_id));
     }
     
     // Determine chain_id: from DB order if available, otherwise from request body
     let chain_id = order.as_ref()
         .map(|o| o.chain_id as u64)
         .or(req.chain_id);
+    if let Some(chain_id) = chain_id {
+        state.require_supported_chain(chain_id)?;
+    }
     
     // CRITICAL: Always verify submitted payment info against on-chain accountLinesHash.
     // This ensures the plain text matches what the seller committed on-chain.
@@ -555,112 +1089,150 @@ pub async fn submit_payment_info(
     //
     // FALLBACK: If the order_id from the URL doesn't exist on-chain (all-zeros hash),
     // the frontend may have sent a tx hash instead. Use tx_hash to look up the real orderId.
-    let mut effective_order_id = order_id.clone();
-    
-    if let Some(chain_id) = chain_id {
-        if let Some(blockchain_client) = state.get_blockchain_client(chain_id) {
-            const MAX_RETRIES: u32 = 3;
-            const RETRY_DELAY_SECS: u64 = 3;
-            
-            let mut verified = false;
-            let mut last_on_chain_hash_hex = String::new();
-            let mut got_zero_hash = false;
-            
-            for attempt in 1..=MAX_RETRIES {
-                match blockchain_client.get_order_hash(&effective_order_id).await {
-                    Ok(on_chain_hash) => {
-                        last_on_chain_hash_hex = format!("0x{}", hex::encode(on_chain_hash));
-                        
-                        if on_chain_hash == computed_hash {
-                            tracing::info!("✅ Hash verified on-chain for order {} (attempt {})", effective_order_id, attempt);
-                            verified = true;
-                            break;
-                        } else if on_chain_hash == [0u8; 32] {
-                            // Order doesn't exist on-chain — might be a tx hash instead
-                            got_zero_hash = true;
-                            if attempt < MAX_RETRIES {
+    let on_chain_check = async {
+        let mut effective_order_id = order_id.clone();
+        
+        if let Some(chain_id) = chain_id {
+            let client = state.get_blockchain_client(chain_id);
+            // A chain that's still reconnecting is a retryable outage, not a bad request
+            if let Err(unavailable @ ChainClientError::Unavailable { .. }) = client {
+                return Err(unavailable.into());
+            }
+            if let Ok(blockchain_client) = client {
+                let max_attempts = verify_retry_policy(&state.api_config).max_attempts.max(1);
+                
+                let mut verified = false;
+                let mut last_on_chain_hash_hex = String::new();
+                let mut got_zero_hash = false;
+                let mut last_rpc_error = None;
+                let mut attempts = 0;
+                
+                for attempt in 1..=max_attempts {
+                    attempts = attempt;
+                    let retry_delay = verify_retry_delay(&state.api_config, attempt);
+                    match blockchain_client.get_order_hash(&effective_order_id).await {
+                        Ok(on_chain_hash) => {
+                            last_on_chain_hash_hex = format!("0x{}", hex::encode(on_chain_hash));
+                            
+                            if on_chain_hash == computed_hash {
+                                tracing::info!("✅ Hash verified on-chain for order {} (attempt {})", effective_order_id, attempt);
+                                verified = true;
+                                break;
+                            } else if on_chain_hash == [0u8; 32] {
+                                // Order doesn't exist on-chain — might be a tx hash instead
+                                got_zero_hash = true;
+                                if attempt < max_attempts {
+                                    tracing::info!(
+                                        "⏳ Order {} returns zero hash (attempt {}/{}), may not exist yet or may be a tx hash, retrying in {:?}...",
+                                        effective_order_id, attempt, max_attempts, retry_delay
+                                    );
+                                    tokio::time::sleep(retry_delay).await;
+                                }
+                            } else if attempt < max_attempts {
                                 tracing::info!(
-                                    "⏳ Order {} returns zero hash (attempt {}/{}), may not exist yet or may be a tx hash, retrying...",
-                                    effective_order_id, attempt, MAX_RETRIES
+                                    "⏳ Hash mismatch for order {} (attempt {}/{}), waiting {:?} for RPC sync...",
+                                    effective_order_id, attempt, max_attempts, retry_delay
                                 );
-                                tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECS)).await;
+                                tokio::time::sleep(retry_delay).await;
                             }
-                        } else if attempt < MAX_RETRIES {
-                            tracing::info!(
-                                "⏳ Hash mismatch for order {} (attempt {}/{}), waiting {}s for RPC sync...",
-                                effective_order_id, attempt, MAX_RETRIES, RETRY_DELAY_SECS
-                            );
-                            tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECS)).await;
                         }
-                    }
-                    Err(e) => {
-                        tracing::warn!("⚠️ Could not query on-chain hash for order {} (attempt {}): {}", 
-                            effective_order_id, attempt, e);
-                        if attempt < MAX_RETRIES {
-                            tokio::time::sleep(tokio::time::Duration::from_secs(RETRY_DELAY_SECS)).await;
+                        Err(e) => {
+                            tracing::warn!("⚠️ Could not query on-chain hash for order {} (attempt {}): {}", 
+                                effective_order_id, attempt, e);
+                            last_rpc_error = Some(e);
+                            if attempt < max_attempts {
+                                tokio::time::sleep(retry_delay).await;
+                            }
                         }
                     }
                 }
-            }
-            
-            // FALLBACK: If we got zero hashes (order doesn't exist), try tx_hash to find real orderId
-            if !verified && got_zero_hash {
-                if let Some(ref tx_hash) = req.tx_hash {
-                    tracing::info!("🔄 Order {} not found on-chain (zero hash). Trying tx_hash fallback: {}", effective_order_id, tx_hash);
-                    match blockchain_client.get_order_id_from_tx(tx_hash).await {
-                        Ok(real_order_id) => {
-                            tracing::info!("🔄 Found real order ID from tx receipt: {} (was: {})", real_order_id, effective_order_id);
-                            effective_order_id = real_order_id;
-                            
-                            // Verify hash with the real order ID
-                            match blockchain_client.get_order_hash(&effective_order_id).await {
-                                Ok(on_chain_hash) => {
-                                    last_on_chain_hash_hex = format!("0x{}", hex::encode(on_chain_hash));
-                                    if on_chain_hash == computed_hash {
-                                        tracing::info!("✅ Hash verified for real order {} (via tx_hash fallback)", effective_order_id);
-                                        verified = true;
+                
+                // Never got an answer from the chain: report the RPC/revert error itself
+                // rather than a hash mismatch against nothing
+                if let Some(e) = last_rpc_error.filter(|_| !verified && last_on_chain_hash_hex.is_empty()) {
+                    return Err(ApiError::from(e));
+                }
+                
+                // FALLBACK: If we got zero hashes (order doesn't exist), try tx_hash to find real orderId
+                if use_tx_fallback(&state.api_config, verified, got_zero_hash) {
+                    if let Some(ref tx_hash) = req.tx_hash {
+                        tracing::info!("🔄 Order {} not found on-chain (zero hash). Trying tx_hash fallback: {}", effective_order_id, tx_hash);
+                        match blockchain_client.get_order_id_from_tx(tx_hash).await {
+                            Ok(real_order_id) => {
+                                tracing::info!("🔄 Found real order ID from tx receipt: {} (was: {})", real_order_id, effective_order_id);
+                                effective_order_id = real_order_id;
+                                
+                                // Verify hash with the real order ID
+                                match blockchain_client.get_order_hash(&effective_order_id).await {
+                                    Ok(on_chain_hash) => {
+                                        last_on_chain_hash_hex = format!("0x{}", hex::encode(on_chain_hash));
+                                        if on_chain_hash == computed_hash {
+                                            tracing::info!("✅ Hash verified for real order {} (via tx_hash fallback)", effective_order_id);
+                                            verified = true;
+                                        }
+                                    }
+                                    Err(e) => {
+                                        tracing::warn!("⚠️ Could not verify hash for real order {}: {}", effective_order_id, e);
                                     }
-                                }
-                                Err(e) => {
-                                    tracing::warn!("⚠️ Could not verify hash for real order {}: {}", effective_order_id, e);
                                 }
                             }
+                            Err(e) => {
+                                tracing::warn!("⚠️ Could not extract order ID from tx {}: {}", tx_hash, e);
+                            }
                         }
-                        Err(e) => {
-                            tracing::warn!("⚠️ Could not extract order ID from tx {}: {}", tx_hash, e);
-                        }
+                    } else {
+                        tracing::warn!("⚠️ Order {} has zero hash and no tx_hash provided for fallback", effective_order_id);
                     }
-                } else {
-                    tracing::warn!("⚠️ Order {} has zero hash and no tx_hash provided for fallback", effective_order_id);
+                } else if !verified && got_zero_hash {
+                    tracing::warn!("⚠️ Order {} has zero hash on-chain (tx_hash fallback disabled)", effective_order_id);
+                    return Err(ApiError::OnChainNotFound(format!(
+                        "order {} (zero hash after {} attempt(s)); retry once the order transaction is confirmed",
+                        effective_order_id, attempts
+                    )));
                 }
-            }
-            
-            if !verified {
-                tracing::warn!(
-                    "❌ Hash verification failed for order {} after retries:\n  computed: {}\n  on-chain: {}",
-                    effective_order_id, computed_hash_hex, last_on_chain_hash_hex
-                );
-                return Err(ApiError::BadRequest(format!(
-                    "Hash mismatch: computed {} != on-chain {}. The submitted account info does not match what was committed on-chain.",
-                    computed_hash_hex, last_on_chain_hash_hex
-                )));
+                
+                if !verified {
+                    tracing::warn!(
+                        "❌ Hash verification failed for order {} after {} attempt(s):\n  computed: {}\n  on-chain: {}",
+                        effective_order_id, attempts, computed_hash_hex, last_on_chain_hash_hex
+                    );
+                    // Server-side only: shows whether trimming, encoding or line order differs from the frontend
+                    tracing::warn!(
+                        "🔍 Account lines hash diagnostic for order {}:\n  {}",
+                        effective_order_id, AccountLinesDiagnostic::new(&req.account_name, &req.account_id)
+                    );
+                    return Err(ApiError::VerificationFailed {
+                        message: format!(
+                            "Hash mismatch: computed {} != on-chain {}. The submitted account info does not match what was committed on-chain.",
+                            computed_hash_hex, last_on_chain_hash_hex
+                        ),
+                        attempts,
+                    });
+                }
+            } else {
+                tracing::warn!("⚠️ No blockchain client for chain {}, rejecting unverified payment info", chain_id);
+                return Err(ApiError::BadRequest(format!("Cannot verify: no blockchain client for chain {}", chain_id)));
             }
         } else {
-            tracing::warn!("⚠️ No blockchain client for chain {}, rejecting unverified payment info", chain_id);
-            return Err(ApiError::BadRequest(format!("Cannot verify: no blockchain client for chain {}", chain_id)));
+            // No chain_id from DB or request — cannot verify. Reject.
+            tracing::warn!("❌ Cannot verify payment info for order {} — no chain_id available", effective_order_id);
+            return Err(ApiError::BadRequest(
+                "chain_id is required when order is not yet synced. Please include chain_id in the request.".to_string()
+            ));
         }
-    } else {
-        // No chain_id from DB or request — cannot verify. Reject.
-        tracing::warn!("❌ Cannot verify payment info for order {} — no chain_id available", effective_order_id);
-        return Err(ApiError::BadRequest(
-            "chain_id is required when order is not yet synced. Please include chain_id in the request.".to_string()
-        ));
-    }
+        Ok::<_, ApiError>(effective_order_id)
+    };
+    let effective_order_id = verify_with_cache(&state.verified_hashes, &order_id, &computed_hash_hex, on_chain_check).await?;
     
     // Store plain text in database using the effective (possibly resolved) order ID
-    state.db.update_payment_info(&effective_order_id, &req.account_id, &req.account_name).await?;
+    if !state.db.update_payment_info(&effective_order_id, account_id, account_name).await? {
+        return Err(payment_info_already_set(&state, &effective_order_id));
+    }
     
     tracing::info!("✅ Payment info stored for order {} (requested as {})", effective_order_id, order_id);
+    if let Ok(order) = state.db.primary().get_order(&effective_order_id).await {
+        state.live_feed.publish(LiveEventKind::OrderUpdated, &order, None);
+    }
     
     Ok(Json(PaymentInfoResponse {
         success: true,
@@ -668,3 +1240,541 @@ pub async fn submit_payment_info(
         computed_hash: computed_hash_hex,
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::email::format_token_decimal;
+    use crate::db::models::fixtures;
+
+    #[test]
+    fn test_tx_fallback_skipped_when_disabled() {
+        let mut config = ApiConfig::default();
+        assert!(use_tx_fallback(&config, false, true));
+        assert!(!use_tx_fallback(&config, true, false));
+        assert!(!use_tx_fallback(&config, false, false));
+
+        config.payment_info_tx_fallback = false;
+        assert!(!use_tx_fallback(&config, false, true));
+    }
+
+    #[test]
+    fn test_verify_retry_delay_is_exponential() {
+        let config = ApiConfig { payment_info_retry_delay_ms: 500, ..ApiConfig::default() };
+        let delays: Vec<u128> = (1..=4).map(|retry| verify_retry_delay(&config, retry).as_millis()).collect();
+        assert_eq!(delays, vec![500, 1000, 2000, 4000]);
+
+        // Defaults keep the original 3s first retry
+        assert_eq!(verify_retry_delay(&ApiConfig::default(), 1).as_secs(), 3);
+    }
+
+    fn order(order_id: &str, account_id: &str, account_name: &str) -> DbOrder {
+        DbOrder {
+            alipay_id: account_id.to_string(),
+            alipay_name: account_name.to_string(),
+            ..fixtures::order(order_id)
+        }
+    }
+
+    fn trade(status: i32, token_amount: &str, fee_amount: Option<&str>) -> DbTrade {
+        DbTrade {
+            order_id: "0x01".to_string(),
+            token_amount: token_amount.to_string(),
+            fee_amount: fee_amount.map(str::to_string),
+            created_at: 0,
+            expires_at: 0,
+            ..fixtures::trade("0xtrade", status)
+        }
+    }
+
+    fn withdrawal(amount: &str, remaining_after: &str) -> DbWithdrawal {
+        DbWithdrawal {
+            id: 1,
+            order_id: "0x01".to_string(),
+            amount: amount.to_string(),
+            remaining_after: remaining_after.to_string(),
+            tx_hash: None,
+            created_at: Utc::now(),
+        }
+    }
+
+    /// App state over `pool` serving chain 8453, with `orders` stored
+    async fn state_with_orders(pool: sqlx::PgPool, orders: &[DbOrder], api_config: ApiConfig) -> AppState {
+        use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+        use crate::db::Database;
+
+        let repo = PostgresOrderRepository::new(pool.clone());
+        for order in orders {
+            repo.create(order).await.unwrap();
+        }
+        AppState {
+            supported_chains: std::sync::Arc::new(vec![8453]),
+            ..AppState::from_database(Database::from_pool(pool)).with_api_config(api_config)
+        }
+    }
+
+    /// Sorted order ids GET /api/orders/active?`query` returns
+    async fn listed_ids(state: &AppState, query: &str) -> Vec<String> {
+        let Query(params) = Query::try_from_uri(&format!("/api/orders/active?{}", query).parse().unwrap()).unwrap();
+        let Json(list) = get_active_orders(State(state.clone()), HeaderMap::new(), Query(params)).await.unwrap();
+        let mut ids: Vec<String> = list.orders.into_iter().map(|o| o.order_id).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_rail_filter_excludes_alipay_orders(pool: sqlx::PgPool) {
+        let seller = "0x00000000000000000000000000000000000000aa";
+        let alipay = DbOrder { seller: seller.to_string(), ..order("0x01", "acct", "Name") };
+        let wechat = DbOrder { seller: seller.to_string(), rail: PaymentRail::WeChat, ..order("0x02", "wxid", "Name") };
+        let state = state_with_orders(pool, &[alipay, wechat], ApiConfig::default()).await;
+
+        assert_eq!(listed_ids(&state, "rail=1").await, ["0x02"]);
+        assert_eq!(listed_ids(&state, "").await, ["0x01", "0x02"]);
+        // Seller listings are filtered the same way
+        assert_eq!(listed_ids(&state, &format!("seller={}&rail=1", seller)).await, ["0x02"]);
+        assert_eq!(listed_ids(&state, &format!("seller={}", seller)).await, ["0x01", "0x02"]);
+        assert!(matches!(parse_rail_param(Some(7)), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_batch_orders_keep_request_order_and_report_missing() {
+        let ids = dedup_ids(vec!["0x03".to_string(), "0x01".to_string(), "0x09".to_string(), "0x03".to_string()]);
+        assert_eq!(ids, vec!["0x03", "0x01", "0x09"]);
+
+        // The database returns rows in whatever order it likes
+        let found = vec![order("0x01", "acct", "Name"), order("0x03", "acct", "Name")];
+        let (orders, missing) = in_request_order(&ids, found);
+        let order_ids: Vec<&str> = orders.iter().map(|o| o.order_id.as_str()).collect();
+        assert_eq!(order_ids, vec!["0x03", "0x01"]);
+        assert_eq!(missing, vec!["0x09"]);
+    }
+
+    #[test]
+    fn test_private_code_is_only_returned_to_the_seller() {
+        let private = DbOrder { is_public: false, private_code: Some("123456".to_string()), ..order("0x01", "acct", "Name") };
+        let dtos = || vec![order_to_dto(private.clone(), &ApiConfig::default(), &[8453])];
+        let bearer = |wallet: &str| {
+            let mut headers = HeaderMap::new();
+            headers.insert(header::AUTHORIZATION, format!("Bearer {}", auth::test_token(wallet)).parse().unwrap());
+            headers
+        };
+
+        for headers in [HeaderMap::new(), bearer("0xother")] {
+            let mut orders = dtos();
+            redact_private_codes(&mut orders, caller_wallet(&headers).as_deref());
+            assert_eq!(orders[0].private_code, None);
+        }
+
+        let mut orders = dtos();
+        redact_private_codes(&mut orders, caller_wallet(&bearer("0xSeller")).as_deref());
+        assert_eq!(orders[0].private_code.as_deref(), Some("123456"));
+    }
+
+    #[test]
+    fn test_partial_fills_reconcile_with_remaining_amount() {
+        // 1000 total: settled 300 (+3 fee), pending 100 (fee estimated at 1%), expired 200 (returned),
+        // then 97 withdrawn -> 499 left
+        let mut o = order("0x01", "acct", "Name");
+        o.total_amount = "1000".to_string();
+        o.remaining_amount = "499".to_string();
+        let trades = vec![trade(1, "300", Some("3")), trade(0, "100", None), trade(2, "200", Some("2"))];
+        let withdrawals = vec![withdrawal("97", "499")];
+        assert_eq!(reconcile_remaining(&o, &trades, &withdrawals, 100), Ok(None));
+
+        // A missed withdrawal event leaves the stored remaining amount too high
+        o.remaining_amount = "596".to_string();
+        let warning = reconcile_remaining(&o, &trades, &withdrawals, 100).unwrap().unwrap();
+        assert!(warning.contains("implied 499"), "{}", warning);
+
+        // History larger than the order itself
+        let warning = reconcile_remaining(&o, &trades, &[withdrawal("900", "0")], 100).unwrap().unwrap();
+        assert!(warning.contains("exceed"), "{}", warning);
+
+        // A malformed amount isn't counted as zero
+        let err = reconcile_remaining(&o, &trades, &[withdrawal("97.5", "499")], 100).unwrap_err();
+        assert_eq!(err.raw, "97.5");
+        let err = reconcile_remaining(&o, &[trade(0, "1e6", None)], &[], 100).unwrap_err();
+        assert_eq!(err.raw, "1e6");
+    }
+
+    fn settled(trade_id: &str, timestamp: i64) -> OrderActivity {
+        OrderActivity::Trade {
+            trade_id: trade_id.to_string(),
+            buyer: "0xbuyer".to_string(),
+            token_amount: "300".to_string(),
+            token_amount_formatted: "0.0003".to_string(),
+            token_amount_decimal: "0.0003".to_string(),
+            fee_amount: "3".to_string(),
+            fee_amount_formatted: "0.000003".to_string(),
+            fee_amount_decimal: "0.000003".to_string(),
+            cny_amount: "216".to_string(),
+            cny_amount_formatted: "¥2.16".to_string(),
+            currency: "CNY".to_string(),
+            settlement_tx: None,
+            settled_at: unix_to_utc(timestamp),
+            timestamp,
+        }
+    }
+
+    fn withdrawn(tx_hash: Option<&str>, timestamp: i64) -> OrderActivity {
+        OrderActivity::Withdrawal {
+            amount: "97".to_string(),
+            amount_formatted: "0.000097".to_string(),
+            amount_decimal: "0.000097".to_string(),
+            remaining_after: "499".to_string(),
+            remaining_after_formatted: "0.000499".to_string(),
+            remaining_after_decimal: "0.000499".to_string(),
+            tx_hash: tx_hash.map(str::to_string),
+            created_at: unix_to_utc(timestamp),
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_same_second_activities_keep_a_stable_order() {
+        let at = 1_714_552_200;
+        let ids = |activities: Vec<OrderActivity>| -> Vec<Option<String>> {
+            into_timeline(activities).iter().map(|a| a.id().map(str::to_string)).collect()
+        };
+        let expected = vec![
+            Some("0xlater".to_string()),
+            Some("0xa1".to_string()),
+            Some("0xb2".to_string()),
+            None,
+            Some("0xc3".to_string()),
+        ];
+
+        // Same history, fetched in two different orders
+        let first = ids(vec![
+            withdrawn(Some("0xb2"), at),
+            settled("0xc3", at - 1),
+            withdrawn(None, at),
+            settled("0xa1", at),
+            settled("0xlater", at + 1),
+        ]);
+        let second = ids(vec![
+            settled("0xlater", at + 1),
+            settled("0xa1", at),
+            withdrawn(None, at),
+            settled("0xc3", at - 1),
+            withdrawn(Some("0xb2"), at),
+        ]);
+        assert_eq!(first, expected);
+        assert_eq!(second, expected);
+    }
+
+    #[test]
+    fn test_duplicated_trade_appears_once() {
+        let at = 1_714_552_200;
+        let timeline = into_timeline(vec![
+            settled("0xt1", at),
+            withdrawn(None, at - 10),
+            settled("0xt1", at),
+            withdrawn(None, at - 20),
+        ]);
+
+        let ids: Vec<Option<&str>> = timeline.iter().map(OrderActivity::id).collect();
+        assert_eq!(ids, [Some("0xt1"), None, None]);
+    }
+
+    #[test]
+    fn test_activity_times_share_one_format() {
+        // 2024-05-01T08:30:00Z; the withdrawal time has DB microseconds
+        let at = 1_714_552_200;
+        let mut withdrawal = withdrawn(None, at + 60);
+        if let OrderActivity::Withdrawal { created_at, .. } = &mut withdrawal {
+            *created_at = DateTime::from_timestamp(at + 60, 123_456_000).unwrap();
+        }
+        let activities = vec![
+            withdrawal,
+            settled("0xt1", at),
+            OrderActivity::PendingTrade {
+                trade_id: "0xt2".to_string(),
+                buyer: "0xbuyer".to_string(),
+                token_amount: "100".to_string(),
+                token_amount_formatted: "0.0001".to_string(),
+                token_amount_decimal: "0.0001".to_string(),
+                cny_amount: "72".to_string(),
+                cny_amount_formatted: "¥0.72".to_string(),
+                currency: "CNY".to_string(),
+                created_at: unix_to_utc(at),
+                expires_at: unix_to_utc(at + 900),
+                timestamp: at,
+            },
+            OrderActivity::ExpiredTrade {
+                trade_id: "0xt3".to_string(),
+                buyer: "0xbuyer".to_string(),
+                token_amount: "200".to_string(),
+                token_amount_formatted: "0.0002".to_string(),
+                token_amount_decimal: "0.0002".to_string(),
+                cny_amount: "144".to_string(),
+                cny_amount_formatted: "¥1.44".to_string(),
+                currency: "CNY".to_string(),
+                created_at: unix_to_utc(at - 3600),
+                expired_at: unix_to_utc(at - 2700),
+                timestamp: at - 3600,
+            },
+        ];
+        let json: Vec<serde_json::Value> = activities.iter().map(|a| serde_json::to_value(a).unwrap()).collect();
+
+        assert_eq!(json[0]["type"], "withdrawal");
+        assert_eq!(json[0]["created_at"], "2024-05-01T08:31:00Z");
+        assert_eq!(json[0]["timestamp"], at + 60);
+        assert_eq!(json[1]["type"], "trade");
+        assert_eq!(json[1]["settled_at"], "2024-05-01T08:30:00Z");
+        assert_eq!(json[1]["timestamp"], at);
+        assert_eq!(json[2]["type"], "pending_trade");
+        assert_eq!(json[2]["created_at"], "2024-05-01T08:30:00Z");
+        assert_eq!(json[2]["expires_at"], "2024-05-01T08:45:00Z");
+        assert_eq!(json[3]["type"], "expired_trade");
+        assert_eq!(json[3]["created_at"], "2024-05-01T07:30:00Z");
+        assert_eq!(json[3]["expired_at"], "2024-05-01T07:45:00Z");
+        assert_eq!(json[3]["timestamp"], at - 3600);
+
+        // The sort key is the same unix seconds that's serialized
+        let mut sorted = activities;
+        sorted.sort_by_key(|activity| std::cmp::Reverse(activity.timestamp()));
+        let order: Vec<i64> = sorted.iter().map(OrderActivity::timestamp).collect();
+        assert_eq!(order, vec![at + 60, at, at, at - 3600]);
+    }
+
+    #[test]
+    fn test_recorded_fee_wins_over_any_rate() {
+        // The fee calculator has since moved to 2%
+        let live_rate_bps = 200;
+
+        // Indexed from TradeCreated: the event's fee, with the contract's 1% rate at
+        // the time (the contract rounded this fee down from 1%)
+        let indexed = DbTrade { fee_rate_bps: Some(100), ..trade(1, "1000003", Some("10000")) };
+        assert!(!indexed.needs_fee_rate());
+        assert_eq!(indexed.fee_or_estimate(live_rate_bps).unwrap(), "10000");
+
+        // Legacy rows from before fees were recorded need the live rate...
+        let legacy = trade(1, "1000000", None);
+        assert!(legacy.needs_fee_rate());
+        assert_eq!(legacy.fee_or_estimate(live_rate_bps).unwrap(), "20000");
+        // ...until settling records the escrow's fee for them
+        let settled = trade(1, "1000000", Some("9999"));
+        assert!(!settled.needs_fee_rate());
+        assert_eq!(settled.fee_or_estimate(live_rate_bps).unwrap(), "9999");
+
+        // Reconciliation uses the event's fee: 1,000,003 + 10,000 reserved
+        let mut o = order("0x01", "acct", "Name");
+        o.total_amount = "2000000".to_string();
+        o.remaining_amount = "989997".to_string();
+        assert_eq!(reconcile_remaining(&o, &[indexed], &[], live_rate_bps), Ok(None));
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_hide_until_ready_excludes_orders_without_payment_info(pool: sqlx::PgPool) {
+        let orders = [order("0x01", "acct", "Name"), order("0x02", "", "")];
+        let config = ApiConfig { hide_orders_without_payment_info: true, ..ApiConfig::default() };
+        let state = state_with_orders(pool, &orders, config).await;
+        assert_eq!(listed_ids(&state, "").await, ["0x01"]);
+
+        let state = AppState { api_config: std::sync::Arc::new(ApiConfig::default()), ..state };
+        assert_eq!(listed_ids(&state, "").await, ["0x01", "0x02"]);
+        assert!(!order_to_dto(orders[1].clone(), &ApiConfig::default(), &[8453]).payment_info_set);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_order_on_unconfigured_chain_filtered_or_flagged(pool: sqlx::PgPool) {
+        let removed = DbOrder { chain_id: 137, ..order("0x02", "acct", "Name") };
+        let state = state_with_orders(pool, &[order("0x01", "acct", "Name"), removed.clone()], ApiConfig::default()).await;
+        assert_eq!(listed_ids(&state, "").await, ["0x01"]);
+
+        // Listed anyway (seller view, or hiding turned off): flagged instead
+        let config = ApiConfig { hide_unsupported_chain_orders: false, ..ApiConfig::default() };
+        let state = AppState { api_config: std::sync::Arc::new(config), ..state };
+        assert_eq!(listed_ids(&state, "").await, ["0x01", "0x02"]);
+        assert!(order_to_dto(removed, &ApiConfig::default(), &[8453, 1]).unsupported_chain);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_rate_band_is_cny_cents_per_token(pool: sqlx::PgPool) {
+        let orders: Vec<DbOrder> = [("0x01", "699"), ("0x02", "700"), ("0x03", "715"), ("0x04", "730"), ("0x05", "731")]
+            .into_iter()
+            .map(|(order_id, rate)| DbOrder { exchange_rate: rate.to_string(), ..fixtures::order(order_id) })
+            .collect();
+        let state = state_with_orders(pool, &orders, ApiConfig::default()).await;
+        let list = |query: &str| {
+            let Query(params) = Query::try_from_uri(&format!("/api/orders/active?{}", query).parse().unwrap()).unwrap();
+            get_active_orders(State(state.clone()), HeaderMap::new(), Query(params))
+        };
+
+        // ¥7.00–¥7.30 per USDC is 700-730, bounds included
+        let Json(band) = list("min_rate=700&max_rate=%20730%20").await.unwrap();
+        let rates: Vec<&str> = band.orders.iter().map(|o| o.exchange_rate.as_str()).collect();
+        assert_eq!(rates, ["700", "715", "730"]);
+
+        assert!(matches!(list("min_rate=7.0cny").await, Err(ApiError::BadRequest(_))));
+        assert!(matches!(list("min_rate=730&max_rate=700").await, Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_tokens_query_param_is_comma_separated() {
+        let Query(params): Query<OrderQueryParams> = Query::try_from_uri(
+            &"/api/orders/active?tokens=0xA,%200xB,&min_amount=100000000".parse().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(params.tokens, Some(vec!["0xA".to_string(), "0xB".to_string()]));
+        assert_eq!(params.min_amount.as_deref(), Some("100000000"));
+
+        let Query(params): Query<OrderQueryParams> =
+            Query::try_from_uri(&"/api/orders/active?tokens=".parse().unwrap()).unwrap();
+        assert_eq!(params.tokens, None);
+    }
+
+    #[test]
+    fn test_usdc_amounts_have_decimal_form() {
+        // 1000000 base units of 6-decimal USDC is exactly 1 token
+        let dto = order_to_dto(order("0x01", "acct", "Name"), &ApiConfig::default(), &[8453]);
+        assert_eq!(dto.total_amount, "1000000");
+        assert_eq!(dto.total_amount_decimal, "1.0");
+
+        assert_eq!(format_token_decimal("1234500", 6).unwrap(), "1.2345");
+        assert_eq!(format_token_decimal("1", 18).unwrap(), "0.000000000000000001");
+
+        // A malformed amount is an error (shown as stored), never "0.0"
+        assert!(format_token_decimal("-5", 6).is_err());
+        let mut malformed = order("0x01", "acct", "Name");
+        malformed.remaining_amount = "12.5".to_string();
+        let dto = order_to_dto(malformed, &ApiConfig::default(), &[8453]);
+        assert_eq!(dto.remaining_amount_decimal, "12.5");
+    }
+
+    #[test]
+    fn test_decimal_scale_override_truncates() {
+        let mut o = order("0x01", "acct", "Name");
+        o.total_amount = "1234567".to_string();
+        o.remaining_amount = "1000000".to_string();
+
+        // No override: USDC keeps its natural 6-decimal precision
+        let dto = order_to_dto(o.clone(), &ApiConfig::default(), &[8453]);
+        assert_eq!(dto.total_amount_decimal, "1.234567");
+        assert_eq!(dto.remaining_amount_decimal, "1.0");
+
+        // USDC:2 truncates (never rounds up) and pads to exactly two digits
+        let config = ApiConfig {
+            decimal_display_scales: [("usdc".to_string(), 2)].into_iter().collect(),
+            ..ApiConfig::default()
+        };
+        let dto = order_to_dto(o, &config, &[8453]);
+        assert_eq!(dto.total_amount_decimal, "1.23");
+        assert_eq!(dto.remaining_amount_decimal, "1.00");
+        assert_eq!(dto.total_amount, "1234567");
+
+        assert_eq!(format_token_decimal_scaled("1999999", 6, Some(0)).unwrap(), "1");
+        assert_eq!(format_token_decimal_scaled("5", 18, Some(4)).unwrap(), "0.0000");
+        assert!(format_token_decimal_scaled("", 6, Some(2)).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_repeat_submit_served_from_cache() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cache = VerifiedHashCache::new(std::time::Duration::from_secs(60));
+        let rpc_calls = AtomicUsize::new(0);
+        let check = || async {
+            rpc_calls.fetch_add(1, Ordering::SeqCst);
+            Ok("0xorder".to_string())
+        };
+
+        let first = verify_with_cache(&cache, "0xORDER", "0xhash", check()).await.unwrap();
+        assert_eq!(rpc_calls.load(Ordering::SeqCst), 1);
+
+        // Identical resubmission: no RPC calls, same verified order id
+        let second = verify_with_cache(&cache, "0xorder", "0xHASH", check()).await.unwrap();
+        assert_eq!(rpc_calls.load(Ordering::SeqCst), 1);
+        assert_eq!(first, second);
+
+        // A different hash for the same order is still checked on-chain
+        verify_with_cache(&cache, "0xorder", "0xother", check()).await.unwrap();
+        assert_eq!(rpc_calls.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_verify_reports_remaining_drift() {
+        let mut indexed = order("0xorder", "acct", "name");
+        indexed.remaining_amount = "5000000".to_string();
+
+        // Chain already saw a fill the indexer missed
+        let report = compare_with_chain(&indexed, async { Ok(U256::from(3_000_000u64)) }).await.unwrap();
+        assert!(!report.in_sync);
+        assert_eq!(report.db_value, "5000000");
+        assert_eq!(report.chain_value, "3000000");
+        assert!(!report.resynced);
+
+        let report = compare_with_chain(&indexed, async { Ok(U256::from(5_000_000u64)) }).await.unwrap();
+        assert!(report.in_sync);
+
+        let missing = compare_with_chain(&indexed, async {
+            Err(EthereumClientError::NotFound("order 0xorder".to_string()))
+        })
+        .await;
+        assert!(matches!(missing, Err(ApiError::OnChainNotFound(_))));
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_only_a_listed_order_is_hidden_on_the_feed(pool: sqlx::PgPool) {
+        use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+        use crate::db::Database;
+
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        let state = AppState::from_database(Database::from_pool(pool));
+        let mut feed = state.live_feed.subscribe();
+        let set_public = |is_public: bool| {
+            set_order_visibility(State(state.clone()), Path("0xorder".to_string()), Json(SetVisibilityRequest { is_public }))
+        };
+
+        // Listed -> private: books drop it
+        let first = set_public(false).await.unwrap();
+        assert!(first.private_code.is_some());
+        let hidden = feed.try_recv().unwrap();
+        assert_eq!(hidden.kind, LiveEventKind::OrderHidden);
+
+        // Private -> private rotates the code but says nothing on the feed
+        let second = set_public(false).await.unwrap();
+        assert_ne!(second.private_code, first.private_code);
+        assert!(feed.try_recv().is_err());
+
+        // Private -> public is announced again
+        assert!(set_public(true).await.unwrap().private_code.is_none());
+        assert_eq!(feed.try_recv().unwrap().kind, LiveEventKind::OrderUpdated);
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_order_ready_fires_on_the_first_choice_only(pool: sqlx::PgPool) {
+        use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+        use crate::db::Database;
+
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        let (webhooks, mut received) = crate::webhooks::test_sender().await;
+        let state = AppState { webhook_sender: Some(webhooks), ..AppState::from_database(Database::from_pool(pool)) };
+        let set_public = |is_public: bool| {
+            set_order_visibility(State(state.clone()), Path("0xorder".to_string()), Json(SetVisibilityRequest { is_public }))
+        };
+
+        assert!(set_public(true).await.unwrap().is_public);
+        let ready = received.recv().await.unwrap();
+        assert_eq!((ready["event"].as_str(), ready["is_public"].as_bool()), (Some("order.ready"), Some(true)));
+
+        // Toggling afterwards doesn't announce the order again
+        assert!(!set_public(false).await.unwrap().is_public);
+        assert!(set_public(true).await.unwrap().is_public);
+        let more = tokio::time::timeout(std::time::Duration::from_millis(300), received.recv()).await;
+        assert!(more.is_err(), "unexpected webhook: {:?}", more);
+    }
+}
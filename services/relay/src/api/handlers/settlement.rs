@@ -11,16 +11,31 @@
 
 use axum::{
     extract::{Path, State, Multipart},
+    http::{header, HeaderMap},
+    response::{sse::{Event, Sse}, IntoResponse, Response},
     Json,
 };
 use serde::Serialize;
-use crate::api::{error::{ApiError, ApiResult}, state::AppState};
+use std::convert::Infallible;
+use std::future::Future;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio_stream::{wrappers::UnboundedReceiverStream, Stream, StreamExt};
+use crate::api::{error::{ApiError, ApiResult}, handlers::require_admin, state::{AppState, InputStreamsCache, ProofGuard}};
 use crate::axiom_prover::AxiomProver;
+use crate::blockchain::client::EthereumClient;
 use crate::blockchain::types::trade_id_to_bytes32;
+use crate::api::proof_jobs::{ProofJobError, ProofJobStatus};
+use crate::db::models::{DbProofJob, DbTrade};
+use chrono::{DateTime, Utc};
+use crate::storage::{self, StorageError};
+use crate::webhooks::{TradeWebhookPayload, WebhookTradeStatus};
 use crate::crypto::{
     compute_tx_id_hash,
     compute_expected_hash_with_onchain_account_hash,
     format_amount_line,
+    sha256_file,
 };
 use openvm::serde::to_vec as openvm_serialize;
 
@@ -33,6 +48,7 @@ pub struct PdfExtractedFields {
     pub transaction_id: String,     // Line 25
     pub payment_time: String,       // Line 27
     pub public_key_der_hash: [u8; 32],
+    pub page_count: usize,
 }
 
 /// Parse Alipay PDF to extract account info, transaction_id, payment_time,
@@ -75,9 +91,76 @@ fn extract_pdf_fields(pdf_bytes: &[u8]) -> Result<PdfExtractedFields, String> {
         transaction_id,
         payment_time,
         public_key_der_hash,
+        page_count: pages.len(),
     })
 }
 
+/// Persist an uploaded receipt: content-addressed object in the PDF bucket + key in the DB,
+/// or bytes in the DB; returns the receipt's SHA-256 (hex)
+async fn store_trade_pdf(state: &AppState, trade: &DbTrade, pdf_data: &[u8], filename: &str) -> ApiResult<String> {
+    let sha256 = sha256_file(pdf_data);
+    match &state.pdf_storage {
+        Some(pdf_storage) => {
+            let key = storage::put_pdf_content(pdf_storage.store.as_ref(), pdf_data, &sha256).await
+                .map_err(|e| ApiError::Internal(format!("Failed to upload PDF to bucket: {}", e)))?;
+            state.db.save_trade_pdf_key(&trade.trade_id, &key, filename, &sha256).await
+                .map_err(|e| ApiError::Database(e.to_string()))?;
+        }
+        None => {
+            state.db.save_trade_pdf(&trade.trade_id, pdf_data, filename, &sha256).await
+                .map_err(|e| ApiError::Database(e.to_string()))?;
+        }
+    }
+    Ok(sha256)
+}
+
+/// Load a trade's stored receipt (from the PDF bucket or the DB); 404 if none was uploaded,
+/// 500 if the bytes no longer match the hash recorded at upload
+async fn load_trade_pdf(state: &AppState, trade_id: &str) -> ApiResult<Vec<u8>> {
+    let pdf = state.db.get_trade_pdf(trade_id).await?;
+    let bytes = match (pdf.object_key(), pdf.pdf_file) {
+        (Some(key), _) => {
+            let storage = state.pdf_storage.as_ref().ok_or_else(|| {
+                ApiError::ServiceUnavailable(format!("PDF for trade {} is in object storage, which is not configured", trade_id))
+            })?;
+            storage.store.get(&key).await.map_err(|e| match e {
+                StorageError::NotFound(_) => ApiError::NotFound(format!("PDF for trade {} is missing from storage", trade_id)),
+                StorageError::Backend(msg) => ApiError::Internal(format!("PDF storage error: {}", msg)),
+            })?
+        }
+        (None, Some(bytes)) => bytes,
+        (None, None) => return Err(ApiError::NotFound(format!("No PDF uploaded for trade {}", trade_id))),
+    };
+    if !storage::matches_content_hash(&bytes, pdf.pdf_sha256.as_deref()) {
+        tracing::error!("❌ PDF for trade {} doesn't match its recorded hash", trade_id);
+        return Err(ApiError::Internal(format!("PDF for trade {} failed its integrity check", trade_id)));
+    }
+    Ok(bytes)
+}
+
+/// PDF files must end with an `%%EOF` marker; readers tolerate up to 1 KiB of trailing bytes
+const PDF_EOF_SEARCH_WINDOW: usize = 1024;
+
+/// Cheap checks on an uploaded receipt before it is parsed or stored:
+/// size limit (413), `%PDF-` header and a trailing `%%EOF` (rejects truncated uploads)
+fn check_pdf_upload(data: &[u8], max_bytes: usize) -> ApiResult<()> {
+    if data.len() > max_bytes {
+        return Err(ApiError::PayloadTooLarge(format!(
+            "PDF too large ({} bytes, max {} bytes)",
+            data.len(),
+            max_bytes
+        )));
+    }
+    if !data.starts_with(b"%PDF-") {
+        return Err(ApiError::BadRequest("File is not a valid PDF".to_string()));
+    }
+    let tail = &data[data.len().saturating_sub(PDF_EOF_SEARCH_WINDOW)..];
+    if !tail.windows(5).any(|w| w == b"%%EOF") {
+        return Err(ApiError::BadRequest("PDF is truncated or incomplete".to_string()));
+    }
+    Ok(())
+}
+
 /// Parse payment time string (format: "YYYY-MM-DD HH:MM:SS") to Unix timestamp
 fn parse_payment_time(payment_time: &str) -> Result<u64, String> {
     // Expected format: "2025-12-27 08:36:12"
@@ -96,7 +179,7 @@ fn parse_payment_time(payment_time: &str) -> Result<u64, String> {
 // Validation Endpoint
 // ============================================================================
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, utoipa::ToSchema)]
 pub struct ValidateResponse {
     pub valid: bool,
     pub expected_hash: String,
@@ -107,15 +190,130 @@ pub struct ValidateResponse {
     pub validation_code: String,
     pub transaction_id: String,
     pub payment_time: String,
+    /// Size of the uploaded receipt in bytes
+    pub pdf_size_bytes: usize,
+    /// Number of pages detected in the receipt
+    pub pdf_page_count: usize,
+    /// Hex SHA-256 of the stored receipt (compare with the local file to check integrity)
+    pub pdf_sha256: String,
+    /// Queued proof job when valid; poll GET /api/proofs/:job_id
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub job_id: Option<i64>,
+}
+
+/// Multipart body of POST /api/trades/:trade_id/validate
+#[derive(utoipa::ToSchema)]
+pub struct ValidateUpload {
+    /// The Alipay receipt PDF
+    #[schema(value_type = String, format = Binary)]
+    pub pdf: Vec<u8>,
+}
+
+/// Validation stage reported to streaming clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ValidateStage {
+    /// PDF accepted, pre-checks running
+    Received,
+    /// Axiom execute running (the ~10s part)
+    Proving,
+    /// Valid; starting proof generation and settlement
+    Submitting,
+}
+
+#[derive(Serialize)]
+struct ProgressData {
+    stage: ValidateStage,
+}
+
+/// Sends `progress` events to a streaming client; a no-op for blocking requests
+#[derive(Clone, Default)]
+struct ValidateProgress(Option<mpsc::UnboundedSender<Event>>);
+
+impl ValidateProgress {
+    fn report(&self, stage: ValidateStage) {
+        if let Some(events) = &self.0 {
+            if let Ok(event) = Event::default().event("progress").json_data(ProgressData { stage }) {
+                // Client gone: validation carries on, there's just no one to tell
+                let _ = events.send(event);
+            }
+        }
+    }
+}
+
+fn wants_event_stream(headers: &HeaderMap) -> bool {
+    headers.get(header::ACCEPT)
+        .and_then(|v| v.to_str().ok())
+        .is_some_and(|accept| accept.contains("text/event-stream"))
+}
+
+/// Run `validate` in the background, streaming its progress and then a `done`
+/// (or `error`) event; the stream ends with the validation
+fn stream_validation<F, Fut>(validate: F) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    F: FnOnce(ValidateProgress) -> Fut,
+    Fut: Future<Output = ApiResult<ValidateResponse>> + Send + 'static,
+{
+    let (events, receiver) = mpsc::unbounded_channel();
+    let validation = validate(ValidateProgress(Some(events.clone())));
+    tokio::spawn(async move {
+        let event = match validation.await {
+            Ok(response) => Event::default().event("done").json_data(&response)
+                .unwrap_or_else(|e| Event::default().event("error").data(e.to_string())),
+            Err(e) => error_event(e).await,
+        };
+        let _ = events.send(event);
+    });
+    Sse::new(UnboundedReceiverStream::new(receiver).map(Ok))
+}
+
+/// `error` event with the same JSON body the blocking response would have
+async fn error_event(error: ApiError) -> Event {
+    let body = axum::body::to_bytes(error.into_response().into_body(), usize::MAX).await.unwrap_or_default();
+    Event::default().event("error").data(String::from_utf8_lossy(&body))
+}
+
+/// Cut `validation` off after `limit` with a 504
+async fn with_validate_timeout<T>(
+    limit: Duration,
+    validation: impl Future<Output = ApiResult<T>>,
+) -> ApiResult<T> {
+    tokio::time::timeout(limit, validation).await.unwrap_or_else(|_| {
+        tracing::warn!("⏱️ Validation did not finish within {:?}", limit);
+        Err(ApiError::GatewayTimeout(format!(
+            "Validation did not finish within {}s, please try again",
+            limit.as_secs()
+        )))
+    })
 }
 
 /// POST /api/trades/:trade_id/validate
 /// Upload PDF and run quick Axiom validation (~10 seconds)
+///
+/// With `Accept: text/event-stream` the response is an SSE stream instead:
+/// `progress` events ({"stage": "received" | "proving" | "submitting"}) as
+/// validation advances, then one `done` event carrying the ValidateResponse, or
+/// an `error` event with the usual error body. Either way validation is cut off
+/// after VALIDATE_TIMEOUT_SECS.
+#[utoipa::path(
+    post, path = "/api/trades/{trade_id}/validate", tag = "settlement",
+    params(("trade_id" = String, Path, description = "bytes32 trade id (0x-prefixed)")),
+    request_body(content = ValidateUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "Validation result (or an SSE stream with Accept: text/event-stream)", body = ValidateResponse),
+        (status = 400, description = "Missing or invalid PDF", body = ErrorBody),
+        (status = 404, description = "Trade not found", body = ErrorBody),
+        (status = 413, description = "PDF too large", body = ErrorBody),
+        (status = 504, description = "Validation did not finish within VALIDATE_TIMEOUT_SECS", body = ErrorBody),
+    )
+)]
+#[tracing::instrument(skip_all, fields(trade_id = %trade_id))]
 pub async fn validate_handler(
     State(state): State<AppState>,
     Path(trade_id): Path<String>,
+    headers: HeaderMap,
     mut multipart: Multipart,
-) -> ApiResult<Json<ValidateResponse>> {
+) -> ApiResult<Response> {
     tracing::info!("⚡ Starting validation for trade {}", trade_id);
     
     // Step 1: Extract PDF from multipart
@@ -128,15 +326,14 @@ pub async fn validate_handler(
         if field.name().unwrap_or("") == "pdf" {
             filename = field.file_name().map(|s| s.to_string());
             let data = field.bytes().await.map_err(|e| {
-                ApiError::BadRequest(format!("Failed to read PDF: {}", e))
+                if e.status() == axum::http::StatusCode::PAYLOAD_TOO_LARGE {
+                    ApiError::PayloadTooLarge(format!("PDF too large (max {} bytes)", state.api_config.max_pdf_bytes))
+                } else {
+                    ApiError::BadRequest(format!("Failed to read PDF: {}", e))
+                }
             })?;
             
-            if !data.starts_with(b"%PDF") {
-                return Err(ApiError::BadRequest("File is not a valid PDF".to_string()));
-            }
-            if data.len() > 10 * 1024 * 1024 {
-                return Err(ApiError::BadRequest("PDF too large (max 10MB)".to_string()));
-            }
+            check_pdf_upload(&data, state.api_config.max_pdf_bytes)?;
             pdf_data = Some(data.to_vec());
         }
     }
@@ -145,21 +342,78 @@ pub async fn validate_handler(
     let filename = filename.unwrap_or_else(|| "payment.pdf".to_string());
     tracing::info!("📄 PDF received: {} ({} bytes)", filename, pdf_data.len());
     
+    let limit = Duration::from_secs(state.api_config.validate_timeout_secs);
+    if wants_event_stream(&headers) {
+        let sse = stream_validation(move |progress| async move {
+            run_validation(&state, limit, trade_id, pdf_data, filename, &progress).await
+        });
+        return Ok(sse.into_response());
+    }
+    
+    let response = run_validation(&state, limit, trade_id, pdf_data, filename, &ValidateProgress::default()).await?;
+    Ok(Json(response).into_response())
+}
+
+/// What the timed part of validate found; nothing has been written yet
+struct ValidationCheck {
+    trade: DbTrade,
+    transaction_id: String,
+    payment_time: String,
+    pdf_pk_hash: [u8; 32],
+    pdf_page_count: usize,
+    outcome: CheckOutcome,
+}
+
+enum CheckOutcome {
+    /// The receipt's transaction id already settled another trade
+    Replay,
+    /// Axiom ran; `valid` when its hash matches the expected one
+    Executed {
+        expected_hash: [u8; 32],
+        actual_hash: Vec<u8>,
+        input_streams: Vec<String>,
+        /// The verifier's current key hash (only fetched for valid receipts)
+        contract_pk_hash: Option<[u8; 32]>,
+    },
+}
+
+/// Steps 2-11 of validate, once the PDF is in hand. Only the read-only checks
+/// (PDF parsing, RPC reads, Axiom execute) run under `limit`; the writes
+/// afterwards always run to completion, so a timeout never leaves a trade
+/// half-updated.
+async fn run_validation(
+    state: &AppState,
+    limit: Duration,
+    trade_id: String,
+    pdf_data: Vec<u8>,
+    filename: String,
+    progress: &ValidateProgress,
+) -> ApiResult<ValidateResponse> {
+    let check = with_validate_timeout(limit, check_validation(state, &trade_id, &pdf_data, progress)).await?;
+    apply_validation(state, trade_id, &pdf_data, &filename, check, progress).await
+}
+
+/// Steps 2-10: everything validate needs to know, without side effects
+async fn check_validation(
+    state: &AppState,
+    trade_id: &str,
+    pdf_data: &[u8],
+    progress: &ValidateProgress,
+) -> ApiResult<ValidationCheck> {
+    progress.report(ValidateStage::Received);
+    
     // Step 2: Extract transaction_id, payment_time, and public key hash from PDF
-    let pdf_fields = extract_pdf_fields(&pdf_data)
+    let pdf_fields = extract_pdf_fields(pdf_data)
         .map_err(|e| ApiError::BadRequest(format!("PDF parsing failed: {}", e)))?;
     let transaction_id = pdf_fields.transaction_id;
     let payment_time = pdf_fields.payment_time;
     let pdf_pk_hash = pdf_fields.public_key_der_hash;
-    let pdf_pk_hash_hex = hex::encode(&pdf_pk_hash);
+    let pdf_page_count = pdf_fields.page_count;
+    let pdf_pk_hash_hex = hex::encode(pdf_pk_hash);
     tracing::info!("📋 Extracted: txid={}, time={}, pk_hash={}", transaction_id, payment_time, &pdf_pk_hash_hex[..16]);
     
-    // Step 3: Save PDF to database
-    state.db.save_trade_pdf(&trade_id, &pdf_data, &filename).await
-        .map_err(|e| ApiError::Database(e.to_string()))?;
-    
-    // Step 4: Get trade (source of truth for line 29 amount)
-    let trade = state.db.get_trade(&trade_id).await
+    // Step 3: Get trade (source of truth for line 29 amount)
+    let trade = state.db.get_trade(trade_id).await
         .map_err(|e| ApiError::Database(e.to_string()))?;
     
     // ===== PRE-CHECKS (before OpenVM execution) =====
@@ -170,19 +424,7 @@ pub async fn validate_handler(
         .map_err(|e| ApiError::Database(e.to_string()))?;
     if txid_used {
         tracing::warn!("❌ Pre-check failed: Transaction ID {} already used", transaction_id);
-        // Clear PDF so user can try with a different receipt
-        if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
-            tracing::error!("Failed to clear PDF after pre-check failure: {}", e);
-        }
-        return Ok(Json(ValidateResponse {
-            valid: false,
-            expected_hash: String::new(),
-            actual_hash: String::new(),
-            message: "This payment receipt has already been used for another trade (replay attack detected).".to_string(),
-            validation_code: "REPLAY_ATTACK".to_string(),
-            transaction_id: transaction_id.clone(),
-            payment_time: payment_time.clone(),
-        }));
+        return Ok(ValidationCheck { trade, transaction_id, payment_time, pdf_pk_hash, pdf_page_count, outcome: CheckOutcome::Replay });
     }
     
     // Pre-check 2: Payment time validation (TEMPORARILY DISABLED)
@@ -197,7 +439,7 @@ pub async fn validate_handler(
     //     if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
     //         tracing::error!("Failed to clear PDF after pre-check failure: {}", e);
     //     }
-    //     return Ok(Json(ValidateResponse {
+    //     return Ok(ValidateResponse {
     //         valid: false,
     //         expected_hash: String::new(),
     //         actual_hash: String::new(),
@@ -205,7 +447,7 @@ pub async fn validate_handler(
     //         validation_code: "PAYMENT_TOO_OLD".to_string(),
     //         transaction_id: transaction_id.clone(),
     //         payment_time: payment_time.clone(),
-    //     }));
+    //     });
     // }
     tracing::info!("🔍 Pre-check 2: Payment time validation skipped (temporarily disabled)");
     
@@ -228,9 +470,7 @@ pub async fn validate_handler(
     // Step 6: Fetch account_lines_hash directly from blockchain
     // This avoids masking edge cases by using the hash that was computed by the frontend
     // and stored on-chain during order creation
-    let trade_chain_id = trade.chain_id as u64;
-    let blockchain_client = state.get_blockchain_client(trade_chain_id)
-        .ok_or_else(|| ApiError::Internal(format!("Blockchain client not available for chain {}", trade_chain_id)))?;
+    let blockchain_client = state.get_blockchain_client(trade.chain_id as u64)?;
     
     let onchain_account_hash = blockchain_client.get_order_hash(&trade.order_id).await
         .map_err(|e| ApiError::Internal(format!("Failed to fetch order hash from blockchain: {}", e)))?;
@@ -242,20 +482,10 @@ pub async fn validate_handler(
         &onchain_account_hash_hex, &line25, &line27, &line29, &pdf_pk_hash_hex
     ).map_err(|e| ApiError::Internal(format!("Hash computation failed: {}", e)))?;
     
-    // Step 7: Generate input streams for Axiom
-    let input_streams = generate_openvm_streams(&pdf_data)
+    // Step 7: Generate input streams for Axiom (cached once validate commits)
+    let input_streams = generate_openvm_streams(pdf_data)
         .map_err(|e| ApiError::Internal(format!("Stream generation failed: {}", e)))?;
     
-    // Step 8: Cache input streams
-    {
-        let mut cache = state.input_streams_cache.write().await;
-        cache.insert(trade_id.clone(), input_streams.clone());
-    }
-    
-    // Save transaction_id and payment_time to database
-    state.db.update_trade_payment_info(&trade_id, &transaction_id, &payment_time).await
-        .map_err(|e| ApiError::Database(format!("Failed to save payment info: {}", e)))?;
-    
     // Step 9: Call Axiom execute mode (fast ~10 seconds)
     let api_key = std::env::var("AXIOM_API_KEY")
         .map_err(|_| ApiError::Internal("AXIOM_API_KEY not set".to_string()))?;
@@ -264,8 +494,9 @@ pub async fn validate_handler(
     
     let axiom = AxiomProver::new(api_key, String::new(), program_id);
     
+    progress.report(ValidateStage::Proving);
     tracing::info!("🚀 Running Axiom execute mode...");
-    let actual_hash = axiom.execute_program(&trade_id, input_streams).await
+    let actual_hash = axiom.execute_program(trade_id, input_streams.clone()).await
         .map_err(|e| ApiError::Internal(format!("Axiom execution failed: {}", e)))?;
     
     // Step 10: Compare hashes
@@ -273,84 +504,99 @@ pub async fn validate_handler(
     
     tracing::info!("{}", if valid { "🎯 VALID" } else { "❌ INVALID" });
     
-    // Step 11: If valid, check key rotation and spawn background settlement
-    if valid {
-        // Get blockchain client for key management (use trade's chain)
-        let blockchain_client = state.get_blockchain_client(trade_chain_id)
-            .ok_or_else(|| ApiError::ServiceUnavailable(format!("Blockchain not enabled for chain {}", trade_chain_id)))?;
+    // Key rotation check: compare the PDF's public key hash with the verifier's
+    let contract_pk_hash = if valid {
+        Some(blockchain_client.get_alipay_public_key_hash().await
+            .map_err(|e| ApiError::Internal(format!("Failed to get contract pk hash: {}", e)))?)
+    } else {
+        None
+    };
+    
+    Ok(ValidationCheck {
+        trade,
+        transaction_id,
+        payment_time,
+        pdf_pk_hash,
+        pdf_page_count,
+        outcome: CheckOutcome::Executed { expected_hash, actual_hash, input_streams, contract_pk_hash },
+    })
+}
+
+/// Step 4 and 11: store the PDF and record the outcome. Runs after the timeout,
+/// so these writes are never cut off partway.
+async fn apply_validation(
+    state: &AppState,
+    trade_id: String,
+    pdf_data: &[u8],
+    filename: &str,
+    check: ValidationCheck,
+    progress: &ValidateProgress,
+) -> ApiResult<ValidateResponse> {
+    let ValidationCheck { trade, transaction_id, payment_time, pdf_pk_hash, pdf_page_count, outcome } = check;
+    let pdf_size_bytes = pdf_data.len();
+    
+    // Step 4: Save PDF (bucket if configured, otherwise the database)
+    let pdf_sha256 = store_trade_pdf(state, &trade, pdf_data, filename).await?;
+    
+    let (expected_hash, actual_hash, input_streams, contract_pk_hash) = match outcome {
+        CheckOutcome::Replay => {
+            // Clear PDF so user can try with a different receipt
+            if let Err(e) = state.db.clear_trade_pdf(&trade_id).await {
+                tracing::error!("Failed to clear PDF after pre-check failure: {}", e);
+            }
+            return Ok(ValidateResponse {
+                valid: false,
+                expected_hash: String::new(),
+                actual_hash: String::new(),
+                message: "This payment receipt has already been used for another trade (replay attack detected).".to_string(),
+                validation_code: "REPLAY_ATTACK".to_string(),
+                transaction_id,
+                payment_time,
+                pdf_size_bytes,
+                pdf_page_count,
+                pdf_sha256,
+                job_id: None,
+            });
+        }
+        CheckOutcome::Executed { expected_hash, actual_hash, input_streams, contract_pk_hash } => {
+            (expected_hash, actual_hash, input_streams, contract_pk_hash)
+        }
+    };
+    
+    // Step 11: If valid, check key rotation and queue settlement
+    if let Some(contract_pk_hash) = contract_pk_hash {
+        progress.report(ValidateStage::Submitting);
         
-        // OPTIMISTIC KEY ROTATION - Compare PDF's public key hash with on-chain hash
-        // Done AFTER validation to not delay the user's response
-        let contract_pk_hash = blockchain_client.get_alipay_public_key_hash().await
-            .map_err(|e| ApiError::Internal(format!("Failed to get contract pk hash: {}", e)))?;
+        // Step 8: Cache the input streams for the proof (also persisted, so a
+        // restart doesn't lose them) and save transaction_id and payment_time
+        state.input_streams_cache.insert(&trade_id, input_streams).await;
+        state.db.update_trade_payment_info(&trade_id, &transaction_id, &payment_time).await
+            .map_err(|e| ApiError::Database(format!("Failed to save payment info: {}", e)))?;
         
         if pdf_pk_hash != contract_pk_hash {
-            let contract_pk_hash_hex = hex::encode(&contract_pk_hash);
-            tracing::warn!(
-                "🔑 Key rotation detected! PDF hash: {}, Contract hash: {}",
-                pdf_pk_hash_hex, contract_pk_hash_hex
-            );
-            
-            // OPTIMISTIC KEY ROTATION: Auto-update the contract with the new key hash
-            // This is safe because:
-            // 1. The PDF signature was already validated against the embedded public key
-            // 2. The key hash comes from a verified Alipay-signed PDF
-            // 3. This is NOT exposed via any public API endpoint
-            tracing::info!("🔄 Updating contract with new public key hash...");
-            match blockchain_client.update_public_key_hash(pdf_pk_hash).await {
-                Ok(tx_hash) => {
-                    tracing::info!("✅ Public key hash updated on-chain! TX: {:#x}", tx_hash);
-                    
-                    // Send admin alert email (fire-and-forget)
-                    if let Some(email_service) = crate::email::EmailService::from_env() {
-                        let old_hash = format!("0x{}", contract_pk_hash_hex);
-                        let new_hash = format!("0x{}", pdf_pk_hash_hex);
-                        let trade_id_clone = trade_id.clone();
-                        tokio::spawn(async move {
-                            if let Err(e) = email_service.send_key_rotation_alert(
-                                &old_hash,
-                                &new_hash,
-                                &trade_id_clone,
-                            ).await {
-                                tracing::error!("Failed to send key rotation alert: {}", e);
-                            }
-                        });
-                    }
-                }
-                Err(e) => {
-                    // Key update failed, but validation passed - log error but don't fail
-                    // The proof will still work since we use the PDF's key hash
-                    tracing::error!("❌ Failed to update public key hash: {}. Continuing with settlement.", e);
-                }
-            }
+            let blockchain_client = state.get_blockchain_client(trade.chain_id as u64)?;
+            spawn_key_rotation(blockchain_client, contract_pk_hash, pdf_pk_hash, trade_id.clone());
         }
         
-        // Spawn background task for proof generation and settlement
-        let state_clone = state.clone();
-        let trade_id_clone = trade_id.clone();
-        let transaction_id_clone = transaction_id.clone();
-        let payment_time_clone = payment_time.clone();
-        
-        tokio::spawn(async move {
-            if let Err(e) = run_background_settlement(
-                state_clone,
-                trade_id_clone,
-                transaction_id_clone,
-                payment_time_clone,
-            ).await {
-                tracing::error!("❌ Background settlement failed for trade: {}", e);
-            }
-        });
+        // Queue proof generation and settlement for the proof job worker
+        // (idempotent: a trade's active job is reused)
+        let job_id = state.db.enqueue_proof_job(&trade_id, &transaction_id, &payment_time).await
+            .map_err(|e| ApiError::Database(format!("Failed to queue proof job: {}", e)))?;
+        tracing::info!("📥 Queued proof job {} for trade {}", job_id, trade_id);
         
-        return Ok(Json(ValidateResponse {
+        return Ok(ValidateResponse {
             valid: true,
-            expected_hash: hex::encode(&expected_hash),
+            expected_hash: hex::encode(expected_hash),
             actual_hash: hex::encode(&actual_hash),
             message: "PDF validated! Proof generation started. You can safely leave this page - we'll complete the settlement automatically.".to_string(),
             validation_code: "SUCCESS".to_string(),
             transaction_id,
             payment_time,
-        }));
+            pdf_size_bytes,
+            pdf_page_count,
+            pdf_sha256,
+            job_id: Some(job_id),
+        });
     }
     
     // Validation failed - clear PDF so user can retry with a different one
@@ -359,51 +605,77 @@ pub async fn validate_handler(
     }
     
     // Also clear from input streams cache
-    {
-        let mut cache = state.input_streams_cache.write().await;
-        cache.remove(&trade_id);
-    }
+    state.input_streams_cache.remove(&trade_id).await;
     
-    Ok(Json(ValidateResponse {
+    Ok(ValidateResponse {
         valid: false,
-        expected_hash: hex::encode(&expected_hash),
+        expected_hash: hex::encode(expected_hash),
         actual_hash: hex::encode(&actual_hash),
         message: "Validation failed. PDF content doesn't match trade details. Please try again with the correct receipt.".to_string(),
         validation_code: "HASH_MISMATCH".to_string(),
         transaction_id,
         payment_time,
-    }))
+        pdf_size_bytes,
+        pdf_page_count,
+        pdf_sha256,
+        job_id: None,
+    })
 }
 
-/// Background task for proof generation and blockchain settlement
-/// Called automatically when validation passes - user doesn't need to wait
-async fn run_background_settlement(
-    state: AppState,
-    trade_id: String,
-    transaction_id: String,
-    payment_time: String,
-) -> Result<(), String> {
-    tracing::info!("🚀 [Background] Starting proof generation for trade {}", trade_id);
-    
-    // Check if already in progress (prevent duplicates)
-    {
-        let mut in_progress = state.proof_in_progress.write().await;
-        if in_progress.contains(&trade_id) {
-            tracing::info!("⏭️ [Background] Trade {} already being processed, skipping", trade_id);
-            return Ok(());
+/// OPTIMISTIC KEY ROTATION: update the verifier with the key hash from a
+/// validated receipt. On its own task, so the on-chain update neither delays
+/// the user's response nor is dropped with the request.
+///
+/// This is safe because:
+/// 1. The PDF signature was already validated against the embedded public key
+/// 2. The key hash comes from a verified Alipay-signed PDF
+/// 3. This is NOT exposed via any public API endpoint
+fn spawn_key_rotation(blockchain_client: Arc<EthereumClient>, contract_pk_hash: [u8; 32], pdf_pk_hash: [u8; 32], trade_id: String) {
+    let old_hash = format!("0x{}", hex::encode(contract_pk_hash));
+    let new_hash = format!("0x{}", hex::encode(pdf_pk_hash));
+    tracing::warn!("🔑 Key rotation detected! PDF hash: {}, Contract hash: {}", new_hash, old_hash);
+    tokio::spawn(async move {
+        tracing::info!("🔄 Updating contract with new public key hash...");
+        match blockchain_client.update_public_key_hash(pdf_pk_hash).await {
+            Ok(tx_hash) => {
+                tracing::info!("✅ Public key hash updated on-chain! TX: {:#x}", tx_hash);
+                
+                // Send admin alert email
+                if let Some(email_service) = crate::email::EmailService::from_env() {
+                    if let Err(e) = email_service.send_key_rotation_alert(&old_hash, &new_hash, &trade_id).await {
+                        tracing::error!("Failed to send key rotation alert: {}", e);
+                    }
+                }
+            }
+            Err(e) => {
+                // Key update failed, but validation passed - log error but don't fail
+                // The proof will still work since we use the PDF's key hash
+                tracing::error!("❌ Failed to update public key hash: {}. Continuing with settlement.", e);
+            }
         }
-        in_progress.insert(trade_id.clone());
-    }
-    
-    // Ensure we remove from in_progress when done (even on error)
-    let result = run_background_settlement_inner(&state, &trade_id, &transaction_id, &payment_time).await;
+    });
+}
+
+/// Proof generation and blockchain settlement for a queued proof job
+/// (run by the proof job worker, see api::proof_jobs); returns the settlement tx hash.
+/// Busy when another proof for the trade holds its proof_in_progress entry.
+pub(crate) async fn run_proof_job(
+    state: &AppState,
+    trade_id: &str,
+    transaction_id: &str,
+    payment_time: &str,
+) -> Result<String, ProofJobError> {
+    tracing::info!("🚀 [Background] Starting proof generation for trade {}", trade_id);
     
-    {
-        let mut in_progress = state.proof_in_progress.write().await;
-        in_progress.remove(&trade_id);
-    }
+    // Check if already in progress (prevent duplicates); the guard removes the
+    // entry when this returns, errors or panics
+    let Some(_guard) = ProofGuard::acquire(&state.proof_in_progress, trade_id).await else {
+        tracing::info!("⏭️ [Background] Trade {} already being processed, retrying later", trade_id);
+        return Err(ProofJobError::Busy);
+    };
     
-    result
+    run_background_settlement_inner(state, trade_id, transaction_id, payment_time).await
+        .map_err(ProofJobError::Failed)
 }
 
 /// Parse contract error selector and return user-friendly error code
@@ -431,20 +703,18 @@ async fn run_background_settlement_inner(
     trade_id: &str,
     transaction_id: &str,
     payment_time: &str,
-) -> Result<(), String> {
+) -> Result<String, String> {
     // Look up the trade to get its chain_id
     let trade = state.db.get_trade(trade_id).await
         .map_err(|e| format!("Failed to get trade: {}", e))?;
     let trade_chain_id = trade.chain_id as u64;
     
     let blockchain_client = state.get_blockchain_client(trade_chain_id)
-        .ok_or_else(|| format!("Blockchain not enabled for chain {}", trade_chain_id))?;
+        .map_err(|e| e.to_string())?;
     
-    // Get input streams from cache
-    let input_streams = {
-        let cache = state.input_streams_cache.read().await;
-        cache.get(trade_id).cloned()
-    }.ok_or_else(|| "Input streams not in cache".to_string())?;
+    // Get input streams from cache (falls back to the DB after a restart)
+    let input_streams = state.input_streams_cache.get(trade_id).await
+        .ok_or_else(|| "Input streams not in cache".to_string())?;
     
     // Generate EVM proof
     let api_key = std::env::var("AXIOM_API_KEY")
@@ -498,13 +768,23 @@ async fn run_background_settlement_inner(
         Ok(tx_hash) => {
             tracing::info!("✅ [Background] Trade {} settled! tx_hash: {}", trade_id, tx_hash);
             
-            // Clean up input streams cache
-            {
-                let mut cache = state.input_streams_cache.write().await;
-                cache.remove(trade_id);
+            let tx_hash = format!("{:#x}", tx_hash);
+            // Recorded right away: a stale-job requeue must not settle this trade again
+            if let Err(e) = state.db.record_trade_settlement_tx(trade_id, &tx_hash).await {
+                tracing::warn!("⚠️ [Background] Failed to record settlement tx for trade {}: {}", trade_id, e);
+            }
+            if let Some(ref webhooks) = state.webhook_sender {
+                webhooks.dispatch(TradeWebhookPayload::from_trade(
+                    &trade,
+                    WebhookTradeStatus::Settled,
+                    Some(tx_hash.clone()),
+                ));
             }
             
-            Ok(())
+            // Proof finalized: clean up input streams cache
+            state.input_streams_cache.remove(trade_id).await;
+            
+            Ok(tx_hash)
         }
         Err(e) => {
             let error_msg = e.to_string();
@@ -523,14 +803,236 @@ async fn run_background_settlement_inner(
     }
 }
 
+// ============================================================================
+// Proof Job Status Endpoint
+// ============================================================================
+
+/// Outcome of a finished proof job
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ProofJobResult {
+    pub settlement_tx_hash: Option<String>,
+}
+
+/// Response for GET /api/proofs/:job_id
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct ProofJobResponse {
+    pub job_id: i64,
+    pub trade_id: String,
+    pub status: ProofJobStatus,
+    /// Set once the job is done
+    pub result: Option<ProofJobResult>,
+    /// Set once the job has failed
+    pub error: Option<String>,
+    pub attempts: i32,
+    pub created_at: DateTime<Utc>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+impl ProofJobResponse {
+    fn from_job(job: DbProofJob) -> ApiResult<Self> {
+        let status = ProofJobStatus::parse(&job.status)
+            .ok_or_else(|| ApiError::Internal(format!("Unknown proof job status '{}'", job.status)))?;
+        Ok(Self {
+            job_id: job.id,
+            trade_id: job.trade_id,
+            status,
+            result: (status == ProofJobStatus::Done).then_some(ProofJobResult {
+                settlement_tx_hash: job.settlement_tx_hash,
+            }),
+            error: if status == ProofJobStatus::Failed { job.error } else { None },
+            attempts: job.attempts,
+            created_at: job.created_at,
+            finished_at: job.finished_at,
+        })
+    }
+}
+
+/// GET /api/proofs/:job_id - Status of a proof job queued by validate
+#[utoipa::path(
+    get, path = "/api/proofs/{job_id}", tag = "settlement",
+    params(("job_id" = i64, Path, description = "Proof job id returned by validate")),
+    responses(
+        (status = 200, description = "Proof job status", body = ProofJobResponse),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
+pub async fn get_proof_job(
+    State(state): State<AppState>,
+    Path(job_id): Path<i64>,
+) -> ApiResult<Json<ProofJobResponse>> {
+    let job = state.db.get_proof_job(job_id).await?
+        .ok_or_else(|| ApiError::NotFound(format!("Proof job {} not found", job_id)))?;
+    Ok(Json(ProofJobResponse::from_job(job)?))
+}
+
+// ============================================================================
+// Settlement Status Endpoint
+// ============================================================================
+
+/// Where a trade is in validate -> prove -> settle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SettlementState {
+    /// No receipt validated yet
+    AwaitingReceipt,
+    /// Receipt validated, no proof job running
+    Validated,
+    /// Proof generation or the settlement transaction is underway
+    ProofInProgress,
+    Settled,
+    /// Latest proof job failed (the buyer may validate again while the trade is pending)
+    Failed,
+    /// Payment window closed and the trade was cancelled
+    Expired,
+}
+
+/// Response for GET /api/trades/:trade_id/settlement
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct SettlementStatusResponse {
+    pub trade_id: String,
+    pub state: SettlementState,
+    pub settlement_tx_hash: Option<String>,
+    /// Set when `state` is failed
+    pub error: Option<String>,
+    /// Latest proof job (see GET /api/proofs/:job_id)
+    pub job_id: Option<i64>,
+    pub created_at: i64,
+    pub expires_at: i64,
+    /// When the receipt PDF was uploaded
+    pub validated_at: Option<DateTime<Utc>>,
+    pub proof_started_at: Option<DateTime<Utc>>,
+    pub proof_finished_at: Option<DateTime<Utc>>,
+}
+
+impl SettlementStatusResponse {
+    /// Derive the state from the trade row, its latest proof job and whether a
+    /// proof for it is running in this process (`proof_in_progress`)
+    fn from_trade(trade: DbTrade, job: Option<DbProofJob>, proof_running: bool) -> Self {
+        let job_status = job.as_ref().and_then(|j| ProofJobStatus::parse(&j.status));
+        let state = match trade.status {
+            1 => SettlementState::Settled,
+            2 => SettlementState::Expired,
+            _ if proof_running || matches!(job_status, Some(ProofJobStatus::Queued | ProofJobStatus::Running)) => {
+                SettlementState::ProofInProgress
+            }
+            _ if job_status == Some(ProofJobStatus::Failed) => SettlementState::Failed,
+            _ if trade.transaction_id.is_some() => SettlementState::Validated,
+            _ => SettlementState::AwaitingReceipt,
+        };
+        let error = (state == SettlementState::Failed)
+            .then(|| job.as_ref().and_then(|j| j.error.clone()).or_else(|| trade.settlement_error.clone()))
+            .flatten();
+        Self {
+            state,
+            settlement_tx_hash: trade.settlement_tx_hash
+                .or_else(|| job.as_ref().and_then(|j| j.settlement_tx_hash.clone())),
+            error,
+            job_id: job.as_ref().map(|j| j.id),
+            created_at: trade.created_at,
+            expires_at: trade.expires_at,
+            validated_at: trade.pdf_uploaded_at,
+            proof_started_at: job.as_ref().and_then(|j| j.started_at),
+            proof_finished_at: job.as_ref().and_then(|j| j.finished_at),
+            trade_id: trade.trade_id,
+        }
+    }
+}
+
+/// GET /api/trades/:trade_id/settlement - Settlement progress for the settlement UI to poll
+#[utoipa::path(
+    get, path = "/api/trades/{trade_id}/settlement", tag = "settlement",
+    params(("trade_id" = String, Path, description = "bytes32 trade id (0x-prefixed)")),
+    responses(
+        (status = 200, description = "Settlement state of the trade", body = SettlementStatusResponse),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
+pub async fn get_settlement_status(
+    State(state): State<AppState>,
+    Path(trade_id): Path<String>,
+) -> ApiResult<Json<SettlementStatusResponse>> {
+    let trade = state.db.get_trade(&trade_id).await?;
+    let job = state.db.get_latest_proof_job(&trade.trade_id).await?;
+    let proof_running = state.proof_in_progress.read().await.contains_key(&trade.trade_id);
+    Ok(Json(SettlementStatusResponse::from_trade(trade, job, proof_running)))
+}
+
 // ============================================================================
 // Settlement Endpoint
 // ============================================================================
 
+// ============================================================================
+// Input Streams Endpoint
+// ============================================================================
+
+/// Response for POST /api/trades/:trade_id/input-streams
+#[derive(Debug, Serialize, utoipa::ToSchema)]
+pub struct InputStreamsResponse {
+    pub trade_id: String,
+    /// Number of streams (7, see `generate_openvm_streams`)
+    pub count: usize,
+    /// `0x01`-prefixed hex strings, in the order the guest program reads them
+    pub streams: Vec<String>,
+}
+
+/// POST /api/trades/:trade_id/input-streams - Regenerate the Axiom input streams
+///
+/// Rebuilds the streams from the stored receipt with the same code validation
+/// uses, refreshes the cache and returns them, so a failing proof can be
+/// reproduced without re-uploading. Admin only (stream generation is CPU-heavy).
+/// Holds the trade's proof claim throughout, so it's refused while a validation
+/// or proof is running for the trade and none starts until the cache is written.
+#[utoipa::path(
+    post, path = "/api/trades/{trade_id}/input-streams", tag = "settlement",
+    params(("trade_id" = String, Path, description = "bytes32 trade id (0x-prefixed)")),
+    responses(
+        (status = 200, description = "Regenerated input streams", body = InputStreamsResponse),
+        (status = 401, description = "Missing or non-admin JWT", body = ErrorBody),
+        (status = 404, description = "Trade or receipt not found", body = ErrorBody),
+        (status = 409, description = "Proof generation in progress", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
+pub async fn regenerate_input_streams(
+    State(state): State<AppState>,
+    Path(trade_id): Path<String>,
+    headers: HeaderMap,
+) -> ApiResult<Json<InputStreamsResponse>> {
+    require_admin(&state.api_config, &headers)?;
+    let trade = state.db.get_trade(&trade_id).await?;
+    let Some(_guard) = ProofGuard::acquire(&state.proof_in_progress, &trade.trade_id).await else {
+        return Err(ApiError::Conflict(format!(
+            "Proof generation in progress for trade {}, try again once it finishes",
+            trade.trade_id
+        )));
+    };
+    
+    let pdf_data = load_trade_pdf(&state, &trade.trade_id).await?;
+    let streams = cache_input_streams(&state.input_streams_cache, &trade.trade_id, &pdf_data).await?;
+    tracing::info!("🔁 Regenerated {} input streams for trade {}", streams.len(), trade.trade_id);
+    
+    Ok(Json(InputStreamsResponse {
+        trade_id: trade.trade_id,
+        count: streams.len(),
+        streams,
+    }))
+}
+
 // ============================================================================
 // OpenVM Stream Generation
 // ============================================================================
 
+/// Generate a trade's input streams from its receipt and cache them, returning
+/// what was cached (shared by validation and the input-streams endpoint)
+async fn cache_input_streams(cache: &InputStreamsCache, trade_id: &str, pdf_data: &[u8]) -> ApiResult<Vec<String>> {
+    let streams = generate_openvm_streams(pdf_data)
+        .map_err(|e| ApiError::Internal(format!("Stream generation failed: {}", e)))?;
+    cache.insert(trade_id, streams.clone()).await;
+    Ok(streams)
+}
+
 /// Generate OpenVM input streams for Axiom API
 /// 
 /// The guest program expects exactly:
@@ -540,6 +1042,12 @@ async fn run_background_settlement_inner(
 /// 
 /// The guest extracts lines from the PDF and computes the hash internally.
 /// Line text and pk_hash are NOT passed - the guest reads them from the PDF.
+///
+/// Format: 7 strings, each `0x01` (Axiom's byte-stream tag) followed by the
+/// little-endian bytes of the OpenVM-serialized value, hex encoded:
+/// - [0]    the PDF, zero-padded to a multiple of 4 bytes
+/// - [1]    the line count, 5 (one u32 word: `0x0105000000`)
+/// - [2..7] lines 20, 21, 25, 27 and 29, one u32 word each (`0x0114000000`, ...)
 fn generate_openvm_streams(pdf_bytes: &[u8]) -> Result<Vec<String>, String> {
     let line_numbers: [u32; 5] = [20, 21, 25, 27, 29];
     let mut streams = Vec::new();
@@ -565,3 +1073,217 @@ fn generate_openvm_streams(pdf_bytes: &[u8]) -> Result<Vec<String>, String> {
     
     Ok(streams)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures;
+
+    const SMALL_PDF: &[u8] = b"%PDF-1.4\n1 0 obj << /Type /Catalog /Pages 2 0 R >> endobj\n\
+2 0 obj << /Type /Pages /Kids [] /Count 0 >> endobj\ntrailer << /Root 1 0 R >>\n%%EOF\n";
+
+    #[test]
+    fn test_valid_small_pdf_is_accepted() {
+        assert!(check_pdf_upload(SMALL_PDF, 1024).is_ok());
+    }
+
+    #[test]
+    fn test_truncated_pdf_is_rejected() {
+        let truncated = &SMALL_PDF[..SMALL_PDF.len() / 2];
+        assert!(matches!(check_pdf_upload(truncated, 1024), Err(ApiError::BadRequest(_))));
+        assert!(matches!(check_pdf_upload(b"%PD", 1024), Err(ApiError::BadRequest(_))));
+        assert!(matches!(check_pdf_upload(b"PK\x03\x04 not a pdf %%EOF", 1024), Err(ApiError::BadRequest(_))));
+    }
+
+    #[test]
+    fn test_oversized_pdf_is_rejected_with_413() {
+        let mut big = SMALL_PDF.to_vec();
+        big.resize(2048, b' ');
+        assert!(matches!(check_pdf_upload(&big, 1024), Err(ApiError::PayloadTooLarge(_))));
+    }
+
+    fn sample_response() -> ValidateResponse {
+        ValidateResponse {
+            valid: true,
+            expected_hash: "ab".to_string(),
+            actual_hash: "ab".to_string(),
+            message: "PDF validated!".to_string(),
+            validation_code: "SUCCESS".to_string(),
+            transaction_id: "2025010122001".to_string(),
+            payment_time: "2025-01-01 12:00:00".to_string(),
+            pdf_size_bytes: 1024,
+            pdf_page_count: 1,
+            pdf_sha256: "cd".to_string(),
+            job_id: Some(7),
+        }
+    }
+
+    async fn sse_body(sse: Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static>) -> String {
+        let body = axum::body::to_bytes(sse.into_response().into_body(), usize::MAX).await.unwrap();
+        String::from_utf8(body.to_vec()).unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_sse_mode_emits_progress_then_result() {
+        let sse = stream_validation(|progress| async move {
+            progress.report(ValidateStage::Received);
+            progress.report(ValidateStage::Proving);
+            progress.report(ValidateStage::Submitting);
+            Ok(sample_response())
+        });
+        let body = sse_body(sse).await;
+
+        let events: Vec<&str> = body.lines().filter_map(|line| line.strip_prefix("event: ")).collect();
+        assert_eq!(events, ["progress", "progress", "progress", "done"]);
+        let stages: Vec<&str> = body.lines()
+            .filter_map(|line| line.strip_prefix("data: {\"stage\":"))
+            .collect();
+        assert_eq!(stages, ["\"received\"}", "\"proving\"}", "\"submitting\"}"]);
+        assert!(body.contains("\"validation_code\":\"SUCCESS\""), "{}", body);
+    }
+
+    #[tokio::test]
+    async fn test_sse_mode_reports_errors_and_timeouts() {
+        let sse = stream_validation(|progress| async move {
+            progress.report(ValidateStage::Received);
+            with_validate_timeout(Duration::from_millis(10), std::future::pending()).await
+        });
+        let body = sse_body(sse).await;
+
+        let events: Vec<&str> = body.lines().filter_map(|line| line.strip_prefix("event: ")).collect();
+        assert_eq!(events, ["progress", "error"]);
+        assert!(body.contains("\"status\":504"), "{}", body);
+        assert!(body.contains("did not finish within 0s"), "{}", body);
+
+        let mut headers = HeaderMap::new();
+        assert!(!wants_event_stream(&headers));
+        headers.insert(header::ACCEPT, "text/event-stream".parse().unwrap());
+        assert!(wants_event_stream(&headers));
+    }
+
+    fn pending_trade() -> DbTrade {
+        fixtures::trade("0xtrade", 0)
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_input_streams_are_admin_only_and_hold_the_proof_claim(pool: sqlx::PgPool) {
+        use crate::config::ApiConfig;
+        use crate::db::{orders::{OrderRepository, PostgresOrderRepository}, trades::{PostgresTradeRepository, TradeRepository}, Database};
+
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        let trades = PostgresTradeRepository::new(pool.clone());
+        trades.create(&pending_trade()).await.unwrap();
+        trades.save_pdf("0xtrade", SMALL_PDF, "receipt.pdf", &sha256_file(SMALL_PDF)).await.unwrap();
+        let state = AppState::from_database(Database::from_pool(pool))
+            .with_api_config(ApiConfig { admin_wallets: vec!["0xadmin".to_string()], ..ApiConfig::default() });
+        let regenerate = |headers| regenerate_input_streams(State(state.clone()), Path("0xtrade".to_string()), headers);
+        let mut admin = HeaderMap::new();
+        admin.insert(header::AUTHORIZATION, format!("Bearer {}", crate::auth::test_token("0xadmin")).parse().unwrap());
+
+        assert!(matches!(regenerate(HeaderMap::new()).await, Err(ApiError::Unauthorized(_))));
+
+        // Refused while a validation or proof holds the trade
+        let proof = ProofGuard::acquire(&state.proof_in_progress, "0xtrade").await.unwrap();
+        assert!(matches!(regenerate(admin.clone()).await, Err(ApiError::Conflict(_))));
+        drop(proof);
+
+        let Json(regenerated) = regenerate(admin).await.unwrap();
+        assert_eq!(regenerated.count, 7);
+        assert_eq!(state.input_streams_cache.get("0xtrade").await, Some(regenerated.streams));
+        // The claim is released once the cache is written
+        assert!(state.proof_in_progress.read().await.is_empty());
+    }
+
+    fn job(status: &str) -> DbProofJob {
+        DbProofJob {
+            id: 7,
+            trade_id: "0xtrade".to_string(),
+            transaction_id: "2025010122001".to_string(),
+            payment_time: "2025-01-01 12:00:00".to_string(),
+            status: status.to_string(),
+            attempts: 1,
+            settlement_tx_hash: (status == "done").then(|| "0xsettle".to_string()),
+            error: (status == "failed").then(|| "ProofVerificationFailed".to_string()),
+            created_at: Utc::now(),
+            started_at: Some(Utc::now()),
+            finished_at: None,
+        }
+    }
+
+    #[test]
+    fn test_settlement_state_transitions() {
+        let state = |trade: DbTrade, job: Option<DbProofJob>, running: bool| {
+            SettlementStatusResponse::from_trade(trade, job, running).state
+        };
+
+        assert_eq!(state(pending_trade(), None, false), SettlementState::AwaitingReceipt);
+
+        let validated = DbTrade { transaction_id: Some("2025010122001".to_string()), ..pending_trade() };
+        assert_eq!(state(validated.clone(), None, false), SettlementState::Validated);
+
+        // In-memory proof set or an active job both count as in progress
+        assert_eq!(state(validated.clone(), None, true), SettlementState::ProofInProgress);
+        assert_eq!(state(validated.clone(), Some(job("queued")), false), SettlementState::ProofInProgress);
+        assert_eq!(state(validated.clone(), Some(job("running")), false), SettlementState::ProofInProgress);
+
+        let failed = SettlementStatusResponse::from_trade(validated.clone(), Some(job("failed")), false);
+        assert_eq!(failed.state, SettlementState::Failed);
+        assert_eq!(failed.error.as_deref(), Some("ProofVerificationFailed"));
+        assert_eq!(failed.job_id, Some(7));
+
+        let settled = DbTrade { status: 1, settlement_tx_hash: Some("0xsettle".to_string()), ..validated.clone() };
+        let settled = SettlementStatusResponse::from_trade(settled, Some(job("done")), false);
+        assert_eq!(settled.state, SettlementState::Settled);
+        assert_eq!(settled.settlement_tx_hash.as_deref(), Some("0xsettle"));
+        assert_eq!(settled.error, None);
+
+        let expired = DbTrade { status: 2, ..pending_trade() };
+        assert_eq!(state(expired, None, false), SettlementState::Expired);
+    }
+
+    /// Stands in for the trade_input_streams table
+    #[derive(Default)]
+    struct MemoryStreamStore(std::sync::Mutex<std::collections::HashMap<String, Vec<String>>>);
+
+    #[async_trait::async_trait]
+    impl crate::api::state::InputStreamStore for MemoryStreamStore {
+        async fn put(&self, trade_id: &str, streams: &[String], _ttl: Duration) -> crate::db::DbResult<()> {
+            self.0.lock().unwrap().insert(trade_id.to_string(), streams.to_vec());
+            Ok(())
+        }
+
+        async fn get(&self, trade_id: &str) -> crate::db::DbResult<Option<Vec<String>>> {
+            Ok(self.0.lock().unwrap().get(trade_id).cloned())
+        }
+
+        async fn delete(&self, trade_id: &str) -> crate::db::DbResult<()> {
+            self.0.lock().unwrap().remove(trade_id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_regenerated_streams_match_validation_cache() {
+        let store = std::sync::Arc::new(MemoryStreamStore::default());
+        let cache = InputStreamsCache::new(store.clone(), AppState::INPUT_STREAMS_TTL);
+
+        // What validation caches; a stale entry is then replaced by the endpoint
+        let validated = cache_input_streams(&cache, "0xtrade", SMALL_PDF).await.unwrap();
+        cache.insert("0xtrade", vec!["0x01stale".to_string()]).await;
+        let regenerated = cache_input_streams(&cache, "0xtrade", SMALL_PDF).await.unwrap();
+
+        assert_eq!(regenerated, validated);
+        assert_eq!(cache.get("0xtrade").await, Some(regenerated.clone()));
+        assert_eq!(store.0.lock().unwrap().get("0xtrade"), Some(&regenerated));
+
+        // PDF (padded to 4 bytes), line count, then lines 20/21/25/27/29
+        assert_eq!(regenerated.len(), 7);
+        assert!(regenerated.iter().all(|s| s.starts_with("0x01")));
+        assert_eq!((regenerated[0].len() - 4) / 2 % 4, 0);
+        assert_eq!(regenerated[1], "0x0105000000");
+        assert_eq!(regenerated[2], "0x0114000000");
+        assert_eq!(regenerated[6], "0x011d000000");
+    }
+}
@@ -4,37 +4,128 @@
 //! Buyers don't need to connect a wallet - the relay pays for gas.
 
 use axum::{
-    extract::{Path, State},
+    extract::{Path, Query, State},
+    http::{header, HeaderMap},
+    response::sse::{Event, KeepAlive, Sse},
     Json,
 };
 use ethers::types::{Address, U256};
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use std::convert::Infallible;
+use std::future::Future;
+use std::time::Duration;
+use tokio::sync::{broadcast::{self, error::RecvError}, mpsc, watch};
+use tokio_stream::{wrappers::UnboundedReceiverStream, Stream, StreamExt};
 
 use crate::api::{
     error::{ApiError, ApiResult},
+    handlers::parse_address,
+    live::LiveEvent,
     state::AppState,
+    types::TradeGasCostsResponse,
 };
+use crate::auth;
+use crate::blockchain::types::ContractConfig;
+use crate::config::ApiConfig;
+use crate::currency::{format_fiat, parse_amount, Currency, InvalidAmount};
+use crate::db::models::{DbOrder, DbTrade};
+use crate::db::trades::{TradeListQuery, TradeParty};
+use crate::email::{format_token_amount, format_token_decimal};
+use crate::rail::PaymentRail;
+use crate::tokens;
+
+/// A trade's protocol fee, in the order's token
+///
+/// The fee is reserved from the order on top of `token_amount`: the buyer
+/// receives `token_amount` in full and the seller gives up `total_amount`.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TradeFeeBreakdown {
+    pub chain_id: i32,
+    pub token_symbol: String,
+    pub token_decimals: u8,
+    /// Fee in token base units
+    pub fee_amount: String,
+    /// e.g. "1.5 USDC"
+    pub fee_amount_formatted: String,
+    /// False once the on-chain fee is recorded; true while it's estimated from the fee rate
+    pub fee_estimated: bool,
+    /// Tokens the buyer receives, in base units (the fee isn't deducted from them)
+    pub net_amount: String,
+    pub net_amount_formatted: String,
+    /// Tokens taken from the order: net_amount + fee_amount
+    pub total_amount: String,
+    pub total_amount_formatted: String,
+}
+
+impl TradeFeeBreakdown {
+    /// Fee of `trade` on an order for `token`: the recorded fee, else the rate
+    /// snapshotted at creation, else `fallback_rate_bps` (see DbTrade::fee_or_estimate)
+    pub fn new(trade: &DbTrade, token: &str, fallback_rate_bps: u128) -> Self {
+        let (token_symbol, token_decimals) = tokens::symbol_and_decimals(token);
+        let fee_amount = trade.fee_or_estimate(fallback_rate_bps).unwrap_or_else(InvalidAmount::into_raw);
+        let total_amount = match (parse_amount::<u128>(&trade.token_amount), parse_amount::<u128>(&fee_amount)) {
+            (Ok(net), Ok(fee)) => (net + fee).to_string(),
+            // Can't be summed: keep the malformed amount so it's shown as stored
+            (Err(e), _) | (_, Err(e)) => e.raw,
+        };
+        let format = |amount: &str| format_token_amount(amount, token_decimals, token_symbol).unwrap_or_else(InvalidAmount::into_raw);
+        Self {
+            chain_id: trade.chain_id,
+            token_symbol: token_symbol.to_string(),
+            token_decimals,
+            fee_amount_formatted: format(&fee_amount),
+            fee_estimated: trade.fee_amount.is_none(),
+            fee_amount,
+            net_amount: trade.token_amount.clone(),
+            net_amount_formatted: format(&trade.token_amount),
+            total_amount_formatted: format(&total_amount),
+            total_amount,
+        }
+    }
+}
+
+/// Single-trade response: the trade plus its fee breakdown
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TradeDetail {
+    #[serde(flatten)]
+    pub trade: DbTrade,
+    /// None when the trade's order is gone, or a legacy trade's fee rate is unavailable
+    pub fee_breakdown: Option<TradeFeeBreakdown>,
+}
 
 /// GET /api/trades/:trade_id
-/// Get trade details by ID
+/// Get trade details by ID, with the fee charged (or to be charged) for it
+#[utoipa::path(
+    get, path = "/api/trades/{trade_id}", tag = "trades",
+    params(("trade_id" = String, Path, description = "bytes32 trade id (0x-prefixed)")),
+    responses(
+        (status = 200, description = "The trade", body = TradeDetail),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 pub async fn get_trade_handler(
     Path(trade_id): Path<String>,
     State(state): State<AppState>,
-) -> ApiResult<Json<crate::db::models::DbTrade>> {
-    // Query trade from database using dynamic query
+) -> ApiResult<Json<TradeDetail>> {
+    // Query trade from database using dynamic query.
+    // LEFT JOIN: a trade is still returned (with order_closed = true) if its order is closed or gone.
     let trade = sqlx::query(
         r#"
         SELECT 
-            "tradeId", "orderId", "buyer", "tokenAmount"::text, "cnyAmount"::text, "feeAmount"::text,
-            "rail", "transactionId", "paymentTime",
-            "createdAt", "expiresAt", "status",
-            "escrowTxHash", "settlementTxHash", "syncedAt",
-            pdf_file, pdf_filename, pdf_uploaded_at,
-            proof_user_public_values, proof_accumulator, proof_data,
-            axiom_proof_id, proof_generated_at, proof_json, settlement_error,
-            "chainId"
-        FROM trades
-        WHERE "tradeId" = $1
+            t."tradeId", t."orderId", t."buyer", t."tokenAmount"::text, t."cnyAmount"::text, t."feeAmount"::text, t."feeRateBps",
+            t."rail", t."transactionId", t."paymentTime",
+            t."createdAt", t."expiresAt", t."status",
+            t."escrowTxHash", t."settlementTxHash", t."syncedAt",
+            t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
+            t.proof_user_public_values, t.proof_accumulator, t.proof_data,
+            t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
+            t."chainId", t.currency, o."token",
+            (o."orderId" IS NULL OR o."remainingAmount" = 0) as order_closed
+        FROM trades t
+        LEFT JOIN orders o ON t."orderId" = o."orderId"
+        WHERE t."tradeId" = $1
         "#,
     )
     .bind(&trade_id)
@@ -52,7 +143,8 @@ pub async fn get_trade_handler(
         token_amount: trade.get("tokenAmount"),
         cny_amount: trade.get("cnyAmount"),
         fee_amount: trade.get("feeAmount"),
-        rail: trade.get("rail"),
+        fee_rate_bps: trade.get("feeRateBps"),
+        rail: trade.try_get("rail").map_err(|e| ApiError::Database(e.to_string()))?,
         transaction_id: trade.get("transactionId"),
         payment_time: trade.get("paymentTime"),
         created_at: trade.get("createdAt"),
@@ -61,7 +153,7 @@ pub async fn get_trade_handler(
         escrow_tx_hash: trade.get("escrowTxHash"),
         settlement_tx_hash: trade.get("settlementTxHash"),
         synced_at: trade.get("syncedAt"),
-        token: None, // Not available in single trade query (would need JOIN)
+        token: trade.get("token"),
         pdf_file: trade.get("pdf_file"),
         pdf_filename: trade.get("pdf_filename"),
         pdf_uploaded_at: trade.get("pdf_uploaded_at"),
@@ -73,195 +165,356 @@ pub async fn get_trade_handler(
         proof_json: trade.get("proof_json"),
         settlement_error: trade.get("settlement_error"),
         chain_id: trade.get("chainId"),
+        currency: trade.get("currency"),
         alipay_id: None,
         alipay_name: None,
+        order_closed: trade.get("order_closed"),
+    };
+
+    let fee_breakdown = match db_trade.token.as_deref() {
+        Some(token) => {
+            // Only legacy trades (no recorded fee, no rate snapshot) need today's rate
+            let fallback_rate_bps = if db_trade.needs_fee_rate() {
+                state.get_config_for_chain(db_trade.chain_id as u64, false).await
+                    .map_err(|e| e.to_string())
+                    .and_then(|config| config.fee_rate_bps().map_err(|e| e.to_string()))
+                    .map(u128::from)
+                    .map_err(|e| tracing::warn!("⚠️ No fee rate for legacy trade {}: {}", trade_id, e))
+                    .ok()
+            } else {
+                Some(0)
+            };
+            fallback_rate_bps.map(|rate| TradeFeeBreakdown::new(&db_trade, token, rate))
+        }
+        None => None,
     };
 
-    Ok(Json(db_trade))
+    Ok(Json(TradeDetail { trade: db_trade, fee_breakdown }))
 }
 
-/// GET /api/trades/buyer/:buyer_address
-/// Get all trades for a specific buyer
-#[derive(Debug, Serialize)]
+/// Query parameters for buyer/seller trade listings
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TradeListParams {
+    /// TradeStatus: 0=PENDING, 1=SETTLED, 2=EXPIRED (optional, None = all)
+    pub status: Option<i32>,
+    /// Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)
+    pub limit: Option<i64>,
+    /// Number of trades to skip (default 0)
+    pub offset: Option<i64>,
+    /// Resolve each buyer's primary ENS name into `buyer_ens` (default false)
+    #[serde(default)]
+    pub include_ens: bool,
+}
+
+/// One trade in a listing
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TradeListItem {
+    #[serde(flatten)]
+    pub trade: DbTrade,
+    /// Buyer's primary ENS name (only resolved with `include_ens=true`; null without a reverse record)
+    pub buyer_ens: Option<String>,
+}
+
+/// Trades list response (one page, newest first)
+#[derive(Debug, Serialize, ToSchema)]
 pub struct TradesResponse {
-    pub trades: Vec<crate::db::models::DbTrade>,
+    pub trades: Vec<TradeListItem>,
+    /// Trades matching the filter across all pages
+    pub total: i64,
+    /// Page size applied (the requested limit after clamping)
+    pub limit: i64,
+    pub offset: i64,
+}
+
+/// Build the listing query from path + query params, rejecting bad input with 400
+fn trade_list_query(party: TradeParty, params: &TradeListParams, config: &ApiConfig) -> ApiResult<TradeListQuery> {
+    let list = TradeListQuery {
+        party,
+        status: params.status,
+        limit: config.page_limit(params.limit),
+        offset: params.offset.unwrap_or(0),
+    };
+    list.validate().map_err(|e| ApiError::BadRequest(e.to_string()))?;
+    Ok(list)
+}
+
+async fn list_trades(state: &AppState, list: TradeListQuery, include_ens: bool) -> ApiResult<Json<TradesResponse>> {
+    let (trades, total) = state.db.list_trades_for_party(&list).await?;
+    let names = if include_ens {
+        state.resolve_ens_names(trades.iter().map(|t| t.buyer.as_str())).await
+    } else {
+        Default::default()
+    };
+    let trades = trades
+        .into_iter()
+        .map(|trade| TradeListItem {
+            buyer_ens: names.get(&trade.buyer.to_lowercase()).cloned(),
+            trade,
+        })
+        .collect();
+    Ok(Json(TradesResponse {
+        trades,
+        total,
+        limit: list.limit,
+        offset: list.offset,
+    }))
 }
 
+/// GET /api/trades/buyer/:buyer_address?status=&limit=&offset=&include_ens=
+/// Get a buyer's trades, optionally filtered by status and paginated
+#[utoipa::path(
+    get, path = "/api/trades/buyer/{buyer_address}", tag = "trades",
+    params(("buyer_address" = String, Path, description = "Buyer address (0x-prefixed)"), TradeListParams),
+    responses(
+        (status = 200, description = "One page of the buyer's trades", body = TradesResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 pub async fn get_trades_by_buyer_handler(
     Path(buyer_address): Path<String>,
+    Query(params): Query<TradeListParams>,
     State(state): State<AppState>,
 ) -> ApiResult<Json<TradesResponse>> {
-    // Normalize buyer address (lowercase, strip 0x if present)
-    let buyer_addr = buyer_address
-        .to_lowercase()
-        .trim_start_matches("0x")
-        .to_string();
-    
-    // Query trades table with JOIN to get token from orders
-    let trades = sqlx::query(
-        r#"
-        SELECT 
-            t."tradeId", t."orderId", t.buyer,
-            t."tokenAmount"::text, t."cnyAmount"::text, t."feeAmount"::text,
-            t.rail, t."transactionId", t."paymentTime",
-            t."createdAt", t."expiresAt", t.status,
-            t."escrowTxHash", t."settlementTxHash", t."syncedAt",
-            t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
-            t.proof_user_public_values, t.proof_accumulator, t.proof_data,
-            t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
-            t."chainId",
-            o.token,
-            o."accountId" as "alipay_id",
-            o."accountName" as "alipay_name"
-        FROM trades t
-        INNER JOIN orders o ON t."orderId" = o."orderId"
-        WHERE LOWER(REPLACE(t.buyer, '0x', '')) = $1
-        ORDER BY t."createdAt" DESC
-        "#
-    )
-    .bind(&buyer_addr)
-    .fetch_all(state.db.pool())
-    .await
-    .map_err(|e| ApiError::Database(e.to_string()))?;
-    
-    // Map to DbTrade structs
-    let db_trades: Vec<crate::db::models::DbTrade> = trades
-        .into_iter()
-        .map(|row| {
-            use sqlx::Row;
-            crate::db::models::DbTrade {
-                trade_id: row.get("tradeId"),
-                order_id: row.get("orderId"),
-                buyer: row.get("buyer"),
-                token_amount: row.get("tokenAmount"),
-                cny_amount: row.get("cnyAmount"),
-                fee_amount: row.get("feeAmount"),
-                rail: row.get("rail"),
-                transaction_id: row.get("transactionId"),
-                payment_time: row.get("paymentTime"),
-                created_at: row.get("createdAt"),
-                expires_at: row.get("expiresAt"),
-                status: row.get("status"),
-                escrow_tx_hash: row.get("escrowTxHash"),
-                settlement_tx_hash: row.get("settlementTxHash"),
-                synced_at: row.get("syncedAt"),
-                pdf_file: row.get("pdf_file"),
-                pdf_filename: row.get("pdf_filename"),
-                pdf_uploaded_at: row.get("pdf_uploaded_at"),
-                proof_user_public_values: row.get("proof_user_public_values"),
-                proof_accumulator: row.get("proof_accumulator"),
-                proof_data: row.get("proof_data"),
-                axiom_proof_id: row.get("axiom_proof_id"),
-                proof_generated_at: row.get("proof_generated_at"),
-                proof_json: row.get("proof_json"),
-                settlement_error: row.get("settlement_error"),
-                chain_id: row.get("chainId"),
-                token: Some(row.get("token")),
-                alipay_id: row.get("alipay_id"),
-                alipay_name: row.get("alipay_name"),
-            }
-        })
-        .collect();
-    
-    Ok(Json(TradesResponse { trades: db_trades }))
+    let buyer_address = parse_address("buyer address", &buyer_address)?;
+    let list = trade_list_query(TradeParty::Buyer(buyer_address), &params, &state.api_config)?;
+    list_trades(&state, list, params.include_ens).await
 }
 
-/// GET /api/trades/seller/:seller_address
-/// Get all trades on orders created by a specific seller
+/// GET /api/trades/seller/:seller_address?status=&limit=&offset=&include_ens=
+/// Get trades on orders created by a seller, optionally filtered by status and paginated
+#[utoipa::path(
+    get, path = "/api/trades/seller/{seller_address}", tag = "trades",
+    params(("seller_address" = String, Path, description = "Seller address (0x-prefixed)"), TradeListParams),
+    responses(
+        (status = 200, description = "One page of trades on the seller's orders", body = TradesResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 500, description = "Internal error", body = ErrorBody),
+    )
+)]
 pub async fn get_trades_by_seller_handler(
     Path(seller_address): Path<String>,
+    Query(params): Query<TradeListParams>,
     State(state): State<AppState>,
 ) -> ApiResult<Json<TradesResponse>> {
-    // Normalize seller address (lowercase, strip 0x if present)
-    let seller_addr = seller_address
-        .to_lowercase()
-        .trim_start_matches("0x")
-        .to_string();
-    
-    // Query trades table with JOIN to get orders where seller matches
-    let trades = sqlx::query(
-        r#"
-        SELECT 
-            t."tradeId", t."orderId", t.buyer,
-            t."tokenAmount"::text, t."cnyAmount"::text, t."feeAmount"::text,
-            t.rail, t."transactionId", t."paymentTime",
-            t."createdAt", t."expiresAt", t.status,
-            t."escrowTxHash", t."settlementTxHash", t."syncedAt",
-            t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
-            t.proof_user_public_values, t.proof_accumulator, t.proof_data,
-            t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
-            t."chainId",
-            COALESCE(t.token, o.token) as token,
-            o."accountId" as "alipay_id",
-            o."accountName" as "alipay_name"
-        FROM trades t
-        LEFT JOIN orders o ON t."orderId" = o."orderId"
-        WHERE LOWER(REPLACE(o.seller, '0x', '')) = $1
-        ORDER BY t."createdAt" DESC
-        "#
-    )
-    .bind(&seller_addr)
-    .fetch_all(state.db.pool())
-    .await
-    .map_err(|e| ApiError::Database(e.to_string()))?;
-    
-    // Map to DbTrade structs
-    let db_trades: Vec<crate::db::models::DbTrade> = trades
-        .into_iter()
-        .map(|row| {
-            use sqlx::Row;
-            crate::db::models::DbTrade {
-                trade_id: row.get("tradeId"),
-                order_id: row.get("orderId"),
-                buyer: row.get("buyer"),
-                token_amount: row.get("tokenAmount"),
-                cny_amount: row.get("cnyAmount"),
-                fee_amount: row.get("feeAmount"),
-                rail: row.get("rail"),
-                transaction_id: row.get("transactionId"),
-                payment_time: row.get("paymentTime"),
-                created_at: row.get("createdAt"),
-                expires_at: row.get("expiresAt"),
-                status: row.get("status"),
-                escrow_tx_hash: row.get("escrowTxHash"),
-                settlement_tx_hash: row.get("settlementTxHash"),
-                synced_at: row.get("syncedAt"),
-                pdf_file: row.get("pdf_file"),
-                pdf_filename: row.get("pdf_filename"),
-                pdf_uploaded_at: row.get("pdf_uploaded_at"),
-                proof_user_public_values: row.get("proof_user_public_values"),
-                proof_accumulator: row.get("proof_accumulator"),
-                proof_data: row.get("proof_data"),
-                axiom_proof_id: row.get("axiom_proof_id"),
-                proof_generated_at: row.get("proof_generated_at"),
-                proof_json: row.get("proof_json"),
-                settlement_error: row.get("settlement_error"),
-                chain_id: row.get("chainId"),
-                token: row.get("token"),
-                alipay_id: row.get("alipay_id"),
-                alipay_name: row.get("alipay_name"),
-            }
+    let seller_address = parse_address("seller address", &seller_address)?;
+    let list = trade_list_query(TradeParty::Seller(seller_address), &params, &state.api_config)?;
+    list_trades(&state, list, params.include_ens).await
+}
+
+// ============ Settlement Receipt ============
+
+/// Machine-readable record of a settled trade (for accounting imports)
+#[derive(Debug, Serialize)]
+pub struct SettlementReceipt {
+    pub trade_id: String,
+    pub order_id: String,
+    pub chain_id: i32,
+    pub buyer: String,
+    pub seller: String,
+    pub token: String,
+    pub token_symbol: String,
+    pub token_decimals: u8,
+    /// Token base units received by the buyer
+    pub token_amount: String,
+    pub token_amount_decimal: String,
+    /// Protocol fee in token base units (None for trades indexed before fees were recorded)
+    pub fee_amount: Option<String>,
+    pub fee_amount_decimal: Option<String>,
+    /// Fiat paid, in minor units of `currency`
+    pub fiat_amount: String,
+    pub fiat_amount_formatted: String,
+    pub currency: String,
+    /// PaymentRail: 0=ALIPAY, 1=WECHAT
+    pub rail: PaymentRail,
+    /// Payment time as shown on the payment receipt
+    pub payment_time: Option<String>,
+    pub settlement_tx: Option<String>,
+    /// Trade creation time (unix timestamp)
+    pub created_at: i64,
+}
+
+impl SettlementReceipt {
+    /// Receipt for a settled trade; None while the trade is pending or expired
+    pub fn from_settled(trade: &DbTrade, order: &DbOrder) -> Option<Self> {
+        if trade.status != 1 {
+            return None;
+        }
+        let (token_symbol, token_decimals) = tokens::symbol_and_decimals(&order.token);
+        Some(Self {
+            trade_id: trade.trade_id.clone(),
+            order_id: trade.order_id.clone(),
+            chain_id: trade.chain_id,
+            buyer: trade.buyer.clone(),
+            seller: order.seller.clone(),
+            token: order.token.clone(),
+            token_symbol: token_symbol.to_string(),
+            token_decimals,
+            token_amount: trade.token_amount.clone(),
+            token_amount_decimal: format_token_decimal(&trade.token_amount, token_decimals)
+                .unwrap_or_else(InvalidAmount::into_raw),
+            fee_amount: trade.fee_amount.clone(),
+            fee_amount_decimal: trade.fee_amount.as_deref()
+                .map(|fee| format_token_decimal(fee, token_decimals).unwrap_or_else(InvalidAmount::into_raw)),
+            fiat_amount: trade.cny_amount.clone(),
+            fiat_amount_formatted: format_fiat(&trade.cny_amount, Currency::from_code_or_default(&trade.currency))
+                .unwrap_or_else(InvalidAmount::into_raw),
+            currency: trade.currency.clone(),
+            rail: trade.rail,
+            payment_time: trade.payment_time.clone(),
+            settlement_tx: trade.settlement_tx_hash.clone(),
+            created_at: trade.created_at,
         })
-        .collect();
-    
-    Ok(Json(TradesResponse { trades: db_trades }))
+    }
+}
+
+/// Only the trade's buyer and the order's seller may read its receipt
+fn require_participant(wallet: &str, trade: &DbTrade, order: &DbOrder) -> ApiResult<()> {
+    let wallet = wallet.to_lowercase();
+    if wallet == trade.buyer.to_lowercase() || wallet == order.seller.to_lowercase() {
+        Ok(())
+    } else {
+        Err(ApiError::Unauthorized("Only the buyer or seller can view this receipt".to_string()))
+    }
+}
+
+/// GET /api/trades/:trade_id/receipt
+/// Structured settlement receipt (JWT required, buyer or seller only)
+pub async fn get_trade_receipt(
+    Path(trade_id): Path<String>,
+    State(state): State<AppState>,
+    headers: HeaderMap,
+) -> ApiResult<Json<SettlementReceipt>> {
+    let auth_header = headers.get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .ok_or_else(|| ApiError::Unauthorized("Authentication required. Please sign in with your wallet.".to_string()))?;
+    let wallet = auth::verify_jwt(auth_header)
+        .map_err(|e| ApiError::Unauthorized(format!("Invalid authentication: {}", e)))?;
+
+    let trade = state.db.get_trade(&trade_id).await?;
+    let order = state.db.get_order(&trade.order_id).await?;
+    require_participant(&wallet, &trade, &order)?;
+
+    SettlementReceipt::from_settled(&trade, &order)
+        .map(Json)
+        .ok_or_else(|| ApiError::NotFound(format!("Trade {} is not settled, no receipt yet", trade_id)))
+}
+
+/// GET /api/trades/:trade_id/gas-costs
+/// Relayer gas spent on the trade (fill, settle, cancel), oldest first, with the total.
+/// A batch cancellation the trade was in is listed with the whole batch's cost.
+/// A trade with nothing recorded gets an empty list; an unknown trade is a 404.
+pub async fn get_trade_gas_costs(
+    Path(trade_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Json<TradeGasCostsResponse>> {
+    let trade = state.db.get_trade(&trade_id).await?;
+    let costs = state.db.get_trade_gas_costs(&trade.trade_id).await?;
+    Ok(Json(TradeGasCostsResponse::new(trade.trade_id, trade.chain_id as u64, costs)))
+}
+
+/// Comment line sent on idle trade event streams so proxies don't time them out
+const TRADE_EVENTS_HEARTBEAT: Duration = Duration::from_secs(15);
+
+#[derive(Serialize)]
+struct TradeStatusData<'a> {
+    trade_id: &'a str,
+    status: i32,
+}
+
+/// Settled (1) and expired (2) trades never change again
+fn is_terminal_status(status: i32) -> bool {
+    status == 1 || status == 2
+}
+
+/// GET /api/trades/:trade_id/events
+/// SSE stream of the trade's status: a `status` event ({"trade_id", "status"})
+/// with the current status, then one per change. The stream ends once the
+/// trade is settled or expired, or when the server shuts down; idle streams
+/// get a heartbeat every 15s.
+pub async fn get_trade_events(
+    Path(trade_id): Path<String>,
+    State(state): State<AppState>,
+) -> ApiResult<Sse<impl Stream<Item = Result<Event, Infallible>>>> {
+    // Subscribe before reading the status, so a change in between isn't missed
+    let events = state.live_feed.subscribe();
+    let trade = state.db.get_trade(&trade_id).await?;
+    let db = state.db.clone();
+    let reload = move |trade_id: String| {
+        let db = db.clone();
+        async move { db.get_trade(&trade_id).await.ok().map(|trade| trade.status) }
+    };
+    Ok(stream_trade_status(trade.trade_id, trade.status, events, state.shutdown.clone(), reload))
+}
+
+/// Follow `trade_id` on the live feed from `status` until it's terminal or
+/// `shutdown` flips (an open stream would otherwise hold up graceful shutdown);
+/// `reload` re-reads the status when the feed skipped events
+fn stream_trade_status<R, Fut>(
+    trade_id: String,
+    mut status: i32,
+    mut events: broadcast::Receiver<LiveEvent>,
+    mut shutdown: watch::Receiver<bool>,
+    reload: R,
+) -> Sse<impl Stream<Item = Result<Event, Infallible>>>
+where
+    R: Fn(String) -> Fut + Send + 'static,
+    Fut: Future<Output = Option<i32>> + Send,
+{
+    let (sender, receiver) = mpsc::unbounded_channel();
+    let status_event = |trade_id: &str, status: i32| {
+        Event::default().event("status").json_data(TradeStatusData { trade_id, status })
+            .unwrap_or_else(|e| Event::default().event("error").data(e.to_string()))
+    };
+    tokio::spawn(async move {
+        let _ = sender.send(status_event(&trade_id, status));
+        while !is_terminal_status(status) && !*shutdown.borrow_and_update() {
+            let next = tokio::select! {
+                // Client disconnected
+                _ = sender.closed() => break,
+                // Server shutting down (a dropped sender never signals)
+                Ok(()) = shutdown.changed() => continue,
+                event = events.recv() => match event {
+                    Ok(event) if event.trade_id.as_deref() == Some(trade_id.as_str()) => {
+                        match event.kind.trade_status() {
+                            Some(next) => next,
+                            None => continue,
+                        }
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(_)) => match reload(trade_id.clone()).await {
+                        Some(next) => next,
+                        None => break,
+                    },
+                    Err(RecvError::Closed) => break,
+                },
+            };
+            if next != status {
+                status = next;
+                let _ = sender.send(status_event(&trade_id, status));
+            }
+        }
+    });
+    Sse::new(UnboundedReceiverStream::new(receiver).map(Ok))
+        .keep_alive(KeepAlive::new().interval(TRADE_EVENTS_HEARTBEAT))
 }
 
 // ============ Trade Creation ============
 
 /// Request body for creating a trade
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct CreateTradeRequest {
     /// Order ID to fill (0x-prefixed hex string)
     pub order_id: String,
     /// Buyer's address (0x-prefixed)
     pub buyer_address: String,
-    /// Fiat amount in cents (must be divisible by 100 for whole yuan amounts)
+    /// Fiat amount in cents (must be divisible by 100 for whole currency units)
     pub fiat_amount: String,
 }
 
 /// Response for create trade
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct CreateTradeResponse {
     pub trade_id: String,
     pub order_id: String,
@@ -270,10 +523,86 @@ pub struct CreateTradeResponse {
     pub message: String,
 }
 
+/// Validate a requested trade against the order and the escrow's limits, returning
+/// the token amount the buyer would receive (base units, rounded up like fillOrder).
+///
+/// The fee comes on top of the token amount and is only known on-chain, so a
+/// trade that fits the remaining amount here can still revert for lack of room
+/// for the fee.
+fn check_trade_amount(fiat_amount: u128, order: &DbOrder, config: Option<&ContractConfig>) -> ApiResult<u128> {
+    let currency = Currency::from_code_or_default(&order.currency);
+    let fiat = |cents: u128| format_fiat(&cents.to_string(), currency).unwrap_or_else(InvalidAmount::into_raw);
+    
+    if fiat_amount == 0 {
+        return Err(ApiError::BadRequest("fiat_amount must be greater than zero".to_string()));
+    }
+    if fiat_amount % 100 != 0 {
+        return Err(ApiError::BadRequest("fiat_amount must be a whole currency unit (divisible by 100)".to_string()));
+    }
+    
+    if let Some(config) = config {
+        let min = config.min_trade_value_cny().map_err(|e| ApiError::Internal(e.to_string()))?;
+        let max = config.max_trade_value_cny().map_err(|e| ApiError::Internal(e.to_string()))?;
+        if fiat_amount < min {
+            return Err(ApiError::BadRequest(format!(
+                "Trade of {} is below the minimum trade value of {}",
+                fiat(fiat_amount),
+                fiat(min)
+            )));
+        }
+        if fiat_amount > max {
+            return Err(ApiError::BadRequest(format!(
+                "Trade of {} is above the maximum trade value of {}",
+                fiat(fiat_amount),
+                fiat(max)
+            )));
+        }
+    }
+    
+    let exchange_rate: u128 = order.exchange_rate.parse()
+        .map_err(|_| ApiError::Internal(format!("Invalid exchange rate on order {}: {:?}", order.order_id, order.exchange_rate)))?;
+    let remaining: u128 = order.remaining_amount.parse()
+        .map_err(|_| ApiError::Internal(format!("Invalid remaining amount on order {}: {:?}", order.order_id, order.remaining_amount)))?;
+    if exchange_rate == 0 {
+        return Err(ApiError::BadRequest(format!("Order {} has no exchange rate", order.order_id)));
+    }
+    
+    // tokenAmount = ceil(fiatAmount * 10^decimals / exchangeRate), as in fillOrder
+    let (symbol, decimals) = tokens::symbol_and_decimals(&order.token);
+    let token_amount = fiat_amount
+        .checked_mul(10u128.pow(decimals as u32))
+        .map(|scaled| scaled.div_ceil(exchange_rate))
+        .ok_or_else(|| ApiError::BadRequest("fiat_amount is too large".to_string()))?;
+    if token_amount > remaining {
+        return Err(ApiError::BadRequest(format!(
+            "Trade of {} needs {} {} but the order only has {} {} remaining",
+            fiat(fiat_amount),
+            format_token_decimal(&token_amount.to_string(), decimals).unwrap_or_else(InvalidAmount::into_raw),
+            symbol,
+            format_token_decimal(&remaining.to_string(), decimals).unwrap_or_else(InvalidAmount::into_raw),
+            symbol
+        )));
+    }
+    
+    Ok(token_amount)
+}
+
 /// POST /api/trades/create
 /// Create a new trade by filling an order
 /// 
 /// The relay wallet pays for gas - buyers don't need to connect a wallet.
+#[utoipa::path(
+    post, path = "/api/trades/create", tag = "trades",
+    request_body = CreateTradeRequest,
+    responses(
+        (status = 200, description = "Trade created on-chain", body = CreateTradeResponse),
+        (status = 400, description = "Invalid request", body = ErrorBody),
+        (status = 404, description = "Not found", body = ErrorBody),
+        (status = 502, description = "Blockchain error", body = ErrorBody),
+        (status = 503, description = "RPC or service unavailable", body = ErrorBody),
+    )
+)]
+#[tracing::instrument(skip_all, fields(order_id = %request.order_id))]
 pub async fn create_trade_handler(
     State(state): State<AppState>,
     Json(request): Json<CreateTradeRequest>,
@@ -303,28 +632,38 @@ pub async fn create_trade_handler(
     let chain_id = order.chain_id as u64;
     
     // Get the correct blockchain client for this order's chain
-    let blockchain_client = state.get_blockchain_client(chain_id)
-        .ok_or_else(|| ApiError::ServiceUnavailable(
-            format!("Blockchain client not available for chain {}", chain_id)
-        ))?;
+    let blockchain_client = state.get_blockchain_client(chain_id)?;
 
     // Parse buyer address
-    let buyer_address: Address = request.buyer_address.parse()
+    let buyer = parse_address("buyer_address", &request.buyer_address)?;
+    let buyer_address: Address = buyer.parse()
         .map_err(|e| ApiError::BadRequest(format!("Invalid buyer_address: {}", e)))?;
 
     // Parse fiat amount as decimal (in cents, must be divisible by 100 for whole yuan)
-    let fiat_amount: U256 = U256::from_dec_str(&request.fiat_amount)
+    let fiat_amount: u128 = request.fiat_amount.trim().parse()
         .map_err(|e| ApiError::BadRequest(format!("Invalid fiat_amount (must be decimal): {}", e)))?;
     
-    // Validate that fiat amount is whole yuan (divisible by 100)
-    if fiat_amount % U256::from(100) != U256::zero() {
-        return Err(ApiError::BadRequest("fiat_amount must be whole yuan (divisible by 100)".to_string()));
-    }
+    // Reject trades the escrow would revert on before paying gas for them.
+    // Without a config (RPC down) only the order-based checks run; fillOrder
+    // still enforces the limits on-chain.
+    let config = match state.get_config_for_chain(chain_id, false).await {
+        Ok(config) => Some(config),
+        Err(e) => {
+            tracing::warn!("⚠️ Contract config unavailable for chain {}, skipping trade limit checks: {}", chain_id, e);
+            None
+        }
+    };
+    let token_amount = check_trade_amount(fiat_amount, &order, config.as_ref())?;
+    tracing::debug!("Trade amount {} for fiat {} on order {}", token_amount, fiat_amount, request.order_id);
+    let fiat_amount = U256::from(fiat_amount);
 
     // Call fillOrder on-chain via relay wallet (using correct chain client)
     let (tx_hash, trade_id) = blockchain_client.fill_order(order_id, buyer_address, fiat_amount)
         .await
-        .map_err(|e| ApiError::BlockchainError(format!("fillOrder failed on chain {}: {}", chain_id, e)))?;
+        .map_err(|e| {
+            tracing::warn!("fillOrder failed on chain {}: {}", chain_id, e);
+            ApiError::from(e)
+        })?;
 
     let trade_id_hex = format!("0x{}", hex::encode(trade_id));
     let tx_hash_hex = format!("{:#x}", tx_hash);
@@ -339,8 +678,195 @@ pub async fn create_trade_handler(
     Ok(Json(CreateTradeResponse {
         trade_id: trade_id_hex,
         order_id: request.order_id,
-        buyer: request.buyer_address,
+        buyer,
         tx_hash: tx_hash_hex,
         message: "Trade created successfully".to_string(),
     }))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::api::live::LiveEventKind;
+    use crate::db::models::fixtures;
+
+    fn settled_trade() -> DbTrade {
+        DbTrade {
+            buyer: "0xBuyer".to_string(),
+            token_amount: "100000000".to_string(),
+            cny_amount: "72000".to_string(),
+            fee_amount: Some("1000000".to_string()),
+            fee_rate_bps: Some(100),
+            payment_time: Some("2026-01-05 12:30:00".to_string()),
+            created_at: 1_767_600_000,
+            expires_at: 1_767_600_900,
+            escrow_tx_hash: Some("0xescrow".to_string()),
+            settlement_tx_hash: Some("0xsettle".to_string()),
+            ..fixtures::trade("0xtrade", 1)
+        }
+    }
+
+    fn order() -> DbOrder {
+        DbOrder {
+            total_amount: "500000000".to_string(),
+            remaining_amount: "400000000".to_string(),
+            ..fixtures::order("0xorder")
+        }
+    }
+
+    fn limits() -> ContractConfig {
+        ContractConfig {
+            min_trade_value_cny: "10000".to_string(),
+            max_trade_value_cny: "5000000".to_string(),
+            ..ContractConfig::default()
+        }
+    }
+
+    fn rejection(result: ApiResult<u128>) -> String {
+        match result {
+            Err(ApiError::BadRequest(msg)) => msg,
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_trade_amount_within_bounds_is_accepted() {
+        // ¥720 at ¥7.20 per USDC is 100 USDC of the 400 remaining
+        assert_eq!(check_trade_amount(72_000, &order(), Some(&limits())).unwrap(), 100_000_000);
+        // Rounded up like fillOrder: ¥100 at ¥7.20 is 13.888889 USDC
+        assert_eq!(check_trade_amount(10_000, &order(), Some(&limits())).unwrap(), 13_888_889);
+        // Without a config only the order is checked
+        assert_eq!(check_trade_amount(100, &order(), None).unwrap(), 138_889);
+    }
+
+    #[test]
+    fn test_trade_amount_rejections() {
+        let zero = rejection(check_trade_amount(0, &order(), Some(&limits())));
+        assert_eq!(zero, "fiat_amount must be greater than zero");
+
+        let fen = rejection(check_trade_amount(72_050, &order(), Some(&limits())));
+        assert!(fen.contains("whole currency unit"), "{}", fen);
+
+        let below = rejection(check_trade_amount(9_900, &order(), Some(&limits())));
+        assert!(below.contains("below the minimum trade value of ¥100.00"), "{}", below);
+
+        let above = rejection(check_trade_amount(5_000_100, &order(), Some(&limits())));
+        assert!(above.contains("above the maximum trade value"), "{}", above);
+
+        // ¥3000 at ¥7.20 is 416.67 USDC, more than the 400 remaining
+        let exceeds = rejection(check_trade_amount(300_000, &order(), Some(&limits())));
+        assert!(exceeds.contains("only has 400.0 USDC remaining"), "{}", exceeds);
+
+        let closed = DbOrder { remaining_amount: "0".to_string(), ..order() };
+        assert!(rejection(check_trade_amount(10_000, &closed, Some(&limits()))).contains("remaining"));
+    }
+
+    #[test]
+    fn test_settled_trade_returns_structured_receipt() {
+        let receipt = SettlementReceipt::from_settled(&settled_trade(), &order()).unwrap();
+        assert_eq!(receipt.seller, "0xseller");
+        assert_eq!(receipt.token_symbol, "USDC");
+        assert_eq!(receipt.token_amount_decimal, "100.0");
+        assert_eq!(receipt.fee_amount_decimal.as_deref(), Some("1.0"));
+        assert_eq!(receipt.fiat_amount_formatted, "¥720.00");
+        assert_eq!(receipt.settlement_tx.as_deref(), Some("0xsettle"));
+
+        let mut pending = settled_trade();
+        pending.status = 0;
+        assert!(SettlementReceipt::from_settled(&pending, &order()).is_none());
+    }
+
+    #[test]
+    fn test_fee_breakdown_formats_six_decimal_token() {
+        let usdc = order().token;
+
+        // Recorded fee: 1% of 100 USDC, on top of what the buyer receives
+        let recorded = TradeFeeBreakdown::new(&settled_trade(), &usdc, 0);
+        assert_eq!((recorded.token_symbol.as_str(), recorded.token_decimals), ("USDC", 6));
+        assert_eq!(recorded.fee_amount, "1000000");
+        assert_eq!(recorded.fee_amount_formatted, "1 USDC");
+        assert!(!recorded.fee_estimated);
+        assert_eq!(recorded.net_amount_formatted, "100 USDC");
+        assert_eq!((recorded.total_amount.as_str(), recorded.total_amount_formatted.as_str()), ("101000000", "101 USDC"));
+
+        // Legacy trade indexed before fees were recorded: estimated from today's 0.5%
+        let legacy = DbTrade {
+            status: 0,
+            token_amount: "12345678".to_string(),
+            fee_amount: None,
+            fee_rate_bps: None,
+            ..settled_trade()
+        };
+        let estimated = TradeFeeBreakdown::new(&legacy, &usdc, 50);
+        assert!(estimated.fee_estimated);
+        assert_eq!(estimated.fee_amount_formatted, "0.061728 USDC");
+        assert_eq!(estimated.total_amount_formatted, "12.407406 USDC");
+    }
+
+    #[test]
+    fn test_receipt_limited_to_participants() {
+        let (trade, order) = (settled_trade(), order());
+        assert!(require_participant("0xbuyer", &trade, &order).is_ok());
+        assert!(require_participant("0xSELLER", &trade, &order).is_ok());
+        assert!(matches!(require_participant("0xother", &trade, &order), Err(ApiError::Unauthorized(_))));
+    }
+
+    async fn sse_statuses(sse: Sse<impl Stream<Item = Result<Event, Infallible>> + Send + 'static>) -> Vec<String> {
+        use axum::response::IntoResponse;
+        let body = tokio::time::timeout(Duration::from_secs(1), axum::body::to_bytes(sse.into_response().into_body(), usize::MAX))
+            .await
+            .expect("stream did not close")
+            .unwrap();
+        String::from_utf8(body.to_vec()).unwrap().lines()
+            .filter_map(|line| line.strip_prefix("data: "))
+            .map(str::to_string)
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn test_trade_stream_closes_at_terminal_status() {
+        let feed = crate::api::live::LiveFeed::default();
+        let mut private_order = order();
+        private_order.is_public = false;
+
+        let sse = stream_trade_status("0xtrade".to_string(), 0, feed.subscribe(), running(), |_| async { None });
+        feed.publish(LiveEventKind::TradeCreated, &private_order, Some("0xother"));
+        feed.publish(LiveEventKind::OrderUpdated, &private_order, None);
+        feed.publish(LiveEventKind::TradeSettled, &private_order, Some("0xtrade"));
+        assert_eq!(sse_statuses(sse).await, [
+            r#"{"trade_id":"0xtrade","status":0}"#,
+            r#"{"trade_id":"0xtrade","status":1}"#,
+        ]);
+
+        // Already expired: current status, then closed straight away
+        let sse = stream_trade_status("0xtrade".to_string(), 2, feed.subscribe(), running(), |_| async { None });
+        assert_eq!(sse_statuses(sse).await, [r#"{"trade_id":"0xtrade","status":2}"#]);
+
+        // Events skipped by a lagging stream are recovered from the database
+        let lagging = crate::api::live::LiveFeed::new(1);
+        let events = lagging.subscribe();
+        lagging.publish(LiveEventKind::TradeCreated, &order(), Some("0xother"));
+        lagging.publish(LiveEventKind::TradeCreated, &order(), Some("0xother"));
+        let sse = stream_trade_status("0xtrade".to_string(), 0, events, running(), |_| async { Some(2) });
+        assert_eq!(sse_statuses(sse).await.len(), 2);
+    }
+
+    /// Shutdown receiver whose sender is gone (never signals)
+    fn running() -> watch::Receiver<bool> {
+        watch::channel(false).1
+    }
+
+    #[tokio::test]
+    async fn test_trade_stream_closes_on_shutdown() {
+        let feed = crate::api::live::LiveFeed::default();
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+
+        let sse = stream_trade_status("0xtrade".to_string(), 0, feed.subscribe(), shutdown_rx.clone(), |_| async { None });
+        shutdown_tx.send(true).unwrap();
+        assert_eq!(sse_statuses(sse).await, [r#"{"trade_id":"0xtrade","status":0}"#]);
+
+        // Opened after shutdown began: current status, then closed
+        let sse = stream_trade_status("0xtrade".to_string(), 0, feed.subscribe(), shutdown_rx, |_| async { None });
+        assert_eq!(sse_statuses(sse).await, [r#"{"trade_id":"0xtrade","status":0}"#]);
+    }
+}
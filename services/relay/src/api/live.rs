@@ -0,0 +1,315 @@
+//! Live orderbook feed (GET /api/ws/orders)
+//!
+//! The event listener publishes order and trade changes as it syncs them from
+//! the chain; handlers publish the off-chain ones (payment info, visibility).
+//! Besides the WebSocket below, the feed drives per-trade status streams
+//! (GET /api/trades/:id/events), which also follow trades on private orders.
+//!
+//! Each WebSocket client receives every event for public orders, narrowed by an
+//! optional subscription filter it can send (and replace) at any time:
+//! `{"chain_id": 8453, "token": "0x..."}`. Private orders are left out; the one
+//! exception is the `order_hidden` sent when a listed order is made private or
+//! delisted, so books that show it can drop it.
+//!
+//! The feed is a bounded broadcast channel. A client that falls more than
+//! LIVE_FEED_CAPACITY events behind is disconnected - it can reconnect and
+//! reload GET /api/orders/active - instead of events buffering without limit.
+
+use async_trait::async_trait;
+use axum::{
+    extract::{
+        ws::{Message, WebSocket, WebSocketUpgrade},
+        State,
+    },
+    response::Response,
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::broadcast::{self, error::RecvError};
+
+use crate::api::state::AppState;
+use crate::db::models::DbOrder;
+
+/// Events a client may fall behind before it's disconnected
+pub const LIVE_FEED_CAPACITY: usize = 256;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LiveEventKind {
+    OrderCreated,
+    /// Remaining amount, rate, payment info or visibility changed
+    OrderUpdated,
+    /// Remaining amount reached zero
+    OrderFilled,
+    /// Order made private or delisted for inactivity: drop it from the book
+    OrderHidden,
+    TradeCreated,
+    TradeSettled,
+    TradeExpired,
+}
+
+impl LiveEventKind {
+    /// Trade status this event moves its trade to (0 = pending, 1 = settled, 2 = expired)
+    pub fn trade_status(&self) -> Option<i32> {
+        match self {
+            LiveEventKind::TradeCreated => Some(0),
+            LiveEventKind::TradeSettled => Some(1),
+            LiveEventKind::TradeExpired => Some(2),
+            _ => None,
+        }
+    }
+}
+
+/// One orderbook change, with the order's state after it
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct LiveEvent {
+    #[serde(rename = "type")]
+    pub kind: LiveEventKind,
+    pub chain_id: i32,
+    pub order_id: String,
+    pub token: String,
+    pub remaining_amount: String,
+    pub exchange_rate: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub trade_id: Option<String>,
+    /// Private orders are only sent to WebSocket clients as OrderHidden, which is
+    /// published only for orders that were listed until then
+    #[serde(skip)]
+    pub is_public: bool,
+}
+
+/// Publishing side of the feed; cheap to clone
+#[derive(Clone)]
+pub struct LiveFeed {
+    sender: broadcast::Sender<LiveEvent>,
+}
+
+impl Default for LiveFeed {
+    fn default() -> Self {
+        Self::new(LIVE_FEED_CAPACITY)
+    }
+}
+
+impl LiveFeed {
+    pub fn new(capacity: usize) -> Self {
+        let (sender, _) = broadcast::channel(capacity);
+        Self { sender }
+    }
+
+    /// Publish a change to `order`
+    pub fn publish(&self, kind: LiveEventKind, order: &DbOrder, trade_id: Option<&str>) {
+        // Err only means nobody is connected
+        let _ = self.sender.send(LiveEvent {
+            kind,
+            chain_id: order.chain_id,
+            order_id: order.order_id.clone(),
+            token: order.token.clone(),
+            remaining_amount: order.remaining_amount.clone(),
+            exchange_rate: order.exchange_rate.clone(),
+            trade_id: trade_id.map(str::to_string),
+            is_public: order.is_public,
+        });
+    }
+
+    /// Publish `order`'s new remaining amount: OrderFilled at zero, otherwise OrderUpdated
+    pub fn publish_remaining(&self, order: &DbOrder) {
+        let kind = if order.remaining_amount == "0" { LiveEventKind::OrderFilled } else { LiveEventKind::OrderUpdated };
+        self.publish(kind, order, None);
+    }
+
+    pub fn subscribe(&self) -> broadcast::Receiver<LiveEvent> {
+        self.sender.subscribe()
+    }
+}
+
+/// Client filter; omitted fields match everything
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Subscription {
+    pub chain_id: Option<i32>,
+    pub token: Option<String>,
+}
+
+impl Subscription {
+    fn matches(&self, event: &LiveEvent) -> bool {
+        (event.is_public || event.kind == LiveEventKind::OrderHidden)
+            && self.chain_id.is_none_or(|chain_id| chain_id == event.chain_id)
+            && self.token.as_ref().is_none_or(|token| token.eq_ignore_ascii_case(&event.token))
+    }
+}
+
+/// Replies to client messages (events themselves are sent as LiveEvent)
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ControlMessage<'a> {
+    Subscribed { chain_id: Option<i32>, token: Option<&'a str> },
+    Error { message: String },
+}
+
+/// Text frames of a live session: the WebSocket, or channels in tests
+#[async_trait]
+trait LiveSocket: Send {
+    /// Next text message; None once the client is gone
+    async fn recv_text(&mut self) -> Option<String>;
+    /// False once the client is gone
+    async fn send_text(&mut self, text: String) -> bool;
+}
+
+#[async_trait]
+impl LiveSocket for WebSocket {
+    async fn recv_text(&mut self) -> Option<String> {
+        loop {
+            match self.recv().await? {
+                Ok(Message::Text(text)) => return Some(text),
+                Ok(Message::Close(_)) | Err(_) => return None,
+                // Pings are answered by axum; binary frames aren't part of the protocol
+                Ok(_) => continue,
+            }
+        }
+    }
+
+    async fn send_text(&mut self, text: String) -> bool {
+        self.send(Message::Text(text)).await.is_ok()
+    }
+}
+
+/// GET /api/ws/orders - WebSocket upgrade for the live orderbook feed
+pub async fn orders_ws(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    let events = state.live_feed.subscribe();
+    ws.on_upgrade(move |socket| run_session(socket, events))
+}
+
+async fn run_session<S: LiveSocket>(mut socket: S, mut events: broadcast::Receiver<LiveEvent>) {
+    let mut subscription = Subscription::default();
+    loop {
+        tokio::select! {
+            message = socket.recv_text() => {
+                let Some(text) = message else { break };
+                let reply = match serde_json::from_str::<Subscription>(&text) {
+                    Ok(next) => {
+                        subscription = next;
+                        ControlMessage::Subscribed {
+                            chain_id: subscription.chain_id,
+                            token: subscription.token.as_deref(),
+                        }
+                    }
+                    Err(e) => ControlMessage::Error { message: format!("Invalid subscription: {}", e) },
+                };
+                if !send_json(&mut socket, &reply).await {
+                    break;
+                }
+            }
+            event = events.recv() => match event {
+                Ok(event) if subscription.matches(&event) => {
+                    if !send_json(&mut socket, &event).await {
+                        break;
+                    }
+                }
+                Ok(_) => {}
+                Err(RecvError::Lagged(missed)) => {
+                    tracing::warn!("🐢 Live feed client fell {} events behind, disconnecting", missed);
+                    let message = format!("Too slow: missed {} events, reconnect and reload /api/orders/active", missed);
+                    send_json(&mut socket, &ControlMessage::Error { message }).await;
+                    break;
+                }
+                Err(RecvError::Closed) => break,
+            },
+        }
+    }
+}
+
+async fn send_json<S: LiveSocket, T: Serialize>(socket: &mut S, message: &T) -> bool {
+    match serde_json::to_string(message) {
+        Ok(text) => socket.send_text(text).await,
+        Err(_) => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+    use tokio::sync::mpsc;
+    use crate::db::models::fixtures;
+
+    struct ChannelSocket {
+        incoming: mpsc::Receiver<String>,
+        outgoing: mpsc::Sender<String>,
+    }
+
+    #[async_trait]
+    impl LiveSocket for ChannelSocket {
+        async fn recv_text(&mut self) -> Option<String> {
+            self.incoming.recv().await
+        }
+
+        async fn send_text(&mut self, text: String) -> bool {
+            self.outgoing.send(text).await.is_ok()
+        }
+    }
+
+    fn order(chain_id: i32, is_public: bool) -> DbOrder {
+        DbOrder {
+            remaining_amount: "400000".to_string(),
+            chain_id,
+            is_public,
+            ..fixtures::order("0xorder")
+        }
+    }
+
+    async fn next_message(outgoing: &mut mpsc::Receiver<String>) -> serde_json::Value {
+        let text = tokio::time::timeout(Duration::from_secs(1), outgoing.recv()).await
+            .expect("no message within 1s")
+            .expect("session ended");
+        serde_json::from_str(&text).unwrap()
+    }
+
+    /// Connect a session to `feed`; returns (client -> server, server -> client)
+    fn connect(feed: &LiveFeed) -> (mpsc::Sender<String>, mpsc::Receiver<String>) {
+        let (client_tx, incoming) = mpsc::channel(8);
+        let (outgoing, client_rx) = mpsc::channel(8);
+        tokio::spawn(run_session(ChannelSocket { incoming, outgoing }, feed.subscribe()));
+        (client_tx, client_rx)
+    }
+
+    #[tokio::test]
+    async fn test_subscribed_client_receives_trade_created() {
+        let feed = LiveFeed::default();
+        let (client, mut messages) = connect(&feed);
+
+        client.send(r#"{"chain_id": 8453, "token": "0x833589FCD6EDB6E08F4C7C32D4F71B54BDA02913"}"#.to_string()).await.unwrap();
+        let ack = next_message(&mut messages).await;
+        assert_eq!(ack["type"], "subscribed");
+        assert_eq!(ack["chain_id"], 8453);
+
+        // Other chain and private orders never reach this client
+        feed.publish(LiveEventKind::TradeCreated, &order(1, true), Some("0xother"));
+        feed.publish(LiveEventKind::TradeCreated, &order(8453, false), Some("0xprivate"));
+        feed.publish(LiveEventKind::TradeCreated, &order(8453, true), Some("0xtrade"));
+
+        let event = next_message(&mut messages).await;
+        assert_eq!(event["type"], "trade_created");
+        assert_eq!(event["trade_id"], "0xtrade");
+        assert_eq!(event["order_id"], "0xorder");
+        assert_eq!(event["remaining_amount"], "400000");
+
+        client.send("{\"chain\": 1}".to_string()).await.unwrap();
+        assert_eq!(next_message(&mut messages).await["type"], "error");
+    }
+
+    #[tokio::test]
+    async fn test_slow_client_disconnected() {
+        let feed = LiveFeed::new(2);
+        let (_client, mut messages) = connect(&feed);
+
+        // Session can't keep up: outgoing buffer (8) fills, then the feed laps it
+        for _ in 0..20 {
+            feed.publish_remaining(&order(8453, true));
+        }
+        let mut last = serde_json::Value::Null;
+        while let Ok(Some(text)) = tokio::time::timeout(Duration::from_secs(1), messages.recv()).await {
+            last = serde_json::from_str(&text).unwrap();
+        }
+        assert_eq!(last["type"], "error");
+        assert!(last["message"].as_str().unwrap().starts_with("Too slow"), "{}", last);
+    }
+}
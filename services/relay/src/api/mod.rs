@@ -1,6 +1,14 @@
+pub mod concurrency;
 pub mod error;
 pub mod handlers;
+pub mod live;
+pub mod openapi;
+pub mod proof_jobs;
+pub mod rate_limit;
+pub mod request_id;
+pub mod response_size;
 pub mod routes;
+pub mod stale_orders;
 pub mod state;
 pub mod types;
 
@@ -0,0 +1,153 @@
+//! OpenAPI description of the public HTTP API (GET /api/openapi.json)
+//!
+//! Generated from `#[utoipa::path]` annotations on the handlers and `ToSchema` /
+//! `IntoParams` derives on their DTOs, so it can't drift from the code the way a
+//! hand-written spec would. Frontends and integrators generate clients from it.
+//!
+//! Only the public order, trade, seller and settlement endpoints are described; admin,
+//! debug, auth, WebSocket and SSE routes are left out.
+
+use axum::Json;
+use utoipa::{OpenApi, ToSchema};
+
+use crate::api::handlers::{self, orders, settlement, trades};
+use crate::api::proof_jobs::ProofJobStatus;
+use crate::api::types::{
+    ChainInfo, ChainsResponse, HealthResponse, OrderVerifyResponse, SellerStatsResponse, SellerWithdrawal, SellerWithdrawalsResponse,
+    TokenVolume, TokensResponse, VersionResponse,
+};
+use crate::db::models::DbTrade;
+use crate::tokens::TokenInfo;
+
+/// Body of every error response (built by `ApiError::into_response`)
+#[derive(Debug, ToSchema)]
+pub struct ErrorBody {
+    pub error: String,
+    /// HTTP status code, repeated in the body
+    pub status: u16,
+    /// Verification attempts made (payment-info hash mismatch only)
+    pub attempts: Option<u32>,
+    /// The request's `X-Request-Id`, to quote in support reports
+    pub request_id: Option<String>,
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "LyncZ Relay API"),
+    paths(
+        handlers::health_check,
+        handlers::get_version,
+        handlers::get_chains,
+        handlers::get_tokens,
+        handlers::get_seller_stats,
+        handlers::get_seller_withdrawals,
+        orders::get_active_orders,
+        orders::get_orders_batch,
+        orders::get_order_by_private_code,
+        orders::set_order_visibility,
+        orders::submit_payment_info,
+        orders::verify_order_remaining,
+        orders::get_order_activities,
+        handlers::get_trade_pdf,
+        trades::get_trade_handler,
+        trades::get_trades_by_buyer_handler,
+        trades::get_trades_by_seller_handler,
+        trades::create_trade_handler,
+        settlement::validate_handler,
+        settlement::get_settlement_status,
+        settlement::get_proof_job,
+        settlement::regenerate_input_streams,
+    ),
+    components(schemas(
+        ErrorBody,
+        HealthResponse,
+        VersionResponse,
+        ChainsResponse,
+        ChainInfo,
+        TokensResponse,
+        TokenInfo,
+        SellerStatsResponse,
+        TokenVolume,
+        SellerWithdrawalsResponse,
+        SellerWithdrawal,
+        orders::OrderDto,
+        orders::OrderListResponse,
+        orders::BatchOrdersRequest,
+        orders::BatchOrdersResponse,
+        orders::SetVisibilityRequest,
+        orders::SetVisibilityResponse,
+        orders::PaymentInfoRequest,
+        orders::PaymentInfoResponse,
+        OrderVerifyResponse,
+        orders::OrderActivity,
+        orders::OrderActivitiesResponse,
+        DbTrade,
+        trades::TradeDetail,
+        trades::TradeFeeBreakdown,
+        trades::TradeListItem,
+        trades::TradesResponse,
+        trades::CreateTradeRequest,
+        trades::CreateTradeResponse,
+        settlement::ValidateUpload,
+        settlement::ValidateResponse,
+        settlement::SettlementState,
+        settlement::SettlementStatusResponse,
+        settlement::ProofJobResponse,
+        settlement::ProofJobResult,
+        settlement::InputStreamsResponse,
+        ProofJobStatus,
+    )),
+    tags(
+        (name = "health", description = "Service status, chains and tokens"),
+        (name = "orders", description = "Order listing, visibility and payment info"),
+        (name = "trades", description = "Trade creation and queries"),
+        (name = "settlement", description = "Receipt validation and proof progress"),
+        (name = "sellers", description = "Seller reputation and withdrawal history"),
+    )
+)]
+pub struct ApiDoc;
+
+/// GET /api/openapi.json - The generated OpenAPI spec
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(ApiDoc::openapi())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_covers_public_endpoints() {
+        let spec = serde_json::to_value(ApiDoc::openapi()).unwrap();
+        let paths = spec["paths"].as_object().unwrap();
+        for path in [
+            "/health",
+            "/api/orders/active",
+            "/api/orders/{order_id}/payment-info",
+            "/api/trades/{trade_id}",
+            "/api/trades/create",
+            "/api/trades/{trade_id}/validate",
+            "/api/trades/{trade_id}/pdf",
+            "/api/orders/{order_id}/activities",
+            "/api/sellers/{address}/stats",
+            "/api/sellers/{address}/withdrawals",
+            "/api/proofs/{job_id}",
+        ] {
+            assert!(paths.contains_key(path), "missing {}", path);
+        }
+
+        // Query params come from OrderQueryParams, errors share one schema
+        let list = &spec["paths"]["/api/orders/active"]["get"];
+        let params: Vec<&str> = list["parameters"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|p| p["name"].as_str().unwrap())
+            .collect();
+        assert!(params.contains(&"min_rate") && params.contains(&"tokens"), "{:?}", params);
+        assert_eq!(list["responses"]["400"]["content"]["application/json"]["schema"]["$ref"], "#/components/schemas/ErrorBody");
+
+        let schemas = spec["components"]["schemas"].as_object().unwrap();
+        assert!(schemas.contains_key("OrderDto") && schemas.contains_key("PaymentInfoRequest"));
+    }
+}
@@ -0,0 +1,388 @@
+//! Proof job worker
+//!
+//! Validate queues a job in `proofs` and returns its id; this worker claims
+//! queued jobs, generates the Axiom proof and submits it on-chain (see
+//! `handlers::settlement::run_proof_job`, which also holds the trade's
+//! `proof_in_progress` entry and reads `input_streams_cache`). At most
+//! PROOF_CONCURRENCY_LIMIT jobs run at once so we stay within Axiom rate limits.
+//!
+//! Lifecycle: queued -> running -> done | failed. Jobs stuck running after
+//! MAX_PROOF_DURATION_SECS (their process died) are put back in the queue,
+//! unless their trade's settlement tx is already recorded. A job whose trade
+//! is already being proven (its proof_in_progress entry is held) isn't failed:
+//! it goes back in the queue and is retried after BUSY_RETRY_SECS.
+
+use async_trait::async_trait;
+use serde::Serialize;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{watch, Semaphore};
+
+use crate::api::handlers::settlement::run_proof_job;
+use crate::api::state::AppState;
+use crate::db::models::DbProofJob;
+use crate::db::proofs::ProofJobRepository;
+use crate::db::DbResult;
+
+/// How often the queue is polled while idle
+const POLL_INTERVAL_SECS: u64 = 2;
+
+/// How long a job waits in the queue after finding its trade already being proven
+const BUSY_RETRY_SECS: u64 = 30;
+
+/// Why a job didn't settle
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum ProofJobError {
+    /// Another proof for the trade holds its proof_in_progress entry (retried later)
+    Busy,
+    /// Proving or settlement failed (the job fails with this message)
+    Failed(String),
+}
+
+/// Proof job status (`proofs.status`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProofJobStatus {
+    Queued,
+    Running,
+    Done,
+    Failed,
+}
+
+impl ProofJobStatus {
+    pub fn parse(status: &str) -> Option<Self> {
+        match status {
+            "queued" => Some(Self::Queued),
+            "running" => Some(Self::Running),
+            "done" => Some(Self::Done),
+            "failed" => Some(Self::Failed),
+            _ => None,
+        }
+    }
+
+    /// Done and failed jobs never change again
+    pub fn is_terminal(self) -> bool {
+        matches!(self, Self::Done | Self::Failed)
+    }
+
+    /// Transitions the worker makes (running -> queued is the stale-job requeue)
+    pub fn can_transition_to(self, next: Self) -> bool {
+        matches!(
+            (self, next),
+            (Self::Queued, Self::Running)
+                | (Self::Running, Self::Done)
+                | (Self::Running, Self::Failed)
+                | (Self::Running, Self::Queued)
+        )
+    }
+}
+
+/// Job storage plus the proving/settlement work, as seen by the worker
+#[async_trait]
+trait ProofJobBackend: Send + Sync {
+    /// Claim the oldest queued job (queued -> running)
+    async fn claim_next(&self) -> DbResult<Option<DbProofJob>>;
+    /// Generate the proof and settle the trade; returns the settlement tx hash
+    async fn prove_and_settle(&self, job: &DbProofJob) -> Result<String, ProofJobError>;
+    async fn mark_done(&self, id: i64, settlement_tx_hash: &str) -> DbResult<()>;
+    /// Put a running job back in the queue, claimable again after `delay`
+    async fn requeue(&self, id: i64, delay: Duration) -> DbResult<()>;
+    async fn mark_failed(&self, id: i64, error: &str) -> DbResult<()>;
+    /// Requeue unsettled jobs running longer than the max proof duration; returns how many
+    async fn requeue_stale(&self) -> DbResult<u64>;
+}
+
+/// Postgres job queue settled through the relay's blockchain clients
+struct SettlementBackend {
+    state: AppState,
+    repo: ProofJobRepository,
+}
+
+#[async_trait]
+impl ProofJobBackend for SettlementBackend {
+    async fn claim_next(&self) -> DbResult<Option<DbProofJob>> {
+        self.repo.claim_next().await
+    }
+
+    async fn prove_and_settle(&self, job: &DbProofJob) -> Result<String, ProofJobError> {
+        run_proof_job(&self.state, &job.trade_id, &job.transaction_id, &job.payment_time).await
+    }
+
+    async fn mark_done(&self, id: i64, settlement_tx_hash: &str) -> DbResult<()> {
+        self.repo.mark_done(id, settlement_tx_hash).await
+    }
+
+    async fn requeue(&self, id: i64, delay: Duration) -> DbResult<()> {
+        self.repo.requeue(id, delay).await
+    }
+
+    async fn mark_failed(&self, id: i64, error: &str) -> DbResult<()> {
+        self.repo.mark_failed(id, error).await
+    }
+
+    async fn requeue_stale(&self) -> DbResult<u64> {
+        self.repo.requeue_stale(self.state.api_config.max_proof_duration_secs as i64).await
+    }
+}
+
+/// Start the proof job worker (called once from api-server); it stops claiming
+/// jobs once `state.shutdown` flips
+pub fn spawn(state: AppState) {
+    let concurrency = state.api_config.proof_concurrency;
+    let shutdown = state.shutdown.clone();
+    let backend = Arc::new(SettlementBackend {
+        repo: ProofJobRepository::new(state.db.pool().clone()),
+        state,
+    });
+    tokio::spawn(run_with(backend, concurrency, Duration::from_secs(POLL_INTERVAL_SECS), shutdown));
+}
+
+async fn run_with(
+    backend: Arc<dyn ProofJobBackend>,
+    concurrency: usize,
+    poll_interval: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    tracing::info!("🔐 Proof job worker started ({} at a time)", concurrency);
+    let permits = Arc::new(Semaphore::new(concurrency));
+
+    while !*shutdown.borrow_and_update() {
+        let permit = tokio::select! {
+            permit = permits.clone().acquire_owned() => permit.expect("semaphore never closed"),
+            Ok(()) = shutdown.changed() => continue,
+        };
+        match backend.claim_next().await {
+            Ok(Some(job)) => {
+                let backend = backend.clone();
+                tokio::spawn(async move {
+                    process(&*backend, job).await;
+                    drop(permit);
+                });
+            }
+            Ok(None) => {
+                drop(permit);
+                match backend.requeue_stale().await {
+                    Ok(0) => {}
+                    Ok(n) => tracing::warn!("🔐 Requeued {} proof job(s) left running by a dead worker", n),
+                    Err(e) => tracing::warn!("🔐 Failed to requeue stale proof jobs: {}", e),
+                }
+                idle(poll_interval, &mut shutdown).await;
+            }
+            Err(e) => {
+                drop(permit);
+                tracing::warn!("🔐 Failed to claim proof job: {}", e);
+                idle(poll_interval, &mut shutdown).await;
+            }
+        }
+    }
+    tracing::info!("🔐 Proof job worker stopped (shutting down)");
+}
+
+/// Wait out the poll interval, waking early on shutdown
+async fn idle(poll_interval: Duration, shutdown: &mut watch::Receiver<bool>) {
+    tokio::select! {
+        _ = tokio::time::sleep(poll_interval) => {}
+        Ok(()) = shutdown.changed() => {}
+    }
+}
+
+/// Run one claimed job and record its outcome
+#[tracing::instrument(skip_all, fields(job_id = job.id, trade_id = %job.trade_id))]
+async fn process(backend: &dyn ProofJobBackend, job: DbProofJob) {
+    tracing::info!("🔐 Proof job {} started for trade {} (attempt {})", job.id, job.trade_id, job.attempts);
+    let result = match backend.prove_and_settle(&job).await {
+        Ok(tx_hash) => {
+            tracing::info!("✅ Proof job {} done: trade {} settled in {}", job.id, job.trade_id, tx_hash);
+            backend.mark_done(job.id, &tx_hash).await
+        }
+        Err(ProofJobError::Busy) => {
+            tracing::info!("🔐 Proof job {}: trade {} is already being proven, retrying in {}s", job.id, job.trade_id, BUSY_RETRY_SECS);
+            backend.requeue(job.id, Duration::from_secs(BUSY_RETRY_SECS)).await
+        }
+        Err(ProofJobError::Failed(e)) => {
+            tracing::error!("❌ Proof job {} failed for trade {}: {}", job.id, job.trade_id, e);
+            backend.mark_failed(job.id, &e).await
+        }
+    };
+    if let Err(e) = result {
+        tracing::warn!("🔐 Failed to update proof job {}: {}", job.id, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Utc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// In-memory queue; every status change is checked against `can_transition_to`
+    struct FakeQueue {
+        jobs: Mutex<Vec<DbProofJob>>,
+        history: Mutex<Vec<(i64, ProofJobStatus)>>,
+        running: AtomicUsize,
+        max_running: AtomicUsize,
+        /// Times "0xbusy" still finds its trade claimed
+        busy_for: AtomicUsize,
+    }
+
+    impl FakeQueue {
+        fn with_jobs(trade_ids: &[&str]) -> Self {
+            let jobs = trade_ids.iter().enumerate().map(|(i, trade_id)| DbProofJob {
+                id: i as i64 + 1,
+                trade_id: trade_id.to_string(),
+                transaction_id: "2024010122001400000000000001".to_string(),
+                payment_time: "2024-01-01 12:00:00".to_string(),
+                status: "queued".to_string(),
+                attempts: 0,
+                settlement_tx_hash: None,
+                error: None,
+                created_at: Utc::now(),
+                started_at: None,
+                finished_at: None,
+            }).collect();
+            Self {
+                jobs: Mutex::new(jobs),
+                history: Mutex::new(Vec::new()),
+                running: AtomicUsize::new(0),
+                max_running: AtomicUsize::new(0),
+                busy_for: AtomicUsize::new(1),
+            }
+        }
+
+        fn transition(&self, id: i64, next: ProofJobStatus, update: impl FnOnce(&mut DbProofJob)) -> Option<DbProofJob> {
+            let mut jobs = self.jobs.lock().unwrap();
+            let job = jobs.iter_mut().find(|j| j.id == id)?;
+            let current = ProofJobStatus::parse(&job.status).unwrap();
+            assert!(current.can_transition_to(next), "job {}: {:?} -> {:?}", id, current, next);
+            job.status = serde_json::to_value(next).unwrap().as_str().unwrap().to_string();
+            update(job);
+            self.history.lock().unwrap().push((id, next));
+            Some(job.clone())
+        }
+
+        fn statuses(&self) -> Vec<(String, String)> {
+            self.jobs.lock().unwrap().iter().map(|j| (j.trade_id.clone(), j.status.clone())).collect()
+        }
+    }
+
+    #[async_trait]
+    impl ProofJobBackend for FakeQueue {
+        async fn claim_next(&self) -> DbResult<Option<DbProofJob>> {
+            let next = self.jobs.lock().unwrap().iter().find(|j| j.status == "queued").map(|j| j.id);
+            Ok(next.and_then(|id| self.transition(id, ProofJobStatus::Running, |j| j.attempts += 1)))
+        }
+
+        async fn prove_and_settle(&self, job: &DbProofJob) -> Result<String, ProofJobError> {
+            if job.trade_id == "0xbusy" && self.busy_for.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)).is_ok() {
+                return Err(ProofJobError::Busy);
+            }
+            let running = self.running.fetch_add(1, Ordering::SeqCst) + 1;
+            self.max_running.fetch_max(running, Ordering::SeqCst);
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            self.running.fetch_sub(1, Ordering::SeqCst);
+            if job.trade_id == "0xbad" {
+                Err(ProofJobError::Failed("Proof generation failed: boom".to_string()))
+            } else {
+                Ok(format!("0xtx{}", job.id))
+            }
+        }
+
+        async fn mark_done(&self, id: i64, settlement_tx_hash: &str) -> DbResult<()> {
+            self.transition(id, ProofJobStatus::Done, |j| j.settlement_tx_hash = Some(settlement_tx_hash.to_string()));
+            Ok(())
+        }
+
+        async fn requeue(&self, id: i64, _delay: Duration) -> DbResult<()> {
+            self.transition(id, ProofJobStatus::Queued, |_| {});
+            Ok(())
+        }
+
+        async fn mark_failed(&self, id: i64, error: &str) -> DbResult<()> {
+            self.transition(id, ProofJobStatus::Failed, |j| j.error = Some(error.to_string()));
+            Ok(())
+        }
+
+        async fn requeue_stale(&self) -> DbResult<u64> {
+            Ok(0)
+        }
+    }
+
+    #[test]
+    fn test_status_transitions() {
+        use ProofJobStatus::*;
+        assert!(Queued.can_transition_to(Running));
+        assert!(Running.can_transition_to(Done));
+        assert!(Running.can_transition_to(Failed));
+        assert!(Running.can_transition_to(Queued));
+        assert!(!Queued.can_transition_to(Done));
+        assert!(!Done.can_transition_to(Running));
+        assert!(!Failed.can_transition_to(Queued));
+        assert!(Done.is_terminal() && Failed.is_terminal() && !Running.is_terminal());
+        for status in [Queued, Running, Done, Failed] {
+            let name = serde_json::to_value(status).unwrap();
+            assert_eq!(ProofJobStatus::parse(name.as_str().unwrap()), Some(status));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_jobs_run_to_done_or_failed_within_concurrency() {
+        let queue = Arc::new(FakeQueue::with_jobs(&["0x1", "0xbad", "0x3", "0x4", "0x5"]));
+        let worker = tokio::spawn(run_with(queue.clone(), 2, Duration::from_millis(5), watch::channel(false).1));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while !queue.statuses().iter().all(|(_, s)| s == "done" || s == "failed") {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }).await.expect("all jobs finish");
+        worker.abort();
+
+        assert_eq!(queue.statuses(), vec![
+            ("0x1".to_string(), "done".to_string()),
+            ("0xbad".to_string(), "failed".to_string()),
+            ("0x3".to_string(), "done".to_string()),
+            ("0x4".to_string(), "done".to_string()),
+            ("0x5".to_string(), "done".to_string()),
+        ]);
+        assert!(queue.max_running.load(Ordering::SeqCst) <= 2);
+
+        let history = queue.history.lock().unwrap();
+        let bad: Vec<ProofJobStatus> = history.iter().filter(|(id, _)| *id == 2).map(|(_, s)| *s).collect();
+        assert_eq!(bad, vec![ProofJobStatus::Running, ProofJobStatus::Failed]);
+        let jobs = queue.jobs.lock().unwrap();
+        assert_eq!(jobs[0].settlement_tx_hash.as_deref(), Some("0xtx1"));
+        assert_eq!(jobs[1].error.as_deref(), Some("Proof generation failed: boom"));
+    }
+
+    #[tokio::test]
+    async fn test_busy_trade_requeued_not_failed() {
+        let queue = Arc::new(FakeQueue::with_jobs(&["0xbusy"]));
+        let worker = tokio::spawn(run_with(queue.clone(), 1, Duration::from_millis(5), watch::channel(false).1));
+
+        tokio::time::timeout(Duration::from_secs(5), async {
+            while queue.statuses()[0].1 != "done" {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        }).await.expect("the job is retried and settles");
+        worker.abort();
+
+        use ProofJobStatus::*;
+        let history: Vec<ProofJobStatus> = queue.history.lock().unwrap().iter().map(|(_, s)| *s).collect();
+        assert_eq!(history, vec![Running, Queued, Running, Done]);
+        let jobs = queue.jobs.lock().unwrap();
+        assert_eq!((jobs[0].attempts, jobs[0].error.as_deref()), (2, None));
+    }
+
+    #[tokio::test]
+    async fn test_worker_stops_on_shutdown() {
+        let queue = Arc::new(FakeQueue::with_jobs(&[]));
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        let worker = tokio::spawn(run_with(queue, 2, Duration::from_secs(60), shutdown_rx));
+
+        tokio::time::sleep(Duration::from_millis(20)).await;
+        shutdown_tx.send(true).unwrap();
+        tokio::time::timeout(Duration::from_secs(1), worker).await
+            .expect("worker stops without waiting out the poll interval")
+            .unwrap();
+    }
+}
@@ -0,0 +1,564 @@
+//! Per-IP request rate limiting
+//!
+//! Every request is charged against a token bucket for its client IP. Two budgets
+//! exist: `expensive` for POST validate/payment-info (PDF parsing, RPC calls, proof
+//! work) and `read` for everything else, so a client polling the order book cannot
+//! starve its own uploads and vice versa. An empty bucket answers 429 with
+//! `Retry-After` (seconds until the next token).
+//!
+//! Validate/payment-info calls carrying a valid JWT are also charged to a
+//! per-wallet budget (`WalletRateLimiter`, shared through AppState), so one
+//! signed-in wallet can't spread its proof/RPC work across many IPs. Calls
+//! without a token are only IP-limited.
+//!
+//! GET /api/orders/private/:code is a lookup by secret, so it also has its own,
+//! tighter per-IP budget (`rate_limit_private_code_per_min`). Its 404s count as
+//! misses: after PRIVATE_CODE_MISSES_BEFORE_LOCKOUT misses in a row the IP is
+//! locked out of the route for a minute, doubling with every further miss (up to
+//! an hour); a successful lookup clears the count. With 22-character codes (see
+//! crypto::private_code) this makes guessing a code infeasible.
+//!
+//! The client IP is the TCP peer address, or the rightmost entry of
+//! `ApiConfig.rate_limit_client_ip_header` when the relay sits behind a proxy that
+//! sets it. Only configure that header if the proxy overwrites/appends it, since
+//! clients can send it themselves. IPs in `rate_limit_exempt_ips` are never limited.
+
+use axum::{
+    extract::{ConnectInfo, Request, State},
+    http::{header, HeaderName, HeaderValue, Method, StatusCode},
+    middleware::{self, Next},
+    response::{IntoResponse, Response},
+    Router,
+};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::net::{IpAddr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::api::ApiError;
+use crate::auth;
+use crate::config::ApiConfig;
+
+/// Idle buckets are pruned once this many IPs are tracked
+const MAX_TRACKED_IPS: usize = 10_000;
+
+/// Idle buckets are pruned once this many wallets are tracked
+const MAX_TRACKED_WALLETS: usize = 10_000;
+
+/// Consecutive private-code misses from one IP before it is locked out
+const PRIVATE_CODE_MISSES_BEFORE_LOCKOUT: u32 = 5;
+
+/// First private-code lockout; doubled for every further miss
+const PRIVATE_CODE_BASE_LOCKOUT: Duration = Duration::from_secs(60);
+
+/// Longest private-code lockout
+const PRIVATE_CODE_MAX_LOCKOUT: Duration = Duration::from_secs(3600);
+
+/// An IP's private-code misses are forgotten after this long without another miss (counted from the end of its lockout)
+const PRIVATE_CODE_MISS_WINDOW: Duration = Duration::from_secs(3600);
+
+struct Bucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+/// Token buckets for one budget, keyed by client IP or wallet
+struct BucketSet<K> {
+    name: &'static str,
+    capacity: f64,
+    refill_per_sec: f64,
+    max_tracked: usize,
+    buckets: Mutex<HashMap<K, Bucket>>,
+}
+
+impl<K: Hash + Eq> BucketSet<K> {
+    fn new(name: &'static str, per_minute: u32, max_tracked: usize) -> Self {
+        let per_minute = per_minute.max(1) as f64;
+        Self {
+            name,
+            capacity: per_minute,
+            refill_per_sec: per_minute / 60.0,
+            max_tracked,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Take one token for `key`, or return how long until one is available
+    fn check(&self, key: K, now: Instant) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        if buckets.len() >= self.max_tracked && !buckets.contains_key(&key) {
+            // Forget keys whose bucket has refilled completely; they start full anyway
+            buckets.retain(|_, b| {
+                let elapsed = now.saturating_duration_since(b.updated).as_secs_f64();
+                b.tokens + elapsed * self.refill_per_sec < self.capacity
+            });
+        }
+
+        let bucket = buckets.entry(key).or_insert(Bucket { tokens: self.capacity, updated: now });
+        let elapsed = now.saturating_duration_since(bucket.updated).as_secs_f64();
+        bucket.tokens = (bucket.tokens + elapsed * self.refill_per_sec).min(self.capacity);
+        bucket.updated = now;
+
+        if bucket.tokens >= 1.0 {
+            bucket.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - bucket.tokens) / self.refill_per_sec))
+        }
+    }
+}
+
+/// Per-wallet budget for validate/payment-info, keyed on the JWT's address
+/// (lives in AppState; the middleware charges it for authenticated calls)
+#[derive(Clone)]
+pub struct WalletRateLimiter {
+    buckets: Arc<BucketSet<String>>,
+}
+
+impl WalletRateLimiter {
+    pub fn new(per_minute: u32) -> Self {
+        Self {
+            buckets: Arc::new(BucketSet::new("per-wallet validate/payment-info", per_minute, MAX_TRACKED_WALLETS)),
+        }
+    }
+
+    /// Take one token for `wallet`, or return how long until one is available
+    pub fn check(&self, wallet: &str) -> Result<(), Duration> {
+        self.buckets.check(wallet.to_lowercase(), Instant::now())
+    }
+}
+
+/// Private-code misses from one IP
+struct MissRecord {
+    misses: u32,
+    last_miss: Instant,
+    locked_until: Option<Instant>,
+}
+
+/// Private-code lookup budget plus exponential lockout after repeated misses
+struct PrivateCodeGuard {
+    buckets: BucketSet<IpAddr>,
+    misses: Mutex<HashMap<IpAddr, MissRecord>>,
+}
+
+impl PrivateCodeGuard {
+    fn new(per_minute: u32) -> Self {
+        Self {
+            buckets: BucketSet::new("private-code lookups", per_minute, MAX_TRACKED_IPS),
+            misses: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Allow a lookup from `ip`, or return how long until one is allowed
+    fn check(&self, ip: IpAddr, now: Instant) -> Result<(), Duration> {
+        let locked_until = self.lock().get(&ip).and_then(|record| record.locked_until);
+        if let Some(until) = locked_until.filter(|&until| until > now) {
+            return Err(until - now);
+        }
+        self.buckets.check(ip, now)
+    }
+
+    /// A lookup from `ip` found no order; locks the IP out once it has missed too often
+    fn record_miss(&self, ip: IpAddr, now: Instant) {
+        let mut records = self.lock();
+        if records.len() >= MAX_TRACKED_IPS && !records.contains_key(&ip) {
+            records.retain(|_, r| now.saturating_duration_since(r.last_miss) < PRIVATE_CODE_MISS_WINDOW);
+        }
+
+        let record = records.entry(ip).or_insert(MissRecord { misses: 0, last_miss: now, locked_until: None });
+        // The quiet period only starts once the last lockout is over
+        let quiet_since = record.locked_until.map_or(record.last_miss, |until| until.max(record.last_miss));
+        if now.saturating_duration_since(quiet_since) >= PRIVATE_CODE_MISS_WINDOW {
+            record.misses = 0;
+        }
+        record.misses += 1;
+        record.last_miss = now;
+
+        if record.misses >= PRIVATE_CODE_MISSES_BEFORE_LOCKOUT {
+            let doublings = (record.misses - PRIVATE_CODE_MISSES_BEFORE_LOCKOUT).min(16);
+            let lockout = (PRIVATE_CODE_BASE_LOCKOUT * 2u32.pow(doublings)).min(PRIVATE_CODE_MAX_LOCKOUT);
+            record.locked_until = Some(now + lockout);
+            tracing::warn!(
+                "🚨 {} private-code misses in a row from {}, locked out for {}s (possible code enumeration)",
+                record.misses,
+                ip,
+                lockout.as_secs()
+            );
+        }
+    }
+
+    /// A lookup from `ip` found an order: its miss count starts over
+    fn record_hit(&self, ip: IpAddr) {
+        self.lock().remove(&ip);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<IpAddr, MissRecord>> {
+        self.misses.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Shared limiter state for the middleware
+#[derive(Clone)]
+pub struct RateLimiter {
+    read: Arc<BucketSet<IpAddr>>,
+    expensive: Arc<BucketSet<IpAddr>>,
+    wallets: WalletRateLimiter,
+    private_codes: Arc<PrivateCodeGuard>,
+    client_ip_header: Option<HeaderName>,
+    exempt: Arc<HashSet<IpAddr>>,
+}
+
+impl RateLimiter {
+    /// None when rate limiting is disabled (RATE_LIMIT_ENABLED=false)
+    pub fn from_config(config: &ApiConfig, wallets: WalletRateLimiter) -> Option<Self> {
+        if !config.rate_limit_enabled {
+            return None;
+        }
+        let client_ip_header = config.rate_limit_client_ip_header.as_deref()
+            .and_then(|name| match HeaderName::from_bytes(name.as_bytes()) {
+                Ok(header) => Some(header),
+                Err(_) => {
+                    tracing::warn!("⚠️ Ignoring invalid RATE_LIMIT_CLIENT_IP_HEADER: {:?}", name);
+                    None
+                }
+            });
+        Some(Self {
+            read: Arc::new(BucketSet::new("reads", config.rate_limit_read_per_min, MAX_TRACKED_IPS)),
+            expensive: Arc::new(BucketSet::new("validate/payment-info", config.rate_limit_expensive_per_min, MAX_TRACKED_IPS)),
+            wallets,
+            private_codes: Arc::new(PrivateCodeGuard::new(config.rate_limit_private_code_per_min)),
+            client_ip_header,
+            exempt: Arc::new(config.rate_limit_exempt_ips.iter().copied().collect()),
+        })
+    }
+
+    /// Client IP from the trusted proxy header, else the TCP peer address
+    fn client_ip(&self, request: &Request) -> Option<IpAddr> {
+        let from_header = self.client_ip_header.as_ref()
+            .and_then(|name| request.headers().get(name))
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.rsplit(',').next())
+            .and_then(|ip| ip.trim().parse().ok());
+        from_header.or_else(|| {
+            request.extensions()
+                .get::<ConnectInfo<SocketAddr>>()
+                .map(|ConnectInfo(addr)| addr.ip())
+        })
+    }
+}
+
+/// Validate and payment-info trigger RPC/proof work and get the smaller budget
+fn is_expensive(method: &Method, path: &str) -> bool {
+    method == Method::POST
+        && ((path.starts_with("/api/trades/") && path.ends_with("/validate"))
+            || (path.starts_with("/api/orders/") && path.ends_with("/payment-info")))
+}
+
+/// Lookup of an unlisted order by its secret code
+fn is_private_code_lookup(method: &Method, path: &str) -> bool {
+    method == Method::GET && path.starts_with("/api/orders/private/")
+}
+
+/// Wallet of a valid bearer token on the request, if any
+fn authenticated_wallet(request: &Request) -> Option<String> {
+    let auth_header = request.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    auth::verify_jwt(auth_header).ok()
+}
+
+/// 429 telling the client when to come back
+fn too_many_requests(wait: Duration) -> Response {
+    let retry_after = wait.as_secs_f64().ceil().max(1.0) as u64;
+    let mut response = ApiError::TooManyRequests(format!(
+        "Too many requests, retry in {}s",
+        retry_after
+    ))
+    .into_response();
+    response.headers_mut().insert(header::RETRY_AFTER, HeaderValue::from(retry_after));
+    response
+}
+
+/// Apply per-IP (and, for authenticated expensive calls, per-wallet) rate
+/// limiting to every route in `router` (no-op when disabled)
+pub fn rate_limit<S>(router: Router<S>, config: &ApiConfig, wallets: WalletRateLimiter) -> Router<S>
+where
+    S: Clone + Send + Sync + 'static,
+{
+    match RateLimiter::from_config(config, wallets) {
+        Some(limiter) => router.layer(middleware::from_fn_with_state(limiter, enforce_rate_limit)),
+        None => router,
+    }
+}
+
+async fn enforce_rate_limit(
+    State(limiter): State<RateLimiter>,
+    request: Request,
+    next: Next,
+) -> Response {
+    let Some(ip) = limiter.client_ip(&request) else {
+        tracing::debug!("🚦 No client IP for {}, not rate limited", request.uri().path());
+        return next.run(request).await;
+    };
+    if limiter.exempt.contains(&ip) {
+        return next.run(request).await;
+    }
+
+    let expensive = is_expensive(request.method(), request.uri().path());
+    let buckets = if expensive {
+        &limiter.expensive
+    } else {
+        &limiter.read
+    };
+
+    if let Err(wait) = buckets.check(ip, Instant::now()) {
+        tracing::warn!("🚦 {} rate limit exceeded by {} ({} {})", buckets.name, ip, request.method(), request.uri().path());
+        return too_many_requests(wait);
+    }
+
+    if expensive {
+        if let Some(wallet) = authenticated_wallet(&request) {
+            if let Err(wait) = limiter.wallets.check(&wallet) {
+                tracing::warn!("🚦 {} rate limit exceeded by {} ({} {})", limiter.wallets.buckets.name, wallet, request.method(), request.uri().path());
+                return too_many_requests(wait);
+            }
+        }
+    }
+
+    if is_private_code_lookup(request.method(), request.uri().path()) {
+        let guard = &limiter.private_codes;
+        if let Err(wait) = guard.check(ip, Instant::now()) {
+            tracing::warn!("🚦 {} rate limit or lockout hit by {}", guard.buckets.name, ip);
+            return too_many_requests(wait);
+        }
+        let response = next.run(request).await;
+        if response.status() == StatusCode::NOT_FOUND {
+            guard.record_miss(ip, Instant::now());
+        } else if response.status().is_success() {
+            guard.record_hit(ip);
+        }
+        return response;
+    }
+
+    next.run(request).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::DEFAULT_RATE_LIMIT_WALLET_PER_MIN;
+    use axum::{body::Body, extract::Path, routing::{get, post}};
+    use tower::ServiceExt;
+
+    fn config(expensive_per_min: u32) -> ApiConfig {
+        ApiConfig {
+            rate_limit_expensive_per_min: expensive_per_min,
+            rate_limit_client_ip_header: Some("X-Forwarded-For".to_string()),
+            rate_limit_exempt_ips: vec!["10.0.0.5".parse().unwrap()],
+            ..ApiConfig::default()
+        }
+    }
+
+    fn app(config: &ApiConfig) -> Router {
+        let routes = Router::new()
+            .route("/api/trades/:trade_id/validate", post(|| async { "validated" }))
+            .route("/api/trades/:trade_id", get(|| async { "trade" }))
+            .route("/api/orders/private/:code", get(|Path(code): Path<String>| async move {
+                if code == "good" { StatusCode::OK } else { StatusCode::NOT_FOUND }
+            }));
+        rate_limit(routes, config, WalletRateLimiter::new(config.rate_limit_wallet_per_min))
+    }
+
+    fn request(method: Method, path: &str, forwarded_for: &str) -> Request {
+        Request::builder()
+            .method(method)
+            .uri(path)
+            .header("X-Forwarded-For", forwarded_for)
+            .body(Body::empty())
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_validate_throttled_after_limit() {
+        let app = app(&config(3));
+        let validate = |ip: &str| request(Method::POST, "/api/trades/0x01/validate", ip);
+
+        for _ in 0..3 {
+            let response = app.clone().oneshot(validate("203.0.113.7")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+
+        let throttled = app.clone().oneshot(validate("203.0.113.7")).await.unwrap();
+        assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = throttled.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert!((1..=20).contains(&retry_after));
+
+        // Other clients, the read budget and exempt IPs are unaffected
+        let other = app.clone().oneshot(validate("198.51.100.1")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+        let read = app.clone().oneshot(request(Method::GET, "/api/trades/0x01", "203.0.113.7")).await.unwrap();
+        assert_eq!(read.status(), StatusCode::OK);
+        for _ in 0..5 {
+            let exempt = app.clone().oneshot(validate("10.0.0.5")).await.unwrap();
+            assert_eq!(exempt.status(), StatusCode::OK);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_wallet_throttled_across_ips() {
+        let config = ApiConfig { rate_limit_wallet_per_min: 2, ..config(10) };
+        let app = app(&config);
+        let token = format!("Bearer {}", auth::test_token("0x00000000000000000000000000000000000000AA"));
+        let validate = |ip: &str, token: Option<&str>| {
+            let mut req = request(Method::POST, "/api/trades/0x01/validate", ip);
+            if let Some(token) = token {
+                req.headers_mut().insert(header::AUTHORIZATION, HeaderValue::from_str(token).unwrap());
+            }
+            req
+        };
+
+        for ip in ["203.0.113.1", "203.0.113.2"] {
+            let response = app.clone().oneshot(validate(ip, Some(&token))).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+        let throttled = app.clone().oneshot(validate("203.0.113.3", Some(&token))).await.unwrap();
+        assert_eq!(throttled.status(), StatusCode::TOO_MANY_REQUESTS);
+        assert!(throttled.headers().contains_key(header::RETRY_AFTER));
+
+        // Unauthenticated (or badly signed) calls only count against their IP
+        let anonymous = app.clone().oneshot(validate("203.0.113.3", None)).await.unwrap();
+        assert_eq!(anonymous.status(), StatusCode::OK);
+        let forged = app.clone().oneshot(validate("203.0.113.3", Some("Bearer not-a-jwt"))).await.unwrap();
+        assert_eq!(forged.status(), StatusCode::OK);
+    }
+
+    #[tokio::test]
+    async fn test_disabled_limiter_passes_everything() {
+        let config = ApiConfig { rate_limit_enabled: false, ..config(1) };
+        let app = app(&config);
+        for _ in 0..3 {
+            let response = app.clone().oneshot(request(Method::POST, "/api/trades/0x01/validate", "203.0.113.7")).await.unwrap();
+            assert_eq!(response.status(), StatusCode::OK);
+        }
+    }
+
+    #[test]
+    fn test_client_ip_prefers_trusted_header() {
+        let wallets = WalletRateLimiter::new(DEFAULT_RATE_LIMIT_WALLET_PER_MIN);
+        let limiter = RateLimiter::from_config(&config(3), wallets.clone()).unwrap();
+        let mut req = request(Method::GET, "/health", "1.2.3.4, 203.0.113.7");
+        req.extensions_mut().insert(ConnectInfo(SocketAddr::from(([127, 0, 0, 1], 4000))));
+        assert_eq!(limiter.client_ip(&req), Some("203.0.113.7".parse().unwrap()));
+
+        // Without the header configured, the peer address is used
+        let limiter = RateLimiter::from_config(&ApiConfig::default(), wallets).unwrap();
+        assert_eq!(limiter.client_ip(&req), Some("127.0.0.1".parse().unwrap()));
+    }
+
+    #[test]
+    fn test_bucket_refills_over_time() {
+        let buckets = BucketSet::new("test", 60, MAX_TRACKED_IPS);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let start = Instant::now();
+        for _ in 0..60 {
+            assert!(buckets.check(ip, start).is_ok());
+        }
+        let wait = buckets.check(ip, start).unwrap_err();
+        assert!(wait <= Duration::from_secs(1));
+        assert!(buckets.check(ip, start + Duration::from_secs(1)).is_ok());
+    }
+
+    #[test]
+    fn test_stale_wallets_evicted() {
+        let buckets = BucketSet::new("test", 60, 2);
+        let start = Instant::now();
+        assert!(buckets.check("0xaa".to_string(), start).is_ok());
+        assert!(buckets.check("0xbb".to_string(), start).is_ok());
+
+        // Both still refilling: nothing can be dropped, the map grows past the cap
+        assert!(buckets.check("0xcc".to_string(), start).is_ok());
+        assert_eq!(buckets.buckets.lock().unwrap().len(), 3);
+
+        // A second later all three have refilled, so they're forgotten on the next new wallet
+        assert!(buckets.check("0xdd".to_string(), start + Duration::from_secs(1)).is_ok());
+        let buckets = buckets.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert!(buckets.contains_key("0xdd"));
+    }
+
+    #[tokio::test]
+    async fn test_private_code_misses_lock_out_ip() {
+        let app = app(&ApiConfig { rate_limit_private_code_per_min: 100, ..config(10) });
+        let lookup = |code: &str, ip: &str| request(Method::GET, &format!("/api/orders/private/{}", code), ip);
+
+        // A hit clears earlier misses
+        for _ in 0..PRIVATE_CODE_MISSES_BEFORE_LOCKOUT - 1 {
+            let miss = app.clone().oneshot(lookup("guess", "203.0.113.7")).await.unwrap();
+            assert_eq!(miss.status(), StatusCode::NOT_FOUND);
+        }
+        let hit = app.clone().oneshot(lookup("good", "203.0.113.7")).await.unwrap();
+        assert_eq!(hit.status(), StatusCode::OK);
+
+        for _ in 0..PRIVATE_CODE_MISSES_BEFORE_LOCKOUT {
+            let miss = app.clone().oneshot(lookup("guess", "203.0.113.7")).await.unwrap();
+            assert_eq!(miss.status(), StatusCode::NOT_FOUND);
+        }
+
+        // Locked out, even with the right code; other IPs and routes are unaffected
+        let locked = app.clone().oneshot(lookup("good", "203.0.113.7")).await.unwrap();
+        assert_eq!(locked.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = locked.headers()[header::RETRY_AFTER].to_str().unwrap().parse().unwrap();
+        assert_eq!(retry_after, PRIVATE_CODE_BASE_LOCKOUT.as_secs());
+        let other = app.clone().oneshot(lookup("good", "198.51.100.1")).await.unwrap();
+        assert_eq!(other.status(), StatusCode::OK);
+        let read = app.clone().oneshot(request(Method::GET, "/api/trades/0x01", "203.0.113.7")).await.unwrap();
+        assert_eq!(read.status(), StatusCode::OK);
+    }
+
+    #[test]
+    fn test_private_code_lockout_doubles_and_caps() {
+        let guard = PrivateCodeGuard::new(1000);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let mut now = Instant::now();
+
+        for _ in 0..PRIVATE_CODE_MISSES_BEFORE_LOCKOUT {
+            assert!(guard.check(ip, now).is_ok());
+            guard.record_miss(ip, now);
+        }
+        assert_eq!(guard.check(ip, now).unwrap_err(), Duration::from_secs(60));
+
+        // Each miss after the lockout expires doubles it: 2, 4, 8, 16, 32 minutes, then the cap
+        for expected in [120, 240, 480, 960, 1920, 3600, 3600] {
+            now += guard.check(ip, now).unwrap_err();
+            assert!(guard.check(ip, now).is_ok());
+            guard.record_miss(ip, now);
+            assert_eq!(guard.check(ip, now).unwrap_err(), Duration::from_secs(expected));
+        }
+
+        // Misses are forgotten after a quiet hour
+        now += PRIVATE_CODE_MAX_LOCKOUT + PRIVATE_CODE_MISS_WINDOW;
+        guard.record_miss(ip, now);
+        assert!(guard.check(ip, now).is_ok());
+    }
+
+    #[test]
+    fn test_private_code_budget_is_tighter_than_reads() {
+        let guard = PrivateCodeGuard::new(3);
+        let ip: IpAddr = "203.0.113.7".parse().unwrap();
+        let now = Instant::now();
+        for _ in 0..3 {
+            assert!(guard.check(ip, now).is_ok());
+        }
+        assert!(guard.check(ip, now).is_err());
+        assert!(is_private_code_lookup(&Method::GET, "/api/orders/private/abc"));
+        assert!(!is_private_code_lookup(&Method::GET, "/api/orders/active"));
+    }
+
+    #[test]
+    fn test_expensive_routes() {
+        assert!(is_expensive(&Method::POST, "/api/trades/0xab/validate"));
+        assert!(is_expensive(&Method::POST, "/api/orders/0xab/payment-info"));
+        assert!(!is_expensive(&Method::GET, "/api/trades/0xab"));
+        assert!(!is_expensive(&Method::POST, "/api/orders/0xab/visibility"));
+    }
+}
@@ -0,0 +1,120 @@
+//! Request correlation IDs
+//!
+//! Every request gets an id: the client's `X-Request-Id` when it is sane
+//! (visible ASCII, at most 128 chars), otherwise a fresh UUID. The id is recorded
+//! on a `request` span wrapping the handler, so every log line the handler emits
+//! carries it, echoed back in the `X-Request-Id` response header, and included
+//! as `request_id` in ApiError bodies so users can quote it in support reports.
+
+use axum::{
+    extract::Request,
+    http::{HeaderName, HeaderValue},
+    middleware::Next,
+    response::Response,
+};
+use tracing::Instrument;
+
+pub const REQUEST_ID_HEADER: HeaderName = HeaderName::from_static("x-request-id");
+
+/// Longest client-supplied id we keep; anything longer is replaced
+const MAX_REQUEST_ID_LEN: usize = 128;
+
+tokio::task_local! {
+    static REQUEST_ID: String;
+}
+
+/// The id of the request being handled, if called inside one
+pub fn current() -> Option<String> {
+    REQUEST_ID.try_with(|id| id.clone()).ok()
+}
+
+/// Middleware: assign the id, run the request inside its span, echo the header
+pub async fn propagate(request: Request, next: Next) -> Response {
+    let request_id = request
+        .headers()
+        .get(&REQUEST_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|id| is_valid_request_id(id))
+        .map(str::to_string)
+        .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+
+    let span = tracing::info_span!(
+        "request",
+        request_id = %request_id,
+        method = %request.method(),
+        path = %request.uri().path(),
+    );
+
+    let mut response = REQUEST_ID
+        .scope(request_id.clone(), next.run(request).instrument(span))
+        .await;
+
+    if let Ok(value) = HeaderValue::from_str(&request_id) {
+        response.headers_mut().insert(REQUEST_ID_HEADER, value);
+    }
+    response
+}
+
+fn is_valid_request_id(id: &str) -> bool {
+    !id.is_empty() && id.len() <= MAX_REQUEST_ID_LEN && id.bytes().all(|b| b.is_ascii_graphic())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::StatusCode, middleware, routing::get, Router};
+    use tower::ServiceExt;
+
+    use crate::api::ApiError;
+
+    fn app() -> Router {
+        Router::new()
+            .route("/ok", get(|| async { "ok" }))
+            .route("/missing", get(|| async { Err::<(), _>(ApiError::NotFound("Trade not found".to_string())) }))
+            .layer(middleware::from_fn(propagate))
+    }
+
+    async fn get_with(path: &str, request_id: Option<&str>) -> Response {
+        let mut request = Request::get(path);
+        if let Some(id) = request_id {
+            request = request.header(REQUEST_ID_HEADER, id);
+        }
+        app().oneshot(request.body(Body::empty()).unwrap()).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_generates_request_id() {
+        let response = get_with("/ok", None).await;
+        let id = response.headers()[REQUEST_ID_HEADER].to_str().unwrap();
+        assert!(uuid::Uuid::parse_str(id).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_preserves_client_request_id() {
+        let response = get_with("/ok", Some("client-abc-123")).await;
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "client-abc-123");
+
+        // Unusable ids are replaced
+        let response = get_with("/ok", Some("has spaces")).await;
+        assert_ne!(response.headers()[REQUEST_ID_HEADER], "has spaces");
+    }
+
+    #[tokio::test]
+    async fn test_error_body_carries_request_id() {
+        let response = get_with("/missing", Some("support-42")).await;
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[REQUEST_ID_HEADER], "support-42");
+
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let body: serde_json::Value = serde_json::from_slice(&body).unwrap();
+        assert_eq!(body["request_id"], "support-42");
+    }
+
+    #[test]
+    fn test_is_valid_request_id() {
+        assert!(is_valid_request_id("3f1c2a9e-0d4b-4c1a-9f2e-7b6a5d4c3b2a"));
+        assert!(!is_valid_request_id(""));
+        assert!(!is_valid_request_id("a b"));
+        assert!(!is_valid_request_id(&"x".repeat(MAX_REQUEST_ID_LEN + 1)));
+    }
+}
@@ -0,0 +1,77 @@
+//! Response size guard for aggregate endpoints
+//!
+//! The debug dump and the stats endpoints aggregate over whole tables, so a
+//! busy deployment can turn one request into a multi-megabyte body. These
+//! responses are serialized against `MAX_AGGREGATE_RESPONSE_BYTES` first; once
+//! the cap is crossed serialization stops and the client gets a 413 telling it
+//! how to narrow the query instead.
+
+use axum::{
+    http::header,
+    response::{IntoResponse, Response},
+};
+use serde::Serialize;
+use std::io;
+
+use crate::api::error::{ApiError, ApiResult};
+
+/// Serialize `value` as a JSON response, or 413 if it would exceed `max_bytes`
+///
+/// `narrow_hint` is appended to the error, e.g. "pass chain_id, from and to".
+pub fn capped_json<T: Serialize>(value: &T, max_bytes: usize, narrow_hint: &str) -> ApiResult<Response> {
+    let mut writer = CappedWriter { buf: Vec::new(), max_bytes };
+    if let Err(e) = serde_json::to_writer(&mut writer, value) {
+        if e.io_error_kind() == Some(io::ErrorKind::WriteZero) {
+            tracing::warn!("📦 Aggregate response exceeded {} bytes, rejected", max_bytes);
+            return Err(ApiError::PayloadTooLarge(format!(
+                "Response exceeds the {} byte limit; narrow the query: {}",
+                max_bytes, narrow_hint
+            )));
+        }
+        return Err(ApiError::Internal(format!("Failed to serialize response: {}", e)));
+    }
+    Ok(([(header::CONTENT_TYPE, "application/json")], writer.buf).into_response())
+}
+
+/// Buffers up to `max_bytes`, then fails the write so serde stops early
+struct CappedWriter {
+    buf: Vec<u8>,
+    max_bytes: usize,
+}
+
+impl io::Write for CappedWriter {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        if self.buf.len() + data.len() > self.max_bytes {
+            return Err(io::Error::new(io::ErrorKind::WriteZero, "response size limit exceeded"));
+        }
+        self.buf.extend_from_slice(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+
+    #[test]
+    fn test_over_cap_aggregation_rejected_with_guidance() {
+        let rows: Vec<String> = (0..1000).map(|i| format!("order-{:04}", i)).collect();
+        let body = serde_json::to_vec(&rows).unwrap();
+
+        let ok = capped_json(&rows, body.len(), "pass chain_id").unwrap();
+        assert_eq!(ok.status(), StatusCode::OK);
+
+        match capped_json(&rows, body.len() - 1, "pass chain_id, from and to") {
+            Err(ApiError::PayloadTooLarge(msg)) => {
+                assert!(msg.contains(&format!("{} byte limit", body.len() - 1)), "{}", msg);
+                assert!(msg.contains("pass chain_id, from and to"), "{}", msg);
+            }
+            other => panic!("expected PayloadTooLarge, got {:?}", other.map(|r| r.status())),
+        }
+    }
+}
@@ -1,11 +1,19 @@
 use axum::{
+    extract::DefaultBodyLimit,
+    middleware,
     routing::{get, post, delete},
     Router,
 };
-use tower_http::cors::{CorsLayer, Any};
+use tower_http::cors::{AllowOrigin, CorsLayer, Any};
 
-use crate::api::{handlers, state::AppState};
+use axum::http::{header, HeaderName, HeaderValue, Method};
+
+use crate::api::{concurrency::limit_concurrency, handlers, live, openapi, rate_limit::rate_limit, request_id, state::AppState};
 use crate::auth;
+use crate::config::{ApiConfig, CorsOrigins};
+
+/// Slack on top of the PDF size limit for multipart boundaries and headers
+const MULTIPART_OVERHEAD_BYTES: usize = 64 * 1024;
 
 /// Create the API router
 /// 
@@ -13,57 +21,337 @@ use crate::auth;
 /// - GET  /api/auth/nonce              - Get SIWE nonce
 /// - POST /api/auth/verify             - Verify SIWE signature, get JWT
 /// - GET  /health                      - Health check
+/// - GET  /version                     - Crate version, git commit, build time and configured chain ids
+/// - GET  /api/openapi.json           - OpenAPI spec of the public endpoints (see api::openapi)
+/// - GET  /api/chains                 - Configured chains (id, name, escrow) and their tokens
+/// - GET  /api/chains/:id/sync         - Indexer progress per chain (last block, head, lag)
+/// - GET  /api/tokens?chain_id=        - Supported tokens (address, symbol, decimals)
+/// - GET  /api/stats?chain_id=&from=&to= - Settled volume per token, trade counts, success rate
+/// - GET  /api/market/stats?chain_id= - Active liquidity per token (public orders only)
+/// - GET  /api/sellers/:addr/stats    - Seller reputation summary (orders, settled/expired trades, completion rate)
+/// - GET  /api/sellers/:addr/withdrawals - Seller's withdrawals across all orders, with token symbol/decimals (?limit=&offset=)
 /// - GET  /api/orders/active           - List active sell orders (auth required for ?seller=)
+/// - POST /api/orders/batch            - Get up to 100 orders by ID ({ "order_ids": [..] })
 /// - GET  /api/orders/:id/activities   - Get order with activity timeline
+/// - GET  /api/orders/:id/verify       - Indexed vs on-chain remaining amount (?resync=true repairs drift, admin JWT)
+/// - GET  /api/ws/orders               - WebSocket feed of order/trade updates (filter: { "chain_id", "token" })
 /// - GET  /api/trades/:id              - Get trade by ID
+/// - GET  /api/trades/:id/receipt      - Structured settlement receipt (JWT, buyer/seller only)
+/// - GET  /api/trades/:id/events       - SSE stream of the trade's status, ends once settled/expired
+/// - GET  /api/trades/:id/gas-costs    - Relayer gas spent on the trade, per transaction + total
 /// - GET  /api/trades/buyer/:addr      - Get trades by buyer
-/// - POST /api/trades/:id/validate     - Upload PDF + quick validation (~10s)
+/// - POST /api/trades/:id/validate     - Upload PDF + quick validation (~10s; SSE progress with Accept: text/event-stream)
+/// - POST /api/trades/:id/input-streams - Regenerate + re-cache the Axiom input streams from the stored PDF (admin JWT, not during a proof)
+/// - GET  /api/trades/:id/settlement   - Settlement state (awaiting_receipt/validated/proof_in_progress/settled/failed/expired)
+/// - GET  /api/proofs/:job_id          - Status of the proof job queued by validate ({status, result, error})
+/// - GET  /api/debug/decode/:id        - Classify a bytes32 as trade/order/unknown (DEBUG_ENDPOINTS)
+/// - GET  /api/account/email/unsubscribe - Disable notifications via signed email link (no JWT)
+/// - GET  /api/account/email/confirm   - Verify a stored address via its emailed link (no JWT, single-use)
+/// - GET  /api/admin/config           - Contract config per chain (cached, ?refresh=true)
+/// - GET  /api/admin/config/cache      - Config cache state per chain (age, TTL)
+/// - POST /api/admin/config/invalidate - Drop cached contract config (?chain_id=), e.g. after a cast change
+/// - GET  /api/admin/email-outbox      - Queued notification emails (pending/delivered/failed)
+/// - GET  /api/admin/stuck-trades      - Pending trades past expiry (auto-cancel health)
+/// - GET  /api/admin/listener-status   - Event listener progress per chain (last block, head, lag, stalled)
+/// - GET  /api/admin/relayer           - Relayer address + native balance per chain (low_balance below RELAYER_LOW_BALANCE_ETH)
+/// - GET  /api/admin/market-stats      - Market stats including private orders
+/// - GET  /api/admin/gas-costs/summary - Relayer gas spend by operation, per chain + cross-chain total (?chain_id=)
+///
+/// Every /api/admin route needs an admin wallet's JWT (see `admin_routes`).
+///
+/// Validate and the order/trade reads each have their own concurrency limit
+/// (ApiConfig); requests beyond it are rejected with 503. Every route is also
+/// rate limited per client IP, and validate/payment-info per signed-in wallet
+/// (see api::rate_limit), answering 429 when exceeded. Private-code lookups get
+/// a tighter per-IP budget and lock the IP out after repeated misses.
+///
+/// Every response carries an `X-Request-Id` (the client's, or a generated UUID)
+/// that also tags the request's log lines and error bodies (see api::request_id).
 pub fn create_router(state: AppState) -> Router {
-    let cors = CorsLayer::new()
-        .allow_origin(Any)
-        .allow_methods(Any)
-        .allow_headers(Any);
+    let validate_limit = state.api_config.validate_concurrency;
+    let read_limit = state.api_config.read_concurrency;
+    let debug_enabled = state.api_config.debug_endpoints;
+    // Receipt size is enforced by the handler (413); allow room for multipart framing
+    let validate_body_limit = state.api_config.max_pdf_bytes + MULTIPART_OVERHEAD_BYTES;
 
-    Router::new()
+    let cors = cors_layer(&state.api_config);
+
+    let routes = Router::new()
         // Authentication (SIWE)
         .route("/api/auth/nonce", get(auth::get_nonce))
         .route("/api/auth/verify", post(auth::verify_siwe))
         
         // Health
         .route("/health", get(handlers::health_check))
+        .route("/version", get(handlers::get_version))
+        .route("/api/openapi.json", get(openapi::openapi_json))
+        .route("/api/chains", get(handlers::get_chains))
+        .route("/api/chains/:chain_id/sync", get(handlers::get_chain_sync_status))
+        .route("/api/tokens", get(handlers::get_tokens))
+        .route("/api/stats", limit_concurrency(get(handlers::get_stats), "stats", read_limit))
+        .route("/api/market/stats", limit_concurrency(get(handlers::get_market_stats), "market/stats", read_limit))
+        .route("/api/sellers/:address/stats", limit_concurrency(get(handlers::get_seller_stats), "sellers/stats", read_limit))
+        .route("/api/sellers/:address/withdrawals", limit_concurrency(get(handlers::get_seller_withdrawals), "sellers/withdrawals", read_limit))
         
         // Orders (read-only + visibility + payment-info)
-        .route("/api/orders/active", get(handlers::get_active_orders))
-        .route("/api/orders/private/:code", get(handlers::get_order_by_private_code))
-        .route("/api/orders/:order_id/activities", get(handlers::get_order_activities))
+        .route("/api/orders/active", limit_concurrency(get(handlers::get_active_orders), "orders/active", read_limit))
+        .route("/api/orders/batch", limit_concurrency(post(handlers::get_orders_batch), "orders/batch", read_limit))
+        .route("/api/orders/private/:code", limit_concurrency(get(handlers::get_order_by_private_code), "orders/private", read_limit))
+        .route("/api/orders/:order_id/activities", limit_concurrency(get(handlers::get_order_activities), "orders/activities", read_limit))
+        .route("/api/orders/:order_id/verify", limit_concurrency(get(handlers::verify_order_remaining), "orders/verify", read_limit))
         .route("/api/orders/:order_id/visibility", post(handlers::set_order_visibility))
         .route("/api/orders/:order_id/payment-info", post(handlers::submit_payment_info))
+        .route("/api/ws/orders", get(live::orders_ws))
         
         // Trades
         .route("/api/trades/create", post(handlers::create_trade_handler))
-        .route("/api/trades/:trade_id", get(handlers::get_trade_handler))
-        .route("/api/trades/buyer/:buyer_address", get(handlers::get_trades_by_buyer_handler))
-        .route("/api/trades/seller/:seller_address", get(handlers::get_trades_by_seller_handler))
+        .route("/api/trades/:trade_id", limit_concurrency(get(handlers::get_trade_handler), "trades/get", read_limit))
+        .route("/api/trades/:trade_id/receipt", limit_concurrency(get(handlers::get_trade_receipt), "trades/receipt", read_limit))
+        .route("/api/trades/:trade_id/events", get(handlers::get_trade_events))
+        .route("/api/trades/:trade_id/gas-costs", limit_concurrency(get(handlers::get_trade_gas_costs), "trades/gas-costs", read_limit))
+        .route("/api/trades/buyer/:buyer_address", limit_concurrency(get(handlers::get_trades_by_buyer_handler), "trades/buyer", read_limit))
+        .route("/api/trades/seller/:seller_address", limit_concurrency(get(handlers::get_trades_by_seller_handler), "trades/seller", read_limit))
         
         // Settlement
-        .route("/api/trades/:trade_id/validate", post(handlers::validate_handler))
+        .route(
+            "/api/trades/:trade_id/validate",
+            limit_concurrency(post(handlers::validate_handler), "validate", validate_limit)
+                .layer(DefaultBodyLimit::max(validate_body_limit)),
+        )
+        .route("/api/trades/:trade_id/input-streams", limit_concurrency(post(handlers::regenerate_input_streams), "trades/input-streams", read_limit))
+        .route("/api/trades/:trade_id/settlement", limit_concurrency(get(handlers::get_settlement_status), "trades/settlement", read_limit))
+        .route("/api/proofs/:job_id", limit_concurrency(get(handlers::get_proof_job), "proofs", read_limit))
         
-        // Debug endpoints (for development)
-        .route("/api/debug/database", get(handlers::debug_database))
+        // Debug endpoints (for development, disabled with DEBUG_ENDPOINTS=false)
+        .merge(debug_routes(debug_enabled))
         
-        // Admin endpoints (read-only - all write operations removed for security)
-        // Contract modifications must be done directly via cast/forge with owner wallet
-        .route("/api/admin/config", get(handlers::get_contract_config))
+        // Admin endpoints (admin JWT)
+        .merge(admin_routes(&state))
         
         // Trade file endpoints
-        .route("/api/trades/:trade_id/pdf", get(handlers::get_trade_pdf))
+        .route("/api/trades/:trade_id/pdf", limit_concurrency(get(handlers::get_trade_pdf), "trades/pdf", read_limit))
         
         // Account settings (email notifications) - account-based, not role-based
         .route("/api/account/email", post(handlers::account::set_account_email))
         .route("/api/account/email", get(handlers::account::get_account_email))
         .route("/api/account/email", delete(handlers::account::delete_account_email))
         .route("/api/account/email/toggle", post(handlers::account::toggle_account_email))
-        
+        .route("/api/account/email/digest", post(handlers::account::toggle_account_digest))
+        .route("/api/account/email/unsubscribe", get(handlers::account::unsubscribe_account_email).post(handlers::account::unsubscribe_account_email))
+        .route("/api/account/email/confirm", get(handlers::account::confirm_account_email));
+
+    rate_limit(routes, &state.api_config, state.wallet_rate_limiter.clone())
+        .layer(middleware::from_fn(request_id::propagate))
         .layer(cors)
         .with_state(state)
 }
+
+/// CORS policy from ApiConfig
+///
+/// With an origin allowlist only matching origins are reflected, and credentials
+/// (cookies, Authorization) are allowed. `*` allows any origin without credentials
+/// and is meant for development. Invalid origins and expose-header names are
+/// skipped with a warning.
+fn cors_layer(config: &ApiConfig) -> CorsLayer {
+    let expose: Vec<HeaderName> = config.cors_expose_headers.iter()
+        .filter_map(|name| match HeaderName::from_bytes(name.as_bytes()) {
+            Ok(header) => Some(header),
+            Err(_) => {
+                tracing::warn!("⚠️ Ignoring invalid CORS expose header: {:?}", name);
+                None
+            }
+        })
+        .collect();
+    
+    let cors = CorsLayer::new().expose_headers(expose);
+
+    match &config.cors_allowed_origins {
+        CorsOrigins::Any => {
+            tracing::warn!("⚠️ CORS allows any origin (CORS_ALLOWED_ORIGINS=*), do not use in production");
+            cors.allow_origin(Any)
+                .allow_methods(Any)
+                .allow_headers(Any)
+        }
+        CorsOrigins::List(origins) => {
+            let origins: Vec<HeaderValue> = origins.iter()
+                .filter_map(|origin| match parse_origin(origin) {
+                    Ok(value) => Some(value),
+                    Err(e) => {
+                        tracing::warn!("⚠️ Ignoring invalid CORS origin {:?}: {}", origin, e);
+                        None
+                    }
+                })
+                .collect();
+            if origins.is_empty() {
+                tracing::warn!("⚠️ No CORS_ALLOWED_ORIGINS configured, cross-origin requests will be refused by browsers");
+            } else {
+                let names: Vec<&str> = origins.iter().filter_map(|o| o.to_str().ok()).collect();
+                tracing::info!("🌐 CORS allowed origins (with credentials): {}", names.join(", "));
+            }
+            // Credentials rule out wildcards, so methods and headers are listed explicitly
+            cors.allow_origin(AllowOrigin::list(origins))
+                .allow_credentials(true)
+                .allow_methods([Method::GET, Method::POST, Method::DELETE, Method::OPTIONS])
+                .allow_headers([header::AUTHORIZATION, header::CONTENT_TYPE, header::ACCEPT, request_id::REQUEST_ID_HEADER])
+        }
+    }
+}
+
+/// Accept `scheme://host[:port]` only - the exact form browsers send in Origin
+fn parse_origin(origin: &str) -> Result<HeaderValue, String> {
+    let rest = origin.strip_prefix("https://")
+        .or_else(|| origin.strip_prefix("http://"))
+        .ok_or("must start with http:// or https://")?;
+    if rest.is_empty() || rest.contains(['/', '?', '#', '*']) {
+        return Err("must be scheme://host[:port] with no path or wildcard".to_string());
+    }
+    HeaderValue::from_str(origin).map_err(|e| e.to_string())
+}
+
+/// Admin routes, all behind an admin wallet's JWT (401 otherwise)
+///
+/// Read-only apart from cache management - all contract write operations were
+/// removed for security; contract changes are made via cast/forge with the owner wallet.
+fn admin_routes(state: &AppState) -> Router<AppState> {
+    Router::new()
+        .route("/api/admin/config", get(handlers::get_contract_config))
+        .route("/api/admin/config/cache", get(handlers::get_config_cache_status))
+        // Read-cache management only (no contract writes)
+        .route("/api/admin/config/invalidate", post(handlers::invalidate_config_cache))
+        .route("/api/admin/email-outbox", get(handlers::get_email_outbox_status))
+        .route("/api/admin/stuck-trades", get(handlers::get_stuck_trades))
+        .route("/api/admin/listener-status", get(handlers::get_listener_status))
+        .route("/api/admin/relayer", get(handlers::get_relayer_status))
+        .route("/api/admin/market-stats", get(handlers::get_admin_market_stats))
+        .route("/api/admin/gas-costs/summary", get(handlers::get_gas_cost_summary))
+        .route_layer(middleware::from_fn_with_state(state.clone(), handlers::admin_only))
+}
+
+/// Debug/support routes, empty when disabled
+fn debug_routes(enabled: bool) -> Router<AppState> {
+    if !enabled {
+        return Router::new();
+    }
+    Router::new()
+        .route("/api/debug/database", get(handlers::debug_database))
+        .route("/api/debug/decode/:id", get(handlers::debug_decode_id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::Body, http::{header, Request, StatusCode}};
+    use tower::ServiceExt;
+
+    fn cors_app(config: &ApiConfig) -> Router {
+        Router::new()
+            .route("/health", get(|| async { "ok" }))
+            .layer(cors_layer(config))
+    }
+
+    async fn get_from(app: Router, origin: &str) -> axum::response::Response {
+        app.oneshot(
+            Request::get("/health")
+                .header(header::ORIGIN, origin)
+                .body(Body::empty())
+                .unwrap(),
+        )
+        .await
+        .unwrap()
+    }
+
+    fn allowlist(origins: &[&str]) -> CorsOrigins {
+        CorsOrigins::List(origins.iter().map(|o| o.to_string()).collect())
+    }
+
+    #[tokio::test]
+    async fn test_cors_exposes_configured_headers() {
+        let config = ApiConfig {
+            cors_expose_headers: vec!["ETag".to_string(), "X-Total-Count".to_string(), "bad header".to_string()],
+            cors_allowed_origins: allowlist(&["https://lync-z.xyz"]),
+            ..ApiConfig::default()
+        };
+        let response = get_from(cors_app(&config), "https://lync-z.xyz").await;
+
+        let exposed = response.headers()[header::ACCESS_CONTROL_EXPOSE_HEADERS].to_str().unwrap();
+        let exposed: Vec<&str> = exposed.split(',').map(str::trim).collect();
+        assert_eq!(exposed, vec!["etag", "x-total-count"]);
+    }
+
+    #[tokio::test]
+    async fn test_cors_allowlist_reflects_only_matching_origins() {
+        let config = ApiConfig {
+            cors_allowed_origins: allowlist(&["https://lync-z.xyz", "http://localhost:3000", "https://*.evil.com"]),
+            ..ApiConfig::default()
+        };
+
+        let allowed = get_from(cors_app(&config), "http://localhost:3000").await;
+        assert_eq!(allowed.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "http://localhost:3000");
+        assert_eq!(allowed.headers()[header::ACCESS_CONTROL_ALLOW_CREDENTIALS], "true");
+
+        let refused = get_from(cors_app(&config), "https://attacker.example").await;
+        assert!(refused.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+
+        // Unset allowlist refuses every origin
+        let refused = get_from(cors_app(&ApiConfig::default()), "https://lync-z.xyz").await;
+        assert!(refused.headers().get(header::ACCESS_CONTROL_ALLOW_ORIGIN).is_none());
+    }
+
+    #[tokio::test]
+    async fn test_cors_wildcard_has_no_credentials() {
+        let config = ApiConfig { cors_allowed_origins: CorsOrigins::Any, ..ApiConfig::default() };
+        let response = get_from(cors_app(&config), "https://anything.example").await;
+        assert_eq!(response.headers()[header::ACCESS_CONTROL_ALLOW_ORIGIN], "*");
+        assert!(response.headers().get(header::ACCESS_CONTROL_ALLOW_CREDENTIALS).is_none());
+    }
+
+    #[test]
+    fn test_parse_origin() {
+        assert!(parse_origin("https://lync-z.xyz").is_ok());
+        assert!(parse_origin("http://localhost:3000").is_ok());
+        assert!(parse_origin("lync-z.xyz").is_err());
+        assert!(parse_origin("https://lync-z.xyz/app").is_err());
+        assert!(parse_origin("https://*.lync-z.xyz").is_err());
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_admin_routes_need_an_admin_token(pool: sqlx::PgPool) {
+        let state = AppState::from_database(crate::db::Database::from_pool(pool))
+            .with_api_config(ApiConfig { admin_wallets: vec!["0xadmin".to_string()], ..ApiConfig::default() });
+        let app = create_router(state);
+        let get = |path: &str, wallet: Option<&str>| {
+            let mut request = Request::get(path);
+            if let Some(wallet) = wallet {
+                request = request.header(header::AUTHORIZATION, format!("Bearer {}", crate::auth::test_token(wallet)));
+            }
+            app.clone().oneshot(request.body(Body::empty()).unwrap())
+        };
+
+        for path in [
+            "/api/admin/config",
+            "/api/admin/config/cache",
+            "/api/admin/email-outbox",
+            "/api/admin/stuck-trades",
+            "/api/admin/listener-status",
+            "/api/admin/relayer",
+            "/api/admin/market-stats",
+            "/api/admin/gas-costs/summary",
+        ] {
+            assert_eq!(get(path, None).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{}", path);
+            assert_eq!(get(path, Some("0xseller")).await.unwrap().status(), StatusCode::UNAUTHORIZED, "{}", path);
+        }
+        assert_eq!(get("/api/admin/email-outbox", Some("0xadmin")).await.unwrap().status(), StatusCode::OK);
+        // Unknown admin paths are still a 404
+        assert_eq!(get("/api/admin/nope", None).await.unwrap().status(), StatusCode::NOT_FOUND);
+    }
+
+    #[test]
+    fn test_default_exposed_headers() {
+        let config = ApiConfig::default();
+        for name in ["ETag", "X-Total-Count", "X-Request-Id", "Retry-After"] {
+            assert!(config.cors_expose_headers.iter().any(|h| h == name));
+        }
+    }
+}
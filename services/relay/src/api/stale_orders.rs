@@ -0,0 +1,63 @@
+//! Stale order delisting
+//!
+//! Orders with funds left stay in the public book until the seller withdraws,
+//! so abandoned ones linger for months. When ORDER_DELIST_AFTER_DAYS is set,
+//! this job periodically delists open public orders that have seen no trade
+//! or withdrawal in that many days (see migration 016). Delisting is
+//! off-chain only: the escrow is untouched, the order still resolves by id
+//! and private code, and any new on-chain activity or visibility change
+//! lists it again. Off by default.
+
+use std::time::Duration;
+
+use crate::api::live::LiveEventKind;
+use crate::api::state::AppState;
+
+/// How often stale orders are looked for (1 hour)
+const DELIST_INTERVAL_SECS: u64 = 3600;
+
+/// Unix time before which an order must have gone quiet to be delisted
+pub fn delist_cutoff(now: i64, max_age_days: u64) -> i64 {
+    let max_age_secs = i64::try_from(max_age_days).unwrap_or(i64::MAX).saturating_mul(86_400);
+    now.saturating_sub(max_age_secs)
+}
+
+/// Start the delisting job if ORDER_DELIST_AFTER_DAYS is set (called once from api-server)
+pub fn spawn(state: AppState) {
+    if let Some(days) = state.api_config.order_delist_after_days {
+        tokio::spawn(run(state, days));
+    }
+}
+
+async fn run(state: AppState, max_age_days: u64) {
+    tracing::info!("🧹 Stale order delisting started (orders idle {}+ days)", max_age_days);
+
+    let mut ticker = tokio::time::interval(Duration::from_secs(DELIST_INTERVAL_SECS));
+    loop {
+        ticker.tick().await;
+
+        let cutoff = delist_cutoff(chrono::Utc::now().timestamp(), max_age_days);
+        match state.db.delist_stale_orders(cutoff).await {
+            Ok(orders) if orders.is_empty() => {}
+            Ok(orders) => {
+                for order in &orders {
+                    state.live_feed.publish(LiveEventKind::OrderHidden, order, None);
+                }
+                tracing::info!("🧹 Delisted {} order(s) idle for {}+ days", orders.len(), max_age_days);
+            }
+            Err(e) => tracing::warn!("🧹 Failed to delist stale orders: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cutoff_is_max_age_before_now() {
+        assert_eq!(delist_cutoff(1_700_000_000, 30), 1_700_000_000 - 30 * 86_400);
+        assert_eq!(delist_cutoff(1_700_000_000, 0), 1_700_000_000);
+        assert!(delist_cutoff(1_700_000_000, u64::MAX) < 0);
+    }
+}
@@ -1,16 +1,284 @@
 use std::sync::Arc;
-use std::collections::{HashMap, HashSet};
+use std::collections::HashMap;
+use std::future::Future;
 use std::time::{Duration, Instant};
-use tokio::sync::RwLock;
-use crate::db::Database;
-use crate::blockchain::client::EthereumClient;
+use tokio::sync::{watch, RwLock};
+use async_trait::async_trait;
+use ethers::types::Address;
+use crate::db::input_streams::InputStreamRepository;
+use crate::db::{Database, DbResult};
+use crate::blockchain::chain_clients::{ChainClientError, ChainClients};
+use crate::blockchain::client::{EthereumClient, EthereumClientError};
+use crate::blockchain::ens::{self, EnsResolver};
+use crate::blockchain::events::FeeRateSource;
+use crate::blockchain::listener_status::ListenerProgress;
+use crate::blockchain::relayer_balance::LowBalanceThresholds;
 use crate::blockchain::types::ContractConfig;
 use crate::auth::NonceStore;
+use crate::config::{ApiConfig, ChainConfig, DbPoolConfig, DEFAULT_RATE_LIMIT_WALLET_PER_MIN};
+use crate::email::EmailService;
+use crate::price_oracle::PriceOracle;
+use crate::storage::PdfStorage;
+use crate::webhooks::WebhookSender;
+use crate::api::error::{ApiError, ApiResult};
+use crate::api::types::{ChainsResponse, ConfigCacheStatus};
+use crate::api::live::LiveFeed;
+use crate::api::rate_limit::WalletRateLimiter;
 
 /// Cache entry with expiration
 pub struct CachedConfig {
     pub config: ContractConfig,
     pub cached_at: Instant,
+    /// A background refresh of this (stale) entry is in flight
+    pub refreshing: bool,
+}
+
+type ConfigCache = Arc<RwLock<HashMap<u64, CachedConfig>>>;
+
+impl CachedConfig {
+    /// Whether this entry is still within the config cache TTL
+    pub fn is_fresh(&self) -> bool {
+        self.cached_at.elapsed() < AppState::CONFIG_CACHE_TTL
+    }
+
+    /// Whether this entry may still be served while a refresh runs
+    pub fn is_servable(&self) -> bool {
+        self.cached_at.elapsed() < AppState::CONFIG_CACHE_MAX_STALE
+    }
+}
+
+/// (requested order id, computed hash) -> (verified order id, verified at)
+type VerifiedHashEntries = HashMap<(String, String), (String, Instant)>;
+
+/// Account-lines hashes already verified on-chain by payment-info.
+/// Key: (requested order id, computed hash) -> order id the hash was verified for
+/// (differs from the requested id when resolved through the tx_hash fallback).
+#[derive(Clone)]
+pub struct VerifiedHashCache {
+    entries: Arc<std::sync::Mutex<VerifiedHashEntries>>,
+    ttl: Duration,
+}
+
+impl VerifiedHashCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            ttl,
+        }
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, HashMap<(String, String), (String, Instant)>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Verified order id for this submission, if checked within the TTL
+    pub fn get(&self, order_id: &str, computed_hash: &str) -> Option<String> {
+        let mut entries = self.lock();
+        let key = (order_id.to_lowercase(), computed_hash.to_lowercase());
+        match entries.get(&key) {
+            Some((verified_id, at)) if at.elapsed() < self.ttl => Some(verified_id.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+    
+    pub fn insert(&self, order_id: &str, computed_hash: &str, verified_order_id: &str) {
+        let mut entries = self.lock();
+        entries.retain(|_, (_, at)| at.elapsed() < self.ttl);
+        entries.insert(
+            (order_id.to_lowercase(), computed_hash.to_lowercase()),
+            (verified_order_id.to_string(), Instant::now()),
+        );
+    }
+    
+    /// Drop every entry requested as, or resolved to, `order_id`
+    pub fn invalidate_order(&self, order_id: &str) {
+        let order_id = order_id.to_lowercase();
+        self.lock().retain(|(requested, _), (verified, _)| {
+            *requested != order_id && verified.to_lowercase() != order_id
+        });
+    }
+}
+
+/// lowercase address -> (primary ENS name, expires at)
+type EnsEntries = HashMap<String, (Option<String>, Instant)>;
+
+/// Reverse ENS resolutions for `include_ens` listings. Addresses without a
+/// name are cached too, so they aren't looked up again on every page; failed
+/// lookups are cached as nameless for the shorter `failure_ttl`, so an RPC
+/// outage doesn't turn every listing into a round of timeouts.
+#[derive(Clone)]
+pub struct EnsCache {
+    entries: Arc<std::sync::Mutex<EnsEntries>>,
+    ttl: Duration,
+    failure_ttl: Duration,
+}
+
+impl EnsCache {
+    pub fn new(ttl: Duration, failure_ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            ttl,
+            failure_ttl,
+        }
+    }
+    
+    fn lock(&self) -> std::sync::MutexGuard<'_, EnsEntries> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Cached resolution for `address` (Some(None) = known to have no name)
+    pub fn get(&self, address: &str) -> Option<Option<String>> {
+        let mut entries = self.lock();
+        let key = address.to_lowercase();
+        match entries.get(&key) {
+            Some((name, expires_at)) if Instant::now() < *expires_at => Some(name.clone()),
+            Some(_) => {
+                entries.remove(&key);
+                None
+            }
+            None => None,
+        }
+    }
+    
+    pub fn insert(&self, address: &str, name: Option<String>) {
+        self.insert_for(address, name, self.ttl);
+    }
+    
+    fn insert_for(&self, address: &str, name: Option<String>, ttl: Duration) {
+        let mut entries = self.lock();
+        let now = Instant::now();
+        entries.retain(|_, (_, expires_at)| now < *expires_at);
+        entries.insert(address.to_lowercase(), (name, now + ttl));
+    }
+    
+    /// Cached name for `address`, or the result of `lookup` (a failure is
+    /// cached as no name for `failure_ttl`)
+    pub async fn resolve<F, Fut, E>(&self, address: &str, lookup: F) -> Option<String>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<Option<String>, E>>,
+        E: std::fmt::Display,
+    {
+        if let Some(name) = self.get(address) {
+            return name;
+        }
+        match lookup().await {
+            Ok(name) => {
+                self.insert(address, name.clone());
+                name
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ ENS lookup failed for {}: {}", address, e);
+                self.insert_for(address, None, self.failure_ttl);
+                None
+            }
+        }
+    }
+}
+
+/// trade_id -> (input streams, cached at)
+type InputStreamEntries = HashMap<String, (Vec<String>, Instant)>;
+
+/// Durable backing store for input streams (Postgres in production)
+#[async_trait]
+pub trait InputStreamStore: Send + Sync {
+    async fn put(&self, trade_id: &str, streams: &[String], ttl: Duration) -> DbResult<()>;
+    async fn get(&self, trade_id: &str) -> DbResult<Option<Vec<String>>>;
+    async fn delete(&self, trade_id: &str) -> DbResult<()>;
+}
+
+#[async_trait]
+impl InputStreamStore for InputStreamRepository {
+    async fn put(&self, trade_id: &str, streams: &[String], ttl: Duration) -> DbResult<()> {
+        InputStreamRepository::put(self, trade_id, streams, ttl).await
+    }
+
+    async fn get(&self, trade_id: &str) -> DbResult<Option<Vec<String>>> {
+        InputStreamRepository::get(self, trade_id).await
+    }
+
+    async fn delete(&self, trade_id: &str) -> DbResult<()> {
+        InputStreamRepository::delete(self, trade_id).await
+    }
+}
+
+/// Axiom input streams per trade (hex strings, see
+/// `settlement::generate_openvm_streams`), kept between validation and
+/// proof generation so they aren't rebuilt from the PDF. Reads hit memory
+/// first, then the store, so streams survive an API restart; the store is
+/// written through on insert. Store errors are logged: memory still works.
+#[derive(Clone)]
+pub struct InputStreamsCache {
+    entries: Arc<RwLock<InputStreamEntries>>,
+    store: Arc<dyn InputStreamStore>,
+    ttl: Duration,
+}
+
+impl InputStreamsCache {
+    pub fn new(store: Arc<dyn InputStreamStore>, ttl: Duration) -> Self {
+        Self {
+            entries: Arc::new(RwLock::new(HashMap::new())),
+            store,
+            ttl,
+        }
+    }
+
+    pub async fn insert(&self, trade_id: &str, streams: Vec<String>) {
+        if let Err(e) = self.store.put(trade_id, &streams, self.ttl).await {
+            tracing::warn!("⚠️ Failed to persist input streams for trade {} (kept in memory only): {}", trade_id, e);
+        }
+        let mut entries = self.entries.write().await;
+        entries.retain(|_, (_, at)| at.elapsed() < self.ttl);
+        entries.insert(trade_id.to_string(), (streams, Instant::now()));
+    }
+
+    /// Streams for a trade from memory, or from the store after a restart
+    pub async fn get(&self, trade_id: &str) -> Option<Vec<String>> {
+        if let Some((streams, at)) = self.entries.read().await.get(trade_id) {
+            if at.elapsed() < self.ttl {
+                return Some(streams.clone());
+            }
+        }
+
+        match self.store.get(trade_id).await {
+            Ok(Some(streams)) => {
+                tracing::info!("📦 Input streams for trade {} recovered from the database", trade_id);
+                self.entries.write().await.insert(trade_id.to_string(), (streams.clone(), Instant::now()));
+                Some(streams)
+            }
+            Ok(None) => None,
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to load input streams for trade {}: {}", trade_id, e);
+                None
+            }
+        }
+    }
+
+    /// Forget a trade's streams (settled, or validation failed)
+    pub async fn remove(&self, trade_id: &str) {
+        self.entries.write().await.remove(trade_id);
+        if let Err(e) = self.store.delete(trade_id).await {
+            tracing::warn!("⚠️ Failed to delete persisted input streams for trade {}: {}", trade_id, e);
+        }
+    }
+}
+
+impl ConfigCacheStatus {
+    /// Describe the cache entry (if any) for a chain
+    pub fn from_entry(chain_id: u64, entry: Option<&CachedConfig>) -> Self {
+        Self {
+            chain_id,
+            cached: entry.is_some(),
+            age_secs: entry.map(|e| e.cached_at.elapsed().as_secs()),
+            within_ttl: entry.map(|e| e.is_fresh()).unwrap_or(false),
+            refreshing: entry.map(|e| e.refreshing).unwrap_or(false),
+            ttl_secs: AppState::CONFIG_CACHE_TTL.as_secs(),
+        }
+    }
 }
 
 /// Shared application state
@@ -20,88 +288,847 @@ pub struct AppState {
     /// Database connection for persistence and queries
     pub db: Arc<Database>,
     
-    /// Multi-chain blockchain clients: chain_id -> EthereumClient
-    pub blockchain_clients: Arc<HashMap<u64, Arc<EthereumClient>>>,
+    /// Multi-chain blockchain clients: chain_id -> EthereumClient, or why it's unavailable
+    pub blockchain_clients: ChainClients,
     
-    /// In-memory cache for input streams (trade_id -> 46 hex strings)
+    /// Chain ids from Config.chains (accepted in chain_id params, even without a client)
+    pub supported_chains: Arc<Vec<u64>>,
+    
+    /// GET /api/chains body, built from Config.chains and the token registry
+    pub chains_response: Arc<ChainsResponse>,
+    
+    /// Input streams per trade (trade_id -> 46 hex strings), in memory and in the DB
     /// Used to avoid regenerating input streams between validation and proof generation
-    pub input_streams_cache: Arc<RwLock<HashMap<String, Vec<String>>>>,
+    pub input_streams_cache: InputStreamsCache,
     
     /// Cache for contract config per chain: chain_id -> CachedConfig
-    pub config_cache: Arc<RwLock<HashMap<u64, CachedConfig>>>,
+    pub config_cache: ConfigCache,
+    
+    /// Payment-info hashes verified on-chain recently (skips repeat RPC checks)
+    pub verified_hashes: VerifiedHashCache,
+    
+    /// Reverse ENS names for listing addresses (resolved on Ethereum mainnet)
+    pub ens_cache: EnsCache,
     
-    /// Set of trade IDs currently generating proofs (prevents duplicate requests)
-    pub proof_in_progress: Arc<RwLock<HashSet<String>>>,
+    /// Mainnet resolver behind `ens_cache` (None = ENS_RPC_URL unset and no
+    /// mainnet chain; listings carry no names)
+    pub ens_resolver: Option<Arc<EnsResolver>>,
+    
+    /// Trade IDs currently generating proofs, with start time (prevents duplicate
+    /// requests; held through `ProofGuard`, stale entries swept)
+    pub proof_in_progress: Arc<RwLock<HashMap<String, Instant>>>,
     
     /// Nonce store for SIWE authentication
     pub nonce_store: NonceStore,
+    
+    /// API behaviour settings (from Config)
+    pub api_config: Arc<ApiConfig>,
+    
+    /// Per-wallet validate/payment-info buckets (sized from api_config)
+    pub wallet_rate_limiter: WalletRateLimiter,
+    
+    /// Outbound trade webhooks (None if WEBHOOK_URLS/WEBHOOK_SECRET not set)
+    pub webhook_sender: Option<Arc<WebhookSender>>,
+    
+    /// Reference prices for flagging outlier exchange rates (None unless PRICE_ORACLE_ENABLED)
+    pub price_oracle: Option<Arc<PriceOracle>>,
+    
+    /// Resend client, for the /health email check (None if RESEND_API_KEY not set)
+    pub email_service: Option<Arc<EmailService>>,
+    
+    /// Receipt PDF bucket (None = PDFs stored in trades.pdf_file)
+    pub pdf_storage: Option<PdfStorage>,
+    
+    /// Live orderbook events for /api/ws/orders subscribers
+    pub live_feed: LiveFeed,
+    
+    /// Event listener progress per chain, reported by the listeners
+    pub listener_progress: ListenerProgress,
+    
+    /// Relayer low-balance threshold per chain (GET /api/admin/relayer)
+    pub relayer_balance_thresholds: Arc<LowBalanceThresholds>,
+    
+    /// Process start time (uptime in /health)
+    pub started_at: tokio::time::Instant,
+    
+    /// Flips when the server starts shutting down, ending open SSE streams
+    /// (never flips unless set through `with_shutdown`)
+    pub shutdown: watch::Receiver<bool>,
 }
 
 impl AppState {
     /// Config cache TTL (15 minutes)
     pub const CONFIG_CACHE_TTL: Duration = Duration::from_secs(900);
+    
+    /// Oldest cached config served while a background refresh runs (1 hour);
+    /// past this, requests wait on the RPC like an empty cache
+    pub const CONFIG_CACHE_MAX_STALE: Duration = Duration::from_secs(3600);
+    
+    /// How long a verified payment-info hash is trusted without re-checking the chain
+    pub const VERIFIED_HASH_TTL: Duration = Duration::from_secs(300);
+    
+    /// How often stale proof_in_progress entries are swept
+    pub const PROOF_SWEEP_INTERVAL: Duration = Duration::from_secs(60);
+    
+    /// How long validated input streams are kept for proof generation (24 hours)
+    pub const INPUT_STREAMS_TTL: Duration = Duration::from_secs(86_400);
+    
+    /// How long a reverse ENS resolution (or its absence) is reused (1 hour)
+    pub const ENS_CACHE_TTL: Duration = Duration::from_secs(3_600);
+    
+    /// How long an address whose lookup failed is shown without a name (1 minute)
+    pub const ENS_FAILURE_TTL: Duration = Duration::from_secs(60);
 }
 
 impl AppState {
     /// Create new app state
-    pub async fn new(database_url: &str) -> Result<Self, Box<dyn std::error::Error>> {
-        // Connect to database
-        let db = Database::new(database_url).await?;
+    pub async fn new(database_url: &str, replica_url: Option<&str>, pool_config: &DbPoolConfig) -> Result<Self, Box<dyn std::error::Error>> {
+        // Connect to database (and read replica, if configured)
+        let db = Database::new(database_url, replica_url, pool_config).await?;
         
         // Run migrations
         db.migrate().await?;
         
         tracing::info!("App state initialized (DB-based orderbook with direct queries)");
         
-        Ok(Self {
+        Ok(Self::from_database(db))
+    }
+    
+    /// App state over an already connected (and migrated) database
+    pub fn from_database(db: Database) -> Self {
+        let input_stream_store = Arc::new(InputStreamRepository::new(db.pool().clone()));
+        
+        Self {
             db: Arc::new(db),
-            blockchain_clients: Arc::new(HashMap::new()),
-            input_streams_cache: Arc::new(RwLock::new(HashMap::new())),
+            blockchain_clients: ChainClients::default(),
+            supported_chains: Arc::new(Vec::new()),
+            chains_response: Arc::new(ChainsResponse::default()),
+            input_streams_cache: InputStreamsCache::new(input_stream_store, Self::INPUT_STREAMS_TTL),
             config_cache: Arc::new(RwLock::new(HashMap::new())),
-            proof_in_progress: Arc::new(RwLock::new(HashSet::new())),
-            nonce_store: NonceStore::new(),
-        })
+            verified_hashes: VerifiedHashCache::new(Self::VERIFIED_HASH_TTL),
+            ens_cache: EnsCache::new(Self::ENS_CACHE_TTL, Self::ENS_FAILURE_TTL),
+            ens_resolver: None,
+            proof_in_progress: Arc::new(RwLock::new(HashMap::new())),
+            nonce_store: NonceStore::from_env(),
+            api_config: Arc::new(ApiConfig::default()),
+            wallet_rate_limiter: WalletRateLimiter::new(DEFAULT_RATE_LIMIT_WALLET_PER_MIN),
+            webhook_sender: WebhookSender::from_env(),
+            price_oracle: PriceOracle::from_env(),
+            email_service: EmailService::from_env(),
+            pdf_storage: PdfStorage::from_env(),
+            live_feed: LiveFeed::default(),
+            listener_progress: ListenerProgress::default(),
+            relayer_balance_thresholds: Arc::new(LowBalanceThresholds::default()),
+            started_at: tokio::time::Instant::now(),
+            shutdown: watch::channel(false).1,
+        }
     }
     
     /// Set multi-chain blockchain clients
-    pub fn with_blockchain_clients(mut self, clients: HashMap<u64, Arc<EthereumClient>>) -> Self {
-        self.blockchain_clients = Arc::new(clients);
+    pub fn with_blockchain_clients(mut self, clients: ChainClients) -> Self {
+        self.blockchain_clients = clients;
+        self
+    }
+    
+    /// Set the configured chains
+    pub fn with_chains(mut self, chains: &[ChainConfig]) -> Self {
+        self.supported_chains = Arc::new(chains.iter().map(|c| c.chain_id).collect());
+        self.chains_response = Arc::new(ChainsResponse::from_config(chains));
+        self.relayer_balance_thresholds = Arc::new(LowBalanceThresholds::from_env(chains));
         self
     }
     
-    /// Get blockchain client for a specific chain ID
-    pub fn get_blockchain_client(&self, chain_id: u64) -> Option<&Arc<EthereumClient>> {
-        self.blockchain_clients.get(&chain_id)
+    /// Resolve ENS names over `rpc_urls` (Config.ens_rpc_url); a bad URL is
+    /// logged and leaves names off rather than failing startup
+    pub fn with_ens_rpc(mut self, rpc_urls: Option<&str>) -> Self {
+        self.ens_resolver = rpc_urls.and_then(|urls| match EnsResolver::connect(urls) {
+            Ok(resolver) => Some(Arc::new(resolver)),
+            Err(e) => {
+                tracing::warn!("⚠️ ENS disabled: {}", e);
+                None
+            }
+        });
+        self
+    }
+    
+    /// Set the server shutdown signal
+    pub fn with_shutdown(mut self, shutdown: watch::Receiver<bool>) -> Self {
+        self.shutdown = shutdown;
+        self
+    }
+    
+    /// Set API behaviour settings
+    pub fn with_api_config(mut self, api_config: ApiConfig) -> Self {
+        self.wallet_rate_limiter = WalletRateLimiter::new(api_config.rate_limit_wallet_per_min);
+        self.api_config = Arc::new(api_config);
+        self
+    }
+    
+    /// Whether `chain_id` is one of the configured chains
+    pub fn is_supported_chain(&self, chain_id: u64) -> bool {
+        self.supported_chains.contains(&chain_id)
+    }
+    
+    /// Reject an unconfigured chain_id up front with a 400 listing the supported chains
+    pub fn require_supported_chain(&self, chain_id: u64) -> ApiResult<()> {
+        check_supported_chain(&self.supported_chains, chain_id)
+    }
+    
+    /// Get blockchain client for a specific chain ID (Unavailable while a chain
+    /// that failed at startup is still reconnecting)
+    pub fn get_blockchain_client(&self, chain_id: u64) -> Result<Arc<EthereumClient>, ChainClientError> {
+        self.blockchain_clients.get(chain_id)
+    }
+    
+    /// Configured name of `chain_id` ("Unknown" for chains not in Config.chains)
+    pub fn chain_name(&self, chain_id: u64) -> &str {
+        self.chains_response.chains.iter()
+            .find(|chain| chain.chain_id == chain_id)
+            .map_or("Unknown", |chain| chain.name.as_str())
+    }
+    
+    /// Primary ENS names of `addresses`: lowercase address -> name, for those
+    /// that have one. Cached addresses are answered inline; at most
+    /// `ens::MAX_CONCURRENT_LOOKUPS` of the rest are looked up at a time.
+    pub async fn resolve_ens_names<'a>(&self, addresses: impl IntoIterator<Item = &'a str>) -> HashMap<String, String> {
+        let mut names = HashMap::new();
+        let Some(resolver) = self.ens_resolver.clone() else {
+            return names;
+        };
+        
+        let mut unique: Vec<String> = addresses.into_iter().map(str::to_lowercase).collect();
+        unique.sort();
+        unique.dedup();
+        
+        let mut lookups = tokio::task::JoinSet::new();
+        let collect = |joined: Option<Result<(String, Option<String>), tokio::task::JoinError>>, names: &mut HashMap<String, String>| {
+            if let Some(Ok((address, Some(name)))) = joined {
+                names.insert(address, name);
+            }
+        };
+        for address in unique {
+            match self.ens_cache.get(&address) {
+                Some(Some(name)) => {
+                    names.insert(address, name);
+                    continue;
+                }
+                Some(None) => continue,
+                None => {}
+            }
+            let Ok(parsed) = address.parse::<Address>() else {
+                continue;
+            };
+            if lookups.len() >= ens::MAX_CONCURRENT_LOOKUPS {
+                collect(lookups.join_next().await, &mut names);
+            }
+            let cache = self.ens_cache.clone();
+            let resolver = resolver.clone();
+            lookups.spawn(async move {
+                let name = cache.resolve(&address, || resolver.resolve(parsed)).await;
+                (address, name)
+            });
+        }
+        while let Some(joined) = lookups.join_next().await {
+            collect(Some(joined), &mut names);
+        }
+        names
     }
     
     /// Get cached config for a specific chain
-    pub async fn get_config_for_chain(&self, chain_id: u64, force_refresh: bool) -> Result<ContractConfig, String> {
-        let blockchain_client = self.get_blockchain_client(chain_id)
-            .ok_or_else(|| format!("No blockchain client for chain {}", chain_id))?;
+    ///
+    /// Past the TTL the stale config is still returned immediately while one
+    /// background fetch refreshes it, for up to CONFIG_CACHE_MAX_STALE; an empty
+    /// cache, a config older than that, or `force_refresh` waits on the RPC.
+    pub async fn get_config_for_chain(&self, chain_id: u64, force_refresh: bool) -> Result<ContractConfig, EthereumClientError> {
+        let blockchain_client = self.get_blockchain_client(chain_id)?;
+        let fetch = move || fetch_contract_config(blockchain_client, chain_id);
+        cached_config(&self.config_cache, chain_id, force_refresh, fetch).await
+    }
+    
+    /// Fetch every connected chain's contract config into the cache at once, so
+    /// the first requests after a deploy don't each wait on the RPC. Failures
+    /// are only logged; those chains are fetched on first use as usual.
+    pub async fn warm_config_cache(&self) {
+        let started = Instant::now();
+        let fetches = self.blockchain_clients.ready()
+            .into_iter()
+            .map(|(chain_id, client)| (chain_id, fetch_contract_config(client, chain_id)));
+        let warmed = warm_configs(&self.config_cache, fetches).await;
+        tracing::info!("🔥 Contract config cached for {} chain(s) {:?} in {:?}", warmed.len(), warmed, started.elapsed());
+    }
+    
+    /// Drop cached config for one chain (or every chain) so the next read fetches
+    /// fresh; returns the chains that had an entry
+    pub async fn invalidate_config_cache(&self, chain_id: Option<u64>) -> Vec<u64> {
+        remove_cached_configs(&mut *self.config_cache.write().await, chain_id)
+    }
+    
+    /// Snapshot of the config cache for every configured chain (read-only, never fetches)
+    pub async fn config_cache_status(&self) -> Vec<ConfigCacheStatus> {
+        let cache = self.config_cache.read().await;
+        let mut chain_ids: Vec<u64> = self.blockchain_clients.chain_ids()
+            .into_iter()
+            .chain(cache.keys().copied())
+            .collect();
+        chain_ids.sort_unstable();
+        chain_ids.dedup();
         
-        // Check cache first (unless force refresh)
-        if !force_refresh {
-            let cache = self.config_cache.read().await;
-            if let Some(cached) = cache.get(&chain_id) {
-                if cached.cached_at.elapsed() < Self::CONFIG_CACHE_TTL {
+        chain_ids.into_iter()
+            .map(|chain_id| ConfigCacheStatus::from_entry(chain_id, cache.get(&chain_id)))
+            .collect()
+    }
+    
+    /// Periodically drop proof_in_progress entries older than the max proof duration.
+    /// Settlement always removes its own entry; this only catches ones left behind
+    /// by a task that died before cleanup, which would otherwise block retries forever.
+    pub fn spawn_proof_sweeper(&self) {
+        let proof_in_progress = self.proof_in_progress.clone();
+        let max_age = Duration::from_secs(self.api_config.max_proof_duration_secs);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(Self::PROOF_SWEEP_INTERVAL);
+            loop {
+                interval.tick().await;
+                let mut in_progress = proof_in_progress.write().await;
+                for trade_id in sweep_stale_proofs(&mut in_progress, max_age) {
+                    tracing::warn!("🧹 Evicted stale proof_in_progress entry for trade {} (older than {:?})", trade_id, max_age);
+                }
+            }
+        });
+    }
+}
+
+fn check_supported_chain(supported: &[u64], chain_id: u64) -> ApiResult<()> {
+    if supported.contains(&chain_id) {
+        return Ok(());
+    }
+    let list: Vec<String> = supported.iter().map(|id| id.to_string()).collect();
+    Err(ApiError::BadRequest(format!(
+        "unsupported chain_id {} (supported: {})",
+        chain_id,
+        list.join(", ")
+    )))
+}
+
+/// Read `chain_id`'s contract config from the escrow, rejecting malformed ones
+async fn fetch_contract_config(client: Arc<EthereumClient>, chain_id: u64) -> Result<ContractConfig, EthereumClientError> {
+    let config = client.get_contract_config().await?;
+    config.validate().map_err(|e| {
+        tracing::error!("❌ Rejected contract config for chain {}: {}", chain_id, e);
+        EthereumClientError::ContractError(format!("Invalid contract config for chain {}: {}", chain_id, e))
+    })?;
+    Ok(config)
+}
+
+/// Run every chain's fetch concurrently through the cache; returns the chains
+/// now cached (sorted)
+async fn warm_configs<Fut>(cache: &ConfigCache, fetches: impl IntoIterator<Item = (u64, Fut)>) -> Vec<u64>
+where
+    Fut: Future<Output = Result<ContractConfig, EthereumClientError>> + Send + 'static,
+{
+    let mut tasks = tokio::task::JoinSet::new();
+    for (chain_id, fetch) in fetches {
+        let cache = cache.clone();
+        tasks.spawn(async move { (chain_id, cached_config(&cache, chain_id, false, move || fetch).await) });
+    }
+    let mut warmed = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        match joined {
+            Ok((chain_id, Ok(_))) => warmed.push(chain_id),
+            Ok((chain_id, Err(e))) => tracing::warn!("⚠️ Config warm-up failed for chain {} (fetched on first use instead): {}", chain_id, e),
+            Err(e) => tracing::warn!("⚠️ Config warm-up task failed: {}", e),
+        }
+    }
+    warmed.sort_unstable();
+    warmed
+}
+
+/// Config cache lookup with stale-while-revalidate (see `AppState::get_config_for_chain`)
+async fn cached_config<F, Fut>(
+    cache: &ConfigCache,
+    chain_id: u64,
+    force_refresh: bool,
+    fetch: F,
+) -> Result<ContractConfig, EthereumClientError>
+where
+    F: FnOnce() -> Fut,
+    Fut: Future<Output = Result<ContractConfig, EthereumClientError>> + Send + 'static,
+{
+    if !force_refresh {
+        {
+            let entries = cache.read().await;
+            match entries.get(&chain_id) {
+                Some(cached) if cached.is_fresh() => {
                     tracing::debug!("Returning cached config for chain {} (age: {:?})", chain_id, cached.cached_at.elapsed());
                     return Ok(cached.config.clone());
                 }
+                Some(cached) if cached.refreshing && cached.is_servable() => return Ok(cached.config.clone()),
+                _ => {}
             }
         }
         
-        // Fetch fresh from blockchain
-        tracing::info!("Fetching fresh contract config from chain {}", chain_id);
-        let config = blockchain_client.get_contract_config().await
-            .map_err(|e| format!("Failed to get contract config for chain {}: {}", chain_id, e))?;
-        
-        // Update cache
-        let mut cache = self.config_cache.write().await;
-        cache.insert(chain_id, CachedConfig {
-            config: config.clone(),
-            cached_at: Instant::now(),
+        // Stale: claim the refresh under the write lock so concurrent requests don't stampede
+        let mut entries = cache.write().await;
+        if let Some(cached) = entries.get_mut(&chain_id).filter(|c| c.is_servable()) {
+            let stale = cached.config.clone();
+            if !cached.refreshing && !cached.is_fresh() {
+                cached.refreshing = true;
+                tracing::info!("Refreshing stale contract config for chain {} in background (age: {:?})", chain_id, cached.cached_at.elapsed());
+                let cache = cache.clone();
+                let refresh = fetch();
+                tokio::spawn(async move {
+                    // Clears `refreshing` however this task ends, even if the fetch panics
+                    let _guard = RefreshGuard { cache: cache.clone(), chain_id };
+                    let result = refresh.await;
+                    let mut entries = cache.write().await;
+                    match result {
+                        // Skipped if the entry was invalidated meanwhile: the fetch may predate the change
+                        Ok(config) => {
+                            if let Some(cached) = entries.get_mut(&chain_id).filter(|c| c.refreshing) {
+                                *cached = CachedConfig { config, cached_at: Instant::now(), refreshing: false };
+                            }
+                        }
+                        Err(e) => {
+                            // Keep serving the stale config; the next request retries
+                            tracing::warn!("⚠️ Background config refresh failed: {}", e);
+                        }
+                    }
+                });
+            }
+            return Ok(stale);
+        }
+    }
+    
+    // Empty cache (or forced): fetch synchronously
+    tracing::info!("Fetching fresh contract config from chain {}", chain_id);
+    let config = fetch().await?;
+    cache.write().await.insert(chain_id, CachedConfig {
+        config: config.clone(),
+        cached_at: Instant::now(),
+        refreshing: false,
+    });
+    Ok(config)
+}
+
+/// Background refresh claim on a config cache entry; dropping it (on success,
+/// error or panic) clears the entry's `refreshing` flag so a later request can
+/// retry the refresh.
+struct RefreshGuard {
+    cache: ConfigCache,
+    chain_id: u64,
+}
+
+impl RefreshGuard {
+    fn release(entries: &mut HashMap<u64, CachedConfig>, chain_id: u64) {
+        if let Some(cached) = entries.get_mut(&chain_id) {
+            cached.refreshing = false;
+        }
+    }
+}
+
+impl Drop for RefreshGuard {
+    fn drop(&mut self) {
+        // Drop can't await the lock; release inline if it's free, otherwise on a task
+        if let Ok(mut entries) = self.cache.try_write() {
+            Self::release(&mut entries, self.chain_id);
+            return;
+        }
+        let (cache, chain_id) = (self.cache.clone(), self.chain_id);
+        tokio::spawn(async move {
+            Self::release(&mut *cache.write().await, chain_id);
         });
-        
-        Ok(config)
+    }
+}
+
+/// Remove the entry for `chain_id` (all entries when None), returning the removed chain ids
+fn remove_cached_configs(cache: &mut HashMap<u64, CachedConfig>, chain_id: Option<u64>) -> Vec<u64> {
+    let mut removed: Vec<u64> = match chain_id {
+        Some(chain_id) => cache.remove(&chain_id).map(|_| chain_id).into_iter().collect(),
+        None => cache.drain().map(|(chain_id, _)| chain_id).collect(),
+    };
+    removed.sort_unstable();
+    removed
+}
+
+/// Remove in-progress entries started more than `max_age` ago, returning their trade ids
+fn sweep_stale_proofs(in_progress: &mut HashMap<String, Instant>, max_age: Duration) -> Vec<String> {
+    let stale: Vec<String> = in_progress
+        .iter()
+        .filter(|(_, started_at)| started_at.elapsed() > max_age)
+        .map(|(trade_id, _)| trade_id.clone())
+        .collect();
+    for trade_id in &stale {
+        in_progress.remove(trade_id);
+    }
+    stale
+}
+
+/// Trades are snapshotted at the cached contract config's rate
+#[async_trait]
+impl FeeRateSource for AppState {
+    async fn fee_rate_bps(&self, chain_id: u64) -> Option<u32> {
+        let rate = self.get_config_for_chain(chain_id, false).await
+            .map_err(|e| e.to_string())
+            .and_then(|config| config.fee_rate_bps().map_err(|e| e.to_string()));
+        rate.map_err(|e| tracing::warn!("⚠️ No fee rate to snapshot on chain {}: {}", chain_id, e)).ok()
+    }
+}
+
+/// Claim on a trade's proof_in_progress entry; dropping it (on success, error
+/// or panic) removes the entry. Only removes the entry it inserted, so a guard
+/// whose entry was swept doesn't clear the one a later retry inserted.
+pub struct ProofGuard {
+    in_progress: Arc<RwLock<HashMap<String, Instant>>>,
+    trade_id: String,
+    started_at: Instant,
+}
+
+impl ProofGuard {
+    /// Mark `trade_id` as in progress, or None if a proof is already running for it
+    pub async fn acquire(in_progress: &Arc<RwLock<HashMap<String, Instant>>>, trade_id: &str) -> Option<Self> {
+        let mut entries = in_progress.write().await;
+        if entries.contains_key(trade_id) {
+            return None;
+        }
+        let started_at = Instant::now();
+        entries.insert(trade_id.to_string(), started_at);
+        Some(Self {
+            in_progress: in_progress.clone(),
+            trade_id: trade_id.to_string(),
+            started_at,
+        })
+    }
+
+    fn release(entries: &mut HashMap<String, Instant>, trade_id: &str, started_at: Instant) {
+        if entries.get(trade_id) == Some(&started_at) {
+            entries.remove(trade_id);
+        }
+    }
+}
+
+impl Drop for ProofGuard {
+    fn drop(&mut self) {
+        // Drop can't await the lock; release inline if it's free, otherwise on a task
+        if let Ok(mut entries) = self.in_progress.try_write() {
+            Self::release(&mut entries, &self.trade_id, self.started_at);
+            return;
+        }
+        let in_progress = self.in_progress.clone();
+        let trade_id = std::mem::take(&mut self.trade_id);
+        let started_at = self.started_at;
+        tokio::spawn(async move {
+            Self::release(&mut *in_progress.write().await, &trade_id, started_at);
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cache_status_reflects_recent_entry_age() {
+        let entry = CachedConfig {
+            config: ContractConfig::default(),
+            cached_at: Instant::now() - Duration::from_secs(42),
+            refreshing: false,
+        };
+        let status = ConfigCacheStatus::from_entry(8453, Some(&entry));
+        assert!(status.cached);
+        assert!(status.within_ttl);
+        let age = status.age_secs.unwrap();
+        assert!((42..45).contains(&age));
+    }
+
+    #[test]
+    fn test_cache_status_expired_and_missing() {
+        let entry = CachedConfig {
+            config: ContractConfig::default(),
+            cached_at: Instant::now() - AppState::CONFIG_CACHE_TTL - Duration::from_secs(1),
+            refreshing: false,
+        };
+        assert!(!ConfigCacheStatus::from_entry(1, Some(&entry)).within_ttl);
+
+        let missing = ConfigCacheStatus::from_entry(1, None);
+        assert!(!missing.cached);
+        assert_eq!(missing.age_secs, None);
+        assert!(!missing.within_ttl);
+    }
+
+    #[tokio::test]
+    async fn test_stale_config_served_immediately_then_refreshed() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        let cache: ConfigCache = Arc::new(RwLock::new(HashMap::new()));
+        let stale = ContractConfig { fee_rate_bps: "100".to_string(), ..ContractConfig::default() };
+        cache.write().await.insert(8453, CachedConfig {
+            config: stale,
+            cached_at: Instant::now() - AppState::CONFIG_CACHE_TTL - Duration::from_secs(1),
+            refreshing: false,
+        });
+
+        // The RPC fetch is held until `release` fires
+        let fetches = Arc::new(AtomicUsize::new(0));
+        let release = Arc::new(tokio::sync::Notify::new());
+        let fetch = || {
+            let (fetches, release) = (fetches.clone(), release.clone());
+            async move {
+                fetches.fetch_add(1, Ordering::SeqCst);
+                release.notified().await;
+                Ok(ContractConfig { fee_rate_bps: "200".to_string(), ..ContractConfig::default() })
+            }
+        };
+
+        // Both requests get the stale value without waiting; only one refresh starts
+        let first = cached_config(&cache, 8453, false, fetch).await.unwrap();
+        let second = cached_config(&cache, 8453, false, fetch).await.unwrap();
+        assert_eq!((first.fee_rate_bps.as_str(), second.fee_rate_bps.as_str()), ("100", "100"));
+        assert!(cache.read().await[&8453].refreshing);
+
+        release.notify_one();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while cache.read().await[&8453].config.fee_rate_bps != "200" {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("background refresh updates the cache");
+        assert!(!cache.read().await[&8453].refreshing);
+        assert_eq!(fetches.load(Ordering::SeqCst), 1);
+    }
+
+    #[tokio::test]
+    async fn test_panicked_refresh_clears_refreshing() {
+        let cache: ConfigCache = Arc::new(RwLock::new(HashMap::new()));
+        cache.write().await.insert(8453, CachedConfig {
+            config: ContractConfig::default(),
+            cached_at: Instant::now() - AppState::CONFIG_CACHE_TTL - Duration::from_secs(1),
+            refreshing: false,
+        });
+
+        let fetch = || async { panic!("rpc client bug") };
+        cached_config(&cache, 8453, false, fetch).await.unwrap();
+        tokio::time::timeout(Duration::from_secs(1), async {
+            while cache.read().await[&8453].refreshing {
+                tokio::time::sleep(Duration::from_millis(5)).await;
+            }
+        })
+        .await
+        .expect("the next request can retry the refresh");
+    }
+
+    #[tokio::test]
+    async fn test_config_past_max_stale_waits_on_rpc() {
+        let cache: ConfigCache = Arc::new(RwLock::new(HashMap::new()));
+        cache.write().await.insert(8453, CachedConfig {
+            config: ContractConfig { fee_rate_bps: "100".to_string(), ..ContractConfig::default() },
+            cached_at: Instant::now() - AppState::CONFIG_CACHE_MAX_STALE - Duration::from_secs(1),
+            // Even with a refresh stuck in flight
+            refreshing: true,
+        });
+
+        let fetch = || std::future::ready(Ok(ContractConfig { fee_rate_bps: "200".to_string(), ..ContractConfig::default() }));
+        assert_eq!(cached_config(&cache, 8453, false, fetch).await.unwrap().fee_rate_bps, "200");
+        assert!(cache.read().await[&8453].is_fresh());
+
+        // Too old and the RPC is down: an error, not the ancient config
+        cache.write().await.get_mut(&8453).unwrap().cached_at = Instant::now() - AppState::CONFIG_CACHE_MAX_STALE;
+        let down = || std::future::ready(Err(EthereumClientError::ProviderError("connection refused".to_string())));
+        assert!(cached_config(&cache, 8453, false, down).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_warmed_config_is_served_without_rpc() {
+        let cache: ConfigCache = Arc::new(RwLock::new(HashMap::new()));
+        let config = ContractConfig { fee_rate_bps: "100".to_string(), ..ContractConfig::default() };
+        let warmed = warm_configs(&cache, [
+            (8453, std::future::ready(Ok(config))),
+            (1, std::future::ready(Err(EthereumClientError::ProviderError("connection refused".to_string())))),
+        ]).await;
+        assert_eq!(warmed, vec![8453]);
+
+        // The first request after startup is a cache hit
+        let fetched = std::sync::atomic::AtomicBool::new(false);
+        let fetch = || {
+            fetched.store(true, std::sync::atomic::Ordering::SeqCst);
+            std::future::ready(Ok(ContractConfig::default()))
+        };
+        let served = cached_config(&cache, 8453, false, fetch).await.unwrap();
+        assert_eq!(served.fee_rate_bps, "100");
+        assert!(!fetched.load(std::sync::atomic::Ordering::SeqCst));
+
+        // A chain that failed warm-up is fetched on first use
+        assert!(!cache.read().await.contains_key(&1));
+    }
+
+    #[test]
+    fn test_config_cache_invalidation_per_chain_and_all() {
+        let entry = || CachedConfig { config: ContractConfig::default(), cached_at: Instant::now(), refreshing: false };
+        let mut cache: HashMap<u64, CachedConfig> = [(8453, entry()), (1, entry())].into_iter().collect();
+
+        assert_eq!(remove_cached_configs(&mut cache, Some(10)), Vec::<u64>::new());
+        assert_eq!(remove_cached_configs(&mut cache, Some(8453)), vec![8453]);
+        assert!(cache.contains_key(&1));
+
+        cache.insert(8453, entry());
+        assert_eq!(remove_cached_configs(&mut cache, None), vec![1, 8453]);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_verified_hash_cache_hit_expiry_and_invalidation() {
+        let cache = VerifiedHashCache::new(Duration::from_secs(60));
+        cache.insert("0xTX", "0xHASH", "0xorder");
+        assert_eq!(cache.get("0xtx", "0xhash").as_deref(), Some("0xorder"));
+        assert_eq!(cache.get("0xtx", "0xother"), None);
+
+        // Invalidating the resolved order id also drops the entry requested via the tx hash
+        cache.invalidate_order("0xORDER");
+        assert_eq!(cache.get("0xtx", "0xhash"), None);
+
+        let expired = VerifiedHashCache::new(Duration::ZERO);
+        expired.insert("0xorder", "0xhash", "0xorder");
+        assert_eq!(expired.get("0xorder", "0xhash"), None);
+    }
+
+    #[tokio::test]
+    async fn test_ens_cache_hit_skips_lookup() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        let cache = EnsCache::new(Duration::from_secs(60), Duration::from_secs(60));
+        let lookups = AtomicUsize::new(0);
+        let lookup = |name: Option<&'static str>| {
+            lookups.fetch_add(1, Ordering::SeqCst);
+            async move { Ok::<_, String>(name.map(str::to_string)) }
+        };
+
+        let first = cache.resolve("0xABC", || lookup(Some("alice.eth"))).await;
+        let second = cache.resolve("0xabc", || lookup(Some("other.eth"))).await;
+        assert_eq!(first.as_deref(), Some("alice.eth"));
+        assert_eq!(second.as_deref(), Some("alice.eth"));
+        assert_eq!(lookups.load(Ordering::SeqCst), 1);
+
+        // No reverse record is cached as such, and so is a failed lookup
+        assert_eq!(cache.resolve("0xdef", || lookup(None)).await, None);
+        assert_eq!(cache.resolve("0xdef", || lookup(Some("late.eth"))).await, None);
+        assert_eq!(lookups.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.resolve("0x123", || async { Err::<Option<String>, _>("rpc down") }).await, None);
+        assert_eq!(cache.get("0x123"), Some(None));
+
+        let expired = EnsCache::new(Duration::ZERO, Duration::ZERO);
+        expired.insert("0xabc", Some("alice.eth".to_string()));
+        assert_eq!(expired.get("0xabc"), None);
+    }
+
+    #[tokio::test]
+    async fn test_failed_ens_lookup_retried_after_failure_ttl() {
+        let cache = EnsCache::new(Duration::from_secs(3_600), Duration::ZERO);
+        assert_eq!(cache.resolve("0x123", || async { Err::<Option<String>, _>("rpc down") }).await, None);
+        assert_eq!(cache.get("0x123"), None);
+        let name = cache.resolve("0x123", || async { Ok::<_, String>(Some("bob.eth".to_string())) }).await;
+        assert_eq!(name.as_deref(), Some("bob.eth"));
+    }
+
+    #[test]
+    fn test_unsupported_chain_lists_configured_chains() {
+        assert!(check_supported_chain(&[8453, 1], 1).is_ok());
+        match check_supported_chain(&[8453, 1], 10) {
+            Err(ApiError::BadRequest(msg)) => assert_eq!(msg, "unsupported chain_id 10 (supported: 8453, 1)"),
+            other => panic!("expected BadRequest, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_stale_proof_in_progress_is_swept() {
+        let max_age = Duration::from_secs(600);
+        let mut in_progress = HashMap::new();
+        in_progress.insert("0xstale".to_string(), Instant::now() - max_age - Duration::from_secs(1));
+        in_progress.insert("0xfresh".to_string(), Instant::now());
+
+        let evicted = sweep_stale_proofs(&mut in_progress, max_age);
+        assert_eq!(evicted, vec!["0xstale".to_string()]);
+        assert!(in_progress.contains_key("0xfresh"));
+        assert!(!in_progress.contains_key("0xstale"));
+    }
+
+    /// Stands in for the trade_input_streams table
+    #[derive(Default)]
+    struct MemoryStreamStore(std::sync::Mutex<HashMap<String, Vec<String>>>);
+
+    #[async_trait]
+    impl InputStreamStore for MemoryStreamStore {
+        async fn put(&self, trade_id: &str, streams: &[String], _ttl: Duration) -> DbResult<()> {
+            self.0.lock().unwrap().insert(trade_id.to_string(), streams.to_vec());
+            Ok(())
+        }
+
+        async fn get(&self, trade_id: &str) -> DbResult<Option<Vec<String>>> {
+            Ok(self.0.lock().unwrap().get(trade_id).cloned())
+        }
+
+        async fn delete(&self, trade_id: &str) -> DbResult<()> {
+            self.0.lock().unwrap().remove(trade_id);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_input_streams_recovered_after_restart() {
+        let store = Arc::new(MemoryStreamStore::default());
+        let streams: Vec<String> = (0..46).map(|i| format!("0x{:02x}", i)).collect();
+
+        let before_restart = InputStreamsCache::new(store.clone(), AppState::INPUT_STREAMS_TTL);
+        before_restart.insert("0xtrade", streams.clone()).await;
+        drop(before_restart);
+
+        // Fresh process: empty memory, same database
+        let after_restart = InputStreamsCache::new(store.clone(), AppState::INPUT_STREAMS_TTL);
+        assert_eq!(after_restart.get("0xtrade").await, Some(streams));
+
+        // Finalized: gone from both
+        after_restart.remove("0xtrade").await;
+        assert_eq!(after_restart.get("0xtrade").await, None);
+        assert!(store.0.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_swept_proof_can_be_retried() {
+        let max_age = Duration::from_secs(600);
+        let in_progress = Arc::new(RwLock::new(HashMap::new()));
+
+        // A proof task that died without cleaning up blocks new attempts...
+        in_progress.write().await.insert("0xtrade".to_string(), Instant::now() - max_age - Duration::from_secs(1));
+        assert!(ProofGuard::acquire(&in_progress, "0xtrade").await.is_none());
+
+        // ...until the sweeper evicts it
+        sweep_stale_proofs(&mut *in_progress.write().await, max_age);
+        let guard = ProofGuard::acquire(&in_progress, "0xtrade").await.expect("retry proceeds");
+        assert!(ProofGuard::acquire(&in_progress, "0xtrade").await.is_none());
+
+        drop(guard);
+        assert!(in_progress.read().await.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_proof_guard_released_on_panic() {
+        let in_progress = Arc::new(RwLock::new(HashMap::new()));
+        let task_in_progress = in_progress.clone();
+        let result = tokio::spawn(async move {
+            let _guard = ProofGuard::acquire(&task_in_progress, "0xtrade").await.unwrap();
+            panic!("proof task died");
+        }).await;
+        assert!(result.is_err());
+        assert!(ProofGuard::acquire(&in_progress, "0xtrade").await.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_stale_guard_keeps_newer_entry() {
+        let in_progress = Arc::new(RwLock::new(HashMap::new()));
+        let stale = ProofGuard::acquire(&in_progress, "0xtrade").await.unwrap();
+
+        // Swept while still running, then retried
+        in_progress.write().await.clear();
+        let _retry = ProofGuard::acquire(&in_progress, "0xtrade").await.unwrap();
+
+        drop(stale);
+        assert!(in_progress.read().await.contains_key("0xtrade"));
     }
 }
@@ -1,10 +1,1044 @@
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use tokio::time::Instant;
+use utoipa::{IntoParams, ToSchema};
+
+use crate::blockchain::chain_clients::ClientStatus;
+use crate::blockchain::listener_status::ListenerStatus;
+use crate::blockchain::relayer_balance::RelayerBalance;
+use crate::blockchain::types::ContractConfig;
+use crate::config::ChainConfig;
+use crate::currency::{parse_amount, InvalidAmount};
+use crate::db::gas_costs::{format_units, parse_wei, GasCostSummary, TradeGasCost};
+use crate::db::models::{DbChainCounts, DbGasCost, DbMarketLiquidityRow, DbOrder, DbSellerStatsRow, DbSellerWithdrawal, DbStuckTrade, DbTrade, DbTradeStatsRow};
+use crate::email::format_token_decimal;
+use crate::tokens::{self, TokenInfo};
 
 /// Health check response
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct HealthResponse {
+    /// "ok", or "degraded" when an optional dependency (email) is down
     pub status: String,
     pub database: String,
+    /// Resend: "healthy", "unhealthy" or "disabled" (no RESEND_API_KEY)
+    pub email: String,
     pub orderbook: String,
     pub timestamp: String,
+    /// Crate version (Cargo.toml)
+    pub version: String,
+    /// Git commit baked in at build time (see build.rs), "unknown" without git or GIT_COMMIT
+    pub commit: String,
+    /// Seconds since this process started
+    pub uptime_secs: u64,
+}
+
+impl HealthResponse {
+    pub fn new(database: &str, email: &str, started_at: Instant) -> Self {
+        let status = if email == "unhealthy" { "degraded" } else { "ok" };
+        Self {
+            status: status.to_string(),
+            database: database.to_string(),
+            email: email.to_string(),
+            orderbook: "read-only".to_string(),
+            timestamp: chrono::Utc::now().to_rfc3339(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: env!("GIT_COMMIT").to_string(),
+            uptime_secs: started_at.elapsed().as_secs(),
+        }
+    }
+}
+
+/// Build metadata of the running binary (GET /version)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionResponse {
+    /// Crate version (Cargo.toml)
+    pub version: String,
+    /// Git commit the binary was built from (see build.rs)
+    pub commit: String,
+    /// When the binary was built (RFC 3339)
+    pub build_timestamp: String,
+    /// Chains this replica is configured for, to spot a misconfigured deploy
+    pub chain_ids: Vec<u64>,
+}
+
+impl VersionResponse {
+    pub fn new(chain_ids: &[u64]) -> Self {
+        let build_secs: i64 = env!("BUILD_TIMESTAMP").parse().unwrap_or(0);
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            commit: env!("GIT_COMMIT").to_string(),
+            build_timestamp: chrono::DateTime::from_timestamp(build_secs, 0)
+                .unwrap_or_default()
+                .to_rfc3339(),
+            chain_ids: chain_ids.to_vec(),
+        }
+    }
+}
+
+/// What a raw bytes32 id refers to (GET /api/debug/decode/:id)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DecodedId {
+    /// Normalized id (lowercase, 0x-prefixed)
+    pub id: String,
+    /// "trade", "order" or "unknown"
+    pub kind: String,
+    pub chain_id: Option<i32>,
+    /// Trade: "pending"/"settled"/"expired"; order: "active"/"filled"
+    pub state: Option<String>,
+    /// Parent order (trades only)
+    pub order_id: Option<String>,
+    /// Trade: settlement tx if settled, otherwise the escrow (creation) tx
+    pub tx_hash: Option<String>,
+}
+
+/// How far the indexer is behind the chain head (GET /api/chains/:chain_id/sync)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ChainSyncStatus {
+    pub chain_id: u64,
+    pub escrow_address: String,
+    /// Last block the event listener has indexed (None before the first sync)
+    pub last_indexed_block: Option<u64>,
+    pub head_block: u64,
+    /// head_block - last_indexed_block (None before the first sync)
+    pub lag_blocks: Option<u64>,
+}
+
+impl ChainSyncStatus {
+    pub fn new(chain_id: u64, escrow_address: String, last_indexed_block: Option<u64>, head_block: u64) -> Self {
+        Self {
+            chain_id,
+            escrow_address,
+            last_indexed_block,
+            head_block,
+            lag_blocks: last_indexed_block.map(|block| head_block.saturating_sub(block)),
+        }
+    }
+}
+
+/// Query for GET /api/tokens
+#[derive(Debug, Clone, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TokensQuery {
+    /// Only tokens on this chain (all chains when omitted)
+    pub chain_id: Option<u64>,
+}
+
+/// Supported tokens (GET /api/tokens)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct TokensResponse {
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// One configured chain and its registry tokens (GET /api/chains)
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct ChainInfo {
+    pub chain_id: u64,
+    pub name: String,
+    /// Escrow contract address (lowercase)
+    pub escrow_address: String,
+    pub tokens: Vec<TokenInfo>,
+}
+
+/// Chains this relay serves (GET /api/chains); built once from config
+#[derive(Debug, Clone, Default, Serialize, ToSchema)]
+pub struct ChainsResponse {
+    pub chains: Vec<ChainInfo>,
+}
+
+impl ChainsResponse {
+    pub fn from_config(chains: &[ChainConfig]) -> Self {
+        Self {
+            chains: chains
+                .iter()
+                .map(|c| ChainInfo {
+                    chain_id: c.chain_id,
+                    name: c.name.clone(),
+                    escrow_address: c.escrow_address.to_lowercase(),
+                    tokens: tokens::tokens_for_chain(c.chain_id).copied().collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Config cache state for a single chain (GET /api/admin/config/cache)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConfigCacheStatus {
+    pub chain_id: u64,
+    pub cached: bool,
+    /// Seconds since the config was fetched from chain (None if not cached)
+    pub age_secs: Option<u64>,
+    pub within_ttl: bool,
+    /// A background refresh of the stale entry is in flight
+    pub refreshing: bool,
+    pub ttl_secs: u64,
+}
+
+/// Pending trade past its expiry that auto-cancel hasn't cleared
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StuckTrade {
+    pub trade_id: String,
+    pub order_id: String,
+    pub chain_id: i32,
+    pub buyer: String,
+    pub expires_at: i64,
+    /// Seconds since the trade expired
+    pub stuck_secs: i64,
+    /// Auto-cancel gave up on this trade (needs manual review)
+    pub cancel_failed: bool,
+    pub cancel_error: Option<String>,
+}
+
+/// Event listener health per configured chain (GET /api/admin/listener-status)
+#[derive(Debug, Clone, Serialize)]
+pub struct ListenerStatusResponse {
+    /// A chain without a completed sync cycle for this long is `stalled`
+    pub stall_after_secs: u64,
+    pub chains: Vec<ListenerStatus>,
+}
+
+/// Relayer wallet per chain with a client (GET /api/admin/relayer)
+#[derive(Debug, Clone, Serialize)]
+pub struct RelayerStatusResponse {
+    pub chains: Vec<RelayerBalance>,
+    /// Any chain's balance is below its threshold
+    pub any_low_balance: bool,
+    /// Client connection status per configured chain
+    pub client_status: BTreeMap<u64, ClientStatus>,
+}
+
+/// Expired-but-pending trades (GET /api/admin/stuck-trades); a growing list
+/// means the auto-cancel service is down or failing
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StuckTradesResponse {
+    pub total: usize,
+    /// Longest time any listed trade has been stuck (0 when none)
+    pub max_stuck_secs: i64,
+    pub trades: Vec<StuckTrade>,
+}
+
+impl StuckTradesResponse {
+    pub fn new(trades: Vec<DbStuckTrade>, now: i64) -> Self {
+        let trades: Vec<StuckTrade> = trades
+            .into_iter()
+            .filter(|t| t.expires_at < now)
+            .map(|t| StuckTrade {
+                stuck_secs: now - t.expires_at,
+                cancel_failed: t.cancel_failed_at.is_some(),
+                trade_id: t.trade_id,
+                order_id: t.order_id,
+                chain_id: t.chain_id,
+                buyer: t.buyer,
+                expires_at: t.expires_at,
+                cancel_error: t.cancel_error,
+            })
+            .collect();
+        Self {
+            total: trades.len(),
+            max_stuck_secs: trades.iter().map(|t| t.stuck_secs).max().unwrap_or(0),
+            trades,
+        }
+    }
+}
+
+/// Query for GET /api/stats
+#[derive(Debug, Clone, Deserialize)]
+pub struct StatsQuery {
+    pub chain_id: Option<u64>,
+    /// Window start (unix seconds, inclusive) on trade creation time
+    pub from: Option<i64>,
+    /// Window end (unix seconds, exclusive)
+    pub to: Option<i64>,
+}
+
+/// Settled volume for one token
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct TokenVolume {
+    pub token: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub settled_count: i64,
+    /// Token base units
+    pub settled_volume: String,
+    pub settled_volume_decimal: String,
+    /// settled_volume / settled_count in base units (rounded down)
+    pub average_trade_size: String,
+    pub average_trade_size_decimal: String,
+}
+
+impl TokenVolume {
+    /// `settled_count` trades of `token` totalling `settled_volume` base units
+    /// (a malformed volume is an error rather than a zero volume)
+    pub fn settled(token: &str, settled_count: i64, settled_volume: &str) -> Result<Self, InvalidAmount> {
+        let (symbol, decimals) = tokens::symbol_and_decimals(token);
+        let volume: u128 = parse_amount(settled_volume)?;
+        let average = match settled_count {
+            0 => "0".to_string(),
+            n => (volume / n as u128).to_string(),
+        };
+        Ok(Self {
+            token: token.to_string(),
+            symbol: symbol.to_string(),
+            decimals,
+            settled_count,
+            settled_volume: settled_volume.to_string(),
+            settled_volume_decimal: format_token_decimal(settled_volume, decimals)?,
+            average_trade_size_decimal: format_token_decimal(&average, decimals)?,
+            average_trade_size: average,
+        })
+    }
+}
+
+/// Settled / (settled + expired); None when no trade has finished yet.
+/// Pending trades are left out since they can still go either way.
+fn completion_rate(settled: i64, expired: i64) -> Option<f64> {
+    let finished = settled + expired;
+    (finished > 0).then(|| settled as f64 / finished as f64)
+}
+
+/// Trade volume and settlement stats (GET /api/stats)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TradeStatsResponse {
+    pub chain_id: Option<u64>,
+    pub from: Option<i64>,
+    pub to: Option<i64>,
+    /// Trades created in the window, any status
+    pub trade_count: i64,
+    pub settled_count: i64,
+    pub expired_count: i64,
+    pub pending_count: i64,
+    /// settled / (settled + expired); None when nothing has finished yet
+    pub success_rate: Option<f64>,
+    pub tokens: Vec<TokenVolume>,
+}
+
+impl TradeStatsResponse {
+    /// Fold the per-(token, status) rows from `Database::trade_stats`
+    pub fn from_rows(query: &StatsQuery, rows: &[DbTradeStatsRow]) -> Result<Self, InvalidAmount> {
+        let count_with_status = |status: i32| -> i64 {
+            rows.iter().filter(|r| r.status == status).map(|r| r.trade_count).sum()
+        };
+        let (settled_count, expired_count, pending_count) = (count_with_status(1), count_with_status(2), count_with_status(0));
+
+        let tokens = rows.iter()
+            .filter(|r| r.status == 1 && r.trade_count > 0)
+            .map(|r| TokenVolume::settled(&r.token, r.trade_count, &r.token_volume))
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            chain_id: query.chain_id,
+            from: query.from,
+            to: query.to,
+            trade_count: rows.iter().map(|r| r.trade_count).sum(),
+            settled_count,
+            expired_count,
+            pending_count,
+            success_rate: completion_rate(settled_count, expired_count),
+            tokens,
+        })
+    }
+}
+
+/// Query for GET /api/market/stats (and the admin variant)
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketStatsQuery {
+    pub chain_id: Option<u64>,
+}
+
+/// Liquidity on active orders for one token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TokenLiquidity {
+    pub token: String,
+    pub symbol: String,
+    pub decimals: u8,
+    pub order_count: i64,
+    /// Sum of remaining_amount, token base units
+    pub remaining_amount: String,
+    pub remaining_amount_decimal: String,
+}
+
+/// Active order liquidity (GET /api/market/stats, GET /api/admin/market-stats)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MarketStatsResponse {
+    pub chain_id: Option<u64>,
+    /// Whether private orders are counted. They can only be taken with their
+    /// private code, so the public endpoint leaves them out.
+    pub includes_private: bool,
+    pub order_count: i64,
+    pub tokens: Vec<TokenLiquidity>,
+}
+
+impl MarketStatsResponse {
+    /// Fold the per-(token, visibility) rows from `Database::market_liquidity`
+    /// (a malformed amount is an error rather than left out of the total)
+    pub fn from_rows(chain_id: Option<u64>, rows: &[DbMarketLiquidityRow], include_private: bool) -> Result<Self, InvalidAmount> {
+        let mut tokens: Vec<TokenLiquidity> = Vec::new();
+        for row in rows.iter().filter(|r| r.is_public || include_private) {
+            let amount: u128 = parse_amount(&row.remaining_amount)?;
+            match tokens.iter_mut().find(|t| t.token == row.token) {
+                Some(entry) => {
+                    let total = parse_amount::<u128>(&entry.remaining_amount)? + amount;
+                    entry.order_count += row.order_count;
+                    entry.remaining_amount = total.to_string();
+                }
+                None => {
+                    let (symbol, decimals) = tokens::symbol_and_decimals(&row.token);
+                    tokens.push(TokenLiquidity {
+                        token: row.token.clone(),
+                        symbol: symbol.to_string(),
+                        decimals,
+                        order_count: row.order_count,
+                        remaining_amount: amount.to_string(),
+                        remaining_amount_decimal: String::new(),
+                    });
+                }
+            }
+        }
+        for entry in &mut tokens {
+            entry.remaining_amount_decimal = format_token_decimal(&entry.remaining_amount, entry.decimals)?;
+        }
+
+        Ok(Self {
+            chain_id,
+            includes_private: include_private,
+            order_count: tokens.iter().map(|t| t.order_count).sum(),
+            tokens,
+        })
+    }
+}
+
+/// Seller reputation summary (GET /api/sellers/:address/stats)
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct SellerStatsResponse {
+    /// Lowercased seller address
+    pub seller: String,
+    /// Orders created, any state
+    pub total_orders: i64,
+    pub settled_trades: i64,
+    /// Trades that expired unpaid (the escrow has no separate cancelled state:
+    /// a cancelled trade is one that was left to expire)
+    pub expired_trades: i64,
+    pub pending_trades: i64,
+    /// settled / (settled + expired); None until a trade has finished
+    pub completion_rate: Option<f64>,
+    /// Earliest order or trade creation (unix seconds); None for unknown sellers
+    pub first_activity_at: Option<i64>,
+    /// Latest order or trade creation (unix seconds)
+    pub last_activity_at: Option<i64>,
+    /// Settled volume per token (amounts in different tokens don't add up)
+    pub settled_volume: Vec<TokenVolume>,
+}
+
+impl SellerStatsResponse {
+    /// Fold the per-token rows from `Database::seller_stats`
+    pub fn from_rows(seller: String, rows: &[DbSellerStatsRow]) -> Result<Self, InvalidAmount> {
+        let settled_trades = rows.iter().map(|r| r.settled_count).sum();
+        let expired_trades = rows.iter().map(|r| r.expired_count).sum();
+        Ok(Self {
+            seller,
+            total_orders: rows.iter().map(|r| r.order_count).sum(),
+            settled_trades,
+            expired_trades,
+            pending_trades: rows.iter().map(|r| r.pending_count).sum(),
+            completion_rate: completion_rate(settled_trades, expired_trades),
+            first_activity_at: rows.iter().map(|r| r.first_activity_at).min(),
+            last_activity_at: rows.iter().map(|r| r.last_activity_at).max(),
+            settled_volume: rows.iter()
+                .filter(|r| r.settled_count > 0)
+                .map(|r| TokenVolume::settled(&r.token, r.settled_count, &r.settled_volume))
+                .collect::<Result<_, _>>()?,
+        })
+    }
+}
+
+/// Query for GET /api/sellers/:address/withdrawals
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+pub struct SellerWithdrawalsQuery {
+    /// Page size (default DEFAULT_PAGE_SIZE, clamped to MAX_PAGE_SIZE)
+    pub limit: Option<i64>,
+    /// Withdrawals to skip (default 0)
+    pub offset: Option<i64>,
+}
+
+/// One withdrawal in a seller's history
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SellerWithdrawal {
+    pub order_id: String,
+    pub chain_id: i32,
+    pub token: String,
+    pub symbol: String,
+    pub decimals: u8,
+    /// Token base units withdrawn
+    pub amount: String,
+    pub amount_decimal: String,
+    /// Token base units left on the order afterwards
+    pub remaining_after: String,
+    pub remaining_after_decimal: String,
+    pub tx_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<DbSellerWithdrawal> for SellerWithdrawal {
+    fn from(row: DbSellerWithdrawal) -> Self {
+        let (symbol, decimals) = tokens::symbol_and_decimals(&row.token);
+        Self {
+            amount_decimal: format_token_decimal(&row.amount, decimals).unwrap_or_else(InvalidAmount::into_raw),
+            remaining_after_decimal: format_token_decimal(&row.remaining_after, decimals).unwrap_or_else(InvalidAmount::into_raw),
+            order_id: row.order_id,
+            chain_id: row.chain_id,
+            token: row.token,
+            symbol: symbol.to_string(),
+            decimals,
+            amount: row.amount,
+            remaining_after: row.remaining_after,
+            tx_hash: row.tx_hash,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// GET /api/sellers/:address/withdrawals - One page of a seller's withdrawals, newest first
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct SellerWithdrawalsResponse {
+    /// Lowercased seller address
+    pub seller: String,
+    pub withdrawals: Vec<SellerWithdrawal>,
+    /// Withdrawals across all pages
+    pub total: i64,
+    /// Page size applied (the requested limit after clamping)
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl SellerWithdrawalsResponse {
+    pub fn from_rows(seller: String, rows: Vec<DbSellerWithdrawal>, total: i64, limit: i64, offset: i64) -> Self {
+        Self {
+            seller,
+            withdrawals: rows.into_iter().map(SellerWithdrawal::from).collect(),
+            total,
+            limit,
+            offset,
+        }
+    }
+}
+
+/// Query for GET /api/debug/database
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct DebugDatabaseQuery {
+    /// Orders and trades per page (default and max: DEBUG_MAX_ROWS)
+    pub limit: Option<i64>,
+    /// Rows to skip in both lists
+    #[serde(default)]
+    pub offset: i64,
+}
+
+/// GET /api/debug/database - One page of the database with per-chain counts
+#[derive(Debug, Serialize)]
+pub struct DebugResponse {
+    /// Counted in SQL over every row, not just this page
+    pub summary: DebugSummary,
+    /// Keyed by chain name ("Base", "Ethereum")
+    pub chain_configs: BTreeMap<String, DebugChainConfig>,
+    pub limit: i64,
+    pub offset: i64,
+    /// More orders or trades exist past this page (raise `offset` to see them)
+    pub truncated: bool,
+    pub orders: Vec<DbOrder>,
+    pub trades: Vec<DbTrade>,
+    /// Gas totals of the trades on this page
+    pub trade_gas_costs: Vec<TradeGasCost>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct DebugSummary {
+    pub base: ChainSummary,
+    pub ethereum: ChainSummary,
+}
+
+/// Order and trade counts for one chain
+#[derive(Debug, Serialize)]
+pub struct ChainSummary {
+    pub chain_id: u64,
+    pub orders: i64,
+    pub trades: i64,
+    pub trades_pending: i64,
+    pub trades_settled: i64,
+    /// None when the gas cost query failed
+    pub gas_costs: Option<Vec<GasCostSummary>>,
+}
+
+impl ChainSummary {
+    pub fn new(chain_id: u64, counts: DbChainCounts, gas_costs: Option<Vec<GasCostSummary>>) -> Self {
+        Self {
+            chain_id,
+            orders: counts.orders,
+            trades: counts.trades,
+            trades_pending: counts.trades_pending,
+            trades_settled: counts.trades_settled,
+            gas_costs,
+        }
+    }
+}
+
+/// Contract config of one chain, or why it couldn't be fetched
+#[derive(Debug, Serialize)]
+pub struct DebugChainConfig {
+    pub chain_id: u64,
+    /// The config, or the string "failed to fetch"
+    pub config: DebugConfigValue,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum DebugConfigValue {
+    Loaded(Box<ContractConfig>),
+    Failed(&'static str),
+}
+
+/// Query for POST /api/admin/config/invalidate
+#[derive(Debug, Clone, Deserialize)]
+pub struct InvalidateConfigQuery {
+    /// Only this chain (every cached chain when omitted)
+    pub chain_id: Option<u64>,
+}
+
+/// Chains whose cached contract config was dropped (POST /api/admin/config/invalidate)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvalidateConfigResponse {
+    pub invalidated: Vec<u64>,
+}
+
+/// Query for GET /api/orders/:order_id/verify
+#[derive(Debug, Clone, Default, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct VerifyOrderQuery {
+    /// Overwrite the DB remaining amount with the on-chain one when they differ
+    /// (needs an ADMIN_WALLETS bearer token)
+    #[serde(default)]
+    pub resync: bool,
+}
+
+/// DB vs on-chain remaining amount of one order (GET /api/orders/:order_id/verify)
+#[derive(Debug, Clone, Serialize, PartialEq, ToSchema)]
+pub struct OrderVerifyResponse {
+    pub order_id: String,
+    pub chain_id: i32,
+    pub in_sync: bool,
+    /// remainingAmount as indexed (token base units)
+    pub db_value: String,
+    /// remainingAmount read from the escrow contract
+    pub chain_value: String,
+    /// The DB value was overwritten with `chain_value` (only with ?resync=true, and
+    /// not when an event changed it while verifying)
+    pub resynced: bool,
+}
+
+impl OrderVerifyResponse {
+    /// Compare numerically, so a NUMERIC rendering like "100.0" still matches "100"
+    pub fn compare(order: &DbOrder, chain_value: &str) -> Self {
+        let normalize = |value: &str| value.split('.').next().unwrap_or(value).trim_start_matches('0').to_string();
+        Self {
+            order_id: order.order_id.clone(),
+            chain_id: order.chain_id,
+            in_sync: normalize(&order.remaining_amount) == normalize(chain_value),
+            db_value: order.remaining_amount.clone(),
+            chain_value: chain_value.to_string(),
+            resynced: false,
+        }
+    }
+}
+
+/// One relayer transaction in a trade's gas cost breakdown
+#[derive(Debug, Serialize)]
+pub struct TradeGasCostEntry {
+    /// fill, settle, cancel, ...
+    pub operation: String,
+    pub tx_hash: String,
+    pub gas_used: i64,
+    /// Pre-send eth_estimateGas, to compare with gas_used (None when not estimated)
+    pub gas_estimate: Option<i64>,
+    pub gas_price_gwei: String,
+    pub cost_wei: String,
+    pub cost_eth: String,
+    pub created_at: DateTime<Utc>,
+    /// False while the cost is still a send-time estimate
+    pub reconciled: bool,
+}
+
+/// GET /api/trades/:trade_id/gas-costs response (empty `costs` when nothing was recorded)
+#[derive(Debug, Serialize)]
+pub struct TradeGasCostsResponse {
+    pub trade_id: String,
+    pub chain_id: u64,
+    pub costs: Vec<TradeGasCostEntry>,
+    pub total_cost_wei: String,
+    pub total_cost_eth: String,
+}
+
+impl TradeGasCostsResponse {
+    pub fn new(trade_id: String, chain_id: u64, costs: Vec<DbGasCost>) -> Self {
+        let total_wei: u128 = costs.iter().map(|c| parse_wei(&c.cost_wei)).sum();
+        Self {
+            trade_id,
+            chain_id,
+            costs: costs.into_iter().map(|c| TradeGasCostEntry {
+                operation: c.operation,
+                tx_hash: c.tx_hash,
+                gas_used: c.gas_used,
+                gas_estimate: c.gas_estimate,
+                gas_price_gwei: c.gas_price_gwei,
+                cost_wei: c.cost_wei,
+                cost_eth: c.cost_eth,
+                created_at: c.created_at,
+                reconciled: c.reconciled_at.is_some(),
+            }).collect(),
+            total_cost_wei: total_wei.to_string(),
+            total_cost_eth: format_units(total_wei, 18),
+        }
+    }
+}
+
+/// Query for GET /api/admin/gas-costs/summary
+#[derive(Debug, Clone, Deserialize)]
+pub struct GasCostSummaryQuery {
+    /// Only this chain (every chain when omitted)
+    pub chain_id: Option<u64>,
+}
+
+/// Relayer gas spend of one chain, by operation
+#[derive(Debug, Serialize)]
+pub struct ChainGasCosts {
+    pub chain_id: u64,
+    pub operations: Vec<GasCostSummary>,
+}
+
+/// GET /api/admin/gas-costs/summary response: per-chain summaries plus the
+/// roll-up across them (wei totals are exact; both chains pay gas in ETH)
+#[derive(Debug, Serialize)]
+pub struct GasCostSummaryResponse {
+    pub chains: Vec<ChainGasCosts>,
+    /// Per-operation totals over all listed chains
+    pub total_by_operation: Vec<GasCostSummary>,
+    pub total_cost_wei: String,
+    pub total_cost_eth: String,
+}
+
+impl GasCostSummaryResponse {
+    pub fn new(chains: Vec<(i32, Vec<GasCostSummary>)>) -> Self {
+        let total_by_operation = GasCostSummary::roll_up(&chains);
+        let total_wei = GasCostSummary::total_wei(&total_by_operation);
+        Self {
+            chains: chains.into_iter()
+                .map(|(chain_id, operations)| ChainGasCosts { chain_id: chain_id as u64, operations })
+                .collect(),
+            total_by_operation,
+            total_cost_wei: total_wei.to_string(),
+            total_cost_eth: format_units(total_wei, 18),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures;
+
+    #[test]
+    fn test_trade_gas_costs_total() {
+        let empty = TradeGasCostsResponse::new("0x01".to_string(), 8453, Vec::new());
+        assert!(empty.costs.is_empty());
+        assert_eq!(empty.total_cost_wei, "0");
+        assert_eq!(empty.total_cost_eth, "0.000000000000000000");
+
+        let fill = DbGasCost::from_receipt(8453, "fill", Some("0x01"), "0xaa".to_string(), 150_000, 10_000_000);
+        let cancel = DbGasCost::from_receipt(8453, "cancel", Some("0x01"), "0xbb".to_string(), 60_000, 20_000_000);
+        let response = TradeGasCostsResponse::new("0x01".to_string(), 8453, vec![fill, cancel]);
+        assert_eq!(response.chain_id, 8453);
+        assert_eq!(response.costs.iter().map(|c| c.operation.as_str()).collect::<Vec<_>>(), vec!["fill", "cancel"]);
+        assert!(response.costs.iter().all(|c| c.reconciled));
+        assert_eq!(response.total_cost_wei, "2700000000000");
+        assert_eq!(response.total_cost_eth, "0.000002700000000000");
+    }
+
+    #[test]
+    fn test_chain_sync_status_reports_checkpoint_and_lag() {
+        let status = ChainSyncStatus::new(8453, "0xescrow".to_string(), Some(24_000_000), 24_000_012);
+        assert_eq!(status.last_indexed_block, Some(24_000_000));
+        assert_eq!(status.lag_blocks, Some(12));
+
+        // Checkpoint ahead of a lagging RPC head never underflows
+        let ahead = ChainSyncStatus::new(1, "0xescrow".to_string(), Some(100), 99);
+        assert_eq!(ahead.lag_blocks, Some(0));
+
+        let never_synced = ChainSyncStatus::new(1, "0xescrow".to_string(), None, 99);
+        assert_eq!(never_synced.lag_blocks, None);
+    }
+
+    #[test]
+    fn test_chains_response_follows_config() {
+        let chain = |chain_id, name: &str, escrow: &str| ChainConfig {
+            escrow_address: escrow.to_string(),
+            ..fixtures::chain(name, chain_id)
+        };
+        let base = chain(8453, "Base", "0xABCDEF0000000000000000000000000000000001");
+        let only_base = ChainsResponse::from_config(std::slice::from_ref(&base));
+        assert_eq!(only_base.chains.len(), 1);
+
+        let response = ChainsResponse::from_config(&[base, chain(1, "Ethereum", "0x02")]);
+        let ids: Vec<u64> = response.chains.iter().map(|c| c.chain_id).collect();
+        assert_eq!(ids, [8453, 1]);
+
+        let eth = &response.chains[1];
+        assert_eq!(eth.name, "Ethereum");
+        assert!(eth.tokens.iter().any(|t| t.symbol == "USDT" && t.decimals == 6));
+        assert!(eth.tokens.iter().all(|t| t.chain_id == 1));
+        assert_eq!(response.chains[0].escrow_address, "0xabcdef0000000000000000000000000000000001");
+
+        // RPC URLs (which may embed API keys) are never served
+        let json = serde_json::to_string(&response).unwrap();
+        assert!(!json.contains("rpc"));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_health_uptime_increases() {
+        let started_at = Instant::now();
+        assert_eq!(HealthResponse::new("healthy", "disabled", started_at).uptime_secs, 0);
+
+        tokio::time::advance(std::time::Duration::from_secs(90)).await;
+        let later = HealthResponse::new("healthy", "disabled", started_at);
+        assert_eq!(later.uptime_secs, 90);
+        assert_eq!(later.version, env!("CARGO_PKG_VERSION"));
+    }
+
+    #[test]
+    fn test_version_reports_build_metadata() {
+        let version = VersionResponse::new(&[8453, 1]);
+        assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+        assert!(!version.commit.is_empty());
+        assert!(chrono::DateTime::parse_from_rfc3339(&version.build_timestamp).unwrap().timestamp() > 0);
+        assert_eq!(version.chain_ids, vec![8453, 1]);
+
+        let json = serde_json::to_value(&version).unwrap();
+        for field in ["version", "commit", "build_timestamp"] {
+            assert!(!json[field].as_str().unwrap().is_empty(), "{}", field);
+        }
+    }
+
+    #[test]
+    fn test_stats_success_rate_and_token_volume() {
+        const USDC: &str = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+        const WETH: &str = "0x4200000000000000000000000000000000000006";
+        let row = |token: &str, status, trade_count, token_volume: &str| DbTradeStatsRow {
+            token: token.to_string(),
+            status,
+            trade_count,
+            token_volume: token_volume.to_string(),
+        };
+        let rows = vec![
+            row(USDC, 0, 2, "20000000"),
+            row(USDC, 1, 4, "250000000"),
+            row(USDC, 2, 1, "5000000"),
+            row(WETH, 1, 2, "3000000000000000000"),
+            row(WETH, 2, 3, "1000000000000000000"),
+        ];
+        let query = StatsQuery { chain_id: Some(8453), from: None, to: None };
+        let stats = TradeStatsResponse::from_rows(&query, &rows).unwrap();
+
+        assert_eq!(stats.trade_count, 12);
+        assert_eq!((stats.settled_count, stats.expired_count, stats.pending_count), (6, 4, 2));
+        // Pending trades don't count against the success rate: 6 / (6 + 4)
+        assert_eq!(stats.success_rate, Some(0.6));
+
+        let usdc = stats.tokens.iter().find(|t| t.symbol == "USDC").unwrap();
+        assert_eq!(usdc.settled_volume_decimal, "250.0");
+        assert_eq!(usdc.average_trade_size, "62500000");
+        assert_eq!(usdc.average_trade_size_decimal, "62.5");
+        let weth = stats.tokens.iter().find(|t| t.symbol == "WETH").unwrap();
+        assert_eq!(weth.average_trade_size_decimal, "1.5");
+
+        let empty = TradeStatsResponse::from_rows(&query, &[]).unwrap();
+        assert_eq!(empty.success_rate, None);
+        assert!(empty.tokens.is_empty());
+    }
+
+    #[test]
+    fn test_seller_withdrawals_from_two_orders() {
+        const USDC: &str = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+        const WETH: &str = "0x4200000000000000000000000000000000000006";
+        let row = |order_id: &str, token: &str, amount: &str, remaining_after: &str| DbSellerWithdrawal {
+            order_id: order_id.to_string(),
+            token: token.to_string(),
+            chain_id: 8453,
+            amount: amount.to_string(),
+            remaining_after: remaining_after.to_string(),
+            tx_hash: Some(format!("0xtx{}", order_id)),
+            created_at: Utc::now(),
+        };
+        let rows = vec![
+            row("0xorder1", USDC, "25000000", "75000000"),
+            row("0xorder2", WETH, "1500000000000000000", "0"),
+        ];
+        let page = SellerWithdrawalsResponse::from_rows("0xseller".to_string(), rows, 2, 100, 0);
+
+        assert_eq!(page.total, 2);
+        let orders: Vec<&str> = page.withdrawals.iter().map(|w| w.order_id.as_str()).collect();
+        assert_eq!(orders, ["0xorder1", "0xorder2"]);
+        // Each row is rendered with its own token's decimals
+        assert_eq!((page.withdrawals[0].symbol.as_str(), page.withdrawals[0].decimals), ("USDC", 6));
+        assert_eq!(page.withdrawals[0].amount_decimal, "25.0");
+        assert_eq!(page.withdrawals[0].remaining_after_decimal, "75.0");
+        assert_eq!((page.withdrawals[1].symbol.as_str(), page.withdrawals[1].decimals), ("WETH", 18));
+        assert_eq!(page.withdrawals[1].amount_decimal, "1.5");
+        assert_eq!(page.withdrawals[1].tx_hash.as_deref(), Some("0xtx0xorder2"));
+    }
+
+    #[test]
+    fn test_market_stats_exclude_private_orders_unless_admin() {
+        const USDC: &str = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+        const WETH: &str = "0x4200000000000000000000000000000000000006";
+        let row = |token: &str, is_public, order_count, remaining_amount: &str| DbMarketLiquidityRow {
+            token: token.to_string(),
+            is_public,
+            order_count,
+            remaining_amount: remaining_amount.to_string(),
+        };
+        let rows = vec![
+            row(USDC, false, 1, "50000000"),
+            row(USDC, true, 3, "150000000"),
+            row(WETH, false, 2, "2000000000000000000"),
+        ];
+
+        let public = MarketStatsResponse::from_rows(Some(8453), &rows, false).unwrap();
+        assert!(!public.includes_private);
+        assert_eq!(public.order_count, 3);
+        assert_eq!(public.tokens.len(), 1);
+        assert_eq!(public.tokens[0].symbol, "USDC");
+        assert_eq!(public.tokens[0].remaining_amount_decimal, "150.0");
+
+        let admin = MarketStatsResponse::from_rows(Some(8453), &rows, true).unwrap();
+        assert!(admin.includes_private);
+        assert_eq!(admin.order_count, 6);
+        let usdc = admin.tokens.iter().find(|t| t.symbol == "USDC").unwrap();
+        assert_eq!((usdc.order_count, usdc.remaining_amount.as_str()), (4, "200000000"));
+        let weth = admin.tokens.iter().find(|t| t.symbol == "WETH").unwrap();
+        assert_eq!(weth.remaining_amount_decimal, "2.0");
+
+        // A malformed amount fails the totals instead of counting as zero
+        let corrupt = [row(USDC, true, 1, "1.5")];
+        assert_eq!(MarketStatsResponse::from_rows(None, &corrupt, true).unwrap_err().raw, "1.5");
+    }
+
+    #[test]
+    fn test_seller_stats_with_settled_and_expired_trades() {
+        const USDC: &str = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+        const WETH: &str = "0x4200000000000000000000000000000000000006";
+        let rows = vec![
+            DbSellerStatsRow {
+                token: USDC.to_string(),
+                order_count: 2,
+                settled_count: 3,
+                expired_count: 1,
+                pending_count: 1,
+                settled_volume: "45000000".to_string(),
+                first_activity_at: 1_700_000_000,
+                last_activity_at: 1_700_500_000,
+            },
+            // An order that only ever had an expired trade
+            DbSellerStatsRow {
+                token: WETH.to_string(),
+                order_count: 1,
+                settled_count: 0,
+                expired_count: 1,
+                pending_count: 0,
+                settled_volume: "0".to_string(),
+                first_activity_at: 1_699_000_000,
+                last_activity_at: 1_699_100_000,
+            },
+        ];
+        let stats = SellerStatsResponse::from_rows("0xseller".to_string(), &rows).unwrap();
+
+        assert_eq!(stats.total_orders, 3);
+        assert_eq!((stats.settled_trades, stats.expired_trades, stats.pending_trades), (3, 2, 1));
+        // 3 settled / (3 settled + 2 expired); the pending trade is left out
+        assert_eq!(stats.completion_rate, Some(0.6));
+        assert_eq!(stats.first_activity_at, Some(1_699_000_000));
+        assert_eq!(stats.last_activity_at, Some(1_700_500_000));
+        assert_eq!(stats.settled_volume.len(), 1);
+        assert_eq!(stats.settled_volume[0].symbol, "USDC");
+        assert_eq!(stats.settled_volume[0].settled_volume_decimal, "45.0");
+        assert_eq!(stats.settled_volume[0].average_trade_size, "15000000");
+
+        let unknown = SellerStatsResponse::from_rows("0xnobody".to_string(), &[]).unwrap();
+        assert_eq!((unknown.total_orders, unknown.completion_rate, unknown.first_activity_at), (0, None, None));
+    }
+
+    #[test]
+    fn test_expired_pending_trade_listed_as_stuck() {
+        let stuck = |trade_id: &str, expires_at: i64| DbStuckTrade {
+            trade_id: trade_id.to_string(),
+            order_id: "0xorder".to_string(),
+            chain_id: 8453,
+            buyer: "0xbuyer".to_string(),
+            expires_at,
+            cancel_failed_at: None,
+            cancel_error: None,
+        };
+        let now = 1_700_000_000;
+        let report = StuckTradesResponse::new(vec![stuck("0xexpired", now - 900), stuck("0xlive", now + 60)], now);
+
+        assert_eq!(report.total, 1);
+        assert_eq!(report.trades[0].trade_id, "0xexpired");
+        assert_eq!(report.trades[0].stuck_secs, 900);
+        assert_eq!(report.max_stuck_secs, 900);
+        assert!(!report.trades[0].cancel_failed);
+
+        assert_eq!(StuckTradesResponse::new(Vec::new(), now).max_stuck_secs, 0);
+    }
+
+    #[test]
+    fn test_debug_response_schema() {
+        let mut chain_configs = BTreeMap::new();
+        chain_configs.insert("Base".to_string(), DebugChainConfig {
+            chain_id: 8453,
+            config: DebugConfigValue::Loaded(Box::default()),
+        });
+        chain_configs.insert("Ethereum".to_string(), DebugChainConfig {
+            chain_id: 1,
+            config: DebugConfigValue::Failed("failed to fetch"),
+        });
+        let response = DebugResponse {
+            summary: DebugSummary {
+                base: ChainSummary::new(8453, DbChainCounts::default(), None),
+                ethereum: ChainSummary::new(1, DbChainCounts::default(), Some(Vec::new())),
+            },
+            chain_configs,
+            limit: 1000,
+            offset: 0,
+            truncated: false,
+            orders: Vec::new(),
+            trades: Vec::new(),
+            trade_gas_costs: Vec::new(),
+        };
+
+        let json = serde_json::to_value(&response).unwrap();
+        let keys = |v: &serde_json::Value| {
+            let mut keys: Vec<String> = v.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+        assert_eq!(
+            keys(&json),
+            ["chain_configs", "limit", "offset", "orders", "summary", "trade_gas_costs", "trades", "truncated"]
+        );
+        assert_eq!(keys(&json["summary"]), ["base", "ethereum"]);
+        assert_eq!(
+            keys(&json["summary"]["base"]),
+            ["chain_id", "gas_costs", "orders", "trades", "trades_pending", "trades_settled"]
+        );
+        assert_eq!(json["summary"]["base"]["chain_id"], 8453);
+        assert!(json["summary"]["base"]["gas_costs"].is_null());
+        assert_eq!(json["chain_configs"]["Base"]["config"]["fee_rate_bps"], "");
+        assert_eq!(json["chain_configs"]["Ethereum"]["config"], "failed to fetch");
+    }
 }
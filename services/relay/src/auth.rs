@@ -7,20 +7,29 @@
 //! 4. Backend verifies the SIWE message and returns a JWT
 //! 5. Frontend attaches the JWT to subsequent requests via Authorization header
 //! 6. Backend middleware extracts and validates the JWT on protected endpoints
+//!
+//! Smart-contract wallets (Safe, Coinbase Smart Wallet) can't produce an ECDSA
+//! signature for their own address. When EOA recovery fails, the signature is
+//! checked with the wallet contract's EIP-1271 `isValidSignature` on the chain
+//! named by the message's Chain ID (only chains the relay has a client for).
 
 use std::collections::HashMap;
 use std::sync::{Arc, OnceLock};
 use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use async_trait::async_trait;
 use axum::{
     extract::{State, Json},
     http::StatusCode,
 };
+use ethers::types::Address;
 use jsonwebtoken::{encode, decode, Header, Validation, EncodingKey, DecodingKey};
 use serde::{Deserialize, Serialize};
 use siwe::{Message, VerificationOpts};
+use time::OffsetDateTime;
 
 use crate::api::state::AppState;
+use crate::blockchain::client::{EthereumClient, EthereumClientError};
 
 // ============================================================================
 // JWT Configuration
@@ -45,11 +54,84 @@ fn jwt_secret() -> &'static str {
     })
 }
 
-/// JWT token expiry (24 hours)
-const JWT_EXPIRY_HOURS: i64 = 24;
+/// JWT token expiry when JWT_EXPIRY_HOURS is unset (24 hours)
+pub const DEFAULT_JWT_EXPIRY_HOURS: i64 = 24;
+
+/// Token lifetime and scope, from the environment:
+///
+///   JWT_EXPIRY_HOURS  token lifetime (default 24)
+///   JWT_ISSUER        `iss` claim set and required (default: none, not checked)
+///   JWT_AUDIENCE      `aud` claim set and required (default: none, not checked)
+///
+/// Give each deployment its own issuer/audience so a token minted by one
+/// (e.g. staging sharing a secret with production) is rejected by the other.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JwtSettings {
+    pub expiry_hours: i64,
+    pub issuer: Option<String>,
+    pub audience: Option<String>,
+}
+
+impl Default for JwtSettings {
+    fn default() -> Self {
+        Self {
+            expiry_hours: DEFAULT_JWT_EXPIRY_HOURS,
+            issuer: None,
+            audience: None,
+        }
+    }
+}
+
+impl JwtSettings {
+    pub fn from_env() -> Self {
+        let non_empty = |name: &str| {
+            std::env::var(name).ok()
+                .map(|v| v.trim().to_string())
+                .filter(|v| !v.is_empty())
+        };
+        Self {
+            expiry_hours: std::env::var("JWT_EXPIRY_HOURS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .filter(|&hours: &i64| hours > 0)
+                .unwrap_or(DEFAULT_JWT_EXPIRY_HOURS),
+            issuer: non_empty("JWT_ISSUER"),
+            audience: non_empty("JWT_AUDIENCE"),
+        }
+    }
+
+    /// Token validation: expiry always, issuer/audience when configured
+    fn validation(&self) -> Validation {
+        let mut validation = Validation::default();
+        let mut required = vec!["exp"];
+        if let Some(issuer) = &self.issuer {
+            validation.set_issuer(&[issuer]);
+            required.push("iss");
+        }
+        match &self.audience {
+            Some(audience) => {
+                validation.set_audience(&[audience]);
+                required.push("aud");
+            }
+            // Not scoped to an audience: don't reject tokens that carry one
+            None => validation.validate_aud = false,
+        }
+        validation.set_required_spec_claims(&required);
+        validation
+    }
+}
+
+/// JWT settings (read from the environment once)
+fn jwt_settings() -> &'static JwtSettings {
+    static JWT_SETTINGS: OnceLock<JwtSettings> = OnceLock::new();
+    JWT_SETTINGS.get_or_init(JwtSettings::from_env)
+}
+
+/// Nonce lifetime when NONCE_EXPIRY_SECS is unset (5 minutes)
+pub const DEFAULT_NONCE_EXPIRY_SECS: u64 = 300;
 
-/// Nonce expiry (5 minutes)
-const NONCE_EXPIRY_SECS: u64 = 300;
+/// What EIP-1271 `isValidSignature` returns when the wallet accepts the signature
+pub const EIP1271_MAGIC_VALUE: [u8; 4] = [0x16, 0x26, 0xba, 0x7e];
 
 // ============================================================================
 // Types
@@ -64,6 +146,12 @@ pub struct Claims {
     pub exp: usize,
     /// Issued at timestamp
     pub iat: usize,
+    /// Issuer (JWT_ISSUER, if configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub iss: Option<String>,
+    /// Audience (JWT_AUDIENCE, if configured)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aud: Option<String>,
 }
 
 /// Nonce store entry
@@ -72,20 +160,51 @@ struct NonceEntry {
 }
 
 /// Shared nonce store (in-memory with TTL)
-#[derive(Clone, Default)]
+#[derive(Clone)]
 pub struct NonceStore {
     nonces: Arc<RwLock<HashMap<String, NonceEntry>>>,
+    ttl: Duration,
+}
+
+impl Default for NonceStore {
+    fn default() -> Self {
+        Self::with_ttl(Duration::from_secs(DEFAULT_NONCE_EXPIRY_SECS))
+    }
 }
 
 impl NonceStore {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_ttl(ttl: Duration) -> Self {
         Self {
             nonces: Arc::new(RwLock::new(HashMap::new())),
+            ttl,
         }
     }
 
+    /// Nonce lifetime from NONCE_EXPIRY_SECS (default 300)
+    pub fn from_env() -> Self {
+        let secs = std::env::var("NONCE_EXPIRY_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs: &u64| secs > 0)
+            .unwrap_or(DEFAULT_NONCE_EXPIRY_SECS);
+        Self::with_ttl(Duration::from_secs(secs))
+    }
+
+    /// How long a nonce stays valid after it's generated
+    pub fn ttl(&self) -> Duration {
+        self.ttl
+    }
+
     /// Generate and store a new nonce
     pub async fn generate(&self) -> String {
+        self.generate_at(Instant::now()).await
+    }
+
+    async fn generate_at(&self, now: Instant) -> String {
         use rand::Rng;
         let nonce: String = rand::thread_rng()
             .sample_iter(&rand::distributions::Alphanumeric)
@@ -96,24 +215,37 @@ impl NonceStore {
         let mut store = self.nonces.write().await;
 
         // Clean up expired nonces while we're here
-        store.retain(|_, entry| entry.created_at.elapsed() < Duration::from_secs(NONCE_EXPIRY_SECS));
+        store.retain(|_, entry| self.is_live(entry, now));
 
         store.insert(nonce.clone(), NonceEntry {
-            created_at: Instant::now(),
+            created_at: now,
         });
 
         nonce
     }
 
     /// Consume a nonce (returns true if valid and not expired)
+    ///
+    /// Single-use under concurrency: the lookup and removal happen under one write
+    /// lock, so when the same nonce is submitted twice at once (e.g. a double-click
+    /// on sign-in) exactly one caller gets `true`. Any shared backend added later
+    /// must keep this guarantee with an atomic get-and-delete (e.g. Redis GETDEL).
     pub async fn consume(&self, nonce: &str) -> bool {
+        self.consume_at(nonce, Instant::now()).await
+    }
+
+    async fn consume_at(&self, nonce: &str, now: Instant) -> bool {
         let mut store = self.nonces.write().await;
         if let Some(entry) = store.remove(nonce) {
-            entry.created_at.elapsed() < Duration::from_secs(NONCE_EXPIRY_SECS)
+            self.is_live(&entry, now)
         } else {
             false
         }
     }
+
+    fn is_live(&self, entry: &NonceEntry, now: Instant) -> bool {
+        now.saturating_duration_since(entry.created_at) < self.ttl
+    }
 }
 
 // ============================================================================
@@ -123,6 +255,8 @@ impl NonceStore {
 #[derive(Serialize)]
 pub struct NonceResponse {
     pub nonce: String,
+    /// Seconds until the nonce expires (sign and verify before then)
+    pub expires_in: u64,
 }
 
 #[derive(Deserialize)]
@@ -154,7 +288,7 @@ pub async fn get_nonce(
     State(state): State<AppState>,
 ) -> Json<NonceResponse> {
     let nonce = state.nonce_store.generate().await;
-    Json(NonceResponse { nonce })
+    Json(NonceResponse { nonce, expires_in: state.nonce_store.ttl().as_secs() })
 }
 
 /// POST /api/auth/verify - Verify SIWE signature and return JWT
@@ -185,15 +319,13 @@ pub async fn verify_siwe(
         }))
     })?;
 
-    // Verify the SIWE message signature
-    let opts = VerificationOpts {
-        timestamp: Some(time::OffsetDateTime::now_utc()),
-        ..Default::default()
-    };
-    message.verify(&sig_bytes, &opts).await.map_err(|e| {
-        tracing::warn!("SIWE verification failed: {:?}", e);
+    // Verify the SIWE message signature (EOA, else EIP-1271 on the message's chain)
+    let client = state.get_blockchain_client(message.chain_id).ok();
+    let contract_wallets = client.as_deref().map(|client| client as &dyn ContractWalletVerifier);
+    verify_signature(&message, &payload.message, &sig_bytes, OffsetDateTime::now_utc(), contract_wallets).await.map_err(|e| {
+        tracing::warn!("SIWE verification failed: {}", e);
         (StatusCode::UNAUTHORIZED, Json(AuthError {
-            error: format!("Signature verification failed: {:?}", e),
+            error: format!("Signature verification failed: {}", e),
         }))
     })?;
 
@@ -201,21 +333,8 @@ pub async fn verify_siwe(
     let address = format!("0x{}", hex::encode(message.address));
 
     // Generate JWT
-    let now = chrono::Utc::now();
-    let exp = now + chrono::Duration::hours(JWT_EXPIRY_HOURS);
-
-    let claims = Claims {
-        sub: address.clone(),
-        iat: now.timestamp() as usize,
-        exp: exp.timestamp() as usize,
-    };
-
-    let secret = jwt_secret();
-    let token = encode(
-        &Header::default(),
-        &claims,
-        &EncodingKey::from_secret(secret.as_bytes()),
-    ).map_err(|e| {
+    let settings = jwt_settings();
+    let token = issue_jwt(&address, settings, jwt_secret()).map_err(|e| {
         tracing::error!("Failed to encode JWT: {}", e);
         (StatusCode::INTERNAL_SERVER_ERROR, Json(AuthError {
             error: "Failed to generate token".to_string(),
@@ -227,10 +346,79 @@ pub async fn verify_siwe(
     Ok(Json(VerifyResponse {
         token,
         address,
-        expires_in: JWT_EXPIRY_HOURS * 3600,
+        expires_in: settings.expiry_hours * 3600,
     }))
 }
 
+/// Calls a contract wallet's EIP-1271 `isValidSignature` (an EthereumClient in production)
+#[async_trait]
+pub trait ContractWalletVerifier: Send + Sync {
+    async fn is_valid_signature(&self, wallet: Address, hash: [u8; 32], signature: Vec<u8>) -> Result<[u8; 4], EthereumClientError>;
+}
+
+#[async_trait]
+impl ContractWalletVerifier for EthereumClient {
+    async fn is_valid_signature(&self, wallet: Address, hash: [u8; 32], signature: Vec<u8>) -> Result<[u8; 4], EthereumClientError> {
+        EthereumClient::is_valid_signature(self, wallet, hash, signature).await
+    }
+}
+
+/// Check `signature` over the SIWE message (`raw_message` as signed) at `now`:
+/// EOA recovery first, then the wallet contract's EIP-1271 check if
+/// `contract_wallets` is given (no client for the message's chain: EOA only).
+async fn verify_signature(
+    message: &Message,
+    raw_message: &str,
+    signature: &[u8],
+    now: OffsetDateTime,
+    contract_wallets: Option<&dyn ContractWalletVerifier>,
+) -> Result<(), String> {
+    let opts = VerificationOpts {
+        timestamp: Some(now),
+        ..Default::default()
+    };
+    let eoa_error = match message.verify(signature, &opts).await {
+        Ok(()) => return Ok(()),
+        Err(e) => format!("{:?}", e),
+    };
+
+    // A contract wallet's signature still has to be inside the message's validity window
+    let Some(verifier) = contract_wallets.filter(|_| message.valid_at(&now)) else {
+        return Err(eoa_error);
+    };
+    let hash = ethers::utils::hash_message(raw_message);
+    match verifier.is_valid_signature(Address::from(message.address), hash.0, signature.to_vec()).await {
+        Ok(magic) if magic == EIP1271_MAGIC_VALUE => {
+            tracing::info!("🔏 EIP-1271 signature accepted for contract wallet 0x{}", hex::encode(message.address));
+            Ok(())
+        }
+        Ok(magic) => Err(format!("{} (EIP-1271: wallet returned 0x{})", eoa_error, hex::encode(magic))),
+        Err(e) => Err(format!("{} (EIP-1271: {})", eoa_error, e)),
+    }
+}
+
+/// Sign a token for `address` with the configured lifetime and scope
+fn issue_jwt(address: &str, settings: &JwtSettings, secret: &str) -> jsonwebtoken::errors::Result<String> {
+    let now = chrono::Utc::now();
+    let exp = now + chrono::Duration::hours(settings.expiry_hours);
+
+    let claims = Claims {
+        sub: address.to_string(),
+        iat: now.timestamp() as usize,
+        exp: exp.timestamp() as usize,
+        iss: settings.issuer.clone(),
+        aud: settings.audience.clone(),
+    };
+
+    encode(&Header::default(), &claims, &EncodingKey::from_secret(secret.as_bytes()))
+}
+
+/// Token for `address` signed with the process secret (tests of JWT-gated middleware)
+#[cfg(test)]
+pub(crate) fn test_token(address: &str) -> String {
+    issue_jwt(address, jwt_settings(), jwt_secret()).expect("token signs")
+}
+
 // ============================================================================
 // JWT Verification (for middleware)
 // ============================================================================
@@ -239,15 +427,15 @@ pub async fn verify_siwe(
 /// Returns the wallet address (lowercase) if valid.
 pub fn verify_jwt(auth_header: &str) -> Result<String, String> {
     let token = auth_header.trim_start_matches("Bearer ").trim();
+    let claims = decode_jwt(token, jwt_settings(), jwt_secret())?;
+    Ok(claims.sub.to_lowercase())
+}
 
-    let secret = jwt_secret();
-    let token_data = decode::<Claims>(
-        token,
-        &DecodingKey::from_secret(secret.as_bytes()),
-        &Validation::default(),
-    ).map_err(|e| format!("Invalid token: {}", e))?;
-
-    Ok(token_data.claims.sub.to_lowercase())
+/// Check a token's signature, expiry and (when configured) issuer and audience
+fn decode_jwt(token: &str, settings: &JwtSettings, secret: &str) -> Result<Claims, String> {
+    decode::<Claims>(token, &DecodingKey::from_secret(secret.as_bytes()), &settings.validation())
+        .map(|data| data.claims)
+        .map_err(|e| format!("Invalid token: {}", e))
 }
 
 // Note: For future middleware-based auth, you can use:
@@ -256,3 +444,130 @@ pub fn verify_jwt(auth_header: &str) -> Result<String, String> {
 //     let auth_str = auth_header.to_str().ok()?;
 //     verify_jwt(auth_str).ok()
 // }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_consume_is_single_use() {
+        let store = NonceStore::new();
+        let nonce = store.generate().await;
+
+        let attempts: Vec<_> = (0..2)
+            .map(|_| {
+                let store = store.clone();
+                let nonce = nonce.clone();
+                tokio::spawn(async move { store.consume(&nonce).await })
+            })
+            .collect();
+
+        let mut accepted = 0;
+        for attempt in attempts {
+            if attempt.await.unwrap() {
+                accepted += 1;
+            }
+        }
+        assert_eq!(accepted, 1);
+        assert!(!store.consume(&nonce).await);
+    }
+
+    #[tokio::test]
+    async fn test_nonce_expires_at_configured_ttl() {
+        let store = NonceStore::with_ttl(Duration::from_secs(120));
+        let issued = Instant::now();
+
+        let nonce = store.generate_at(issued).await;
+        assert!(store.consume_at(&nonce, issued + Duration::from_secs(119)).await);
+
+        let nonce = store.generate_at(issued).await;
+        assert!(!store.consume_at(&nonce, issued + Duration::from_secs(121)).await);
+    }
+
+    /// Contract wallet answering isValidSignature with a fixed value
+    struct MockWallet {
+        returns: [u8; 4],
+        expected_hash: [u8; 32],
+    }
+
+    #[async_trait]
+    impl ContractWalletVerifier for MockWallet {
+        async fn is_valid_signature(&self, _wallet: Address, hash: [u8; 32], _signature: Vec<u8>) -> Result<[u8; 4], EthereumClientError> {
+            assert_eq!(hash, self.expected_hash, "EIP-191 hash of the signed message");
+            Ok(self.returns)
+        }
+    }
+
+    /// SIWE message for `wallet` on Base, valid until 2030
+    fn siwe_message(wallet: Address) -> String {
+        format!(
+            "lync-z.xyz wants you to sign in with your Ethereum account:\n{}\n\nSign in to LyncZ\n\n\
+             URI: https://lync-z.xyz\nVersion: 1\nChain ID: 8453\nNonce: abcdef1234567890\n\
+             Issued At: 2024-01-01T00:00:00Z\nExpiration Time: 2030-01-01T00:00:00Z",
+            ethers::utils::to_checksum(&wallet, None)
+        )
+    }
+
+    #[tokio::test]
+    async fn test_contract_wallet_signature_via_eip1271() {
+        let safe = Address::repeat_byte(0x5a);
+        let raw = siwe_message(safe);
+        let message: Message = raw.parse().unwrap();
+        // Not an ECDSA signature by `safe`, so EOA recovery fails
+        let signature = vec![0x11; 65];
+        let now = OffsetDateTime::from_unix_timestamp(1_750_000_000).unwrap();
+        let expected_hash = ethers::utils::hash_message(&raw).0;
+
+        let accepts = MockWallet { returns: EIP1271_MAGIC_VALUE, expected_hash };
+        assert!(verify_signature(&message, &raw, &signature, now, Some(&accepts)).await.is_ok());
+
+        let rejects = MockWallet { returns: [0xff; 4], expected_hash };
+        let err = verify_signature(&message, &raw, &signature, now, Some(&rejects)).await.unwrap_err();
+        assert!(err.contains("0xffffffff"), "{}", err);
+
+        // No client for the message's chain: EOA only
+        assert!(verify_signature(&message, &raw, &signature, now, None).await.is_err());
+
+        // An expired message isn't rescued by the wallet contract
+        let after_expiry = OffsetDateTime::from_unix_timestamp(1_900_000_000).unwrap();
+        assert!(verify_signature(&message, &raw, &signature, after_expiry, Some(&accepts)).await.is_err());
+    }
+
+    const SECRET: &str = "test-secret";
+    const ADDRESS: &str = "0xAbC0000000000000000000000000000000000001";
+
+    fn scoped(audience: &str) -> JwtSettings {
+        JwtSettings {
+            expiry_hours: DEFAULT_JWT_EXPIRY_HOURS,
+            issuer: Some("lyncz-relay".to_string()),
+            audience: Some(audience.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_expiry_override() {
+        let settings = JwtSettings { expiry_hours: 2, ..JwtSettings::default() };
+        let token = issue_jwt(ADDRESS, &settings, SECRET).unwrap();
+
+        let claims = decode_jwt(&token, &settings, SECRET).unwrap();
+        assert_eq!(claims.exp - claims.iat, 2 * 3600);
+        assert_eq!(claims.sub, ADDRESS);
+        assert_eq!((claims.iss, claims.aud), (None, None));
+    }
+
+    #[test]
+    fn test_audience_mismatch_rejected() {
+        let staging = issue_jwt(ADDRESS, &scoped("staging.lync-z.xyz"), SECRET).unwrap();
+        let production = scoped("lync-z.xyz");
+
+        assert!(decode_jwt(&staging, &production, SECRET).unwrap_err().contains("InvalidAudience"));
+        let claims = decode_jwt(&issue_jwt(ADDRESS, &production, SECRET).unwrap(), &production, SECRET).unwrap();
+        assert_eq!(claims.aud.as_deref(), Some("lync-z.xyz"));
+
+        // Once scoped, unscoped tokens (no iss/aud) are rejected too...
+        let unscoped = issue_jwt(ADDRESS, &JwtSettings::default(), SECRET).unwrap();
+        assert!(decode_jwt(&unscoped, &production, SECRET).is_err());
+        // ...while an unscoped deployment still accepts scoped tokens signed with its secret
+        assert!(decode_jwt(&staging, &JwtSettings::default(), SECRET).is_ok());
+    }
+}
@@ -4,93 +4,119 @@
 //! - Event listener syncs blockchain → DB
 //! - Read-only APIs for orders and trades
 //! - Two-step settlement: validate → settle
+//!
+//! Usage: api-server [--backfill-from <block>]
+//!   --backfill-from  Replay escrow events from <block> to the current head on
+//!                    every chain before following new blocks
 
-use std::sync::Arc;
-use std::collections::HashMap;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use lyncz_relay::{Config, AppState, create_router};
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::task::JoinHandle;
+use lyncz_relay::{Config, AppState, EmailService, create_router};
+use lyncz_relay::email;
+use lyncz_relay::api::{proof_jobs, stale_orders};
+use lyncz_relay::blockchain;
+use lyncz_relay::blockchain::chain_clients::reconnect_interval_from_env;
 use lyncz_relay::blockchain::client::EthereumClient;
 use lyncz_relay::blockchain::events::EventListener;
+use lyncz_relay::blockchain::gas_reconciler;
+use lyncz_relay::config::ChainConfig;
+
+/// Event listener tasks; each resolves to whether it stopped cleanly
+type Listeners = Arc<Mutex<Vec<JoinHandle<bool>>>>;
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,lyncz_relay=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    lyncz_relay::telemetry::init();
 
     tracing::info!("🚀 Starting LyncZ Relay Server");
 
     let config = Config::load()?;
     config.log_summary();
 
+    // A broken template set should stop the deploy, not the first notification
+    if let Some(email_service) = EmailService::from_env() {
+        email_service.validate_templates()?;
+        tracing::info!("✅ Email templates validated ({} languages)", email::language::SUPPORTED_LANGUAGES.len());
+    }
+
+    let backfill_from = parse_backfill_from(std::env::args().skip(1))?;
+    if let Some(block) = backfill_from {
+        tracing::info!("⏪ Backfill requested from block {}", block);
+    }
+
     let addr = format!("{}:{}", config.api_host, config.api_port);
 
+    // Flipped as soon as the shutdown signal arrives, so open SSE streams end
+    // instead of holding up the graceful shutdown
+    let (streams_shutdown_tx, streams_shutdown_rx) = watch::channel(false);
+
     // Initialize state
-    let mut state = AppState::new(&config.database_url).await?;
+    let mut state = AppState::new(&config.database_url, config.database_replica_url.as_deref(), &config.db_pool).await?
+        .with_api_config(config.api.clone())
+        .with_chains(&config.chains)
+        .with_ens_rpc(config.ens_rpc_url.as_deref())
+        .with_shutdown(streams_shutdown_rx);
     tracing::info!("✅ Database connected");
 
-    // Initialize blockchain clients for all configured chains
-    if config.relayer_private_key.is_some() {
-        let private_key = config.relayer_private_key.as_ref().unwrap();
-        let mut clients: HashMap<u64, Arc<EthereumClient>> = HashMap::new();
-        
+    // Flipped once the server has stopped accepting requests
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    // Shared with the reconnect task, which adds listeners for chains that come up later
+    let listeners: Listeners = Arc::new(Mutex::new(Vec::new()));
+
+    // Initialize blockchain clients for all configured chains. Chains whose RPC
+    // doesn't answer are kept as unavailable and retried in the background; a
+    // chain with bad config (e.g. escrow address) stops startup.
+    if let Some(private_key) = config.relayer_private_key.clone() {
+        let clients = blockchain::init_chain_clients(&config).await?;
+        state = state.with_blockchain_clients(clients.clone());
+
+        // Start an event listener for each chain that has a client
         for chain_config in &config.chains {
-            let escrow_address: ethers::types::Address = match chain_config.escrow_address.parse() {
-                Ok(addr) => addr,
-                Err(e) => {
-                    tracing::warn!("⚠️ Invalid escrow address for {} (chain {}): {}", 
-                        chain_config.name, chain_config.chain_id, e);
-                    continue;
-                }
-            };
-            
-            match EthereumClient::new(
-                &chain_config.rpc_url,
-                private_key,
-                escrow_address,
-                chain_config.chain_id,
-            ).await {
-                Ok(client) => {
-                    let client = Arc::new(client);
-                    tracing::info!("✅ Blockchain client initialized for {} (chain {})", 
-                        chain_config.name, chain_config.chain_id);
-                    
-                    // Start event listener for this chain
-                    let rpc_url = chain_config.rpc_url.clone();
-                    let chain_id = chain_config.chain_id;
-                    let chain_name = chain_config.name.clone();
-                    let db_pool = state.db.pool().clone();
-                    
-                    if let Ok(mut listener) = EventListener::new(&rpc_url, escrow_address, db_pool, None, chain_id).await {
-                        tokio::spawn(async move {
-                            tracing::info!("🎧 Event listener started for {} (chain {})", chain_name, chain_id);
-                            if let Err(e) = listener.start().await {
-                                tracing::error!("Event listener error for {} (chain {}): {:?}", chain_name, chain_id, e);
-                            }
-                        });
-                    }
-                    
-                    clients.insert(chain_config.chain_id, client);
-                }
-                Err(e) => {
-                    tracing::warn!("⚠️ Blockchain client failed for {} (chain {}): {}", 
-                        chain_config.name, chain_config.chain_id, e);
-                }
+            if let Ok(client) = clients.get(chain_config.chain_id) {
+                spawn_listener(&state, chain_config, client, shutdown_rx.clone(), backfill_from, &listeners).await;
             }
         }
         
         if !clients.is_empty() {
-            tracing::info!("✅ {} blockchain client(s) initialized", clients.len());
-            state = state.with_blockchain_clients(clients);
+            tracing::info!("✅ {} of {} blockchain client(s) initialized",
+                clients.ready().len(), clients.chain_ids().len());
+            
+            // Correct send-time gas estimates against on-chain receipts
+            tokio::spawn(gas_reconciler::run(state.db.clone(), clients.clone()));
+        }
+
+        if config.api.config_warmup {
+            state.warm_config_cache().await;
+        }
+
+        if !clients.unavailable().is_empty() {
+            let interval = reconnect_interval_from_env();
+            tracing::warn!("⚠️ Chains {:?} unavailable, retrying every {}s", clients.unavailable(), interval.as_secs());
+            tokio::spawn(reconnect_chains(
+                state.clone(), config.chains.clone(), private_key, interval,
+                shutdown_rx.clone(), backfill_from, listeners.clone(),
+            ));
         }
     } else {
         tracing::info!("⚠️ Blockchain disabled (no RELAYER_PRIVATE_KEY)");
     }
 
+    state.spawn_proof_sweeper();
+    proof_jobs::spawn(state.clone());
+    stale_orders::spawn(state.clone());
+
+    // Retry notification emails that failed on first send, remind buyers before trades expire,
+    // email sellers whose frontend never set the new order's visibility, and send daily digests
+    let outbox_worker = EmailService::from_env().map(|email_service| {
+        tokio::spawn(email::reminders::run(email_service.clone(), state.db.clone()));
+        tokio::spawn(email::digest::run(email_service.clone(), state.db.clone()));
+        tokio::spawn(email::order_created::run(email_service.clone(), state.db.clone()));
+        tokio::spawn(email::outbox::run(email_service, state.db.pool().clone(), shutdown_rx))
+    });
+
     let app = create_router(state);
     let listener = tokio::net::TcpListener::bind(&addr).await?;
     
@@ -101,7 +127,174 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     tracing::info!("   GET  /api/orders/active           List orders (?chain_id=8453)");
     tracing::info!("   GET  /api/trades/:id              Get trade");
     tracing::info!("   POST /api/trades/:id/validate     Upload PDF + validate (~10s)");
+    tracing::info!("   GET  /api/proofs/:job_id          Proof job status");
     
-    axum::serve(listener, app).await?;
+    // Peer addresses feed the per-IP rate limiter
+    axum::serve(listener, app.into_make_service_with_connect_info::<SocketAddr>())
+        .with_graceful_shutdown(async move {
+            shutdown_signal().await;
+            let _ = streams_shutdown_tx.send(true);
+        })
+        .await?;
+
+    // Let listeners finish their current block range and the outbox do a bounded
+    // final drain before exiting
+    let _ = shutdown_tx.send(true);
+    let listeners = std::mem::take(&mut *listeners.lock().unwrap_or_else(|e| e.into_inner()));
+    let total_listeners = listeners.len();
+    let mut listeners_stopped = 0;
+    for listener in listeners {
+        match listener.await {
+            Ok(true) => listeners_stopped += 1,
+            Ok(false) => {}
+            Err(e) => tracing::warn!("🎧 Event listener ended abnormally: {}", e),
+        }
+    }
+    let outbox_drained = match outbox_worker {
+        Some(worker) => match worker.await {
+            Ok(()) => "drained",
+            Err(e) => {
+                tracing::warn!("📮 Email outbox worker ended abnormally: {}", e);
+                "aborted"
+            }
+        },
+        None => "disabled",
+    };
+    tracing::info!("👋 Server stopped (event listeners: {}/{} stopped cleanly, email outbox: {})",
+        listeners_stopped, total_listeners, outbox_drained);
     Ok(())
 }
+
+/// Start `chain_config`'s event listener (after the optional backfill)
+async fn spawn_listener(
+    state: &AppState,
+    chain_config: &ChainConfig,
+    client: Arc<EthereumClient>,
+    shutdown_rx: watch::Receiver<bool>,
+    backfill_from: Option<u64>,
+    listeners: &Listeners,
+) {
+    let chain_id = chain_config.chain_id;
+    let chain_name = chain_config.name.clone();
+    let confirmations = chain_config.confirmations;
+    let listener = match EventListener::new(&chain_config.rpc_url, client.escrow_address(), state.db.pool().clone(), None, chain_id).await {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::error!("Failed to create event listener for {} (chain {}): {:?}", chain_name, chain_id, e);
+            return;
+        }
+    };
+    let mut listener = listener
+        .with_live_feed(state.live_feed.clone())
+        .with_progress(state.listener_progress.clone())
+        .with_confirmations(confirmations)
+        .with_fee_rates(Arc::new(state.clone()));
+    let handle = tokio::spawn(async move {
+        if let Some(from_block) = backfill_from {
+            // Stay the confirmation depth behind head, like live sync does
+            let result = match client.get_block_number().await {
+                Ok(head) => listener.backfill(from_block, head.saturating_sub(confirmations)).await
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e.to_string()),
+            };
+            if let Err(e) = result {
+                tracing::error!("Backfill failed for {} (chain {}): {}", chain_name, chain_id, e);
+            }
+        }
+        tracing::info!("🎧 Event listener started for {} (chain {})", chain_name, chain_id);
+        match listener.start(shutdown_rx).await {
+            Ok(()) => true,
+            Err(e) => {
+                tracing::error!("Event listener error for {} (chain {}): {:?}", chain_name, chain_id, e);
+                false
+            }
+        }
+    });
+    listeners.lock().unwrap_or_else(|e| e.into_inner()).push(handle);
+}
+
+/// Retry chains that failed at startup every `interval` until all are connected
+/// (or the server shuts down), starting each one's event listener once it's up
+async fn reconnect_chains(
+    state: AppState,
+    chains: Vec<ChainConfig>,
+    private_key: String,
+    interval: Duration,
+    mut shutdown_rx: watch::Receiver<bool>,
+    backfill_from: Option<u64>,
+    listeners: Listeners,
+) {
+    let clients = state.blockchain_clients.clone();
+    let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+    while !clients.unavailable().is_empty() {
+        tokio::select! {
+            _ = ticker.tick() => {}
+            _ = shutdown_rx.changed() => return,
+        }
+
+        let connected = clients.reconnect(|chain_id| {
+            let chain_config = chains.iter().find(|chain| chain.chain_id == chain_id);
+            let private_key = private_key.as_str();
+            async move {
+                let chain_config = chain_config.ok_or_else(|| format!("Chain {} not configured", chain_id))?;
+                blockchain::connect_chain(chain_config, private_key).await
+            }
+        }).await;
+
+        for (chain_id, client) in connected {
+            if let Some(chain_config) = chains.iter().find(|chain| chain.chain_id == chain_id) {
+                spawn_listener(&state, chain_config, client, shutdown_rx.clone(), backfill_from, &listeners).await;
+            }
+        }
+    }
+    tracing::info!("✅ All configured chains connected");
+}
+
+/// Resolve on Ctrl+C or SIGTERM
+async fn shutdown_signal() {
+    let ctrl_c = async {
+        if let Err(e) = tokio::signal::ctrl_c().await {
+            tracing::warn!("Failed to listen for Ctrl+C: {}", e);
+            std::future::pending::<()>().await;
+        }
+    };
+
+    #[cfg(unix)]
+    let terminate = async {
+        match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate()) {
+            Ok(mut sigterm) => {
+                sigterm.recv().await;
+            }
+            Err(e) => {
+                tracing::warn!("Failed to listen for SIGTERM: {}", e);
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+
+    tokio::select! {
+        _ = ctrl_c => {}
+        _ = terminate => {}
+    }
+    tracing::info!("🛑 Shutdown signal received, draining requests");
+}
+
+/// Parse the optional `--backfill-from <block>` flag
+fn parse_backfill_from(mut args: impl Iterator<Item = String>) -> Result<Option<u64>, String> {
+    let Some(arg) = args.next() else {
+        return Ok(None);
+    };
+    let value = if arg == "--backfill-from" {
+        args.next().ok_or("--backfill-from requires a block number")?
+    } else if let Some(value) = arg.strip_prefix("--backfill-from=") {
+        value.to_string()
+    } else {
+        return Err(format!("Unknown argument: {}", arg));
+    };
+    value
+        .parse()
+        .map(Some)
+        .map_err(|_| format!("Invalid block number for --backfill-from: {}", value))
+}
@@ -5,16 +5,158 @@
 //! 
 //! Runs as a separate process alongside the API server.
 //! The relay wallet pays gas fees for each cancellation (~0.0001 ETH on L2).
+//! Expired trades are grouped by chain and cancelled in batches
+//! (AUTO_CANCEL_BATCH_SIZE, default 20) to share the per-transaction overhead.
+//! Failed cancellations back off exponentially; after MAX_CANCEL_ATTEMPTS the
+//! trade is flagged in the DB (cancel_failed_at) for manual review.
+//! When a chain's gas price exceeds its MAX_CANCEL_GAS_PRICE_GWEI ceiling,
+//! cancellations are deferred to a later tick (see blockchain::gas_ceiling).
+//! Each cancelled trade fires a signed `trade.expired` webhook when WEBHOOK_URLS is set.
+//! Single cancellations are gas-estimated first, and one whose estimate reverts isn't
+//! sent. A TradeNotPending revert (settled or cancelled meanwhile) is skipped without
+//! counting as a failure, and the trade is only re-checked after RETRY_BACKOFF_MAX_SECS
+//! so the listener can catch up; any other revert counts as a failed attempt with the
+//! decoded revert reason. The estimate is stored with the gas cost for comparison.
+//! Every cancellation's gas cost is recorded in `gas_costs`; daily spend per chain
+//! is checked against the gas budget (see blockchain::gas_budget) after each round.
+//! The relayer balance on each chain is checked at startup and after each round,
+//! warning when it drops below RELAYER_LOW_BALANCE_ETH (see blockchain::relayer_balance).
+//! The first pass runs immediately on startup, then every AUTO_CANCEL_INTERVAL_SECS
+//! (default 30). A pass cancels at most AUTO_CANCEL_MAX_PER_PASS trades (oldest first);
+//! when it hits that limit the next pass starts right away instead of sleeping, so a
+//! backlog drains quickly - up to MAX_CATCH_UP_PASSES in a row before sleeping again.
+//! A trade is only cancelled once it has been expired for AUTO_CANCEL_EXPIRY_BUFFER_SECS
+//! (default 30), so cancellation doesn't race a buyer settling at the last second
+//! (one of the two would revert). A longer buffer means fewer such reverts but keeps
+//! the seller's funds locked that much longer after expiry.
+//! DRY_RUN=true only logs which trades (and chains) would be cancelled: no
+//! transactions are sent and the DB is not updated.
 
 use std::sync::Arc;
-use std::collections::HashMap;
-use std::time::Duration;
-use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
-use lyncz_relay::{Config, Database};
-use lyncz_relay::blockchain::client::EthereumClient;
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use lyncz_relay::{ChainConfig, Config, Database};
+use async_trait::async_trait;
+use lyncz_relay::blockchain;
+use lyncz_relay::blockchain::client::{EthereumClient, EthereumClientError};
+use lyncz_relay::blockchain::gas_budget::{GasBudget, GasBudgetMonitor};
+use lyncz_relay::blockchain::gas_ceiling::GasCeiling;
+use lyncz_relay::blockchain::relayer_balance::{self, LowBalanceThresholds};
+use ethers::types::{H256, U256};
+use lyncz_relay::db::models::{DbGasCost, DbTrade};
+use lyncz_relay::email::EmailService;
+use lyncz_relay::webhooks::{TradeWebhookPayload, WebhookSender, WebhookTradeStatus};
 
-/// Check interval for expired trades (30 seconds)
-const CHECK_INTERVAL_SECS: u64 = 30;
+/// Default check interval for expired trades (30 seconds)
+const DEFAULT_CHECK_INTERVAL_SECS: u64 = 30;
+
+/// Default grace period after expiry before a trade is cancelled
+const DEFAULT_EXPIRY_BUFFER_SECS: u64 = 30;
+
+/// Default max trades cancelled in one pass
+const DEFAULT_MAX_PER_PASS: usize = 100;
+
+/// Back-to-back passes allowed while draining a backlog before sleeping anyway
+const MAX_CATCH_UP_PASSES: u32 = 10;
+
+/// Default number of trades cancelled per batch transaction
+const DEFAULT_BATCH_SIZE: usize = 20;
+
+/// Intrinsic gas of a transaction - paid once per batch instead of once per trade
+const TX_BASE_GAS: u64 = 21_000;
+
+/// Retry backoff after a failed cancellation: 30s, 60s, 120s, ... capped at 10 minutes
+const RETRY_BACKOFF_BASE_SECS: u64 = 30;
+const RETRY_BACKOFF_MAX_SECS: u64 = 600;
+
+/// Consecutive failures before a trade is flagged for manual review
+const MAX_CANCEL_ATTEMPTS: u32 = 5;
+
+/// Selector of LyncZEscrow's TradeNotPending() error (trade already settled or cancelled)
+const TRADE_NOT_PENDING_SELECTOR: &str = "0x5f3f6cfc";
+
+/// Auto-cancel tuning, loaded from env (chain setup comes from the shared Config)
+struct AutoCancelConfig {
+    /// Seconds between passes (AUTO_CANCEL_INTERVAL_SECS)
+    check_interval_secs: u64,
+    /// Trades cancelled per batch transaction (AUTO_CANCEL_BATCH_SIZE)
+    batch_size: usize,
+    /// Max trades attempted in one pass (AUTO_CANCEL_MAX_PER_PASS)
+    max_per_pass: usize,
+    /// Seconds a trade must be expired before it's cancelled (AUTO_CANCEL_EXPIRY_BUFFER_SECS, 0 = none)
+    expiry_buffer_secs: u64,
+    /// Only log what would be cancelled (DRY_RUN)
+    dry_run: bool,
+    gas: GasCeiling,
+}
+
+impl AutoCancelConfig {
+    fn from_env(chains: &[ChainConfig]) -> Self {
+        Self::from_vars(chains, |key| std::env::var(key).ok())
+    }
+
+    fn from_vars(chains: &[ChainConfig], var: impl Fn(&str) -> Option<String>) -> Self {
+        let positive = |key: &str| var(key).and_then(|s| s.parse::<u64>().ok()).filter(|&n| n > 0);
+
+        Self {
+            check_interval_secs: positive("AUTO_CANCEL_INTERVAL_SECS").unwrap_or(DEFAULT_CHECK_INTERVAL_SECS),
+            batch_size: positive("AUTO_CANCEL_BATCH_SIZE").map_or(DEFAULT_BATCH_SIZE, |n| n as usize),
+            max_per_pass: positive("AUTO_CANCEL_MAX_PER_PASS").map_or(DEFAULT_MAX_PER_PASS, |n| n as usize),
+            expiry_buffer_secs: var("AUTO_CANCEL_EXPIRY_BUFFER_SECS")
+                .and_then(|s| s.parse::<u64>().ok())
+                .unwrap_or(DEFAULT_EXPIRY_BUFFER_SECS),
+            dry_run: var("DRY_RUN").map(|v| v == "true" || v == "1").unwrap_or(false),
+            gas: GasCeiling::from_vars(chains, &var),
+        }
+    }
+
+    fn log_summary(&self) {
+        if self.dry_run {
+            tracing::warn!("🧪 DRY_RUN enabled: expired trades are only logged, nothing is cancelled");
+        }
+        for (chain_id, ceiling) in &self.gas.ceilings_wei {
+            tracing::info!("⛽ Chain {} cancel gas ceiling: {:.3} gwei (max deferral {}s)", 
+                chain_id, *ceiling as f64 / 1e9, self.gas.max_deferral_secs);
+        }
+    }
+}
+
+/// The chain calls the cancellation loop makes (EthereumClient in production)
+#[async_trait]
+trait CancelClient: Send + Sync {
+    async fn get_gas_price(&self) -> Result<U256, EthereumClientError>;
+    async fn estimate_cancel_gas(&self, trade_id: [u8; 32]) -> Result<U256, EthereumClientError>;
+    async fn cancel_expired_trade(&self, trade_id: [u8; 32], gas_estimate: U256) -> Result<(H256, U256, U256), EthereumClientError>;
+    async fn cancel_expired_trades_batch(&self, trade_ids: &[[u8; 32]]) -> Result<(H256, U256, U256), EthereumClientError>;
+}
+
+#[async_trait]
+impl CancelClient for EthereumClient {
+    async fn get_gas_price(&self) -> Result<U256, EthereumClientError> {
+        EthereumClient::get_gas_price(self).await
+    }
+
+    async fn estimate_cancel_gas(&self, trade_id: [u8; 32]) -> Result<U256, EthereumClientError> {
+        EthereumClient::estimate_cancel_gas(self, trade_id).await
+    }
+
+    async fn cancel_expired_trade(&self, trade_id: [u8; 32], gas_estimate: U256) -> Result<(H256, U256, U256), EthereumClientError> {
+        EthereumClient::cancel_expired_trade(self, trade_id, gas_estimate).await
+    }
+
+    async fn cancel_expired_trades_batch(&self, trade_ids: &[[u8; 32]]) -> Result<(H256, U256, U256), EthereumClientError> {
+        EthereumClient::cancel_expired_trades_batch(self, trade_ids).await
+    }
+}
+
+/// Expired trades grouped by chain: chain_id -> [(trade, trade_id_bytes)]
+type TradesByChain = BTreeMap<u64, Vec<(DbTrade, [u8; 32])>>;
+
+/// Retry state for a trade whose cancellation failed (or was skipped: attempts stays 0)
+struct CancelRetry {
+    attempts: u32,
+    next_eligible: Instant,
+}
 
 /// Status codes matching the smart contract (from LyncZEscrow.sol enum TradeStatus)
 #[allow(dead_code)]
@@ -25,13 +167,7 @@ const TRADE_STATUS_EXPIRED: i32 = 2;  // Trade expired, tokens returned to order
 
 #[tokio::main]
 async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    tracing_subscriber::registry()
-        .with(
-            tracing_subscriber::EnvFilter::try_from_default_env()
-                .unwrap_or_else(|_| "info,lyncz_relay=debug".into()),
-        )
-        .with(tracing_subscriber::fmt::layer())
-        .init();
+    lyncz_relay::telemetry::init();
 
     tracing::info!("🕐 Starting LyncZ Auto-Cancellation Service");
 
@@ -39,65 +175,73 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     config.log_summary();
 
     // Connect to database
-    let db = Database::new(&config.database_url).await?;
+    let db = Database::new(&config.database_url, config.database_replica_url.as_deref(), &config.db_pool).await?;
     tracing::info!("✅ Database connected");
 
     // Initialize blockchain clients for all configured chains
-    let private_key = config.relayer_private_key.as_ref()
-        .ok_or("RELAYER_PRIVATE_KEY not set")?;
-    
-    let mut clients: HashMap<u64, Arc<EthereumClient>> = HashMap::new();
-    
-    for chain_config in &config.chains {
-        let escrow_address: ethers::types::Address = chain_config.escrow_address.parse()?;
-        
-        match EthereumClient::new(
-            &chain_config.rpc_url,
-            private_key,
-            escrow_address,
-            chain_config.chain_id,
-        ).await {
-            Ok(client) => {
-                let client = Arc::new(client);
-                tracing::info!("✅ Blockchain client for {} (chain {}), relayer: {:?}", 
-                    chain_config.name, chain_config.chain_id, client.relayer_address());
-                clients.insert(chain_config.chain_id, client);
-            }
-            Err(e) => {
-                tracing::warn!("⚠️ Failed to init client for {} (chain {}): {}", 
-                    chain_config.name, chain_config.chain_id, e);
-            }
-        }
+    if config.relayer_private_key.is_none() {
+        return Err("RELAYER_PRIVATE_KEY not set".into());
     }
-    
+    let clients = blockchain::init_clients(&config).await;
     if clients.is_empty() {
         return Err("No blockchain clients initialized".into());
     }
 
-    // Track total gas spent for logging
+    let settings = AutoCancelConfig::from_env(&config.chains);
+    settings.log_summary();
+    let webhooks = WebhookSender::from_env();
+    let mut gas_budget = GasBudgetMonitor::new(GasBudget::from_env(&config.chains));
+    let alert_email = EmailService::from_env();
+    for (chain_id, budget) in &gas_budget.budget().budgets_wei {
+        tracing::info!("⛽ Chain {} daily gas budget: {:.6} ETH (warning at {}%)", 
+            chain_id, *budget as f64 / 1e18, gas_budget.budget().warn_percent);
+    }
+    let balance_thresholds = LowBalanceThresholds::from_env(&config.chains);
+    for balance in relayer_balance::check_balances(&clients, &balance_thresholds).await {
+        tracing::info!("💰 Chain {} relayer {}: {} ETH", balance.chain_id, balance.relayer_address,
+            balance.balance_eth.as_deref().unwrap_or("?"));
+    }
+
+    // Track total gas spent (and saved by batching) for logging
     let mut total_gas_spent_wei: u128 = 0;
+    let mut total_gas_saved_wei: u128 = 0;
     let mut total_trades_cancelled: u64 = 0;
+    
+    // Per-trade retry state (trade_id -> attempts, next eligible time)
+    let mut retry_state: HashMap<String, CancelRetry> = HashMap::new();
 
-    tracing::info!("🔄 Starting monitoring loop (check every {} seconds, {} chain(s))", 
-        CHECK_INTERVAL_SECS, clients.len());
+    tracing::info!("🔄 Starting monitoring loop (check every {} seconds, {} chain(s), batch size {}, max {} per pass, {}s expiry buffer)", 
+        settings.check_interval_secs, clients.len(), settings.batch_size, settings.max_per_pass, settings.expiry_buffer_secs);
+
+    // Consecutive passes run without sleeping (backlog catch-up)
+    let mut catch_up_passes: u32 = 0;
 
     loop {
-        match check_and_cancel_expired(&db, &clients).await {
-            Ok((cancelled_count, gas_spent)) => {
+        let mut run_again = false;
+
+        match check_and_cancel_expired(&db, &clients, &settings, &mut retry_state, webhooks.as_ref()).await {
+            Ok((cancelled_count, gas_spent, gas_saved)) => {
+                run_again = should_run_again(cancelled_count, settings.max_per_pass, catch_up_passes);
+
                 if cancelled_count > 0 {
                     total_trades_cancelled += cancelled_count;
                     total_gas_spent_wei += gas_spent;
+                    total_gas_saved_wei += gas_saved;
                     
                     let gas_eth = gas_spent as f64 / 1e18;
                     let total_gas_eth = total_gas_spent_wei as f64 / 1e18;
                     
                     tracing::info!(
-                        "✅ Cancelled {} trades (gas: {:.6} ETH) | Total: {} trades, {:.6} ETH",
+                        "✅ Cancelled {} trades (gas: {:.6} ETH, saved by batching: {:.6} ETH) | Total: {} trades, {:.6} ETH spent, {:.6} ETH saved",
                         cancelled_count,
                         gas_eth,
+                        gas_saved as f64 / 1e18,
                         total_trades_cancelled,
-                        total_gas_eth
+                        total_gas_eth,
+                        total_gas_saved_wei as f64 / 1e18
                     );
+                    gas_budget.check(&db, alert_email.as_deref()).await;
+                    relayer_balance::check_balances(&clients, &balance_thresholds).await;
                 }
             }
             Err(e) => {
@@ -105,77 +249,407 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             }
         }
 
-        tokio::time::sleep(Duration::from_secs(CHECK_INTERVAL_SECS)).await;
+        if run_again {
+            catch_up_passes += 1;
+            tracing::info!("⏩ Pass hit the {} trade limit, running catch-up pass {}/{}", 
+                settings.max_per_pass, catch_up_passes, MAX_CATCH_UP_PASSES);
+            continue;
+        }
+
+        catch_up_passes = 0;
+        tokio::time::sleep(Duration::from_secs(settings.check_interval_secs)).await;
     }
 }
 
+/// Whether to start the next pass immediately: the last one cancelled a full
+/// `max_per_pass` (so more are likely waiting) and the catch-up cap isn't reached
+fn should_run_again(cancelled: u64, max_per_pass: usize, catch_up_passes: u32) -> bool {
+    cancelled >= max_per_pass as u64 && catch_up_passes < MAX_CATCH_UP_PASSES
+}
+
 /// Check for expired trades and cancel them using the correct chain's client
-/// Returns (number_cancelled, total_gas_spent_wei)
-async fn check_and_cancel_expired(
+/// At most `settings.max_per_pass` trades are attempted per call, oldest expiry first.
+/// Trades are grouped by chain and cancelled in batches of `settings.batch_size`; if a batch
+/// reverts (e.g. one trade was settled meanwhile) its trades are cancelled one by one.
+/// Trades still backing off from an earlier failure are skipped until eligible, and
+/// trades on a chain whose gas price is above its ceiling are deferred (see GasCeiling).
+/// In dry-run the grouped trades are only logged (see log_dry_run) and nothing is cancelled.
+/// Returns (number_cancelled, total_gas_spent_wei, estimated_gas_saved_wei)
+async fn check_and_cancel_expired<C: CancelClient>(
     db: &Database,
-    clients: &HashMap<u64, Arc<EthereumClient>>,
-) -> Result<(u64, u128), Box<dyn std::error::Error + Send + Sync>> {
+    clients: &HashMap<u64, Arc<C>>,
+    settings: &AutoCancelConfig,
+    retry_state: &mut HashMap<String, CancelRetry>,
+    webhooks: Option<&Arc<WebhookSender>>,
+) -> Result<(u64, u128, u128), Box<dyn std::error::Error + Send + Sync>> {
     // Get all expired pending trades from database (across all chains)
-    let expired_trades = db.get_expired_pending_trades().await?;
+    let expired_trades = db.get_expired_pending_trades(settings.expiry_buffer_secs).await?;
+    
+    // Forget retry state for trades that are no longer pending (settled, cancelled, flagged)
+    retry_state.retain(|trade_id, _| expired_trades.iter().any(|t| &t.trade_id == trade_id));
     
     if expired_trades.is_empty() {
-        return Ok((0, 0));
+        return Ok((0, 0, 0));
     }
 
-    tracing::info!("📋 Found {} expired trades to cancel", expired_trades.len());
+    let now = Instant::now();
+    let total_expired = expired_trades.len();
+    let mut expired_trades: Vec<_> = expired_trades
+        .into_iter()
+        .filter(|t| retry_state.get(&t.trade_id).is_none_or(|r| r.next_eligible <= now))
+        .collect();
+    let backing_off = total_expired - expired_trades.len();
+
+    // Already ordered by expiry, so the oldest go first
+    if expired_trades.len() > settings.max_per_pass {
+        tracing::info!("📋 {} eligible expired trades, cancelling the oldest {} this pass", 
+            expired_trades.len(), settings.max_per_pass);
+        expired_trades.truncate(settings.max_per_pass);
+    }
+
+    if expired_trades.is_empty() {
+        tracing::debug!("⏳ {} expired trades backing off after failures", backing_off);
+        return Ok((0, 0, 0));
+    }
+
+    tracing::info!("📋 Found {} expired trades to cancel ({} backing off)", expired_trades.len(), backing_off);
+
+    let by_chain = group_by_chain(expired_trades)?;
+
+    if settings.dry_run {
+        log_dry_run(&by_chain, clients);
+        return Ok((0, 0, 0));
+    }
 
     let mut cancelled_count = 0u64;
+    let mut skipped_count = 0u64;
     let mut total_gas_wei = 0u128;
+    let mut gas_saved_wei = 0u128;
 
-    for trade in expired_trades {
-        let trade_id = &trade.trade_id;
-        let trade_chain_id = trade.chain_id as u64;
-        
-        // Get the correct blockchain client for this trade's chain
+    for (trade_chain_id, trades) in by_chain {
+        // Get the correct blockchain client for this chain
         let eth_client = match clients.get(&trade_chain_id) {
             Some(client) => client,
             None => {
-                tracing::warn!("⚠️ No client for chain {} (trade {}), skipping", trade_chain_id, trade_id);
+                tracing::warn!("⚠️ No client for chain {} ({} trades), skipping", trade_chain_id, trades.len());
                 continue;
             }
         };
-        
-        // Parse trade_id to bytes32
-        let trade_id_bytes = parse_trade_id(trade_id)?;
-        
-        tracing::info!("🔄 Cancelling trade {} on chain {}", trade_id, trade_chain_id);
-        
-        match eth_client.cancel_expired_trade(trade_id_bytes).await {
-            Ok((tx_hash, gas_cost)) => {
-                tracing::info!(
-                    "✅ Trade {} cancelled on chain {}: tx={:#x}, gas_cost={} wei ({:.6} ETH)",
-                    trade_id,
-                    trade_chain_id,
-                    tx_hash,
-                    gas_cost,
-                    gas_cost.as_u128() as f64 / 1e18
-                );
-                
-                // Update database status
-                if let Err(e) = db.update_trade_status(trade_id, TRADE_STATUS_EXPIRED).await {
-                    tracing::warn!("⚠️ Failed to update DB status for {}: {}", trade_id, e);
+
+        // Defer while gas is above this chain's ceiling (except trades waiting too long)
+        let trades = match settings.gas.ceilings_wei.get(&trade_chain_id) {
+            Some(&ceiling) => {
+                let now = chrono::Utc::now().timestamp();
+                match without_gas_deferrals(eth_client.as_ref(), trade_chain_id, &settings.gas, ceiling, trades, now).await {
+                    Some(trades) => trades,
+                    None => continue,
                 }
-                
-                cancelled_count += 1;
-                total_gas_wei += gas_cost.as_u128();
             }
-            Err(e) => {
-                tracing::warn!(
-                    "⚠️ Failed to cancel trade {} on chain {}: {}",
-                    trade_id,
-                    trade_chain_id,
-                    e
-                );
+            None => trades,
+        };
+
+        for batch in trades.chunks(settings.batch_size) {
+            if batch.len() > 1 {
+                let ids: Vec<[u8; 32]> = batch.iter().map(|(_, bytes)| *bytes).collect();
+                tracing::info!("🔄 Cancelling {} trades on chain {} in one batch", batch.len(), trade_chain_id);
+
+                match eth_client.cancel_expired_trades_batch(&ids).await {
+                    Ok((tx_hash, gas_cost, gas_price)) => {
+                        let saved = batch_gas_saved_wei(batch.len(), gas_price.as_u128());
+                        let row = gas_cost_row(trade_chain_id, "cancel_batch", None, tx_hash, gas_cost, gas_price);
+                        let trade_ids: Vec<String> = batch.iter().map(|(trade, _)| trade.trade_id.clone()).collect();
+                        if let Err(e) = db.record_batch_gas_cost(&row, &trade_ids).await {
+                            tracing::warn!("⚠️ Failed to record gas cost for {}: {}", row.tx_hash, e);
+                        }
+                        tracing::info!(
+                            "✅ {} trades cancelled on chain {}: tx={:#x}, gas_cost={} wei ({:.6} ETH), saved ~{} wei vs per-trade",
+                            batch.len(),
+                            trade_chain_id,
+                            tx_hash,
+                            gas_cost,
+                            gas_cost.as_u128() as f64 / 1e18,
+                            saved
+                        );
+
+                        for (trade, _) in batch {
+                            retry_state.remove(&trade.trade_id);
+                            if let Err(e) = db.update_trade_status(&trade.trade_id, TRADE_STATUS_EXPIRED).await {
+                                tracing::warn!("⚠️ Failed to update DB status for {}: {}", trade.trade_id, e);
+                            }
+                            notify_expired(webhooks, trade, tx_hash);
+                        }
+
+                        cancelled_count += batch.len() as u64;
+                        total_gas_wei += gas_cost.as_u128();
+                        gas_saved_wei += saved;
+                        continue;
+                    }
+                    Err(e) => {
+                        tracing::warn!(
+                            "⚠️ Batch cancel failed on chain {}, falling back to per-trade: {}",
+                            trade_chain_id,
+                            e
+                        );
+                    }
+                }
+            }
+
+            for (trade, trade_id_bytes) in batch {
+                match cancel_single(db, eth_client.as_ref(), &trade.trade_id, *trade_id_bytes, trade_chain_id).await {
+                    Ok(SingleCancel::Sent { tx_hash, gas_cost, .. }) => {
+                        retry_state.remove(&trade.trade_id);
+                        notify_expired(webhooks, trade, tx_hash);
+                        cancelled_count += 1;
+                        total_gas_wei += gas_cost.as_u128();
+                    }
+                    // Settled or cancelled meanwhile: not a failure, the DB catches up from
+                    // events; re-checked after the longest backoff rather than every pass
+                    Ok(SingleCancel::Skipped(_)) => {
+                        retry_state.insert(trade.trade_id.clone(), CancelRetry {
+                            attempts: 0,
+                            next_eligible: Instant::now() + Duration::from_secs(RETRY_BACKOFF_MAX_SECS),
+                        });
+                        skipped_count += 1;
+                    }
+                    Err(e) => record_cancel_failure(db, retry_state, &trade.trade_id, &e).await,
+                }
             }
         }
     }
 
-    Ok((cancelled_count, total_gas_wei))
+    if skipped_count > 0 {
+        tracing::info!("⏭️ Skipped {} trades no longer cancellable on-chain", skipped_count);
+    }
+
+    Ok((cancelled_count, total_gas_wei, gas_saved_wei))
+}
+
+/// The trades to cancel now on a chain with a gas ceiling: those `GasCeiling::should_defer`
+/// holds back are logged and left for a later tick. None when the gas price can't be
+/// read, in which case the whole chain waits.
+async fn without_gas_deferrals<C: CancelClient + ?Sized>(
+    eth_client: &C,
+    chain_id: u64,
+    gas: &GasCeiling,
+    ceiling_wei: u128,
+    trades: Vec<(DbTrade, [u8; 32])>,
+    now: i64,
+) -> Option<Vec<(DbTrade, [u8; 32])>> {
+    let gas_price = match eth_client.get_gas_price().await {
+        Ok(gas_price) => gas_price.as_u128(),
+        Err(e) => {
+            tracing::warn!("⚠️ Failed to get gas price for chain {}, deferring: {}", chain_id, e);
+            return None;
+        }
+    };
+    let (deferred, trades): (Vec<_>, Vec<_>) = trades.into_iter().partition(|(trade, _)| {
+        gas.should_defer(gas_price, ceiling_wei, now - trade.expires_at)
+    });
+    if !deferred.is_empty() {
+        tracing::info!(
+            "⛽ Deferred {} cancellations on chain {}: gas {:.3} gwei > ceiling {:.3} gwei",
+            deferred.len(),
+            chain_id,
+            gas_price as f64 / 1e9,
+            ceiling_wei as f64 / 1e9
+        );
+    }
+    Some(trades)
+}
+
+/// Group trades by chain, oldest expiry first within each chain
+fn group_by_chain(trades: Vec<DbTrade>) -> Result<TradesByChain, Box<dyn std::error::Error + Send + Sync>> {
+    let mut by_chain = TradesByChain::new();
+    for trade in trades {
+        let trade_id_bytes = parse_trade_id(&trade.trade_id)?;
+        by_chain
+            .entry(trade.chain_id as u64)
+            .or_default()
+            .push((trade, trade_id_bytes));
+    }
+    Ok(by_chain)
+}
+
+/// Log what a real round would cancel, per chain. Touches neither the clients nor the DB;
+/// only checks that each chain has a client so a misconfigured chain shows up too.
+fn log_dry_run<C>(by_chain: &TradesByChain, clients: &HashMap<u64, Arc<C>>) {
+    for (chain_id, trades) in by_chain {
+        if !clients.contains_key(chain_id) {
+            tracing::warn!("🧪 [dry-run] No client for chain {} ({} trades), would skip", chain_id, trades.len());
+            continue;
+        }
+        tracing::info!("🧪 [dry-run] Would cancel {} trades on chain {}", trades.len(), chain_id);
+        for (trade, _) in trades {
+            tracing::info!("🧪 [dry-run]   {} (expired at {})", trade.trade_id, trade.expires_at);
+        }
+    }
+}
+
+/// Fire the `trade.expired` webhook for a cancelled trade (no-op when webhooks are disabled)
+fn notify_expired(webhooks: Option<&Arc<WebhookSender>>, trade: &DbTrade, tx_hash: H256) {
+    if let Some(webhooks) = webhooks {
+        webhooks.dispatch(TradeWebhookPayload::from_trade(
+            trade,
+            WebhookTradeStatus::Expired,
+            Some(format!("{:#x}", tx_hash)),
+        ));
+    }
+}
+
+/// Outcome of estimate_and_cancel
+#[derive(Debug, PartialEq)]
+enum SingleCancel {
+    /// Confirmed cancellation and the gas it was estimated at
+    Sent { tx_hash: H256, gas_cost: U256, gas_price: U256, gas_estimate: U256 },
+    /// The gas estimate reverted with TradeNotPending (decoded reason); nothing was sent
+    Skipped(String),
+}
+
+/// Estimate the cancellation and only send it when the estimate succeeds, so a
+/// cancel that would revert never costs gas. Only a TradeNotPending revert is a skip;
+/// any other revert (e.g. TradeNotExpired, an unauthorized relayer) is an error.
+async fn estimate_and_cancel<C: CancelClient + ?Sized>(
+    eth_client: &C,
+    trade_id_bytes: [u8; 32],
+) -> Result<SingleCancel, EthereumClientError> {
+    let gas_estimate = match eth_client.estimate_cancel_gas(trade_id_bytes).await {
+        Ok(estimate) => estimate,
+        Err(EthereumClientError::Revert(reason)) if is_not_pending_revert(&reason) => {
+            return Ok(SingleCancel::Skipped(reason));
+        }
+        Err(e) => return Err(e),
+    };
+    let (tx_hash, gas_cost, gas_price) = eth_client.cancel_expired_trade(trade_id_bytes, gas_estimate).await?;
+    Ok(SingleCancel::Sent { tx_hash, gas_cost, gas_price, gas_estimate })
+}
+
+/// Whether a revert reason is TradeNotPending (raw selector, or the name if decoded)
+fn is_not_pending_revert(reason: &str) -> bool {
+    reason.contains(TRADE_NOT_PENDING_SELECTOR) || reason.contains("TradeNotPending")
+}
+
+/// Cancel one expired trade and mark it expired in the DB
+/// Returns what was sent (or skipped because the gas estimate reverted with
+/// TradeNotPending), or the error message if the cancellation failed
+#[tracing::instrument(skip_all, fields(chain_id = trade_chain_id, trade_id = %trade_id))]
+async fn cancel_single<C: CancelClient + ?Sized>(
+    db: &Database,
+    eth_client: &C,
+    trade_id: &str,
+    trade_id_bytes: [u8; 32],
+    trade_chain_id: u64,
+) -> Result<SingleCancel, String> {
+    tracing::info!("🔄 Cancelling trade {} on chain {}", trade_id, trade_chain_id);
+    
+    match estimate_and_cancel(eth_client, trade_id_bytes).await {
+        Ok(sent @ SingleCancel::Sent { tx_hash, gas_cost, gas_price, gas_estimate }) => {
+            tracing::info!(
+                "✅ Trade {} cancelled on chain {}: tx={:#x}, gas_cost={} wei ({:.6} ETH), estimated {} gas",
+                trade_id,
+                trade_chain_id,
+                tx_hash,
+                gas_cost,
+                gas_cost.as_u128() as f64 / 1e18,
+                gas_estimate
+            );
+            
+            // Update database status
+            if let Err(e) = db.update_trade_status(trade_id, TRADE_STATUS_EXPIRED).await {
+                tracing::warn!("⚠️ Failed to update DB status for {}: {}", trade_id, e);
+            }
+            let row = gas_cost_row(trade_chain_id, "cancel", Some(trade_id), tx_hash, gas_cost, gas_price)
+                .with_gas_estimate(gas_estimate.as_u64());
+            record_gas_cost(db, row).await;
+            
+            Ok(sent)
+        }
+        Ok(SingleCancel::Skipped(reason)) => {
+            tracing::info!(
+                "⏭️ Not sending cancel for trade {} on chain {}: {}",
+                trade_id,
+                trade_chain_id,
+                reason
+            );
+            Ok(SingleCancel::Skipped(reason))
+        }
+        Err(e) => {
+            tracing::warn!(
+                "⚠️ Failed to cancel trade {} on chain {}: {}",
+                trade_id,
+                trade_chain_id,
+                e
+            );
+            Err(e.to_string())
+        }
+    }
+}
+
+/// `gas_costs` row for a confirmed cancellation
+fn gas_cost_row(
+    chain_id: u64,
+    operation: &str,
+    trade_id: Option<&str>,
+    tx_hash: H256,
+    gas_cost: U256,
+    gas_price: U256,
+) -> DbGasCost {
+    let gas_used = if gas_price.is_zero() { 0 } else { (gas_cost / gas_price).as_u64() };
+    DbGasCost::from_receipt(chain_id, operation, trade_id, format!("{:#x}", tx_hash), gas_used, gas_price.as_u128())
+}
+
+/// Record a confirmed cancellation's gas cost in `gas_costs` (feeds the daily gas budget)
+async fn record_gas_cost(db: &Database, row: DbGasCost) {
+    if let Err(e) = db.record_gas_cost(&row).await {
+        tracing::warn!("⚠️ Failed to record gas cost for {}: {}", row.tx_hash, e);
+    }
+}
+
+/// Record a failed cancellation: schedule the next attempt with exponential backoff,
+/// or flag the trade in the DB once it has failed MAX_CANCEL_ATTEMPTS times in a row
+#[tracing::instrument(skip_all, fields(trade_id = %trade_id))]
+async fn record_cancel_failure(
+    db: &Database,
+    retry_state: &mut HashMap<String, CancelRetry>,
+    trade_id: &str,
+    error: &str,
+) {
+    let attempts = retry_state.get(trade_id).map_or(0, |r| r.attempts) + 1;
+
+    if attempts >= MAX_CANCEL_ATTEMPTS {
+        tracing::error!(
+            "🚩 Trade {} failed to cancel {} times, flagging for manual review: {}",
+            trade_id,
+            attempts,
+            error
+        );
+        if let Err(e) = db.mark_trade_cancel_failed(trade_id, error).await {
+            tracing::warn!("⚠️ Failed to flag trade {} in DB: {}", trade_id, e);
+        }
+        retry_state.remove(trade_id);
+        return;
+    }
+
+    let delay = retry_backoff(attempts);
+    tracing::info!("⏳ Trade {} will be retried in {}s (attempt {}/{})", 
+        trade_id, delay.as_secs(), attempts, MAX_CANCEL_ATTEMPTS);
+    retry_state.insert(trade_id.to_string(), CancelRetry {
+        attempts,
+        next_eligible: Instant::now() + delay,
+    });
+}
+
+/// Backoff before the next attempt after `attempts` consecutive failures
+/// (30s, 60s, 120s, ... capped at RETRY_BACKOFF_MAX_SECS)
+fn retry_backoff(attempts: u32) -> Duration {
+    let exponent = attempts.saturating_sub(1).min(16);
+    let secs = RETRY_BACKOFF_BASE_SECS.saturating_mul(1 << exponent);
+    Duration::from_secs(secs.min(RETRY_BACKOFF_MAX_SECS))
+}
+
+/// Estimated gas saved by cancelling `batch_len` trades in one transaction
+/// instead of one each: every trade after the first avoids the intrinsic tx gas.
+fn batch_gas_saved_wei(batch_len: usize, gas_price_wei: u128) -> u128 {
+    batch_len.saturating_sub(1) as u128 * TX_BASE_GAS as u128 * gas_price_wei
 }
 
 /// Parse trade_id string (0x...) to [u8; 32]
@@ -192,3 +666,374 @@ fn parse_trade_id(trade_id: &str) -> Result<[u8; 32], Box<dyn std::error::Error
     Ok(result)
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Estimates fail with `estimate_error` (when set); counts estimates and sends
+    #[derive(Default)]
+    struct EstimatingClient {
+        estimate_error: Option<fn() -> EthereumClientError>,
+        estimates: AtomicUsize,
+        sends: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl CancelClient for EstimatingClient {
+        async fn get_gas_price(&self) -> Result<U256, EthereumClientError> {
+            Ok(U256::zero())
+        }
+
+        async fn estimate_cancel_gas(&self, _trade_id: [u8; 32]) -> Result<U256, EthereumClientError> {
+            self.estimates.fetch_add(1, Ordering::SeqCst);
+            match self.estimate_error {
+                Some(error) => Err(error()),
+                None => Ok(U256::from(61_000)),
+            }
+        }
+
+        async fn cancel_expired_trade(&self, _trade_id: [u8; 32], _gas_estimate: U256) -> Result<(H256, U256, U256), EthereumClientError> {
+            self.sends.fetch_add(1, Ordering::SeqCst);
+            Ok((H256::repeat_byte(0x11), U256::from(52_000u64 * 10), U256::from(10)))
+        }
+
+        async fn cancel_expired_trades_batch(&self, _trade_ids: &[[u8; 32]]) -> Result<(H256, U256, U256), EthereumClientError> {
+            self.sends.fetch_add(1, Ordering::SeqCst);
+            Ok((H256::zero(), U256::zero(), U256::zero()))
+        }
+    }
+
+    /// Reports a fixed gas price (None = the RPC call fails); never expects a send
+    struct GasPriceClient(Option<u64>);
+
+    #[async_trait]
+    impl CancelClient for GasPriceClient {
+        async fn get_gas_price(&self) -> Result<U256, EthereumClientError> {
+            self.0.map(U256::from).ok_or_else(|| EthereumClientError::ProviderError("timeout".to_string()))
+        }
+
+        async fn estimate_cancel_gas(&self, _trade_id: [u8; 32]) -> Result<U256, EthereumClientError> {
+            unreachable!("gas deferral doesn't estimate")
+        }
+
+        async fn cancel_expired_trade(&self, _trade_id: [u8; 32], _gas_estimate: U256) -> Result<(H256, U256, U256), EthereumClientError> {
+            unreachable!("gas deferral doesn't send")
+        }
+
+        async fn cancel_expired_trades_batch(&self, _trade_ids: &[[u8; 32]]) -> Result<(H256, U256, U256), EthereumClientError> {
+            unreachable!("gas deferral doesn't send")
+        }
+    }
+
+    fn chain(name: &str, chain_id: u64) -> ChainConfig {
+        ChainConfig {
+            chain_id,
+            rpc_url: "https://rpc.example".to_string(),
+            escrow_address: "0x0000000000000000000000000000000000000001".to_string(),
+            name: name.to_string(),
+            confirmations: 2,
+        }
+    }
+
+    fn expired_trade(trade_id_byte: u8, chain_id: i32) -> DbTrade {
+        DbTrade {
+            trade_id: format!("0x{}", hex::encode([trade_id_byte; 32])),
+            order_id: format!("0x{}", hex::encode([0xaa; 32])),
+            chain_id,
+            status: TRADE_STATUS_PENDING,
+            expires_at: 1_700_000_900,
+            ..DbTrade::default()
+        }
+    }
+
+    #[test]
+    fn test_group_by_chain() {
+        let by_chain = group_by_chain(vec![
+            expired_trade(1, 8453),
+            expired_trade(2, 1),
+            expired_trade(3, 8453),
+        ])
+        .unwrap();
+        let ids = |chain_id: u64| by_chain[&chain_id].iter().map(|(_, id)| id[0]).collect::<Vec<_>>();
+        assert_eq!((ids(8453), ids(1)), (vec![1, 3], vec![2]));
+
+        assert!(group_by_chain(vec![DbTrade { trade_id: "0x1234".to_string(), ..expired_trade(1, 8453) }]).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_reverting_estimate_skips_send() {
+        let not_pending = EstimatingClient {
+            estimate_error: Some(|| EthereumClientError::Revert("cancelExpiredTrade gas estimation: execution reverted: 0x5f3f6cfc".to_string())),
+            ..Default::default()
+        };
+        let outcome = estimate_and_cancel(&not_pending, [1; 32]).await.unwrap();
+        assert_eq!(outcome, SingleCancel::Skipped("cancelExpiredTrade gas estimation: execution reverted: 0x5f3f6cfc".to_string()));
+        assert_eq!(not_pending.sends.load(Ordering::SeqCst), 0);
+
+        // Any other revert (here TradeNotExpired) is an error to retry and eventually flag
+        let not_expired = EstimatingClient {
+            estimate_error: Some(|| EthereumClientError::Revert("cancelExpiredTrade gas estimation: execution reverted: 0xe170cd29".to_string())),
+            ..Default::default()
+        };
+        assert!(estimate_and_cancel(&not_expired, [1; 32]).await.is_err());
+        assert_eq!(not_expired.sends.load(Ordering::SeqCst), 0);
+
+        // An RPC failure isn't a revert: reported as an error, still nothing sent
+        let unreachable = EstimatingClient {
+            estimate_error: Some(|| EthereumClientError::ProviderError("timeout".to_string())),
+            ..Default::default()
+        };
+        assert!(estimate_and_cancel(&unreachable, [1; 32]).await.is_err());
+        assert_eq!(unreachable.sends.load(Ordering::SeqCst), 0);
+
+        // A successful estimate is sent and kept for the gas cost record
+        let ok = EstimatingClient::default();
+        match estimate_and_cancel(&ok, [1; 32]).await.unwrap() {
+            SingleCancel::Sent { gas_estimate, gas_cost, gas_price, .. } => {
+                assert_eq!(gas_estimate, U256::from(61_000));
+                let row = gas_cost_row(8453, "cancel", Some("0x01"), H256::zero(), gas_cost, gas_price)
+                    .with_gas_estimate(gas_estimate.as_u64());
+                assert_eq!((row.gas_used, row.gas_estimate), (52_000, Some(61_000)));
+            }
+            other => panic!("expected a send, got {:?}", other),
+        }
+        assert_eq!(ok.sends.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_auto_cancel_config_parsing() {
+        let chains = [chain("Base", 8453), chain("Ethereum", 1)];
+
+        let defaults = AutoCancelConfig::from_vars(&chains, |_| None);
+        assert_eq!(defaults.check_interval_secs, DEFAULT_CHECK_INTERVAL_SECS);
+        assert_eq!((defaults.batch_size, defaults.max_per_pass), (DEFAULT_BATCH_SIZE, DEFAULT_MAX_PER_PASS));
+        assert_eq!(defaults.expiry_buffer_secs, DEFAULT_EXPIRY_BUFFER_SECS);
+        assert!(!defaults.dry_run);
+        assert!(defaults.gas.ceilings_wei.is_empty());
+
+        let vars = HashMap::from([
+            ("AUTO_CANCEL_INTERVAL_SECS", "60"),
+            ("AUTO_CANCEL_BATCH_SIZE", "0"),   // not positive -> default
+            ("AUTO_CANCEL_MAX_PER_PASS", "abc"), // unparseable -> default
+            ("AUTO_CANCEL_EXPIRY_BUFFER_SECS", "0"), // buffer can be turned off
+            ("DRY_RUN", "1"),
+            ("MAX_CANCEL_GAS_PRICE_GWEI", "0.5"),
+        ]);
+        let config = AutoCancelConfig::from_vars(&chains, |key| vars.get(key).map(|v| v.to_string()));
+        assert_eq!(config.check_interval_secs, 60);
+        assert_eq!((config.batch_size, config.max_per_pass), (DEFAULT_BATCH_SIZE, DEFAULT_MAX_PER_PASS));
+        assert_eq!(config.expiry_buffer_secs, 0);
+        assert!(config.dry_run);
+        assert_eq!(config.gas.ceilings_wei[&8453], 500_000_000);
+    }
+
+    #[test]
+    fn test_batch_gas_saved() {
+        assert_eq!(batch_gas_saved_wei(1, 1_000), 0);
+        assert_eq!(batch_gas_saved_wei(0, 1_000), 0);
+        assert_eq!(batch_gas_saved_wei(20, 30_000_000), 19 * 21_000 * 30_000_000);
+    }
+
+    #[tokio::test]
+    async fn test_cancellation_deferred_above_gas_ceiling() {
+        let ceiling = 1_000_000_000; // 1 gwei
+        let now = 1_700_010_000;
+        let trades = || {
+            let recent = DbTrade { expires_at: now - 60, ..expired_trade(1, 8453) };
+            let overdue = DbTrade { expires_at: now - 7200, ..expired_trade(2, 8453) };
+            group_by_chain(vec![recent, overdue]).unwrap().remove(&8453).unwrap()
+        };
+        let ids = |trades: Vec<(DbTrade, [u8; 32])>| trades.into_iter().map(|(_, id)| id[0]).collect::<Vec<_>>();
+
+        let gas = GasCeiling { ceilings_wei: HashMap::from([(8453, ceiling)]), max_deferral_secs: 3600 };
+
+        // 2 gwei: the recent trade waits for a later tick, the overdue one goes anyway
+        let spiking = GasPriceClient(Some(2_000_000_000));
+        let kept = without_gas_deferrals(&spiking, 8453, &gas, ceiling, trades(), now).await.unwrap();
+        assert_eq!(ids(kept), [2]);
+
+        // At the ceiling nothing is deferred
+        let calm = GasPriceClient(Some(ceiling as u64));
+        let kept = without_gas_deferrals(&calm, 8453, &gas, ceiling, trades(), now).await.unwrap();
+        assert_eq!(ids(kept), [1, 2]);
+
+        // No gas price: the whole chain waits
+        assert!(without_gas_deferrals(&GasPriceClient(None), 8453, &gas, ceiling, trades(), now).await.is_none());
+    }
+
+    #[test]
+    fn test_should_run_again_while_full() {
+        // Full pass -> go again immediately
+        assert!(should_run_again(100, 100, 0));
+        assert!(should_run_again(100, 100, MAX_CATCH_UP_PASSES - 1));
+        // Partial pass -> backlog drained, sleep
+        assert!(!should_run_again(99, 100, 0));
+        assert!(!should_run_again(0, 100, 0));
+        // Still full but catch-up cap reached -> sleep
+        assert!(!should_run_again(100, 100, MAX_CATCH_UP_PASSES));
+    }
+
+    #[test]
+    fn test_retry_backoff_schedule() {
+        assert_eq!(retry_backoff(1), Duration::from_secs(30));
+        assert_eq!(retry_backoff(2), Duration::from_secs(60));
+        assert_eq!(retry_backoff(3), Duration::from_secs(120));
+        assert_eq!(retry_backoff(5), Duration::from_secs(480));
+        assert_eq!(retry_backoff(6), Duration::from_secs(RETRY_BACKOFF_MAX_SECS));
+        assert_eq!(retry_backoff(100), Duration::from_secs(RETRY_BACKOFF_MAX_SECS));
+    }
+
+    /// Tests against the DB: they need the library's fixtures, so run them with
+    /// `DATABASE_URL=postgres://... cargo test --features test-fixtures -- --ignored`
+    #[cfg(feature = "test-fixtures")]
+    mod db {
+        use super::*;
+        use std::sync::Mutex;
+        use lyncz_relay::db::models::fixtures;
+        use lyncz_relay::db::orders::{OrderRepository, PostgresOrderRepository};
+        use lyncz_relay::db::trades::{PostgresTradeRepository, TradeRepository};
+
+        /// Records the name of every chain call made through it
+        #[derive(Default)]
+        struct RecordingClient {
+            calls: Mutex<Vec<&'static str>>,
+        }
+
+        impl RecordingClient {
+            fn record(&self, call: &'static str) {
+                self.calls.lock().unwrap().push(call);
+            }
+        }
+
+        #[async_trait]
+        impl CancelClient for RecordingClient {
+            async fn get_gas_price(&self) -> Result<U256, EthereumClientError> {
+                self.record("get_gas_price");
+                Ok(U256::zero())
+            }
+
+            async fn estimate_cancel_gas(&self, _trade_id: [u8; 32]) -> Result<U256, EthereumClientError> {
+                self.record("estimate_cancel_gas");
+                Ok(U256::zero())
+            }
+
+            async fn cancel_expired_trade(&self, _trade_id: [u8; 32], _gas_estimate: U256) -> Result<(H256, U256, U256), EthereumClientError> {
+                self.record("cancel_expired_trade");
+                Ok((H256::zero(), U256::zero(), U256::zero()))
+            }
+
+            async fn cancel_expired_trades_batch(&self, _trade_ids: &[[u8; 32]]) -> Result<(H256, U256, U256), EthereumClientError> {
+                self.record("cancel_expired_trades_batch");
+                Ok((H256::zero(), U256::zero(), U256::zero()))
+            }
+        }
+
+        /// Make every backing-off trade eligible again, as if its backoff had elapsed
+        fn elapse_backoff(retry_state: &mut HashMap<String, CancelRetry>) {
+            for retry in retry_state.values_mut() {
+                retry.next_eligible = Instant::now();
+            }
+        }
+
+        /// expired_trade as a full row the DB accepts
+        fn stored_trade(trade_id_byte: u8, chain_id: i32) -> DbTrade {
+            DbTrade {
+                order_id: format!("0x{}", hex::encode([0xaa; 32])),
+                chain_id,
+                ..fixtures::trade(&format!("0x{}", hex::encode([trade_id_byte; 32])), TRADE_STATUS_PENDING)
+            }
+        }
+
+        #[sqlx::test]
+        #[ignore = "needs DATABASE_URL"]
+        async fn test_dry_run_invokes_no_client_methods(pool: sqlx::PgPool) {
+            let order = fixtures::order(&format!("0x{}", hex::encode([0xaa; 32])));
+            PostgresOrderRepository::new(pool.clone()).create(&order).await.unwrap();
+            let trades = PostgresTradeRepository::new(pool.clone());
+            for trade in [stored_trade(1, 8453), stored_trade(2, 8453), stored_trade(3, 1)] {
+                trades.create(&trade).await.unwrap();
+            }
+            let db = Database::from_pool(pool);
+
+            let base = Arc::new(RecordingClient::default());
+            let clients: HashMap<u64, Arc<RecordingClient>> = HashMap::from([(8453, base.clone())]);
+            let vars = HashMap::from([("DRY_RUN", "true"), ("MAX_CANCEL_GAS_PRICE_GWEI", "1")]);
+            let settings = AutoCancelConfig::from_vars(&[chain("Base", 8453)], |key| vars.get(key).map(|v| v.to_string()));
+            let mut retry_state = HashMap::new();
+
+            let outcome = check_and_cancel_expired(&db, &clients, &settings, &mut retry_state, None).await.unwrap();
+            assert_eq!(outcome, (0, 0, 0));
+            assert!(base.calls.lock().unwrap().is_empty(), "dry run called {:?}", base.calls.lock().unwrap());
+            for id in [1, 2, 3] {
+                let trade_id = format!("0x{}", hex::encode([id; 32]));
+                assert_eq!(db.get_trade(&trade_id).await.unwrap().status, TRADE_STATUS_PENDING);
+            }
+
+            // The same pass for real goes through the client
+            let settings = AutoCancelConfig { dry_run: false, ..settings };
+            check_and_cancel_expired(&db, &clients, &settings, &mut retry_state, None).await.unwrap();
+            assert_eq!(*base.calls.lock().unwrap(), ["get_gas_price", "cancel_expired_trades_batch"]);
+        }
+
+        #[sqlx::test]
+        #[ignore = "needs DATABASE_URL"]
+        async fn test_skipped_cancel_never_flagged(pool: sqlx::PgPool) {
+            let order = fixtures::order(&format!("0x{}", hex::encode([0xaa; 32])));
+            PostgresOrderRepository::new(pool.clone()).create(&order).await.unwrap();
+            PostgresTradeRepository::new(pool.clone()).create(&stored_trade(1, 8453)).await.unwrap();
+            let db = Database::from_pool(pool);
+
+            // The trade was settled on-chain before the DB caught up
+            let settled = Arc::new(EstimatingClient {
+                estimate_error: Some(|| EthereumClientError::Revert("cancelExpiredTrade gas estimation: execution reverted: 0x5f3f6cfc".to_string())),
+                ..Default::default()
+            });
+            let clients = HashMap::from([(8453, settled.clone())]);
+            let settings = AutoCancelConfig::from_vars(&[chain("Base", 8453)], |_| None);
+            let mut retry_state = HashMap::new();
+            let trade_id = stored_trade(1, 8453).trade_id;
+
+            for pass in 1..=MAX_CANCEL_ATTEMPTS as usize + 1 {
+                let outcome = check_and_cancel_expired(&db, &clients, &settings, &mut retry_state, None).await.unwrap();
+                assert_eq!(outcome, (0, 0, 0));
+                assert_eq!(retry_state[&trade_id].attempts, 0);
+
+                // Not estimated again until the skip's backoff elapses
+                check_and_cancel_expired(&db, &clients, &settings, &mut retry_state, None).await.unwrap();
+                assert_eq!(settled.estimates.load(Ordering::SeqCst), pass);
+                elapse_backoff(&mut retry_state);
+            }
+            let pending = db.get_expired_pending_trades(settings.expiry_buffer_secs).await.unwrap();
+            assert_eq!(pending.len(), 1, "skipped trade flagged as cancel_failed");
+            assert_eq!(settled.sends.load(Ordering::SeqCst), 0);
+        }
+
+        #[sqlx::test]
+        #[ignore = "needs DATABASE_URL"]
+        async fn test_other_revert_flagged_after_max_attempts(pool: sqlx::PgPool) {
+            let order = fixtures::order(&format!("0x{}", hex::encode([0xaa; 32])));
+            PostgresOrderRepository::new(pool.clone()).create(&order).await.unwrap();
+            PostgresTradeRepository::new(pool.clone()).create(&stored_trade(1, 8453)).await.unwrap();
+            let db = Database::from_pool(pool);
+
+            // NotAuthorized, e.g. a misconfigured relayer: retried with backoff, then flagged
+            let unauthorized = Arc::new(EstimatingClient {
+                estimate_error: Some(|| EthereumClientError::Revert("cancelExpiredTrade gas estimation: execution reverted: 0xea8e4eb5".to_string())),
+                ..Default::default()
+            });
+            let clients = HashMap::from([(8453, unauthorized.clone())]);
+            let settings = AutoCancelConfig::from_vars(&[chain("Base", 8453)], |_| None);
+            let mut retry_state = HashMap::new();
+
+            for _ in 0..MAX_CANCEL_ATTEMPTS {
+                check_and_cancel_expired(&db, &clients, &settings, &mut retry_state, None).await.unwrap();
+                elapse_backoff(&mut retry_state);
+            }
+            let pending = db.get_expired_pending_trades(settings.expiry_buffer_secs).await.unwrap();
+            assert!(pending.is_empty(), "trade not flagged after {} reverts", MAX_CANCEL_ATTEMPTS);
+            assert_eq!(unauthorized.estimates.load(Ordering::SeqCst), MAX_CANCEL_ATTEMPTS as usize);
+            assert_eq!(unauthorized.sends.load(Ordering::SeqCst), 0);
+        }
+    }
+}
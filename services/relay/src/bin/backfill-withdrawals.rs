@@ -0,0 +1,293 @@
+//! Withdrawal Transaction Hash Backfill for LyncZ
+//!
+//! Withdrawals recorded before the event listener required a transaction hash
+//! can have no `txHash`, so the UI can't link them to a block explorer. This
+//! reads the OrderWithdrawn events in a block range and fills `txHash` and
+//! `blockNumber` on the chain's withdrawal rows that still lack them.
+//!
+//! An event is matched to a row of the same order with the same withdrawn and
+//! remaining amounts (oldest row first). Rows that already have a hash are
+//! never changed, and a hash already recorded for the order isn't assigned
+//! twice, so the tool is idempotent and safe to run repeatedly.
+//!
+//! Usage: backfill-withdrawals --chain <id> --from-block <n> [--to-block <n>] [--dry-run]
+//!   --chain       Chain whose escrow events are read
+//!   --from-block  First block to search (e.g. the escrow's deployment block)
+//!   --to-block    Last block to search (default: the current head)
+//!   --dry-run     Only report what would be filled in
+
+use async_trait::async_trait;
+use lyncz_relay::blockchain;
+use lyncz_relay::blockchain::types::WithdrawalLog;
+use lyncz_relay::db::models::DbWithdrawal;
+use lyncz_relay::{Config, Database, DbResult};
+
+#[derive(Debug, PartialEq)]
+struct BackfillArgs {
+    chain_id: u64,
+    from_block: u64,
+    to_block: Option<u64>,
+    dry_run: bool,
+}
+
+/// Parse the command line (without the program name)
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<BackfillArgs, String> {
+    let mut chain_id = None;
+    let mut from_block = None;
+    let mut to_block = None;
+    let mut dry_run = false;
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if flag == "--dry-run" {
+            dry_run = true;
+            continue;
+        }
+        let value = match inline {
+            Some(value) => value,
+            None => args.next().ok_or_else(|| format!("{} requires a value", flag))?,
+        };
+        let number = |value: &str| value.parse::<u64>().map_err(|_| format!("Invalid number for {}: {}", flag, value));
+        match flag.as_str() {
+            "--chain" => chain_id = Some(number(&value)?),
+            "--from-block" => from_block = Some(number(&value)?),
+            "--to-block" => to_block = Some(number(&value)?),
+            _ => return Err(format!("Unknown argument: {}", flag)),
+        }
+    }
+
+    let chain_id = chain_id.ok_or("--chain is required")?;
+    let from_block = from_block.ok_or("--from-block is required")?;
+    if to_block.is_some_and(|to| to < from_block) {
+        return Err("--to-block is before --from-block".to_string());
+    }
+    Ok(BackfillArgs { chain_id, from_block, to_block, dry_run })
+}
+
+/// Withdrawal rows in the DB (Database in production)
+#[async_trait]
+trait WithdrawalStore: Send + Sync {
+    async fn missing_tx_hash(&self, chain_id: i32) -> DbResult<Vec<DbWithdrawal>>;
+    /// false when the row was filled in (or the hash recorded) in the meantime
+    async fn set_tx_hash(&self, id: i32, tx_hash: &str, block_number: u64) -> DbResult<bool>;
+}
+
+#[async_trait]
+impl WithdrawalStore for Database {
+    async fn missing_tx_hash(&self, chain_id: i32) -> DbResult<Vec<DbWithdrawal>> {
+        self.get_withdrawals_missing_tx_hash(chain_id).await
+    }
+
+    async fn set_tx_hash(&self, id: i32, tx_hash: &str, block_number: u64) -> DbResult<bool> {
+        self.set_withdrawal_tx_hash(id, tx_hash, block_number).await
+    }
+}
+
+/// Outcome counts of one backfill run
+#[derive(Debug, Default, PartialEq)]
+struct BackfillSummary {
+    /// Rows without a hash before the run
+    missing: usize,
+    /// Rows given a hash (or that would be, in a dry run)
+    filled: usize,
+    /// Rows no event in the range matched
+    unmatched: usize,
+    /// DB errors
+    failed: usize,
+}
+
+/// Pair each event with the oldest unclaimed row it describes: (row id, event)
+fn match_withdrawals<'a>(missing: &[DbWithdrawal], events: &'a [WithdrawalLog]) -> Vec<(i32, &'a WithdrawalLog)> {
+    let mut unclaimed: Vec<&DbWithdrawal> = missing.iter().collect();
+    let mut matches = Vec::new();
+    for event in events {
+        let found = unclaimed.iter().position(|row| {
+            row.order_id.eq_ignore_ascii_case(&event.order_id)
+                && row.amount == event.amount
+                && row.remaining_after == event.remaining_after
+        });
+        if let Some(index) = found {
+            matches.push((unclaimed.remove(index).id, event));
+        }
+    }
+    matches
+}
+
+/// Fill in the transaction hash of every row missing one that an event matches
+async fn backfill_tx_hashes(
+    store: &dyn WithdrawalStore,
+    chain_id: i32,
+    events: &[WithdrawalLog],
+    dry_run: bool,
+) -> DbResult<BackfillSummary> {
+    let missing = store.missing_tx_hash(chain_id).await?;
+    let matches = match_withdrawals(&missing, events);
+    let mut summary = BackfillSummary {
+        missing: missing.len(),
+        unmatched: missing.len() - matches.len(),
+        ..Default::default()
+    };
+
+    for (id, event) in matches {
+        if dry_run {
+            tracing::info!("🧪 [DRY RUN] Would set withdrawal {} (order {}) tx {}", id, event.order_id, event.tx_hash);
+            summary.filled += 1;
+            continue;
+        }
+        match store.set_tx_hash(id, &event.tx_hash, event.block_number).await {
+            Ok(true) => {
+                tracing::info!("🔧 Withdrawal {} (order {}) tx {}", id, event.order_id, event.tx_hash);
+                summary.filled += 1;
+            }
+            Ok(false) => {
+                tracing::warn!("⚠️ Withdrawal {} was filled in or tx {} is already recorded - not changed", id, event.tx_hash);
+                summary.unmatched += 1;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to update withdrawal {}: {}", id, e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    lyncz_relay::telemetry::init();
+
+    let args = parse_args(std::env::args().skip(1))?;
+    tracing::info!("🔁 Starting LyncZ withdrawal tx hash backfill (chain {}{})",
+        args.chain_id, if args.dry_run { ", dry run" } else { "" });
+
+    let config = Config::load()?;
+
+    let db = Database::new(&config.database_url, config.database_replica_url.as_deref(), &config.db_pool).await?;
+    tracing::info!("✅ Database connected");
+
+    if config.relayer_private_key.is_none() {
+        return Err("RELAYER_PRIVATE_KEY not set".into());
+    }
+    let clients = blockchain::init_clients(&config).await;
+    let client = clients
+        .get(&args.chain_id)
+        .ok_or_else(|| format!("No blockchain client for chain {}", args.chain_id))?;
+
+    let to_block = match args.to_block {
+        Some(to) => to,
+        None => client.get_block_number().await?,
+    };
+    let events = client.get_withdrawals(args.from_block, to_block).await?;
+    tracing::info!("📦 {} withdrawal(s) in blocks {} to {}", events.len(), args.from_block, to_block);
+
+    let summary = backfill_tx_hashes(&db, args.chain_id as i32, &events, args.dry_run).await?;
+    tracing::info!(
+        "✅ Backfill complete: {} missing, {} filled, {} unmatched, {} failed",
+        summary.missing, summary.filled, summary.unmatched, summary.failed
+    );
+    if summary.unmatched > 0 {
+        tracing::info!("💡 Unmatched rows may predate --from-block; rerun with an earlier block");
+    }
+
+    db.close().await;
+    if summary.failed > 0 {
+        return Err(format!("{} withdrawal(s) could not be updated", summary.failed).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{TimeZone, Utc};
+    use std::sync::Mutex;
+
+    #[derive(Default)]
+    struct MockStore(Mutex<Vec<DbWithdrawal>>);
+
+    #[async_trait]
+    impl WithdrawalStore for MockStore {
+        async fn missing_tx_hash(&self, _chain_id: i32) -> DbResult<Vec<DbWithdrawal>> {
+            Ok(self.0.lock().unwrap().iter().filter(|w| w.tx_hash.is_none()).cloned().collect())
+        }
+
+        async fn set_tx_hash(&self, id: i32, tx_hash: &str, _block_number: u64) -> DbResult<bool> {
+            let mut rows = self.0.lock().unwrap();
+            let Some(row) = rows.iter_mut().find(|w| w.id == id && w.tx_hash.is_none()) else {
+                return Ok(false);
+            };
+            row.tx_hash = Some(tx_hash.to_string());
+            Ok(true)
+        }
+    }
+
+    fn row(id: i32, order_id: &str, amount: &str, remaining_after: &str) -> DbWithdrawal {
+        DbWithdrawal {
+            id,
+            order_id: order_id.to_string(),
+            amount: amount.to_string(),
+            remaining_after: remaining_after.to_string(),
+            tx_hash: None,
+            created_at: Utc.timestamp_opt(1_700_000_000 + id as i64, 0).unwrap(),
+        }
+    }
+
+    fn event(order_id: &str, amount: &str, remaining_after: &str, tx: &str) -> WithdrawalLog {
+        WithdrawalLog {
+            order_id: order_id.to_string(),
+            amount: amount.to_string(),
+            remaining_after: remaining_after.to_string(),
+            tx_hash: tx.to_string(),
+            block_number: 100,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_missing_hashes_are_filled_from_matching_events() {
+        let store = MockStore(Mutex::new(vec![
+            row(1, "0xaa", "100", "900"),
+            row(2, "0xaa", "100", "800"),
+            row(3, "0xbb", "50", "0"),
+            DbWithdrawal { tx_hash: Some("0xknown".to_string()), ..row(4, "0xbb", "10", "50") },
+        ]));
+        let events = [
+            event("0xAA", "100", "900", "0xt1"),
+            event("0xaa", "100", "800", "0xt2"),
+            event("0xbb", "10", "50", "0xknown"),
+            event("0xcc", "1", "0", "0xt3"),
+        ];
+
+        // A dry run reports the fills without writing them
+        let dry = backfill_tx_hashes(&store, 8453, &events, true).await.unwrap();
+        assert_eq!((dry.filled, dry.unmatched), (2, 1));
+        assert!(store.0.lock().unwrap()[0].tx_hash.is_none());
+
+        let summary = backfill_tx_hashes(&store, 8453, &events, false).await.unwrap();
+        assert_eq!(summary, BackfillSummary { missing: 3, filled: 2, unmatched: 1, failed: 0 });
+        let hashes: Vec<Option<String>> = store.0.lock().unwrap().iter().map(|w| w.tx_hash.clone()).collect();
+        assert_eq!(hashes, [Some("0xt1".to_string()), Some("0xt2".to_string()), None, Some("0xknown".to_string())]);
+
+        // Running again changes nothing
+        let again = backfill_tx_hashes(&store, 8453, &events, false).await.unwrap();
+        assert_eq!(again, BackfillSummary { missing: 1, filled: 0, unmatched: 1, failed: 0 });
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |s: &str| parse_args(s.split_whitespace().map(String::from));
+
+        assert_eq!(args("--chain 8453 --from-block 100 --to-block=200 --dry-run"), Ok(BackfillArgs {
+            chain_id: 8453,
+            from_block: 100,
+            to_block: Some(200),
+            dry_run: true,
+        }));
+        assert!(args("--from-block 100").is_err());
+        assert!(args("--chain 1").is_err());
+        assert!(args("--chain 1 --from-block 200 --to-block 100").is_err());
+    }
+}
@@ -0,0 +1,458 @@
+//! Trade Status Re-sync for LyncZ
+//!
+//! Repairs trade statuses in the DB that drifted from the escrow contract
+//! (e.g. a missed TradeSettled/TradeExpired event). Each selected trade's
+//! status is read from the contract; where it differs, the trade's
+//! TradeSettled/TradeExpired log is looked up and applied by the event
+//! listener's handlers, so an expiry returns the reserved amount to the order
+//! and a settlement records its transaction. Logs are claimed in
+//! processed_events like the listener's own, so none is applied twice and the
+//! tool is idempotent and safe to run repeatedly.
+//!
+//! The contract is authoritative, but a trade never goes back to PENDING
+//! on-chain: a DB trade that is SETTLED/EXPIRED while the chain (or a lagging
+//! RPC node) reports PENDING is reported as a conflict and not changed.
+//! Trades on-chain but missing from the DB can't be repaired from a status
+//! alone - they're reported so the range can be replayed with
+//! `lyncz-relay --backfill-from <block>`.
+//!
+//! Usage: resync --chain <id> --from-block <n> [--to-block <n> | --trade <id>...] [--dry-run]
+//!   --chain       Chain whose escrow contract is read
+//!   --from-block  Re-sync the trades created from this block (TradeCreated events)...
+//!   --to-block    ...up to this block (default: the current head)
+//!   --trade       Only re-sync this trade ID (repeatable), created at or after --from-block
+//!   --dry-run     Only report what would be corrected
+
+use async_trait::async_trait;
+use ethers::types::Log;
+use lyncz_relay::blockchain;
+use lyncz_relay::blockchain::client::{EthereumClient, EthereumClientError};
+use lyncz_relay::blockchain::events::{EventListener, EventListenerError};
+use lyncz_relay::{Config, Database, DbError, DbResult};
+
+/// Status codes matching the smart contract (from LyncZEscrow.sol enum TradeStatus)
+const TRADE_STATUS_PENDING: i32 = 0;
+
+/// Which trades to re-sync
+#[derive(Debug, PartialEq)]
+enum Selection {
+    /// Those created from `from_block` up to this block (None = the current head)
+    Blocks { to: Option<u64> },
+    Trades(Vec<String>),
+}
+
+#[derive(Debug, PartialEq)]
+struct ResyncArgs {
+    chain_id: u64,
+    /// Trades are created, and so settle or expire, at or after this block
+    from_block: u64,
+    selection: Selection,
+    dry_run: bool,
+}
+
+/// Parse the command line (without the program name)
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<ResyncArgs, String> {
+    let mut chain_id = None;
+    let mut from_block = None;
+    let mut to_block = None;
+    let mut trade_ids = Vec::new();
+    let mut dry_run = false;
+
+    while let Some(arg) = args.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) => (flag.to_string(), Some(value.to_string())),
+            None => (arg, None),
+        };
+        if flag == "--dry-run" {
+            dry_run = true;
+            continue;
+        }
+        let value = match inline {
+            Some(value) => value,
+            None => args.next().ok_or_else(|| format!("{} requires a value", flag))?,
+        };
+        let number = |value: &str| value.parse::<u64>().map_err(|_| format!("Invalid number for {}: {}", flag, value));
+        match flag.as_str() {
+            "--chain" => chain_id = Some(number(&value)?),
+            "--from-block" => from_block = Some(number(&value)?),
+            "--to-block" => to_block = Some(number(&value)?),
+            "--trade" => trade_ids.push(value.to_lowercase()),
+            _ => return Err(format!("Unknown argument: {}", flag)),
+        }
+    }
+
+    let chain_id = chain_id.ok_or("--chain is required")?;
+    let from_block = from_block.ok_or("--from-block is required")?;
+    let selection = match (to_block, trade_ids.is_empty()) {
+        (Some(_), false) => return Err("Use either --to-block or --trade, not both".to_string()),
+        (Some(to), true) if to < from_block => return Err("--to-block is before --from-block".to_string()),
+        (to, true) => Selection::Blocks { to },
+        (None, false) => Selection::Trades(trade_ids),
+    };
+    Ok(ResyncArgs { chain_id, from_block, selection, dry_run })
+}
+
+/// On-chain trades (EthereumClient in production)
+#[async_trait]
+trait TradeStatusSource: Send + Sync {
+    async fn get_trade_status(&self, trade_id: &str) -> Result<u8, EthereumClientError>;
+    /// The trade's TradeSettled/TradeExpired log, searched from `from_block` to the head
+    async fn get_outcome_log(&self, trade_id: &str, from_block: u64) -> Result<Option<Log>, EthereumClientError>;
+}
+
+#[async_trait]
+impl TradeStatusSource for EthereumClient {
+    async fn get_trade_status(&self, trade_id: &str) -> Result<u8, EthereumClientError> {
+        EthereumClient::get_trade_status(self, trade_id).await
+    }
+
+    async fn get_outcome_log(&self, trade_id: &str, from_block: u64) -> Result<Option<Log>, EthereumClientError> {
+        let head = self.get_block_number().await?;
+        self.get_trade_outcome_log(trade_id, from_block, head).await
+    }
+}
+
+/// Trade statuses in the DB (Database in production)
+#[async_trait]
+trait TradeStatusStore: Send + Sync {
+    /// None when the trade isn't in the DB
+    async fn trade_status(&self, trade_id: &str) -> DbResult<Option<i32>>;
+}
+
+#[async_trait]
+impl TradeStatusStore for Database {
+    async fn trade_status(&self, trade_id: &str) -> DbResult<Option<i32>> {
+        match self.get_trade(trade_id).await {
+            Ok(trade) => Ok(Some(trade.status)),
+            Err(DbError::TradeNotFound(_)) => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
+}
+
+/// Writes a trade's TradeSettled/TradeExpired log to the DB (EventListener in production)
+#[async_trait]
+trait OutcomeWriter: Send + Sync {
+    /// false when the log was already applied
+    async fn apply(&self, log: Log) -> Result<bool, EventListenerError>;
+}
+
+#[async_trait]
+impl OutcomeWriter for EventListener {
+    async fn apply(&self, log: Log) -> Result<bool, EventListenerError> {
+        self.apply_log(log).await
+    }
+}
+
+/// Outcome counts of one re-sync run
+#[derive(Debug, Default, PartialEq)]
+struct ResyncSummary {
+    checked: usize,
+    /// DB status differed and was updated (or would be, in a dry run)
+    corrected: usize,
+    unchanged: usize,
+    /// DB is SETTLED/EXPIRED but the chain says PENDING, or the trade's log was
+    /// already applied without the DB showing it (left as is)
+    conflicts: usize,
+    missing_in_db: usize,
+    missing_on_chain: usize,
+    /// RPC or DB errors
+    failed: usize,
+}
+
+/// Bring the DB status of each trade in line with the chain
+async fn resync_trades(
+    chain: &dyn TradeStatusSource,
+    store: &dyn TradeStatusStore,
+    writer: &dyn OutcomeWriter,
+    trade_ids: &[String],
+    from_block: u64,
+    dry_run: bool,
+) -> ResyncSummary {
+    let mut summary = ResyncSummary::default();
+
+    for trade_id in trade_ids {
+        summary.checked += 1;
+
+        let on_chain = match chain.get_trade_status(trade_id).await {
+            Ok(status) => status as i32,
+            Err(EthereumClientError::NotFound(_)) => {
+                tracing::warn!("⚠️ Trade {} not found on-chain", trade_id);
+                summary.missing_on_chain += 1;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Could not read trade {} from chain: {}", trade_id, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+
+        let in_db = match store.trade_status(trade_id).await {
+            Ok(Some(status)) => status,
+            Ok(None) => {
+                tracing::warn!("⚠️ Trade {} is on-chain (status {}) but not in the DB", trade_id, on_chain);
+                summary.missing_in_db += 1;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Could not read trade {} from the DB: {}", trade_id, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+
+        if in_db == on_chain {
+            summary.unchanged += 1;
+            continue;
+        }
+        if on_chain == TRADE_STATUS_PENDING {
+            tracing::warn!("⚠️ Trade {} is {} in the DB but PENDING on-chain - not changed", trade_id, in_db);
+            summary.conflicts += 1;
+            continue;
+        }
+
+        let log = match chain.get_outcome_log(trade_id, from_block).await {
+            Ok(Some(log)) => log,
+            Ok(None) => {
+                tracing::warn!("⚠️ No TradeSettled/TradeExpired log for trade {} since block {}", trade_id, from_block);
+                summary.failed += 1;
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Could not read the logs of trade {}: {}", trade_id, e);
+                summary.failed += 1;
+                continue;
+            }
+        };
+
+        if dry_run {
+            tracing::info!("🧪 [DRY RUN] Would update trade {} status {} -> {}", trade_id, in_db, on_chain);
+            summary.corrected += 1;
+            continue;
+        }
+        match writer.apply(log).await {
+            Ok(true) => {
+                tracing::info!("🔧 Trade {} status {} -> {}", trade_id, in_db, on_chain);
+                summary.corrected += 1;
+            }
+            Ok(false) => {
+                tracing::warn!("⚠️ Trade {}'s log was already applied, but the DB has status {} - not changed", trade_id, in_db);
+                summary.conflicts += 1;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to update trade {}: {}", trade_id, e);
+                summary.failed += 1;
+            }
+        }
+    }
+
+    summary
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    lyncz_relay::telemetry::init();
+
+    let args = parse_args(std::env::args().skip(1))?;
+    tracing::info!("🔁 Starting LyncZ trade status re-sync (chain {}{})",
+        args.chain_id, if args.dry_run { ", dry run" } else { "" });
+
+    let config = Config::load()?;
+
+    let db = Database::new(&config.database_url, config.database_replica_url.as_deref(), &config.db_pool).await?;
+    tracing::info!("✅ Database connected");
+
+    if config.relayer_private_key.is_none() {
+        return Err("RELAYER_PRIVATE_KEY not set".into());
+    }
+    let clients = blockchain::init_clients(&config).await;
+    let client = clients
+        .get(&args.chain_id)
+        .ok_or_else(|| format!("No blockchain client for chain {}", args.chain_id))?;
+    let chain_config = config.chains.iter()
+        .find(|chain| chain.chain_id == args.chain_id)
+        .ok_or_else(|| format!("Chain {} is not configured", args.chain_id))?;
+    // Applies the logs like the running listener would, minus emails and the live feed
+    let listener = EventListener::new(&chain_config.rpc_url, client.escrow_address(), db.pool().clone(), Some(args.from_block), args.chain_id)
+        .await?
+        .replaying();
+
+    let trade_ids = match args.selection {
+        Selection::Trades(trade_ids) => trade_ids,
+        Selection::Blocks { to } => {
+            let to = match to {
+                Some(to) => to,
+                None => client.get_block_number().await?,
+            };
+            let trade_ids = client.get_trade_ids_created(args.from_block, to).await?;
+            tracing::info!("📦 {} trade(s) created in blocks {} to {}", trade_ids.len(), args.from_block, to);
+            trade_ids
+        }
+    };
+
+    let summary = resync_trades(client.as_ref(), &db, &listener, &trade_ids, args.from_block, args.dry_run).await;
+    tracing::info!(
+        "✅ Re-sync complete: {} checked, {} corrected, {} unchanged, {} conflicts, {} missing in DB, {} missing on-chain, {} failed",
+        summary.checked, summary.corrected, summary.unchanged, summary.conflicts,
+        summary.missing_in_db, summary.missing_on_chain, summary.failed
+    );
+    if summary.missing_in_db > 0 {
+        tracing::info!("💡 Replay the missing trades with: lyncz-relay --backfill-from <block>");
+    }
+
+    db.close().await;
+    if summary.failed > 0 {
+        return Err(format!("{} trade(s) could not be re-synced", summary.failed).into());
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::Mutex;
+
+    /// Fixed on-chain statuses; unknown trades are NotFound. Trades in `unlogged`
+    /// have no outcome log in the searched range.
+    struct MockChain {
+        statuses: HashMap<String, u8>,
+        unlogged: Vec<&'static str>,
+    }
+
+    /// Stand-in for a trade's TradeSettled/TradeExpired log: the status in topic0,
+    /// the trade ID in data
+    fn outcome_log(trade_id: &str, status: u8) -> Log {
+        Log {
+            topics: vec![ethers::types::H256::from_low_u64_be(status as u64)],
+            data: trade_id.as_bytes().to_vec().into(),
+            ..Default::default()
+        }
+    }
+
+    #[async_trait]
+    impl TradeStatusSource for MockChain {
+        async fn get_trade_status(&self, trade_id: &str) -> Result<u8, EthereumClientError> {
+            self.statuses.get(trade_id).copied().ok_or_else(|| EthereumClientError::NotFound(format!("trade {}", trade_id)))
+        }
+
+        async fn get_outcome_log(&self, trade_id: &str, _from_block: u64) -> Result<Option<Log>, EthereumClientError> {
+            let status = self.get_trade_status(trade_id).await?;
+            Ok((!self.unlogged.contains(&trade_id)).then(|| outcome_log(trade_id, status)))
+        }
+    }
+
+    /// Trade statuses, and the logs applied to them
+    #[derive(Default)]
+    struct MockStore {
+        statuses: Mutex<HashMap<String, i32>>,
+        applied: Mutex<Vec<Log>>,
+    }
+
+    #[async_trait]
+    impl TradeStatusStore for MockStore {
+        async fn trade_status(&self, trade_id: &str) -> DbResult<Option<i32>> {
+            Ok(self.statuses.lock().unwrap().get(trade_id).copied())
+        }
+    }
+
+    #[async_trait]
+    impl OutcomeWriter for MockStore {
+        async fn apply(&self, log: Log) -> Result<bool, EventListenerError> {
+            if self.applied.lock().unwrap().contains(&log) {
+                return Ok(false);
+            }
+            let trade_id = String::from_utf8(log.data.to_vec()).unwrap();
+            let status = log.topics[0].to_low_u64_be() as i32;
+            self.statuses.lock().unwrap().insert(trade_id, status);
+            self.applied.lock().unwrap().push(log);
+            Ok(true)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_settled_on_chain_pending_in_db_is_corrected() {
+        let chain = MockChain {
+            statuses: HashMap::from([
+                ("0xsettled".to_string(), 1),
+                ("0xexpired".to_string(), 2),
+                ("0xpending".to_string(), 0),
+                ("0xregressed".to_string(), 0),
+                ("0xnotindb".to_string(), 2),
+                ("0xnolog".to_string(), 2),
+            ]),
+            unlogged: vec!["0xnolog"],
+        };
+        let store = MockStore {
+            statuses: Mutex::new(HashMap::from([
+                ("0xsettled".to_string(), 0),
+                ("0xexpired".to_string(), 0),
+                ("0xpending".to_string(), 0),
+                ("0xregressed".to_string(), 2),
+                ("0xnotonchain".to_string(), 0),
+                ("0xnolog".to_string(), 0),
+            ])),
+            ..Default::default()
+        };
+        let trade_ids: Vec<String> = ["0xsettled", "0xexpired", "0xpending", "0xregressed", "0xnotindb", "0xnotonchain", "0xnolog"]
+            .iter()
+            .map(|id| id.to_string())
+            .collect();
+
+        // A dry run reports the corrections without writing them
+        let dry = resync_trades(&chain, &store, &store, &trade_ids, 100, true).await;
+        assert_eq!(dry.corrected, 2);
+        assert!(store.applied.lock().unwrap().is_empty());
+
+        let summary = resync_trades(&chain, &store, &store, &trade_ids, 100, false).await;
+        assert_eq!(summary, ResyncSummary {
+            checked: 7,
+            corrected: 2,
+            unchanged: 1,
+            conflicts: 1,
+            missing_in_db: 1,
+            missing_on_chain: 1,
+            failed: 1,
+        });
+        // Each drifted trade's own settle/expire log is applied
+        assert_eq!(*store.applied.lock().unwrap(), [outcome_log("0xsettled", 1), outcome_log("0xexpired", 2)]);
+        // Never regressed to PENDING
+        assert_eq!(store.statuses.lock().unwrap()["0xregressed"], 2);
+
+        // Running again changes nothing
+        let again = resync_trades(&chain, &store, &store, &trade_ids, 100, false).await;
+        assert_eq!((again.corrected, again.unchanged), (0, 3));
+        assert_eq!(store.applied.lock().unwrap().len(), 2);
+
+        // A log the listener already applied is a conflict, not a second write
+        store.statuses.lock().unwrap().insert("0xsettled".to_string(), 0);
+        let replayed = resync_trades(&chain, &store, &store, &trade_ids[..1], 100, false).await;
+        assert_eq!((replayed.corrected, replayed.conflicts), (0, 1));
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let args = |s: &str| parse_args(s.split_whitespace().map(String::from));
+
+        assert_eq!(args("--chain 8453 --from-block 100 --to-block=200 --dry-run"), Ok(ResyncArgs {
+            chain_id: 8453,
+            from_block: 100,
+            selection: Selection::Blocks { to: Some(200) },
+            dry_run: true,
+        }));
+        assert_eq!(args("--chain=1 --from-block 5 --trade 0xAB --trade 0xcd"), Ok(ResyncArgs {
+            chain_id: 1,
+            from_block: 5,
+            selection: Selection::Trades(vec!["0xab".to_string(), "0xcd".to_string()]),
+            dry_run: false,
+        }));
+
+        assert!(args("--from-block 100").is_err());
+        assert!(args("--chain 1").is_err());
+        assert!(args("--chain 1 --trade 0xab").is_err());
+        assert!(args("--chain 1 --from-block 100 --to-block 200 --trade 0xab").is_err());
+        assert!(args("--chain 1 --from-block 200 --to-block 100").is_err());
+        assert!(args("--chain 1 --trade").is_err());
+    }
+}
@@ -0,0 +1,218 @@
+//! Relayer clients per configured chain, with their connection status
+//!
+//! A chain whose client can't be set up at startup (RPC not answering yet, bad
+//! endpoint) is kept as `ClientStatus::Unavailable` instead of being dropped, so
+//! requests for it fail with "chain temporarily unavailable" rather than looking
+//! like an unconfigured chain. The api-server retries those chains every
+//! CHAIN_RECONNECT_INTERVAL_SECS (default 30, see `reconnect`) and swaps the
+//! client in once its RPC answers - no restart needed.
+
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use serde::Serialize;
+use thiserror::Error;
+
+use super::client::{EthereumClient, EthereumClientError};
+
+/// Default seconds between reconnect attempts for unavailable chains
+pub const DEFAULT_RECONNECT_INTERVAL_SECS: u64 = 30;
+
+/// Connection state of one configured chain's client
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum ClientStatus {
+    Healthy,
+    /// Not connected since startup; `attempts` counts connection attempts so far
+    Unavailable { error: String, attempts: u32 },
+}
+
+/// Why a chain has no client to hand out
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ChainClientError {
+    /// Not a configured chain, or blockchain disabled (no RELAYER_PRIVATE_KEY)
+    #[error("Blockchain not enabled for chain {0}")]
+    NotConfigured(u64),
+    #[error("Chain {chain_id} is temporarily unavailable (reconnecting): {error}")]
+    Unavailable { chain_id: u64, error: String },
+}
+
+impl From<ChainClientError> for EthereumClientError {
+    fn from(err: ChainClientError) -> Self {
+        EthereumClientError::ProviderError(err.to_string())
+    }
+}
+
+enum Slot {
+    Ready(Arc<EthereumClient>),
+    Unavailable { error: String, attempts: u32 },
+}
+
+/// Shared registry of chain clients (cheap to clone)
+#[derive(Clone, Default)]
+pub struct ChainClients {
+    slots: Arc<Mutex<HashMap<u64, Slot>>>,
+}
+
+impl ChainClients {
+    /// Connected clients plus the chains that failed (chain_id -> error)
+    pub fn new(ready: HashMap<u64, Arc<EthereumClient>>, failed: HashMap<u64, String>) -> Self {
+        let slots = failed
+            .into_iter()
+            .map(|(chain_id, error)| (chain_id, Slot::Unavailable { error, attempts: 1 }))
+            .chain(ready.into_iter().map(|(chain_id, client)| (chain_id, Slot::Ready(client))))
+            .collect();
+        Self { slots: Arc::new(Mutex::new(slots)) }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, HashMap<u64, Slot>> {
+        self.slots.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Client for `chain_id`, or why there is none
+    pub fn get(&self, chain_id: u64) -> Result<Arc<EthereumClient>, ChainClientError> {
+        match self.lock().get(&chain_id) {
+            Some(Slot::Ready(client)) => Ok(client.clone()),
+            Some(Slot::Unavailable { error, .. }) => Err(ChainClientError::Unavailable { chain_id, error: error.clone() }),
+            None => Err(ChainClientError::NotConfigured(chain_id)),
+        }
+    }
+
+    /// Snapshot of the connected clients
+    pub fn ready(&self) -> HashMap<u64, Arc<EthereumClient>> {
+        self.lock()
+            .iter()
+            .filter_map(|(&chain_id, slot)| match slot {
+                Slot::Ready(client) => Some((chain_id, client.clone())),
+                Slot::Unavailable { .. } => None,
+            })
+            .collect()
+    }
+
+    /// Every chain with a client or a failed attempt, sorted
+    pub fn chain_ids(&self) -> Vec<u64> {
+        self.statuses().into_keys().collect()
+    }
+
+    /// Connection status per chain
+    pub fn statuses(&self) -> BTreeMap<u64, ClientStatus> {
+        self.lock()
+            .iter()
+            .map(|(&chain_id, slot)| {
+                let status = match slot {
+                    Slot::Ready(_) => ClientStatus::Healthy,
+                    Slot::Unavailable { error, attempts } => ClientStatus::Unavailable { error: error.clone(), attempts: *attempts },
+                };
+                (chain_id, status)
+            })
+            .collect()
+    }
+
+    /// Chains still waiting to connect
+    pub fn unavailable(&self) -> Vec<u64> {
+        let mut chain_ids: Vec<u64> = self.lock()
+            .iter()
+            .filter(|(_, slot)| matches!(slot, Slot::Unavailable { .. }))
+            .map(|(&chain_id, _)| chain_id)
+            .collect();
+        chain_ids.sort_unstable();
+        chain_ids
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lock().is_empty()
+    }
+
+    /// One reconnect round: `connect` every unavailable chain, swap in the clients
+    /// that come up and return them (e.g. to start their event listeners)
+    pub async fn reconnect<F, Fut>(&self, connect: F) -> Vec<(u64, Arc<EthereumClient>)>
+    where
+        F: Fn(u64) -> Fut,
+        Fut: Future<Output = Result<EthereumClient, String>>,
+    {
+        let mut connected = Vec::new();
+        for chain_id in self.unavailable() {
+            let result = connect(chain_id).await;
+            let mut slots = self.lock();
+            let Some(slot) = slots.get_mut(&chain_id) else {
+                continue;
+            };
+            match result {
+                Ok(client) => {
+                    let client = Arc::new(client);
+                    tracing::info!("✅ Chain {} reconnected, relayer: {:?}", chain_id, client.relayer_address());
+                    *slot = Slot::Ready(client.clone());
+                    connected.push((chain_id, client));
+                }
+                Err(e) => {
+                    if let Slot::Unavailable { error, attempts } = slot {
+                        *attempts += 1;
+                        tracing::warn!("⚠️ Chain {} still unavailable (attempt {}): {}", chain_id, attempts, e);
+                        *error = e;
+                    }
+                }
+            }
+        }
+        connected
+    }
+}
+
+/// Seconds between reconnect rounds (CHAIN_RECONNECT_INTERVAL_SECS)
+pub fn reconnect_interval_from_env() -> Duration {
+    let secs = std::env::var("CHAIN_RECONNECT_INTERVAL_SECS")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .filter(|&secs: &u64| secs > 0)
+        .unwrap_or(DEFAULT_RECONNECT_INTERVAL_SECS);
+    Duration::from_secs(secs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::types::Address;
+
+    async fn client(chain_id: u64) -> EthereumClient {
+        // Building a client doesn't touch the network
+        EthereumClient::new(
+            "http://localhost:8545",
+            "0x0123456789012345678901234567890123456789012345678901234567890123",
+            Address::repeat_byte(0x01),
+            chain_id,
+        )
+        .await
+        .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_chain_down_at_startup_recovers() {
+        let base = Arc::new(client(8453).await);
+        let clients = ChainClients::new(
+            HashMap::from([(8453, base)]),
+            HashMap::from([(1, "RPC unreachable: connection refused".to_string())]),
+        );
+
+        assert!(clients.get(8453).is_ok());
+        assert!(matches!(clients.get(1), Err(ChainClientError::Unavailable { chain_id: 1, .. })));
+        assert!(matches!(clients.get(10), Err(ChainClientError::NotConfigured(10))));
+        assert_eq!(clients.ready().len(), 1);
+
+        // RPC still down: stays unavailable, attempts counted
+        let connected = clients.reconnect(|_| async { Err("RPC unreachable: timeout".to_string()) }).await;
+        assert!(connected.is_empty());
+        assert_eq!(
+            clients.statuses()[&1],
+            ClientStatus::Unavailable { error: "RPC unreachable: timeout".to_string(), attempts: 2 }
+        );
+
+        // RPC comes up: the client is swapped in and usable
+        let connected = clients.reconnect(|chain_id| async move { Ok(client(chain_id).await) }).await;
+        assert_eq!(connected.iter().map(|(chain_id, _)| *chain_id).collect::<Vec<_>>(), vec![1]);
+        assert_eq!(clients.get(1).unwrap().chain_id(), 1);
+        assert_eq!(clients.statuses()[&1], ClientStatus::Healthy);
+        assert!(clients.unavailable().is_empty());
+        assert_eq!(clients.chain_ids(), vec![1, 8453]);
+    }
+}
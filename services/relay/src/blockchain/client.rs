@@ -4,14 +4,18 @@
 //! - submit_proof(): Relayer submits ZK proof to settle trades (pays gas)
 //! - Read-only queries for validation
 
+use ethers::abi::Detokenize;
 use ethers::prelude::*;
-use ethers::providers::{Http, Provider};
 use ethers::signers::{LocalWallet, Signer};
 use std::sync::Arc;
+use std::time::Duration;
 use thiserror::Error;
 
-use super::{LyncZEscrow, AlipayVerifier, SimpleFeeCalculator};
-use super::types::ContractConfig;
+use super::{LyncZEscrow, AlipayVerifier, IERC1271, SimpleFeeCalculator};
+use super::types::{ContractConfig, WithdrawalLog};
+use super::failover::{self, FailoverTransport, RpcProvider};
+use super::nonce::{self, NonceManager};
+use crate::retry::{self, RetryPolicy};
 
 #[derive(Error, Debug)]
 pub enum EthereumClientError {
@@ -23,15 +27,23 @@ pub enum EthereumClientError {
     WalletError(String),
     #[error("Transaction failed: {0}")]
     TransactionFailed(String),
+    #[error("Contract reverted: {0}")]
+    Revert(String),
+    #[error("Not found on chain: {0}")]
+    NotFound(String),
 }
 
 pub struct EthereumClient {
-    provider: Arc<Provider<Http>>,
+    provider: Arc<RpcProvider>,
     wallet: LocalWallet,
-    escrow_contract: LyncZEscrow<SignerMiddleware<Provider<Http>, LocalWallet>>,
+    escrow_contract: LyncZEscrow<SignerClient>,
     chain_id: u64,
+    /// Shared by every send from the relayer wallet on this chain
+    nonces: NonceManager,
 }
 
+type SignerClient = SignerMiddleware<RpcProvider, LocalWallet>;
+
 // Gas price caps per chain type
 // Using fixed legacy gas prices for predictable costs.
 //
@@ -43,7 +55,70 @@ const BASE_L2_GAS_PRICE_WEI: u64 = 30_000_000; // 0.03 gwei
 // If gas exceeds this cap, transactions will wait until fees drop
 const ETH_L1_GAS_PRICE_WEI: u64 = 100_000_000; // 0.1 gwei
 
+/// Retries for view calls (on top of endpoint failover): 250ms, 500ms
+const READ_RETRY: RetryPolicy = RetryPolicy::new(3, Duration::from_millis(250))
+    .with_max_delay(Duration::from_secs(2))
+    .with_jitter(0.2);
+
+/// Block range of one TradeCreated log query (see get_trade_ids_created)
+const TRADE_LOG_CHUNK_BLOCKS: u64 = 2000;
+
+/// Retries for broadcasting a transaction: 1s, 2s. Only transport failures are
+/// retried, always with the same signed transaction, so a resend after a lost
+/// response can't send twice (see send_call).
+const SEND_RETRY: RetryPolicy = RetryPolicy::new(3, Duration::from_secs(1))
+    .with_max_delay(Duration::from_secs(5))
+    .with_jitter(0.2);
+
+/// Whether a contract call failed in a way worth retrying: transport errors and
+/// rate limits, not reverts or other JSON-RPC error responses
+fn is_transient<M: Middleware>(error: &ContractError<M>) -> bool {
+    match error {
+        ContractError::MiddlewareError { e } => failover::is_transient(e.as_error_response()),
+        ContractError::ProviderError { e } => failover::is_transient(RpcError::as_error_response(e)),
+        _ => false,
+    }
+}
+
+/// Classify a failed contract call: a revert (with the contract's reason when it
+/// gave one), a transport failure or rate limit (provider, worth retrying later),
+/// or anything else via `other`
+fn classify_contract_error<M: Middleware>(
+    context: &str,
+    error: ContractError<M>,
+    other: fn(String) -> EthereumClientError,
+) -> EthereumClientError {
+    if error.is_revert() {
+        // Custom errors don't decode as a string; keep their raw data (selector) instead
+        let reason = error.decode_revert::<String>()
+            .or_else(|| error.as_revert().map(|data| format!("execution reverted: {}", data)))
+            .unwrap_or_else(|| "execution reverted".to_string());
+        return EthereumClientError::Revert(format!("{}: {}", context, reason));
+    }
+    if is_transient(&error) {
+        return EthereumClientError::ProviderError(format!("{}: {}", context, error));
+    }
+    other(format!("{}: {}", context, error))
+}
+
+/// Whether the node rejected a send because its nonce is already used or taken
+fn is_nonce_error<M: Middleware>(error: &ContractError<M>) -> bool {
+    let response = match error {
+        ContractError::MiddlewareError { e } => e.as_error_response(),
+        ContractError::ProviderError { e } => RpcError::as_error_response(e),
+        _ => None,
+    };
+    response.is_some_and(|r| nonce::is_nonce_error_message(&r.message))
+}
+
+/// Run a view call with READ_RETRY
+async fn read<M: Middleware, D: Detokenize>(call: ContractCall<M, D>) -> Result<D, ContractError<M>> {
+    retry::with_backoff(|| call.call(), &READ_RETRY, is_transient::<M>).await
+}
+
 impl EthereumClient {
+    /// `rpc_url` may be a comma-separated list; requests fail over between the
+    /// endpoints (see blockchain::failover)
     pub async fn new(
         rpc_url: &str,
         private_key: &str,
@@ -51,8 +126,8 @@ impl EthereumClient {
         chain_id: u64,
     ) -> Result<Self, EthereumClientError> {
         // Create provider
-        let provider = Provider::<Http>::try_from(rpc_url)
-            .map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
+        let provider = failover::connect(rpc_url)
+            .map_err(EthereumClientError::ProviderError)?;
 
         // Create wallet
         let wallet: LocalWallet = private_key
@@ -72,9 +147,63 @@ impl EthereumClient {
             wallet,
             escrow_contract,
             chain_id,
+            nonces: NonceManager::new(),
         })
     }
 
+    /// Broadcast `call` with the relayer's next nonce (see blockchain::nonce);
+    /// `retry_policy` retries transport failures of the broadcast itself. The
+    /// transaction is signed once per nonce, so a retry resends the very same
+    /// transaction, and a node answering "already known" (or "nonce too low" to a
+    /// resend) means an earlier attempt got through: its hash is returned to wait on.
+    async fn send_call<D: Detokenize>(
+        &self,
+        call: ContractCall<SignerClient, D>,
+        retry_policy: Option<&RetryPolicy>,
+    ) -> Result<PendingTransaction<'_, FailoverTransport>, ContractError<SignerClient>> {
+        let relayer = self.wallet.address();
+        let sync = || async {
+            self.provider.get_transaction_count(relayer, Some(BlockNumber::Pending.into())).await
+                .map_err(|e| ContractError::ProviderError { e })
+        };
+        let send = |nonce: U256| {
+            let mut tx = call.tx.clone();
+            tx.set_nonce(nonce);
+            async move {
+                self.escrow_contract.client().fill_transaction(&mut tx, None).await
+                    .map_err(|e| ContractError::MiddlewareError { e })?;
+                let signature = self.wallet.sign_transaction(&tx).await
+                    .map_err(|e| ContractError::MiddlewareError { e: ethers::middleware::signer::SignerMiddlewareError::SignerError(e) })?;
+                let raw = tx.rlp_signed(&signature);
+                let tx_hash = H256::from(ethers::utils::keccak256(&raw));
+
+                let mut sent = false;
+                let mut broadcast = || {
+                    let resend = std::mem::replace(&mut sent, true);
+                    let raw = raw.clone();
+                    async move {
+                        match self.provider.send_raw_transaction(raw).await {
+                            Ok(_) => Ok(tx_hash),
+                            Err(e) if RpcError::as_error_response(&e)
+                                .is_some_and(|r| nonce::is_in_flight_message(&r.message, resend)) =>
+                            {
+                                tracing::info!("📨 Transaction {:#x} already with the node ({}), waiting for it", tx_hash, e);
+                                Ok(tx_hash)
+                            }
+                            Err(e) => Err(ContractError::ProviderError { e }),
+                        }
+                    }
+                };
+                match retry_policy {
+                    Some(policy) => retry::with_backoff(broadcast, policy, is_transient).await,
+                    None => broadcast().await,
+                }
+            }
+        };
+        let tx_hash = self.nonces.send(sync, send, is_nonce_error).await?;
+        Ok(PendingTransaction::new(tx_hash, &self.provider))
+    }
+
     /// Get the gas price cap for this chain (in Wei)
     /// All chains use capped legacy gas prices for predictable relay costs.
     fn gas_price_cap(&self) -> u64 {
@@ -130,21 +259,16 @@ impl EthereumClient {
         let gas_estimate = call
             .estimate_gas()
             .await
-            .map_err(|e| {
-                EthereumClientError::ContractError(format!("Gas estimation failed: {}", e))
-            })?;
+            .map_err(|e| classify_contract_error("Gas estimation failed", e, EthereumClientError::ContractError))?;
         
         call = call
             .gas(gas_estimate * 120 / 100) // 20% buffer
             .legacy()
             .gas_price(U256::from(self.gas_price_cap()));
         
-        let tx = call
-            .send()
+        let tx = self.send_call(call, None)
             .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("submitProof failed: {}", e))
-            })?;
+            .map_err(|e| classify_contract_error("submitProof failed", e, EthereumClientError::TransactionFailed))?;
 
         let tx_hash = tx.tx_hash();
         tracing::info!("submitProof tx sent on chain {}: {:#x}", self.chain_id, tx_hash);
@@ -158,11 +282,10 @@ impl EthereumClient {
             .ok_or_else(|| {
                 EthereumClientError::TransactionFailed("No receipt returned".to_string())
             })?;
+        self.nonces.confirmed(tx_hash).await;
 
         if receipt.status != Some(U64::from(1)) {
-            return Err(EthereumClientError::TransactionFailed(
-                "Transaction reverted".to_string(),
-            ));
+            return Err(EthereumClientError::Revert(format!("transaction {:#x} reverted", tx_hash)));
         }
 
         tracing::info!("✅ submitProof confirmed: {:#x}", tx_hash);
@@ -180,6 +303,10 @@ impl EthereumClient {
         self.chain_id
     }
 
+    pub fn escrow_address(&self) -> Address {
+        self.escrow_contract.address()
+    }
+
     /// Get current block number
     pub async fn get_block_number(&self) -> Result<u64, EthereumClientError> {
         let block_number = self
@@ -190,13 +317,59 @@ impl EthereumClient {
         Ok(block_number.as_u64())
     }
 
+    /// Native-token balance (wei) of the relayer wallet, which pays for settlements and cancellations
+    pub async fn relayer_balance(&self) -> Result<U256, EthereumClientError> {
+        self.provider
+            .get_balance(self.relayer_address(), None)
+            .await
+            .map_err(|e| EthereumClientError::ProviderError(e.to_string()))
+    }
+
+    /// Get current network gas price (in Wei)
+    pub async fn get_gas_price(&self) -> Result<U256, EthereumClientError> {
+        self.provider
+            .get_gas_price()
+            .await
+            .map_err(|e| EthereumClientError::ProviderError(e.to_string()))
+    }
+
+    /// EIP-1271 `isValidSignature(hash, signature)` of the contract wallet at `wallet`:
+    /// the magic value 0x1626ba7e if the wallet accepts the signature. A wallet that
+    /// rejects by reverting, or an address without code, comes back as an error.
+    pub async fn is_valid_signature(&self, wallet: Address, hash: [u8; 32], signature: Vec<u8>) -> Result<[u8; 4], EthereumClientError> {
+        let contract = IERC1271::new(wallet, Arc::new(self.provider.clone()));
+        read(contract.is_valid_signature(hash, signature.into()))
+            .await
+            .map_err(|e| classify_contract_error("isValidSignature failed", e, EthereumClientError::ContractError))
+    }
+
+    /// Get (gas_used, effective_gas_price_wei) from a transaction receipt
+    /// Returns None if the transaction isn't mined yet
+    pub async fn get_receipt_gas(&self, tx_hash: &str) -> Result<Option<(u64, u128)>, EthereumClientError> {
+        let hash: H256 = tx_hash
+            .parse()
+            .map_err(|e| EthereumClientError::ContractError(format!("Invalid tx hash {}: {}", tx_hash, e)))?;
+        let receipt = self
+            .provider
+            .get_transaction_receipt(hash)
+            .await
+            .map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
+        
+        Ok(receipt.map(|r| {
+            (
+                r.gas_used.unwrap_or_default().as_u64(),
+                r.effective_gas_price.unwrap_or_default().as_u128(),
+            )
+        }))
+    }
+
     /// Get payment window from contract
     pub async fn get_payment_window(&self) -> Result<U256, EthereumClientError> {
         self.escrow_contract
             .payment_window()
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(e.to_string()))
+            .map_err(|e| classify_contract_error("paymentWindow", e, EthereumClientError::ContractError))
     }
 
     /// Check if order exists on blockchain
@@ -206,12 +379,31 @@ impl EthereumClient {
             .orders(order_id)
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(e.to_string()))?;
+            .map_err(|e| classify_contract_error("orders", e, EthereumClientError::ContractError))?;
         
         // Check if the order has a non-zero remaining amount
         Ok(order.4 > U256::zero()) // order.4 is remainingAmount
     }
 
+    /// Current remainingAmount of an order, read from the contract
+    /// (NotFound when the order doesn't exist on-chain)
+    pub async fn get_order_remaining(&self, order_id: &str) -> Result<U256, EthereumClientError> {
+        use crate::blockchain::types::order_id_to_bytes32;
+        
+        let order_id_bytes = order_id_to_bytes32(order_id)
+            .map_err(|e| EthereumClientError::ContractError(format!("Invalid order ID: {}", e)))?;
+        
+        let order = read(self.escrow_contract.orders(order_id_bytes))
+            .await
+            .map_err(|e| classify_contract_error("orders", e, EthereumClientError::ContractError))?;
+        
+        // Unknown orders read back as all zeroes; a real one always has a seller
+        if order.1 == Address::zero() {
+            return Err(EthereumClientError::NotFound(format!("order {}", order_id)));
+        }
+        Ok(order.4) // remainingAmount
+    }
+
     /// Get order's accountLinesHash from blockchain
     /// Used to verify that submitted payment info matches on-chain hash
     pub async fn get_order_hash(&self, order_id: &str) -> Result<[u8; 32], EthereumClientError> {
@@ -220,12 +412,9 @@ impl EthereumClient {
         let order_id_bytes = order_id_to_bytes32(order_id)
             .map_err(|e| EthereumClientError::ContractError(format!("Invalid order ID: {}", e)))?;
         
-        let order = self
-            .escrow_contract
-            .orders(order_id_bytes)
-            .call()
+        let order = read(self.escrow_contract.orders(order_id_bytes))
             .await
-            .map_err(|e| EthereumClientError::ContractError(e.to_string()))?;
+            .map_err(|e| classify_contract_error("orders", e, EthereumClientError::ContractError))?;
         
         // The Order struct (v4):
         // bytes32 orderId (0), address seller (1), address token (2), uint256 totalAmount (3),
@@ -247,7 +436,7 @@ impl EthereumClient {
             .get_transaction_receipt(tx_hash)
             .await
             .map_err(|e| EthereumClientError::ProviderError(format!("Failed to get receipt: {}", e)))?
-            .ok_or_else(|| EthereumClientError::ContractError("Transaction receipt not found".to_string()))?;
+            .ok_or_else(|| EthereumClientError::NotFound(format!("transaction receipt {:#x}", tx_hash)))?;
         
         // OrderCreated event signature: keccak256("OrderCreated(bytes32,address,address,uint256,uint256,uint8,bytes32,bool)")
         // = 0x9f6b9c5cb3f3c820f7b25bf8bee9719189eecc5e2fe55367659ec4365c5003da
@@ -262,7 +451,7 @@ impl EthereumClient {
             }
         }
         
-        Err(EthereumClientError::ContractError("OrderCreated event not found in transaction receipt".to_string()))
+        Err(EthereumClientError::NotFound("OrderCreated event in transaction receipt".to_string()))
     }
 
     /// Check if trade exists on blockchain
@@ -272,12 +461,110 @@ impl EthereumClient {
             .trades(trade_id)
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(e.to_string()))?;
+            .map_err(|e| classify_contract_error("trades", e, EthereumClientError::ContractError))?;
         
         // Check if the trade has a non-zero token amount
         Ok(trade.3 > U256::zero()) // trade.3 is tokenAmount
     }
 
+    /// Current status of a trade, read from the contract: 0=PENDING, 1=SETTLED, 2=EXPIRED
+    /// (NotFound when the trade doesn't exist on-chain)
+    pub async fn get_trade_status(&self, trade_id: &str) -> Result<u8, EthereumClientError> {
+        use crate::blockchain::types::trade_id_to_bytes32;
+
+        let trade_id_bytes = trade_id_to_bytes32(trade_id)
+            .map_err(|e| EthereumClientError::ContractError(format!("Invalid trade ID: {}", e)))?;
+
+        let trade = read(self.escrow_contract.trades(trade_id_bytes))
+            .await
+            .map_err(|e| classify_contract_error("trades", e, EthereumClientError::ContractError))?;
+
+        // Unknown trades read back as all zeroes; a real one always stores its own ID
+        if trade.0 == [0u8; 32] {
+            return Err(EthereumClientError::NotFound(format!("trade {}", trade_id)));
+        }
+        Ok(trade.7) // status
+    }
+
+    /// IDs of the trades created in `[from_block, to_block]`, from TradeCreated events
+    /// (queried TRADE_LOG_CHUNK_BLOCKS at a time)
+    pub async fn get_trade_ids_created(&self, from_block: u64, to_block: u64) -> Result<Vec<String>, EthereumClientError> {
+        let mut trade_ids = Vec::new();
+        let mut block = from_block;
+        while block <= to_block {
+            let chunk_end = std::cmp::min(block.saturating_add(TRADE_LOG_CHUNK_BLOCKS - 1), to_block);
+            let events = self.escrow_contract
+                .trade_created_filter()
+                .from_block(block)
+                .to_block(chunk_end)
+                .query()
+                .await
+                .map_err(|e| classify_contract_error("TradeCreated logs", e, EthereumClientError::ProviderError))?;
+            trade_ids.extend(events.iter().map(|e| format!("0x{}", hex::encode(e.trade_id))));
+            if chunk_end == to_block {
+                break;
+            }
+            block = chunk_end + 1;
+        }
+        Ok(trade_ids)
+    }
+
+    /// OrderWithdrawn events in `[from_block, to_block]` with their transaction
+    /// hashes (queried TRADE_LOG_CHUNK_BLOCKS at a time)
+    pub async fn get_withdrawals(&self, from_block: u64, to_block: u64) -> Result<Vec<WithdrawalLog>, EthereumClientError> {
+        let mut withdrawals = Vec::new();
+        let mut block = from_block;
+        while block <= to_block {
+            let chunk_end = std::cmp::min(block.saturating_add(TRADE_LOG_CHUNK_BLOCKS - 1), to_block);
+            let events = self.escrow_contract
+                .order_withdrawn_filter()
+                .from_block(block)
+                .to_block(chunk_end)
+                .query_with_meta()
+                .await
+                .map_err(|e| classify_contract_error("OrderWithdrawn logs", e, EthereumClientError::ProviderError))?;
+            withdrawals.extend(events.iter().map(|(event, meta)| {
+                WithdrawalLog::new(event, meta.transaction_hash, meta.block_number.as_u64())
+            }));
+            if chunk_end == to_block {
+                break;
+            }
+            block = chunk_end + 1;
+        }
+        Ok(withdrawals)
+    }
+
+    /// The TradeSettled or TradeExpired log of a trade, if it settled or expired in
+    /// `[from_block, to_block]` (queried TRADE_LOG_CHUNK_BLOCKS at a time)
+    pub async fn get_trade_outcome_log(&self, trade_id: &str, from_block: u64, to_block: u64) -> Result<Option<Log>, EthereumClientError> {
+        use crate::blockchain::types::trade_id_to_bytes32;
+        use super::{TradeExpiredFilter, TradeSettledFilter};
+
+        let trade_id_bytes = trade_id_to_bytes32(trade_id)
+            .map_err(|e| EthereumClientError::ContractError(format!("Invalid trade ID: {}", e)))?;
+        let filter = Filter::new()
+            .address(self.escrow_address())
+            .topic0(vec![TradeSettledFilter::signature(), TradeExpiredFilter::signature()])
+            .topic1(H256::from(trade_id_bytes));
+
+        let mut block = from_block;
+        while block <= to_block {
+            let chunk_end = std::cmp::min(block.saturating_add(TRADE_LOG_CHUNK_BLOCKS - 1), to_block);
+            let logs = self.provider
+                .get_logs(&filter.clone().from_block(block).to_block(chunk_end))
+                .await
+                .map_err(|e| EthereumClientError::ProviderError(format!("Trade outcome logs: {}", e)))?;
+            if let Some(log) = logs.into_iter().next() {
+                return Ok(Some(log));
+            }
+            if chunk_end == to_block {
+                break;
+            }
+            block = chunk_end + 1;
+        }
+        Ok(None)
+    }
+
     /// Get the Alipay verifier address from the escrow contract
     /// PaymentRail::ALIPAY = 0
     pub async fn get_alipay_verifier_address(&self) -> Result<Address, EthereumClientError> {
@@ -288,7 +575,7 @@ impl EthereumClient {
             .verifiers(0) // 0 = ALIPAY
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get verifier: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get verifier", e, EthereumClientError::ContractError))?;
         
         Ok(verifier_address)
     }
@@ -316,7 +603,7 @@ impl EthereumClient {
             .alipay_public_key_hash()
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get public key hash: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get public key hash", e, EthereumClientError::ContractError))?;
         
         Ok(hash)
     }
@@ -328,7 +615,7 @@ impl EthereumClient {
             .fee_calculator()
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get feeCalculator: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get feeCalculator", e, EthereumClientError::ContractError))?;
         
         if fee_calculator_address == Address::zero() {
             return Err(EthereumClientError::ContractError(
@@ -347,7 +634,7 @@ impl EthereumClient {
             .get_fee_rate()
             .call()
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get fee rate: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get fee rate", e, EthereumClientError::ContractError))?;
         
         Ok(fee_rate)
     }
@@ -356,29 +643,21 @@ impl EthereumClient {
 
     /// Get contract configuration values
     pub async fn get_contract_config(&self) -> Result<ContractConfig, EthereumClientError> {
-        let min_trade_value = self.escrow_contract
-            .min_trade_value()
-            .call()
+        let min_trade_value = read(self.escrow_contract.min_trade_value())
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get minTradeValue: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get minTradeValue", e, EthereumClientError::ContractError))?;
 
-        let max_trade_value = self.escrow_contract
-            .max_trade_value()
-            .call()
+        let max_trade_value = read(self.escrow_contract.max_trade_value())
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get maxTradeValue: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get maxTradeValue", e, EthereumClientError::ContractError))?;
 
-        let payment_window = self.escrow_contract
-            .payment_window()
-            .call()
+        let payment_window = read(self.escrow_contract.payment_window())
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get paymentWindow: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get paymentWindow", e, EthereumClientError::ContractError))?;
 
-        let paused = self.escrow_contract
-            .paused()
-            .call()
+        let paused = read(self.escrow_contract.paused())
             .await
-            .map_err(|e| EthereumClientError::ContractError(format!("Failed to get paused: {}", e)))?;
+            .map_err(|e| classify_contract_error("Failed to get paused", e, EthereumClientError::ContractError))?;
 
         // Get fee rate (in basis points) - via external fee calculator
         let fee_rate = self.get_fee_rate().await.unwrap_or(U256::from(100)); // Default 1%
@@ -540,10 +819,7 @@ impl EthereumClient {
         
         // Create AlipayVerifier contract instance with signer
         let wallet = self.wallet.clone().with_chain_id(self.chain_id);
-        let provider = Provider::<Http>::try_from(
-            self.provider.url().to_string()
-        ).map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
-        let client = SignerMiddleware::new(provider, wallet);
+        let client = SignerMiddleware::new((*self.provider).clone(), wallet);
         let client = Arc::new(client);
         
         let alipay_verifier = AlipayVerifier::new(verifier_address, client);
@@ -553,13 +829,13 @@ impl EthereumClient {
         
         // Estimate gas
         let gas_estimate = call.estimate_gas().await
-            .map_err(|e| EthereumClientError::ContractError(format!("Gas estimation failed: {}", e)))?;
+            .map_err(|e| classify_contract_error("Gas estimation failed", e, EthereumClientError::ContractError))?;
         
         // Send with gas buffer and per-chain gas cap
         let mut call = call.gas(gas_estimate * 120 / 100);
         call = call.legacy().gas_price(U256::from(self.gas_price_cap()));
-        let tx = call.send().await
-            .map_err(|e| EthereumClientError::TransactionFailed(format!("Failed to update public key hash: {}", e)))?;
+        let tx = self.send_call(call, None).await
+            .map_err(|e| classify_contract_error("Failed to update public key hash", e, EthereumClientError::TransactionFailed))?;
 
         let tx_hash = tx.tx_hash();
         tracing::info!("updatePublicKeyHash tx sent: {:#x}", tx_hash);
@@ -568,6 +844,7 @@ impl EthereumClient {
         tx.await.map_err(|e| {
             EthereumClientError::TransactionFailed(format!("Transaction receipt error: {}", e))
         })?;
+        self.nonces.confirmed(tx_hash).await;
 
         Ok(tx_hash)
     }
@@ -598,21 +875,16 @@ impl EthereumClient {
         let gas_estimate = call
             .estimate_gas()
             .await
-            .map_err(|e| {
-                EthereumClientError::ContractError(format!("Gas estimation failed: {}", e))
-            })?;
+            .map_err(|e| classify_contract_error("Gas estimation failed", e, EthereumClientError::ContractError))?;
         
         call = call
             .gas(gas_estimate * 120 / 100) // 20% buffer
             .legacy()
             .gas_price(U256::from(self.gas_price_cap()));
         
-        let tx = call
-            .send()
+        let tx = self.send_call(call, None)
             .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("fillOrder failed: {}", e))
-            })?;
+            .map_err(|e| classify_contract_error("fillOrder failed", e, EthereumClientError::TransactionFailed))?;
 
         let tx_hash = tx.tx_hash();
         tracing::info!("fillOrder tx sent: {:#x}", tx_hash);
@@ -626,11 +898,10 @@ impl EthereumClient {
             .ok_or_else(|| {
                 EthereumClientError::TransactionFailed("No receipt returned".to_string())
             })?;
+        self.nonces.confirmed(tx_hash).await;
 
         if receipt.status != Some(U64::from(1)) {
-            return Err(EthereumClientError::TransactionFailed(
-                "Transaction reverted".to_string(),
-            ));
+            return Err(EthereumClientError::Revert(format!("transaction {:#x} reverted", tx_hash)));
         }
 
         tracing::info!("✅ fillOrder confirmed: {:#x}", tx_hash);
@@ -662,28 +933,45 @@ impl EthereumClient {
         Ok((tx_hash, trade_id))
     }
 
+    /// Estimate the gas of cancelExpiredTrade without sending it (eth_estimateGas)
+    /// 
+    /// A cancellation that would revert (trade already settled or cancelled by
+    /// someone else, not expired yet) fails here with `Revert` and the decoded
+    /// reason, so the caller can skip the send instead of paying for a failed tx.
+    pub async fn estimate_cancel_gas(&self, trade_id: [u8; 32]) -> Result<U256, EthereumClientError> {
+        self.escrow_contract
+            .cancel_expired_trade(trade_id)
+            .estimate_gas()
+            .await
+            .map_err(|e| classify_contract_error("cancelExpiredTrade gas estimation", e, EthereumClientError::ContractError))
+    }
+
     /// Cancel an expired trade and return funds to seller
     /// The relayer pays for gas. Anyone can call this function on-chain.
+    /// `gas_estimate` comes from estimate_cancel_gas; the gas limit adds a 20% buffer.
     /// 
-    /// Returns: Transaction hash on success, or error if trade is not expired/not pending
+    /// Returns: (tx hash, gas cost in wei, effective gas price) on success, or error if
+    /// trade is not expired/not pending
     pub async fn cancel_expired_trade(
         &self,
         trade_id: [u8; 32],
-    ) -> Result<(H256, U256), EthereumClientError> {
+        gas_estimate: U256,
+    ) -> Result<(H256, U256, U256), EthereumClientError> {
         tracing::info!(
-            "Calling cancelExpiredTrade: trade_id={}",
+            "Calling cancelExpiredTrade: trade_id={}, gas_estimate={}",
             hex::encode(trade_id),
+            gas_estimate,
         );
 
         // Configure gas pricing with per-chain cap
         let mut call = self.escrow_contract.cancel_expired_trade(trade_id);
-        call = call.legacy().gas_price(U256::from(self.gas_price_cap()));
-        let tx = call
-            .send()
+        call = call
+            .gas(gas_estimate * 120 / 100) // 20% buffer
+            .legacy()
+            .gas_price(U256::from(self.gas_price_cap()));
+        let tx = self.send_call(call, Some(&SEND_RETRY))
             .await
-            .map_err(|e| {
-                EthereumClientError::TransactionFailed(format!("cancelExpiredTrade failed: {}", e))
-            })?;
+            .map_err(|e| classify_contract_error("cancelExpiredTrade failed", e, EthereumClientError::TransactionFailed))?;
 
         let tx_hash = tx.tx_hash();
         tracing::info!("cancelExpiredTrade tx sent: {:#x}", tx_hash);
@@ -697,11 +985,10 @@ impl EthereumClient {
             .ok_or_else(|| {
                 EthereumClientError::TransactionFailed("No receipt returned".to_string())
             })?;
+        self.nonces.confirmed(tx_hash).await;
 
         if receipt.status != Some(U64::from(1)) {
-            return Err(EthereumClientError::TransactionFailed(
-                "Transaction reverted".to_string(),
-            ));
+            return Err(EthereumClientError::Revert(format!("transaction {:#x} reverted", tx_hash)));
         }
 
         // Calculate gas cost for return value
@@ -711,6 +998,50 @@ impl EthereumClient {
 
         tracing::info!("✅ cancelExpiredTrade confirmed: {:#x}", tx_hash);
 
-        Ok((tx_hash, gas_cost))
+        Ok((tx_hash, gas_cost, effective_gas_price))
+    }
+
+    /// Cancel multiple expired trades in a single transaction
+    /// Reverts as a whole if any trade can't be cancelled (e.g. already settled),
+    /// and fails on contracts deployed before cancelExpiredTradesBatch existed.
+    /// Returns (tx_hash, gas_cost, effective_gas_price)
+    pub async fn cancel_expired_trades_batch(
+        &self,
+        trade_ids: &[[u8; 32]],
+    ) -> Result<(H256, U256, U256), EthereumClientError> {
+        tracing::info!("Calling cancelExpiredTradesBatch: {} trades", trade_ids.len());
+
+        // Configure gas pricing with per-chain cap
+        let mut call = self.escrow_contract.cancel_expired_trades_batch(trade_ids.to_vec());
+        call = call.legacy().gas_price(U256::from(self.gas_price_cap()));
+        let tx = self.send_call(call, Some(&SEND_RETRY))
+            .await
+            .map_err(|e| classify_contract_error("cancelExpiredTradesBatch failed", e, EthereumClientError::TransactionFailed))?;
+
+        let tx_hash = tx.tx_hash();
+        tracing::info!("cancelExpiredTradesBatch tx sent: {:#x}", tx_hash);
+
+        // Wait for confirmation
+        let receipt = tx
+            .await
+            .map_err(|e| {
+                EthereumClientError::TransactionFailed(format!("Transaction receipt error: {}", e))
+            })?
+            .ok_or_else(|| {
+                EthereumClientError::TransactionFailed("No receipt returned".to_string())
+            })?;
+        self.nonces.confirmed(tx_hash).await;
+
+        if receipt.status != Some(U64::from(1)) {
+            return Err(EthereumClientError::Revert(format!("transaction {:#x} reverted", tx_hash)));
+        }
+
+        let gas_used = receipt.gas_used.unwrap_or_default();
+        let effective_gas_price = receipt.effective_gas_price.unwrap_or_default();
+        let gas_cost = gas_used * effective_gas_price;
+
+        tracing::info!("✅ cancelExpiredTradesBatch confirmed: {:#x}", tx_hash);
+
+        Ok((tx_hash, gas_cost, effective_gas_price))
     }
 }
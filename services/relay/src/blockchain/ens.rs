@@ -0,0 +1,46 @@
+//! Reverse ENS lookups for `include_ens` listings
+//!
+//! ENS lives on Ethereum mainnet whatever chain an order is on, so names are
+//! resolved over their own RPC (Config.ens_rpc_url) rather than a chain client:
+//! a Base-only relay still gets names. Lookups share one semaphore, so a burst
+//! of listing requests can't flood the RPC.
+
+use std::sync::Arc;
+
+use ethers::providers::{Middleware, ProviderError};
+use ethers::types::Address;
+use tokio::sync::Semaphore;
+
+use super::client::EthereumClientError;
+use super::failover::{self, RpcProvider};
+
+/// Reverse lookups in flight at once, across all requests
+pub const MAX_CONCURRENT_LOOKUPS: usize = 8;
+
+pub struct EnsResolver {
+    provider: RpcProvider,
+    permits: Arc<Semaphore>,
+}
+
+impl EnsResolver {
+    /// Resolver over `rpc_urls` (one URL or a comma-separated failover list)
+    pub fn connect(rpc_urls: &str) -> Result<Self, String> {
+        Ok(Self {
+            provider: failover::connect(rpc_urls)?,
+            permits: Arc::new(Semaphore::new(MAX_CONCURRENT_LOOKUPS)),
+        })
+    }
+
+    /// Primary ENS name of `address` (reverse record, checked against the forward
+    /// record). Returns None when no valid reverse record exists.
+    pub async fn resolve(&self, address: Address) -> Result<Option<String>, EthereumClientError> {
+        let _permit = self.permits.acquire().await
+            .map_err(|e| EthereumClientError::ProviderError(e.to_string()))?;
+        match self.provider.lookup_address(address).await {
+            Ok(name) if name.is_empty() => Ok(None),
+            Ok(name) => Ok(Some(name)),
+            Err(ProviderError::EnsError(_)) | Err(ProviderError::EnsNotOwned(_)) => Ok(None),
+            Err(e) => Err(EthereumClientError::ProviderError(e.to_string())),
+        }
+    }
+}
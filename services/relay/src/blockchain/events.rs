@@ -1,20 +1,32 @@
 //! Blockchain event listener for LyncZ escrow contract
 //! Syncs on-chain events to the database and sends email notifications
 
+use async_trait::async_trait;
 use ethers::prelude::*;
-use ethers::providers::{Http, Provider};
+use sqlx::{Acquire, Postgres, Transaction};
+use std::collections::HashSet;
 use std::sync::Arc;
 use thiserror::Error;
+use tokio::sync::watch;
 use tokio::time::{interval, Duration};
 
-use super::{OrderCreatedFilter, OrderWithdrawnFilter, TradeCreatedFilter, TradeSettledFilter, TradeExpiredFilter, ExchangeRateUpdatedFilter, AccountLinesHashUpdatedFilter};
+use super::{LyncZEscrow, OrderCreatedFilter, OrderWithdrawnFilter, TradeCreatedFilter, TradeSettledFilter, TradeExpiredFilter, ExchangeRateUpdatedFilter, AccountLinesHashUpdatedFilter};
+use super::failover::{self, RpcProvider};
+use super::listener_status::ListenerProgress;
+use super::types::WithdrawalLog;
 use crate::db::{
     models::{DbOrder, DbTrade},
-    orders::{OrderRepository, PostgresOrderRepository},
-    trades::{TradeRepository, PostgresTradeRepository},
+    orders::{self, PostgresOrderRepository},
+    processed_events::{ProcessedEventKey, ProcessedEventRepository},
+    trades::{self, TradeRepository, PostgresTradeRepository},
+    withdrawals,
     account_emails::AccountEmailRepository,
+    DbError, DbResult,
 };
 use crate::email::{EmailService, EmailEvent, EmailInfo, format_token_amount};
+use crate::currency::{Currency, InvalidAmount};
+use crate::rail::PaymentRail;
+use crate::api::live::{LiveEventKind, LiveFeed};
 
 #[derive(Error, Debug)]
 pub enum EventListenerError {
@@ -26,6 +38,30 @@ pub enum EventListenerError {
     EventDecodeError(String),
 }
 
+impl From<DbError> for EventListenerError {
+    fn from(e: DbError) -> Self {
+        EventListenerError::DatabaseError(e.to_string())
+    }
+}
+
+impl From<sqlx::Error> for EventListenerError {
+    fn from(e: sqlx::Error) -> Self {
+        EventListenerError::DatabaseError(e.to_string())
+    }
+}
+
+/// The escrow's current fee rate (ContractConfig::fee_rate_bps), snapshotted on
+/// each trade as its TradeCreated event is indexed (AppState in production)
+#[async_trait]
+pub trait FeeRateSource: Send + Sync {
+    /// None when the contract config can't be read
+    async fn fee_rate_bps(&self, chain_id: u64) -> Option<u32>;
+}
+
+/// Transaction a log is applied in: it holds the log's processed_events record,
+/// so committing it records the log together with the handler's writes
+type EventTx = Transaction<'static, Postgres>;
+
 /// Configuration constants - UNIFIED POLLING (optimized for RPC cost)
 /// Single eth_getLogs call per cycle with no topic filter = ~75 CUs base
 /// Pay-as-you-go tier: no block range limits
@@ -34,16 +70,41 @@ pub enum EventListenerError {
 /// With 6s polling: ~3 new blocks per cycle (normal operation)
 /// BLOCKS_PER_QUERY of 200 allows fast catch-up after restarts
 const BLOCKS_PER_QUERY: u64 = 200;     // Max blocks per query (for catch-up)
-const MAX_REORG_DEPTH: u64 = 2;        // Wait 2 blocks for finality  
+const DEFAULT_CONFIRMATIONS: u64 = 2;  // Blocks behind head before indexing (see with_confirmations)
 const POLL_INTERVAL_SECS: u64 = 6;     // Poll every 6 seconds (~37M CUs/month)
 
+/// Backfill uses larger ranges to replay history quickly; the chunk is halved
+/// whenever the RPC provider rejects a range as too large.
+const BACKFILL_CHUNK_BLOCKS: u64 = 2000;
+
+/// How long applied events are remembered (re-reads older than this aren't deduplicated)
+const PROCESSED_EVENTS_RETENTION: Duration = Duration::from_secs(7 * 24 * 3600);
+
+/// How often the listener prunes processed-event records
+const PROCESSED_EVENTS_PRUNE_INTERVAL: Duration = Duration::from_secs(3600);
+
+/// Fee rate assumed in settlement emails for legacy trades with neither a recorded
+/// fee nor a creation-time rate (1%)
+const LEGACY_FEE_RATE_BPS: u128 = 100;
+
 pub struct EventListener {
-    provider: Arc<Provider<Http>>,
+    provider: Arc<RpcProvider>,
     contract_address: Address,
     db_pool: sqlx::PgPool,
     start_block: u64,
     chain_id: i32,
     email_service: Option<Arc<EmailService>>,
+    /// True while replaying historical events (suppresses email notifications)
+    backfilling: bool,
+    /// Live orderbook feed for WebSocket clients (None = not published)
+    live_feed: Option<LiveFeed>,
+    /// Shared per-chain progress for /api/admin/listener-status (None = not reported)
+    progress: Option<ListenerProgress>,
+    /// Only blocks at least this far behind head are indexed, so events from
+    /// blocks that get re-orged out are (almost) never written
+    confirmations: u64,
+    /// Rate snapshotted on created trades (None = not snapshotted)
+    fee_rates: Option<Arc<dyn FeeRateSource>>,
 }
 
 impl EventListener {
@@ -55,8 +116,8 @@ impl EventListener {
         start_block: Option<u64>,
         chain_id: u64,
     ) -> Result<Self, EventListenerError> {
-        let provider = Provider::<Http>::try_from(rpc_url)
-            .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
+        let provider = failover::connect(rpc_url)
+            .map_err(EventListenerError::ProviderError)?;
         let provider = Arc::new(provider);
 
         // Determine start block
@@ -94,22 +155,73 @@ impl EventListener {
         Ok(Self {
             provider,
             contract_address,
-            db_pool,
             start_block,
             chain_id: chain_id as i32,
             email_service,
+            backfilling: false,
+            live_feed: None,
+            progress: None,
+            confirmations: DEFAULT_CONFIRMATIONS,
+            fee_rates: None,
+            db_pool,
         })
     }
 
-    /// Start the event listener (runs indefinitely)
-    pub async fn start(&mut self) -> Result<(), EventListenerError> {
-        tracing::info!("🚀 Starting event listener...");
+    /// Publish synced order and trade changes to `feed`
+    pub fn with_live_feed(mut self, feed: LiveFeed) -> Self {
+        self.live_feed = Some(feed);
+        self
+    }
+
+    /// Report sync progress and errors to `progress`
+    pub fn with_progress(mut self, progress: ListenerProgress) -> Self {
+        self.progress = Some(progress);
+        self
+    }
+
+    /// Index a block's events only once it is `confirmations` blocks behind head
+    /// (ChainConfig.confirmations)
+    pub fn with_confirmations(mut self, confirmations: u64) -> Self {
+        self.confirmations = confirmations;
+        self
+    }
+
+    /// Snapshot the fee rate from `rates` on each trade as it is created
+    pub fn with_fee_rates(mut self, rates: Arc<dyn FeeRateSource>) -> Self {
+        self.fee_rates = Some(rates);
+        self
+    }
+
+    /// Apply logs as replayed history, like `backfill` does: no emails or
+    /// live-feed events (for one-off repairs, see `apply_log`)
+    pub fn replaying(mut self) -> Self {
+        self.backfilling = true;
+        self
+    }
+
+    /// Start the event listener; runs until `shutdown` flips (or its sender is dropped)
+    ///
+    /// Shutdown is only checked between sync cycles, so a block range that is being
+    /// written always finishes and checkpoints before the listener returns.
+    pub async fn start(&mut self, mut shutdown: watch::Receiver<bool>) -> Result<(), EventListenerError> {
+        tracing::info!("🚀 Starting event listener ({} confirmations)...", self.confirmations);
+        if let Some(progress) = &self.progress {
+            progress.record_confirmations(self.chain_id as u64, self.confirmations);
+        }
 
         let mut poll_interval = interval(Duration::from_secs(POLL_INTERVAL_SECS));
+        let mut prune_interval = interval(PROCESSED_EVENTS_PRUNE_INTERVAL);
         let mut consecutive_errors = 0u32;
 
         loop {
-            poll_interval.tick().await;
+            tokio::select! {
+                _ = poll_interval.tick() => {}
+                _ = prune_interval.tick() => {
+                    self.prune_processed_events().await;
+                    continue;
+                }
+                _ = shutdown.changed() => break,
+            }
 
             match self.sync_events().await {
                 Ok(_) => {
@@ -118,16 +230,27 @@ impl EventListener {
                 Err(e) => {
                     consecutive_errors += 1;
                     tracing::error!("❌ Event sync error (attempt {}): {}", consecutive_errors, e);
+                    if let Some(progress) = &self.progress {
+                        progress.record_error(self.chain_id as u64, &e.to_string());
+                    }
                     
                     // If we have multiple consecutive errors, add exponential backoff
                     if consecutive_errors >= 3 {
                         let backoff_secs = std::cmp::min(60, 5 * consecutive_errors as u64);
                         tracing::warn!("⏳ Too many errors, backing off for {}s", backoff_secs);
-                        tokio::time::sleep(Duration::from_secs(backoff_secs)).await;
+                        tokio::select! {
+                            _ = tokio::time::sleep(Duration::from_secs(backoff_secs)) => {}
+                            _ = shutdown.changed() => break,
+                        }
                     }
                 }
             }
         }
+
+        // Each successful cycle already checkpoints; saving again covers a final failed one
+        Self::save_last_synced_block(&self.db_pool, &self.contract_address, self.start_block).await?;
+        tracing::info!("🛑 Event listener for chain {} stopped, resuming from block {} on restart", self.chain_id, self.start_block);
+        Ok(())
     }
 
     /// Sync events from blockchain to database using UNIFIED POLLING
@@ -140,16 +263,12 @@ impl EventListener {
             .map_err(|e| EventListenerError::ProviderError(e.to_string()))?
             .as_u64();
 
-        // Apply reorg protection (don't process very recent blocks)
-        let safe_block = current_block.saturating_sub(MAX_REORG_DEPTH);
-
-        if self.start_block >= safe_block {
+        // Reorg protection: blocks within the confirmation window wait for a later cycle
+        let Some(to_block) = next_sync_end(self.start_block, current_block, self.confirmations) else {
             // Nothing new to sync
+            self.report_progress(current_block, 0);
             return Ok(());
-        }
-
-        // Process blocks in chunks
-        let to_block = std::cmp::min(self.start_block + BLOCKS_PER_QUERY, safe_block);
+        };
 
         tracing::debug!(
             "📊 Syncing blocks {} to {} (current: {})",
@@ -171,60 +290,210 @@ impl EventListener {
             .await
             .map_err(|e| EventListenerError::ProviderError(e.to_string()))?;
 
-        if !all_logs.is_empty() {
-            tracing::info!("📦 Fetched {} total events in unified call", all_logs.len());
+        let event_count = all_logs.len();
+        if event_count > 0 {
+            tracing::info!("📦 Fetched {} total events in unified call", event_count);
         }
 
         // Route logs to appropriate handlers based on topic0 (event signature)
         for log in all_logs {
-            if log.topics.is_empty() {
-                continue;
-            }
-            
-            let topic0 = log.topics[0];
-            
-            // Route by event signature hash
-            if topic0 == OrderCreatedFilter::signature() {
-                if let Err(e) = self.handle_order_created(log).await {
-                    tracing::error!("❌ Failed to handle OrderCreated: {}", e);
-                }
-            } else if topic0 == OrderWithdrawnFilter::signature() {
-                if let Err(e) = self.handle_order_withdrawn(log).await {
-                    tracing::error!("❌ Failed to handle OrderWithdrawn: {}", e);
-                }
-            } else if topic0 == TradeCreatedFilter::signature() {
-                if let Err(e) = self.handle_trade_created(log).await {
-                    tracing::error!("❌ Failed to handle TradeCreated: {}", e);
-                }
-            } else if topic0 == TradeSettledFilter::signature() {
-                if let Err(e) = self.handle_trade_settled(log).await {
-                    tracing::error!("❌ Failed to handle TradeSettled: {}", e);
-                }
-            } else if topic0 == TradeExpiredFilter::signature() {
-                if let Err(e) = self.handle_trade_expired(log).await {
-                    tracing::error!("❌ Failed to handle TradeExpired: {}", e);
-                }
-            } else if topic0 == ExchangeRateUpdatedFilter::signature() {
-                if let Err(e) = self.handle_exchange_rate_updated(log).await {
-                    tracing::error!("❌ Failed to handle ExchangeRateUpdated: {}", e);
-                }
-            } else if topic0 == AccountLinesHashUpdatedFilter::signature() {
-                if let Err(e) = self.handle_account_lines_hash_updated(log).await {
-                    tracing::error!("❌ Failed to handle AccountLinesHashUpdated: {}", e);
-                }
-            } else {
-                tracing::debug!("Unknown event topic: {:?}", topic0);
-            }
+            self.process_log(log).await;
         }
 
         // Update last synced block
         self.start_block = to_block + 1;
         Self::save_last_synced_block(&self.db_pool, &self.contract_address, self.start_block)
             .await?;
+        self.report_progress(current_block, event_count);
 
         Ok(())
     }
 
+    /// Drop processed-event records past the retention window
+    async fn prune_processed_events(&self) {
+        let repo = ProcessedEventRepository::new(self.db_pool.clone());
+        match repo.prune(PROCESSED_EVENTS_RETENTION).await {
+            Ok(0) => {}
+            Ok(pruned) => tracing::info!("🧹 Pruned {} processed event records (chain {})", pruned, self.chain_id),
+            Err(e) => tracing::warn!("⚠️ Failed to prune processed events (chain {}): {}", self.chain_id, e),
+        }
+    }
+
+    /// Record a completed cycle (`start_block` is the next block to process)
+    fn report_progress(&self, head_block: u64, events_written: usize) {
+        if let Some(progress) = &self.progress {
+            progress.record_sync(self.chain_id as u64, self.start_block.saturating_sub(1), head_block, events_written);
+        }
+    }
+
+    /// Replay historical events in `[from_block, to_block]` into the database.
+    ///
+    /// Logs are fetched in chunks of `BACKFILL_CHUNK_BLOCKS`; if the provider caps
+    /// the range or result count, the chunk is halved and retried. Handlers are
+    /// idempotent (upserts), and remaining amounts of every touched order are
+    /// re-read from the contract afterwards, so re-running a backfill is safe.
+    /// Email notifications are suppressed while replaying.
+    ///
+    /// Returns the number of logs processed.
+    pub async fn backfill(&mut self, from_block: u64, to_block: u64) -> Result<u64, EventListenerError> {
+        tracing::info!("⏪ Backfilling blocks {} to {} (chain {})", from_block, to_block, self.chain_id);
+
+        let mut chunk = BACKFILL_CHUNK_BLOCKS;
+        let mut block = from_block;
+        let mut processed = 0u64;
+        let mut touched_orders: HashSet<H256> = HashSet::new();
+
+        self.backfilling = true;
+
+        while block <= to_block {
+            let chunk_end = std::cmp::min(block.saturating_add(chunk - 1), to_block);
+            let filter = Filter::new()
+                .address(self.contract_address)
+                .from_block(block)
+                .to_block(chunk_end);
+
+            let logs = match self.provider.get_logs(&filter).await {
+                Ok(logs) => logs,
+                Err(e) if chunk > 1 && is_log_range_error(&e.to_string()) => {
+                    chunk /= 2;
+                    tracing::warn!("⚠️ Log range {}-{} rejected, retrying with {} blocks", block, chunk_end, chunk);
+                    continue;
+                }
+                Err(e) => {
+                    self.backfilling = false;
+                    return Err(EventListenerError::ProviderError(e.to_string()));
+                }
+            };
+
+            for log in logs {
+                if let Some(order_id) = order_id_topic(&log) {
+                    touched_orders.insert(order_id);
+                }
+                self.process_log(log).await;
+                processed += 1;
+            }
+
+            tracing::info!("⏪ Backfilled blocks {} to {} ({} events so far)", block, chunk_end, processed);
+            block = chunk_end + 1;
+        }
+
+        self.backfilling = false;
+
+        // Trade created/expired handlers apply deltas to remainingAmount, which is not
+        // idempotent on replay - take the authoritative value from the contract instead.
+        let contract = LyncZEscrow::new(self.contract_address, self.provider.clone());
+        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
+        for order_id in touched_orders {
+            let order_id_str = format!("0x{}", hex::encode(order_id));
+            let remaining = match contract.orders(order_id.0).call().await {
+                Ok(order) => order.4,
+                Err(e) => {
+                    tracing::warn!("⚠️ Failed to read order {} from chain: {}", order_id_str, e);
+                    continue;
+                }
+            };
+            if let Err(e) = order_repo.set_remaining_amount(&order_id_str, &remaining.to_string()).await {
+                tracing::warn!("⚠️ Failed to reconcile order {}: {}", order_id_str, e);
+            }
+        }
+
+        // Resume live sync after the backfilled range
+        if to_block + 1 > self.start_block {
+            self.start_block = to_block + 1;
+            Self::save_last_synced_block(&self.db_pool, &self.contract_address, self.start_block)
+                .await?;
+        }
+
+        tracing::info!("✅ Backfill complete: {} events processed", processed);
+        Ok(processed)
+    }
+
+    /// Apply a log unless it was already applied, so re-reading a block range
+    /// (restart, backfill, reorg re-processing) applies each event once
+    ///
+    /// The log is claimed in processed_events inside the transaction its handler
+    /// writes in. A failed handler rolls the claim back with its writes, but the
+    /// sync loop still moves past the log, so it is only retried by a backfill
+    /// (`--backfill-from`) or the resync tool; notifications are only sent once
+    /// the transaction commits.
+    async fn process_log(&self, log: Log) {
+        let (block, index, tx_hash) = (log.block_number, log.log_index, log.transaction_hash);
+        // Failures are logged with the log's position so the range can be repaired
+        if let Err(e) = self.apply_log(log).await {
+            let block = block.map_or_else(|| "pending".to_string(), |b| b.to_string());
+            tracing::error!(
+                "❌ Skipped log on chain {} at block {}, index {} (tx {}): {} - replay with --backfill-from {}",
+                self.chain_id,
+                block,
+                index.map_or_else(|| "pending".to_string(), |i| i.to_string()),
+                tx_hash.map_or_else(|| "pending".to_string(), |h| format!("{:#x}", h)),
+                e,
+                block,
+            );
+        }
+    }
+
+    /// Apply one log the way the sync loop does (see process_log), e.g. a repair
+    /// by the resync tool; false if it was already applied
+    pub async fn apply_log(&self, log: Log) -> Result<bool, EventListenerError> {
+        let key = processed_event_key(self.chain_id, &log);
+        let tx = match self.begin_once(key.as_ref()).await {
+            Ok(Some(tx)) => tx,
+            Ok(None) => {
+                tracing::debug!("⏭️ Skipping already processed log {:?}", key);
+                return Ok(false);
+            }
+            Err(e) => {
+                tracing::error!("❌ Failed to claim log {:?}: {}", key, e);
+                return Err(e.into());
+            }
+        };
+        self.route_log(log, tx).await?;
+        Ok(true)
+    }
+
+    /// Transaction to apply a log in, with the log claimed; None if it already was.
+    /// A pending log (no block or index yet) can't be keyed, so it isn't claimed.
+    async fn begin_once(&self, key: Option<&ProcessedEventKey>) -> DbResult<Option<EventTx>> {
+        match key {
+            Some(key) => ProcessedEventRepository::new(self.db_pool.clone()).begin_claimed(key).await,
+            None => Ok(Some(self.db_pool.begin().await?)),
+        }
+    }
+
+    /// Route a log to the appropriate handler based on topic0 (event signature);
+    /// a handler that fails drops its transaction, rolling the log's claim back
+    async fn route_log(&self, log: Log, tx: EventTx) -> Result<(), EventListenerError> {
+        let Some(&topic0) = log.topics.first() else {
+            return commit_unhandled(tx).await;
+        };
+        
+        // Route by event signature hash
+        let (event, result) = if topic0 == OrderCreatedFilter::signature() {
+            ("OrderCreated", self.handle_order_created(log, tx).await)
+        } else if topic0 == OrderWithdrawnFilter::signature() {
+            ("OrderWithdrawn", self.handle_order_withdrawn(log, tx).await)
+        } else if topic0 == TradeCreatedFilter::signature() {
+            ("TradeCreated", self.handle_trade_created(log, tx).await)
+        } else if topic0 == TradeSettledFilter::signature() {
+            ("TradeSettled", self.handle_trade_settled(log, tx).await)
+        } else if topic0 == TradeExpiredFilter::signature() {
+            ("TradeExpired", self.handle_trade_expired(log, tx).await)
+        } else if topic0 == ExchangeRateUpdatedFilter::signature() {
+            ("ExchangeRateUpdated", self.handle_exchange_rate_updated(log, tx).await)
+        } else if topic0 == AccountLinesHashUpdatedFilter::signature() {
+            ("AccountLinesHashUpdated", self.handle_account_lines_hash_updated(log, tx).await)
+        } else {
+            tracing::debug!("Unknown event topic: {:?}", topic0);
+            return commit_unhandled(tx).await;
+        };
+        
+        if let Err(e) = &result {
+            tracing::error!("❌ Failed to handle {}: {}", event, e);
+        }
+        result
+    }
+
     // ================================================================
     // EVENT HANDLER: OrderCreated (v4 - Privacy)
     // New signature: OrderCreated(bytes32 indexed orderId, address indexed seller, address indexed token, 
@@ -235,7 +504,7 @@ impl EventListener {
     //          Seller must call POST /api/orders/:orderId/payment-info to submit plain text.
     // ================================================================
 
-    async fn handle_order_created(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_order_created(&self, log: Log, mut tx: EventTx) -> Result<(), EventListenerError> {
         // Decode event
         let event: OrderCreatedFilter = ethers::contract::parse_log(log)
             .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
@@ -275,7 +544,7 @@ impl EventListener {
             total_amount: event.total_amount.to_string(),
             remaining_amount: event.total_amount.to_string(),
             exchange_rate: event.exchange_rate.to_string(),
-            rail: event.rail as i32,                   // PaymentRail: 0=ALIPAY, 1=WECHAT
+            rail: PaymentRail::try_from(event.rail).map_err(EventListenerError::EventDecodeError)?,
             alipay_id: String::new(),                  // Empty - seller submits via API
             alipay_name: String::new(),                // Empty - seller submits via API
             created_at: chrono::Utc::now().timestamp(),
@@ -283,17 +552,13 @@ impl EventListener {
             synced_at: chrono::Utc::now(),
             is_public: event.is_public,                // From on-chain event
             private_code: None,                        // Generated when seller sets visibility
+            currency: Currency::default().code().to_string(), // Not on-chain yet: CNY
         };
 
-        match order_repo.create(&db_order).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} synced to database (awaiting payment info)", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database insert failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        orders::insert_order(&mut *tx, &db_order).await?;
+        tx.commit().await?;
+        tracing::info!("✅ Order {} synced to database (awaiting payment info)", order_id);
+        self.publish_live(&order_id, |feed, order| feed.publish(LiveEventKind::OrderCreated, order, None)).await;
 
         // After syncing, check if payment info was submitted before the event (race condition)
         // If so, verify the hash matches and send the order creation email
@@ -321,6 +586,11 @@ impl EventListener {
                         synced_order.alipay_name,
                         synced_order.alipay_id
                     );
+                    tracing::error!(
+                        "🔍 Account lines hash diagnostic for order {}:\n  {}",
+                        order_id,
+                        crate::crypto::AccountLinesDiagnostic::new(&synced_order.alipay_name, &synced_order.alipay_id)
+                    );
                     // Don't send email - something is wrong!
                     return Ok(());
                 }
@@ -331,22 +601,25 @@ impl EventListener {
                 let seller_lower = format!("{:#x}", event.seller).to_lowercase();
                 let email_repo = AccountEmailRepository::new(self.db_pool.clone());
                 if let Ok(Some(account_email)) = email_repo.get_if_enabled(&seller_lower).await {
-                    if let Some(ref email_service) = self.email_service {
+                    if let Some(email_service) = self.email_service.as_ref().filter(|_| !self.backfilling) {
                         let token_symbol = get_token_symbol(&synced_order.token);
                         let token_decimals = get_token_decimals(&synced_order.token);
                         
-                        let _ = email_service.send_notification(
+                        email_service.send_or_enqueue(
+                            &self.db_pool,
+                            &account_email.wallet,
                             &account_email.email,
                             &account_email.language,
                             crate::email::EmailEvent::OrderCreated,
                             &crate::email::EmailInfo::OrderCreated {
                                 order_id: order_id.clone(),
-                                token_amount: format_token_amount(&synced_order.total_amount, token_decimals, ""),
+                                token_amount: format_token_amount(&synced_order.total_amount, token_decimals, "").unwrap_or_else(InvalidAmount::into_raw),
                                 token_symbol,
                                 exchange_rate: synced_order.exchange_rate.clone(),
+                                currency: Currency::from_code_or_default(&synced_order.currency),
                                 account_id: synced_order.alipay_id.clone(),
                                 account_name: synced_order.alipay_name.clone(),
-                                rail: synced_order.rail,  // Pass rail number, template will localize
+                                rail: synced_order.rail,
                                 is_private: !synced_order.is_public,
                                 private_code: synced_order.private_code.clone(),
                             },
@@ -365,60 +638,49 @@ impl EventListener {
     // EVENT HANDLER: OrderWithdrawn
     // ================================================================
 
-    async fn handle_order_withdrawn(&self, log: Log) -> Result<(), EventListenerError> {
-        let tx_hash = log.transaction_hash
-            .map(|h| format!("{:#x}", h));
-        
-        let event: OrderWithdrawnFilter = ethers::contract::parse_log(log)
-            .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
-
-        let order_id = format!("0x{}", hex::encode(event.order_id));
+    async fn handle_order_withdrawn(&self, log: Log, mut tx: EventTx) -> Result<(), EventListenerError> {
+        let withdrawal = withdrawal_from_log(log)?;
+        let order_id = withdrawal.order_id.clone();
 
         tracing::info!(
-            "💸 OrderWithdrawn:\n  order_id: {}\n  withdrawnAmount: {}\n  remainingAmount: {}",
+            "💸 OrderWithdrawn:\n  order_id: {}\n  withdrawnAmount: {}\n  remainingAmount: {}\n  tx: {}",
             order_id,
-            event.withdrawn_amount,
-            event.remaining_amount
+            withdrawal.amount,
+            withdrawal.remaining_after,
+            withdrawal.tx_hash
         );
 
         // DATABASE SYNC: Update remaining amount
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        let delta = format!("-{}", event.withdrawn_amount);
-        
-        match order_repo.adjust_remaining_amount(&order_id, &delta).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} remaining amount adjusted", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        let delta = format!("-{}", withdrawal.amount);
+        orders::adjust_remaining_amount(&mut *tx, &order_id, &delta).await?;
 
         // DATABASE SYNC: Record withdrawal for activity timeline
-        use crate::db::withdrawals::PostgresWithdrawalRepository;
-        let withdrawal_repo = PostgresWithdrawalRepository::new(self.db_pool.clone());
-        match withdrawal_repo.create(
-            &order_id,
-            &event.withdrawn_amount.to_string(),
-            &event.remaining_amount.to_string(),
-            tx_hash.as_deref(),
-        ).await {
-            Ok(_) => {
-                tracing::info!("✅ Withdrawal recorded for order {}", order_id);
-            }
+        if withdrawals::withdrawal_exists(&mut *tx, &order_id, &withdrawal.tx_hash).await? {
+            tx.commit().await?;
+            tracing::info!("⏭️ Withdrawal {} for order {} already recorded", withdrawal.tx_hash, order_id);
+            return Ok(());
+        }
+        // Don't fail the whole handler - withdrawal is recorded for UI only, so
+        // a failed insert only rolls back to its savepoint
+        let mut savepoint = tx.begin().await?;
+        match withdrawals::insert_withdrawal(&mut *savepoint, &withdrawal).await {
+            Ok(()) => savepoint.commit().await?,
             Err(e) => {
                 tracing::error!("❌ Failed to record withdrawal: {}", e);
-                // Don't fail the whole handler - withdrawal is recorded for UI only
+                savepoint.rollback().await?;
             }
         }
+        tx.commit().await?;
+        tracing::info!("✅ Order {} remaining amount adjusted", order_id);
+        self.publish_live(&order_id, LiveFeed::publish_remaining).await;
 
         // Send email notification to seller
+        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
         if let Ok(order) = order_repo.get(&order_id).await {
             let token_symbol = get_token_symbol(&order.token);
             let decimals = get_token_decimals(&order.token);
-            let formatted_withdrawn = format_token_amount(&event.withdrawn_amount.to_string(), decimals, "");
-            let formatted_remaining = format_token_amount(&event.remaining_amount.to_string(), decimals, "");
+            let formatted_withdrawn = format_token_amount(&withdrawal.amount, decimals, "").unwrap_or_else(InvalidAmount::into_raw);
+            let formatted_remaining = format_token_amount(&withdrawal.remaining_after, decimals, "").unwrap_or_else(InvalidAmount::into_raw);
             
             self.send_email_notification(
                 EmailEvent::OrderWithdrawn,
@@ -439,7 +701,7 @@ impl EventListener {
     // EVENT HANDLER: ExchangeRateUpdated
     // ================================================================
 
-    async fn handle_exchange_rate_updated(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_exchange_rate_updated(&self, log: Log, mut tx: EventTx) -> Result<(), EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h));
         
@@ -457,30 +719,22 @@ impl EventListener {
         );
 
         // DATABASE SYNC: Update exchange rate
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        match order_repo.update_exchange_rate(&order_id, &event.new_rate.to_string()).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} exchange rate updated to {}", order_id, event.new_rate);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        orders::update_exchange_rate(&mut *tx, &order_id, &event.new_rate.to_string()).await?;
+        tx.commit().await?;
+        tracing::info!("✅ Order {} exchange rate updated to {}", order_id, event.new_rate);
+        self.publish_live(&order_id, |feed, order| feed.publish(LiveEventKind::OrderUpdated, order, None)).await;
 
         // Send email notification to seller
+        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
         if let Ok(order) = order_repo.get(&order_id).await {
-            // Format exchange rates (divide by 100 since stored in cents)
-            let old_rate = (event.old_rate.as_u64() as f64) / 100.0;
-            let new_rate = (event.new_rate.as_u64() as f64) / 100.0;
-            
             self.send_email_notification(
                 EmailEvent::OrderUpdated,
                 &order.seller,
                 EmailInfo::ExchangeRateUpdated {
                     order_id: order_id.clone(),
-                    old_rate: format!("{:.2}", old_rate),
-                    new_rate: format!("{:.2}", new_rate),
+                    old_rate: event.old_rate.to_string(),
+                    new_rate: event.new_rate.to_string(),
+                    currency: Currency::from_code_or_default(&order.currency),
                 },
             ).await;
         }
@@ -497,7 +751,7 @@ impl EventListener {
     // Users must create a new order if they want different payment details.
     // ================================================================
 
-    async fn handle_account_lines_hash_updated(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_account_lines_hash_updated(&self, log: Log, tx: EventTx) -> Result<(), EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h));
         
@@ -519,6 +773,7 @@ impl EventListener {
         // NOTE: Payment info updates are no longer allowed via the API.
         // This event is kept for auditing in case someone calls the contract directly.
 
+        tx.commit().await?;
         Ok(())
     }
 
@@ -529,7 +784,7 @@ impl EventListener {
     // NOTE: No more paymentNonce - transaction_id comes from PDF parsing
     // ================================================================
 
-    async fn handle_trade_created(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_trade_created(&self, log: Log, mut tx: EventTx) -> Result<(), EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h))
             .unwrap_or_default();
@@ -561,13 +816,12 @@ impl EventListener {
         );
 
         // DATABASE SYNC: Create trade record
-        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
         let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
         
-        // Get order to fetch the rail (payment method)
-        let rail = match order_repo.get(&order_id).await {
-            Ok(order) => order.rail,
-            Err(_) => 0, // Default to ALIPAY if order not found
+        // Get order to fetch the rail (payment method) and fiat currency
+        let (rail, currency) = match order_repo.get(&order_id).await {
+            Ok(order) => (order.rail, order.currency),
+            Err(_) => (PaymentRail::default(), Currency::default().code().to_string()), // Default to ALIPAY/CNY if order not found
         };
         
         let db_trade = DbTrade {
@@ -577,6 +831,7 @@ impl EventListener {
             token_amount: event.token_amount.to_string(),
             cny_amount: event.fiat_amount.to_string(), // fiatAmount in cents
             fee_amount: Some(event.fee_amount.to_string()), // Fee from blockchain event (actual fee rate)
+            fee_rate_bps: self.creation_fee_rate_bps().await, // Rate this trade was created at
             rail, // PaymentRail from order
             transaction_id: None, // Populated when proof is submitted
             payment_time: None, // Populated when proof is submitted
@@ -598,47 +853,31 @@ impl EventListener {
             proof_json: None,
             settlement_error: None, // Set when blockchain submission fails
             chain_id: self.chain_id, // Chain this event listener is monitoring
+            currency, // Fiat currency from order
             alipay_id: None, // Will be fetched from order when needed
             alipay_name: None, // Will be fetched from order when needed
+            order_closed: None,
         };
 
-        match trade_repo.create(&db_trade).await {
-            Ok(_) => {
-                tracing::info!("✅ Trade {} created in database", trade_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database insert failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        trades::insert_trade(&mut *tx, &db_trade).await?;
 
         // Adjust order remaining amount (tokenAmount + feeAmount from event)
         // Fee comes directly from blockchain event - no hardcoding needed!
         let total_reserve = event.token_amount + event.fee_amount;
         let delta = format!("-{}", total_reserve);
-        
-        match order_repo.adjust_remaining_amount(&order_id, &delta).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} remaining amount adjusted by -{} (token: {}, fee: {})", 
-                    order_id, total_reserve, event.token_amount, event.fee_amount);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        orders::adjust_remaining_amount(&mut *tx, &order_id, &delta).await?;
+        tx.commit().await?;
 
-        // Send email notifications to both seller AND buyer
-        if let Ok(order) = order_repo.get(&order_id).await {
-            let token_symbol = get_token_symbol(&order.token);
-            let decimals = get_token_decimals(&order.token);
-            let buyer_address = format!("{:#x}", event.buyer).to_lowercase();
-            let formatted_token_amount = format_token_amount(&event.token_amount.to_string(), decimals, "");
-            
-            // NOTE: TradeCreated emails removed - users see pending trades in activity timeline instead
-            // (Both seller and buyer will see the trade in their respective order/purchase pages)
-            let _ = (buyer_address, formatted_token_amount, token_symbol); // Silence unused warnings
-        }
+        tracing::info!("✅ Trade {} created in database", trade_id);
+        tracing::info!("✅ Order {} remaining amount adjusted by -{} (token: {}, fee: {})", 
+            order_id, total_reserve, event.token_amount, event.fee_amount);
+        self.publish_live(&order_id, |feed, order| {
+            feed.publish(LiveEventKind::TradeCreated, order, Some(&trade_id));
+            feed.publish_remaining(order);
+        }).await;
+
+        // NOTE: TradeCreated emails removed - users see pending trades in activity timeline instead
+        // (Both seller and buyer will see the trade in their respective order/purchase pages)
 
         Ok(())
     }
@@ -648,7 +887,7 @@ impl EventListener {
     // New signature: TradeSettled(bytes32 indexed tradeId, string transactionId)
     // ================================================================
 
-    async fn handle_trade_settled(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_trade_settled(&self, log: Log, mut tx: EventTx) -> Result<(), EventListenerError> {
         let tx_hash = log.transaction_hash
             .map(|h| format!("{:#x}", h))
             .unwrap_or_default();
@@ -670,42 +909,37 @@ impl EventListener {
 
         // DATABASE SYNC: Update trade status to SETTLED
         // NOTE: remainingAmount was already deducted at TradeCreated, no adjustment needed here
-        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
-        
-        match trade_repo.update_status(&trade_id, 1).await {
-            Ok(_) => {
-                tracing::info!("✅ Trade {} status updated to SETTLED", trade_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
-
+        trades::update_status(&mut *tx, &trade_id, 1).await?;
         if !tx_hash.is_empty() {
-            if let Err(e) = trade_repo.update_settlement_tx(&trade_id, &tx_hash).await {
-                tracing::warn!("⚠️ Failed to update settlement tx hash: {}", e);
-            }
+            trades::update_settlement_tx(&mut *tx, &trade_id, &tx_hash).await?;
         }
+        tx.commit().await?;
+        tracing::info!("✅ Trade {} status updated to SETTLED", trade_id);
+
+        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
 
         // Send email notifications to both seller AND buyer
-        if let Ok(trade) = trade_repo.get(&trade_id).await {
+        if let Ok(mut trade) = trade_repo.get(&trade_id).await {
+            if trade.fee_amount.is_none() {
+                trade.fee_amount = self.record_trade_fee(&trade_repo, event.trade_id, &trade_id).await;
+            }
             let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
             if let Ok(order) = order_repo.get(&trade.order_id).await {
+                if let Some(feed) = self.live_feed() {
+                    feed.publish(LiveEventKind::TradeSettled, &order, Some(&trade_id));
+                }
+                
                 let token_symbol = get_token_symbol(&order.token);
                 let decimals = get_token_decimals(&order.token);
-                let formatted_token_amount = format_token_amount(&trade.token_amount, decimals, "");
+                let formatted_token_amount = format_token_amount(&trade.token_amount, decimals, "").unwrap_or_else(InvalidAmount::into_raw);
                 
-                // Use fee from database (stored from TradeCreated event) - blockchain is source of truth
-                let formatted_fee = match &trade.fee_amount {
-                    Some(fee) => format_token_amount(fee, decimals, ""),
-                    None => {
-                        // Fallback: calculate from current blockchain config if fee wasn't stored
-                        tracing::warn!("Trade {} missing fee_amount, falling back to 1%", trade_id);
-                        let token_amount_u256 = trade.token_amount.parse::<u128>().unwrap_or(0);
-                        format_token_amount(&(token_amount_u256 / 100).to_string(), decimals, "")
-                    }
-                };
+                // Use fee from database (TradeCreated event, or the escrow's record above) - blockchain is source of truth
+                if trade.fee_amount.is_none() {
+                    tracing::warn!("Trade {} missing fee_amount, estimating from its creation fee rate (or 1%)", trade_id);
+                }
+                let formatted_fee = trade.fee_or_estimate(LEGACY_FEE_RATE_BPS)
+                    .and_then(|fee| format_token_amount(&fee, decimals, ""))
+                    .unwrap_or_else(InvalidAmount::into_raw);
                 
                 // Email to SELLER: Trade settled, payment received
                 self.send_email_notification(
@@ -717,6 +951,7 @@ impl EventListener {
                         token_amount: formatted_token_amount.clone(),
                         token_symbol: token_symbol.clone(),
                         cny_amount: trade.cny_amount.clone(),
+                        currency: Currency::from_code_or_default(&trade.currency),
                         fee_amount: formatted_fee,
                         buyer_address: trade.buyer.clone(),
                         settlement_tx: tx_hash.clone(),
@@ -743,11 +978,46 @@ impl EventListener {
         Ok(())
     }
 
+    /// The contract's fee rate to snapshot on a trade being created; None while
+    /// backfilling, since today's rate needn't be the one a replayed trade was
+    /// created at
+    async fn creation_fee_rate_bps(&self) -> Option<i32> {
+        let rates = self.fee_rates.as_ref().filter(|_| !self.backfilling)?;
+        let bps = rates.fee_rate_bps(self.chain_id as u64).await?;
+        i32::try_from(bps).ok()
+    }
+
+    /// TradeSettled doesn't carry the fee: for a trade indexed without one, read the
+    /// escrow's per-trade fee and persist it so later displays don't have to estimate
+    async fn record_trade_fee(
+        &self,
+        trade_repo: &PostgresTradeRepository,
+        trade_id_bytes: [u8; 32],
+        trade_id: &str,
+    ) -> Option<String> {
+        let contract = LyncZEscrow::new(self.contract_address, self.provider.clone());
+        let fee = match contract.get_trade_fee(trade_id_bytes).call().await {
+            // Zero can't be told apart from a cleared record; leave the fee unknown
+            Ok(fee) if fee.is_zero() => return None,
+            Ok(fee) => fee.to_string(),
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to read fee of trade {} from chain: {}", trade_id, e);
+                return None;
+            }
+        };
+        match trade_repo.update_fee_amount(trade_id, &fee).await {
+            Ok(true) => tracing::info!("✅ Trade {} fee recorded from chain: {}", trade_id, fee),
+            Ok(false) => tracing::debug!("Trade {} fee was recorded in the meantime", trade_id),
+            Err(e) => tracing::warn!("⚠️ Failed to record fee of trade {}: {}", trade_id, e),
+        }
+        Some(fee)
+    }
+
     // ================================================================
     // EVENT HANDLER: TradeExpired
     // ================================================================
 
-    async fn handle_trade_expired(&self, log: Log) -> Result<(), EventListenerError> {
+    async fn handle_trade_expired(&self, log: Log, mut tx: EventTx) -> Result<(), EventListenerError> {
         let event: TradeExpiredFilter = ethers::contract::parse_log(log)
             .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
 
@@ -761,38 +1031,40 @@ impl EventListener {
             event.total_returned
         );
 
-        // DATABASE SYNC: Update trade status to EXPIRED
-        let trade_repo = PostgresTradeRepository::new(self.db_pool.clone());
-        
-        match trade_repo.update_status(&trade_id, 2).await {
-            Ok(_) => {
-                tracing::info!("✅ Trade {} status updated to EXPIRED", trade_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        // DATABASE SYNC: Update trade status to EXPIRED and add tokens back to order (includes fee)
+        trades::update_status(&mut *tx, &trade_id, 2).await?;
+        orders::adjust_remaining_amount(&mut *tx, &order_id, &event.total_returned.to_string()).await?;
+        tx.commit().await?;
+        tracing::info!("✅ Trade {} status updated to EXPIRED, order {} remaining amount adjusted", trade_id, order_id);
 
-        // Add tokens back to order (includes fee)
-        let order_repo = PostgresOrderRepository::new(self.db_pool.clone());
-        let delta = event.total_returned.to_string();
-        
-        match order_repo.adjust_remaining_amount(&order_id, &delta).await {
-            Ok(_) => {
-                tracing::info!("✅ Order {} remaining amount adjusted", order_id);
-            }
-            Err(e) => {
-                tracing::error!("❌ Database update failed: {}", e);
-                return Err(EventListenerError::DatabaseError(e.to_string()));
-            }
-        }
+        self.publish_live(&order_id, |feed, order| {
+            feed.publish(LiveEventKind::TradeExpired, order, Some(&trade_id));
+            feed.publish_remaining(order);
+        }).await;
 
         // NOTE: TradeExpired emails removed - users see expired trades in their activity timeline instead
 
         Ok(())
     }
 
+    // ================================================================
+    // LIVE FEED HELPERS
+    // ================================================================
+
+    /// Feed to publish to; None while backfilling (replayed history isn't live)
+    fn live_feed(&self) -> Option<&LiveFeed> {
+        self.live_feed.as_ref().filter(|_| !self.backfilling)
+    }
+
+    /// Re-read `order_id` after a sync and hand it to `publish`
+    async fn publish_live<F: FnOnce(&LiveFeed, &DbOrder)>(&self, order_id: &str, publish: F) {
+        let Some(feed) = self.live_feed() else { return };
+        match PostgresOrderRepository::new(self.db_pool.clone()).get(order_id).await {
+            Ok(order) => publish(feed, &order),
+            Err(e) => tracing::warn!("⚠️ Live feed: failed to re-read order {}: {}", order_id, e),
+        }
+    }
+
     // ================================================================
     // EMAIL NOTIFICATION HELPER (Account-based, not role-based)
     // ================================================================
@@ -805,8 +1077,8 @@ impl EventListener {
         info: EmailInfo,
     ) {
         let email_service = match &self.email_service {
-            Some(s) => s,
-            None => return, // Email not configured
+            Some(s) if !self.backfilling => s,
+            _ => return, // Email not configured (or replaying history)
         };
 
         // Look up account's email settings
@@ -827,12 +1099,11 @@ impl EventListener {
         let email_service = email_service.clone();
         let to_email = account_email.email.clone();
         let language = account_email.language.clone();
+        let wallet = account_email.wallet.clone();
+        let pool = self.db_pool.clone();
         
         tokio::spawn(async move {
-            match email_service.send_notification(&to_email, &language, event, &info).await {
-                Ok(_) => tracing::info!("📧 Email sent successfully for {:?} event", event),
-                Err(e) => tracing::error!("📧 Failed to send email: {}", e),
-            }
+            email_service.send_or_enqueue(&pool, &wallet, &to_email, &language, event, &info).await;
         });
     }
 
@@ -878,6 +1149,81 @@ impl EventListener {
     }
 }
 
+// ================================================================
+// BACKFILL HELPERS
+// ================================================================
+
+/// Decode an OrderWithdrawn log. Its transaction hash is required, so every
+/// recorded withdrawal can link to the transaction that made it.
+fn withdrawal_from_log(log: Log) -> Result<WithdrawalLog, EventListenerError> {
+    let (Some(tx_hash), Some(block_number)) = (log.transaction_hash, log.block_number) else {
+        return Err(EventListenerError::EventDecodeError(
+            "OrderWithdrawn log without transaction hash or block number".to_string(),
+        ));
+    };
+    let event: OrderWithdrawnFilter = ethers::contract::parse_log(log)
+        .map_err(|e| EventListenerError::EventDecodeError(e.to_string()))?;
+    Ok(WithdrawalLog::new(&event, tx_hash, block_number.as_u64()))
+}
+
+/// Extract the indexed orderId from an escrow log, if the event carries one.
+/// Order events index it first; TradeCreated/TradeExpired index it after tradeId.
+fn order_id_topic(log: &Log) -> Option<H256> {
+    let topic0 = *log.topics.first()?;
+    if topic0 == OrderCreatedFilter::signature()
+        || topic0 == OrderWithdrawnFilter::signature()
+        || topic0 == ExchangeRateUpdatedFilter::signature()
+        || topic0 == AccountLinesHashUpdatedFilter::signature()
+    {
+        log.topics.get(1).copied()
+    } else if topic0 == TradeCreatedFilter::signature() || topic0 == TradeExpiredFilter::signature() {
+        log.topics.get(2).copied()
+    } else {
+        None
+    }
+}
+
+/// processed_events key of a mined log (None for pending logs without a position)
+fn processed_event_key(chain_id: i32, log: &Log) -> Option<ProcessedEventKey> {
+    Some(ProcessedEventKey {
+        chain_id,
+        tx_hash: format!("{:#x}", log.transaction_hash?),
+        log_index: log.log_index?.as_u64() as i64,
+        block_number: log.block_number?.as_u64() as i64,
+    })
+}
+
+/// Commit the claim of a log no handler applies, so it isn't looked at again
+async fn commit_unhandled(tx: EventTx) -> Result<(), EventListenerError> {
+    if let Err(e) = tx.commit().await {
+        tracing::warn!("⚠️ Failed to record processed log: {}", e);
+        return Err(e.into());
+    }
+    Ok(())
+}
+
+/// Last block of the next sync chunk starting at `start_block`, or None while
+/// `start_block` is still within `confirmations` blocks of `head`
+fn next_sync_end(start_block: u64, head: u64, confirmations: u64) -> Option<u64> {
+    let confirmed = head.checked_sub(confirmations)?;
+    (start_block <= confirmed).then(|| std::cmp::min(start_block + BLOCKS_PER_QUERY, confirmed))
+}
+
+/// Whether an eth_getLogs error means the requested range was too large
+/// (providers word this differently, e.g. "query returned more than 10000 results")
+fn is_log_range_error(message: &str) -> bool {
+    let message = message.to_lowercase();
+    [
+        "query returned more than",
+        "block range",
+        "range is too large",
+        "response size exceeded",
+        "limit exceeded",
+    ]
+    .iter()
+    .any(|pattern| message.contains(pattern))
+}
+
 // ================================================================
 // TOKEN HELPERS
 // ================================================================
@@ -915,3 +1261,215 @@ fn get_token_decimals(token_address: &str) -> u8 {
         _ => 18,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_log_range_errors_detected() {
+        assert!(is_log_range_error("(code: -32005, message: query returned more than 10000 results, data: None)"));
+        assert!(is_log_range_error("eth_getLogs block range is too large"));
+        assert!(is_log_range_error("Log response size exceeded"));
+        assert!(!is_log_range_error("connection refused"));
+    }
+
+    #[test]
+    fn test_blocks_within_confirmation_window_not_synced() {
+        // Ethereum at 12 confirmations, head 1000: block 988 is the newest indexable one
+        assert_eq!(next_sync_end(980, 1000, 12), Some(988));
+        assert_eq!(next_sync_end(988, 1000, 12), Some(988));
+        assert_eq!(next_sync_end(989, 1000, 12), None);
+        // Base at 1 confirmation indexes the same range up to 999
+        assert_eq!(next_sync_end(989, 1000, 1), Some(999));
+
+        // Catch-up is still chunked, and a chain younger than the window syncs nothing
+        assert_eq!(next_sync_end(0, 10_000, 12), Some(BLOCKS_PER_QUERY));
+        assert_eq!(next_sync_end(0, 5, 12), None);
+    }
+
+    #[test]
+    fn test_processed_event_key() {
+        let log = Log {
+            transaction_hash: Some(H256::repeat_byte(0x11)),
+            log_index: Some(U256::from(3)),
+            block_number: Some(U64::from(1_000)),
+            ..Default::default()
+        };
+        let key = processed_event_key(8453, &log).unwrap();
+        assert_eq!(key.tx_hash, format!("0x{}", "11".repeat(32)));
+        assert_eq!((key.log_index, key.block_number), (3, 1_000));
+
+        // The same position on another chain is a different event
+        assert_ne!(processed_event_key(1, &log), Some(key));
+        // Pending logs have no position and are never deduplicated
+        assert_eq!(processed_event_key(8453, &Log::default()), None);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_log_and_its_writes_commit_together(pool: sqlx::PgPool) {
+        use crate::db::{models::fixtures, orders::OrderRepository};
+
+        let order_id = format!("0x{}", "ab".repeat(32));
+        let trade_id = format!("0x{}", "cd".repeat(32));
+        let order = DbOrder { remaining_amount: "999000".to_string(), ..fixtures::order(&order_id) };
+        PostgresOrderRepository::new(pool.clone()).create(&order).await.unwrap();
+        let trade_repo = PostgresTradeRepository::new(pool.clone());
+        trade_repo.create(&DbTrade { order_id: order_id.clone(), ..fixtures::trade(&trade_id, 0) }).await.unwrap();
+        let remaining = || async { PostgresOrderRepository::new(pool.clone()).get(&order_id).await.unwrap().remaining_amount };
+
+        let listener = EventListener::new("http://127.0.0.1:1", Address::zero(), pool.clone(), Some(0), 8453).await.unwrap();
+        let expired = |order: H256| Log {
+            topics: vec![TradeExpiredFilter::signature(), H256::repeat_byte(0xcd), order],
+            data: ethers::abi::encode(&[ethers::abi::Token::Uint(U256::from(500u64))]).into(),
+            transaction_hash: Some(H256::repeat_byte(0x11)),
+            log_index: Some(U256::from(3)),
+            block_number: Some(U64::from(1_000)),
+            ..Default::default()
+        };
+
+        // The order update fails after the trade was marked expired: neither that
+        // write nor the log's claim survives, so the next pass applies it afresh
+        listener.process_log(expired(H256::repeat_byte(0xee))).await;
+        assert_eq!(trade_repo.get(&trade_id).await.unwrap().status, 0);
+        let key = processed_event_key(8453, &expired(H256::zero())).unwrap();
+        let ledger = ProcessedEventRepository::new(pool.clone());
+        ledger.begin_claimed(&key).await.unwrap().expect("claim was rolled back");
+
+        // Applied, then re-read (restart, backfill): the second pass is a no-op
+        listener.process_log(expired(H256::repeat_byte(0xab))).await;
+        listener.process_log(expired(H256::repeat_byte(0xab))).await;
+        assert_eq!(trade_repo.get(&trade_id).await.unwrap().status, 2);
+        assert_eq!(remaining().await, "999500");
+        assert!(ledger.begin_claimed(&key).await.unwrap().is_none());
+    }
+
+    /// Always reports the same rate
+    struct FixedRate(u32);
+
+    #[async_trait]
+    impl FeeRateSource for FixedRate {
+        async fn fee_rate_bps(&self, _chain_id: u64) -> Option<u32> {
+            Some(self.0)
+        }
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_created_trade_snapshots_the_contract_fee_rate(pool: sqlx::PgPool) {
+        use crate::db::{models::fixtures, orders::OrderRepository};
+
+        let order_id = format!("0x{}", "ab".repeat(32));
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order(&order_id)).await.unwrap();
+        let listener = EventListener::new("http://127.0.0.1:1", Address::zero(), pool.clone(), Some(0), 8453).await.unwrap()
+            .with_fee_rates(Arc::new(FixedRate(75)));
+        let created = |trade: u8, log_index: u64| Log {
+            topics: vec![TradeCreatedFilter::signature(), H256::repeat_byte(trade), H256::repeat_byte(0xab), H256::zero()],
+            data: ethers::abi::encode(&[
+                ethers::abi::Token::Address(Address::zero()),
+                ethers::abi::Token::Uint(U256::from(100_000u64)),
+                ethers::abi::Token::Uint(U256::from(1_000u64)),
+                ethers::abi::Token::Uint(U256::from(72u64)),
+                ethers::abi::Token::Uint(U256::from(1_700_000_000u64)),
+            ]).into(),
+            transaction_hash: Some(H256::repeat_byte(0x33)),
+            log_index: Some(U256::from(log_index)),
+            block_number: Some(U64::from(3_000)),
+            ..Default::default()
+        };
+
+        // The rate in force, next to the event's own fee (1% here)
+        listener.apply_log(created(0x01, 0)).await.unwrap();
+        let trade_repo = PostgresTradeRepository::new(pool);
+        let trade = trade_repo.get(&format!("0x{}", "01".repeat(32))).await.unwrap();
+        assert_eq!((trade.fee_amount.as_deref(), trade.fee_rate_bps), (Some("1000"), Some(75)));
+
+        // Replayed trades may predate today's rate: none is snapshotted
+        let listener = listener.replaying();
+        listener.apply_log(created(0x02, 1)).await.unwrap();
+        let replayed = trade_repo.get(&format!("0x{}", "02".repeat(32))).await.unwrap();
+        assert_eq!(replayed.fee_rate_bps, None);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_applied_settlement_records_its_tx(pool: sqlx::PgPool) {
+        use crate::db::{models::fixtures, orders::OrderRepository};
+
+        let trade_id = format!("0x{}", "cd".repeat(32));
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        let trade_repo = PostgresTradeRepository::new(pool.clone());
+        trade_repo.create(&DbTrade { fee_amount: Some("0".to_string()), ..fixtures::trade(&trade_id, 0) }).await.unwrap();
+
+        let listener = EventListener::new("http://127.0.0.1:1", Address::zero(), pool, Some(0), 8453).await.unwrap().replaying();
+        let settled = Log {
+            topics: vec![TradeSettledFilter::signature(), H256::repeat_byte(0xcd), H256::repeat_byte(0x01)],
+            transaction_hash: Some(H256::repeat_byte(0x22)),
+            log_index: Some(U256::zero()),
+            block_number: Some(U64::from(2_000)),
+            ..Default::default()
+        };
+
+        assert!(listener.apply_log(settled.clone()).await.unwrap());
+        let trade = trade_repo.get(&trade_id).await.unwrap();
+        assert_eq!(trade.status, 1);
+        assert_eq!(trade.settlement_tx_hash, Some(format!("0x{}", "22".repeat(32))));
+        // e.g. the resync tool repairing a trade the listener has since caught up on
+        assert!(!listener.apply_log(settled).await.unwrap());
+    }
+
+    #[test]
+    fn test_indexed_withdrawal_carries_its_tx_hash() {
+        let data = ethers::abi::encode(&[
+            ethers::abi::Token::Uint(U256::from(250_000u64)),
+            ethers::abi::Token::Uint(U256::from(750_000u64)),
+        ]);
+        let mut log = Log {
+            topics: vec![OrderWithdrawnFilter::signature(), H256::repeat_byte(0xab)],
+            data: data.into(),
+            transaction_hash: Some(H256::repeat_byte(0x22)),
+            block_number: Some(U64::from(1_234)),
+            ..Default::default()
+        };
+
+        assert_eq!(withdrawal_from_log(log.clone()).unwrap(), WithdrawalLog {
+            order_id: format!("0x{}", "ab".repeat(32)),
+            amount: "250000".to_string(),
+            remaining_after: "750000".to_string(),
+            tx_hash: format!("0x{}", "22".repeat(32)),
+            block_number: 1_234,
+        });
+
+        // Never recorded without a hash
+        log.transaction_hash = None;
+        assert!(withdrawal_from_log(log).is_err());
+    }
+
+    #[test]
+    fn test_order_id_topic() {
+        let order_id = H256::repeat_byte(0xab);
+        let trade_id = H256::repeat_byte(0xcd);
+
+        let withdrawn = Log {
+            topics: vec![OrderWithdrawnFilter::signature(), order_id],
+            ..Default::default()
+        };
+        assert_eq!(order_id_topic(&withdrawn), Some(order_id));
+
+        let trade_created = Log {
+            topics: vec![TradeCreatedFilter::signature(), trade_id, order_id, H256::zero()],
+            ..Default::default()
+        };
+        assert_eq!(order_id_topic(&trade_created), Some(order_id));
+
+        let settled = Log {
+            topics: vec![TradeSettledFilter::signature(), trade_id, H256::zero()],
+            ..Default::default()
+        };
+        assert_eq!(order_id_topic(&settled), None);
+    }
+}
@@ -0,0 +1,308 @@
+//! RPC failover transport
+//!
+//! A chain's RPC setting may list several endpoints (comma-separated). Each
+//! request goes to the healthiest endpoint first - not cooling down after a
+//! failure, lowest observed latency, then configuration order - and moves on to
+//! the next one on connection errors, garbled responses or rate limiting.
+//! Ordinary JSON-RPC errors (reverts, bad params) are returned as-is: another
+//! provider would answer the same.
+//!
+//! Signing happens locally (LocalWallet), so the relayer's transactions and
+//! nonces are unaffected by which endpoint carries them.
+
+use async_trait::async_trait;
+use ethers::providers::{Http, JsonRpcClient, JsonRpcError, Provider, ProviderError, RpcError};
+use serde::{de::DeserializeOwned, Serialize};
+use std::fmt::Debug;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+/// Provider used by the relay for every chain
+pub type RpcProvider = Provider<FailoverTransport>;
+
+/// How long a failed endpoint is skipped (unless every endpoint is failing)
+const FAILURE_COOLDOWN: Duration = Duration::from_secs(30);
+
+/// Weight of the newest sample in the latency moving average
+const LATENCY_EWMA_WEIGHT: f64 = 0.2;
+
+/// Build a provider from one RPC URL or a comma-separated list of them
+pub fn connect(rpc_urls: &str) -> Result<RpcProvider, String> {
+    let endpoints = split_rpc_urls(rpc_urls)
+        .into_iter()
+        .map(|url| {
+            Http::from_str(url).map_err(|e| format!("Invalid RPC URL {}: {}", redact_url(url), e))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    if endpoints.is_empty() {
+        return Err("No RPC URL configured".to_string());
+    }
+    let labels = endpoints.iter().map(|http| redact_url(http.url().as_str())).collect();
+    Ok(Provider::new(FailoverTransport::new(endpoints, labels)))
+}
+
+/// Individual URLs of an RPC setting (`https://a,https://b`)
+pub fn split_rpc_urls(rpc_urls: &str) -> Vec<&str> {
+    rpc_urls.split(',').map(str::trim).filter(|u| !u.is_empty()).collect()
+}
+
+/// scheme://host of an RPC URL - paths and query strings often carry API keys
+pub fn redact_url(url: &str) -> String {
+    match reqwest::Url::parse(url) {
+        Ok(parsed) => format!("{}://{}", parsed.scheme(), parsed.host_str().unwrap_or("?")),
+        Err(_) => "<invalid url>".to_string(),
+    }
+}
+
+#[derive(Debug, Default, Clone)]
+struct EndpointHealth {
+    /// Moving average of successful request latency; None until first success
+    latency_ms: Option<f64>,
+    /// Set on failure; the endpoint is deprioritised until then
+    cooldown_until: Option<Instant>,
+    consecutive_failures: u32,
+}
+
+impl EndpointHealth {
+    fn is_cooling_down(&self, now: Instant) -> bool {
+        self.cooldown_until.is_some_and(|until| until > now)
+    }
+
+    fn record_success(&mut self, latency: Duration) {
+        let sample = latency.as_secs_f64() * 1000.0;
+        self.latency_ms = Some(match self.latency_ms {
+            Some(avg) => avg + LATENCY_EWMA_WEIGHT * (sample - avg),
+            None => sample,
+        });
+        self.cooldown_until = None;
+        self.consecutive_failures = 0;
+    }
+
+    fn record_failure(&mut self, now: Instant) {
+        self.consecutive_failures += 1;
+        self.cooldown_until = Some(now + FAILURE_COOLDOWN);
+    }
+}
+
+#[derive(Debug)]
+struct Inner<T> {
+    endpoints: Vec<T>,
+    labels: Vec<String>,
+    health: Mutex<Vec<EndpointHealth>>,
+    /// Endpoint that served the last successful request (for switch logging)
+    active: Mutex<usize>,
+}
+
+/// JSON-RPC transport that fails over between several endpoints.
+/// Cheap to clone; clones share endpoint health.
+#[derive(Debug)]
+pub struct FailoverTransport<T = Http> {
+    inner: Arc<Inner<T>>,
+}
+
+impl<T> Clone for FailoverTransport<T> {
+    fn clone(&self) -> Self {
+        Self { inner: self.inner.clone() }
+    }
+}
+
+impl<T> FailoverTransport<T> {
+    /// Endpoints in priority order with their log labels
+    pub fn new(endpoints: Vec<T>, labels: Vec<String>) -> Self {
+        let health = vec![EndpointHealth::default(); endpoints.len()];
+        Self {
+            inner: Arc::new(Inner {
+                endpoints,
+                labels,
+                health: Mutex::new(health),
+                active: Mutex::new(0),
+            }),
+        }
+    }
+
+    /// Endpoint indices in the order they should be tried
+    fn attempt_order(&self) -> Vec<usize> {
+        let now = Instant::now();
+        let health = self.inner.health.lock().unwrap();
+        let mut order: Vec<usize> = (0..health.len()).collect();
+        // Stable sort keeps configuration order between equally healthy endpoints
+        order.sort_by(|&a, &b| {
+            let (ha, hb) = (&health[a], &health[b]);
+            ha.is_cooling_down(now)
+                .cmp(&hb.is_cooling_down(now))
+                .then_with(|| match (ha.latency_ms, hb.latency_ms) {
+                    (Some(la), Some(lb)) => la.total_cmp(&lb),
+                    (Some(_), None) => std::cmp::Ordering::Less,
+                    (None, Some(_)) => std::cmp::Ordering::Greater,
+                    (None, None) => std::cmp::Ordering::Equal,
+                })
+        });
+        order
+    }
+
+    fn label(&self, index: usize) -> &str {
+        self.inner.labels.get(index).map(String::as_str).unwrap_or("?")
+    }
+
+    fn record_success(&self, index: usize, latency: Duration) {
+        self.inner.health.lock().unwrap()[index].record_success(latency);
+        let mut active = self.inner.active.lock().unwrap();
+        if *active != index {
+            tracing::warn!("🔀 RPC switched from {} to {}", self.label(*active), self.label(index));
+            *active = index;
+        }
+    }
+
+    fn record_failure(&self, index: usize) {
+        let mut health = self.inner.health.lock().unwrap();
+        health[index].record_failure(Instant::now());
+        tracing::debug!("RPC endpoint {} failed ({} in a row)", self.label(index), health[index].consecutive_failures);
+    }
+}
+
+/// Whether an error means "try another endpoint": anything except a well-formed
+/// JSON-RPC error response, unless that response is a rate limit
+fn should_fail_over<E: RpcError>(error: &E) -> bool {
+    is_transient(error.as_error_response())
+}
+
+/// Whether an RPC failure may succeed on retry, given its JSON-RPC error
+/// response (None for transport errors and garbled responses)
+pub fn is_transient(error_response: Option<&JsonRpcError>) -> bool {
+    match error_response {
+        Some(response) => is_rate_limited(response),
+        None => true,
+    }
+}
+
+/// Rate-limit responses of the common providers (Alchemy, Infura, QuickNode)
+fn is_rate_limited(error: &JsonRpcError) -> bool {
+    let message = error.message.to_lowercase();
+    error.code == 429
+        || error.code == -32005
+        || message.contains("rate limit")
+        || message.contains("too many requests")
+}
+
+/// Params serialized once and replayed to each endpoint (zero-sized params,
+/// e.g. `()`, must stay zero-sized so the request omits them)
+enum FailoverParams {
+    Value(serde_json::Value),
+    Zst(()),
+}
+
+#[async_trait]
+impl<T> JsonRpcClient for FailoverTransport<T>
+where
+    T: JsonRpcClient,
+    T::Error: Debug,
+{
+    type Error = ProviderError;
+
+    async fn request<P, R>(&self, method: &str, params: P) -> Result<R, Self::Error>
+    where
+        P: Debug + Serialize + Send + Sync,
+        R: DeserializeOwned + Send,
+    {
+        let params = if std::mem::size_of::<P>() == 0 {
+            FailoverParams::Zst(())
+        } else {
+            FailoverParams::Value(serde_json::to_value(params).map_err(ProviderError::SerdeJson)?)
+        };
+
+        let mut last_error = None;
+        for index in self.attempt_order() {
+            let endpoint = &self.inner.endpoints[index];
+            let started = Instant::now();
+            let result = match params {
+                FailoverParams::Value(ref params) => endpoint.request(method, params).await,
+                FailoverParams::Zst(unit) => endpoint.request(method, unit).await,
+            };
+            match result {
+                Ok(response) => {
+                    self.record_success(index, started.elapsed());
+                    return Ok(response);
+                }
+                Err(e) if should_fail_over(&e) => {
+                    tracing::warn!("⚠️ RPC {} via {} failed: {:?}", method, self.label(index), e);
+                    self.record_failure(index);
+                    last_error = Some(e.into());
+                }
+                Err(e) => return Err(e.into()),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| ProviderError::CustomError("No RPC endpoints configured".to_string())))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ethers::providers::{Middleware, MockProvider, MockResponse};
+    use ethers::types::U64;
+
+    fn failover(endpoints: Vec<MockProvider>) -> FailoverTransport<MockProvider> {
+        let labels = (0..endpoints.len()).map(|i| format!("mock-{}", i)).collect();
+        FailoverTransport::new(endpoints, labels)
+    }
+
+    #[tokio::test]
+    async fn test_failing_primary_falls_over_to_secondary() {
+        // Primary has no queued responses, so every request errors
+        let primary = MockProvider::new();
+        let secondary = MockProvider::new();
+        secondary.push::<U64, _>(U64::from(42)).unwrap();
+        secondary.push::<U64, _>(U64::from(43)).unwrap();
+
+        let provider = Provider::new(failover(vec![primary, secondary]));
+        assert_eq!(provider.get_block_number().await.unwrap(), U64::from(43));
+
+        // The primary is now cooling down, so the next request goes straight to the secondary
+        assert_eq!(provider.get_block_number().await.unwrap(), U64::from(42));
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_fails_over_but_rpc_errors_do_not() {
+        let rate_limited = JsonRpcError { code: 429, message: "Too Many Requests".to_string(), data: None };
+        let reverted = JsonRpcError { code: 3, message: "execution reverted".to_string(), data: None };
+
+        let primary = MockProvider::new();
+        primary.push_response(MockResponse::Error(rate_limited));
+        let secondary = MockProvider::new();
+        secondary.push::<U64, _>(U64::from(7)).unwrap();
+        let transport = failover(vec![primary, secondary.clone()]);
+        let block: U64 = transport.request("eth_blockNumber", ()).await.unwrap();
+        assert_eq!(block, U64::from(7));
+
+        // A revert is a real answer: returned without trying the other endpoint
+        secondary.push_response(MockResponse::Error(reverted));
+        let only = failover(vec![secondary]);
+        let err = only.request::<_, U64>("eth_call", ()).await.unwrap_err();
+        assert_eq!(err.as_error_response().map(|e| e.code), Some(3));
+    }
+
+    #[test]
+    fn test_fastest_healthy_endpoint_preferred() {
+        let transport = failover(vec![MockProvider::new(), MockProvider::new(), MockProvider::new()]);
+        assert_eq!(transport.attempt_order(), vec![0, 1, 2]);
+
+        transport.record_success(0, Duration::from_millis(300));
+        transport.record_success(1, Duration::from_millis(50));
+        assert_eq!(transport.attempt_order(), vec![1, 0, 2]);
+
+        transport.record_failure(1);
+        assert_eq!(transport.attempt_order(), vec![0, 2, 1]);
+    }
+
+    #[test]
+    fn test_rpc_url_list_parsing_and_redaction() {
+        assert_eq!(
+            split_rpc_urls(" https://a.example/v2/key , ,https://b.example "),
+            vec!["https://a.example/v2/key", "https://b.example"]
+        );
+        assert_eq!(redact_url("https://base-mainnet.g.alchemy.com/v2/secret"), "https://base-mainnet.g.alchemy.com");
+        assert!(connect("https://a.example,https://b.example").is_ok());
+        assert!(connect(" , ").is_err());
+    }
+}
@@ -0,0 +1,201 @@
+//! Daily relayer gas budget alerts
+//!
+//! The relayer wallet pays gas for every cancellation (and settlement). Each
+//! chain can be given a daily budget: GAS_DAILY_BUDGET_ETH applies to every
+//! chain, BASE_GAS_DAILY_BUDGET_ETH / ETH_GAS_DAILY_BUDGET_ETH override it per
+//! chain. Spend is summed from the `gas_costs` table for the current UTC day,
+//! so it survives restarts. Reaching GAS_BUDGET_WARN_PERCENT (default 80) of
+//! the budget raises a warning, reaching the budget a critical alert - each
+//! once per chain per day, as a log line and (when Resend is configured) an
+//! email to ADMIN_ALERT_EMAIL. Nothing is blocked when a budget runs out.
+//!
+//! Which levels were already raised is kept in memory, so a restart can
+//! repeat the current day's alert once.
+
+use std::collections::HashMap;
+
+use chrono::{DateTime, NaiveDate, TimeZone, Utc};
+
+use crate::config::ChainConfig;
+use crate::db::Database;
+use crate::email::EmailService;
+
+/// Default share of the budget at which the warning fires
+pub const DEFAULT_WARN_PERCENT: u32 = 80;
+
+/// How close a chain's spend is to its daily budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum BudgetLevel {
+    Warning,
+    Critical,
+}
+
+impl BudgetLevel {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Warning => "warning",
+            Self::Critical => "exceeded",
+        }
+    }
+}
+
+/// Daily gas budgets per chain
+#[derive(Debug, Clone, Default)]
+pub struct GasBudget {
+    /// chain_id -> daily budget (wei); chains without one are not monitored
+    pub budgets_wei: HashMap<u64, u128>,
+    /// Percent of the budget at which the warning fires
+    pub warn_percent: u32,
+}
+
+impl GasBudget {
+    /// Load budgets from env (see module docs)
+    pub fn from_env(chains: &[ChainConfig]) -> Self {
+        let eth_env = |key: &str| {
+            std::env::var(key).ok().and_then(|v| v.parse::<f64>().ok()).filter(|eth| *eth > 0.0)
+        };
+        let default_eth = eth_env("GAS_DAILY_BUDGET_ETH");
+
+        let mut budgets_wei = HashMap::new();
+        for chain in chains {
+            if let Some(eth) = eth_env(&format!("{}_GAS_DAILY_BUDGET_ETH", chain.env_prefix())).or(default_eth) {
+                budgets_wei.insert(chain.chain_id, (eth * 1e18) as u128);
+            }
+        }
+
+        let warn_percent = std::env::var("GAS_BUDGET_WARN_PERCENT")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|p| (1..=100).contains(p))
+            .unwrap_or(DEFAULT_WARN_PERCENT);
+
+        Self { budgets_wei, warn_percent }
+    }
+
+    /// Level `spent_wei` has reached on `chain_id` (None below the warning or without a budget)
+    pub fn level(&self, chain_id: u64, spent_wei: u128) -> Option<BudgetLevel> {
+        let budget = *self.budgets_wei.get(&chain_id)?;
+        if spent_wei >= budget {
+            Some(BudgetLevel::Critical)
+        } else if spent_wei.saturating_mul(100) >= budget.saturating_mul(self.warn_percent as u128) {
+            Some(BudgetLevel::Warning)
+        } else {
+            None
+        }
+    }
+}
+
+/// Tracks which alerts went out today, so each level fires once per chain per day
+pub struct GasBudgetMonitor {
+    budget: GasBudget,
+    /// chain_id -> (day, highest level alerted that day)
+    alerted: HashMap<u64, (NaiveDate, BudgetLevel)>,
+}
+
+impl GasBudgetMonitor {
+    pub fn new(budget: GasBudget) -> Self {
+        Self { budget, alerted: HashMap::new() }
+    }
+
+    pub fn budget(&self) -> &GasBudget {
+        &self.budget
+    }
+
+    /// Level to alert for, if `spent_wei` reached one not yet alerted for `chain_id` on `day`
+    pub fn observe(&mut self, chain_id: u64, day: NaiveDate, spent_wei: u128) -> Option<BudgetLevel> {
+        let level = self.budget.level(chain_id, spent_wei)?;
+        match self.alerted.get(&chain_id) {
+            Some(&(alerted_day, alerted_level)) if alerted_day == day && alerted_level >= level => None,
+            _ => {
+                self.alerted.insert(chain_id, (day, level));
+                Some(level)
+            }
+        }
+    }
+
+    /// Sum today's recorded spend for every budgeted chain and alert on newly reached levels
+    pub async fn check(&mut self, db: &Database, email: Option<&EmailService>) {
+        let now = Utc::now();
+        let day = now.date_naive();
+        let chains: Vec<(u64, u128)> = self.budget.budgets_wei.iter().map(|(c, b)| (*c, *b)).collect();
+
+        for (chain_id, budget_wei) in chains {
+            let spent_wei = match db.get_gas_spent_since(chain_id as i32, day_start(day)).await {
+                Ok(spent) => spent,
+                Err(e) => {
+                    tracing::warn!("⚠️ Failed to sum gas spend for chain {}: {}", chain_id, e);
+                    continue;
+                }
+            };
+            let Some(level) = self.observe(chain_id, day, spent_wei) else {
+                continue;
+            };
+
+            let spent_eth = spent_wei as f64 / 1e18;
+            let budget_eth = budget_wei as f64 / 1e18;
+            match level {
+                BudgetLevel::Warning => tracing::warn!(
+                    chain_id, spent_wei, budget_wei, level = level.as_str(),
+                    "⛽ Gas spend on chain {} reached {:.6} of {:.6} ETH daily budget", chain_id, spent_eth, budget_eth
+                ),
+                BudgetLevel::Critical => tracing::error!(
+                    chain_id, spent_wei, budget_wei, level = level.as_str(),
+                    "⛽ Gas spend on chain {} exceeded daily budget: {:.6} of {:.6} ETH", chain_id, spent_eth, budget_eth
+                ),
+            }
+
+            if let Some(email) = email {
+                let day = day.format("%Y-%m-%d").to_string();
+                if let Err(e) = email.send_gas_budget_alert(chain_id, level.as_str(), &day, spent_eth, budget_eth).await {
+                    tracing::warn!("⚠️ Failed to email gas budget alert: {}", e);
+                }
+            }
+        }
+    }
+}
+
+/// Midnight UTC at the start of `day`
+fn day_start(day: NaiveDate) -> DateTime<Utc> {
+    Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).expect("midnight"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ETH: u128 = 1_000_000_000_000_000_000;
+
+    fn budget() -> GasBudget {
+        GasBudget { budgets_wei: HashMap::from([(8453, ETH)]), warn_percent: 80 }
+    }
+
+    #[test]
+    fn test_levels() {
+        let budget = budget();
+        assert_eq!(budget.level(8453, ETH * 79 / 100), None);
+        assert_eq!(budget.level(8453, ETH * 80 / 100), Some(BudgetLevel::Warning));
+        assert_eq!(budget.level(8453, ETH), Some(BudgetLevel::Critical));
+        assert_eq!(budget.level(1, ETH * 10), None); // No budget for mainnet
+    }
+
+    #[test]
+    fn test_one_alert_per_level_per_day() {
+        let mut monitor = GasBudgetMonitor::new(budget());
+        let day = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+
+        // One cancellation at a time, 0.1 ETH each
+        let alerts: Vec<_> = (1..=12u128)
+            .map(|trades| monitor.observe(8453, day, trades * ETH / 10))
+            .collect();
+        let raised: Vec<_> = alerts.iter().flatten().copied().collect();
+        assert_eq!(raised, vec![BudgetLevel::Warning, BudgetLevel::Critical]);
+        assert_eq!(alerts[7], Some(BudgetLevel::Warning));
+        assert_eq!(alerts[9], Some(BudgetLevel::Critical));
+
+        // The next day starts a new window
+        let next_day = day.succ_opt().unwrap();
+        assert_eq!(monitor.observe(8453, next_day, ETH / 10), None);
+        assert_eq!(monitor.observe(8453, next_day, ETH * 2), Some(BudgetLevel::Critical));
+        assert_eq!(monitor.observe(8453, next_day, ETH * 3), None);
+    }
+}
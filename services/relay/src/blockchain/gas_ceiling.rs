@@ -0,0 +1,92 @@
+//! Gas-price ceiling for auto-cancel
+//!
+//! Cancellations are paid by the relayer, so during a gas spike they can cost
+//! more than they're worth. MAX_CANCEL_GAS_PRICE_GWEI sets a ceiling for every
+//! chain, BASE_MAX_CANCEL_GAS_PRICE_GWEI / ETH_MAX_CANCEL_GAS_PRICE_GWEI
+//! override it per chain. While a chain's gas price is above its ceiling,
+//! cancellations are deferred to a later tick - expired trades stay escrowed,
+//! so waiting is harmless - but never for longer than MAX_CANCEL_DEFERRAL_SECS
+//! past expiry (default 6 hours). Chains without a ceiling always cancel.
+
+use std::collections::HashMap;
+
+use crate::config::ChainConfig;
+
+/// Default max time a cancellation may be deferred for high gas (6 hours past expiry)
+pub const DEFAULT_MAX_DEFERRAL_SECS: i64 = 6 * 3600;
+
+/// Cancellation gas ceilings per chain
+#[derive(Debug, Clone)]
+pub struct GasCeiling {
+    /// chain_id -> max gas price (wei) at which we still send cancellations
+    pub ceilings_wei: HashMap<u64, u128>,
+    /// Trades expired longer than this are cancelled regardless of gas price
+    pub max_deferral_secs: i64,
+}
+
+impl GasCeiling {
+    /// Load ceilings through `var` (env lookups, see module docs)
+    pub fn from_vars(chains: &[ChainConfig], var: &impl Fn(&str) -> Option<String>) -> Self {
+        let gwei_var = |key: &str| {
+            var(key).and_then(|v| v.parse::<f64>().ok()).filter(|g| *g > 0.0)
+        };
+        let default_gwei = gwei_var("MAX_CANCEL_GAS_PRICE_GWEI");
+
+        let mut ceilings_wei = HashMap::new();
+        for chain in chains {
+            if let Some(gwei) = gwei_var(&format!("{}_MAX_CANCEL_GAS_PRICE_GWEI", chain.env_prefix())).or(default_gwei) {
+                ceilings_wei.insert(chain.chain_id, (gwei * 1e9) as u128);
+            }
+        }
+
+        let max_deferral_secs = var("MAX_CANCEL_DEFERRAL_SECS")
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_MAX_DEFERRAL_SECS);
+
+        Self { ceilings_wei, max_deferral_secs }
+    }
+
+    /// Whether a cancellation should wait for cheaper gas: only when the price is above
+    /// the ceiling and the trade hasn't been expired longer than the max deferral
+    pub fn should_defer(&self, gas_price_wei: u128, ceiling_wei: u128, expired_for_secs: i64) -> bool {
+        gas_price_wei > ceiling_wei && expired_for_secs < self.max_deferral_secs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures::chain;
+
+    #[test]
+    fn test_ceilings_from_vars() {
+        let chains = [chain("Base", 8453), chain("Ethereum", 1)];
+
+        let none = GasCeiling::from_vars(&chains, &|_| None);
+        assert!(none.ceilings_wei.is_empty());
+        assert_eq!(none.max_deferral_secs, DEFAULT_MAX_DEFERRAL_SECS);
+
+        let vars = HashMap::from([
+            ("MAX_CANCEL_GAS_PRICE_GWEI", "0.5"),
+            ("ETH_MAX_CANCEL_GAS_PRICE_GWEI", "20"),
+            ("BASE_MAX_CANCEL_GAS_PRICE_GWEI", "-1"), // not positive -> shared ceiling
+            ("MAX_CANCEL_DEFERRAL_SECS", "3600"),
+        ]);
+        let ceiling = GasCeiling::from_vars(&chains, &|key| vars.get(key).map(|v| v.to_string()));
+        assert_eq!(ceiling.ceilings_wei[&8453], 500_000_000);
+        assert_eq!(ceiling.ceilings_wei[&1], 20_000_000_000);
+        assert_eq!(ceiling.max_deferral_secs, 3600);
+    }
+
+    #[test]
+    fn test_should_defer() {
+        let policy = GasCeiling { ceilings_wei: HashMap::new(), max_deferral_secs: 3600 };
+        let ceiling = 1_000_000_000; // 1 gwei
+        // Above ceiling, recently expired -> defer
+        assert!(policy.should_defer(2_000_000_000, ceiling, 60));
+        // At or below ceiling -> cancel
+        assert!(!policy.should_defer(ceiling, ceiling, 60));
+        // Above ceiling but waited past max deferral -> cancel anyway
+        assert!(!policy.should_defer(2_000_000_000, ceiling, 3600));
+    }
+}
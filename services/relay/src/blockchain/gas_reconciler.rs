@@ -0,0 +1,87 @@
+//! Gas cost reconciliation against on-chain receipts
+//!
+//! Gas costs recorded at send time may be estimates. This background job walks
+//! recent unreconciled `gas_costs` rows, fetches each transaction's receipt and
+//! replaces the estimate with the actual gasUsed / effective gas price.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use tokio::time::{interval, Duration};
+
+use super::chain_clients::ChainClients;
+use super::client::EthereumClient;
+use crate::db::{Database, DbResult};
+
+/// How often to run reconciliation (5 minutes)
+const RECONCILE_INTERVAL_SECS: u64 = 300;
+
+/// Only reconcile rows recorded within this window
+const RECONCILE_MAX_AGE_DAYS: i32 = 7;
+
+/// Max rows handled per run (keeps RPC usage bounded)
+const RECONCILE_BATCH_SIZE: i64 = 100;
+
+/// Run reconciliation forever on a fixed interval (chains that reconnect later
+/// are picked up on the next run)
+pub async fn run(db: Arc<Database>, clients: ChainClients) {
+    tracing::info!("🧾 Gas cost reconciler started (every {}s)", RECONCILE_INTERVAL_SECS);
+
+    let mut ticker = interval(Duration::from_secs(RECONCILE_INTERVAL_SECS));
+    loop {
+        ticker.tick().await;
+
+        match reconcile_gas_costs(&db, &clients.ready()).await {
+            Ok(0) => {}
+            Ok(count) => tracing::info!("🧾 Reconciled {} gas cost rows against receipts", count),
+            Err(e) => tracing::error!("❌ Gas cost reconciliation failed: {}", e),
+        }
+    }
+}
+
+/// Reconcile one batch of recent gas cost rows. Returns the number of rows updated.
+/// Rows whose transaction isn't mined yet (or whose chain has no client) are left for the next run.
+pub async fn reconcile_gas_costs(
+    db: &Database,
+    clients: &HashMap<u64, Arc<EthereumClient>>,
+) -> DbResult<usize> {
+    let rows = db
+        .get_unreconciled_gas_costs(RECONCILE_MAX_AGE_DAYS, RECONCILE_BATCH_SIZE)
+        .await?;
+
+    let mut reconciled = 0;
+    for mut row in rows {
+        let client = match clients.get(&(row.chain_id as u64)) {
+            Some(client) => client,
+            None => continue,
+        };
+
+        let (gas_used, gas_price) = match client.get_receipt_gas(&row.tx_hash).await {
+            Ok(Some(receipt)) => receipt,
+            Ok(None) => {
+                tracing::debug!("🧾 No receipt yet for {} (chain {})", row.tx_hash, row.chain_id);
+                continue;
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Failed to fetch receipt for {}: {}", row.tx_hash, e);
+                continue;
+            }
+        };
+
+        let estimated_cost = row.cost_wei.clone();
+        row.apply_receipt(gas_used, gas_price);
+        db.save_reconciled_gas_cost(&row).await?;
+
+        if estimated_cost != row.cost_wei {
+            tracing::info!(
+                "🧾 Gas cost for {} ({}) corrected: {} → {} wei",
+                row.tx_hash,
+                row.operation,
+                estimated_cost,
+                row.cost_wei
+            );
+        }
+        reconciled += 1;
+    }
+
+    Ok(reconciled)
+}
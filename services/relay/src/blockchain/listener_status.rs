@@ -0,0 +1,166 @@
+//! Event listener progress per chain (GET /api/admin/listener-status)
+//!
+//! Each EventListener reports into a shared `ListenerProgress` after every sync
+//! cycle: the last block it processed, the chain head it saw, and when it last
+//! wrote events to the database, plus the confirmation depth it runs with.
+//! Reading it never touches the RPC or the DB.
+//!
+//! A chain is stalled when its listener hasn't completed a sync cycle for longer
+//! than LISTENER_STALL_SECS - including a configured chain whose listener never
+//! reported at all (failed to start or crashed). A listener that is caught up
+//! with an idle chain still completes cycles, so it isn't stalled.
+
+use std::collections::HashMap;
+use std::sync::{Arc, RwLock};
+
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+
+/// What one chain's listener last reported
+#[derive(Debug, Clone, Default, PartialEq)]
+struct ChainProgress {
+    last_processed_block: Option<u64>,
+    head_block: Option<u64>,
+    /// Last completed sync cycle (advanced or already caught up)
+    last_sync_at: Option<DateTime<Utc>>,
+    /// Last cycle that wrote at least one event
+    last_event_write_at: Option<DateTime<Utc>>,
+    last_error: Option<String>,
+    confirmations: Option<u64>,
+}
+
+/// Listener health for one chain
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ListenerStatus {
+    pub chain_id: u64,
+    pub last_processed_block: Option<u64>,
+    pub head_block: Option<u64>,
+    /// head_block - last_processed_block (None until both are known)
+    pub lag_blocks: Option<u64>,
+    pub last_sync_at: Option<DateTime<Utc>>,
+    pub last_event_write_at: Option<DateTime<Utc>>,
+    /// Error of the most recent failed cycle, cleared by the next successful one
+    pub last_error: Option<String>,
+    /// Blocks behind head the listener waits before indexing (None until it starts)
+    pub confirmations: Option<u64>,
+    pub stalled: bool,
+}
+
+/// Shared progress of every chain's listener; cheap to clone
+#[derive(Clone, Default)]
+pub struct ListenerProgress {
+    chains: Arc<RwLock<HashMap<u64, ChainProgress>>>,
+}
+
+impl ListenerProgress {
+    /// A completed sync cycle: `processed_block` is the last block now indexed
+    pub fn record_sync(&self, chain_id: u64, processed_block: u64, head_block: u64, events_written: usize) {
+        self.record_sync_at(chain_id, processed_block, head_block, events_written, Utc::now());
+    }
+
+    /// A failed sync cycle (progress is kept; only the error is recorded)
+    pub fn record_error(&self, chain_id: u64, error: &str) {
+        let mut chains = self.chains.write().unwrap_or_else(|e| e.into_inner());
+        chains.entry(chain_id).or_default().last_error = Some(error.to_string());
+    }
+
+    /// The confirmation depth a chain's listener started with
+    pub fn record_confirmations(&self, chain_id: u64, confirmations: u64) {
+        let mut chains = self.chains.write().unwrap_or_else(|e| e.into_inner());
+        chains.entry(chain_id).or_default().confirmations = Some(confirmations);
+    }
+
+    /// Status of each of `chain_ids` (configured chains without a report are stalled)
+    pub fn snapshot(&self, chain_ids: &[u64], stall_after_secs: u64) -> Vec<ListenerStatus> {
+        self.snapshot_at(chain_ids, stall_after_secs, Utc::now())
+    }
+
+    fn record_sync_at(&self, chain_id: u64, processed_block: u64, head_block: u64, events_written: usize, now: DateTime<Utc>) {
+        let mut chains = self.chains.write().unwrap_or_else(|e| e.into_inner());
+        let progress = chains.entry(chain_id).or_default();
+        progress.last_processed_block = Some(processed_block);
+        progress.head_block = Some(head_block);
+        progress.last_sync_at = Some(now);
+        if events_written > 0 {
+            progress.last_event_write_at = Some(now);
+        }
+        progress.last_error = None;
+    }
+
+    fn snapshot_at(&self, chain_ids: &[u64], stall_after_secs: u64, now: DateTime<Utc>) -> Vec<ListenerStatus> {
+        let chains = self.chains.read().unwrap_or_else(|e| e.into_inner());
+        chain_ids
+            .iter()
+            .map(|&chain_id| {
+                let progress = chains.get(&chain_id).cloned().unwrap_or_default();
+                let stalled = match progress.last_sync_at {
+                    Some(at) => (now - at).num_seconds() > stall_after_secs as i64,
+                    None => true,
+                };
+                ListenerStatus {
+                    chain_id,
+                    last_processed_block: progress.last_processed_block,
+                    head_block: progress.head_block,
+                    lag_blocks: progress.head_block
+                        .zip(progress.last_processed_block)
+                        .map(|(head, processed)| head.saturating_sub(processed)),
+                    last_sync_at: progress.last_sync_at,
+                    last_event_write_at: progress.last_event_write_at,
+                    last_error: progress.last_error,
+                    confirmations: progress.confirmations,
+                    stalled,
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::Duration;
+
+    #[test]
+    fn test_listener_that_stopped_advancing_is_stalled() {
+        let progress = ListenerProgress::default();
+        let start = Utc::now();
+
+        // Base keeps syncing; Ethereum's listener last reported 10 minutes ago
+        progress.record_sync_at(1, 21_000_000, 21_000_010, 3, start - Duration::minutes(10));
+        progress.record_sync_at(8453, 24_000_000, 24_000_002, 0, start - Duration::seconds(5));
+        progress.record_error(1, "Provider error: connection refused");
+
+        let statuses = progress.snapshot_at(&[8453, 1, 84532], 180, start);
+
+        let base = &statuses[0];
+        assert!(!base.stalled);
+        assert_eq!(base.lag_blocks, Some(2));
+        assert_eq!(base.last_event_write_at, None);
+
+        let eth = &statuses[1];
+        assert!(eth.stalled);
+        assert_eq!(eth.last_processed_block, Some(21_000_000));
+        assert_eq!(eth.lag_blocks, Some(10));
+        assert_eq!(eth.last_event_write_at, Some(start - Duration::minutes(10)));
+        assert_eq!(eth.last_error.as_deref(), Some("Provider error: connection refused"));
+
+        // Configured but never reported
+        let missing = &statuses[2];
+        assert!(missing.stalled);
+        assert_eq!(missing.lag_blocks, None);
+    }
+
+    #[test]
+    fn test_successful_sync_clears_error() {
+        let progress = ListenerProgress::default();
+        progress.record_confirmations(8453, 2);
+        progress.record_error(8453, "timeout");
+        progress.record_sync(8453, 100, 102, 1);
+
+        let status = &progress.snapshot(&[8453], 180)[0];
+        assert_eq!(status.confirmations, Some(2));
+        assert_eq!(status.last_error, None);
+        assert!(status.last_event_write_at.is_some());
+        assert!(!status.stalled);
+    }
+}
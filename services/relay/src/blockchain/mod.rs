@@ -1,12 +1,29 @@
 // Blockchain integration module
 // LyncZ: Multi-rail escrow with ZK verification
 
+pub mod chain_clients;
 pub mod client;
+pub mod ens;
 pub mod events;
+pub mod failover;
+pub mod gas_budget;
+pub mod gas_ceiling;
+pub mod gas_reconciler;
+pub mod listener_status;
+pub mod nonce;
+pub mod relayer_balance;
 pub mod types;
 
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
 use ethers::prelude::abigen;
 
+use crate::config::{ChainConfig, Config};
+use chain_clients::ChainClients;
+use client::EthereumClient;
+
 // Generate contract bindings from ABI files
 abigen!(
     LyncZEscrow,
@@ -29,3 +46,134 @@ abigen!(
     "./abi/IERC20.json"
 );
 
+abigen!(
+    IERC1271,
+    "./abi/IERC1271.json"
+);
+
+
+/// Build the relayer client for one chain (doesn't contact the RPC)
+async fn build_client(chain_config: &ChainConfig, private_key: &str) -> Result<EthereumClient, String> {
+    let escrow_address: ethers::types::Address = chain_config.escrow_address.parse()
+        .map_err(|e| format!("Invalid escrow address: {}", e))?;
+    EthereumClient::new(&chain_config.rpc_url, private_key, escrow_address, chain_config.chain_id)
+        .await
+        .map_err(|e| e.to_string())
+}
+
+/// How long the RPC check waits for a block number before calling the chain unreachable
+const RPC_PROBE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// Check that a client's RPC answers within `timeout`
+async fn probe_rpc(client: &EthereumClient, timeout: Duration) -> Result<(), String> {
+    match tokio::time::timeout(timeout, client.get_block_number()).await {
+        Ok(Ok(_)) => Ok(()),
+        Ok(Err(e)) => Err(format!("RPC unreachable: {}", e)),
+        Err(_) => Err(format!("RPC unreachable: no block number within {}s", timeout.as_secs())),
+    }
+}
+
+/// Build the relayer client for one chain and check that its RPC answers
+pub async fn connect_chain(chain_config: &ChainConfig, private_key: &str) -> Result<EthereumClient, String> {
+    let client = build_client(chain_config, private_key).await?;
+    probe_rpc(&client, RPC_PROBE_TIMEOUT).await?;
+    Ok(client)
+}
+
+/// Connect a relayer client to every configured chain (auto-cancel and resync)
+///
+/// Chains with an invalid escrow address or RPC URL are logged and left out, so
+/// the map may be empty. Empty without RELAYER_PRIVATE_KEY.
+pub async fn init_clients(config: &Config) -> HashMap<u64, Arc<EthereumClient>> {
+    let mut clients = HashMap::new();
+    let Some(private_key) = config.relayer_private_key.as_deref() else {
+        return clients;
+    };
+
+    for chain_config in &config.chains {
+        match build_client(chain_config, private_key).await {
+            Ok(client) => {
+                tracing::info!("✅ Blockchain client initialized for {} (chain {}), relayer: {:?}",
+                    chain_config.name, chain_config.chain_id, client.relayer_address());
+                clients.insert(chain_config.chain_id, Arc::new(client));
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Blockchain client failed for {} (chain {}): {}",
+                    chain_config.name, chain_config.chain_id, e);
+            }
+        }
+    }
+
+    clients
+}
+
+/// Connect every configured chain for the api-server, checking each RPC answers.
+/// Chains whose RPC fails are kept as unavailable (see chain_clients) for the
+/// reconnect task to retry. A chain that can't be built at all (bad escrow
+/// address, RPC URL or key) is a config error, returned so startup fails.
+/// Empty without RELAYER_PRIVATE_KEY.
+pub async fn init_chain_clients(config: &Config) -> Result<ChainClients, String> {
+    let Some(private_key) = config.relayer_private_key.as_deref() else {
+        return Ok(ChainClients::default());
+    };
+    connect_chains(&config.chains, private_key, RPC_PROBE_TIMEOUT).await
+}
+
+async fn connect_chains(chains: &[ChainConfig], private_key: &str, probe_timeout: Duration) -> Result<ChainClients, String> {
+    let mut ready = HashMap::new();
+    let mut failed = HashMap::new();
+    for chain_config in chains {
+        let client = build_client(chain_config, private_key).await
+            .map_err(|e| format!("{} (chain {}): {}", chain_config.name, chain_config.chain_id, e))?;
+        match probe_rpc(&client, probe_timeout).await.map(|()| client) {
+            Ok(client) => {
+                tracing::info!("✅ Blockchain client initialized for {} (chain {}), relayer: {:?}",
+                    chain_config.name, chain_config.chain_id, client.relayer_address());
+                ready.insert(chain_config.chain_id, Arc::new(client));
+            }
+            Err(e) => {
+                tracing::warn!("⚠️ Blockchain client failed for {} (chain {}), will retry: {}",
+                    chain_config.name, chain_config.chain_id, e);
+                failed.insert(chain_config.chain_id, e);
+            }
+        }
+    }
+    Ok(ChainClients::new(ready, failed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures::chain;
+
+    const KEY: &str = "0x0123456789012345678901234567890123456789012345678901234567890123";
+
+    #[tokio::test]
+    async fn test_bad_escrow_address_fails_startup() {
+        let bad = ChainConfig { escrow_address: "0xnot-an-address".to_string(), ..chain("Base", 8453) };
+        let Err(err) = connect_chains(&[bad], KEY, Duration::from_millis(50)).await else {
+            panic!("a bad escrow address connected");
+        };
+        assert!(err.contains("Invalid escrow address"), "{}", err);
+    }
+
+    #[tokio::test]
+    async fn test_hung_rpc_is_unavailable_not_blocking() {
+        // Accepts connections and never answers
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let rpc_url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            let mut held = Vec::new();
+            while let Ok((socket, _)) = listener.accept().await {
+                held.push(socket);
+            }
+        });
+
+        let hung = ChainConfig { rpc_url, ..chain("Base", 8453) };
+        let clients = tokio::time::timeout(Duration::from_secs(5), connect_chains(&[hung], KEY, Duration::from_millis(100)))
+            .await
+            .expect("probe times out")
+            .unwrap();
+        assert_eq!(clients.unavailable(), vec![8453]);
+    }
+}
@@ -0,0 +1,212 @@
+//! Relayer nonce assignment
+//!
+//! Settlement, fillOrder and the auto-cancel loop all send from the same
+//! relayer wallet. Letting each send ask the node for the pending nonce races:
+//! two concurrent sends can get the same nonce and one of them is dropped or
+//! replaces the other. `NonceManager` hands nonces out one broadcast at a time
+//! and only advances once the node has accepted the transaction.
+//!
+//! Another process sending from the same wallet (the auto-cancel binary next
+//! to the API server) still moves the account nonce underneath us; a "nonce
+//! too low" / "replacement underpriced" rejection re-syncs from the node and
+//! the send is tried once more with the fresh nonce.
+
+use ethers::types::{H256, U256};
+use std::collections::BTreeMap;
+use std::fmt::Display;
+use std::future::Future;
+use tokio::sync::Mutex;
+
+/// Node rejections meaning our nonce is already used or taken by another transaction
+/// ("already known" is not one: that's this very transaction, already in the mempool)
+pub fn is_nonce_error_message(message: &str) -> bool {
+    let message = message.to_lowercase();
+    message.contains("nonce too low")
+        || message.contains("replacement transaction underpriced")
+        || message.contains("nonce has already been used")
+}
+
+/// Node rejections of a broadcast meaning the signed transaction itself already
+/// reached the node: "already known" always, "nonce too low" only when resending
+/// a transaction an earlier attempt may have delivered (otherwise it's a stale nonce)
+pub fn is_in_flight_message(message: &str, resend: bool) -> bool {
+    let message = message.to_lowercase();
+    message.contains("already known") || (resend && message.contains("nonce too low"))
+}
+
+#[derive(Debug, Default)]
+struct NonceState {
+    /// Next nonce to hand out; None until synced from the node (or after a nonce error)
+    next: Option<U256>,
+    /// Broadcast but not yet confirmed, by nonce
+    pending: BTreeMap<U256, H256>,
+}
+
+/// Serializes nonce assignment for one relayer wallet on one chain
+#[derive(Debug, Default)]
+pub struct NonceManager {
+    state: Mutex<NonceState>,
+}
+
+impl NonceManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Broadcast a transaction with the next nonce
+    ///
+    /// `sync` fetches the account's pending nonce from the node; `send` must
+    /// broadcast with the given nonce and return the hash once the node has
+    /// accepted it (not wait for the receipt). Sends are serialized, and a
+    /// failed send doesn't use up its nonce.
+    pub async fn send<E, S, SFut, F, Fut, P>(&self, sync: S, send: F, is_nonce_error: P) -> Result<H256, E>
+    where
+        S: Fn() -> SFut,
+        SFut: Future<Output = Result<U256, E>>,
+        F: Fn(U256) -> Fut,
+        Fut: Future<Output = Result<H256, E>>,
+        P: Fn(&E) -> bool,
+        E: Display,
+    {
+        let mut state = self.state.lock().await;
+        let mut resynced = false;
+        loop {
+            let nonce = match state.next {
+                Some(nonce) => nonce,
+                None => {
+                    let nonce = sync().await?;
+                    state.pending.retain(|&pending, _| pending >= nonce);
+                    nonce
+                }
+            };
+            match send(nonce).await {
+                Ok(tx_hash) => {
+                    state.next = Some(nonce + 1);
+                    state.pending.insert(nonce, tx_hash);
+                    return Ok(tx_hash);
+                }
+                Err(e) if !resynced && is_nonce_error(&e) => {
+                    tracing::warn!(
+                        "🔢 Nonce {} rejected ({} pending), re-syncing from node: {}",
+                        nonce,
+                        state.pending.len(),
+                        e
+                    );
+                    state.next = None;
+                    resynced = true;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// Forget a pending transaction once it's mined (reverted or not)
+    pub async fn confirmed(&self, tx_hash: H256) {
+        self.state.lock().await.pending.retain(|_, pending| *pending != tx_hash);
+    }
+
+    /// Transactions broadcast by this manager and not yet confirmed
+    pub async fn pending_count(&self) -> usize {
+        self.state.lock().await.pending.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::Mutex as StdMutex;
+
+    /// Node that only accepts the account's next nonce, like a real mempool
+    /// without gaps or replacements
+    #[derive(Default)]
+    struct MockNode {
+        account_nonce: StdMutex<u64>,
+        accepted: StdMutex<Vec<u64>>,
+    }
+
+    impl MockNode {
+        async fn pending_nonce(&self) -> Result<U256, String> {
+            tokio::task::yield_now().await;
+            Ok(U256::from(*self.account_nonce.lock().unwrap()))
+        }
+
+        async fn broadcast(&self, nonce: U256) -> Result<H256, String> {
+            tokio::task::yield_now().await;
+            let mut account_nonce = self.account_nonce.lock().unwrap();
+            if nonce.as_u64() != *account_nonce {
+                return Err(format!("nonce too low: next nonce {}, tx nonce {}", account_nonce, nonce));
+            }
+            *account_nonce += 1;
+            self.accepted.lock().unwrap().push(nonce.as_u64());
+            Ok(H256::from_low_u64_be(nonce.as_u64() + 1))
+        }
+    }
+
+    async fn send(manager: &NonceManager, node: &MockNode) -> Result<H256, String> {
+        manager.send(|| node.pending_nonce(), |nonce| node.broadcast(nonce), |e| is_nonce_error_message(e)).await
+    }
+
+    #[tokio::test(flavor = "multi_thread", worker_threads = 4)]
+    async fn test_concurrent_sends_get_unique_sequential_nonces() {
+        let manager = Arc::new(NonceManager::new());
+        let node = Arc::new(MockNode { account_nonce: StdMutex::new(7), ..MockNode::default() });
+
+        let handles: Vec<_> = (0..64).map(|_| {
+            let (manager, node) = (manager.clone(), node.clone());
+            tokio::spawn(async move { send(&manager, &node).await })
+        }).collect();
+        for handle in handles {
+            handle.await.unwrap().unwrap();
+        }
+
+        let mut accepted = node.accepted.lock().unwrap().clone();
+        assert_eq!(accepted.len(), 64);
+        accepted.sort_unstable();
+        assert_eq!(accepted, (7..71).collect::<Vec<u64>>());
+        assert_eq!(manager.pending_count().await, 64);
+
+        manager.confirmed(H256::from_low_u64_be(8)).await;
+        assert_eq!(manager.pending_count().await, 63);
+    }
+
+    #[tokio::test]
+    async fn test_resyncs_after_nonce_used_elsewhere() {
+        let manager = NonceManager::new();
+        let node = MockNode::default();
+        send(&manager, &node).await.unwrap();
+
+        // Another process sends from the same wallet
+        *node.account_nonce.lock().unwrap() += 2;
+
+        send(&manager, &node).await.unwrap();
+        assert_eq!(*node.accepted.lock().unwrap(), vec![0, 3]);
+        // Nonce 0 is below the synced account nonce, so it's no longer tracked as pending
+        assert_eq!(manager.pending_count().await, 1);
+
+        // Errors other than nonce rejections come straight back
+        let result = manager.send(|| node.pending_nonce(), |_| async { Err("execution reverted".to_string()) }, |e| is_nonce_error_message(e)).await;
+        assert_eq!(result, Err("execution reverted".to_string()));
+        assert_eq!(*node.account_nonce.lock().unwrap(), 4);
+    }
+
+    #[test]
+    fn test_nonce_error_messages() {
+        assert!(is_nonce_error_message("nonce too low: next nonce 5, tx nonce 4"));
+        assert!(is_nonce_error_message("replacement transaction underpriced"));
+        assert!(is_nonce_error_message("Nonce has already been used"));
+        assert!(!is_nonce_error_message("already known"));
+        assert!(!is_nonce_error_message("execution reverted: TradeNotExpired"));
+    }
+
+    #[test]
+    fn test_in_flight_messages() {
+        assert!(is_in_flight_message("already known", false));
+        assert!(is_in_flight_message("Already Known", true));
+        // A first send with a used nonce is stale; a resend's was likely our own
+        assert!(!is_in_flight_message("nonce too low: next nonce 5, tx nonce 4", false));
+        assert!(is_in_flight_message("nonce too low: next nonce 5, tx nonce 4", true));
+        assert!(!is_in_flight_message("replacement transaction underpriced", true));
+        assert!(!is_in_flight_message("insufficient funds for gas * price + value", true));
+    }
+}
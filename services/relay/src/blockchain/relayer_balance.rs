@@ -0,0 +1,157 @@
+//! Relayer wallet balance per chain (GET /api/admin/relayer)
+//!
+//! Settlements and auto-cancellations pay gas from the relayer wallet, so an
+//! empty wallet otherwise only shows up as failing cancellations. Each chain's
+//! native balance is compared with a low-balance threshold:
+//! RELAYER_LOW_BALANCE_ETH applies to every chain (default 0.01),
+//! BASE_RELAYER_LOW_BALANCE_ETH / ETH_RELAYER_LOW_BALANCE_ETH override it per
+//! chain. Every check that finds a chain below its threshold logs a warning;
+//! the admin endpoint checks on request, auto-cancel after each round of
+//! cancellations.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ethers::types::{Address, U256};
+use ethers::utils::format_ether;
+use serde::Serialize;
+
+use super::client::EthereumClient;
+use crate::config::ChainConfig;
+
+/// Default low-balance threshold (ETH)
+pub const DEFAULT_LOW_BALANCE_ETH: f64 = 0.01;
+
+/// Low-balance thresholds per chain
+#[derive(Debug, Clone, Default)]
+pub struct LowBalanceThresholds {
+    /// chain_id -> threshold (wei)
+    pub thresholds_wei: HashMap<u64, u128>,
+}
+
+impl LowBalanceThresholds {
+    /// Load thresholds from env (see module docs)
+    pub fn from_env(chains: &[ChainConfig]) -> Self {
+        Self::from_vars(chains, &|key| std::env::var(key).ok())
+    }
+
+    fn from_vars(chains: &[ChainConfig], var: &impl Fn(&str) -> Option<String>) -> Self {
+        let eth_var = |key: &str| {
+            var(key).and_then(|v| v.parse::<f64>().ok()).filter(|eth| *eth >= 0.0)
+        };
+        let default_eth = eth_var("RELAYER_LOW_BALANCE_ETH").unwrap_or(DEFAULT_LOW_BALANCE_ETH);
+
+        let thresholds_wei = chains
+            .iter()
+            .map(|chain| {
+                let eth = eth_var(&format!("{}_RELAYER_LOW_BALANCE_ETH", chain.env_prefix())).unwrap_or(default_eth);
+                (chain.chain_id, (eth * 1e18) as u128)
+            })
+            .collect();
+        Self { thresholds_wei }
+    }
+
+    /// Threshold for `chain_id` (the default for chains that weren't configured)
+    pub fn threshold_wei(&self, chain_id: u64) -> u128 {
+        self.thresholds_wei
+            .get(&chain_id)
+            .copied()
+            .unwrap_or((DEFAULT_LOW_BALANCE_ETH * 1e18) as u128)
+    }
+}
+
+/// One chain's relayer wallet and balance
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct RelayerBalance {
+    pub chain_id: u64,
+    pub relayer_address: String,
+    /// Native balance in wei (None when the RPC call failed)
+    pub balance_wei: Option<String>,
+    /// Same, in ETH
+    pub balance_eth: Option<String>,
+    pub low_balance_threshold_wei: String,
+    /// Balance is below the threshold (false when unknown)
+    pub low_balance: bool,
+    pub error: Option<String>,
+}
+
+impl RelayerBalance {
+    pub fn new(chain_id: u64, relayer_address: Address, balance: Result<U256, String>, threshold_wei: u128) -> Self {
+        let (balance, error) = match balance {
+            Ok(balance) => (Some(balance), None),
+            Err(e) => (None, Some(e)),
+        };
+        Self {
+            chain_id,
+            relayer_address: format!("{:?}", relayer_address),
+            balance_wei: balance.map(|b| b.to_string()),
+            balance_eth: balance.map(format_ether),
+            low_balance_threshold_wei: threshold_wei.to_string(),
+            low_balance: balance.is_some_and(|b| b < U256::from(threshold_wei)),
+            error,
+        }
+    }
+}
+
+/// Read every client's relayer balance (sorted by chain) and warn about low ones
+pub async fn check_balances(
+    clients: &HashMap<u64, Arc<EthereumClient>>,
+    thresholds: &LowBalanceThresholds,
+) -> Vec<RelayerBalance> {
+    let mut balances = Vec::with_capacity(clients.len());
+    for (&chain_id, client) in clients {
+        let balance = client.relayer_balance().await.map_err(|e| e.to_string());
+        let status = RelayerBalance::new(chain_id, client.relayer_address(), balance, thresholds.threshold_wei(chain_id));
+        if status.low_balance {
+            tracing::warn!(
+                chain_id, relayer = %status.relayer_address,
+                "⛽ Relayer balance on chain {} is low: {} ETH (threshold {} wei)",
+                chain_id, status.balance_eth.as_deref().unwrap_or("?"), status.low_balance_threshold_wei
+            );
+        }
+        if let Some(e) = &status.error {
+            tracing::warn!("⚠️ Failed to read relayer balance on chain {}: {}", chain_id, e);
+        }
+        balances.push(status);
+    }
+    balances.sort_by_key(|b| b.chain_id);
+    balances
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures::chain;
+
+    const ETH: u128 = 1_000_000_000_000_000_000;
+
+    #[test]
+    fn test_low_balance_flag() {
+        let vars = HashMap::from([
+            ("RELAYER_LOW_BALANCE_ETH", "0.05"),
+            ("ETH_RELAYER_LOW_BALANCE_ETH", "0.2"),
+        ]);
+        let thresholds = LowBalanceThresholds::from_vars(
+            &[chain("Base", 8453), chain("Ethereum", 1)],
+            &|key| vars.get(key).map(|v| v.to_string()),
+        );
+        assert_eq!(thresholds.threshold_wei(8453), ETH / 20);
+        assert_eq!(thresholds.threshold_wei(1), ETH / 5);
+
+        let relayer = Address::repeat_byte(0x11);
+        let base = RelayerBalance::new(8453, relayer, Ok(U256::from(ETH / 10)), thresholds.threshold_wei(8453));
+        assert!(!base.low_balance);
+        assert_eq!(base.balance_eth.as_deref(), Some("0.100000000000000000"));
+
+        // The same 0.1 ETH is below mainnet's threshold
+        let mainnet = RelayerBalance::new(1, relayer, Ok(U256::from(ETH / 10)), thresholds.threshold_wei(1));
+        assert!(mainnet.low_balance);
+        assert_eq!(mainnet.relayer_address, "0x1111111111111111111111111111111111111111");
+
+        // An unreadable balance isn't reported as low
+        let failed = RelayerBalance::new(1, relayer, Err("timeout".to_string()), thresholds.threshold_wei(1));
+        assert!(!failed.low_balance);
+        assert_eq!(failed.balance_wei, None);
+        assert_eq!(failed.error.as_deref(), Some("timeout"));
+    }
+}
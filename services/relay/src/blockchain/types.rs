@@ -1,10 +1,17 @@
 //! Blockchain-specific types and helpers
 
 use anyhow::Result;
+use ethers::types::{Address, H256};
 use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::OrderWithdrawnFilter;
+
+/// Basis points in 100%
+const MAX_FEE_RATE_BPS: u32 = 10_000;
 
 /// Contract configuration from on-chain
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ContractConfig {
     pub min_trade_value_cny: String,
     pub max_trade_value_cny: String,
@@ -29,6 +36,98 @@ pub struct ContractConfig {
     pub fee_calculator_address: String, // Fee calculator contract address
 }
 
+/// A `ContractConfig` field that doesn't hold a usable value
+#[derive(Error, Debug, PartialEq)]
+pub enum ConfigParseError {
+    #[error("{field} is not a valid number: {value:?}")]
+    InvalidNumber { field: &'static str, value: String },
+    #[error("{field} is not a valid address: {value:?}")]
+    InvalidAddress { field: &'static str, value: String },
+    #[error("{0} is the zero address")]
+    ZeroAddress(&'static str),
+    #[error("fee_rate_bps {0} is above 100% ({MAX_FEE_RATE_BPS} bps)")]
+    FeeRateTooHigh(u32),
+    #[error("min_trade_value_cny {min} is above max_trade_value_cny {max}")]
+    TradeRangeInverted { min: u128, max: u128 },
+}
+
+impl ContractConfig {
+    /// Fee rate in basis points, at most 10000 (100%)
+    pub fn fee_rate_bps(&self) -> Result<u32, ConfigParseError> {
+        let bps = parse_number("fee_rate_bps", &self.fee_rate_bps)?;
+        if bps > MAX_FEE_RATE_BPS {
+            return Err(ConfigParseError::FeeRateTooHigh(bps));
+        }
+        Ok(bps)
+    }
+
+    /// Smallest trade the escrow accepts, in CNY cents
+    pub fn min_trade_value_cny(&self) -> Result<u128, ConfigParseError> {
+        parse_number("min_trade_value_cny", &self.min_trade_value_cny)
+    }
+
+    /// Largest trade the escrow accepts, in CNY cents
+    pub fn max_trade_value_cny(&self) -> Result<u128, ConfigParseError> {
+        parse_number("max_trade_value_cny", &self.max_trade_value_cny)
+    }
+
+    /// Seconds a buyer has to pay before the trade can be cancelled
+    pub fn payment_window_secs(&self) -> Result<u64, ConfigParseError> {
+        parse_number("payment_window", &self.payment_window)
+    }
+
+    /// ZK verifier contract; the escrow can't settle without one
+    pub fn zk_verifier(&self) -> Result<Address, ConfigParseError> {
+        let address: Address = self.zk_verifier.parse().map_err(|_| ConfigParseError::InvalidAddress {
+            field: "zk_verifier",
+            value: self.zk_verifier.clone(),
+        })?;
+        if address.is_zero() {
+            return Err(ConfigParseError::ZeroAddress("zk_verifier"));
+        }
+        Ok(address)
+    }
+
+    /// Check every typed field; run on each fetch so a malformed config is
+    /// rejected where it's read instead of surfacing as a wrong fee later
+    pub fn validate(&self) -> Result<(), ConfigParseError> {
+        self.fee_rate_bps()?;
+        self.payment_window_secs()?;
+        self.zk_verifier()?;
+        let (min, max) = (self.min_trade_value_cny()?, self.max_trade_value_cny()?);
+        if min > max {
+            return Err(ConfigParseError::TradeRangeInverted { min, max });
+        }
+        Ok(())
+    }
+}
+
+/// One OrderWithdrawn event and the transaction that emitted it
+#[derive(Debug, Clone, PartialEq)]
+pub struct WithdrawalLog {
+    pub order_id: String,
+    pub amount: String,
+    pub remaining_after: String,
+    pub tx_hash: String,
+    pub block_number: u64,
+}
+
+impl WithdrawalLog {
+    pub fn new(event: &OrderWithdrawnFilter, tx_hash: H256, block_number: u64) -> Self {
+        Self {
+            order_id: format!("0x{}", hex::encode(event.order_id)),
+            amount: event.withdrawn_amount.to_string(),
+            remaining_after: event.remaining_amount.to_string(),
+            tx_hash: format!("{:#x}", tx_hash),
+            block_number,
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(field: &'static str, value: &str) -> Result<T, ConfigParseError> {
+    value.parse().map_err(|_| ConfigParseError::InvalidNumber { field, value: value.to_string() })
+}
+
 /// Convert bytes32 string (0x-prefixed hex) to [u8; 32]
 /// Works for trade_id, order_id, or any bytes32 value
 fn bytes32_from_hex(hex_value: &str) -> Result<[u8; 32]> {
@@ -69,6 +168,42 @@ mod tests {
         assert_eq!(bytes, [1u8; 32]);
     }
     
+    fn valid_config() -> ContractConfig {
+        ContractConfig {
+            min_trade_value_cny: "1000".to_string(),
+            max_trade_value_cny: "7200000".to_string(),
+            payment_window: "900".to_string(),
+            fee_rate_bps: "100".to_string(),
+            zk_verifier: "0x1111111111111111111111111111111111111111".to_string(),
+            ..ContractConfig::default()
+        }
+    }
+
+    #[test]
+    fn test_config_typed_accessors() {
+        let config = valid_config();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!(config.fee_rate_bps(), Ok(100));
+        assert_eq!(config.payment_window_secs(), Ok(900));
+
+        let config = ContractConfig { fee_rate_bps: "1%".to_string(), ..valid_config() };
+        assert_eq!(config.fee_rate_bps(), Err(ConfigParseError::InvalidNumber { field: "fee_rate_bps", value: "1%".to_string() }));
+    }
+
+    #[test]
+    fn test_config_validation_rejects_invalid_values() {
+        let config = ContractConfig { fee_rate_bps: "10001".to_string(), ..valid_config() };
+        assert_eq!(config.validate(), Err(ConfigParseError::FeeRateTooHigh(10001)));
+
+        let config = ContractConfig { zk_verifier: format!("{:#x}", Address::zero()), ..valid_config() };
+        assert_eq!(config.validate(), Err(ConfigParseError::ZeroAddress("zk_verifier")));
+
+        let config = ContractConfig { min_trade_value_cny: "8000000".to_string(), ..valid_config() };
+        assert_eq!(config.validate(), Err(ConfigParseError::TradeRangeInverted { min: 8_000_000, max: 7_200_000 }));
+
+        assert!(ContractConfig::default().validate().is_err());
+    }
+
     #[test]
     fn test_trade_id_without_prefix() {
         let trade_id = "0202020202020202020202020202020202020202020202020202020202020202";
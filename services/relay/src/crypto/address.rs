@@ -0,0 +1,56 @@
+//! Ethereum address validation
+//!
+//! Addresses arrive from query strings and paths in whatever case the client
+//! used (checksummed, lowercase, with or without 0x). Every handler that takes
+//! one normalizes it here first, so a malformed address is a 400 rather than
+//! a query that quietly matches nothing. The canonical form is lowercase with
+//! a 0x prefix - what the indexer stores and the DB compares against.
+
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq, Eq)]
+pub enum AddressError {
+    #[error("expected 40 hex characters, got {0}")]
+    InvalidLength(usize),
+    #[error("contains non-hex characters")]
+    InvalidHex,
+}
+
+/// Lowercase 0x-prefixed form of a 20-byte address (0x prefix optional on input)
+pub fn normalize_address(raw: &str) -> Result<String, AddressError> {
+    let hex = raw.trim();
+    let hex = hex.strip_prefix("0x").or_else(|| hex.strip_prefix("0X")).unwrap_or(hex);
+    if hex.len() != 40 {
+        return Err(AddressError::InvalidLength(hex.len()));
+    }
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Err(AddressError::InvalidHex);
+    }
+    Ok(format!("0x{}", hex.to_lowercase()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_normalize_accepts_any_case_and_prefix() {
+        let canonical = "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913";
+        assert_eq!(normalize_address("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").unwrap(), canonical);
+        assert_eq!(normalize_address(" 0X833589FCD6EDB6E08F4C7C32D4F71B54BDA02913 ").unwrap(), canonical);
+        assert_eq!(normalize_address("833589fcd6edb6e08f4c7c32d4f71b54bda02913").unwrap(), canonical);
+    }
+
+    #[test]
+    fn test_normalize_rejects_short_and_non_hex() {
+        assert_eq!(
+            normalize_address("0x833589fcd6edb6e08f4c7c32d4f71b54bda0291"),
+            Err(AddressError::InvalidLength(39))
+        );
+        assert_eq!(
+            normalize_address("0x833589fcd6edb6e08f4c7c32d4f71b54bda0291g"),
+            Err(AddressError::InvalidHex)
+        );
+        assert!(normalize_address("").is_err());
+    }
+}
@@ -74,10 +74,22 @@ pub fn mask_alipay_account_id(account_id: &str) -> String {
 /// - Line 21 = `"账号："` + masked_account_id (e.g., `"账号：138******88"`)
 /// 
 /// The ZK circuit hashes the FULL line text including prefixes.
+/// See [`account_lines`] for how the inputs are normalized and
+/// [`account_lines_preimage`] for the exact bytes hashed.
 pub fn compute_account_lines_hash(account_name: &str, account_id: &str) -> [u8; 32] {
-    // Build full line text as it appears in Alipay PDF
-    // NOTE: Alipay converts English names to UPPERCASE in their receipts, so we must match
-    // Chinese names are left as-is (Chinese characters don't have uppercase/lowercase)
+    let (line20, line21) = account_lines(account_name, account_id);
+    compute_account_lines_hash_from_lines(&line20, &line21)
+}
+
+/// Build lines 20 and 21 as they appear in the Alipay PDF
+/// 
+/// Normalization (the frontend must do the same):
+/// - Both inputs are trimmed of leading/trailing whitespace; inner spaces are kept
+/// - ASCII names are uppercased, because Alipay prints English names in UPPERCASE.
+///   Chinese names are left as-is (Chinese characters don't have uppercase/lowercase)
+/// - The account ID is masked with [`mask_alipay_account_id`]
+pub fn account_lines(account_name: &str, account_id: &str) -> (String, String) {
+    let account_name = account_name.trim();
     let formatted_name = if account_name.is_ascii() {
         account_name.to_uppercase()
     } else {
@@ -85,19 +97,85 @@ pub fn compute_account_lines_hash(account_name: &str, account_id: &str) -> [u8;
     };
     let line20 = format!("账户名：{}", formatted_name);
     let line21 = format!("账号：{}", mask_alipay_account_id(account_id));
-    
-    compute_account_lines_hash_from_lines(&line20, &line21)
+    (line20, line21)
 }
 
-/// Compute account_lines_hash from pre-processed line text (already has prefixes)
-/// Use this when you have the raw line text as it appears in the PDF.
-pub fn compute_account_lines_hash_from_lines(line20: &str, line21: &str) -> [u8; 32] {
-    let mut data = Vec::new();
+/// Exact bytes hashed into account_lines_hash
+/// 
+/// ```text
+/// 14 00 00 00 || UTF-8(line20) || 15 00 00 00 || UTF-8(line21)
+/// ```
+/// 
+/// i.e. each line number as a little-endian u32 followed by the line's UTF-8 bytes,
+/// with no length prefixes, separators or terminators. The full-width colon `：`
+/// (U+FF1A) in the prefixes is 3 bytes (`ef bc 9a`).
+pub fn account_lines_preimage(line20: &str, line21: &str) -> Vec<u8> {
+    let mut data = Vec::with_capacity(8 + line20.len() + line21.len());
     data.extend_from_slice(&20u32.to_le_bytes());
     data.extend_from_slice(line20.as_bytes());
     data.extend_from_slice(&21u32.to_le_bytes());
     data.extend_from_slice(line21.as_bytes());
-    Sha256::digest(&data).into()
+    data
+}
+
+/// Compute account_lines_hash from pre-processed line text (already has prefixes)
+/// Use this when you have the raw line text as it appears in the PDF.
+pub fn compute_account_lines_hash_from_lines(line20: &str, line21: &str) -> [u8; 32] {
+    Sha256::digest(account_lines_preimage(line20, line21)).into()
+}
+
+/// How an account_lines_hash was built, for diagnosing mismatches with the frontend
+/// 
+/// Contains the plain text account info, so it is only ever logged server-side and
+/// must not be returned to clients.
+#[derive(Debug, Clone, PartialEq)]
+pub struct AccountLinesDiagnostic {
+    /// Byte lengths of the inputs as submitted and after trimming
+    pub name_len: usize,
+    pub name_trimmed_len: usize,
+    pub id_len: usize,
+    pub id_trimmed_len: usize,
+    pub line20: String,
+    pub line21: String,
+    /// Hex of [`account_lines_preimage`]
+    pub preimage_hex: String,
+    /// 0x-prefixed hash of the preimage
+    pub hash_hex: String,
+}
+
+impl AccountLinesDiagnostic {
+    pub fn new(account_name: &str, account_id: &str) -> Self {
+        let (line20, line21) = account_lines(account_name, account_id);
+        let preimage = account_lines_preimage(&line20, &line21);
+        Self {
+            name_len: account_name.len(),
+            name_trimmed_len: account_name.trim().len(),
+            id_len: account_id.len(),
+            id_trimmed_len: account_id.trim().len(),
+            hash_hex: format!("0x{}", hex::encode(Sha256::digest(&preimage))),
+            preimage_hex: hex::encode(&preimage),
+            line20,
+            line21,
+        }
+    }
+}
+
+impl std::fmt::Display for AccountLinesDiagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "account_name: {} bytes ({} trimmed), account_id: {} bytes ({} trimmed)\n  \
+            line20: {:?} ({} bytes)\n  \
+            line21: {:?} ({} bytes)\n  \
+            preimage: {}\n  \
+            hash: {}",
+            self.name_len, self.name_trimmed_len, self.id_len, self.id_trimmed_len,
+            self.line20, self.line20.len(),
+            self.line21, self.line21.len(),
+            self.preimage_hex,
+            self.hash_hex,
+        )
+    }
 }
 
 // ============================================================================
@@ -199,6 +277,15 @@ pub fn format_amount_line(cny_cents: u64) -> String {
     format!("小写：{}", amount_str)
 }
 
+// ============================================================================
+// File Content Hash
+// ============================================================================
+
+/// Lowercase hex SHA-256 of a whole file (content address of stored receipt PDFs)
+pub fn sha256_file(data: &[u8]) -> String {
+    hex::encode(Sha256::digest(data))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -364,4 +451,59 @@ mod tests {
         
         assert_eq!(hash1, hash2);
     }
+
+    #[test]
+    fn test_account_lines_known_vectors() {
+        // preimage -> hash vectors for frontend implementations to check against
+        // (apps/web/src/lib/accountLines.test.ts)
+        let cases = [
+            (
+                "张三", "13800138000",
+                "14000000e8b4a6e688b7e5908defbc9ae5bca0e4b88915000000e8b4a6e58fb7efbc9a3133382a2a2a2a2a2a3030",
+                "0x4905c4dcca29e1ac9a9522f98e7bc8afc54f8cb79e6f11557b3664cd592169dd",
+            ),
+            (
+                "TestName", "test@example.com",
+                "14000000e8b4a6e688b7e5908defbc9a544553544e414d4515000000e8b4a6e58fb7efbc9a7465732a2a2a406578616d706c652e636f6d",
+                "0xce7bdc250e8ab9a62b66309c474ca6b3f4ebfd3a3b777c8a4f48fdb8201eab48",
+            ),
+        ];
+        for (name, id, preimage_hex, hash_hex) in cases {
+            let diagnostic = AccountLinesDiagnostic::new(name, id);
+            assert_eq!(diagnostic.preimage_hex, preimage_hex);
+            assert_eq!(diagnostic.hash_hex, hash_hex);
+            assert_eq!(format!("0x{}", hex::encode(compute_account_lines_hash(name, id))), hash_hex);
+        }
+    }
+
+    #[test]
+    fn test_account_lines_padded_name_vectors() {
+        // Shared with apps/web/src/lib/accountLines.test.ts: the frontend must hash
+        // a padded name to the same value, or payment info verification fails
+        let cases = [
+            (" 张三\t", "13800138000", "0x4905c4dcca29e1ac9a9522f98e7bc8afc54f8cb79e6f11557b3664cd592169dd"),
+            ("  TestName ", "test@example.com", "0xce7bdc250e8ab9a62b66309c474ca6b3f4ebfd3a3b777c8a4f48fdb8201eab48"),
+        ];
+        for (name, id, hash_hex) in cases {
+            assert_eq!(format!("0x{}", hex::encode(compute_account_lines_hash(name, id))), hash_hex);
+        }
+    }
+
+    #[test]
+    fn test_account_lines_trims_whitespace() {
+        let hash = compute_account_lines_hash("张三", "13800138000");
+        assert_eq!(compute_account_lines_hash(" 张三\t", "13800138000\n"), hash);
+        assert_eq!(compute_account_lines_hash("  John Doe ", "john@example.com"), compute_account_lines_hash("JOHN DOE", "john@example.com"));
+
+        let diagnostic = AccountLinesDiagnostic::new(" 张三\t", "13800138000\n");
+        assert_eq!((diagnostic.name_len, diagnostic.name_trimmed_len), (8, 6));
+        assert_eq!((diagnostic.id_len, diagnostic.id_trimmed_len), (12, 11));
+        assert_eq!(diagnostic.line20, "账户名：张三");
+        assert!(diagnostic.to_string().contains("line21: \"账号：138******00\" (20 bytes)"));
+    }
+
+    #[test]
+    fn test_sha256_file() {
+        assert_eq!(sha256_file(b"abc"), "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad");
+    }
 }
@@ -1,12 +1,18 @@
 //! Cryptographic utilities for hash computation and verification
 
+pub mod address;
 pub mod hash;
+pub mod private_code;
 
 // Re-export commonly used functions for convenience
 pub use hash::{
     mask_alipay_account_id,
     compute_account_lines_hash,
+    AccountLinesDiagnostic,
     compute_tx_id_hash,
     compute_expected_hash_with_onchain_account_hash,
     format_amount_line,
+    sha256_file,
 };
+pub use address::{normalize_address, AddressError};
+pub use private_code::generate_private_code;
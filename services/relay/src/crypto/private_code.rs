@@ -0,0 +1,46 @@
+//! Private order codes
+//!
+//! An unlisted order is reachable by anyone holding its code
+//! (GET /api/orders/private/:code), so the code is the only secret protecting
+//! it: 22 base62 characters from the OS CSPRNG (62^22 > 2^130), URL-safe as-is.
+//! Orders made private before this scheme keep their 6-digit code until the
+//! seller re-lists them.
+
+use rand::rngs::OsRng;
+use rand::Rng;
+
+/// Characters in a private code
+pub const PRIVATE_CODE_LEN: usize = 22;
+
+const BASE62: &[u8; 62] = b"0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz";
+
+/// New random private code (PRIVATE_CODE_LEN base62 characters)
+pub fn generate_private_code() -> String {
+    let mut rng = OsRng;
+    // gen_range is uniform over 0..62, so every character carries log2(62) bits
+    (0..PRIVATE_CODE_LEN)
+        .map(|_| BASE62[rng.gen_range(0..BASE62.len())] as char)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    #[test]
+    fn test_codes_unique_and_well_formed() {
+        assert!((PRIVATE_CODE_LEN as f64) * 62f64.log2() >= 128.0);
+
+        let codes: HashSet<String> = (0..20_000).map(|_| generate_private_code()).collect();
+        assert_eq!(codes.len(), 20_000, "duplicate private code");
+        for code in &codes {
+            assert_eq!(code.len(), PRIVATE_CODE_LEN);
+            assert!(code.bytes().all(|b| b.is_ascii_alphanumeric()), "{}", code);
+        }
+
+        // Every symbol shows up: the alphabet isn't accidentally truncated
+        let used: HashSet<u8> = codes.iter().flat_map(|code| code.bytes()).collect();
+        assert_eq!(used.len(), BASE62.len());
+    }
+}
@@ -0,0 +1,151 @@
+//! Fiat currencies quoted by sellers
+//!
+//! On-chain amounts (`fiatAmount`, `exchangeRate`) are integers in the currency's
+//! minor unit (fen for CNY, cents for USD/EUR, whole yen for JPY). The currency
+//! code is stored alongside orders and trades; rows created before multi-currency
+//! support default to CNY.
+
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::str::FromStr;
+
+/// Supported fiat currencies
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "UPPERCASE")]
+pub enum Currency {
+    #[default]
+    Cny,
+    Usd,
+    Eur,
+    Jpy,
+}
+
+impl Currency {
+    /// ISO 4217 code (as stored in the `currency` column)
+    pub fn code(&self) -> &'static str {
+        match self {
+            Currency::Cny => "CNY",
+            Currency::Usd => "USD",
+            Currency::Eur => "EUR",
+            Currency::Jpy => "JPY",
+        }
+    }
+
+    pub fn symbol(&self) -> &'static str {
+        match self {
+            Currency::Cny => "¥",
+            Currency::Usd => "$",
+            Currency::Eur => "€",
+            Currency::Jpy => "JP¥",
+        }
+    }
+
+    /// Number of minor-unit digits (2 for CNY cents/fen, 0 for JPY)
+    pub fn decimals(&self) -> u32 {
+        match self {
+            Currency::Cny | Currency::Usd | Currency::Eur => 2,
+            Currency::Jpy => 0,
+        }
+    }
+
+    /// Minor units per major unit (100 for CNY, 1 for JPY)
+    pub fn minor_unit_divisor(&self) -> u64 {
+        10u64.pow(self.decimals())
+    }
+
+    /// Parse a stored code, falling back to CNY for unknown/legacy values
+    pub fn from_code_or_default(code: &str) -> Self {
+        code.parse().unwrap_or_default()
+    }
+}
+
+impl fmt::Display for Currency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.code())
+    }
+}
+
+impl FromStr for Currency {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_uppercase().as_str() {
+            "CNY" => Ok(Currency::Cny),
+            "USD" => Ok(Currency::Usd),
+            "EUR" => Ok(Currency::Eur),
+            "JPY" => Ok(Currency::Jpy),
+            other => Err(format!("Unsupported currency: {}", other)),
+        }
+    }
+}
+
+/// A stored amount that isn't a non-negative integer in base/minor units.
+/// Display helpers return it instead of formatting the amount as zero.
+#[derive(Debug, Clone, PartialEq, thiserror::Error)]
+#[error("invalid amount {raw:?}")]
+pub struct InvalidAmount {
+    pub raw: String,
+}
+
+impl InvalidAmount {
+    /// Display fallback: the raw string as stored, with a warning so it gets noticed
+    pub fn into_raw(self) -> String {
+        tracing::warn!("⚠️ Can't format amount {:?}, showing it unformatted", self.raw);
+        self.raw
+    }
+}
+
+/// Parse an integer amount for display (never defaults to zero)
+pub(crate) fn parse_amount<T: FromStr>(raw: &str) -> Result<T, InvalidAmount> {
+    raw.trim().parse().map_err(|_| InvalidAmount { raw: raw.to_string() })
+}
+
+/// Format an amount in minor units for display (e.g. "72000" CNY → "¥720.00", "1500" JPY → "JP¥1500")
+pub fn format_fiat(minor_units: &str, currency: Currency) -> Result<String, InvalidAmount> {
+    let minor: u64 = parse_amount(minor_units)?;
+    let divisor = currency.minor_unit_divisor();
+    if divisor == 1 {
+        return Ok(format!("{}{}", currency.symbol(), minor));
+    }
+    Ok(format!(
+        "{}{}.{:0width$}",
+        currency.symbol(),
+        minor / divisor,
+        minor % divisor,
+        width = currency.decimals() as usize
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_two_decimal_currency() {
+        assert_eq!(format_fiat("72000", Currency::Cny).unwrap(), "¥720.00");
+        assert_eq!(format_fiat("1205", Currency::Usd).unwrap(), "$12.05");
+        assert_eq!(format_fiat("7", Currency::Eur).unwrap(), "€0.07");
+    }
+
+    #[test]
+    fn test_malformed_amount_not_shown_as_zero() {
+        let err = format_fiat("72,000", Currency::Cny).unwrap_err();
+        assert_eq!(err, InvalidAmount { raw: "72,000".to_string() });
+        assert_eq!(err.into_raw(), "72,000");
+        assert!(format_fiat("-5", Currency::Usd).is_err());
+        assert!(format_fiat("", Currency::Eur).is_err());
+    }
+
+    #[test]
+    fn test_format_zero_decimal_currency() {
+        assert_eq!(Currency::Jpy.minor_unit_divisor(), 1);
+        assert_eq!(format_fiat("1500", Currency::Jpy).unwrap(), "JP¥1500");
+    }
+
+    #[test]
+    fn test_parse_code() {
+        assert_eq!("usd".parse::<Currency>().unwrap(), Currency::Usd);
+        assert_eq!(Currency::from_code_or_default("XYZ"), Currency::Cny);
+        assert_eq!(serde_json::to_string(&Currency::Eur).unwrap(), "\"EUR\"");
+    }
+}
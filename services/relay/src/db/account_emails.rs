@@ -1,8 +1,9 @@
 //! Account Email Repository - CRUD operations for account email notifications
 //! This is account-based (by wallet address), not role-based. Any wallet can be buyer or seller.
 
-use super::models::DbAccountEmail;
+use super::models::{DbAccountActivity, DbAccountEmail};
 use super::DbResult;
+use chrono::NaiveDate;
 use sqlx::PgPool;
 use std::time::{SystemTime, UNIX_EPOCH};
 
@@ -30,7 +31,7 @@ impl AccountEmailRepository {
         
         let result = sqlx::query_as::<_, DbAccountEmail>(
             r#"
-            SELECT wallet, email, language, enabled, "createdAt", "updatedAt"
+            SELECT wallet, email, language, enabled, digest_enabled, verified, "createdAt", "updatedAt"
             FROM account_emails
             WHERE wallet = $1
             "#,
@@ -43,32 +44,63 @@ impl AccountEmailRepository {
     }
 
     /// Set or update account email (upsert)
-    pub async fn upsert(&self, wallet: &str, email: &str, language: &str) -> DbResult<DbAccountEmail> {
+    ///
+    /// A new or changed address is stored unverified with `verification_nonce`
+    /// pending; re-storing an already verified address keeps it verified.
+    pub async fn upsert(&self, wallet: &str, email: &str, language: &str, verification_nonce: &str) -> DbResult<DbAccountEmail> {
         let wallet_lower = wallet.to_lowercase();
         let now = Self::now();
         
         let result = sqlx::query_as::<_, DbAccountEmail>(
             r#"
-            INSERT INTO account_emails (wallet, email, language, enabled, "createdAt", "updatedAt")
-            VALUES ($1, $2, $3, TRUE, $4, $4)
+            INSERT INTO account_emails (wallet, email, language, enabled, verified, verification_nonce, "createdAt", "updatedAt")
+            VALUES ($1, $2, $3, TRUE, FALSE, $5, $4, $4)
             ON CONFLICT (wallet) 
             DO UPDATE SET 
                 email = EXCLUDED.email,
                 language = EXCLUDED.language,
+                verified = account_emails.verified AND LOWER(account_emails.email) = LOWER(EXCLUDED.email),
+                verification_nonce = CASE
+                    WHEN account_emails.verified AND LOWER(account_emails.email) = LOWER(EXCLUDED.email) THEN NULL
+                    ELSE EXCLUDED.verification_nonce
+                END,
                 "updatedAt" = EXCLUDED."updatedAt"
-            RETURNING wallet, email, language, enabled, "createdAt", "updatedAt"
+            RETURNING wallet, email, language, enabled, digest_enabled, verified, "createdAt", "updatedAt"
             "#,
         )
         .bind(&wallet_lower)
         .bind(email)
         .bind(language)
         .bind(now)
+        .bind(verification_nonce)
         .fetch_one(&self.pool)
         .await?;
 
         Ok(result)
     }
 
+    /// Mark an account's address verified if `verification_nonce` is its pending one;
+    /// false if the link was already used or the address changed since
+    pub async fn confirm(&self, wallet: &str, verification_nonce: &str) -> DbResult<bool> {
+        let wallet_lower = wallet.to_lowercase();
+        let now = Self::now();
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE account_emails
+            SET verified = TRUE, verification_nonce = NULL, "updatedAt" = $3
+            WHERE wallet = $1 AND verification_nonce = $2
+            "#,
+        )
+        .bind(&wallet_lower)
+        .bind(verification_nonce)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
     /// Enable or disable notifications for an account
     pub async fn set_enabled(&self, wallet: &str, enabled: bool) -> DbResult<()> {
         let wallet_lower = wallet.to_lowercase();
@@ -90,36 +122,109 @@ impl AccountEmailRepository {
         Ok(())
     }
 
-    /// Delete account email (opt out completely)
-    pub async fn delete(&self, wallet: &str) -> DbResult<()> {
+    /// Opt in to or out of the daily activity digest
+    pub async fn set_digest_enabled(&self, wallet: &str, digest_enabled: bool) -> DbResult<()> {
         let wallet_lower = wallet.to_lowercase();
+        let now = Self::now();
         
         sqlx::query(
-            r#"DELETE FROM account_emails WHERE wallet = $1"#,
+            r#"
+            UPDATE account_emails 
+            SET digest_enabled = $2, "updatedAt" = $3
+            WHERE wallet = $1
+            "#,
         )
         .bind(&wallet_lower)
+        .bind(digest_enabled)
+        .bind(now)
         .execute(&self.pool)
         .await?;
 
         Ok(())
     }
 
-    /// Get email for an account if notifications are enabled
-    pub async fn get_if_enabled(&self, wallet: &str) -> DbResult<Option<DbAccountEmail>> {
-        let wallet_lower = wallet.to_lowercase();
-        
+    /// Verified accounts with notifications on that opted in to the digest and haven't had one for `day`
+    pub async fn get_digest_due(&self, day: NaiveDate) -> DbResult<Vec<DbAccountEmail>> {
         let result = sqlx::query_as::<_, DbAccountEmail>(
             r#"
-            SELECT wallet, email, language, enabled, "createdAt", "updatedAt"
+            SELECT wallet, email, language, enabled, digest_enabled, verified, "createdAt", "updatedAt"
             FROM account_emails
-            WHERE wallet = $1 AND enabled = TRUE
+            WHERE enabled = TRUE AND verified = TRUE AND digest_enabled = TRUE
+              AND (digest_sent_for IS NULL OR digest_sent_for < $1)
+            ORDER BY wallet
+            "#,
+        )
+        .bind(day)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(result)
+    }
+
+    /// Claim an account's digest for `day`; false if it was already sent (by this or another instance)
+    pub async fn claim_digest(&self, wallet: &str, day: NaiveDate) -> DbResult<bool> {
+        let wallet_lower = wallet.to_lowercase();
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE account_emails
+            SET digest_sent_for = $2
+            WHERE wallet = $1 AND (digest_sent_for IS NULL OR digest_sent_for < $2)
             "#,
         )
         .bind(&wallet_lower)
-        .fetch_optional(&self.pool)
+        .bind(day)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+
+    /// Trades an account took part in (as buyer or seller) during [from, to), unix seconds
+    pub async fn get_activity(&self, wallet: &str, from: i64, to: i64) -> DbResult<DbAccountActivity> {
+        let wallet_lower = wallet.to_lowercase();
+        
+        let result = sqlx::query_as::<_, DbAccountActivity>(
+            r#"
+            SELECT
+                COUNT(*) FILTER (WHERE t."createdAt" >= $2 AND t."createdAt" < $3) AS new_trades,
+                COUNT(*) FILTER (
+                    WHERE t.status = 1
+                      AND t.settled_at >= TO_TIMESTAMP($2) AND t.settled_at < TO_TIMESTAMP($3)
+                ) AS settled_trades,
+                COUNT(*) FILTER (
+                    WHERE t.status = 2 AND t."expiresAt" >= $2 AND t."expiresAt" < $3
+                ) AS expired_trades
+            FROM trades t
+            JOIN orders o ON t."orderId" = o."orderId"
+            WHERE LOWER(t.buyer) = $1 OR LOWER(o.seller) = $1
+            "#,
+        )
+        .bind(&wallet_lower)
+        .bind(from)
+        .bind(to)
+        .fetch_one(&self.pool)
         .await?;
 
         Ok(result)
     }
-}
 
+    /// Delete account email (opt out completely)
+    pub async fn delete(&self, wallet: &str) -> DbResult<()> {
+        let wallet_lower = wallet.to_lowercase();
+        
+        sqlx::query(
+            r#"DELETE FROM account_emails WHERE wallet = $1"#,
+        )
+        .bind(&wallet_lower)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Get email for an account if it receives notifications (enabled and verified)
+    pub async fn get_if_enabled(&self, wallet: &str) -> DbResult<Option<DbAccountEmail>> {
+        Ok(self.get(wallet).await?.filter(DbAccountEmail::receives_notifications))
+    }
+}
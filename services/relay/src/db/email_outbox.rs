@@ -0,0 +1,207 @@
+//! Email Outbox Repository - notification emails waiting to be retried
+//! Rows are inserted when a send fails and drained by the outbox worker (email::outbox).
+
+use super::models::DbEmailOutboxEntry;
+use super::DbResult;
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use sqlx::PgPool;
+
+/// Outbox row counts by status (GET /api/admin/email-outbox)
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct EmailOutboxCounts {
+    pub pending: i64,
+    pub delivered: i64,
+    pub failed: i64,
+}
+
+/// A rendered email to queue for retry
+#[derive(Debug, Clone, Copy)]
+pub struct OutboxEmail<'a> {
+    pub to_email: &'a str,
+    pub subject: &'a str,
+    pub html: &'a str,
+    pub unsubscribe_url: Option<&'a str>,
+    /// EmailEvent name (for monitoring)
+    pub event: &'a str,
+}
+
+/// How long a claimed row is hidden from other workers; longer than any send
+/// (or the shutdown flush), so a claim only runs out if its worker died
+const CLAIM_LEASE_SECS: i64 = 300;
+
+/// Repository for email outbox operations
+pub struct EmailOutboxRepository {
+    pool: PgPool,
+}
+
+impl EmailOutboxRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Queue a rendered email whose first send failed
+    pub async fn enqueue(
+        &self,
+        email: OutboxEmail<'_>,
+        error: &str,
+        next_attempt_at: DateTime<Utc>,
+    ) -> DbResult<i64> {
+        let row: (i64,) = sqlx::query_as(
+            r#"
+            INSERT INTO email_outbox (to_email, subject, html, unsubscribe_url, event, last_error, next_attempt_at)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            RETURNING id
+            "#,
+        )
+        .bind(email.to_email)
+        .bind(email.subject)
+        .bind(email.html)
+        .bind(email.unsubscribe_url)
+        .bind(email.event)
+        .bind(error)
+        .bind(next_attempt_at)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.0)
+    }
+
+    /// Claim pending emails whose next attempt is due (oldest first)
+    pub async fn claim_due(&self, limit: i64) -> DbResult<Vec<DbEmailOutboxEntry>> {
+        self.claim(limit, true).await
+    }
+
+    /// Claim all pending emails, including ones still backing off (shutdown flush)
+    pub async fn claim_pending(&self, limit: i64) -> DbResult<Vec<DbEmailOutboxEntry>> {
+        self.claim(limit, false).await
+    }
+
+    /// Claim unclaimed pending rows for this worker. Rows locked by a concurrent
+    /// claim are skipped rather than waited on, and a claimed row stays hidden
+    /// from other workers until its attempt is recorded or the lease runs out.
+    async fn claim(&self, limit: i64, due_only: bool) -> DbResult<Vec<DbEmailOutboxEntry>> {
+        let mut rows = sqlx::query_as::<_, DbEmailOutboxEntry>(
+            r#"
+            UPDATE email_outbox
+            SET claimed_until = NOW() + make_interval(secs => $3)
+            WHERE id IN (
+                SELECT id FROM email_outbox
+                WHERE status = 'pending'
+                  AND (claimed_until IS NULL OR claimed_until < NOW())
+                  AND (NOT $2 OR next_attempt_at <= NOW())
+                ORDER BY next_attempt_at ASC
+                LIMIT $1
+                FOR UPDATE SKIP LOCKED
+            )
+            RETURNING id, to_email, subject, html, unsubscribe_url, event, status, attempts, last_error, next_attempt_at, created_at
+            "#,
+        )
+        .bind(limit)
+        .bind(due_only)
+        .bind(CLAIM_LEASE_SECS as f64)
+        .fetch_all(&self.pool)
+        .await?;
+
+        // RETURNING doesn't keep the subquery's order
+        rows.sort_by_key(|row| (row.next_attempt_at, row.id));
+        Ok(rows)
+    }
+
+    pub async fn mark_delivered(&self, id: i64) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET status = 'delivered', attempts = attempts + 1, claimed_until = NULL, updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Record another failed attempt; schedule a retry, or give up when `next_attempt_at` is None
+    pub async fn mark_attempt_failed(&self, id: i64, error: &str, next_attempt_at: Option<DateTime<Utc>>) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE email_outbox
+            SET attempts = attempts + 1,
+                last_error = $2,
+                status = CASE WHEN $3::TIMESTAMPTZ IS NULL THEN 'failed' ELSE 'pending' END,
+                next_attempt_at = COALESCE($3, next_attempt_at),
+                claimed_until = NULL,
+                updated_at = NOW()
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .bind(next_attempt_at)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn counts(&self) -> DbResult<EmailOutboxCounts> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
+            "SELECT status, COUNT(*) FROM email_outbox GROUP BY status",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut counts = EmailOutboxCounts::default();
+        for (status, count) in rows {
+            match status.as_str() {
+                "pending" => counts.pending = count,
+                "delivered" => counts.delivered = count,
+                "failed" => counts.failed = count,
+                _ => {}
+            }
+        }
+        Ok(counts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn email(to_email: &str) -> OutboxEmail<'_> {
+        OutboxEmail { to_email, subject: "Subject", html: "<p>Hi</p>", unsubscribe_url: None, event: "TradeSettledSeller" }
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_rows_are_claimed_by_one_worker(pool: PgPool) {
+        let worker = EmailOutboxRepository::new(pool.clone());
+        let replica = EmailOutboxRepository::new(pool);
+        let due = Utc::now() - chrono::Duration::seconds(1);
+        for to in ["a@example.com", "b@example.com", "c@example.com"] {
+            worker.enqueue(email(to), "timeout", due).await.unwrap();
+        }
+        let backing_off = worker.enqueue(email("d@example.com"), "timeout", Utc::now() + chrono::Duration::minutes(5)).await.unwrap();
+
+        // Concurrent claims split the due rows between them
+        let (first, second) = tokio::join!(worker.claim_due(2), replica.claim_due(2));
+        let mut ids: Vec<i64> = first.unwrap().iter().chain(second.as_ref().unwrap()).map(|row| row.id).collect();
+        ids.sort();
+        assert_eq!(ids.len(), 3);
+        ids.dedup();
+        assert_eq!(ids.len(), 3, "a row was claimed twice");
+        assert!(replica.claim_due(10).await.unwrap().is_empty());
+
+        // The shutdown flush only gets the row nobody holds
+        let flushed: Vec<i64> = replica.claim_pending(10).await.unwrap().iter().map(|row| row.id).collect();
+        assert_eq!(flushed, [backing_off]);
+
+        // Recording a failed attempt releases the row for its retry
+        worker.mark_attempt_failed(ids[0], "timeout", Some(due)).await.unwrap();
+        let retried: Vec<i64> = replica.claim_due(10).await.unwrap().iter().map(|row| row.id).collect();
+        assert_eq!(retried, [ids[0]]);
+    }
+}
@@ -3,13 +3,91 @@
 //! Records every on-chain transaction's gas cost per chain,
 //! enabling fee optimization and cost analysis.
 
-use sqlx::PgPool;
+use chrono::{DateTime, Utc};
+use sqlx::postgres::PgRow;
+use sqlx::{PgPool, Row};
 
 use super::{DbResult};
 use super::models::DbGasCost;
 
+impl DbGasCost {
+    /// New row for a confirmed transaction, from its receipt (already reconciled)
+    pub fn from_receipt(
+        chain_id: u64,
+        operation: &str,
+        trade_id: Option<&str>,
+        tx_hash: String,
+        gas_used: u64,
+        effective_gas_price_wei: u128,
+    ) -> Self {
+        let mut row = Self {
+            id: 0,
+            chain_id: chain_id as i32,
+            operation: operation.to_string(),
+            trade_id: trade_id.map(str::to_string),
+            order_id: None,
+            tx_hash,
+            gas_used: 0,
+            gas_price_gwei: String::new(),
+            cost_wei: String::new(),
+            cost_eth: String::new(),
+            created_at: Utc::now(),
+            reconciled_at: None,
+            gas_estimate: None,
+        };
+        row.apply_receipt(gas_used, effective_gas_price_wei);
+        row
+    }
+
+    /// Keep the pre-send gas estimate for comparison with the gas actually used
+    pub fn with_gas_estimate(mut self, gas_estimate: u64) -> Self {
+        self.gas_estimate = Some(gas_estimate as i64);
+        self
+    }
+
+    /// Overwrite estimated gas values with the actual receipt values and mark reconciled
+    pub fn apply_receipt(&mut self, gas_used: u64, effective_gas_price_wei: u128) {
+        let cost_wei = gas_used as u128 * effective_gas_price_wei;
+        self.gas_used = gas_used as i64;
+        self.gas_price_gwei = format_units(effective_gas_price_wei, 9);
+        self.cost_wei = cost_wei.to_string();
+        self.cost_eth = format_units(cost_wei, 18);
+        self.reconciled_at = Some(Utc::now());
+    }
+}
+
+/// Format an integer amount with a fixed number of decimals (exact, no float rounding)
+pub(crate) fn format_units(amount: u128, decimals: u32) -> String {
+    let divisor = 10u128.pow(decimals);
+    format!("{}.{:0width$}", amount / divisor, amount % divisor, width = decimals as usize)
+}
+
+/// Map a full `gas_costs` row (NUMERIC columns selected as TEXT)
+fn gas_cost_from_row(row: &PgRow) -> DbGasCost {
+    DbGasCost {
+        id: row.get("id"),
+        chain_id: row.get("chainId"),
+        operation: row.get("operation"),
+        trade_id: row.get("tradeId"),
+        order_id: row.get("orderId"),
+        tx_hash: row.get("txHash"),
+        gas_used: row.get("gasUsed"),
+        gas_price_gwei: row.get::<Option<String>, _>("gasPriceGwei").unwrap_or_default(),
+        cost_wei: row.get::<Option<String>, _>("costWei").unwrap_or_default(),
+        cost_eth: row.get::<Option<String>, _>("costEth").unwrap_or_default(),
+        created_at: row.get("createdAt"),
+        reconciled_at: row.get("reconciledAt"),
+        gas_estimate: row.get("gasEstimate"),
+    }
+}
+
+/// Whole wei from a NUMERIC rendered as text (any fraction is dropped)
+pub(crate) fn parse_wei(value: &str) -> u128 {
+    value.split('.').next().and_then(|whole| whole.parse().ok()).unwrap_or(0)
+}
+
 /// Summary of gas costs grouped by operation
-#[derive(Debug, serde::Serialize)]
+#[derive(Debug, Clone, serde::Serialize)]
 pub struct GasCostSummary {
     pub operation: String,
     pub count: i64,
@@ -30,13 +108,21 @@ impl GasCostRepository {
     
     /// Record a new gas cost entry
     pub async fn create(&self, gas_cost: &DbGasCost) -> DbResult<()> {
-        sqlx::query(
+        self.create_for_trades(gas_cost, &[]).await
+    }
+
+    /// Record one transaction's gas cost, linked to every trade in `trade_ids`
+    /// (the trades a batch covered). The row and its links commit together.
+    pub async fn create_for_trades(&self, gas_cost: &DbGasCost, trade_ids: &[String]) -> DbResult<()> {
+        let mut tx = self.pool.begin().await?;
+        let id: i32 = sqlx::query_scalar(
             r#"
             INSERT INTO gas_costs (
                 "chainId", "operation", "tradeId", "orderId", "txHash",
-                "gasUsed", "gasPriceGwei", "costWei", "costEth"
+                "gasUsed", "gasPriceGwei", "costWei", "costEth", "gasEstimate"
             )
-            VALUES ($1, $2, $3, $4, $5, $6, $7::numeric, $8::numeric, $9::numeric)
+            VALUES ($1, $2, $3, $4, $5, $6, $7::numeric, $8::numeric, $9::numeric, $10)
+            RETURNING "id"
             "#,
         )
         .bind(gas_cost.chain_id)
@@ -48,6 +134,91 @@ impl GasCostRepository {
         .bind(&gas_cost.gas_price_gwei)
         .bind(&gas_cost.cost_wei)
         .bind(&gas_cost.cost_eth)
+        .bind(gas_cost.gas_estimate)
+        .fetch_one(&mut *tx)
+        .await?;
+
+        if !trade_ids.is_empty() {
+            let trade_ids: Vec<String> = trade_ids.iter().map(|id| id.to_lowercase()).collect();
+            sqlx::query(
+                r#"
+                INSERT INTO gas_cost_trades ("gasCostId", "tradeId")
+                SELECT $1, UNNEST($2::TEXT[])
+                ON CONFLICT DO NOTHING
+                "#,
+            )
+            .bind(id)
+            .bind(&trade_ids)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+    
+    /// Get recent rows not yet reconciled against their on-chain receipt (oldest first)
+    pub async fn get_unreconciled(&self, max_age_days: i32, limit: i64) -> DbResult<Vec<DbGasCost>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 
+                "id", "chainId", "operation", "tradeId", "orderId", "txHash", "gasUsed",
+                "gasPriceGwei"::TEXT, "costWei"::TEXT, "costEth"::TEXT, "createdAt", "reconciledAt", "gasEstimate"
+            FROM gas_costs
+            WHERE "reconciledAt" IS NULL
+              AND "createdAt" > NOW() - make_interval(days => $1)
+            ORDER BY "createdAt" ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(max_age_days)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.iter().map(gas_cost_from_row).collect())
+    }
+    
+    /// Every recorded transaction of one trade (oldest first; empty if none),
+    /// including batch transactions linked to it (with the whole batch's cost)
+    pub async fn get_by_trade(&self, trade_id: &str) -> DbResult<Vec<DbGasCost>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 
+                "id", "chainId", "operation", "tradeId", "orderId", "txHash", "gasUsed",
+                "gasPriceGwei"::TEXT, "costWei"::TEXT, "costEth"::TEXT, "createdAt", "reconciledAt", "gasEstimate"
+            FROM gas_costs
+            WHERE LOWER("tradeId") = LOWER($1)
+               OR "id" IN (SELECT "gasCostId" FROM gas_cost_trades WHERE "tradeId" = LOWER($1))
+            ORDER BY "createdAt" ASC, "id" ASC
+            "#,
+        )
+        .bind(trade_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows.iter().map(gas_cost_from_row).collect())
+    }
+    
+    /// Persist receipt-corrected gas values for a row (see `DbGasCost::apply_receipt`)
+    pub async fn save_reconciled(&self, gas_cost: &DbGasCost) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE gas_costs
+            SET "gasUsed" = $1,
+                "gasPriceGwei" = $2::numeric,
+                "costWei" = $3::numeric,
+                "costEth" = $4::numeric,
+                "reconciledAt" = $5
+            WHERE "id" = $6
+            "#,
+        )
+        .bind(gas_cost.gas_used)
+        .bind(&gas_cost.gas_price_gwei)
+        .bind(&gas_cost.cost_wei)
+        .bind(&gas_cost.cost_eth)
+        .bind(gas_cost.reconciled_at)
+        .bind(gas_cost.id)
         .execute(&self.pool)
         .await?;
         
@@ -75,7 +246,6 @@ impl GasCostRepository {
         .fetch_all(&self.pool)
         .await?;
         
-        use sqlx::Row;
         let summaries = rows.into_iter().map(|row| {
             GasCostSummary {
                 operation: row.get("operation"),
@@ -90,8 +260,67 @@ impl GasCostRepository {
         Ok(summaries)
     }
     
-    /// Get total gas cost per trade (for DB viewer display)
-    pub async fn get_costs_by_trade(&self) -> DbResult<Vec<TradeGasCost>> {
+    /// Total cost (wei) of a chain's transactions recorded since `since`
+    pub async fn total_cost_wei_since(&self, chain_id: i32, since: DateTime<Utc>) -> DbResult<u128> {
+        let total: Option<String> = sqlx::query_scalar(
+            r#"
+            SELECT SUM("costWei")::TEXT
+            FROM gas_costs
+            WHERE "chainId" = $1 AND "createdAt" >= $2
+            "#,
+        )
+        .bind(chain_id)
+        .bind(since)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(total.as_deref().map(parse_wei).unwrap_or(0))
+    }
+    
+    /// Per-operation summaries for every chain with recorded costs (ordered by chain)
+    pub async fn get_summary_all_chains(&self) -> DbResult<Vec<(i32, Vec<GasCostSummary>)>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 
+                "chainId",
+                "operation",
+                COUNT(*) as count,
+                SUM("costWei")::TEXT as total_cost_wei,
+                SUM("costEth")::TEXT as total_cost_eth,
+                AVG("gasUsed")::FLOAT8 as avg_gas_used,
+                AVG("gasPriceGwei"::FLOAT8) as avg_gas_price_gwei
+            FROM gas_costs
+            GROUP BY "chainId", "operation"
+            ORDER BY "chainId", "operation"
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let mut chains: Vec<(i32, Vec<GasCostSummary>)> = Vec::new();
+        for row in rows {
+            let chain_id: i32 = row.get("chainId");
+            let summary = GasCostSummary {
+                operation: row.get("operation"),
+                count: row.get("count"),
+                total_cost_wei: row.get::<Option<String>, _>("total_cost_wei").unwrap_or_default(),
+                total_cost_eth: row.get::<Option<String>, _>("total_cost_eth").unwrap_or_default(),
+                avg_gas_used: row.get::<Option<f64>, _>("avg_gas_used").unwrap_or(0.0),
+                avg_gas_price_gwei: row.get::<Option<f64>, _>("avg_gas_price_gwei").unwrap_or(0.0),
+            };
+            match chains.last_mut() {
+                Some((last, summaries)) if *last == chain_id => summaries.push(summary),
+                _ => chains.push((chain_id, vec![summary])),
+            }
+        }
+        
+        Ok(chains)
+    }
+    
+    /// Get total gas cost per trade of `trade_ids` (for DB viewer display; the
+    /// caller's page of trades bounds the result)
+    pub async fn get_costs_by_trade(&self, trade_ids: &[String]) -> DbResult<Vec<TradeGasCost>> {
+        let trade_ids: Vec<String> = trade_ids.iter().map(|id| id.to_lowercase()).collect();
         let rows = sqlx::query(
             r#"
             SELECT 
@@ -100,15 +329,15 @@ impl GasCostRepository {
                 SUM("gasUsed")::BIGINT as total_gas_used,
                 COUNT(*) as operations
             FROM gas_costs
-            WHERE "tradeId" IS NOT NULL AND "tradeId" != ''
+            WHERE LOWER("tradeId") = ANY($1)
             GROUP BY "tradeId"
             ORDER BY "tradeId"
             "#,
         )
+        .bind(&trade_ids)
         .fetch_all(&self.pool)
         .await?;
         
-        use sqlx::Row;
         let costs = rows.into_iter().map(|row| {
             TradeGasCost {
                 trade_id: row.get("tradeId"),
@@ -122,6 +351,39 @@ impl GasCostRepository {
     }
 }
 
+impl GasCostSummary {
+    /// Combine per-operation summaries of several chains into one per operation.
+    /// Base and Ethereum both pay gas in ETH (18 decimals), so wei add up directly;
+    /// totals are summed as integers, averages weighted by transaction count.
+    pub fn roll_up(chains: &[(i32, Vec<GasCostSummary>)]) -> Vec<GasCostSummary> {
+        let mut by_operation: std::collections::BTreeMap<&str, (i64, u128, f64, f64)> = Default::default();
+        for summary in chains.iter().flat_map(|(_, summaries)| summaries) {
+            let entry = by_operation.entry(summary.operation.as_str()).or_default();
+            entry.0 += summary.count;
+            entry.1 += parse_wei(&summary.total_cost_wei);
+            entry.2 += summary.avg_gas_used * summary.count as f64;
+            entry.3 += summary.avg_gas_price_gwei * summary.count as f64;
+        }
+
+        by_operation.into_iter().map(|(operation, (count, cost_wei, gas_used, gas_price))| {
+            let weight = count.max(1) as f64;
+            GasCostSummary {
+                operation: operation.to_string(),
+                count,
+                total_cost_wei: cost_wei.to_string(),
+                total_cost_eth: format_units(cost_wei, 18),
+                avg_gas_used: gas_used / weight,
+                avg_gas_price_gwei: gas_price / weight,
+            }
+        }).collect()
+    }
+
+    /// Total cost (wei) over all `summaries`
+    pub fn total_wei(summaries: &[GasCostSummary]) -> u128 {
+        summaries.iter().map(|s| parse_wei(&s.total_cost_wei)).sum()
+    }
+}
+
 /// Gas cost for a specific trade
 #[derive(Debug, serde::Serialize)]
 pub struct TradeGasCost {
@@ -130,3 +392,122 @@ pub struct TradeGasCost {
     pub total_gas_used: i64,
     pub operations: i64,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reconciliation_replaces_estimate_with_receipt() {
+        let mut row = DbGasCost {
+            id: 1,
+            chain_id: 8453,
+            operation: "cancel".to_string(),
+            trade_id: Some("0xabc".to_string()),
+            order_id: None,
+            tx_hash: "0xdef".to_string(),
+            gas_used: 100_000,
+            gas_price_gwei: "0.030000000".to_string(),
+            cost_wei: "3000000000000".to_string(),
+            cost_eth: "0.000003000000000000".to_string(),
+            created_at: Utc::now(),
+            reconciled_at: None,
+            gas_estimate: None,
+        };
+
+        // Receipt: 61,234 gas at 0.0125 gwei
+        row.apply_receipt(61_234, 12_500_000);
+
+        assert_eq!(row.gas_used, 61_234);
+        assert_eq!(row.gas_price_gwei, "0.012500000");
+        assert_eq!(row.cost_wei, "765425000000");
+        assert_eq!(row.cost_eth, "0.000000765425000000");
+        assert!(row.reconciled_at.is_some());
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_costs_by_trade_only_for_requested_trades(pool: PgPool) {
+        let repo = GasCostRepository::new(pool);
+        for (trade_id, tx_hash) in [("0xAAA", "0x01"), ("0xaaa", "0x02"), ("0xbbb", "0x03")] {
+            let row = DbGasCost::from_receipt(8453, "settle", Some(trade_id), tx_hash.to_string(), 50_000, 10_000_000);
+            repo.create(&row).await.unwrap();
+        }
+        repo.create(&DbGasCost::from_receipt(8453, "cancel_batch", None, "0x04".to_string(), 50_000, 10_000_000)).await.unwrap();
+
+        let costs = repo.get_costs_by_trade(&["0xaaa".to_string()]).await.unwrap();
+        assert_eq!(costs.iter().map(|c| c.operations).sum::<i64>(), 2);
+        assert!(costs.iter().all(|c| c.trade_id.eq_ignore_ascii_case("0xaaa")));
+        assert!(repo.get_costs_by_trade(&[]).await.unwrap().is_empty());
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_batch_cost_listed_for_each_trade_in_it(pool: PgPool) {
+        let repo = GasCostRepository::new(pool);
+        repo.create(&DbGasCost::from_receipt(8453, "fill", Some("0xaaa"), "0x01".to_string(), 50_000, 10_000_000)).await.unwrap();
+        let batch = DbGasCost::from_receipt(8453, "cancel_batch", None, "0x02".to_string(), 90_000, 10_000_000);
+        repo.create_for_trades(&batch, &["0xAAA".to_string(), "0xbbb".to_string()]).await.unwrap();
+
+        let operations = |costs: Vec<DbGasCost>| costs.into_iter().map(|c| c.operation).collect::<Vec<_>>();
+        assert_eq!(operations(repo.get_by_trade("0xaaa").await.unwrap()), vec!["fill", "cancel_batch"]);
+        assert_eq!(operations(repo.get_by_trade("0xBBB").await.unwrap()), vec!["cancel_batch"]);
+        assert!(repo.get_by_trade("0xccc").await.unwrap().is_empty());
+
+        // The batch is still one transaction for the budget
+        let spent = repo.total_cost_wei_since(8453, Utc::now() - chrono::Duration::hours(1)).await.unwrap();
+        assert_eq!(spent, 1_400_000_000_000);
+    }
+
+    #[test]
+    fn test_row_from_receipt() {
+        let row = DbGasCost::from_receipt(8453, "cancel_batch", None, "0xdef".to_string(), 61_234, 12_500_000);
+        assert_eq!(row.chain_id, 8453);
+        assert_eq!(row.cost_wei, "765425000000");
+        assert!(row.trade_id.is_none());
+
+        assert_eq!(parse_wei("765425000000"), 765_425_000_000);
+        assert_eq!(parse_wei("12.000"), 12);
+        assert_eq!(parse_wei(""), 0);
+    }
+
+    fn summary(operation: &str, count: i64, total_cost_wei: &str, avg_gas_used: f64) -> GasCostSummary {
+        GasCostSummary {
+            operation: operation.to_string(),
+            count,
+            total_cost_wei: total_cost_wei.to_string(),
+            total_cost_eth: String::new(),
+            avg_gas_used,
+            avg_gas_price_gwei: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_roll_up_equals_sum_of_chains() {
+        let chains = vec![
+            (1, vec![
+                summary("cancel", 2, "4000000000000000", 60_000.0),
+                summary("settle", 1, "9000000000000000", 300_000.0),
+            ]),
+            (8453, vec![
+                summary("cancel", 6, "12000000000000", 40_000.0),
+                summary("cancel_batch", 1, "500000000000", 400_000.0),
+            ]),
+        ];
+
+        let rolled = GasCostSummary::roll_up(&chains);
+        let operations: Vec<&str> = rolled.iter().map(|s| s.operation.as_str()).collect();
+        assert_eq!(operations, vec!["cancel", "cancel_batch", "settle"]);
+
+        let cancel = &rolled[0];
+        assert_eq!(cancel.count, 8);
+        assert_eq!(cancel.total_cost_wei, "4012000000000000");
+        assert_eq!(cancel.total_cost_eth, "0.004012000000000000");
+        assert_eq!(cancel.avg_gas_used, 45_000.0);
+
+        let per_chain: u128 = chains.iter().map(|(_, s)| GasCostSummary::total_wei(s)).sum();
+        assert_eq!(GasCostSummary::total_wei(&rolled), per_chain);
+        assert_eq!(per_chain, 13_012_500_000_000_000);
+    }
+}
@@ -0,0 +1,61 @@
+//! Trade Input Streams Repository - Axiom input streams persisted between validation and settlement
+//! Read through `api::state::InputStreamsCache`; rows expire after the cache TTL.
+
+use super::DbResult;
+use sqlx::PgPool;
+use std::time::Duration;
+
+/// Repository for persisted input streams
+pub struct InputStreamRepository {
+    pool: PgPool,
+}
+
+impl InputStreamRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Store (or replace) a trade's streams, valid for `ttl`; purges expired rows
+    pub async fn put(&self, trade_id: &str, streams: &[String], ttl: Duration) -> DbResult<()> {
+        sqlx::query("DELETE FROM trade_input_streams WHERE expires_at <= NOW()")
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO trade_input_streams (trade_id, streams, expires_at)
+            VALUES ($1, $2, NOW() + make_interval(secs => $3))
+            ON CONFLICT (trade_id)
+            DO UPDATE SET streams = EXCLUDED.streams, created_at = NOW(), expires_at = EXCLUDED.expires_at
+            "#,
+        )
+        .bind(trade_id)
+        .bind(streams)
+        .bind(ttl.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// A trade's streams, unless missing or expired
+    pub async fn get(&self, trade_id: &str) -> DbResult<Option<Vec<String>>> {
+        let row: Option<(Vec<String>,)> = sqlx::query_as(
+            "SELECT streams FROM trade_input_streams WHERE trade_id = $1 AND expires_at > NOW()",
+        )
+        .bind(trade_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|r| r.0))
+    }
+
+    pub async fn delete(&self, trade_id: &str) -> DbResult<()> {
+        sqlx::query("DELETE FROM trade_input_streams WHERE trade_id = $1")
+            .bind(trade_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+}
@@ -1,7 +1,12 @@
 pub mod account_emails;
+pub mod email_outbox;
 pub mod gas_costs;
+pub mod input_streams;
 pub mod models;
 pub mod orders;
+pub mod processed_events;
+pub mod proofs;
+pub mod retry;
 pub mod trades;
 pub mod withdrawals;
 
@@ -10,6 +15,8 @@ use std::time::Duration;
 use thiserror::Error;
 use chrono::{DateTime, Utc};
 use trades::TradeRepository;
+use retry::with_retry;
+use crate::config::DbPoolConfig;
 
 #[derive(Debug, Error)]
 pub enum DbError {
@@ -32,34 +39,66 @@ pub enum DbError {
 pub type DbResult<T> = Result<T, DbError>;
 
 /// Database connection manager for on-chain event tracking
+///
+/// Writes always use the primary pool. When DATABASE_REPLICA_URL is set, the
+/// read-heavy listing/stats methods use a second pool on the replica instead
+/// (see `read_pool`); use `primary()` to read back something just written.
+/// Reads and repeat-safe writes are retried on transient errors (see `retry`);
+/// inserts and claims are not.
 pub struct Database {
     pool: PgPool,
+    replica: Option<PgPool>,
 }
 
 impl Database {
-    /// Create a new database connection from URL
-    pub async fn new(database_url: &str) -> DbResult<Self> {
+    /// Connect to the primary (and the read replica, if given) with the configured pool settings
+    pub async fn new(database_url: &str, replica_url: Option<&str>, pool_config: &DbPoolConfig) -> DbResult<Self> {
+        let pool = Self::connect(database_url, pool_config).await?;
+        let replica = match replica_url {
+            Some(url) => Some(Self::connect(url, pool_config).await?),
+            None => None,
+        };
+        Ok(Self { pool, replica })
+    }
+
+    /// Wrap an existing pool (for `#[sqlx::test]`s, which create a migrated database per test;
+    /// the binaries' tests get it with `cargo test --features test-fixtures`)
+    #[cfg(any(test, feature = "test-fixtures"))]
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool, replica: None }
+    }
+
+    async fn connect(database_url: &str, pool_config: &DbPoolConfig) -> DbResult<PgPool> {
         let pool = PgPoolOptions::new()
-            .max_connections(10)
+            .max_connections(pool_config.max_connections)
             .min_connections(1)
-            .acquire_timeout(Duration::from_secs(30))
-            // Railway drops idle connections aggressively - keep idle timeout short
-            .idle_timeout(Duration::from_secs(60))
+            .acquire_timeout(Duration::from_secs(pool_config.acquire_timeout_secs))
+            .idle_timeout(Duration::from_secs(pool_config.idle_timeout_secs))
             // Force connection refresh every 5 minutes
             .max_lifetime(Duration::from_secs(300))
             // Test connections before use to detect stale connections
             .test_before_acquire(true)
             .connect(database_url)
             .await?;
-
-        Ok(Self { pool })
+        Ok(pool)
     }
 
-    /// Get the connection pool
+    /// Get the (primary) connection pool
     pub fn pool(&self) -> &PgPool {
         &self.pool
     }
 
+    /// Pool for read-only queries that tolerate replica lag (the primary when no replica)
+    fn read_pool(&self) -> &PgPool {
+        self.replica.as_ref().unwrap_or(&self.pool)
+    }
+
+    /// This database with every read on the primary, for read-your-writes
+    /// right after a write (pools are shared, this is cheap)
+    pub fn primary(&self) -> Database {
+        Database { pool: self.pool.clone(), replica: None }
+    }
+
     /// Run database migrations
     pub async fn migrate(&self) -> DbResult<()> {
         sqlx::migrate!("./migrations")
@@ -76,64 +115,188 @@ impl Database {
         Ok(())
     }
 
-    /// Close all connections
+    /// Last block the event listener has indexed for an escrow contract (None before first sync)
+    pub async fn get_last_synced_block(&self, contract_address: &str) -> DbResult<Option<i64>> {
+        let row: Option<(i64,)> = sqlx::query_as(
+            "SELECT last_synced_block FROM event_sync_state WHERE contract_address = $1",
+        )
+        .bind(contract_address.to_lowercase())
+        .fetch_optional(&self.pool)
+        .await?;
+        Ok(row.map(|r| r.0))
+    }
+
+    /// Queue proof generation + settlement for a trade (returns its active job if already queued)
+    pub async fn enqueue_proof_job(&self, trade_id: &str, transaction_id: &str, payment_time: &str) -> DbResult<i64> {
+        proofs::ProofJobRepository::new(self.pool.clone()).enqueue(trade_id, transaction_id, payment_time).await
+    }
+
+    /// Proof job by id
+    pub async fn get_proof_job(&self, job_id: i64) -> DbResult<Option<models::DbProofJob>> {
+        let repo = proofs::ProofJobRepository::new(self.pool.clone());
+        with_retry(|| repo.get(job_id)).await
+    }
+
+    /// Most recent proof job for a trade
+    pub async fn get_latest_proof_job(&self, trade_id: &str) -> DbResult<Option<models::DbProofJob>> {
+        let repo = proofs::ProofJobRepository::new(self.pool.clone());
+        with_retry(|| repo.latest_for_trade(trade_id)).await
+    }
+    
+    /// Email outbox row counts by status
+    pub async fn get_email_outbox_counts(&self) -> DbResult<email_outbox::EmailOutboxCounts> {
+        let repo = email_outbox::EmailOutboxRepository::new(self.pool.clone());
+        with_retry(|| repo.counts()).await
+    }
+
+    /// Close all connections (primary and replica)
     pub async fn close(&self) {
         self.pool.close().await;
+        if let Some(replica) = &self.replica {
+            replica.close().await;
+        }
     }
     
     /// Get all active orders (convenience method for API)
     /// chain_id: None = all chains, Some(8453) = Base only, Some(1) = ETH only
     pub async fn get_active_orders(&self, limit: Option<i64>, chain_id: Option<i32>) -> DbResult<Vec<models::DbOrder>> {
-        let repo = orders::PostgresOrderRepository::new(self.pool.clone());
-        repo.get_active_orders(limit, chain_id).await
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_active_orders(limit, chain_id)).await
+    }
+    
+    /// One page of active public orders on all chains (debug dump)
+    pub async fn get_active_orders_page(&self, limit: i64, offset: i64) -> DbResult<Vec<models::DbOrder>> {
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_active_orders_page(limit, offset)).await
     }
     
     /// Get active orders filtered by token (convenience method for API)
     pub async fn get_active_orders_by_token(&self, token_address: &str, limit: Option<i64>, chain_id: Option<i32>) -> DbResult<Vec<models::DbOrder>> {
-        let repo = orders::PostgresOrderRepository::new(self.pool.clone());
-        repo.get_active_orders_by_token(token_address, limit, chain_id).await
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_active_orders_by_token(token_address, limit, chain_id)).await
+    }
+    
+    /// Search active public orders with optional filters (chain, token, rate range)
+    pub async fn search_orders(&self, search: &orders::OrderSearch, limit: Option<i64>) -> DbResult<Vec<models::DbOrder>> {
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.search(search, limit)).await
     }
     
     /// Get single order by ID (convenience method for API)
     pub async fn get_order(&self, order_id: &str) -> DbResult<models::DbOrder> {
-        let repo = orders::PostgresOrderRepository::new(self.pool.clone());
-        repo.get(order_id).await
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get(order_id)).await
+    }
+    
+    /// Active liquidity per (token, visibility) for market stats
+    pub async fn market_liquidity(&self, chain_id: Option<i32>) -> DbResult<Vec<models::DbMarketLiquidityRow>> {
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.liquidity(chain_id)).await
     }
     
-    /// Get orders by seller (convenience method for API)
-    pub async fn get_orders_by_seller(&self, seller: &str) -> DbResult<Vec<models::DbOrder>> {
+    /// Get several orders in one query (unordered; IDs not found are left out)
+    pub async fn get_orders_by_ids(&self, order_ids: &[String]) -> DbResult<Vec<models::DbOrder>> {
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_many(order_ids)).await
+    }
+    
+    /// Get orders by seller, optionally only on one rail (convenience method for API)
+    pub async fn get_orders_by_seller(&self, seller: &str, rail: Option<crate::rail::PaymentRail>) -> DbResult<Vec<models::DbOrder>> {
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_by_seller(seller, rail)).await
+    }
+    
+    /// Delist open public orders idle since `cutoff` (unix seconds); returns them.
+    /// Not retried: a lost reply after commit would hide which orders went.
+    pub async fn delist_stale_orders(&self, cutoff: i64) -> DbResult<Vec<models::DbOrder>> {
         let repo = orders::PostgresOrderRepository::new(self.pool.clone());
-        repo.get_by_seller(seller).await
+        repo.delist_stale(cutoff).await
     }
     
     /// Get order by private code (for unlisted orders)
     pub async fn get_order_by_private_code(&self, private_code: &str) -> DbResult<models::DbOrder> {
         let repo = orders::PostgresOrderRepository::new(self.pool.clone());
-        repo.get_by_private_code(private_code).await
+        with_retry(|| repo.get_by_private_code(private_code)).await
     }
     
-    /// Set order visibility (public/private) and return private code if applicable
-    pub async fn set_order_visibility(&self, order_id: &str, is_public: bool) -> DbResult<Option<String>> {
+    /// Set order visibility (public/private): the private code if applicable (a new
+    /// code on every switch to private; the previous one stops resolving) and
+    /// whether the order was listed before
+    pub async fn set_order_visibility(&self, order_id: &str, is_public: bool) -> DbResult<orders::VisibilityChange> {
         let repo = orders::PostgresOrderRepository::new(self.pool.clone());
         repo.set_visibility(order_id, is_public).await
     }
     
+    /// Orders past the grace period that never had their order-created email
+    pub async fn get_orders_awaiting_created_email(&self, grace_secs: i64, limit: i64) -> DbResult<Vec<models::DbOrder>> {
+        let repo = orders::PostgresOrderRepository::new(self.pool.clone());
+        with_retry(|| repo.get_awaiting_created_email(grace_secs, limit)).await
+    }
+    
+    /// Claim an order's order-created email; false if it was already handled
+    pub async fn mark_order_created_email_sent(&self, order_id: &str) -> DbResult<bool> {
+        let repo = orders::PostgresOrderRepository::new(self.pool.clone());
+        repo.mark_created_email_sent(order_id).await
+    }
+    
     /// Get single trade by ID (convenience method for API)
     pub async fn get_trade(&self, trade_id: &str) -> DbResult<models::DbTrade> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.get(trade_id).await
+        with_retry(|| repo.get(trade_id)).await
     }
     
     /// Save PDF for a trade (convenience method for API)
-    pub async fn save_trade_pdf(&self, trade_id: &str, pdf_data: &[u8], filename: &str) -> DbResult<DateTime<Utc>> {
+    pub async fn save_trade_pdf(&self, trade_id: &str, pdf_data: &[u8], filename: &str, sha256: &str) -> DbResult<DateTime<Utc>> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.save_pdf(trade_id, pdf_data, filename, sha256)).await
+    }
+    
+    /// Record a trade PDF stored in the bucket (convenience method for API)
+    pub async fn save_trade_pdf_key(&self, trade_id: &str, storage_key: &str, filename: &str, sha256: &str) -> DbResult<DateTime<Utc>> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.save_pdf_key(trade_id, storage_key, filename, sha256)).await
+    }
+    
+    /// Pending trades expiring within `lead_secs` that haven't had an expiry reminder
+    pub async fn get_trades_near_expiry(&self, lead_secs: i64) -> DbResult<Vec<models::DbExpiringTrade>> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.get_trades_near_expiry(lead_secs)).await
+    }
+    
+    /// Trade counts and volume per (token, status) for GET /api/stats
+    pub async fn trade_stats(&self, chain_id: Option<i32>, from: Option<i64>, to: Option<i64>) -> DbResult<Vec<models::DbTradeStatsRow>> {
+        let repo = trades::PostgresTradeRepository::new(self.read_pool().clone());
+        with_retry(|| repo.stats(chain_id, from, to)).await
+    }
+    
+    /// A seller's orders, trades and settled volume per token (GET /api/sellers/:address/stats)
+    pub async fn seller_stats(&self, seller: &str) -> DbResult<Vec<models::DbSellerStatsRow>> {
+        let repo = orders::PostgresOrderRepository::new(self.read_pool().clone());
+        with_retry(|| repo.seller_stats(seller)).await
+    }
+    
+    /// Pending trades past expiry that auto-cancel hasn't cleared
+    pub async fn get_stuck_trades(&self, limit: i64) -> DbResult<Vec<models::DbStuckTrade>> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.get_stuck_trades(limit)).await
+    }
+    
+    /// Mark a trade's expiry reminder as sent; false if it already was
+    pub async fn mark_expiry_reminder_sent(&self, trade_id: &str) -> DbResult<bool> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.save_pdf(trade_id, pdf_data, filename).await
+        repo.mark_expiry_reminder_sent(trade_id).await
+    }
+    
+    /// Where a trade's receipt PDF is stored
+    pub async fn get_trade_pdf(&self, trade_id: &str) -> DbResult<models::DbTradePdf> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.get_pdf(trade_id)).await
     }
     
     /// Clear PDF for a trade when validation fails (allows retry)
     pub async fn clear_trade_pdf(&self, trade_id: &str) -> DbResult<()> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.clear_pdf(trade_id).await
+        with_retry(|| repo.clear_pdf(trade_id)).await
     }
     
     /// Save proof for a trade (convenience method for API)
@@ -145,43 +308,76 @@ impl Database {
     /// Update trade payment info (transaction_id and payment_time from PDF)
     pub async fn update_trade_payment_info(&self, trade_id: &str, transaction_id: &str, payment_time: &str) -> DbResult<()> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.update_payment_info(trade_id, transaction_id, payment_time).await
+        with_retry(|| repo.update_payment_info(trade_id, transaction_id, payment_time)).await
     }
     
-    /// Update order payment info (plain text accountId/accountName for v4 privacy)
-    pub async fn update_payment_info(&self, order_id: &str, account_id: &str, account_name: &str) -> DbResult<()> {
+    /// Overwrite an order's remaining amount with the on-chain value (drift repair),
+    /// unless it no longer holds `expected`; returns whether it was written
+    pub async fn resync_remaining_amount(&self, order_id: &str, expected: &str, remaining: &str) -> DbResult<bool> {
         let repo = orders::PostgresOrderRepository::new(self.pool.clone());
-        repo.update_payment_info(order_id, account_id, account_name).await
+        with_retry(|| repo.resync_remaining_amount(order_id, expected, remaining)).await
     }
     
-    /// Get all expired pending trades for auto-cancellation
-    pub async fn get_expired_pending_trades(&self) -> DbResult<Vec<models::DbTrade>> {
+    /// Set order payment info (plain text accountId/accountName for v4 privacy);
+    /// false when the order already has different info (nothing is written).
+    /// Idempotent for the same info, so a retry after a commit still reports success
+    pub async fn update_payment_info(&self, order_id: &str, account_id: &str, account_name: &str) -> DbResult<bool> {
+        let repo = orders::PostgresOrderRepository::new(self.pool.clone());
+        with_retry(|| repo.update_payment_info(order_id, account_id, account_name)).await
+    }
+    
+    /// Get pending trades expired more than `buffer_secs` ago, for auto-cancellation
+    pub async fn get_expired_pending_trades(&self, buffer_secs: u64) -> DbResult<Vec<models::DbTrade>> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.get_expired_pending_trades().await
+        with_retry(|| repo.get_expired_pending_trades(buffer_secs)).await
     }
     
     /// Update trade status (convenience method for auto-cancellation)
     pub async fn update_trade_status(&self, trade_id: &str, new_status: i32) -> DbResult<()> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.update_status(trade_id, new_status).await
+        with_retry(|| repo.update_status(trade_id, new_status)).await
+    }
+    
+    /// Flag a trade whose auto-cancellation keeps failing (convenience method for auto-cancellation)
+    pub async fn mark_trade_cancel_failed(&self, trade_id: &str, error: &str) -> DbResult<()> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.mark_cancel_failed(trade_id, error)).await
+    }
+    
+    /// List a buyer's or seller's trades (filtered + paginated), with the filtered total
+    pub async fn list_trades_for_party(&self, list: &trades::TradeListQuery) -> DbResult<(Vec<models::DbTrade>, i64)> {
+        let repo = trades::PostgresTradeRepository::new(self.read_pool().clone());
+        with_retry(|| repo.list_for_party(list)).await
     }
     
-    /// Get all trades (for debug purposes)
-    pub async fn get_all_trades(&self) -> DbResult<Vec<models::DbTrade>> {
+    /// One page of all trades, newest first (for debug purposes)
+    pub async fn get_all_trades(&self, limit: i64, offset: i64) -> DbResult<Vec<models::DbTrade>> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.get_all_trades().await
+        with_retry(|| repo.get_all_trades(limit, offset)).await
+    }
+    
+    /// Active public orders and trade counts on one chain (debug dump summary)
+    pub async fn chain_counts(&self, chain_id: i32) -> DbResult<models::DbChainCounts> {
+        let repo = trades::PostgresTradeRepository::new(self.read_pool().clone());
+        with_retry(|| repo.chain_counts(chain_id)).await
     }
     
     /// Check if transaction ID has been used in any settled trade (anti-replay)
     pub async fn is_transaction_id_used(&self, transaction_id: &str) -> DbResult<bool> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.is_transaction_id_used(transaction_id).await
+        with_retry(|| repo.is_transaction_id_used(transaction_id)).await
+    }
+    
+    /// Record the tx that settled a trade (before the event listener sees it)
+    pub async fn record_trade_settlement_tx(&self, trade_id: &str, tx_hash: &str) -> DbResult<()> {
+        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
+        with_retry(|| repo.update_settlement_tx(trade_id, tx_hash)).await
     }
     
     /// Save settlement error for a trade (when blockchain submission fails)
     pub async fn save_trade_settlement_error(&self, trade_id: &str, error_code: &str) -> DbResult<()> {
         let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.save_settlement_error(trade_id, error_code).await
+        with_retry(|| repo.save_settlement_error(trade_id, error_code)).await
     }
     
     // ===== Account Email Methods (account-based, not role-based) =====
@@ -189,31 +385,61 @@ impl Database {
     /// Get account email settings by wallet address
     pub async fn get_account_email(&self, wallet: &str) -> DbResult<Option<models::DbAccountEmail>> {
         let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
-        repo.get(wallet).await
+        with_retry(|| repo.get(wallet)).await
+    }
+    
+    /// Set or update account email (upsert; a new address is unverified until confirmed)
+    pub async fn upsert_account_email(&self, wallet: &str, email: &str, language: &str, verification_nonce: &str) -> DbResult<models::DbAccountEmail> {
+        let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
+        with_retry(|| repo.upsert(wallet, email, language, verification_nonce)).await
     }
     
-    /// Set or update account email (upsert)
-    pub async fn upsert_account_email(&self, wallet: &str, email: &str, language: &str) -> DbResult<models::DbAccountEmail> {
+    /// Verify an account's address with the nonce from its confirmation link (false if stale)
+    pub async fn confirm_account_email(&self, wallet: &str, verification_nonce: &str) -> DbResult<bool> {
         let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
-        repo.upsert(wallet, email, language).await
+        repo.confirm(wallet, verification_nonce).await
     }
     
     /// Enable or disable account notifications
     pub async fn set_account_email_enabled(&self, wallet: &str, enabled: bool) -> DbResult<()> {
         let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
-        repo.set_enabled(wallet, enabled).await
+        with_retry(|| repo.set_enabled(wallet, enabled)).await
+    }
+    
+    /// Opt an account in to or out of the daily digest
+    pub async fn set_account_digest_enabled(&self, wallet: &str, digest_enabled: bool) -> DbResult<()> {
+        let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
+        with_retry(|| repo.set_digest_enabled(wallet, digest_enabled)).await
+    }
+    
+    /// Digest subscribers not yet sent a digest for `day`
+    pub async fn get_digest_due_accounts(&self, day: chrono::NaiveDate) -> DbResult<Vec<models::DbAccountEmail>> {
+        let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
+        with_retry(|| repo.get_digest_due(day)).await
+    }
+    
+    /// Claim an account's digest for `day` (false if already sent)
+    pub async fn claim_account_digest(&self, wallet: &str, day: chrono::NaiveDate) -> DbResult<bool> {
+        let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
+        repo.claim_digest(wallet, day).await
+    }
+    
+    /// An account's trade activity during [from, to) (unix seconds)
+    pub async fn get_account_activity(&self, wallet: &str, from: i64, to: i64) -> DbResult<models::DbAccountActivity> {
+        let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
+        with_retry(|| repo.get_activity(wallet, from, to)).await
     }
     
     /// Delete account email (opt out)
     pub async fn delete_account_email(&self, wallet: &str) -> DbResult<()> {
         let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
-        repo.delete(wallet).await
+        with_retry(|| repo.delete(wallet)).await
     }
     
-    /// Get account email if notifications are enabled
+    /// Get account email if it receives notifications (enabled and verified)
     pub async fn get_account_email_if_enabled(&self, wallet: &str) -> DbResult<Option<models::DbAccountEmail>> {
         let repo = account_emails::AccountEmailRepository::new(self.pool.clone());
-        repo.get_if_enabled(wallet).await
+        with_retry(|| repo.get_if_enabled(wallet)).await
     }
     
     // ===== Gas Cost Methods (relay gas tracking) =====
@@ -224,41 +450,129 @@ impl Database {
         repo.create(gas_cost).await
     }
     
+    /// Record a batch transaction's gas cost, linked to each trade it covered
+    pub async fn record_batch_gas_cost(&self, gas_cost: &models::DbGasCost, trade_ids: &[String]) -> DbResult<()> {
+        let repo = gas_costs::GasCostRepository::new(self.pool.clone());
+        repo.create_for_trades(gas_cost, trade_ids).await
+    }
+    
+    /// Total relayer gas cost (wei) recorded on a chain since `since`
+    pub async fn get_gas_spent_since(&self, chain_id: i32, since: DateTime<Utc>) -> DbResult<u128> {
+        let repo = gas_costs::GasCostRepository::new(self.pool.clone());
+        with_retry(|| repo.total_cost_wei_since(chain_id, since)).await
+    }
+    
+    /// Recorded gas costs of one trade (oldest first)
+    pub async fn get_trade_gas_costs(&self, trade_id: &str) -> DbResult<Vec<models::DbGasCost>> {
+        let repo = gas_costs::GasCostRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_by_trade(trade_id)).await
+    }
+    
+    /// Gas cost summaries by operation for every chain
+    pub async fn get_gas_cost_summary_all_chains(&self) -> DbResult<Vec<(i32, Vec<gas_costs::GasCostSummary>)>> {
+        let repo = gas_costs::GasCostRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_summary_all_chains()).await
+    }
+    
     /// Get gas cost summary by chain and operation
     pub async fn get_gas_cost_summary(&self, chain_id: i32) -> DbResult<Vec<gas_costs::GasCostSummary>> {
+        let repo = gas_costs::GasCostRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_summary_by_chain(chain_id)).await
+    }
+    
+    /// Get recent gas cost rows still holding send-time estimates
+    pub async fn get_unreconciled_gas_costs(&self, max_age_days: i32, limit: i64) -> DbResult<Vec<models::DbGasCost>> {
         let repo = gas_costs::GasCostRepository::new(self.pool.clone());
-        repo.get_summary_by_chain(chain_id).await
+        with_retry(|| repo.get_unreconciled(max_age_days, limit)).await
     }
     
-    /// Get gas costs grouped by trade ID (for DB viewer)
-    pub async fn get_gas_costs_by_trades(&self) -> DbResult<Vec<gas_costs::TradeGasCost>> {
+    /// Save receipt-corrected gas values for a row
+    pub async fn save_reconciled_gas_cost(&self, gas_cost: &models::DbGasCost) -> DbResult<()> {
         let repo = gas_costs::GasCostRepository::new(self.pool.clone());
-        repo.get_costs_by_trade().await
+        with_retry(|| repo.save_reconciled(gas_cost)).await
+    }
+    
+    /// Get gas costs of `trade_ids` grouped by trade ID (for DB viewer)
+    pub async fn get_gas_costs_by_trades(&self, trade_ids: &[String]) -> DbResult<Vec<gas_costs::TradeGasCost>> {
+        let repo = gas_costs::GasCostRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_costs_by_trade(trade_ids)).await
     }
     
     // ===== Withdrawal Methods (order activity timeline) =====
     
     /// Create a withdrawal record
-    pub async fn create_withdrawal(&self, order_id: &str, amount: &str, remaining_after: &str, tx_hash: Option<&str>) -> DbResult<()> {
+    pub async fn create_withdrawal(&self, withdrawal: &crate::blockchain::types::WithdrawalLog) -> DbResult<()> {
         let repo = withdrawals::PostgresWithdrawalRepository::new(self.pool.clone());
-        repo.create(order_id, amount, remaining_after, tx_hash).await
+        repo.create(withdrawal).await
+    }
+    
+    /// Withdrawals on `chain_id` still missing their transaction hash (oldest first)
+    pub async fn get_withdrawals_missing_tx_hash(&self, chain_id: i32) -> DbResult<Vec<models::DbWithdrawal>> {
+        let repo = withdrawals::PostgresWithdrawalRepository::new(self.pool.clone());
+        with_retry(|| repo.get_missing_tx_hash(chain_id)).await
+    }
+    
+    /// Fill in a withdrawal's transaction hash and block (see PostgresWithdrawalRepository::set_tx_hash)
+    pub async fn set_withdrawal_tx_hash(&self, id: i32, tx_hash: &str, block_number: u64) -> DbResult<bool> {
+        let repo = withdrawals::PostgresWithdrawalRepository::new(self.pool.clone());
+        repo.set_tx_hash(id, tx_hash, block_number).await
+    }
+    
+    /// One page of a seller's withdrawals across all their orders, plus the total
+    pub async fn get_withdrawals_by_seller(&self, seller: &str, limit: i64, offset: i64) -> DbResult<(Vec<models::DbSellerWithdrawal>, i64)> {
+        let repo = withdrawals::PostgresWithdrawalRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_by_seller(seller, limit, offset)).await
     }
     
     /// Get all withdrawals for an order
     pub async fn get_withdrawals_by_order(&self, order_id: &str) -> DbResult<Vec<models::DbWithdrawal>> {
-        let repo = withdrawals::PostgresWithdrawalRepository::new(self.pool.clone());
-        repo.get_by_order(order_id).await
+        let repo = withdrawals::PostgresWithdrawalRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_by_order(order_id)).await
     }
     
     /// Get all settled trades for an order (for activity timeline)
     pub async fn get_settled_trades_by_order(&self, order_id: &str) -> DbResult<Vec<models::DbTrade>> {
-        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.get_settled_by_order(order_id).await
+        let repo = trades::PostgresTradeRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_settled_by_order(order_id)).await
     }
     
     /// Get all trades for an order (including pending, for activity timeline)
     pub async fn get_all_trades_by_order(&self, order_id: &str) -> DbResult<Vec<models::DbTrade>> {
-        let repo = trades::PostgresTradeRepository::new(self.pool.clone());
-        repo.get_all_by_order(order_id).await
+        let repo = trades::PostgresTradeRepository::new(self.read_pool().clone());
+        with_retry(|| repo.get_all_by_order(order_id)).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lazy_pool(host: &str) -> PgPool {
+        PgPoolOptions::new().connect_lazy(&format!("postgres://lyncz@{}:5432/lyncz", host)).unwrap()
+    }
+
+    fn host(pool: &PgPool) -> String {
+        pool.connect_options().get_host().to_string()
+    }
+
+    #[tokio::test]
+    async fn test_reads_routed_to_replica_when_configured() {
+        let db = Database { pool: lazy_pool("primary"), replica: Some(lazy_pool("replica")) };
+        assert_eq!(host(db.read_pool()), "replica");
+        assert_eq!(host(db.pool()), "primary");
+
+        // Read-your-writes view never touches the replica
+        assert_eq!(host(db.primary().read_pool()), "primary");
+
+        let db = Database { pool: lazy_pool("primary"), replica: None };
+        assert_eq!(host(db.read_pool()), "primary");
+    }
+
+    #[tokio::test]
+    async fn test_close_closes_the_replica_too() {
+        let db = Database { pool: lazy_pool("primary"), replica: Some(lazy_pool("replica")) };
+        db.close().await;
+        assert!(db.pool().is_closed());
+        assert!(db.read_pool().is_closed());
     }
 }
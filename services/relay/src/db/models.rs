@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use sqlx::FromRow;
 
+use crate::rail::PaymentRail;
+
 /// Database model for Withdrawal - tracks withdrawal history for order activity timeline
 #[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
 pub struct DbWithdrawal {
@@ -24,12 +26,147 @@ pub struct DbAccountEmail {
     pub email: String,                      // Email address
     pub language: String,                   // Language: 'en', 'zh-CN', 'zh-TW'
     pub enabled: bool,                      // Whether notifications are enabled
+    pub digest_enabled: bool,               // Opted in to the daily activity digest
+    pub verified: bool,                     // Owner confirmed the address via the emailed link
     #[sqlx(rename = "createdAt")]
     pub created_at: i64,                    // Unix timestamp
     #[sqlx(rename = "updatedAt")]
     pub updated_at: i64,                    // Unix timestamp
 }
 
+impl DbAccountEmail {
+    /// Notifications only go to enabled accounts whose address was confirmed
+    pub fn receives_notifications(&self) -> bool {
+        self.enabled && self.verified
+    }
+}
+
+/// An account's trade activity over a time window (daily digest)
+#[derive(Debug, Clone, Default, PartialEq, Eq, FromRow)]
+pub struct DbAccountActivity {
+    pub new_trades: i64,                    // Trades created in the window
+    pub settled_trades: i64,                // Trades settled in the window
+    pub expired_trades: i64,                // Trades that expired in the window
+}
+
+/// Database model for a queued notification email (retried by the outbox worker)
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbEmailOutboxEntry {
+    pub id: i64,
+    pub to_email: String,
+    pub subject: String,
+    pub html: String,
+    pub unsubscribe_url: Option<String>,    // Sent as the List-Unsubscribe header
+    pub event: String,                      // EmailEvent name
+    pub status: String,                     // 'pending', 'delivered' or 'failed'
+    pub attempts: i32,                      // Send attempts so far (including the original)
+    pub last_error: Option<String>,
+    pub next_attempt_at: DateTime<Utc>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Database model for a queued proof generation + settlement job
+#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+pub struct DbProofJob {
+    pub id: i64,
+    pub trade_id: String,
+    pub transaction_id: String,             // Alipay transaction ID from the receipt
+    pub payment_time: String,               // Payment timestamp from the receipt
+    pub status: String,                     // 'queued', 'running', 'done' or 'failed'
+    pub attempts: i32,                      // Times a worker picked the job up
+    pub settlement_tx_hash: Option<String>, // Set when done
+    pub error: Option<String>,              // Set when failed
+    pub created_at: DateTime<Utc>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+}
+
+/// Receipt PDF location for a trade (GET /api/trades/:id/pdf)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbTradePdf {
+    pub pdf_storage_key: Option<String>,    // Object key when stored in the PDF bucket
+    pub pdf_file: Option<Vec<u8>>,          // Inline bytes (no bucket configured)
+    pub pdf_filename: Option<String>,
+    pub pdf_sha256: Option<String>,         // Content hash (None for uploads before migration 020)
+}
+
+/// Pending trade close to expiry, with its order's token (expiry reminder job)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbExpiringTrade {
+    pub trade_id: String,
+    pub order_id: String,
+    pub buyer: String,
+    pub token_amount: String,               // uint256 as decimal string
+    pub cny_amount: String,                 // fiat minor units as decimal string
+    pub currency: String,
+    pub expires_at: i64,                    // unix timestamp
+    pub token: String,                      // ERC20 address (from orders)
+}
+
+/// Trade count and volume for one (token, status) group (GET /api/stats)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbTradeStatsRow {
+    pub token: String,                      // ERC20 address (from orders)
+    pub status: i32,                        // 0=PENDING, 1=SETTLED, 2=EXPIRED
+    pub trade_count: i64,
+    pub token_volume: String,               // SUM(tokenAmount) as decimal string
+}
+
+/// Order and trade counts for one chain (debug dump summary)
+#[derive(Debug, Clone, Default, FromRow)]
+pub struct DbChainCounts {
+    pub orders: i64,                        // Active public orders
+    pub trades: i64,
+    pub trades_pending: i64,
+    pub trades_settled: i64,
+}
+
+/// Active orders and remaining amount for one (token, visibility) group (market stats)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbMarketLiquidityRow {
+    pub token: String,                      // ERC20 address
+    pub is_public: bool,
+    pub order_count: i64,
+    pub remaining_amount: String,           // SUM(remainingAmount) as decimal string
+}
+
+/// One seller's orders and trades in one token (GET /api/sellers/:address/stats)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbSellerStatsRow {
+    pub token: String,                      // ERC20 address
+    pub order_count: i64,
+    pub settled_count: i64,
+    pub expired_count: i64,
+    pub pending_count: i64,
+    pub settled_volume: String,             // SUM(tokenAmount) of settled trades as decimal string
+    pub first_activity_at: i64,             // Earliest order/trade createdAt (unix timestamp)
+    pub last_activity_at: i64,              // Latest order/trade createdAt (unix timestamp)
+}
+
+/// A withdrawal joined to its order (GET /api/sellers/:address/withdrawals)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbSellerWithdrawal {
+    pub order_id: String,                   // bytes32 reference to order
+    pub token: String,                      // ERC20 address of the order
+    pub chain_id: i32,
+    pub amount: String,                     // uint256 withdrawn amount
+    pub remaining_after: String,            // uint256 remaining after withdrawal
+    pub tx_hash: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Pending trade past its expiry (GET /api/admin/stuck-trades)
+#[derive(Debug, Clone, FromRow)]
+pub struct DbStuckTrade {
+    pub trade_id: String,
+    pub order_id: String,
+    pub chain_id: i32,
+    pub buyer: String,
+    pub expires_at: i64,                    // unix timestamp
+    pub cancel_failed_at: Option<DateTime<Utc>>, // auto-cancel gave up (manual review)
+    pub cancel_error: Option<String>,
+}
+
 /// Database model for Order - EXACTLY matches on-chain Order struct
 /// Plus convenience field: syncedAt
 /// NOTE: Orders never expire - they remain active until seller withdraws all funds.
@@ -47,7 +184,7 @@ pub struct DbOrder {
     pub remaining_amount: String,           // uint256 as decimal string (determines if order is active)
     #[sqlx(rename = "exchangeRate")]
     pub exchange_rate: String,              // uint256 (CNY cents per token)
-    pub rail: i32,                          // PaymentRail: 0=ALIPAY, 1=WECHAT
+    pub rail: PaymentRail,                  // 0=ALIPAY, 1=WECHAT
     #[sqlx(rename = "accountId")]
     #[serde(rename = "account_id")]
     pub alipay_id: String,                  // Payment account ID (e.g., Alipay ID)
@@ -61,6 +198,9 @@ pub struct DbOrder {
     #[sqlx(rename = "chainId")]
     pub chain_id: i32,                      // Chain ID: 8453=Base, 1=Ethereum
     
+    // Fiat currency of exchangeRate (NOT on-chain, defaults to CNY)
+    pub currency: String,                   // ISO 4217 code, see crate::currency::Currency
+    
     // Additional fields for convenience (NOT on-chain)
     #[sqlx(rename = "syncedAt")]
     pub synced_at: DateTime<Utc>,           // When record was synced to DB
@@ -69,12 +209,12 @@ pub struct DbOrder {
     #[sqlx(rename = "isPublic")]
     pub is_public: bool,                    // Whether order appears in public listings
     #[sqlx(rename = "privateCode")]
-    pub private_code: Option<String>,       // 6-digit code for unlisted orders
+    pub private_code: Option<String>,       // Unguessable code for unlisted orders (crypto::private_code)
 }
 
 /// Database model for Trade - EXACTLY matches on-chain Trade struct
 /// Plus convenience fields: syncedAt, escrowTxHash, settlementTxHash, PDF storage, Axiom proof data
-#[derive(Debug, Clone, FromRow, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, FromRow, Serialize, Deserialize, utoipa::ToSchema)]
 pub struct DbTrade {
     // On-chain fields (EXACT match with ZkAliPayEscrow.sol Trade struct)
     #[sqlx(rename = "tradeId")]
@@ -88,7 +228,11 @@ pub struct DbTrade {
     pub cny_amount: String,                 // uint256 (CNY in cents)
     #[sqlx(rename = "feeAmount")]
     pub fee_amount: Option<String>,         // uint256 fee from TradeCreated event (actual blockchain fee)
-    pub rail: i32,                          // PaymentRail: 0=ALIPAY, 1=WECHAT (denormalized from order)
+    #[sqlx(rename = "feeRateBps")]
+    #[sqlx(default)]
+    pub fee_rate_bps: Option<i32>,          // Fee rate at trade creation (only used when fee_amount is missing)
+    #[schema(value_type = i32)]
+    pub rail: PaymentRail,                  // 0=ALIPAY, 1=WECHAT (denormalized from order)
     #[sqlx(rename = "transactionId")]
     pub transaction_id: Option<String>,     // Alipay transaction ID (anti-replay)
     #[sqlx(rename = "paymentTime")]
@@ -119,6 +263,11 @@ pub struct DbTrade {
     #[serde(rename = "account_name")]
     pub alipay_name: Option<String>,        // Seller's account name (from order)
     
+    // Order state (joined from orders table): true once the order is fully
+    // filled/withdrawn or its row is gone; None when the query didn't join orders
+    #[sqlx(default)]
+    pub order_closed: Option<bool>,
+    
     // PDF storage fields
     #[serde(skip_serializing)]              // Don't send binary data in JSON
     #[sqlx(rename = "pdf_file")]
@@ -151,6 +300,9 @@ pub struct DbTrade {
     #[sqlx(rename = "chainId")]
     #[sqlx(default)]
     pub chain_id: i32,                       // Chain ID: 8453=Base, 1=Ethereum
+    
+    // Fiat currency of cnyAmount (copied from the order, defaults to CNY)
+    pub currency: String,                    // ISO 4217 code, see crate::currency::Currency
 }
 
 /// Database model for Gas Cost tracking - relay wallet gas expenditure per chain
@@ -176,4 +328,89 @@ pub struct DbGasCost {
     pub cost_eth: String,                    // Total cost in ETH
     #[sqlx(rename = "createdAt")]
     pub created_at: DateTime<Utc>,           // When gas cost was recorded
+    #[sqlx(rename = "reconciledAt")]
+    #[sqlx(default)]
+    pub reconciled_at: Option<DateTime<Utc>>, // When corrected against the on-chain receipt (None = estimate)
+    #[sqlx(rename = "gasEstimate")]
+    #[sqlx(default)]
+    pub gas_estimate: Option<i64>,           // eth_estimateGas before sending (None = not estimated)
+}
+
+/// Rows and chain config for unit tests; adjust fields with struct update syntax.
+/// Also built for the binaries' tests with `cargo test --features test-fixtures`.
+#[cfg(any(test, feature = "test-fixtures"))]
+pub mod fixtures {
+    use super::{DbOrder, DbTrade};
+    use crate::config::ChainConfig;
+    use crate::rail::PaymentRail;
+    use chrono::Utc;
+
+    /// 1 USDC for ¥7.20 on Base, created at 1_700_000_000 with a 15 minute window
+    pub fn trade(trade_id: &str, status: i32) -> DbTrade {
+        DbTrade {
+            trade_id: trade_id.to_string(),
+            order_id: "0xorder".to_string(),
+            buyer: "0xbuyer".to_string(),
+            token_amount: "1000000".to_string(),
+            cny_amount: "720".to_string(),
+            fee_amount: None,
+            fee_rate_bps: None,
+            rail: PaymentRail::Alipay,
+            transaction_id: None,
+            payment_time: None,
+            created_at: 1_700_000_000,
+            expires_at: 1_700_000_900,
+            status,
+            synced_at: Utc::now(),
+            escrow_tx_hash: None,
+            settlement_tx_hash: None,
+            token: None,
+            alipay_id: None,
+            alipay_name: None,
+            order_closed: None,
+            pdf_file: None,
+            pdf_filename: None,
+            pdf_uploaded_at: None,
+            proof_user_public_values: None,
+            proof_accumulator: None,
+            proof_data: None,
+            axiom_proof_id: None,
+            proof_generated_at: None,
+            proof_json: None,
+            settlement_error: None,
+            chain_id: 8453,
+            currency: "CNY".to_string(),
+        }
+    }
+
+    /// Public, untouched 1 USDC Alipay order on Base at ¥7.20 per USDC
+    pub fn order(order_id: &str) -> DbOrder {
+        DbOrder {
+            order_id: order_id.to_string(),
+            seller: "0xseller".to_string(),
+            token: "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913".to_string(),
+            total_amount: "1000000".to_string(),
+            remaining_amount: "1000000".to_string(),
+            exchange_rate: "720".to_string(),
+            rail: PaymentRail::Alipay,
+            alipay_id: "acct".to_string(),
+            alipay_name: "Name".to_string(),
+            created_at: 0,
+            chain_id: 8453,
+            currency: "CNY".to_string(),
+            synced_at: Utc::now(),
+            is_public: true,
+            private_code: None,
+        }
+    }
+
+    pub fn chain(name: &str, chain_id: u64) -> ChainConfig {
+        ChainConfig {
+            chain_id,
+            rpc_url: "https://rpc.example".to_string(),
+            escrow_address: "0x0000000000000000000000000000000000000001".to_string(),
+            name: name.to_string(),
+            confirmations: 2,
+        }
+    }
 }
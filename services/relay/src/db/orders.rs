@@ -1,11 +1,12 @@
 use async_trait::async_trait;
-use sqlx::PgPool;
+use ethers::types::U256;
+use sqlx::{PgExecutor, PgPool, Postgres, QueryBuilder};
 use rust_decimal::Decimal;
 use std::str::FromStr;
-use rand::Rng;
 
 use super::{DbError, DbResult};
-use super::models::DbOrder;
+use super::models::{DbMarketLiquidityRow, DbOrder, DbSellerStatsRow};
+use crate::rail::PaymentRail;
 
 /// Repository for Order operations - ONLY methods needed for event sync
 #[async_trait]
@@ -20,6 +21,161 @@ pub trait OrderRepository: Send + Sync {
     async fn adjust_remaining_amount(&self, order_id: &str, delta: &str) -> DbResult<()>;
 }
 
+/// Codes tried before giving up on a collision-free private code
+const PRIVATE_CODE_ATTEMPTS: usize = 10;
+
+/// Fresh private code (see crypto::private_code), never equal to `previous`
+fn new_private_code(previous: Option<&str>) -> String {
+    loop {
+        let code = crate::crypto::generate_private_code();
+        if previous != Some(code.as_str()) {
+            return code;
+        }
+    }
+}
+
+/// Outcome of `set_visibility`
+#[derive(Debug, Clone, PartialEq)]
+pub struct VisibilityChange {
+    /// The order was in the public book (public, not delisted) before the change
+    pub was_listed: bool,
+    /// This is the first time the order's visibility was chosen (it just became ready)
+    pub first_choice: bool,
+    /// The order's new private code (None once public)
+    pub private_code: Option<String>,
+}
+
+/// Filters for searching the active PUBLIC order book (all optional, combined with AND)
+#[derive(Debug, Clone, Default)]
+pub struct OrderSearch {
+    pub chain_id: Option<i32>,
+    /// Token address (case-insensitive)
+    pub token: Option<String>,
+    /// Any of these token addresses (case-insensitive); empty = any token
+    pub tokens: Vec<String>,
+    /// Inclusive exchange rate bounds (same unit as "exchangeRate": CNY cents per token)
+    pub min_rate: Option<Decimal>,
+    pub max_rate: Option<Decimal>,
+    /// Inclusive remaining amount bounds, in the token's base units (e.g. 100 USDC = 100000000);
+    /// uint256 like the column, compared as NUMERIC(78,0)
+    pub min_amount: Option<U256>,
+    pub max_amount: Option<U256>,
+    pub rail: Option<PaymentRail>,
+    /// Only orders whose seller has submitted payment info (accountId and accountName set)
+    pub require_payment_info: bool,
+    /// Only orders on these chains (the relay's configured ones); None = any chain
+    pub chain_ids: Option<Vec<i32>>,
+}
+
+impl OrderSearch {
+    /// Reject contradictory bounds
+    pub fn validate(&self) -> DbResult<()> {
+        if let (Some(min), Some(max)) = (self.min_rate, self.max_rate) {
+            if min > max {
+                return Err(DbError::InvalidInput(format!("min_rate ({}) must be <= max_rate ({})", min, max)));
+            }
+        }
+        if let (Some(min), Some(max)) = (self.min_amount, self.max_amount) {
+            if min > max {
+                return Err(DbError::InvalidInput(format!("min_amount ({}) must be <= max_amount ({})", min, max)));
+            }
+        }
+        Ok(())
+    }
+    
+    /// Build the SELECT for active public orders matching these filters
+    fn build_query(&self, limit: i64) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new(
+            r#"
+            SELECT 
+                "orderId", seller, token,
+                "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
+                rail, "accountId", "accountName", "createdAt", "syncedAt",
+                "isPublic", "privateCode", "chainId", currency
+            FROM orders
+            WHERE closed_at IS NULL AND delisted_at IS NULL AND "remainingAmount" > 0 AND "isPublic" = true"#,
+        );
+        
+        if let Some(chain_id) = self.chain_id {
+            query.push(r#" AND "chainId" = "#).push_bind(chain_id);
+        }
+        if let Some(token) = &self.token {
+            query.push(" AND LOWER(token) = ").push_bind(token.to_lowercase());
+        }
+        if !self.tokens.is_empty() {
+            query.push(" AND LOWER(token) IN (");
+            let mut separated = query.separated(", ");
+            for token in &self.tokens {
+                separated.push_bind(token.to_lowercase());
+            }
+            query.push(")");
+        }
+        if let Some(rail) = self.rail {
+            query.push(" AND rail = ").push_bind(rail);
+        }
+        if self.require_payment_info {
+            query.push(r#" AND "accountId" <> '' AND "accountName" <> ''"#);
+        }
+        if let Some(chain_ids) = &self.chain_ids {
+            query.push(r#" AND "chainId" = ANY("#).push_bind(chain_ids.clone()).push(")");
+        }
+        if let Some(min_rate) = self.min_rate {
+            query.push(r#" AND CAST("exchangeRate" AS NUMERIC) >= "#).push_bind(min_rate);
+        }
+        if let Some(max_rate) = self.max_rate {
+            query.push(r#" AND CAST("exchangeRate" AS NUMERIC) <= "#).push_bind(max_rate);
+        }
+        if let Some(min_amount) = self.min_amount {
+            query.push(r#" AND "remainingAmount" >= CAST("#).push_bind(min_amount.to_string()).push(" AS NUMERIC(78,0))");
+        }
+        if let Some(max_amount) = self.max_amount {
+            query.push(r#" AND "remainingAmount" <= CAST("#).push_bind(max_amount.to_string()).push(" AS NUMERIC(78,0))");
+        }
+        
+        query.push(r#" ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC LIMIT "#);
+        query.push_bind(limit);
+        query
+    }
+}
+
+/// SET clause keeping closed_at in step with the remaining amount being written
+/// (`new_remaining` is that amount as SQL): stamped when it reaches zero, kept
+/// if already closed, cleared when an expired trade reopens the order
+fn closed_at_assignment(new_remaining: &str) -> String {
+    format!("closed_at = CASE WHEN {} <= 0 THEN COALESCE(closed_at, NOW()) ELSE NULL END", new_remaining)
+}
+
+/// UPDATE delisting open public orders with no activity since `cutoff` (unix
+/// seconds): created before it, and no trade or withdrawal after it. Returns
+/// the delisted orders.
+fn build_delist_query(cutoff: i64) -> QueryBuilder<'static, Postgres> {
+    let mut query = QueryBuilder::new(
+        r#"
+        UPDATE orders o
+        SET delisted_at = NOW()
+        WHERE o.delisted_at IS NULL AND o.closed_at IS NULL AND o."remainingAmount" > 0 AND o."isPublic" = true
+          AND o."createdAt" < "#,
+    );
+    query.push_bind(cutoff);
+    query.push(r#"
+          AND NOT EXISTS (
+              SELECT 1 FROM trades t WHERE t."orderId" = o."orderId" AND t."createdAt" >= "#);
+    query.push_bind(cutoff);
+    query.push(r#"
+          )
+          AND NOT EXISTS (
+              SELECT 1 FROM withdrawals w WHERE w."orderId" = o."orderId" AND w."createdAt" >= to_timestamp("#);
+    query.push_bind(cutoff);
+    query.push(r#")
+          )
+        RETURNING
+            "orderId", seller, token,
+            "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
+            rail, "accountId", "accountName", "createdAt", "syncedAt",
+            "isPublic", "privateCode", "chainId", currency"#);
+    query
+}
+
 pub struct PostgresOrderRepository {
     pool: PgPool,
 }
@@ -29,7 +185,7 @@ impl PostgresOrderRepository {
         Self { pool }
     }
     
-    /// Get all active PUBLIC orders (remainingAmount > 0, is_public = true) sorted by exchange rate
+    /// Get all active PUBLIC orders (not closed, is_public = true) sorted by exchange rate
     /// Used by API for matching and order list queries
     /// Optionally filtered by chain_id (None = all chains)
     pub async fn get_active_orders(&self, limit: Option<i64>, chain_id: Option<i32>) -> DbResult<Vec<DbOrder>> {
@@ -42,9 +198,9 @@ impl PostgresOrderRepository {
                     "orderId", seller, token,
                     "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                     rail, "accountId", "accountName", "createdAt", "syncedAt",
-                    "isPublic", "privateCode", "chainId"
+                    "isPublic", "privateCode", "chainId", currency
                 FROM orders
-                WHERE "remainingAmount" > 0 AND "isPublic" = true AND "chainId" = $1
+                WHERE closed_at IS NULL AND delisted_at IS NULL AND "remainingAmount" > 0 AND "isPublic" = true AND "chainId" = $1
                 ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC
                 LIMIT $2
                 "#
@@ -60,9 +216,9 @@ impl PostgresOrderRepository {
                     "orderId", seller, token,
                     "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                     rail, "accountId", "accountName", "createdAt", "syncedAt",
-                    "isPublic", "privateCode", "chainId"
+                    "isPublic", "privateCode", "chainId", currency
                 FROM orders
-                WHERE "remainingAmount" > 0 AND "isPublic" = true
+                WHERE closed_at IS NULL AND delisted_at IS NULL AND "remainingAmount" > 0 AND "isPublic" = true
                 ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC
                 LIMIT $1
                 "#
@@ -72,25 +228,57 @@ impl PostgresOrderRepository {
             .await?
         };
         
-        let orders: Vec<DbOrder> = rows
-            .into_iter()
-            .map(|row| Self::map_row_to_order(row))
-            .collect();
+        rows.into_iter().map(Self::map_row_to_order).collect()
+    }
+    
+    /// One page of active PUBLIC orders on all chains, same order as get_active_orders (debug dump)
+    pub async fn get_active_orders_page(&self, limit: i64, offset: i64) -> DbResult<Vec<DbOrder>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 
+                "orderId", seller, token,
+                "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
+                rail, "accountId", "accountName", "createdAt", "syncedAt",
+                "isPublic", "privateCode", "chainId", currency
+            FROM orders
+            WHERE closed_at IS NULL AND delisted_at IS NULL AND "remainingAmount" > 0 AND "isPublic" = true
+            ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC, "orderId"
+            LIMIT $1 OFFSET $2
+            "#
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        rows.into_iter().map(Self::map_row_to_order).collect()
+    }
+    
+    /// Search active PUBLIC orders with optional filters (chain, tokens, rate and amount ranges)
+    /// Sorted by exchange rate (best first), then creation time
+    pub async fn search(&self, search: &OrderSearch, limit: Option<i64>) -> DbResult<Vec<DbOrder>> {
+        search.validate()?;
         
-        Ok(orders)
+        let rows = search
+            .build_query(limit.unwrap_or(100))
+            .build()
+            .fetch_all(&self.pool)
+            .await?;
+        
+        rows.into_iter().map(Self::map_row_to_order).collect()
     }
     
-    /// Helper function to map a row to DbOrder
-    fn map_row_to_order(row: sqlx::postgres::PgRow) -> DbOrder {
+    /// Helper function to map a row to DbOrder (fails on an unknown rail)
+    fn map_row_to_order(row: sqlx::postgres::PgRow) -> DbResult<DbOrder> {
         use sqlx::Row;
-        DbOrder {
+        Ok(DbOrder {
             order_id: row.get("orderId"),
             seller: row.get("seller"),
             token: row.get("token"),
             total_amount: row.get::<Option<String>, _>("totalAmount").unwrap_or_default(),
             remaining_amount: row.get::<Option<String>, _>("remainingAmount").unwrap_or_default(),
             exchange_rate: row.get::<Option<String>, _>("exchangeRate").unwrap_or_default(),
-            rail: row.get("rail"),
+            rail: row.try_get("rail")?,
             alipay_id: row.get("accountId"),
             alipay_name: row.get("accountName"),
             created_at: row.get("createdAt"),
@@ -98,7 +286,8 @@ impl PostgresOrderRepository {
             synced_at: row.get("syncedAt"),
             is_public: row.get("isPublic"),
             private_code: row.get("privateCode"),
-        }
+            currency: row.get("currency"),
+        })
     }
     
     /// Get active PUBLIC orders filtered by token address (case-insensitive)
@@ -115,9 +304,9 @@ impl PostgresOrderRepository {
                     "orderId", seller, token,
                     "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                     rail, "accountId", "accountName", "createdAt", "syncedAt",
-                    "isPublic", "privateCode", "chainId"
+                    "isPublic", "privateCode", "chainId", currency
                 FROM orders
-                WHERE "remainingAmount" > 0 AND "isPublic" = true
+                WHERE closed_at IS NULL AND delisted_at IS NULL AND "remainingAmount" > 0 AND "isPublic" = true
                 AND LOWER(token) = $1 AND "chainId" = $2
                 ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC
                 LIMIT $3
@@ -135,9 +324,9 @@ impl PostgresOrderRepository {
                     "orderId", seller, token,
                     "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                     rail, "accountId", "accountName", "createdAt", "syncedAt",
-                    "isPublic", "privateCode", "chainId"
+                    "isPublic", "privateCode", "chainId", currency
                 FROM orders
-                WHERE "remainingAmount" > 0 AND "isPublic" = true
+                WHERE closed_at IS NULL AND delisted_at IS NULL AND "remainingAmount" > 0 AND "isPublic" = true
                 AND LOWER(token) = $1
                 ORDER BY CAST("exchangeRate" AS NUMERIC) ASC, "createdAt" ASC
                 LIMIT $2
@@ -149,12 +338,7 @@ impl PostgresOrderRepository {
             .await?
         };
         
-        let orders: Vec<DbOrder> = rows
-            .into_iter()
-            .map(|row| Self::map_row_to_order(row))
-            .collect();
-        
-        Ok(orders)
+        rows.into_iter().map(Self::map_row_to_order).collect()
     }
     
     /// Get single order by ID
@@ -165,7 +349,7 @@ impl PostgresOrderRepository {
                 "orderId", seller, token,
                 "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                 rail, "accountId", "accountName", "createdAt", "syncedAt",
-                "isPublic", "privateCode", "chainId"
+                "isPublic", "privateCode", "chainId", currency
             FROM orders
             WHERE "orderId" = $1
             "#,
@@ -175,7 +359,50 @@ impl PostgresOrderRepository {
         .await?
         .ok_or_else(|| DbError::OrderNotFound(order_id.to_string()))?;
         
-        Ok(Self::map_row_to_order(row))
+        Self::map_row_to_order(row)
+    }
+    
+    /// Active order count and remaining amount per (token, isPublic).
+    /// Private orders are included; callers decide whether to count them.
+    pub async fn liquidity(&self, chain_id: Option<i32>) -> DbResult<Vec<DbMarketLiquidityRow>> {
+        let rows = sqlx::query_as::<_, DbMarketLiquidityRow>(
+            r#"
+            SELECT
+                token, "isPublic" AS is_public,
+                COUNT(*) AS order_count,
+                SUM("remainingAmount")::text AS remaining_amount
+            FROM orders
+            WHERE "remainingAmount" > 0
+              AND ($1::int IS NULL OR "chainId" = $1)
+            GROUP BY token, "isPublic"
+            ORDER BY token, "isPublic"
+            "#,
+        )
+        .bind(chain_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Orders with any of the given IDs, in no particular order (missing IDs are skipped)
+    pub async fn get_many(&self, order_ids: &[String]) -> DbResult<Vec<DbOrder>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 
+                "orderId", seller, token,
+                "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
+                rail, "accountId", "accountName", "createdAt", "syncedAt",
+                "isPublic", "privateCode", "chainId", currency
+            FROM orders
+            WHERE "orderId" = ANY($1)
+            "#,
+        )
+        .bind(order_ids)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        rows.into_iter().map(Self::map_row_to_order).collect()
     }
     
     /// Get single order by private code
@@ -186,7 +413,7 @@ impl PostgresOrderRepository {
                 "orderId", seller, token,
                 "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                 rail, "accountId", "accountName", "createdAt", "syncedAt",
-                "isPublic", "privateCode", "chainId"
+                "isPublic", "privateCode", "chainId", currency
             FROM orders
             WHERE "privateCode" = $1
             "#,
@@ -196,75 +423,132 @@ impl PostgresOrderRepository {
         .await?
         .ok_or_else(|| DbError::OrderNotFound(format!("private_code:{}", private_code)))?;
         
-        Ok(Self::map_row_to_order(row))
+        Self::map_row_to_order(row)
     }
     
-    /// Set order visibility (public/private) and generate private code if needed
-    pub async fn set_visibility(&self, order_id: &str, is_public: bool) -> DbResult<Option<String>> {
-        // Generate a 6-digit code if making private
-        let private_code = if !is_public {
-            Some(Self::generate_unique_code(&self.pool).await?)
-        } else {
-            None
-        };
+    /// Active orders created more than `grace_secs` ago whose order-created email
+    /// was never handled (visibility endpoint not called)
+    pub async fn get_awaiting_created_email(&self, grace_secs: i64, limit: i64) -> DbResult<Vec<DbOrder>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT 
+                "orderId", seller, token,
+                "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
+                rail, "accountId", "accountName", "createdAt", "syncedAt",
+                "isPublic", "privateCode", "chainId", currency
+            FROM orders
+            WHERE created_email_sent_at IS NULL
+              AND "remainingAmount" > 0
+              AND "createdAt" <= EXTRACT(EPOCH FROM NOW())::bigint - $1
+            ORDER BY "createdAt" ASC
+            LIMIT $2
+            "#,
+        )
+        .bind(grace_secs)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
         
+        rows.into_iter().map(Self::map_row_to_order).collect()
+    }
+    
+    /// Claim an order's order-created email; false if it was already handled
+    pub async fn mark_created_email_sent(&self, order_id: &str) -> DbResult<bool> {
         let result = sqlx::query(
             r#"
-            UPDATE orders 
-            SET "isPublic" = $1, "privateCode" = $2
-            WHERE "orderId" = $3
+            UPDATE orders
+            SET created_email_sent_at = NOW()
+            WHERE "orderId" = $1 AND created_email_sent_at IS NULL
             "#,
         )
-        .bind(is_public)
-        .bind(&private_code)
         .bind(order_id)
         .execute(&self.pool)
         .await?;
-
-        if result.rows_affected() == 0 {
-            return Err(DbError::OrderNotFound(order_id.to_string()));
-        }
-
-        Ok(private_code)
+        
+        Ok(result.rows_affected() > 0)
     }
     
-    /// Generate a unique 6-digit code
-    async fn generate_unique_code(pool: &PgPool) -> DbResult<String> {
-        for _ in 0..10 {
-            // Generate random 6-digit code (100000 - 999999)
-            // Use gen_range outside of async context by generating before the await
-            let code: u32 = {
-                let mut rng = rand::thread_rng();
-                rng.gen_range(100000..1000000)
-            };
-            let code_str = code.to_string();
-            
-            // Check if code already exists
-            let exists: Option<(i64,)> = sqlx::query_as(
-                r#"SELECT 1 FROM orders WHERE "privateCode" = $1"#
-            )
-            .bind(&code_str)
-            .fetch_optional(pool)
-            .await?;
-            
-            if exists.is_none() {
-                return Ok(code_str);
+    /// Set order visibility (public/private); returns the order's private code and
+    /// what it was before
+    ///
+    /// Private code lifecycle: every switch to private (including private ->
+    /// private) mints a fresh random code that replaces the previous one, so a
+    /// leaked link stops resolving as soon as the seller re-lists. Going public
+    /// clears the code.
+    pub async fn set_visibility(&self, order_id: &str, is_public: bool) -> DbResult<VisibilityChange> {
+        if is_public {
+            let (was_listed, first_choice) = self.update_visibility(order_id, true, None).await?;
+            return Ok(VisibilityChange { was_listed, first_choice, private_code: None });
+        }
+
+        let previous: Option<String> = sqlx::query_scalar(r#"SELECT "privateCode" FROM orders WHERE "orderId" = $1"#)
+            .bind(order_id)
+            .fetch_optional(&self.pool)
+            .await?
+            .ok_or_else(|| DbError::OrderNotFound(order_id.to_string()))?;
+
+        // Collisions are astronomically unlikely, but the unique index on "privateCode"
+        // has the final say: a clash is retried with a new code instead of failing
+        for _ in 0..PRIVATE_CODE_ATTEMPTS {
+            let code = new_private_code(previous.as_deref());
+            match self.update_visibility(order_id, false, Some(&code)).await {
+                Ok((was_listed, first_choice)) => {
+                    return Ok(VisibilityChange { was_listed, first_choice, private_code: Some(code) });
+                }
+                Err(DbError::SqlxError(sqlx::Error::Database(e))) if e.is_unique_violation() => continue,
+                Err(e) => return Err(e),
             }
         }
-        
-        Err(DbError::InvalidInput("Failed to generate unique private code after 10 attempts".to_string()))
+
+        Err(DbError::InvalidInput(format!(
+            "Failed to generate unique private code after {} attempts",
+            PRIVATE_CODE_ATTEMPTS
+        )))
+    }
+
+    /// Returns whether the order was listed before and whether this is the first
+    /// visibility choice (both read under the same row lock)
+    async fn update_visibility(&self, order_id: &str, is_public: bool, private_code: Option<&str>) -> DbResult<(bool, bool)> {
+        let previous: Option<(bool, bool)> = sqlx::query_as(
+            r#"
+            UPDATE orders o
+            SET "isPublic" = $1, "privateCode" = $2, delisted_at = NULL,
+                created_email_sent_at = COALESCE(o.created_email_sent_at, NOW())
+            FROM (
+                SELECT "orderId", "isPublic" AND delisted_at IS NULL AS listed,
+                       created_email_sent_at IS NULL AS unchosen
+                FROM orders WHERE "orderId" = $3 FOR UPDATE
+            ) previous
+            WHERE o."orderId" = previous."orderId"
+            RETURNING previous.listed, previous.unchosen
+            "#,
+        )
+        .bind(is_public)
+        .bind(private_code)
+        .bind(order_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        previous.ok_or_else(|| DbError::OrderNotFound(order_id.to_string()))
     }
     
     /// Update exchange rate for an order
     pub async fn update_exchange_rate(&self, order_id: &str, new_rate: &str) -> DbResult<()> {
-        let result = sqlx::query(
+        update_exchange_rate(&self.pool, order_id, new_rate).await
+    }
+    
+    /// Overwrite remaining amount with an authoritative value (e.g. read from chain)
+    pub async fn set_remaining_amount(&self, order_id: &str, remaining: &str) -> DbResult<()> {
+        let sql = format!(
             r#"
             UPDATE orders 
-            SET "exchangeRate" = $1::numeric
+            SET "remainingAmount" = $1::numeric, delisted_at = NULL, {}
             WHERE "orderId" = $2
             "#,
-        )
-        .bind(new_rate)
+            closed_at_assignment("$1::numeric")
+        );
+        let result = sqlx::query(&sql)
+        .bind(remaining)
         .bind(order_id)
         .execute(&self.pool)
         .await?;
@@ -275,16 +559,44 @@ impl PostgresOrderRepository {
 
         Ok(())
     }
+
+    /// Overwrite remaining amount with `remaining` only while it is still `expected`
+    /// (the value the caller compared against). Returns false when an event changed
+    /// it in the meantime, so a repair never undoes or doubles a listener delta.
+    pub async fn resync_remaining_amount(&self, order_id: &str, expected: &str, remaining: &str) -> DbResult<bool> {
+        let sql = format!(
+            r#"
+            UPDATE orders 
+            SET "remainingAmount" = $1::numeric, delisted_at = NULL, {}
+            WHERE "orderId" = $2 AND "remainingAmount" = $3::numeric
+            "#,
+            closed_at_assignment("$1::numeric")
+        );
+        let result = sqlx::query(&sql)
+        .bind(remaining)
+        .bind(order_id)
+        .bind(expected)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
     
     /// Update payment info (accountId and accountName) for an order
     /// Uses UPSERT to handle race condition where payment info arrives before event handler creates order
-    pub async fn update_payment_info(&self, order_id: &str, account_id: &str, account_name: &str) -> DbResult<()> {
+    ///
+    /// Payment info is write-once: returns false, changing nothing, when the order
+    /// already has different payment info (including when a concurrent submission
+    /// got there first). Writing the same info again returns true, so a retry of a
+    /// write that committed before its connection dropped isn't reported as a conflict.
+    pub async fn update_payment_info(&self, order_id: &str, account_id: &str, account_name: &str) -> DbResult<bool> {
         // First try to update existing order
         let result = sqlx::query(
             r#"
             UPDATE orders 
             SET "accountId" = $1, "accountName" = $2
             WHERE "orderId" = $3
+              AND ("accountId" = '' OR "accountName" = '' OR ("accountId" = $1 AND "accountName" = $2))
             "#,
         )
         .bind(account_id)
@@ -298,7 +610,8 @@ impl PostgresOrderRepository {
             // Create a minimal placeholder order that will be updated by event handler
             tracing::info!("Order {} doesn't exist yet, creating placeholder with payment info", order_id);
             
-            sqlx::query(
+            // (or it exists with payment info already set: the conflict guard then writes nothing)
+            let result = sqlx::query(
                 r#"
                 INSERT INTO orders (
                     "orderId", "seller", "token", "totalAmount", "remainingAmount",
@@ -308,6 +621,8 @@ impl PostgresOrderRepository {
                 ON CONFLICT ("orderId") DO UPDATE SET
                     "accountId" = EXCLUDED."accountId",
                     "accountName" = EXCLUDED."accountName"
+                WHERE orders."accountId" = '' OR orders."accountName" = ''
+                    OR (orders."accountId" = EXCLUDED."accountId" AND orders."accountName" = EXCLUDED."accountName")
                 "#,
             )
             .bind(order_id)
@@ -315,113 +630,503 @@ impl PostgresOrderRepository {
             .bind(account_name)
             .execute(&self.pool)
             .await?;
+            return Ok(result.rows_affected() == 1);
         }
 
-        Ok(())
+        Ok(true)
     }
     
-    /// Get orders by seller (includes both public and private orders - for seller's own view)
-    pub async fn get_by_seller(&self, seller: &str) -> DbResult<Vec<DbOrder>> {
+    /// Get orders by seller (includes both public and private orders - for seller's own view),
+    /// only those on `rail` if given
+    pub async fn get_by_seller(&self, seller: &str, rail: Option<PaymentRail>) -> DbResult<Vec<DbOrder>> {
         let rows = sqlx::query(
             r#"
             SELECT 
                 "orderId", seller, token,
                 "totalAmount"::TEXT, "remainingAmount"::TEXT, "exchangeRate"::TEXT,
                 rail, "accountId", "accountName", "createdAt", "syncedAt",
-                "isPublic", "privateCode", "chainId"
+                "isPublic", "privateCode", "chainId", currency
             FROM orders
-            WHERE seller = $1
+            WHERE seller = $1 AND ($2::INTEGER IS NULL OR rail = $2)
             ORDER BY "createdAt" DESC
             "#,
         )
         .bind(seller)
+        .bind(rail)
         .fetch_all(&self.pool)
         .await?;
         
-        let orders: Vec<DbOrder> = rows
-            .into_iter()
-            .map(|row| Self::map_row_to_order(row))
-            .collect();
+        rows.into_iter().map(Self::map_row_to_order).collect()
+    }
+    
+    /// Delist open orders with no trade or withdrawal since `cutoff` (unix
+    /// seconds); see migration 016. Returns the orders delisted.
+    pub async fn delist_stale(&self, cutoff: i64) -> DbResult<Vec<DbOrder>> {
+        let rows = build_delist_query(cutoff)
+            .build()
+            .fetch_all(&self.pool)
+            .await?;
         
-        Ok(orders)
+        rows.into_iter().map(Self::map_row_to_order).collect()
     }
-}
-
-#[async_trait]
-impl OrderRepository for PostgresOrderRepository {
-    async fn create(&self, order: &DbOrder) -> DbResult<()> {
-        // Use UPSERT to handle race condition:
-        // - If order doesn't exist: insert with provided values
-        // - If order exists (e.g., from payment-info endpoint): preserve existing accountId/accountName
-        sqlx::query(
+    
+    /// Per-token order/trade counts, settled volume and activity span for one
+    /// seller (address compared case-insensitively, as in trade listings)
+    pub async fn seller_stats(&self, seller: &str) -> DbResult<Vec<DbSellerStatsRow>> {
+        let rows = sqlx::query_as::<_, DbSellerStatsRow>(
             r#"
-            INSERT INTO orders (
-                "orderId", "seller", "token", "totalAmount", "remainingAmount",
-                "exchangeRate", "rail", "accountId", "accountName", "createdAt", "isPublic", "chainId"
-            )
-            VALUES ($1, $2, $3, $4::numeric, $5::numeric, $6::numeric, $7, $8, $9, $10, $11, $12)
-            ON CONFLICT ("orderId") DO UPDATE SET
-                -- Update blockchain-authoritative fields
-                "seller" = EXCLUDED."seller",
-                "token" = EXCLUDED."token",
-                "totalAmount" = EXCLUDED."totalAmount",
-                "remainingAmount" = EXCLUDED."remainingAmount",
-                "exchangeRate" = EXCLUDED."exchangeRate",
-                "rail" = EXCLUDED."rail",
-                "createdAt" = EXCLUDED."createdAt",
-                "isPublic" = EXCLUDED."isPublic",
-                "chainId" = EXCLUDED."chainId",
-                -- PRESERVE existing accountId/accountName if already set (race condition handling)
-                "accountId" = CASE 
-                    WHEN orders."accountId" IS NOT NULL AND orders."accountId" != '' 
-                    THEN orders."accountId" 
-                    ELSE EXCLUDED."accountId" 
-                END,
-                "accountName" = CASE 
-                    WHEN orders."accountName" IS NOT NULL AND orders."accountName" != '' 
-                    THEN orders."accountName" 
-                    ELSE EXCLUDED."accountName" 
-                END
+            SELECT
+                o.token,
+                COUNT(DISTINCT o."orderId") AS order_count,
+                COUNT(t."tradeId") FILTER (WHERE t.status = 1) AS settled_count,
+                COUNT(t."tradeId") FILTER (WHERE t.status = 2) AS expired_count,
+                COUNT(t."tradeId") FILTER (WHERE t.status = 0) AS pending_count,
+                COALESCE(SUM(t."tokenAmount") FILTER (WHERE t.status = 1), 0)::text AS settled_volume,
+                MIN(LEAST(o."createdAt", t."createdAt")) AS first_activity_at,
+                MAX(GREATEST(o."createdAt", t."createdAt")) AS last_activity_at
+            FROM orders o
+            LEFT JOIN trades t ON t."orderId" = o."orderId"
+            WHERE LOWER(REPLACE(o.seller, '0x', '')) = $1
+            GROUP BY o.token
+            ORDER BY o.token
             "#,
         )
-        .bind(&order.order_id)
-        .bind(&order.seller)
-        .bind(&order.token)
-        .bind(&order.total_amount)
-        .bind(&order.remaining_amount)
-        .bind(&order.exchange_rate)
-        .bind(order.rail)
-        .bind(&order.alipay_id)
-        .bind(&order.alipay_name)
-        .bind(order.created_at)
-        .bind(order.is_public)
-        .bind(order.chain_id)
-        .execute(&self.pool)
+        .bind(seller.to_lowercase().trim_start_matches("0x").to_string())
+        .fetch_all(&self.pool)
         .await?;
         
-        Ok(())
+        Ok(rows)
     }
+}
 
-    async fn adjust_remaining_amount(&self, order_id: &str, delta: &str) -> DbResult<()> {
-        let delta_decimal = Decimal::from_str(delta)
-            .map_err(|e| DbError::InvalidInput(format!("Invalid delta: {}", e)))?;
-        
-        let result = sqlx::query(
-            r#"
-            UPDATE orders 
-            SET "remainingAmount" = "remainingAmount" + $1::numeric
-            WHERE "orderId" = $2
-            "#,
+// Writes the event listener applies: they take any executor, so an event's
+// writes can share a transaction with its processed_events claim
+
+/// Upsert an order from its OrderCreated event (see `OrderRepository::create`)
+pub(crate) async fn insert_order<'e>(executor: impl PgExecutor<'e>, order: &DbOrder) -> DbResult<()> {
+    // Use UPSERT to handle race condition:
+    // - If order doesn't exist: insert with provided values
+    // - If order exists (e.g., from payment-info endpoint): preserve existing accountId/accountName
+    sqlx::query(
+        r#"
+        INSERT INTO orders (
+            "orderId", "seller", "token", "totalAmount", "remainingAmount",
+            "exchangeRate", "rail", "accountId", "accountName", "createdAt", "isPublic", "chainId",
+            currency, closed_at
         )
-        .bind(delta_decimal)
-        .bind(order_id)
-        .execute(&self.pool)
-        .await?;
+        VALUES (
+            $1, $2, $3, $4::numeric, $5::numeric, $6::numeric, $7, $8, $9, $10, $11, $12, $13,
+            CASE WHEN $5::numeric <= 0 THEN NOW() END
+        )
+        ON CONFLICT ("orderId") DO UPDATE SET
+            -- Update blockchain-authoritative fields
+            "seller" = EXCLUDED."seller",
+            "token" = EXCLUDED."token",
+            "totalAmount" = EXCLUDED."totalAmount",
+            "remainingAmount" = EXCLUDED."remainingAmount",
+            -- Closed while nothing is left (keeping the first close time), open otherwise
+            closed_at = CASE WHEN EXCLUDED."remainingAmount" <= 0 THEN COALESCE(orders.closed_at, NOW()) ELSE NULL END,
+            "exchangeRate" = EXCLUDED."exchangeRate",
+            "rail" = EXCLUDED."rail",
+            "createdAt" = EXCLUDED."createdAt",
+            "isPublic" = EXCLUDED."isPublic",
+            "chainId" = EXCLUDED."chainId",
+            -- Fresh on-chain activity relists an order delisted for inactivity
+            delisted_at = NULL,
+            -- PRESERVE existing accountId/accountName if already set (race condition handling)
+            "accountId" = CASE 
+                WHEN orders."accountId" IS NOT NULL AND orders."accountId" != '' 
+                THEN orders."accountId" 
+                ELSE EXCLUDED."accountId" 
+            END,
+            "accountName" = CASE 
+                WHEN orders."accountName" IS NOT NULL AND orders."accountName" != '' 
+                THEN orders."accountName" 
+                ELSE EXCLUDED."accountName" 
+            END
+        "#,
+    )
+    .bind(&order.order_id)
+    .bind(&order.seller)
+    .bind(&order.token)
+    .bind(&order.total_amount)
+    .bind(&order.remaining_amount)
+    .bind(&order.exchange_rate)
+    .bind(order.rail)
+    .bind(&order.alipay_id)
+    .bind(&order.alipay_name)
+    .bind(order.created_at)
+    .bind(order.is_public)
+    .bind(order.chain_id)
+    .bind(&order.currency)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
 
-        if result.rows_affected() == 0 {
-            return Err(DbError::OrderNotFound(order_id.to_string()));
+/// Add `delta` (negative to reserve or withdraw) to an order's remaining amount
+pub(crate) async fn adjust_remaining_amount<'e>(executor: impl PgExecutor<'e>, order_id: &str, delta: &str) -> DbResult<()> {
+    let delta_decimal = Decimal::from_str(delta)
+        .map_err(|e| DbError::InvalidInput(format!("Invalid delta: {}", e)))?;
+    
+    let sql = format!(
+        r#"
+        UPDATE orders 
+        SET "remainingAmount" = "remainingAmount" + $1::numeric, delisted_at = NULL, {}
+        WHERE "orderId" = $2
+        "#,
+        closed_at_assignment(r#"("remainingAmount" + $1::numeric)"#)
+    );
+    let result = sqlx::query(&sql)
+    .bind(delta_decimal)
+    .bind(order_id)
+    .execute(executor)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::OrderNotFound(order_id.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Set an order's exchange rate from its ExchangeRateUpdated event
+pub(crate) async fn update_exchange_rate<'e>(executor: impl PgExecutor<'e>, order_id: &str, new_rate: &str) -> DbResult<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE orders 
+        SET "exchangeRate" = $1::numeric, delisted_at = NULL
+        WHERE "orderId" = $2
+        "#,
+    )
+    .bind(new_rate)
+    .bind(order_id)
+    .execute(executor)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::OrderNotFound(order_id.to_string()));
+    }
+
+    Ok(())
+}
+
+#[async_trait]
+impl OrderRepository for PostgresOrderRepository {
+    async fn create(&self, order: &DbOrder) -> DbResult<()> {
+        insert_order(&self.pool, order).await
+    }
+
+    async fn adjust_remaining_amount(&self, order_id: &str, delta: &str) -> DbResult<()> {
+        adjust_remaining_amount(&self.pool, order_id, delta).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{fixtures, DbTrade};
+
+    /// Order repository over `pool` with `orders` stored
+    async fn repo_with(pool: PgPool, orders: &[DbOrder]) -> PostgresOrderRepository {
+        let repo = PostgresOrderRepository::new(pool);
+        for order in orders {
+            repo.create(order).await.unwrap();
         }
+        repo
+    }
 
-        Ok(())
+    /// Sorted ids of the orders `search` returns
+    async fn search_ids(repo: &PostgresOrderRepository, search: &OrderSearch) -> Vec<String> {
+        let mut ids: Vec<String> = repo.search(search, None).await.unwrap().into_iter().map(|o| o.order_id).collect();
+        ids.sort();
+        ids
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_rate_range_bounds_query(pool: PgPool) {
+        let mut orders: Vec<DbOrder> = [("0x01", "699"), ("0x02", "700"), ("0x03", "715"), ("0x04", "730"), ("0x05", "731")]
+            .into_iter()
+            .map(|(order_id, rate)| DbOrder { exchange_rate: rate.to_string(), ..fixtures::order(order_id) })
+            .collect();
+        orders.push(DbOrder { chain_id: 1, exchange_rate: "715".to_string(), ..fixtures::order("0x06") });
+        let repo = repo_with(pool, &orders).await;
+
+        let search = OrderSearch {
+            chain_id: Some(8453),
+            min_rate: Some(Decimal::from(700)),
+            max_rate: Some(Decimal::from(730)),
+            ..Default::default()
+        };
+        assert!(search.validate().is_ok());
+        assert_eq!(search_ids(&repo, &search).await, ["0x02", "0x03", "0x04"]);
+        assert_eq!(search_ids(&repo, &OrderSearch::default()).await.len(), 6);
+    }
+
+    #[test]
+    fn test_rate_range_rejects_min_above_max() {
+        let search = OrderSearch {
+            min_rate: Some(Decimal::from(731)),
+            max_rate: Some(Decimal::from(730)),
+            ..Default::default()
+        };
+        assert!(matches!(search.validate(), Err(DbError::InvalidInput(_))));
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_amount_bounds_are_inclusive(pool: PgPool) {
+        // 10^30 base units is past what a Decimal holds, but a valid uint256 amount
+        let huge = "1000000000000000000000000000000";
+        let orders: Vec<DbOrder> = [("0x01", "99999999"), ("0x02", "100000000"), ("0x03", "100000001"), ("0x04", huge)]
+            .into_iter()
+            .map(|(order_id, remaining)| DbOrder {
+                total_amount: huge.to_string(),
+                remaining_amount: remaining.to_string(),
+                ..fixtures::order(order_id)
+            })
+            .collect();
+        let repo = repo_with(pool, &orders).await;
+
+        // Exactly 100 USDC on both ends must match (>= / <=, not > / <)
+        let exact = OrderSearch {
+            min_amount: Some(U256::from(100_000_000u64)),
+            max_amount: Some(U256::from(100_000_000u64)),
+            ..Default::default()
+        };
+        assert!(exact.validate().is_ok());
+        assert_eq!(search_ids(&repo, &exact).await, ["0x02"]);
+
+        let at_least_huge = OrderSearch { min_amount: Some(U256::from_dec_str(huge).unwrap()), ..Default::default() };
+        assert_eq!(search_ids(&repo, &at_least_huge).await, ["0x04"]);
+        let up_to_max = OrderSearch { min_amount: Some(U256::from(100_000_001u64)), max_amount: Some(U256::MAX), ..Default::default() };
+        assert_eq!(search_ids(&repo, &up_to_max).await, ["0x03", "0x04"]);
+    }
+
+    #[test]
+    fn test_amount_range_rejects_min_above_max() {
+        let search = OrderSearch {
+            min_amount: Some(U256::from(100_000_001u64)),
+            max_amount: Some(U256::from(100_000_000u64)),
+            ..Default::default()
+        };
+        assert!(matches!(search.validate(), Err(DbError::InvalidInput(_))));
+    }
+
+    /// Whether `order_id` has closed_at set
+    async fn is_closed(pool: &PgPool, order_id: &str) -> bool {
+        sqlx::query_scalar(r#"SELECT closed_at IS NOT NULL FROM orders WHERE "orderId" = $1"#)
+            .bind(order_id)
+            .fetch_one(pool)
+            .await
+            .unwrap()
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_fully_filled_order_closed_and_excluded_from_listing(pool: PgPool) {
+        let repo = PostgresOrderRepository::new(pool.clone());
+        let listed = || async { repo.search(&OrderSearch::default(), None).await.unwrap().len() };
+        repo.create(&fixtures::order("0x01")).await.unwrap();
+        assert!(!is_closed(&pool, "0x01").await);
+        assert_eq!(listed().await, 1);
+
+        // The last trade (or a full withdrawal) takes remaining to zero: closed and unlisted
+        repo.adjust_remaining_amount("0x01", "-1000000").await.unwrap();
+        assert!(is_closed(&pool, "0x01").await);
+        assert_eq!(listed().await, 0);
+
+        // An expired trade returns funds and reopens it
+        repo.adjust_remaining_amount("0x01", "400000").await.unwrap();
+        assert!(!is_closed(&pool, "0x01").await);
+        assert_eq!(listed().await, 1);
+
+        // The OrderCreated upsert keeps closed_at in step as well
+        let empty = DbOrder { remaining_amount: "0".to_string(), ..fixtures::order("0x01") };
+        repo.create(&empty).await.unwrap();
+        assert!(is_closed(&pool, "0x01").await);
+        repo.create(&DbOrder { remaining_amount: "0".to_string(), ..fixtures::order("0x02") }).await.unwrap();
+        assert!(is_closed(&pool, "0x02").await);
+        repo.create(&fixtures::order("0x01")).await.unwrap();
+        assert!(!is_closed(&pool, "0x01").await);
+        assert_eq!(listed().await, 1);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_delist_selects_by_last_activity(pool: PgPool) {
+        use crate::blockchain::types::WithdrawalLog;
+        use crate::db::trades::{PostgresTradeRepository, TradeRepository};
+        use crate::db::withdrawals::insert_withdrawal;
+
+        let now = chrono::Utc::now().timestamp();
+        let cutoff = now - 86_400;
+        let old = |order_id: &str| DbOrder { created_at: cutoff - 1, ..fixtures::order(order_id) };
+        let orders = [
+            old("0x01"),
+            old("0x02"),
+            old("0x03"),
+            DbOrder { created_at: cutoff, ..fixtures::order("0x04") },
+            DbOrder { is_public: false, ..old("0x05") },
+            DbOrder { remaining_amount: "0".to_string(), ..old("0x06") },
+        ];
+        let repo = repo_with(pool.clone(), &orders).await;
+
+        // A trade (unix createdAt) or withdrawal (timestamptz) since the cutoff keeps an order listed
+        let trade = DbTrade { order_id: "0x02".to_string(), created_at: now, ..fixtures::trade("0xtrade", 0) };
+        PostgresTradeRepository::new(pool.clone()).create(&trade).await.unwrap();
+        let withdrawal = WithdrawalLog {
+            order_id: "0x03".to_string(),
+            amount: "1".to_string(),
+            remaining_after: "999999".to_string(),
+            tx_hash: "0xtx".to_string(),
+            block_number: 1,
+        };
+        insert_withdrawal(&pool, &withdrawal).await.unwrap();
+
+        // Only listed, open public orders with funds left, created before the cutoff and idle since
+        let delisted: Vec<String> = repo.delist_stale(cutoff).await.unwrap().into_iter().map(|o| o.order_id).collect();
+        assert_eq!(delisted, ["0x01"]);
+        assert_eq!(search_ids(&repo, &OrderSearch::default()).await, ["0x02", "0x03", "0x04"]);
+        assert!(repo.delist_stale(cutoff).await.unwrap().is_empty());
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_rail_filter_combines_with_other_filters(pool: PgPool) {
+        // The fixture order is USDC on Base
+        let orders = [
+            fixtures::order("0x01"),
+            DbOrder { rail: PaymentRail::WeChat, ..fixtures::order("0x02") },
+            DbOrder { rail: PaymentRail::WeChat, chain_id: 1, ..fixtures::order("0x03") },
+            DbOrder { rail: PaymentRail::WeChat, token: "0x50c5725949a6f0c72e6c4a641f24049a917db0cb".to_string(), ..fixtures::order("0x04") },
+        ];
+        let repo = repo_with(pool, &orders).await;
+
+        let search = OrderSearch {
+            chain_id: Some(8453),
+            token: Some("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913".to_string()),
+            rail: Some(PaymentRail::WeChat),
+            ..Default::default()
+        };
+        assert!(search.validate().is_ok());
+        assert_eq!(search_ids(&repo, &search).await, ["0x02"]);
+        assert_eq!(search_ids(&repo, &OrderSearch::default()).await.len(), 4);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_multi_token_in_clause(pool: PgPool) {
+        let usdc = "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48";
+        let usdt = "0xdac17f958d2ee523a2206206994597c13d831ec7";
+        let orders = [
+            DbOrder { chain_id: 1, token: usdc.to_string(), ..fixtures::order("0x01") },
+            DbOrder { chain_id: 1, token: usdt.to_string(), ..fixtures::order("0x02") },
+            DbOrder { chain_id: 1, token: "0x6b175474e89094c44da98b954eedeac495271d0f".to_string(), ..fixtures::order("0x03") },
+        ];
+        let repo = repo_with(pool, &orders).await;
+
+        // Any of the listed tokens, compared case-insensitively
+        let search = OrderSearch {
+            chain_id: Some(1),
+            tokens: vec![
+                "0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string(),
+                "0xdAC17F958D2ee523a2206206994597C13D831ec7".to_string(),
+            ],
+            ..Default::default()
+        };
+        assert_eq!(search_ids(&repo, &search).await, ["0x01", "0x02"]);
+
+        // Single-token filter is unchanged
+        let single = OrderSearch {
+            token: Some("0xA0b86991c6218b36c1d19D4a2e9Eb0cE3606eB48".to_string()),
+            ..Default::default()
+        };
+        assert_eq!(search_ids(&repo, &single).await, ["0x01"]);
+    }
+
+    #[test]
+    fn test_new_private_code_never_repeats_the_previous_one() {
+        let mut code = new_private_code(None);
+        for _ in 0..1000 {
+            let rotated = new_private_code(Some(&code));
+            assert_ne!(rotated, code);
+            assert_eq!(rotated.len(), crate::crypto::private_code::PRIVATE_CODE_LEN);
+            assert!(rotated.bytes().all(|b| b.is_ascii_alphanumeric()), "{}", rotated);
+            code = rotated;
+        }
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_relisting_private_rotates_code_and_old_one_is_not_found(pool: PgPool) {
+        let repo = PostgresOrderRepository::new(pool);
+        repo.create(&crate::db::models::fixtures::order("0xorder")).await.unwrap();
+
+        let first = repo.set_visibility("0xorder", false).await.unwrap().private_code.unwrap();
+        assert_eq!(repo.get_by_private_code(&first).await.unwrap().order_id, "0xorder");
+
+        // Making it private again replaces the code, and the old link stops resolving
+        let second = repo.set_visibility("0xorder", false).await.unwrap().private_code.unwrap();
+        assert_ne!(second, first);
+        assert!(matches!(repo.get_by_private_code(&first).await, Err(DbError::OrderNotFound(_))));
+        assert!(!repo.get("0xorder").await.unwrap().is_public);
+
+        // Going public clears it
+        assert_eq!(repo.set_visibility("0xorder", true).await.unwrap().private_code, None);
+        assert!(matches!(repo.get_by_private_code(&second).await, Err(DbError::OrderNotFound(_))));
+        assert!(matches!(repo.set_visibility("0xmissing", false).await, Err(DbError::OrderNotFound(_))));
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_resync_skips_an_order_changed_since_it_was_read(pool: PgPool) {
+        let repo = PostgresOrderRepository::new(pool);
+        repo.create(&crate::db::models::fixtures::order("0xorder")).await.unwrap();
+
+        // A trade took 100 after the resync read 1000000: writing the chain value
+        // read before the trade would hand the 100 back
+        repo.adjust_remaining_amount("0xorder", "-100").await.unwrap();
+        assert!(!repo.resync_remaining_amount("0xorder", "1000000", "1000000").await.unwrap());
+        assert_eq!(repo.get("0xorder").await.unwrap().remaining_amount, "999900");
+
+        assert!(repo.resync_remaining_amount("0xorder", "999900", "0").await.unwrap());
+        assert_eq!(repo.get("0xorder").await.unwrap().remaining_amount, "0");
+    }
+
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_payment_info_is_write_once(pool: PgPool) {
+        let repo = PostgresOrderRepository::new(pool);
+        let unset = DbOrder {
+            alipay_id: String::new(),
+            alipay_name: String::new(),
+            ..crate::db::models::fixtures::order("0xorder")
+        };
+        repo.create(&unset).await.unwrap();
+
+        assert!(repo.update_payment_info("0xorder", "first", "First").await.unwrap());
+        // A second submission (e.g. racing the first past the handler's check) changes nothing
+        assert!(!repo.update_payment_info("0xorder", "second", "Second").await.unwrap());
+        let order = repo.get("0xorder").await.unwrap();
+        assert_eq!((order.alipay_id.as_str(), order.alipay_name.as_str()), ("first", "First"));
+        // Writing the same info again (a retried write that already committed) succeeds
+        assert!(repo.update_payment_info("0xorder", "first", "First").await.unwrap());
+
+        // Same for the placeholder created before the listener has seen the order
+        assert!(repo.update_payment_info("0xearly", "first", "First").await.unwrap());
+        assert!(!repo.update_payment_info("0xearly", "second", "Second").await.unwrap());
+        assert!(repo.update_payment_info("0xearly", "first", "First").await.unwrap());
+        assert_eq!(repo.get("0xearly").await.unwrap().alipay_id, "first");
     }
 }
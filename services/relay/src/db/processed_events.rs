@@ -0,0 +1,60 @@
+//! Processed Events Repository - logs the event listener has already applied
+//! Claimed in the same transaction as an event's writes; rows are pruned after a retention window.
+
+use super::DbResult;
+use sqlx::{PgPool, Postgres, Transaction};
+use std::time::Duration;
+
+/// Where a log sits on chain; the same log re-read later has the same key
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ProcessedEventKey {
+    pub chain_id: i32,
+    /// 0x-prefixed lowercase transaction hash
+    pub tx_hash: String,
+    pub log_index: i64,
+    pub block_number: i64,
+}
+
+/// Repository for processed event records
+pub struct ProcessedEventRepository {
+    pool: PgPool,
+}
+
+impl ProcessedEventRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Begin the transaction an event is applied in, with the event already
+    /// recorded as processed in it; None if it already was. The record commits
+    /// together with the handler's writes, and a rollback (or a crash before the
+    /// commit) drops both, so the event is applied exactly once.
+    pub async fn begin_claimed(&self, key: &ProcessedEventKey) -> DbResult<Option<Transaction<'static, Postgres>>> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query(
+            r#"
+            INSERT INTO processed_events (chain_id, tx_hash, log_index, block_number)
+            VALUES ($1, $2, $3, $4)
+            ON CONFLICT (chain_id, tx_hash, log_index) DO NOTHING
+            "#,
+        )
+        .bind(key.chain_id)
+        .bind(&key.tx_hash)
+        .bind(key.log_index)
+        .bind(key.block_number)
+        .execute(&mut *tx)
+        .await?;
+
+        Ok((result.rows_affected() == 1).then_some(tx))
+    }
+
+    /// Delete records older than `retention`; returns how many were removed
+    pub async fn prune(&self, retention: Duration) -> DbResult<u64> {
+        let result = sqlx::query("DELETE FROM processed_events WHERE processed_at < NOW() - make_interval(secs => $1)")
+            .bind(retention.as_secs_f64())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected())
+    }
+}
@@ -0,0 +1,226 @@
+//! Proof Job Repository - queued Axiom proof generation + settlement
+//! Rows are inserted by validate and processed by the proof job worker (api::proof_jobs).
+
+use super::models::DbProofJob;
+use super::DbResult;
+use sqlx::PgPool;
+use std::time::Duration;
+
+const JOB_COLUMNS: &str = "id, trade_id, transaction_id, payment_time, status, attempts, \
+    settlement_tx_hash, error, created_at, started_at, finished_at";
+
+/// Repository for proof job operations
+pub struct ProofJobRepository {
+    pool: PgPool,
+}
+
+impl ProofJobRepository {
+    pub fn new(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    /// Queue a job for a trade; returns the trade's already active (queued or running) job if it has one
+    pub async fn enqueue(&self, trade_id: &str, transaction_id: &str, payment_time: &str) -> DbResult<i64> {
+        let row: (i64,) = sqlx::query_as(
+            r#"
+            WITH inserted AS (
+                INSERT INTO proofs (trade_id, transaction_id, payment_time)
+                VALUES ($1, $2, $3)
+                ON CONFLICT (trade_id) WHERE status IN ('queued', 'running') DO NOTHING
+                RETURNING id
+            )
+            SELECT id FROM inserted
+            UNION ALL
+            SELECT id FROM proofs WHERE trade_id = $1 AND status IN ('queued', 'running')
+            LIMIT 1
+            "#,
+        )
+        .bind(trade_id)
+        .bind(transaction_id)
+        .bind(payment_time)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.0)
+    }
+
+    pub async fn get(&self, id: i64) -> DbResult<Option<DbProofJob>> {
+        let job = sqlx::query_as::<_, DbProofJob>(&format!("SELECT {} FROM proofs WHERE id = $1", JOB_COLUMNS))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(job)
+    }
+
+    /// Most recent job for a trade (any status)
+    pub async fn latest_for_trade(&self, trade_id: &str) -> DbResult<Option<DbProofJob>> {
+        let job = sqlx::query_as::<_, DbProofJob>(&format!(
+            "SELECT {} FROM proofs WHERE trade_id = $1 ORDER BY id DESC LIMIT 1",
+            JOB_COLUMNS
+        ))
+        .bind(trade_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    /// Move the oldest queued job to running (skipping rows other instances are
+    /// claiming, and requeued jobs whose retry_at hasn't come yet)
+    pub async fn claim_next(&self) -> DbResult<Option<DbProofJob>> {
+        let job = sqlx::query_as::<_, DbProofJob>(&format!(
+            r#"
+            UPDATE proofs
+            SET status = 'running', attempts = attempts + 1, retry_at = NULL, started_at = NOW(), updated_at = NOW()
+            WHERE id = (
+                SELECT id FROM proofs
+                WHERE status = 'queued' AND (retry_at IS NULL OR retry_at <= NOW())
+                ORDER BY id
+                FOR UPDATE SKIP LOCKED
+                LIMIT 1
+            )
+            RETURNING {}
+            "#,
+            JOB_COLUMNS
+        ))
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(job)
+    }
+
+    pub async fn mark_done(&self, id: i64, settlement_tx_hash: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE proofs
+            SET status = 'done', settlement_tx_hash = $2, error = NULL, finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(id)
+        .bind(settlement_tx_hash)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Put a running job back in the queue, claimable again after `delay`
+    pub async fn requeue(&self, id: i64, delay: Duration) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE proofs
+            SET status = 'queued', retry_at = NOW() + make_interval(secs => $2), updated_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(id)
+        .bind(delay.as_secs_f64())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    pub async fn mark_failed(&self, id: i64, error: &str) -> DbResult<()> {
+        sqlx::query(
+            r#"
+            UPDATE proofs
+            SET status = 'failed', error = $2, finished_at = NOW(), updated_at = NOW()
+            WHERE id = $1 AND status = 'running'
+            "#,
+        )
+        .bind(id)
+        .bind(error)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Settle up jobs running for longer than `max_age_secs` (their worker died).
+    /// A job whose trade already has a settlement tx recorded is marked done with
+    /// it; only the rest go back in the queue, so a trade is never settled twice.
+    /// Returns how many were requeued.
+    pub async fn requeue_stale(&self, max_age_secs: i64) -> DbResult<u64> {
+        let result = sqlx::query(
+            r#"
+            WITH settled AS (
+                UPDATE proofs p
+                SET status = 'done', settlement_tx_hash = t."settlementTxHash", error = NULL,
+                    finished_at = NOW(), updated_at = NOW()
+                FROM trades t
+                WHERE t."tradeId" = p.trade_id AND t."settlementTxHash" IS NOT NULL
+                  AND p.status = 'running' AND p.started_at < NOW() - make_interval(secs => $1)
+                RETURNING p.id
+            )
+            UPDATE proofs p
+            SET status = 'queued', updated_at = NOW()
+            FROM trades t
+            WHERE t."tradeId" = p.trade_id AND t."settlementTxHash" IS NULL
+              AND p.status = 'running' AND p.started_at < NOW() - make_interval(secs => $1)
+            "#,
+        )
+        .bind(max_age_secs as f64)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures;
+    use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+    use crate::db::trades::{PostgresTradeRepository, TradeRepository};
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_stale_job_with_settlement_tx_not_requeued(pool: PgPool) {
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        let trades = PostgresTradeRepository::new(pool.clone());
+        trades.create(&fixtures::trade("0xsettled", 0)).await.unwrap();
+        trades.create(&fixtures::trade("0xdead", 0)).await.unwrap();
+        trades.create(&fixtures::trade("0xfresh", 0)).await.unwrap();
+        trades.update_settlement_tx("0xsettled", "0xsettle").await.unwrap();
+
+        let repo = ProofJobRepository::new(pool.clone());
+        let mut ids = Vec::new();
+        for trade_id in ["0xsettled", "0xdead", "0xfresh"] {
+            ids.push(repo.enqueue(trade_id, "2024010122001400000000000001", "2024-01-01 12:00:00").await.unwrap());
+            repo.claim_next().await.unwrap().unwrap();
+        }
+        sqlx::query("UPDATE proofs SET started_at = NOW() - INTERVAL '1 hour' WHERE trade_id <> '0xfresh'")
+            .execute(&pool).await.unwrap();
+
+        assert_eq!(repo.requeue_stale(600).await.unwrap(), 1);
+        let settled = repo.get(ids[0]).await.unwrap().unwrap();
+        assert_eq!((settled.status.as_str(), settled.settlement_tx_hash.as_deref()), ("done", Some("0xsettle")));
+        assert_eq!(repo.get(ids[1]).await.unwrap().unwrap().status, "queued");
+        assert_eq!(repo.get(ids[2]).await.unwrap().unwrap().status, "running");
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_requeued_job_waits_for_its_retry_time(pool: PgPool) {
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        PostgresTradeRepository::new(pool.clone()).create(&fixtures::trade("0xbusy", 0)).await.unwrap();
+
+        let repo = ProofJobRepository::new(pool.clone());
+        let id = repo.enqueue("0xbusy", "2024010122001400000000000001", "2024-01-01 12:00:00").await.unwrap();
+        repo.claim_next().await.unwrap().unwrap();
+
+        repo.requeue(id, Duration::from_secs(60)).await.unwrap();
+        assert_eq!(repo.get(id).await.unwrap().unwrap().status, "queued");
+        assert!(repo.claim_next().await.unwrap().is_none());
+
+        sqlx::query("UPDATE proofs SET retry_at = NOW() - INTERVAL '1 second'").execute(&pool).await.unwrap();
+        let job = repo.claim_next().await.unwrap().unwrap();
+        assert_eq!((job.id, job.attempts), (id, 2));
+    }
+}
@@ -0,0 +1,144 @@
+//! Retry for transient database errors
+//!
+//! A Postgres failover or a dropped connection fails whatever query was in
+//! flight; trying again a moment later usually lands on a healthy connection.
+//! `with_retry` reruns a query on connection-level errors only - constraint
+//! violations, bad SQL and missing rows come back on the first attempt.
+//!
+//! Only wrap reads and writes that are safe to repeat (plain UPDATEs to fixed
+//! values, upserts, deletes). An INSERT or a claim whose first attempt may
+//! have committed before the connection dropped must not be retried.
+
+use std::future::Future;
+use std::time::Duration;
+
+use super::{DbError, DbResult};
+use crate::retry::{self, RetryPolicy};
+
+/// Three attempts, 100ms then 200ms apart: enough to ride out a reconnect
+/// without holding a request for long
+pub const DB_RETRY: RetryPolicy = RetryPolicy::new(3, Duration::from_millis(100))
+    .with_max_delay(Duration::from_secs(1))
+    .with_jitter(0.2);
+
+/// SQLSTATEs meaning the statement didn't run and can simply be run again
+fn is_transient_sqlstate(code: &str) -> bool {
+    code.starts_with("08")          // connection_exception
+        || code == "57P01"          // admin_shutdown
+        || code == "57P02"          // crash_shutdown
+        || code == "57P03"          // cannot_connect_now (failover in progress)
+        || code == "40001"          // serialization_failure
+        || code == "40P01"          // deadlock_detected
+}
+
+/// Whether `error` is worth retrying: lost connections and pool timeouts,
+/// not constraint violations, syntax errors or missing rows
+pub fn is_transient(error: &DbError) -> bool {
+    match error {
+        DbError::SqlxError(e) => match e {
+            sqlx::Error::Io(_) | sqlx::Error::PoolTimedOut | sqlx::Error::WorkerCrashed => true,
+            sqlx::Error::Database(db) => db.code().is_some_and(|code| is_transient_sqlstate(&code)),
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+/// Run `op` with DB_RETRY, retrying transient errors only
+pub async fn with_retry<T, F, Fut>(op: F) -> DbResult<T>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = DbResult<T>>,
+{
+    retry::with_backoff(op, &DB_RETRY, is_transient).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sqlx::error::{DatabaseError, ErrorKind};
+    use std::borrow::Cow;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    /// Postgres error response with a given SQLSTATE
+    #[derive(Debug)]
+    struct PgError(&'static str);
+
+    impl std::fmt::Display for PgError {
+        fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+            write!(f, "SQLSTATE {}", self.0)
+        }
+    }
+
+    impl std::error::Error for PgError {}
+
+    impl DatabaseError for PgError {
+        fn message(&self) -> &str {
+            self.0
+        }
+
+        fn code(&self) -> Option<Cow<'_, str>> {
+            Some(Cow::Borrowed(self.0))
+        }
+
+        fn as_error(&self) -> &(dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn as_error_mut(&mut self) -> &mut (dyn std::error::Error + Send + Sync + 'static) {
+            self
+        }
+
+        fn into_error(self: Box<Self>) -> Box<dyn std::error::Error + Send + Sync + 'static> {
+            self
+        }
+
+        fn kind(&self) -> ErrorKind {
+            match self.0 {
+                "23505" => ErrorKind::UniqueViolation,
+                _ => ErrorKind::Other,
+            }
+        }
+    }
+
+    fn db_error(code: &'static str) -> DbError {
+        DbError::SqlxError(sqlx::Error::Database(Box::new(PgError(code))))
+    }
+
+    #[test]
+    fn test_error_classification() {
+        let connection_reset = std::io::Error::from(std::io::ErrorKind::ConnectionReset);
+        assert!(is_transient(&DbError::SqlxError(sqlx::Error::Io(connection_reset))));
+        assert!(is_transient(&DbError::SqlxError(sqlx::Error::PoolTimedOut)));
+        assert!(is_transient(&db_error("08006")));
+        assert!(is_transient(&db_error("57P01")));
+        assert!(is_transient(&db_error("40001")));
+
+        assert!(!is_transient(&db_error("23505")), "unique violation must never be retried");
+        assert!(!is_transient(&db_error("23503")));
+        assert!(!is_transient(&db_error("42601")));
+        assert!(!is_transient(&DbError::SqlxError(sqlx::Error::RowNotFound)));
+        assert!(!is_transient(&DbError::SqlxError(sqlx::Error::PoolClosed)));
+        assert!(!is_transient(&DbError::TradeNotFound("0x01".to_string())));
+    }
+
+    #[tokio::test]
+    async fn test_unique_violation_not_retried() {
+        let calls = AtomicU32::new(0);
+        let result: DbResult<()> = with_retry(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err(db_error("23505"))
+        }).await;
+        assert!(result.is_err());
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+        let result = with_retry(|| async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 => Err(DbError::SqlxError(sqlx::Error::PoolTimedOut)),
+                _ => Ok(5),
+            }
+        }).await;
+        assert_eq!(result.unwrap(), 5);
+        assert_eq!(calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -1,9 +1,47 @@
 use async_trait::async_trait;
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool, Postgres, QueryBuilder};
 use chrono::{DateTime, Utc};
 
 use super::{DbError, DbResult};
-use super::models::DbTrade;
+use crate::currency::{parse_amount, InvalidAmount};
+use crate::storage;
+use super::models::{DbChainCounts, DbExpiringTrade, DbStuckTrade, DbTrade, DbTradePdf, DbTradeStatsRow};
+
+/// Trades expiring before this unix time are past their grace buffer and may be
+/// cancelled. The buffer keeps cancellation from racing a buyer's last-second
+/// settlement of a just-expired trade.
+pub fn expiry_cutoff(now: i64, buffer_secs: u64) -> i64 {
+    now.saturating_sub(i64::try_from(buffer_secs).unwrap_or(i64::MAX))
+}
+
+impl DbTrade {
+    /// Fee reserved by this trade (token base units): the recorded on-chain fee,
+    /// else estimated from the rate snapshotted at creation, else from
+    /// `fallback_rate_bps` (legacy rows with neither). An estimate from a
+    /// malformed token amount is an error, never a zero fee.
+    pub fn fee_or_estimate(&self, fallback_rate_bps: u128) -> Result<String, InvalidAmount> {
+        if let Some(fee) = &self.fee_amount {
+            return Ok(fee.clone());
+        }
+        let rate_bps = self.fee_rate_bps.map_or(fallback_rate_bps, |bps| bps.max(0) as u128);
+        let token_amount: u128 = parse_amount(&self.token_amount)?;
+        Ok((token_amount * rate_bps / 10000).to_string())
+    }
+
+    /// Whether fee_or_estimate has to fall back to a rate from outside the row
+    pub fn needs_fee_rate(&self) -> bool {
+        self.fee_amount.is_none() && self.fee_rate_bps.is_none()
+    }
+}
+
+impl DbTradePdf {
+    /// Bucket key of the receipt: its content address when a hash was recorded,
+    /// else the legacy per-trade key (None when the bytes are inline or missing)
+    pub fn object_key(&self) -> Option<String> {
+        let legacy_key = self.pdf_storage_key.as_ref()?;
+        Some(self.pdf_sha256.as_deref().map_or_else(|| legacy_key.clone(), storage::content_pdf_key))
+    }
+}
 
 /// Repository for Trade operations - ONLY methods needed for event sync
 #[async_trait]
@@ -23,8 +61,15 @@ pub trait TradeRepository: Send + Sync {
     /// Update settlement transaction hash from TradeSettled event
     async fn update_settlement_tx(&self, trade_id: &str, settlement_tx_hash: &str) -> DbResult<()>;
     
-    /// Save PDF file for a trade
-    async fn save_pdf(&self, trade_id: &str, pdf_data: &[u8], filename: &str) -> DbResult<DateTime<Utc>>;
+    /// Record the on-chain fee of a trade indexed without one (only fills a missing fee);
+    /// false when the trade already had one (or doesn't exist)
+    async fn update_fee_amount(&self, trade_id: &str, fee_amount: &str) -> DbResult<bool>;
+    
+    /// Save PDF file (and its content hash) for a trade
+    async fn save_pdf(&self, trade_id: &str, pdf_data: &[u8], filename: &str, sha256: &str) -> DbResult<DateTime<Utc>>;
+    
+    /// Record a PDF stored in the bucket (key, content hash + original filename, no bytes)
+    async fn save_pdf_key(&self, trade_id: &str, storage_key: &str, filename: &str, sha256: &str) -> DbResult<DateTime<Utc>>;
     
     /// Clear PDF data when validation fails - allows user to retry
     async fn clear_pdf(&self, trade_id: &str) -> DbResult<()>;
@@ -35,17 +80,104 @@ pub trait TradeRepository: Send + Sync {
     /// Update payment info (transaction_id and payment_time from PDF)
     async fn update_payment_info(&self, trade_id: &str, transaction_id: &str, payment_time: &str) -> DbResult<()>;
     
-    /// Get all expired pending trades (status=0 and expiresAt < now)
-    async fn get_expired_pending_trades(&self) -> DbResult<Vec<DbTrade>>;
+    /// Get all pending trades expired more than `buffer_secs` ago
+    /// (status=0 and expiresAt < now - buffer_secs, see `expiry_cutoff`)
+    async fn get_expired_pending_trades(&self, buffer_secs: u64) -> DbResult<Vec<DbTrade>>;
     
-    /// Get all trades (for debug purposes)
-    async fn get_all_trades(&self) -> DbResult<Vec<DbTrade>>;
+    /// One page of all trades, newest first (for debug purposes)
+    async fn get_all_trades(&self, limit: i64, offset: i64) -> DbResult<Vec<DbTrade>>;
     
     /// Check if transaction ID has been used in any settled trade (anti-replay)
     async fn is_transaction_id_used(&self, transaction_id: &str) -> DbResult<bool>;
     
     /// Save settlement error when blockchain submission fails
     async fn save_settlement_error(&self, trade_id: &str, error_code: &str) -> DbResult<()>;
+    
+    /// Flag a trade whose auto-cancellation keeps failing (excluded from future auto-cancel runs)
+    async fn mark_cancel_failed(&self, trade_id: &str, error: &str) -> DbResult<()>;
+}
+
+/// Whose trades to list: trades created by a buyer, or trades on a seller's orders
+#[derive(Debug, Clone)]
+pub enum TradeParty {
+    Buyer(String),
+    Seller(String),
+}
+
+/// Filtered, paginated trade history for one party (newest first)
+#[derive(Debug, Clone)]
+pub struct TradeListQuery {
+    pub party: TradeParty,
+    /// TradeStatus: 0=PENDING, 1=SETTLED, 2=EXPIRED (None = all)
+    pub status: Option<i32>,
+    pub limit: i64,
+    pub offset: i64,
+}
+
+impl TradeListQuery {
+    /// Reject unknown statuses and negative paging
+    pub fn validate(&self) -> DbResult<()> {
+        if let Some(status) = self.status {
+            if !(0..=2).contains(&status) {
+                return Err(DbError::InvalidInput(format!("status must be 0, 1 or 2 (got {})", status)));
+            }
+        }
+        if self.limit <= 0 || self.offset < 0 {
+            return Err(DbError::InvalidInput("limit must be > 0 and offset >= 0".to_string()));
+        }
+        Ok(())
+    }
+    
+    /// FROM/WHERE shared by the page and count queries, so the total reflects the filter
+    fn push_from_where(&self, query: &mut QueryBuilder<'_, Postgres>) {
+        // Address normalized the same way as the stored value: lowercase, no 0x.
+        // LEFT JOIN so a buyer's trades still list if the order row is missing.
+        let (column, address) = match &self.party {
+            TradeParty::Buyer(address) => ("t.buyer", address),
+            TradeParty::Seller(address) => ("o.seller", address),
+        };
+        query.push(format!(
+            r#" FROM trades t LEFT JOIN orders o ON t."orderId" = o."orderId" WHERE LOWER(REPLACE({}, '0x', '')) = "#,
+            column
+        ));
+        query.push_bind(address.to_lowercase().trim_start_matches("0x").to_string());
+        
+        if let Some(status) = self.status {
+            query.push(" AND t.status = ").push_bind(status);
+        }
+    }
+    
+    /// SELECT for one page of trades
+    fn build_page_query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new(
+            r#"
+            SELECT 
+                t."tradeId", t."orderId", t.buyer,
+                t."tokenAmount"::TEXT, t."cnyAmount"::TEXT, t."feeAmount"::TEXT, t."feeRateBps",
+                t.rail, t."transactionId", t."paymentTime",
+                t."createdAt", t."expiresAt", t.status,
+                t."syncedAt", t."escrowTxHash", t."settlementTxHash",
+                t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
+                t.proof_user_public_values, t.proof_accumulator, t.proof_data,
+                t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
+                t."chainId", t.currency,
+                COALESCE(t.token, o.token) as token,
+                o."accountId" as "alipay_id",
+                o."accountName" as "alipay_name",
+                (o."orderId" IS NULL OR o."remainingAmount" = 0) as order_closed"#,
+        );
+        self.push_from_where(&mut query);
+        query.push(r#" ORDER BY t."createdAt" DESC, t."tradeId" LIMIT "#).push_bind(self.limit);
+        query.push(" OFFSET ").push_bind(self.offset);
+        query
+    }
+    
+    /// SELECT COUNT(*) over the same filter (ignores limit/offset)
+    fn build_count_query(&self) -> QueryBuilder<'_, Postgres> {
+        let mut query = QueryBuilder::new("SELECT COUNT(*)");
+        self.push_from_where(&mut query);
+        query
+    }
 }
 
 pub struct PostgresTradeRepository {
@@ -58,42 +190,91 @@ impl PostgresTradeRepository {
     }
 }
 
+// Writes the event listener applies: they take any executor, so an event's
+// writes can share a transaction with its processed_events claim
+
+/// Insert a trade from its TradeCreated event (a trade already recorded is left as is)
+pub(crate) async fn insert_trade<'e>(executor: impl PgExecutor<'e>, trade: &DbTrade) -> DbResult<()> {
+    // Use dynamic query to avoid SQLX offline cache issues
+    sqlx::query(
+        r#"
+        INSERT INTO trades (
+            "tradeId", "orderId", "buyer", "token", "tokenAmount", "cnyAmount", "feeAmount",
+            "rail", "transactionId", "paymentTime",
+            "createdAt", "expiresAt", "status",
+            "escrowTxHash", "settlementTxHash", "chainId", currency, "feeRateBps"
+        )
+        VALUES ($1, $2, $3, $4, $5::numeric, $6::numeric, $7::numeric, $8, $9, $10, $11, $12, $13, $14, $15, $16, $17, $18)
+        ON CONFLICT ("tradeId") DO NOTHING
+        "#,
+    )
+    .bind(&trade.trade_id)
+    .bind(&trade.order_id)
+    .bind(&trade.buyer)
+    .bind(&trade.token)
+    .bind(&trade.token_amount)
+    .bind(&trade.cny_amount)
+    .bind(&trade.fee_amount)
+    .bind(trade.rail)
+    .bind(&trade.transaction_id)
+    .bind(&trade.payment_time)
+    .bind(trade.created_at)
+    .bind(trade.expires_at)
+    .bind(trade.status)
+    .bind(&trade.escrow_tx_hash)
+    .bind(&trade.settlement_tx_hash)
+    .bind(trade.chain_id)
+    .bind(&trade.currency)
+    .bind(trade.fee_rate_bps)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
+/// Set a trade's status (stamping settled_at the first time it settles)
+pub(crate) async fn update_status<'e>(executor: impl PgExecutor<'e>, trade_id: &str, new_status: i32) -> DbResult<()> {
+    let result = sqlx::query(
+        r#"
+        UPDATE trades
+        SET "status" = $1,
+            settled_at = CASE WHEN $1 = 1 THEN COALESCE(settled_at, NOW()) ELSE settled_at END
+        WHERE "tradeId" = $2
+        "#,
+    )
+    .bind(new_status)
+    .bind(trade_id)
+    .execute(executor)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::TradeNotFound(trade_id.to_string()));
+    }
+
+    Ok(())
+}
+
+/// Record the transaction that settled a trade
+pub(crate) async fn update_settlement_tx<'e>(executor: impl PgExecutor<'e>, trade_id: &str, settlement_tx_hash: &str) -> DbResult<()> {
+    let result = sqlx::query(
+        r#"UPDATE trades SET "settlementTxHash" = $1 WHERE "tradeId" = $2"#,
+    )
+    .bind(settlement_tx_hash)
+    .bind(trade_id)
+    .execute(executor)
+    .await?;
+
+    if result.rows_affected() == 0 {
+        return Err(DbError::TradeNotFound(trade_id.to_string()));
+    }
+
+    Ok(())
+}
+
 #[async_trait]
 impl TradeRepository for PostgresTradeRepository {
     async fn create(&self, trade: &DbTrade) -> DbResult<()> {
-        // Use dynamic query to avoid SQLX offline cache issues
-        sqlx::query(
-            r#"
-            INSERT INTO trades (
-                "tradeId", "orderId", "buyer", "token", "tokenAmount", "cnyAmount", "feeAmount",
-                "rail", "transactionId", "paymentTime",
-                "createdAt", "expiresAt", "status",
-                "escrowTxHash", "settlementTxHash", "chainId"
-            )
-            VALUES ($1, $2, $3, $4, $5::numeric, $6::numeric, $7::numeric, $8, $9, $10, $11, $12, $13, $14, $15, $16)
-            ON CONFLICT ("tradeId") DO NOTHING
-            "#,
-        )
-        .bind(&trade.trade_id)
-        .bind(&trade.order_id)
-        .bind(&trade.buyer)
-        .bind(&trade.token)
-        .bind(&trade.token_amount)
-        .bind(&trade.cny_amount)
-        .bind(&trade.fee_amount)
-        .bind(trade.rail)
-        .bind(&trade.transaction_id)
-        .bind(&trade.payment_time)
-        .bind(trade.created_at)
-        .bind(trade.expires_at)
-        .bind(trade.status)
-        .bind(&trade.escrow_tx_hash)
-        .bind(&trade.settlement_tx_hash)
-        .bind(trade.chain_id)
-        .execute(&self.pool)
-        .await?;
-        
-        Ok(())
+        insert_trade(&self.pool, trade).await
     }
 
     async fn get(&self, trade_id: &str) -> DbResult<DbTrade> {
@@ -101,14 +282,14 @@ impl TradeRepository for PostgresTradeRepository {
         let row = sqlx::query(
             r#"
             SELECT 
-                "tradeId", "orderId", "buyer", "tokenAmount"::text, "cnyAmount"::text, "feeAmount"::text,
+                "tradeId", "orderId", "buyer", "tokenAmount"::text, "cnyAmount"::text, "feeAmount"::text, "feeRateBps",
                 "rail", "transactionId", "paymentTime",
                 "createdAt", "expiresAt", "status",
                 "escrowTxHash", "settlementTxHash", "syncedAt",
                 pdf_file, pdf_filename, pdf_uploaded_at,
                 proof_user_public_values, proof_accumulator, proof_data,
                 axiom_proof_id, proof_generated_at, proof_json, settlement_error,
-                "chainId"
+                "chainId", currency
             FROM trades
             WHERE "tradeId" = $1
             "#,
@@ -126,7 +307,8 @@ impl TradeRepository for PostgresTradeRepository {
             token_amount: row.get("tokenAmount"),
             cny_amount: row.get("cnyAmount"),
             fee_amount: row.get("feeAmount"),
-            rail: row.get("rail"),
+            fee_rate_bps: row.get("feeRateBps"),
+            rail: row.try_get("rail")?,
             transaction_id: row.get("transactionId"),
             payment_time: row.get("paymentTime"),
             created_at: row.get("createdAt"),
@@ -147,25 +329,15 @@ impl TradeRepository for PostgresTradeRepository {
             proof_json: row.get("proof_json"),
             settlement_error: row.get("settlement_error"),
             chain_id: row.get("chainId"),
+            currency: row.get("currency"),
             alipay_id: None, // Not available in single trade query
             alipay_name: None, // Not available in single trade query
+            order_closed: None,
         })
     }
 
     async fn update_status(&self, trade_id: &str, new_status: i32) -> DbResult<()> {
-        let result = sqlx::query(
-            r#"UPDATE trades SET "status" = $1 WHERE "tradeId" = $2"#,
-        )
-        .bind(new_status)
-        .bind(trade_id)
-        .execute(&self.pool)
-        .await?;
-
-        if result.rows_affected() == 0 {
-            return Err(DbError::TradeNotFound(trade_id.to_string()));
-        }
-
-        Ok(())
+        update_status(&self.pool, trade_id, new_status).await
     }
     
     async fn update_proof_hash(&self, _trade_id: &str, _proof_hash: &str) -> DbResult<()> {
@@ -176,10 +348,39 @@ impl TradeRepository for PostgresTradeRepository {
     }
     
     async fn update_settlement_tx(&self, trade_id: &str, settlement_tx_hash: &str) -> DbResult<()> {
+        update_settlement_tx(&self.pool, trade_id, settlement_tx_hash).await
+    }
+    
+    async fn update_fee_amount(&self, trade_id: &str, fee_amount: &str) -> DbResult<bool> {
         let result = sqlx::query(
-            r#"UPDATE trades SET "settlementTxHash" = $1 WHERE "tradeId" = $2"#,
+            r#"
+            UPDATE trades
+            SET "feeAmount" = $1::numeric
+            WHERE "tradeId" = $2 AND "feeAmount" IS NULL
+            "#,
         )
-        .bind(settlement_tx_hash)
+        .bind(fee_amount)
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected() == 1)
+    }
+    
+    async fn save_pdf(&self, trade_id: &str, pdf_data: &[u8], filename: &str, sha256: &str) -> DbResult<DateTime<Utc>> {
+        let uploaded_at = Utc::now();
+        
+        let result = sqlx::query(
+            r#"
+            UPDATE trades 
+            SET pdf_file = $1, pdf_filename = $2, pdf_uploaded_at = $3, pdf_sha256 = $4, pdf_storage_key = NULL
+            WHERE "tradeId" = $5
+            "#,
+        )
+        .bind(pdf_data)
+        .bind(filename)
+        .bind(uploaded_at)
+        .bind(sha256)
         .bind(trade_id)
         .execute(&self.pool)
         .await?;
@@ -188,22 +389,23 @@ impl TradeRepository for PostgresTradeRepository {
             return Err(DbError::TradeNotFound(trade_id.to_string()));
         }
 
-        Ok(())
+        Ok(uploaded_at)
     }
     
-    async fn save_pdf(&self, trade_id: &str, pdf_data: &[u8], filename: &str) -> DbResult<DateTime<Utc>> {
+    async fn save_pdf_key(&self, trade_id: &str, storage_key: &str, filename: &str, sha256: &str) -> DbResult<DateTime<Utc>> {
         let uploaded_at = Utc::now();
         
         let result = sqlx::query(
             r#"
             UPDATE trades 
-            SET pdf_file = $1, pdf_filename = $2, pdf_uploaded_at = $3
-            WHERE "tradeId" = $4
+            SET pdf_storage_key = $1, pdf_filename = $2, pdf_uploaded_at = $3, pdf_sha256 = $4, pdf_file = NULL
+            WHERE "tradeId" = $5
             "#,
         )
-        .bind(pdf_data)
+        .bind(storage_key)
         .bind(filename)
         .bind(uploaded_at)
+        .bind(sha256)
         .bind(trade_id)
         .execute(&self.pool)
         .await?;
@@ -220,7 +422,7 @@ impl TradeRepository for PostgresTradeRepository {
         let result = sqlx::query(
             r#"
             UPDATE trades 
-            SET pdf_file = NULL, pdf_filename = NULL, pdf_uploaded_at = NULL,
+            SET pdf_file = NULL, pdf_filename = NULL, pdf_uploaded_at = NULL, pdf_storage_key = NULL, pdf_sha256 = NULL,
                 "transactionId" = NULL, "paymentTime" = NULL
             WHERE "tradeId" = $1
             "#,
@@ -290,26 +492,28 @@ impl TradeRepository for PostgresTradeRepository {
         Ok(())
     }
     
-    async fn get_expired_pending_trades(&self) -> DbResult<Vec<DbTrade>> {
+    async fn get_expired_pending_trades(&self, buffer_secs: u64) -> DbResult<Vec<DbTrade>> {
         // Find all trades where:
         // - status = 0 (PENDING)
-        // - expiresAt (unix timestamp) < current unix timestamp
+        // - expiresAt (unix timestamp) < current unix timestamp - buffer
         let rows = sqlx::query(
             r#"
             SELECT 
-                "tradeId", "orderId", "buyer", "tokenAmount"::text, "cnyAmount"::text, "feeAmount"::text,
+                "tradeId", "orderId", "buyer", "tokenAmount"::text, "cnyAmount"::text, "feeAmount"::text, "feeRateBps",
                 "rail", "transactionId", "paymentTime",
                 "createdAt", "expiresAt", "status",
                 "escrowTxHash", "settlementTxHash", "syncedAt",
                 pdf_file, pdf_filename, pdf_uploaded_at,
                 proof_user_public_values, proof_accumulator, proof_data,
                 axiom_proof_id, proof_generated_at, proof_json, settlement_error,
-                "chainId"
+                "chainId", currency
             FROM trades
-            WHERE status = 0 AND "expiresAt" < EXTRACT(EPOCH FROM NOW())::bigint
+            WHERE status = 0 AND "expiresAt" < $1
+              AND cancel_failed_at IS NULL
             ORDER BY "expiresAt" ASC
             "#,
         )
+        .bind(expiry_cutoff(Utc::now().timestamp(), buffer_secs))
         .fetch_all(&self.pool)
         .await?;
 
@@ -323,7 +527,8 @@ impl TradeRepository for PostgresTradeRepository {
                 token_amount: row.get("tokenAmount"),
                 cny_amount: row.get("cnyAmount"),
                 fee_amount: row.get("feeAmount"),
-                rail: row.get("rail"),
+                fee_rate_bps: row.get("feeRateBps"),
+                rail: row.try_get("rail")?,
                 transaction_id: row.get("transactionId"),
                 payment_time: row.get("paymentTime"),
                 created_at: row.get("createdAt"),
@@ -344,34 +549,39 @@ impl TradeRepository for PostgresTradeRepository {
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
                 chain_id: row.get("chainId"),
+                currency: row.get("currency"),
                 alipay_id: None, // Not needed for auto-cancellation
                 alipay_name: None, // Not needed for auto-cancellation
+                order_closed: None,
             });
         }
         Ok(trades)
     }
     
-    async fn get_all_trades(&self) -> DbResult<Vec<DbTrade>> {
+    async fn get_all_trades(&self, limit: i64, offset: i64) -> DbResult<Vec<DbTrade>> {
         let rows = sqlx::query(
             r#"
             SELECT 
-                t."tradeId", t."orderId", t."buyer", t."tokenAmount"::text, t."cnyAmount"::text, t."feeAmount"::text,
+                t."tradeId", t."orderId", t."buyer", t."tokenAmount"::text, t."cnyAmount"::text, t."feeAmount"::text, t."feeRateBps",
                 t."rail", t."transactionId", t."paymentTime",
                 t."createdAt", t."expiresAt", t."status",
                 t."escrowTxHash", t."settlementTxHash", t."syncedAt",
                 t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
                 t.proof_user_public_values, t.proof_accumulator, t.proof_data,
                 t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
-                t."chainId",
+                t."chainId", t.currency,
                 COALESCE(t.token, o.token) as token,
                 o."accountId" as "alipay_id",
-                o."accountName" as "alipay_name"
+                o."accountName" as "alipay_name",
+                (o."orderId" IS NULL OR o."remainingAmount" = 0) as order_closed
             FROM trades t
             LEFT JOIN orders o ON t."orderId" = o."orderId"
-            ORDER BY t."createdAt" DESC
-            LIMIT 100
+            ORDER BY t."createdAt" DESC, t."tradeId"
+            LIMIT $1 OFFSET $2
             "#,
         )
+        .bind(limit)
+        .bind(offset)
         .fetch_all(&self.pool)
         .await?;
 
@@ -385,7 +595,8 @@ impl TradeRepository for PostgresTradeRepository {
                 token_amount: row.get("tokenAmount"),
                 cny_amount: row.get("cnyAmount"),
                 fee_amount: row.get("feeAmount"),
-                rail: row.get("rail"),
+                fee_rate_bps: row.get("feeRateBps"),
+                rail: row.try_get("rail")?,
                 transaction_id: row.get("transactionId"),
                 payment_time: row.get("paymentTime"),
                 created_at: row.get("createdAt"),
@@ -406,8 +617,10 @@ impl TradeRepository for PostgresTradeRepository {
                 proof_json: row.get("proof_json"),
                 settlement_error: row.get("settlement_error"),
                 chain_id: row.get("chainId"),
+                currency: row.get("currency"),
                 alipay_id: row.get("alipay_id"),
                 alipay_name: row.get("alipay_name"),
+                order_closed: row.get("order_closed"),
             });
         }
         Ok(trades)
@@ -429,6 +642,22 @@ impl TradeRepository for PostgresTradeRepository {
         Ok(())
     }
     
+    async fn mark_cancel_failed(&self, trade_id: &str, error: &str) -> DbResult<()> {
+        let result = sqlx::query(
+            r#"UPDATE trades SET cancel_failed_at = NOW(), cancel_error = $1 WHERE "tradeId" = $2"#,
+        )
+        .bind(error)
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+
+        if result.rows_affected() == 0 {
+            return Err(DbError::TradeNotFound(trade_id.to_string()));
+        }
+
+        Ok(())
+    }
+    
     async fn is_transaction_id_used(&self, transaction_id: &str) -> DbResult<bool> {
         // Check if any SETTLED trade (status=1) has this transaction ID
         let result: Option<(i32,)> = sqlx::query_as(
@@ -448,17 +677,144 @@ impl TradeRepository for PostgresTradeRepository {
 }
 
 impl PostgresTradeRepository {
+    /// Where a trade's receipt PDF lives (bucket key or inline bytes)
+    pub async fn get_pdf(&self, trade_id: &str) -> DbResult<DbTradePdf> {
+        let pdf = sqlx::query_as::<_, DbTradePdf>(
+            r#"
+            SELECT pdf_storage_key, pdf_file, pdf_filename, pdf_sha256
+            FROM trades
+            WHERE "tradeId" = $1
+            "#,
+        )
+        .bind(trade_id)
+        .fetch_optional(&self.pool)
+        .await?
+        .ok_or_else(|| DbError::TradeNotFound(trade_id.to_string()))?;
+        
+        Ok(pdf)
+    }
+    
+    /// Pending trades expiring within `lead_secs` whose buyer hasn't been reminded yet
+    pub async fn get_trades_near_expiry(&self, lead_secs: i64) -> DbResult<Vec<DbExpiringTrade>> {
+        let trades = sqlx::query_as::<_, DbExpiringTrade>(
+            r#"
+            SELECT
+                t."tradeId" AS trade_id, t."orderId" AS order_id, t.buyer,
+                t."tokenAmount"::text AS token_amount, t."cnyAmount"::text AS cny_amount,
+                t.currency, t."expiresAt" AS expires_at, o.token
+            FROM trades t
+            JOIN orders o ON t."orderId" = o."orderId"
+            WHERE t.status = 0
+              AND t.expiry_reminder_sent_at IS NULL
+              AND t."expiresAt" > EXTRACT(EPOCH FROM NOW())::bigint
+              AND t."expiresAt" <= EXTRACT(EPOCH FROM NOW())::bigint + $1
+            ORDER BY t."expiresAt" ASC
+            "#,
+        )
+        .bind(lead_secs)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(trades)
+    }
+    
+    /// Pending trades already past expiry (oldest first), including ones auto-cancel gave up on
+    pub async fn get_stuck_trades(&self, limit: i64) -> DbResult<Vec<DbStuckTrade>> {
+        let trades = sqlx::query_as::<_, DbStuckTrade>(
+            r#"
+            SELECT
+                "tradeId" AS trade_id, "orderId" AS order_id, "chainId" AS chain_id, buyer,
+                "expiresAt" AS expires_at, cancel_failed_at, cancel_error
+            FROM trades
+            WHERE status = 0 AND "expiresAt" < EXTRACT(EPOCH FROM NOW())::bigint
+            ORDER BY "expiresAt" ASC
+            LIMIT $1
+            "#,
+        )
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(trades)
+    }
+    
+    /// Trade counts and token volume per (token, status), aggregated in SQL.
+    /// Optional chain filter and [from, to) window on trade creation time.
+    pub async fn stats(&self, chain_id: Option<i32>, from: Option<i64>, to: Option<i64>) -> DbResult<Vec<DbTradeStatsRow>> {
+        let rows = sqlx::query_as::<_, DbTradeStatsRow>(
+            r#"
+            SELECT
+                o.token, t.status,
+                COUNT(*) AS trade_count,
+                COALESCE(SUM(t."tokenAmount"), 0)::text AS token_volume
+            FROM trades t
+            JOIN orders o ON t."orderId" = o."orderId"
+            WHERE ($1::int IS NULL OR t."chainId" = $1)
+              AND ($2::bigint IS NULL OR t."createdAt" >= $2)
+              AND ($3::bigint IS NULL OR t."createdAt" < $3)
+            GROUP BY o.token, t.status
+            ORDER BY o.token, t.status
+            "#,
+        )
+        .bind(chain_id)
+        .bind(from)
+        .bind(to)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Active public orders and trades (total, pending, settled) on one chain, counted in SQL
+    pub async fn chain_counts(&self, chain_id: i32) -> DbResult<DbChainCounts> {
+        let counts = sqlx::query_as::<_, DbChainCounts>(
+            r#"
+            SELECT
+                (SELECT COUNT(*) FROM orders
+                 WHERE "chainId" = $1 AND closed_at IS NULL AND delisted_at IS NULL
+                   AND "remainingAmount" > 0 AND "isPublic" = true) AS orders,
+                COUNT(*) AS trades,
+                COUNT(*) FILTER (WHERE status = 0) AS trades_pending,
+                COUNT(*) FILTER (WHERE status = 1) AS trades_settled
+            FROM trades
+            WHERE "chainId" = $1
+            "#,
+        )
+        .bind(chain_id)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok(counts)
+    }
+    
+    /// Claim a trade's expiry reminder; false if it was already sent (or claimed elsewhere)
+    pub async fn mark_expiry_reminder_sent(&self, trade_id: &str) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE trades
+            SET expiry_reminder_sent_at = NOW()
+            WHERE "tradeId" = $1 AND expiry_reminder_sent_at IS NULL
+            "#,
+        )
+        .bind(trade_id)
+        .execute(&self.pool)
+        .await?;
+        
+        Ok(result.rows_affected() == 1)
+    }
+    
     /// Helper to map a joined trade row to DbTrade (includes token, alipay_id, alipay_name from orders JOIN)
-    fn map_joined_row(row: sqlx::postgres::PgRow) -> DbTrade {
+    fn map_joined_row(row: sqlx::postgres::PgRow) -> DbResult<DbTrade> {
         use sqlx::Row;
-        DbTrade {
+        Ok(DbTrade {
             trade_id: row.get("tradeId"),
             order_id: row.get("orderId"),
             buyer: row.get("buyer"),
             token_amount: row.get("tokenAmount"),
             cny_amount: row.get("cnyAmount"),
             fee_amount: row.get("feeAmount"),
-            rail: row.get("rail"),
+            fee_rate_bps: row.get("feeRateBps"),
+            rail: row.try_get("rail")?,
             transaction_id: row.get("transactionId"),
             payment_time: row.get("paymentTime"),
             created_at: row.get("createdAt"),
@@ -479,9 +835,11 @@ impl PostgresTradeRepository {
             proof_json: row.get("proof_json"),
             settlement_error: row.get("settlement_error"),
             chain_id: row.get("chainId"),
+            currency: row.get("currency"),
             alipay_id: row.get("alipay_id"),
             alipay_name: row.get("alipay_name"),
-        }
+            order_closed: row.get("order_closed"),
+        })
     }
     
     /// Get all settled trades for an order (status=1), sorted by creation time descending
@@ -491,17 +849,18 @@ impl PostgresTradeRepository {
             r#"
             SELECT 
                 t."tradeId", t."orderId", t.buyer,
-                t."tokenAmount"::TEXT, t."cnyAmount"::TEXT, t."feeAmount"::TEXT,
+                t."tokenAmount"::TEXT, t."cnyAmount"::TEXT, t."feeAmount"::TEXT, t."feeRateBps",
                 t.rail, t."transactionId", t."paymentTime",
                 t."createdAt", t."expiresAt", t.status,
                 t."syncedAt", t."escrowTxHash", t."settlementTxHash",
                 t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
                 t.proof_user_public_values, t.proof_accumulator, t.proof_data,
                 t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
-                t."chainId",
+                t."chainId", t.currency,
                 COALESCE(t.token, o.token) as token,
                 o."accountId" as "alipay_id",
-                o."accountName" as "alipay_name"
+                o."accountName" as "alipay_name",
+                (o."orderId" IS NULL OR o."remainingAmount" = 0) as order_closed
             FROM trades t
             LEFT JOIN orders o ON t."orderId" = o."orderId"
             WHERE t."orderId" = $1 AND t.status = 1
@@ -512,7 +871,7 @@ impl PostgresTradeRepository {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows.into_iter().map(Self::map_joined_row).collect())
+        rows.into_iter().map(Self::map_joined_row).collect()
     }
     
     /// Get all trades for an order (all statuses), sorted by creation time descending
@@ -522,17 +881,18 @@ impl PostgresTradeRepository {
             r#"
             SELECT 
                 t."tradeId", t."orderId", t.buyer,
-                t."tokenAmount"::TEXT, t."cnyAmount"::TEXT, t."feeAmount"::TEXT,
+                t."tokenAmount"::TEXT, t."cnyAmount"::TEXT, t."feeAmount"::TEXT, t."feeRateBps",
                 t.rail, t."transactionId", t."paymentTime",
                 t."createdAt", t."expiresAt", t.status,
                 t."syncedAt", t."escrowTxHash", t."settlementTxHash",
                 t.pdf_file, t.pdf_filename, t.pdf_uploaded_at,
                 t.proof_user_public_values, t.proof_accumulator, t.proof_data,
                 t.axiom_proof_id, t.proof_generated_at, t.proof_json, t.settlement_error,
-                t."chainId",
+                t."chainId", t.currency,
                 COALESCE(t.token, o.token) as token,
                 o."accountId" as "alipay_id",
-                o."accountName" as "alipay_name"
+                o."accountName" as "alipay_name",
+                (o."orderId" IS NULL OR o."remainingAmount" = 0) as order_closed
             FROM trades t
             LEFT JOIN orders o ON t."orderId" = o."orderId"
             WHERE t."orderId" = $1
@@ -543,6 +903,141 @@ impl PostgresTradeRepository {
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows.into_iter().map(Self::map_joined_row).collect())
+        rows.into_iter().map(Self::map_joined_row).collect()
+    }
+    
+    /// List a buyer's or seller's trades with optional status filter and pagination
+    /// Returns (page, total matching the filter)
+    pub async fn list_for_party(&self, list: &TradeListQuery) -> DbResult<(Vec<DbTrade>, i64)> {
+        list.validate()?;
+        
+        let rows = list.build_page_query().build().fetch_all(&self.pool).await?;
+        let total: i64 = list.build_count_query().build_query_scalar().fetch_one(&self.pool).await?;
+        
+        Ok((rows.into_iter().map(Self::map_joined_row).collect::<DbResult<_>>()?, total))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::{fixtures, DbOrder};
+
+    #[test]
+    fn test_expiry_buffer_holds_back_recent_expiries() {
+        let now = 1_700_000_000;
+        let cutoff = expiry_cutoff(now, 30);
+        // Expired 10s ago: still inside the 30s buffer
+        assert!(now - 10 >= cutoff);
+        // Expired 31s ago: returned
+        assert!(now - 31 < cutoff);
+        // No buffer: anything already expired
+        assert!(now - 1 < expiry_cutoff(now, 0));
+        // An absurd buffer doesn't overflow
+        assert!(expiry_cutoff(now, u64::MAX) < 0);
+    }
+
+    /// Store `orders` and `trades`, returning the trade repository
+    async fn repo_with(pool: PgPool, orders: &[DbOrder], trades: &[DbTrade]) -> PostgresTradeRepository {
+        use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+
+        let order_repo = PostgresOrderRepository::new(pool.clone());
+        for order in orders {
+            order_repo.create(order).await.unwrap();
+        }
+        let repo = PostgresTradeRepository::new(pool);
+        for trade in trades {
+            repo.create(trade).await.unwrap();
+        }
+        repo
+    }
+
+    /// Trade `trade_id` by `buyer` on the fixture order
+    fn trade_at(trade_id: &str, buyer: &str, status: i32, created_at: i64) -> DbTrade {
+        DbTrade { buyer: buyer.to_string(), created_at, ..fixtures::trade(trade_id, status) }
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_pending_only_page_two(pool: PgPool) {
+        let buyer = "0xabc0000000000000000000000000000000000001";
+        let mut trades: Vec<DbTrade> = (0..25)
+            .map(|i| trade_at(&format!("0x{:02}", i), buyer, 0, 1_700_000_000 + i))
+            .collect();
+        trades.push(trade_at("0xsettled", buyer, 1, 1_800_000_000));
+        trades.push(trade_at("0xother", "0xabc0000000000000000000000000000000000002", 0, 1_800_000_000));
+        let repo = repo_with(pool, &[fixtures::order("0xorder")], &trades).await;
+
+        // Matched case-insensitively; newest first, so page two holds the five oldest
+        let list = TradeListQuery {
+            party: TradeParty::Buyer("0xAbC0000000000000000000000000000000000001".to_string()),
+            status: Some(0),
+            limit: 20,
+            offset: 20,
+        };
+        let (page, total) = repo.list_for_party(&list).await.unwrap();
+        let ids: Vec<&str> = page.iter().map(|t| t.trade_id.as_str()).collect();
+        assert_eq!(ids, ["0x04", "0x03", "0x02", "0x01", "0x00"]);
+        // Total is counted over the same filter, without paging
+        assert_eq!(total, 25);
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_seller_listing_rejects_unknown_status(pool: PgPool) {
+        let seller = "0x5e11e40000000000000000000000000000000001";
+        let orders = [
+            DbOrder { seller: seller.to_string(), ..fixtures::order("0xmine") },
+            fixtures::order("0xtheirs"),
+        ];
+        let trades = [
+            DbTrade { order_id: "0xmine".to_string(), ..trade_at("0x01", "0xbuyer", 0, 1) },
+            DbTrade { order_id: "0xmine".to_string(), ..trade_at("0x02", "0xbuyer", 2, 2) },
+            DbTrade { order_id: "0xtheirs".to_string(), ..trade_at("0x03", "0xbuyer", 0, 3) },
+        ];
+        let repo = repo_with(pool, &orders, &trades).await;
+
+        let mut list = TradeListQuery {
+            party: TradeParty::Seller(seller.to_uppercase().replace("0X", "0x")),
+            status: None,
+            limit: 50,
+            offset: 0,
+        };
+        let (page, total) = repo.list_for_party(&list).await.unwrap();
+        assert_eq!(page.iter().map(|t| t.trade_id.as_str()).collect::<Vec<_>>(), ["0x02", "0x01"]);
+        assert_eq!(total, 2);
+
+        list.status = Some(3);
+        assert!(matches!(list.validate(), Err(DbError::InvalidInput(_))));
+        assert!(matches!(repo.list_for_party(&list).await, Err(DbError::InvalidInput(_))));
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_trade_with_closed_order_still_listed(pool: PgPool) {
+        let orders = [
+            fixtures::order("0xopen"),
+            DbOrder { remaining_amount: "0".to_string(), ..fixtures::order("0xfilled") },
+        ];
+        let trades = [
+            DbTrade { order_id: "0xopen".to_string(), ..trade_at("0x01", "0xbuyer", 1, 1) },
+            DbTrade { order_id: "0xfilled".to_string(), ..trade_at("0x02", "0xbuyer", 1, 2) },
+        ];
+        let repo = repo_with(pool, &orders, &trades).await;
+
+        // The order join never filters trades out, and closed orders are flagged
+        let list = TradeListQuery {
+            party: TradeParty::Buyer("0xbuyer".to_string()),
+            status: Some(1),
+            limit: 10,
+            offset: 0,
+        };
+        let (page, total) = repo.list_for_party(&list).await.unwrap();
+        let flags: Vec<(&str, Option<bool>)> = page.iter().map(|t| (t.trade_id.as_str(), t.order_closed)).collect();
+        assert_eq!(flags, [("0x02", Some(true)), ("0x01", Some(false))]);
+        assert_eq!(total, 2);
     }
 }
@@ -1,7 +1,43 @@
-use sqlx::PgPool;
+use sqlx::{PgExecutor, PgPool};
 
 use super::DbResult;
-use super::models::DbWithdrawal;
+use super::models::{DbSellerWithdrawal, DbWithdrawal};
+use crate::blockchain::types::WithdrawalLog;
+
+// Writes the event listener applies: they take any executor, so an event's
+// writes can share a transaction with its processed_events claim
+
+/// Insert a withdrawal from its OrderWithdrawn event
+pub(crate) async fn insert_withdrawal<'e>(executor: impl PgExecutor<'e>, withdrawal: &WithdrawalLog) -> DbResult<()> {
+    sqlx::query(
+        r#"
+        INSERT INTO withdrawals ("orderId", "amount", "remainingAfter", "txHash", "blockNumber")
+        VALUES ($1, $2::numeric, $3::numeric, $4, $5)
+        "#,
+    )
+    .bind(&withdrawal.order_id)
+    .bind(&withdrawal.amount)
+    .bind(&withdrawal.remaining_after)
+    .bind(&withdrawal.tx_hash)
+    .bind(withdrawal.block_number as i64)
+    .execute(executor)
+    .await?;
+    
+    Ok(())
+}
+
+/// Whether the withdrawal made by `tx_hash` is already recorded for the order
+pub(crate) async fn withdrawal_exists<'e>(executor: impl PgExecutor<'e>, order_id: &str, tx_hash: &str) -> DbResult<bool> {
+    let row: (bool,) = sqlx::query_as(
+        r#"SELECT EXISTS(SELECT 1 FROM withdrawals WHERE "orderId" = $1 AND "txHash" = $2)"#,
+    )
+    .bind(order_id)
+    .bind(tx_hash)
+    .fetch_one(executor)
+    .await?;
+    
+    Ok(row.0)
+}
 
 /// Repository for Withdrawal operations - tracks withdrawal history for order activity timeline
 pub struct PostgresWithdrawalRepository {
@@ -14,21 +50,61 @@ impl PostgresWithdrawalRepository {
     }
     
     /// Insert new withdrawal record
-    pub async fn create(&self, order_id: &str, amount: &str, remaining_after: &str, tx_hash: Option<&str>) -> DbResult<()> {
-        sqlx::query(
+    pub async fn create(&self, withdrawal: &WithdrawalLog) -> DbResult<()> {
+        insert_withdrawal(&self.pool, withdrawal).await
+    }
+    
+    /// Withdrawals on `chain_id` recorded without a transaction hash (oldest first)
+    pub async fn get_missing_tx_hash(&self, chain_id: i32) -> DbResult<Vec<DbWithdrawal>> {
+        let rows = sqlx::query_as::<_, DbWithdrawal>(
             r#"
-            INSERT INTO withdrawals ("orderId", "amount", "remainingAfter", "txHash")
-            VALUES ($1, $2::numeric, $3::numeric, $4)
+            SELECT
+                w.id,
+                w."orderId",
+                w."amount"::TEXT AS "amount",
+                w."remainingAfter"::TEXT AS "remainingAfter",
+                w."txHash",
+                w."createdAt"
+            FROM withdrawals w
+            JOIN orders o ON o."orderId" = w."orderId"
+            WHERE w."txHash" IS NULL AND o."chainId" = $1
+            ORDER BY w."createdAt", w.id
             "#,
         )
-        .bind(order_id)
-        .bind(amount)
-        .bind(remaining_after)
+        .bind(chain_id)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        Ok(rows)
+    }
+    
+    /// Fill in a withdrawal's transaction; false if the row already has a hash or
+    /// another withdrawal of the order was recorded with this one
+    pub async fn set_tx_hash(&self, id: i32, tx_hash: &str, block_number: u64) -> DbResult<bool> {
+        let result = sqlx::query(
+            r#"
+            UPDATE withdrawals w
+            SET "txHash" = $2, "blockNumber" = $3
+            WHERE w.id = $1
+              AND w."txHash" IS NULL
+              AND NOT EXISTS (
+                  SELECT 1 FROM withdrawals other
+                  WHERE other."orderId" = w."orderId" AND other."txHash" = $2
+              )
+            "#,
+        )
+        .bind(id)
         .bind(tx_hash)
+        .bind(block_number as i64)
         .execute(&self.pool)
         .await?;
         
-        Ok(())
+        Ok(result.rows_affected() > 0)
+    }
+    
+    /// Check whether a withdrawal from the given transaction is already recorded
+    pub async fn exists(&self, order_id: &str, tx_hash: &str) -> DbResult<bool> {
+        withdrawal_exists(&self.pool, order_id, tx_hash).await
     }
     
     /// Get all withdrawals for an order, sorted by creation time descending
@@ -68,4 +144,48 @@ impl PostgresWithdrawalRepository {
         
         Ok(withdrawals)
     }
+    
+    /// One page of a seller's withdrawals across all their orders (newest first),
+    /// plus the total across all pages
+    pub async fn get_by_seller(&self, seller: &str, limit: i64, offset: i64) -> DbResult<(Vec<DbSellerWithdrawal>, i64)> {
+        // Address normalized the same way as the stored value: lowercase, no 0x
+        let seller = seller.to_lowercase().trim_start_matches("0x").to_string();
+        
+        let rows = sqlx::query_as::<_, DbSellerWithdrawal>(
+            r#"
+            SELECT
+                w."orderId" AS order_id,
+                o.token,
+                o."chainId" AS chain_id,
+                w."amount"::TEXT AS amount,
+                w."remainingAfter"::TEXT AS remaining_after,
+                w."txHash" AS tx_hash,
+                w."createdAt" AS created_at
+            FROM withdrawals w
+            JOIN orders o ON o."orderId" = w."orderId"
+            WHERE LOWER(REPLACE(o.seller, '0x', '')) = $1
+            ORDER BY w."createdAt" DESC, w.id DESC
+            LIMIT $2 OFFSET $3
+            "#,
+        )
+        .bind(&seller)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.pool)
+        .await?;
+        
+        let (total,): (i64,) = sqlx::query_as(
+            r#"
+            SELECT COUNT(*)
+            FROM withdrawals w
+            JOIN orders o ON o."orderId" = w."orderId"
+            WHERE LOWER(REPLACE(o.seller, '0x', '')) = $1
+            "#,
+        )
+        .bind(&seller)
+        .fetch_one(&self.pool)
+        .await?;
+        
+        Ok((rows, total))
+    }
 }
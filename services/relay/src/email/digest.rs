@@ -0,0 +1,156 @@
+//! Daily activity digest
+//!
+//! Accounts that opt in (`account_emails.digest_enabled`) get one email a day,
+//! at `DIGEST_SEND_HOUR_UTC`, summarizing the previous UTC day: trades created
+//! on their orders or by them, and trades settled or expired. Accounts with no
+//! activity that day get nothing. An account is claimed for the day
+//! (`account_emails.digest_sent_for`) before its email goes out, so restarts and
+//! multiple API instances never send a day's digest twice; a digest missed
+//! while the relay was down goes out when it starts.
+
+use std::sync::Arc;
+
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+
+use super::{EmailEvent, EmailInfo, EmailService};
+use crate::db::models::DbAccountActivity;
+use crate::db::Database;
+
+/// Default send time (01:00 UTC)
+pub const DEFAULT_SEND_HOUR_UTC: u32 = 1;
+
+/// Most recent send time (`hour`:00 UTC) at or before `now`
+pub fn last_send_time(now: DateTime<Utc>, hour: u32) -> DateTime<Utc> {
+    let today = Utc.from_utc_datetime(&now.date_naive().and_hms_opt(hour, 0, 0).expect("hour < 24"));
+    if today <= now {
+        today
+    } else {
+        today - Duration::days(1)
+    }
+}
+
+/// The day a digest sent at `send_time` covers (the previous UTC day)
+pub fn digest_day(send_time: DateTime<Utc>) -> NaiveDate {
+    send_time.date_naive() - Duration::days(1)
+}
+
+/// [start, end) of a UTC day as unix timestamps
+pub fn day_window(day: NaiveDate) -> (i64, i64) {
+    let start = Utc.from_utc_datetime(&day.and_hms_opt(0, 0, 0).expect("midnight")).timestamp();
+    (start, start + 86_400)
+}
+
+/// Digest email content, or None if the account had no activity that day
+pub fn digest_info(activity: &DbAccountActivity, day: NaiveDate) -> Option<EmailInfo> {
+    if *activity == DbAccountActivity::default() {
+        return None;
+    }
+    Some(EmailInfo::DailyDigest {
+        date: day.format("%Y-%m-%d").to_string(),
+        new_trades: activity.new_trades,
+        settled_trades: activity.settled_trades,
+        expired_trades: activity.expired_trades,
+    })
+}
+
+/// Send `day`'s digest to every subscribed account that had activity and hasn't had it yet
+async fn send_digests(email_service: &EmailService, db: &Database, day: NaiveDate) {
+    let accounts = match db.get_digest_due_accounts(day).await {
+        Ok(accounts) => accounts,
+        Err(e) => {
+            tracing::warn!("📊 Failed to load digest subscribers: {}", e);
+            return;
+        }
+    };
+
+    let (from, to) = day_window(day);
+    let mut sent = 0;
+    for account in &accounts {
+        let activity = match db.get_account_activity(&account.wallet, from, to).await {
+            Ok(activity) => activity,
+            Err(e) => {
+                tracing::warn!("📊 Failed to load activity for {}: {}", account.wallet, e);
+                continue;
+            }
+        };
+        let Some(info) = digest_info(&activity, day) else {
+            continue; // Nothing happened, no email
+        };
+
+        match db.claim_account_digest(&account.wallet, day).await {
+            Ok(true) => {}
+            Ok(false) => continue, // Already sent by another instance
+            Err(e) => {
+                tracing::warn!("📊 Failed to claim digest for {}: {}", account.wallet, e);
+                continue;
+            }
+        }
+
+        email_service.send_or_enqueue(
+            db.pool(),
+            &account.wallet,
+            &account.email,
+            &account.language,
+            EmailEvent::DailyDigest,
+            &info,
+        ).await;
+        sent += 1;
+    }
+    tracing::info!("📊 Daily digest for {}: {} sent ({} subscribers due)", day, sent, accounts.len());
+}
+
+/// Run the digest job forever (spawned from api-server when email is configured)
+pub async fn run(email_service: Arc<EmailService>, db: Arc<Database>) {
+    let hour = email_service.digest_hour_utc();
+    tracing::info!("📊 Daily digest job started (sends at {:02}:00 UTC)", hour);
+
+    loop {
+        let send_time = last_send_time(Utc::now(), hour);
+        send_digests(&email_service, &db, digest_day(send_time)).await;
+
+        let next = send_time + Duration::days(1);
+        let wait = (next - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(wait).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_send_time_and_covered_day() {
+        // Before today's send hour: yesterday's send time, covering the day before
+        let send_time = last_send_time(at("2024-03-10T00:30:00Z"), 1);
+        assert_eq!(send_time, at("2024-03-09T01:00:00Z"));
+        assert_eq!(digest_day(send_time), NaiveDate::from_ymd_opt(2024, 3, 8).unwrap());
+
+        // At or after it: today's, covering yesterday
+        let send_time = last_send_time(at("2024-03-10T01:00:00Z"), 1);
+        assert_eq!(send_time, at("2024-03-10T01:00:00Z"));
+        assert_eq!(digest_day(send_time), NaiveDate::from_ymd_opt(2024, 3, 9).unwrap());
+
+        let (from, to) = day_window(NaiveDate::from_ymd_opt(2024, 3, 9).unwrap());
+        assert_eq!(from, at("2024-03-09T00:00:00Z").timestamp());
+        assert_eq!(to, at("2024-03-10T00:00:00Z").timestamp());
+    }
+
+    #[test]
+    fn test_no_activity_no_email() {
+        let day = NaiveDate::from_ymd_opt(2024, 3, 9).unwrap();
+        assert!(digest_info(&DbAccountActivity::default(), day).is_none());
+
+        let activity = DbAccountActivity { new_trades: 0, settled_trades: 0, expired_trades: 2 };
+        match digest_info(&activity, day) {
+            Some(EmailInfo::DailyDigest { date, expired_trades, .. }) => {
+                assert_eq!(date, "2024-03-09");
+                assert_eq!(expired_trades, 2);
+            }
+            other => panic!("unexpected info {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+//! Email languages
+//!
+//! Accounts store a language code with their email address; notifications are
+//! rendered in it. Codes are normalized when saved ("zh_cn", "zh-Hans" and
+//! "en-US" all map to a supported code) and anything without templates falls
+//! back to EMAIL_FALLBACK_LANGUAGE (default English), both on write and when
+//! rendering rows saved before normalization existed.
+
+/// Languages with a full template set
+pub const SUPPORTED_LANGUAGES: [&str; 3] = ["en", "zh-CN", "zh-TW"];
+
+/// Fallback when EMAIL_FALLBACK_LANGUAGE is unset
+pub const DEFAULT_FALLBACK_LANGUAGE: &str = "en";
+
+/// The supported language `code` refers to, if any (case-insensitive, `_` or `-`)
+pub fn supported_language(code: &str) -> Option<&'static str> {
+    let code = code.trim().replace('_', "-").to_lowercase();
+    match code.as_str() {
+        "en" => Some("en"),
+        "zh-cn" | "zh-hans" | "zh-sg" | "zh" => Some("zh-CN"),
+        "zh-tw" | "zh-hant" | "zh-hk" | "zh-mo" => Some("zh-TW"),
+        _ if code.starts_with("en-") => Some("en"),
+        _ if code.starts_with("zh-hans-") => Some("zh-CN"),
+        _ if code.starts_with("zh-hant-") => Some("zh-TW"),
+        _ => None,
+    }
+}
+
+/// Supported language for `code`, or `fallback` if it has no templates
+pub fn resolve_language(code: &str, fallback: &'static str) -> &'static str {
+    supported_language(code).unwrap_or(fallback)
+}
+
+/// EMAIL_FALLBACK_LANGUAGE as given (validated by EmailService::validate_templates)
+pub fn fallback_language_setting() -> String {
+    std::env::var("EMAIL_FALLBACK_LANGUAGE").unwrap_or_else(|_| DEFAULT_FALLBACK_LANGUAGE.to_string())
+}
+
+/// EMAIL_FALLBACK_LANGUAGE resolved to a supported language (English if invalid)
+pub fn fallback_language() -> &'static str {
+    supported_language(&fallback_language_setting()).unwrap_or(DEFAULT_FALLBACK_LANGUAGE)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_language_codes_normalized() {
+        assert_eq!(supported_language("zh_cn"), Some("zh-CN"));
+        assert_eq!(supported_language(" ZH-TW "), Some("zh-TW"));
+        assert_eq!(supported_language("zh-Hant-HK"), Some("zh-TW"));
+        assert_eq!(supported_language("en-US"), Some("en"));
+        assert_eq!(supported_language("fr"), None);
+        assert_eq!(supported_language("eng"), None);
+        for language in SUPPORTED_LANGUAGES {
+            assert_eq!(supported_language(language), Some(language));
+        }
+
+        assert_eq!(resolve_language("de-DE", "zh-CN"), "zh-CN");
+        assert_eq!(resolve_language("zh-hk", "en"), "zh-TW");
+    }
+}
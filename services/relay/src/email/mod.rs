@@ -3,12 +3,27 @@
 //! Sends notifications to accounts (wallet addresses) in their preferred language.
 //! Account-based, not role-based - any wallet can be both buyer and seller.
 
+use chrono::Utc;
 use reqwest::Client;
 use serde::{Deserialize, Serialize};
+use sqlx::PgPool;
+use std::collections::HashMap;
 use std::sync::Arc;
-use tracing::{info, error};
+use std::time::{Duration, Instant};
+use tracing::{info, warn, error};
 
+use crate::currency::{parse_amount, Currency, InvalidAmount};
+use crate::db::email_outbox::{EmailOutboxRepository, OutboxEmail};
+use crate::rail::PaymentRail;
+
+pub mod digest;
+pub mod language;
+pub mod order_created;
+pub mod outbox;
+pub mod reminders;
 mod templates;
+pub mod unsubscribe;
+pub mod verification;
 pub use templates::*;
 
 /// Email event types - covers all notification scenarios
@@ -32,6 +47,40 @@ pub enum EmailEvent {
     TradeExpiredSeller,
     /// Trade expired (email to buyer)
     TradeExpiredBuyer,
+    /// Pending trade expires soon (reminder to buyer)
+    TradeExpiringSoon,
+    /// Daily summary of an account's trades (opt-in)
+    DailyDigest,
+    /// Confirm a newly stored notification address
+    EmailVerification,
+}
+
+impl EmailEvent {
+    pub const ALL: [EmailEvent; 12] = [
+        EmailEvent::OrderCreated,
+        EmailEvent::OrderWithdrawn,
+        EmailEvent::OrderUpdated,
+        EmailEvent::TradeCreatedSeller,
+        EmailEvent::TradeCreatedBuyer,
+        EmailEvent::TradeSettledSeller,
+        EmailEvent::TradeSettledBuyer,
+        EmailEvent::TradeExpiredSeller,
+        EmailEvent::TradeExpiredBuyer,
+        EmailEvent::TradeExpiringSoon,
+        EmailEvent::DailyDigest,
+        EmailEvent::EmailVerification,
+    ];
+}
+
+/// Template set problems found at startup by `EmailService::validate_templates`
+#[derive(Debug, thiserror::Error)]
+pub enum EmailTemplateError {
+    #[error("EMAIL_FALLBACK_LANGUAGE '{0}' has no templates (supported: en, zh-CN, zh-TW)")]
+    UnsupportedFallback(String),
+    #[error("No email templates for language '{0}'")]
+    MissingLanguage(&'static str),
+    #[error("No {language} email template for {event:?}")]
+    MissingTemplate { event: EmailEvent, language: &'static str },
 }
 
 /// Email info variants for different event types
@@ -42,10 +91,12 @@ pub enum EmailInfo {
         order_id: String,
         token_amount: String,
         token_symbol: String,
+        /// Minor units of `currency` per token
         exchange_rate: String,
+        currency: Currency,
         account_id: String,
         account_name: String,
-        rail: i32,  // 0 = Alipay, 1 = WeChat (localized in template)
+        rail: PaymentRail,  // Localized in template
         is_private: bool,
         private_code: Option<String>,
     },
@@ -59,15 +110,17 @@ pub enum EmailInfo {
     /// Seller updated exchange rate
     ExchangeRateUpdated {
         order_id: String,
+        /// Minor units of `currency` per token
         old_rate: String,
         new_rate: String,
+        currency: Currency,
     },
     /// Seller updated payment info
     PaymentInfoUpdated {
         order_id: String,
         new_account_id: String,
         new_account_name: String,
-        rail: i32,  // 0 = Alipay, 1 = WeChat
+        rail: PaymentRail,
     },
     /// Someone bought from seller's order
     TradeCreatedSeller {
@@ -75,12 +128,14 @@ pub enum EmailInfo {
         trade_id: String,
         token_amount: String,
         token_symbol: String,
+        /// Fiat amount in minor units of `currency`
         cny_amount: String,
+        currency: Currency,
         fee_amount: String,
         buyer_address: String,
         account_id: String,
         account_name: String,
-        rail: i32,  // 0 = Alipay, 1 = WeChat
+        rail: PaymentRail,
     },
     /// Buyer initiated a purchase
     TradeCreatedBuyer {
@@ -88,10 +143,12 @@ pub enum EmailInfo {
         trade_id: String,
         token_amount: String,
         token_symbol: String,
+        /// Fiat amount in minor units of `currency`
         cny_amount: String,
+        currency: Currency,
         seller_account_id: String,
         seller_account_name: String,
-        rail: i32,  // 0 = Alipay, 1 = WeChat
+        rail: PaymentRail,
         expires_at: u64,
     },
     /// Trade settled - email to seller
@@ -100,7 +157,9 @@ pub enum EmailInfo {
         trade_id: String,
         token_amount: String,
         token_symbol: String,
+        /// Fiat amount in minor units of `currency`
         cny_amount: String,
+        currency: Currency,
         fee_amount: String,
         buyer_address: String,
         settlement_tx: String,
@@ -121,7 +180,9 @@ pub enum EmailInfo {
         trade_id: String,
         token_amount: String,
         token_symbol: String,
+        /// Fiat amount in minor units of `currency`
         cny_amount: String,
+        currency: Currency,
     },
     /// Trade expired (buyer)
     TradeExpiredBuyer {
@@ -129,7 +190,33 @@ pub enum EmailInfo {
         trade_id: String,
         token_amount: String,
         token_symbol: String,
+        /// Fiat amount in minor units of `currency`
         cny_amount: String,
+        currency: Currency,
+    },
+    /// Pending trade about to expire (buyer)
+    TradeExpiringSoon {
+        order_id: String,
+        trade_id: String,
+        token_amount: String,
+        token_symbol: String,
+        /// Fiat amount in minor units of `currency`
+        cny_amount: String,
+        currency: Currency,
+        expires_at: u64,
+    },
+    /// Daily activity summary for one UTC day
+    DailyDigest {
+        date: String,
+        new_trades: i64,
+        settled_trades: i64,
+        expired_trades: i64,
+    },
+    /// Confirmation link for a newly stored address
+    EmailVerification {
+        email: String,
+        confirm_url: String,
+        valid_hours: u64,
     },
 }
 
@@ -139,6 +226,19 @@ pub struct EmailConfig {
     pub api_key: String,
     pub from_email: String,
     pub app_url: String,
+    /// Public base URL of this relay API (unsubscribe links point here)
+    pub api_url: String,
+    /// Remind buyers this many seconds before a pending trade expires (0 = off)
+    pub expiry_reminder_lead_secs: u64,
+    /// Send the order-created email (order made private) if the visibility
+    /// endpoint wasn't called within this many seconds (0 = off)
+    pub order_created_grace_secs: u64,
+    /// Language for accounts whose stored language has no templates (EMAIL_FALLBACK_LANGUAGE)
+    pub fallback_language: String,
+    /// UTC hour (0-23) at which daily digests go out
+    pub digest_hour_utc: u32,
+    /// How long email confirmation links stay valid (EMAIL_VERIFICATION_TTL_SECS)
+    pub verification_ttl_secs: u64,
 }
 
 impl EmailConfig {
@@ -148,11 +248,37 @@ impl EmailConfig {
             .unwrap_or_else(|_| "LyncZ <noreply@lync-z.xyz>".to_string());
         let app_url = std::env::var("APP_URL")
             .unwrap_or_else(|_| "https://lync-z.xyz".to_string());
+        let api_url = std::env::var("API_PUBLIC_URL")
+            .unwrap_or_else(|_| app_url.clone());
+        let expiry_reminder_lead_secs = std::env::var("TRADE_EXPIRY_REMINDER_LEAD_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(reminders::DEFAULT_LEAD_SECS);
+        let order_created_grace_secs = std::env::var("ORDER_CREATED_EMAIL_GRACE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(order_created::DEFAULT_GRACE_SECS);
+        let digest_hour_utc = std::env::var("DIGEST_SEND_HOUR_UTC")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|hour| *hour < 24)
+            .unwrap_or(digest::DEFAULT_SEND_HOUR_UTC);
+        let verification_ttl_secs = std::env::var("EMAIL_VERIFICATION_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs: &u64| secs > 0)
+            .unwrap_or(verification::DEFAULT_TTL_SECS);
         
         Some(Self {
             api_key,
             from_email,
             app_url,
+            api_url,
+            expiry_reminder_lead_secs,
+            order_created_grace_secs,
+            fallback_language: language::fallback_language_setting(),
+            digest_hour_utc,
+            verification_ttl_secs,
         })
     }
 }
@@ -164,6 +290,8 @@ struct ResendEmailRequest {
     to: Vec<String>,
     subject: String,
     html: String,
+    #[serde(skip_serializing_if = "HashMap::is_empty")]
+    headers: HashMap<String, String>,
 }
 
 /// Resend API response
@@ -172,10 +300,32 @@ struct ResendEmailResponse {
     id: Option<String>,
 }
 
+/// Longest /health waits on Resend before calling email unhealthy
+pub const HEALTH_CHECK_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// How long a Resend health result is reused (keeps frequent /health probes
+/// off Resend's rate limit)
+pub const HEALTH_CHECK_CACHE_TTL: Duration = Duration::from_secs(60);
+
+/// Error body of a Resend API response (`{"statusCode": 401, "name": "restricted_api_key", ...}`)
+#[derive(Deserialize)]
+struct ResendError {
+    name: String,
+}
+
+/// Whether a failed health-check read was refused only because the key is
+/// send-only (Resend's `restricted_api_key`) - a valid key for our purposes
+fn is_restricted_key_response(status: reqwest::StatusCode, body: &str) -> bool {
+    status == reqwest::StatusCode::UNAUTHORIZED
+        && serde_json::from_str::<ResendError>(body).is_ok_and(|e| e.name == "restricted_api_key")
+}
+
 /// Email service for sending notifications
 pub struct EmailService {
     client: Client,
     config: EmailConfig,
+    /// Last health check result and when it was taken
+    health: std::sync::Mutex<Option<(bool, Instant)>>,
 }
 
 impl EmailService {
@@ -183,7 +333,47 @@ impl EmailService {
         Self {
             client: Client::new(),
             config,
+            health: std::sync::Mutex::new(None),
+        }
+    }
+    
+    /// Whether Resend accepts our API key, via a cheap authenticated read
+    /// (list domains). A send-only key is refused that read with
+    /// `restricted_api_key`, which still proves the key is valid, so it counts
+    /// as healthy. Cached for HEALTH_CHECK_CACHE_TTL; a request slower than
+    /// HEALTH_CHECK_TIMEOUT counts as unhealthy.
+    pub async fn health_check(&self) -> bool {
+        let cached = *self.health.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some((healthy, at)) = cached {
+            if at.elapsed() < HEALTH_CHECK_CACHE_TTL {
+                return healthy;
+            }
         }
+        
+        let healthy = match self.client
+            .get("https://api.resend.com/domains")
+            .header("Authorization", format!("Bearer {}", self.config.api_key))
+            .timeout(HEALTH_CHECK_TIMEOUT)
+            .send()
+            .await
+        {
+            Ok(response) if response.status().is_success() => true,
+            Ok(response) => {
+                let status = response.status();
+                let body = response.text().await.unwrap_or_default();
+                let key_accepted = is_restricted_key_response(status, &body);
+                if !key_accepted {
+                    warn!("📧 Resend health check failed: {} {}", status, body);
+                }
+                key_accepted
+            }
+            Err(e) => {
+                warn!("📧 Resend health check failed: {}", e);
+                false
+            }
+        };
+        *self.health.lock().unwrap_or_else(|e| e.into_inner()) = Some((healthy, Instant::now()));
+        healthy
     }
     
     /// Create from environment variables
@@ -191,25 +381,155 @@ impl EmailService {
         EmailConfig::from_env().map(|config| Arc::new(Self::new(config)))
     }
     
+    /// Lead time for trade expiry reminders in seconds (0 = disabled)
+    pub fn expiry_reminder_lead_secs(&self) -> u64 {
+        self.config.expiry_reminder_lead_secs
+    }
+    
+    /// Grace before the order-created fallback email in seconds (0 = disabled)
+    pub fn order_created_grace_secs(&self) -> u64 {
+        self.config.order_created_grace_secs
+    }
+    
+    /// UTC hour at which daily digests are sent
+    pub fn digest_hour_utc(&self) -> u32 {
+        self.config.digest_hour_utc
+    }
+    
+    /// Supported language to render for an account's stored `language`
+    fn resolve_language(&self, language: &str) -> &'static str {
+        let fallback = language::supported_language(&self.config.fallback_language)
+            .unwrap_or(language::DEFAULT_FALLBACK_LANGUAGE);
+        language::resolve_language(language, fallback)
+    }
+
+    /// Render the subject and HTML body for an event in the account's language
+    /// (the fallback language if it has no templates)
+    pub fn render(&self, language: &str, event: EmailEvent, info: &EmailInfo) -> (String, String) {
+        let template = templates::template_for(self.resolve_language(language)).unwrap_or(templates::get_email_en);
+        template(event, info, &self.config.app_url)
+    }
+
+    /// Check that the fallback language is supported and that every event has
+    /// a real template in every supported language; run once at startup
+    pub fn validate_templates(&self) -> Result<(), EmailTemplateError> {
+        if language::supported_language(&self.config.fallback_language).is_none() {
+            return Err(EmailTemplateError::UnsupportedFallback(self.config.fallback_language.clone()));
+        }
+        for language in language::SUPPORTED_LANGUAGES {
+            let template = templates::template_for(language).ok_or(EmailTemplateError::MissingLanguage(language))?;
+            for event in EmailEvent::ALL {
+                for info in sample_infos(event) {
+                    let (subject, html) = template(event, &info, &self.config.app_url);
+                    if templates::GENERIC_SUBJECTS.contains(&subject.as_str()) || html.is_empty() {
+                        return Err(EmailTemplateError::MissingTemplate { event, language });
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Render a notification for `wallet`, with its unsubscribe link in the footer.
+    /// Returns (subject, html, unsubscribe_url).
+    fn render_for_wallet(&self, wallet: &str, language: &str, event: EmailEvent, info: &EmailInfo) -> (String, String, String) {
+        let language = self.resolve_language(language);
+        let (subject, html) = self.render(language, event, info);
+        let unsubscribe_url = unsubscribe::unsubscribe_url(&self.config.api_url, wallet);
+        let html = unsubscribe::with_unsubscribe_footer(&html, language, &unsubscribe_url);
+        (subject, html, unsubscribe_url)
+    }
+
     /// Send notification email
     pub async fn send_notification(
         &self,
+        wallet: &str,
         to_email: &str,
         language: &str,
         event: EmailEvent,
         info: &EmailInfo,
     ) -> Result<(), String> {
-        let (subject, html) = match language {
-            "zh-CN" => templates::get_email_zh_cn(event, info, &self.config.app_url),
-            "zh-TW" => templates::get_email_zh_tw(event, info, &self.config.app_url),
-            _ => templates::get_email_en(event, info, &self.config.app_url),
+        let (subject, html, unsubscribe_url) = self.render_for_wallet(wallet, language, event, info);
+        self.send_email(to_email, &subject, &html, Some(&unsubscribe_url)).await?;
+        info!("📧 Notification sent to {} (event: {:?})", to_email, event);
+        Ok(())
+    }
+
+    /// Send the confirmation link for a newly stored address. No unsubscribe
+    /// link: it isn't a notification, and the address gets nothing else until confirmed.
+    pub async fn send_verification(
+        &self,
+        wallet: &str,
+        to_email: &str,
+        language: &str,
+        nonce: &str,
+    ) -> Result<(), String> {
+        let ttl_secs = self.config.verification_ttl_secs;
+        let expires_at = Utc::now().timestamp() + ttl_secs as i64;
+        let info = EmailInfo::EmailVerification {
+            email: to_email.to_string(),
+            confirm_url: verification::confirm_url(&self.config.api_url, wallet, nonce, expires_at),
+            valid_hours: ttl_secs.div_ceil(3600),
+        };
+        let (subject, html) = self.render(language, EmailEvent::EmailVerification, &info);
+        self.send_email(to_email, &subject, &html, None).await?;
+        info!("📧 Verification email sent to {} for {}", to_email, wallet);
+        Ok(())
+    }
+
+    /// Send notification email, queueing it in the outbox for retry if the send fails
+    pub async fn send_or_enqueue(
+        &self,
+        pool: &PgPool,
+        wallet: &str,
+        to_email: &str,
+        language: &str,
+        event: EmailEvent,
+        info: &EmailInfo,
+    ) {
+        let (subject, html, unsubscribe_url) = self.render_for_wallet(wallet, language, event, info);
+        let Err(e) = self.send_email(to_email, &subject, &html, Some(&unsubscribe_url)).await else {
+            info!("📧 Notification sent to {} (event: {:?})", to_email, event);
+            return;
+        };
+
+        let next_attempt_at = Utc::now() + chrono::Duration::seconds(outbox::retry_delay_secs(1));
+        let repo = EmailOutboxRepository::new(pool.clone());
+        let event_name = format!("{:?}", event);
+        let email = OutboxEmail {
+            to_email,
+            subject: &subject,
+            html: &html,
+            unsubscribe_url: Some(&unsubscribe_url),
+            event: &event_name,
         };
+        match repo.enqueue(email, &e, next_attempt_at).await {
+            Ok(id) => warn!("📮 Email to {} ({:?}) failed, queued for retry as #{}: {}", to_email, event, id, e),
+            Err(db_err) => error!("❌ Email to {} ({:?}) failed and could not be queued: {} / {}", to_email, event, e, db_err),
+        }
+    }
+
+    /// Deliver a rendered email through Resend.
+    /// With an unsubscribe URL, also sets the List-Unsubscribe headers (RFC 8058 one-click).
+    pub async fn send_email(
+        &self,
+        to_email: &str,
+        subject: &str,
+        html: &str,
+        unsubscribe_url: Option<&str>,
+    ) -> Result<(), String> {
+        let mut headers = HashMap::new();
+        if let Some(url) = unsubscribe_url {
+            headers.insert("List-Unsubscribe".to_string(), format!("<{}>", url));
+            headers.insert("List-Unsubscribe-Post".to_string(), "List-Unsubscribe=One-Click".to_string());
+        }
         
         let request = ResendEmailRequest {
             from: self.config.from_email.clone(),
             to: vec![to_email.to_string()],
-            subject,
-            html,
+            subject: subject.to_string(),
+            html: html.to_string(),
+            headers,
         };
         
         let response = self.client
@@ -224,12 +544,7 @@ impl EmailService {
         if response.status().is_success() {
             let result: ResendEmailResponse = response.json().await
                 .map_err(|e| format!("Failed to parse response: {}", e))?;
-            info!(
-                "📧 Email sent successfully to {} (id: {:?}, event: {:?})",
-                to_email,
-                result.id,
-                event
-            );
+            info!("📧 Email sent successfully to {} (id: {:?})", to_email, result.id);
             Ok(())
         } else {
             let status = response.status();
@@ -246,9 +561,6 @@ impl EmailService {
         new_hash: &str,
         trade_id: &str,
     ) -> Result<(), String> {
-        let admin_email = std::env::var("ADMIN_ALERT_EMAIL")
-            .unwrap_or_else(|_| "anonysatoshi@proton.me".to_string());
-        
         let subject = "🔑 [LyncZ Alert] Alipay Public Key Rotated";
         let html = format!(r#"
 <!DOCTYPE html>
@@ -284,11 +596,55 @@ impl EmailService {
 </html>
 "#, trade_id, old_hash, new_hash);
 
+        let admin_email = self.send_admin_alert(subject, html).await?;
+        info!(
+            "📧 Key rotation alert sent to {} (old: {}, new: {})",
+            admin_email, &old_hash[..16], &new_hash[..16]
+        );
+        Ok(())
+    }
+    
+    /// Send admin alert for relayer gas spend crossing a chain's daily budget threshold
+    pub async fn send_gas_budget_alert(
+        &self,
+        chain_id: u64,
+        level: &str,
+        day: &str,
+        spent_eth: f64,
+        budget_eth: f64,
+    ) -> Result<(), String> {
+        let subject = format!("⛽ [LyncZ Alert] Gas budget {} on chain {}", level, chain_id);
+        let html = format!(r#"
+<!DOCTYPE html>
+<html>
+<head><meta charset="UTF-8"></head>
+<body style="font-family: system-ui, sans-serif; padding: 20px;">
+    <h2>⛽ Relayer gas budget {}</h2>
+    <p>Chain <strong>{}</strong> has spent <strong>{:.6} ETH</strong> on gas on {} (UTC), against a daily budget of {:.6} ETH.</p>
+    <p>Check the relayer wallet balance and recent cancellations/settlements in the gas cost viewer.</p>
+    <p style="color: #666; font-size: 12px; margin-top: 30px;">
+        This is an automated alert from LyncZ's auto-cancel service. It is sent once per level per chain per day.
+    </p>
+</body>
+</html>
+"#, level, chain_id, spent_eth, day, budget_eth);
+
+        let admin_email = self.send_admin_alert(&subject, html).await?;
+        info!("📧 Gas budget alert ({}) for chain {} sent to {}", level, chain_id, admin_email);
+        Ok(())
+    }
+    
+    /// Send an alert email to ADMIN_ALERT_EMAIL; returns the address it went to
+    async fn send_admin_alert(&self, subject: &str, html: String) -> Result<String, String> {
+        let admin_email = std::env::var("ADMIN_ALERT_EMAIL")
+            .unwrap_or_else(|_| "anonysatoshi@proton.me".to_string());
+        
         let request = ResendEmailRequest {
             from: self.config.from_email.clone(),
             to: vec![admin_email.clone()],
             subject: subject.to_string(),
             html,
+            headers: HashMap::new(),
         };
         
         let response = self.client
@@ -301,58 +657,131 @@ impl EmailService {
             .map_err(|e| format!("Failed to send email request: {}", e))?;
         
         if response.status().is_success() {
-            info!(
-                "📧 Key rotation alert sent to {} (old: {}, new: {})",
-                admin_email, &old_hash[..16], &new_hash[..16]
-            );
-            Ok(())
+            Ok(admin_email)
         } else {
             let status = response.status();
             let body = response.text().await.unwrap_or_default();
-            error!("❌ Failed to send key rotation alert: {} - {}", status, body);
+            error!("❌ Failed to send admin alert \"{}\": {} - {}", subject, status, body);
             Err(format!("Email API error: {} - {}", status, body))
         }
     }
 }
 
-/// Helper to format token amounts for display
-pub fn format_token_amount(amount: &str, decimals: u8, symbol: &str) -> String {
-    let amount_u128: u128 = amount.parse().unwrap_or(0);
-    let divisor = 10u128.pow(decimals as u32);
+/// Every EmailInfo an event is sent with, filled with placeholder values
+/// (exhaustive over EmailEvent, so a new event can't skip template validation)
+fn sample_infos(event: EmailEvent) -> Vec<EmailInfo> {
+    let id = || "0x0000000000000000000000000000000000000000000000000000000000000001".to_string();
+    let text = || "1".to_string();
+    match event {
+        EmailEvent::OrderCreated => vec![EmailInfo::OrderCreated {
+            order_id: id(), token_amount: text(), token_symbol: text(), exchange_rate: text(), currency: Currency::Usd,
+            account_id: text(), account_name: text(), rail: PaymentRail::Alipay, is_private: false, private_code: None,
+        }],
+        EmailEvent::OrderWithdrawn => vec![EmailInfo::OrderWithdrawn {
+            order_id: id(), withdrawn_amount: text(), remaining_amount: text(), token_symbol: text(),
+        }],
+        EmailEvent::OrderUpdated => vec![
+            EmailInfo::ExchangeRateUpdated { order_id: id(), old_rate: text(), new_rate: text(), currency: Currency::Usd },
+            EmailInfo::PaymentInfoUpdated { order_id: id(), new_account_id: text(), new_account_name: text(), rail: PaymentRail::Alipay },
+        ],
+        EmailEvent::TradeCreatedSeller => vec![EmailInfo::TradeCreatedSeller {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), cny_amount: text(), currency: Currency::Usd,
+            fee_amount: text(), buyer_address: id(), account_id: text(), account_name: text(), rail: PaymentRail::Alipay,
+        }],
+        EmailEvent::TradeCreatedBuyer => vec![EmailInfo::TradeCreatedBuyer {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), cny_amount: text(), currency: Currency::Usd,
+            seller_account_id: text(), seller_account_name: text(), rail: PaymentRail::Alipay, expires_at: 0,
+        }],
+        EmailEvent::TradeSettledSeller => vec![EmailInfo::TradeSettledSeller {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), cny_amount: text(), currency: Currency::Usd,
+            fee_amount: text(), buyer_address: id(), settlement_tx: id(), chain_id: 8453,
+        }],
+        EmailEvent::TradeSettledBuyer => vec![EmailInfo::TradeSettledBuyer {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), settlement_tx: id(), chain_id: 8453,
+        }],
+        EmailEvent::TradeExpiredSeller => vec![EmailInfo::TradeExpiredSeller {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), cny_amount: text(), currency: Currency::Usd,
+        }],
+        EmailEvent::TradeExpiredBuyer => vec![EmailInfo::TradeExpiredBuyer {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), cny_amount: text(), currency: Currency::Usd,
+        }],
+        EmailEvent::TradeExpiringSoon => vec![EmailInfo::TradeExpiringSoon {
+            order_id: id(), trade_id: id(), token_amount: text(), token_symbol: text(), cny_amount: text(), currency: Currency::Usd, expires_at: 0,
+        }],
+        EmailEvent::DailyDigest => vec![EmailInfo::DailyDigest {
+            date: "2024-01-01".to_string(), new_trades: 1, settled_trades: 1, expired_trades: 1,
+        }],
+        EmailEvent::EmailVerification => vec![EmailInfo::EmailVerification {
+            email: "a@example.com".to_string(), confirm_url: "https://api.example/confirm".to_string(), valid_hours: 24,
+        }],
+    }
+}
+
+/// 10^decimals as a base-unit divisor. Above 38 decimals it overflows u128, and
+/// the amount is reported as unformattable rather than panicking.
+fn decimals_divisor(amount: &str, decimals: u8) -> Result<u128, InvalidAmount> {
+    10u128.checked_pow(decimals as u32).ok_or_else(|| InvalidAmount { raw: amount.to_string() })
+}
+
+/// Helper to format token amounts for display (base units → "1.5", or "1.5 USDC"
+/// with a symbol). A malformed amount is an error, never "0".
+pub fn format_token_amount(amount: &str, decimals: u8, symbol: &str) -> Result<String, InvalidAmount> {
+    let amount_u128: u128 = parse_amount(amount)?;
+    let divisor = decimals_divisor(amount, decimals)?;
     let whole = amount_u128 / divisor;
     let frac = amount_u128 % divisor;
     
-    if decimals == 0 {
-        if symbol.is_empty() {
-            format!("{}", whole)
-        } else {
-            format!("{} {}", whole, symbol)
-        }
+    let frac_str = format!("{:0width$}", frac, width = decimals as usize);
+    let trimmed = frac_str.trim_end_matches('0');
+    let number = if trimmed.is_empty() {
+        whole.to_string()
     } else {
-        let frac_str = format!("{:0width$}", frac, width = decimals as usize);
-        let trimmed = frac_str.trim_end_matches('0');
-        if trimmed.is_empty() {
-            if symbol.is_empty() {
-                format!("{}", whole)
-            } else {
-                format!("{} {}", whole, symbol)
-            }
-        } else {
-            if symbol.is_empty() {
-                format!("{}.{}", whole, trimmed)
-            } else {
-                format!("{}.{} {}", whole, trimmed, symbol)
-            }
-        }
+        format!("{}.{}", whole, trimmed)
+    };
+    if symbol.is_empty() {
+        Ok(number)
+    } else {
+        Ok(format!("{} {}", number, symbol))
     }
 }
 
-/// Helper to format CNY amount (stored as cents)
-pub fn format_cny_amount(cents: &str) -> String {
-    let cents_u64: u64 = cents.parse().unwrap_or(0);
-    let yuan = cents_u64 / 100;
-    let fen = cents_u64 % 100;
-    format!("¥{}.{:02}", yuan, fen)
+/// Exact human-decimal value of a base-unit amount, always with a fractional part
+/// (e.g. 1000000 at 6 decimals → "1.0", 1234500 → "1.2345"). Kept as a string so
+/// clients can feed it to a decimal library without float rounding. A malformed
+/// amount is an error, never "0.0".
+pub fn format_token_decimal(amount: &str, decimals: u8) -> Result<String, InvalidAmount> {
+    let amount_u128: u128 = parse_amount(amount)?;
+    let divisor = decimals_divisor(amount, decimals)?;
+    let whole = amount_u128 / divisor;
+    let frac = amount_u128 % divisor;
+    
+    let frac_str = format!("{:0width$}", frac, width = decimals as usize);
+    let trimmed = frac_str.trim_end_matches('0');
+    if trimmed.is_empty() {
+        Ok(format!("{}.0", whole))
+    } else {
+        Ok(format!("{}.{}", whole, trimmed))
+    }
+}
+
+/// `format_token_decimal` with an optional fixed number of fractional digits:
+/// extra digits are truncated (never rounded up), missing ones zero-padded
+/// (e.g. 1234567 at 6 decimals, scale 2 → "1.23"; 1000000, scale 2 → "1.00").
+/// `None` keeps the token's natural precision.
+pub fn format_token_decimal_scaled(amount: &str, decimals: u8, scale: Option<u8>) -> Result<String, InvalidAmount> {
+    let Some(scale) = scale else {
+        return format_token_decimal(amount, decimals);
+    };
+    let amount_u128: u128 = parse_amount(amount)?;
+    let divisor = decimals_divisor(amount, decimals)?;
+    let whole = amount_u128 / divisor;
+    if scale == 0 {
+        return Ok(whole.to_string());
+    }
+
+    let mut frac_str = format!("{:0width$}", amount_u128 % divisor, width = decimals as usize);
+    frac_str.truncate(scale as usize);
+    Ok(format!("{}.{:0<width$}", whole, frac_str, width = scale as usize))
 }
 
 /// Helper to truncate address for display
@@ -373,3 +802,136 @@ pub fn format_expires_at(expires_at: u64) -> String {
         _ => format!("{}", expires_at),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::DbOrder;
+
+    fn service(fallback_language: &str) -> EmailService {
+        EmailService::new(EmailConfig {
+            api_key: "test".to_string(),
+            from_email: "LyncZ <noreply@example.com>".to_string(),
+            app_url: "https://app.example".to_string(),
+            api_url: "https://api.example".to_string(),
+            expiry_reminder_lead_secs: 0,
+            order_created_grace_secs: 0,
+            fallback_language: fallback_language.to_string(),
+            digest_hour_utc: 0,
+            verification_ttl_secs: verification::DEFAULT_TTL_SECS,
+        })
+    }
+
+    #[test]
+    fn test_unknown_language_uses_fallback_template() {
+        let info = &sample_infos(EmailEvent::TradeSettledBuyer)[0];
+        let email = service("zh-TW");
+        assert_eq!(email.render("fr", EmailEvent::TradeSettledBuyer, info), email.render("zh-TW", EmailEvent::TradeSettledBuyer, info));
+        // Stored variants of a supported code still get their own language
+        assert_eq!(email.render("zh_cn", EmailEvent::TradeSettledBuyer, info), email.render("zh-CN", EmailEvent::TradeSettledBuyer, info));
+        assert_ne!(email.render("en", EmailEvent::TradeSettledBuyer, info), email.render("zh-TW", EmailEvent::TradeSettledBuyer, info));
+    }
+
+    #[test]
+    fn test_send_only_key_passes_health_check() {
+        use reqwest::StatusCode;
+        let restricted = r#"{"statusCode":401,"message":"This API key is restricted to only send emails","name":"restricted_api_key"}"#;
+        assert!(is_restricted_key_response(StatusCode::UNAUTHORIZED, restricted));
+        assert!(!is_restricted_key_response(StatusCode::UNAUTHORIZED, r#"{"statusCode":401,"name":"missing_api_key"}"#));
+        assert!(!is_restricted_key_response(StatusCode::FORBIDDEN, r#"{"statusCode":403,"name":"invalid_api_key"}"#));
+        assert!(!is_restricted_key_response(StatusCode::UNAUTHORIZED, "not json"));
+    }
+
+    #[test]
+    fn test_order_created_names_wechat_pay() {
+        let info = EmailInfo::OrderCreated {
+            order_id: "0x01".to_string(),
+            token_amount: "10".to_string(),
+            token_symbol: "USDC".to_string(),
+            exchange_rate: "720".to_string(),
+            currency: Currency::Cny,
+            account_id: "wxid".to_string(),
+            account_name: "Name".to_string(),
+            rail: PaymentRail::WeChat,
+            is_private: false,
+            private_code: None,
+        };
+        for language in ["zh-CN", "zh-TW"] {
+            let (_, html) = service("en").render(language, EmailEvent::OrderCreated, &info);
+            assert!(html.contains("微信支付"), "{}", language);
+        }
+    }
+
+    #[test]
+    fn test_fiat_shown_in_the_order_currency() {
+        let expiring = EmailInfo::TradeExpiringSoon {
+            order_id: "0x01".to_string(),
+            trade_id: "0x02".to_string(),
+            token_amount: "10".to_string(),
+            token_symbol: "USDC".to_string(),
+            cny_amount: "1205".to_string(),
+            currency: Currency::Usd,
+            expires_at: 1_700_000_000,
+        };
+        let rate_updated = EmailInfo::ExchangeRateUpdated {
+            order_id: "0x01".to_string(),
+            old_rate: "150".to_string(),
+            new_rate: "152".to_string(),
+            currency: Currency::Jpy,
+        };
+        for language in ["en", "zh-CN", "zh-TW"] {
+            let (_, html) = service("en").render(language, EmailEvent::TradeExpiringSoon, &expiring);
+            assert!(html.contains("$12.05"), "{}", language);
+            assert!(!html.contains('¥') && !html.contains("CNY"), "{}", language);
+
+            let (_, html) = service("en").render(language, EmailEvent::OrderUpdated, &rate_updated);
+            assert!(html.contains("150 JPY") && html.contains("152 JPY"), "{}", language);
+            assert!(!html.contains("CNY") && !html.contains("1.50"), "{}", language);
+        }
+
+        let order_created = order_created::order_created_info(
+            &DbOrder { exchange_rate: "92".to_string(), currency: "EUR".to_string(), ..crate::db::models::fixtures::order("0x01") },
+            false,
+            None,
+        );
+        let (_, html) = service("en").render("en", EmailEvent::OrderCreated, &order_created);
+        assert!(html.contains("0.92 EUR/USDC"), "{}", html);
+    }
+
+    #[test]
+    fn test_malformed_amounts_not_rendered_as_zero() {
+        assert_eq!(format_token_amount("1500000", 6, "USDC").unwrap(), "1.5 USDC");
+        assert_eq!(format_token_amount("0", 6, "").unwrap(), "0");
+        let err = format_token_amount("1.5e6", 6, "").unwrap_err();
+        assert_eq!(err.into_raw(), "1.5e6");
+        // More decimals than a u128 divisor holds: an error, not a panic
+        assert_eq!(format_token_amount("1", 39, "").unwrap_err().raw, "1");
+        assert!(format_token_decimal("1", 39).is_err());
+        assert!(format_token_decimal_scaled("1", 39, Some(2)).is_err());
+        assert_eq!(format_token_decimal("1", 38).unwrap(), format!("0.{}1", "0".repeat(37)));
+
+        // Emails show the stored value instead of ¥0.00
+        let info = EmailInfo::TradeExpiredSeller {
+            order_id: "0x01".to_string(),
+            trade_id: "0x02".to_string(),
+            token_amount: "1.5".to_string(),
+            token_symbol: "USDC".to_string(),
+            cny_amount: "abc".to_string(),
+            currency: Currency::Cny,
+        };
+        for language in ["en", "zh-CN", "zh-TW"] {
+            let (_, html) = service("en").render(language, EmailEvent::TradeExpiredSeller, &info);
+            assert!(html.contains("abc"), "{}", language);
+            assert!(!html.contains("¥0.00"), "{}", language);
+        }
+    }
+
+    #[test]
+    fn test_template_set_validated() {
+        service("en").validate_templates().unwrap();
+        assert!(matches!(
+            service("english").validate_templates(),
+            Err(EmailTemplateError::UnsupportedFallback(language)) if language == "english"
+        ));
+    }
+}
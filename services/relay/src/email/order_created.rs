@@ -0,0 +1,171 @@
+//! Order-created emails
+//!
+//! The seller's order-created email is normally sent by
+//! POST /api/orders/:id/visibility, which the frontend calls right after the
+//! order is created on-chain. If that call never comes (tab closed, network
+//! drop), this job makes the order private and sends the email once
+//! `ORDER_CREATED_EMAIL_GRACE_SECS` have passed. Orders are claimed
+//! (`orders.created_email_sent_at`) first, so each seller gets one email.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{format_token_amount, EmailEvent, EmailInfo, EmailService};
+use crate::currency::{Currency, InvalidAmount};
+use crate::db::models::DbOrder;
+use crate::db::Database;
+use crate::tokens;
+
+/// Default wait for the visibility call before falling back (10 minutes)
+pub const DEFAULT_GRACE_SECS: u64 = 600;
+
+/// How often orders without an email are checked
+const POLL_INTERVAL_SECS: u64 = 60;
+
+/// Orders handled per poll
+const BATCH_SIZE: i64 = 50;
+
+/// Order-created email content
+pub fn order_created_info(order: &DbOrder, is_private: bool, private_code: Option<String>) -> EmailInfo {
+    let (token_symbol, token_decimals) = tokens::symbol_and_decimals(&order.token);
+    EmailInfo::OrderCreated {
+        order_id: order.order_id.clone(),
+        token_amount: format_token_amount(&order.total_amount, token_decimals, "").unwrap_or_else(InvalidAmount::into_raw),
+        token_symbol: token_symbol.to_string(),
+        exchange_rate: order.exchange_rate.clone(),
+        currency: Currency::from_code_or_default(&order.currency),
+        account_id: order.alipay_id.clone(),
+        account_name: order.alipay_name.clone(),
+        rail: order.rail,
+        is_private,
+        private_code,
+    }
+}
+
+/// Whether an order's email is overdue: older than the grace (whether it was
+/// already handled is up to the DB claim in `mark_order_created_email_sent`)
+pub fn needs_fallback_email(created_at: i64, now: i64, grace_secs: i64) -> bool {
+    now - created_at >= grace_secs
+}
+
+/// Run the fallback job forever (spawned from api-server when email is configured)
+pub async fn run(email_service: Arc<EmailService>, db: Arc<Database>) {
+    let grace_secs = email_service.order_created_grace_secs() as i64;
+    if grace_secs == 0 {
+        tracing::info!("📦 Order-created email fallback disabled (ORDER_CREATED_EMAIL_GRACE_SECS=0)");
+        return;
+    }
+    tracing::info!("📦 Order-created email fallback started ({}s grace, every {}s)", grace_secs, POLL_INTERVAL_SECS);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        let orders = match db.get_orders_awaiting_created_email(grace_secs, BATCH_SIZE).await {
+            Ok(orders) => orders,
+            Err(e) => {
+                tracing::warn!("📦 Failed to load orders awaiting order-created email: {}", e);
+                continue;
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        for order in orders.iter().filter(|o| needs_fallback_email(o.created_at, now, grace_secs)) {
+            match db.mark_order_created_email_sent(&order.order_id).await {
+                Ok(true) => {}
+                Ok(false) => continue, // Visibility set meanwhile, or another instance
+                Err(e) => {
+                    tracing::warn!("📦 Failed to claim order-created email for {}: {}", order.order_id, e);
+                    continue;
+                }
+            }
+
+            // Seller never chose: default to private, which is the safe choice
+            let private_code = match db.set_order_visibility(&order.order_id, false).await {
+                Ok(change) => change.private_code,
+                Err(e) => {
+                    tracing::warn!("📦 Failed to make order {} private: {}", order.order_id, e);
+                    continue;
+                }
+            };
+
+            let account = match db.get_account_email_if_enabled(&order.seller).await {
+                Ok(Some(account)) => account,
+                Ok(None) => {
+                    tracing::debug!("📦 No email enabled for seller {} (order {})", order.seller, order.order_id);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("📦 Failed to fetch account email for {}: {}", order.seller, e);
+                    continue;
+                }
+            };
+
+            tracing::info!("📦 Visibility never set for order {} - made private, emailing seller", order.order_id);
+            email_service.send_or_enqueue(
+                db.pool(),
+                &account.wallet,
+                &account.email,
+                &account.language,
+                EmailEvent::OrderCreated,
+                &order_created_info(order, true, private_code),
+            ).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::fixtures;
+
+    fn order(created_at: i64) -> DbOrder {
+        DbOrder {
+            total_amount: "250000000".to_string(),
+            remaining_amount: "250000000".to_string(),
+            created_at,
+            ..fixtures::order("0xorder")
+        }
+    }
+
+    #[test]
+    fn test_order_without_visibility_gets_private_fallback_email_after_grace() {
+        let grace = 600;
+        let o = order(1_700_000_000);
+
+        assert!(!needs_fallback_email(o.created_at, o.created_at + grace - 1, grace));
+        assert!(needs_fallback_email(o.created_at, o.created_at + grace, grace));
+
+        match order_created_info(&o, true, Some("123456".to_string())) {
+            EmailInfo::OrderCreated { is_private, private_code, token_amount, token_symbol, .. } => {
+                assert!(is_private);
+                assert_eq!(private_code.as_deref(), Some("123456"));
+                assert_eq!(token_amount, format_token_amount("250000000", 6, "").unwrap());
+                assert_eq!(token_symbol, "USDC");
+            }
+            other => panic!("unexpected info {:?}", other),
+        }
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_order_created_email_claimed_once(pool: sqlx::PgPool) {
+        use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+
+        let repo = PostgresOrderRepository::new(pool.clone());
+        let now = chrono::Utc::now().timestamp();
+        repo.create(&order(now - 3_600)).await.unwrap();
+        repo.create(&DbOrder { created_at: now, ..fixtures::order("0xnew") }).await.unwrap();
+        let db = Database::from_pool(pool);
+
+        let due = db.get_orders_awaiting_created_email(600, 50).await.unwrap();
+        assert_eq!(due.iter().map(|o| o.order_id.as_str()).collect::<Vec<_>>(), ["0xorder"]);
+
+        // The visibility endpoint and the fallback race for one claim
+        assert!(db.mark_order_created_email_sent("0xorder").await.unwrap());
+        assert!(!db.mark_order_created_email_sent("0xorder").await.unwrap());
+        assert!(db.get_orders_awaiting_created_email(600, 50).await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,269 @@
+//! Email outbox worker
+//!
+//! Failed notification sends are queued in `email_outbox` (see
+//! `EmailService::send_or_enqueue`). This worker claims due rows (so two
+//! replicas never send the same one), retries them with exponential backoff
+//! and marks them delivered, or failed once
+//! MAX_SEND_ATTEMPTS is reached. On shutdown it makes one last attempt at
+//! every pending row (bounded by SHUTDOWN_FLUSH_TIMEOUT_SECS) so a deploy
+//! doesn't hold notifications back until the next process starts.
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use sqlx::PgPool;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::watch;
+
+use super::EmailService;
+use crate::db::email_outbox::EmailOutboxRepository;
+use crate::db::models::DbEmailOutboxEntry;
+use crate::db::DbResult;
+
+/// How often the outbox is polled
+const POLL_INTERVAL_SECS: u64 = 30;
+
+/// Rows retried per poll
+const BATCH_SIZE: i64 = 20;
+
+/// Rows attempted by the final flush on shutdown
+const SHUTDOWN_FLUSH_LIMIT: i64 = 100;
+
+/// Upper bound on the shutdown flush
+const SHUTDOWN_FLUSH_TIMEOUT_SECS: u64 = 10;
+
+/// Attempts (including the original send) before an email is marked failed
+pub const MAX_SEND_ATTEMPTS: i32 = 6;
+
+/// Backoff after the Nth failed attempt: 1m, 2m, 4m, ... capped at 1h
+const RETRY_BASE_SECS: i64 = 60;
+const RETRY_MAX_SECS: i64 = 3600;
+
+/// Delay before the next attempt after `attempts` failures
+pub fn retry_delay_secs(attempts: i32) -> i64 {
+    let exponent = attempts.saturating_sub(1).clamp(0, 16) as u32;
+    (RETRY_BASE_SECS << exponent).min(RETRY_MAX_SECS)
+}
+
+/// Outbox storage plus mail transport, as seen by the worker
+#[async_trait]
+trait OutboxBackend: Send + Sync {
+    /// Claim pending rows for this worker; with `due_only` = false, rows still
+    /// backing off are included
+    async fn pending(&self, limit: i64, due_only: bool) -> DbResult<Vec<DbEmailOutboxEntry>>;
+    async fn send(&self, entry: &DbEmailOutboxEntry) -> Result<(), String>;
+    async fn mark_delivered(&self, id: i64) -> DbResult<()>;
+    async fn mark_attempt_failed(&self, id: i64, error: &str, next_attempt_at: Option<DateTime<Utc>>) -> DbResult<()>;
+}
+
+/// Postgres outbox delivered through Resend
+struct ResendOutbox {
+    email_service: Arc<EmailService>,
+    repo: EmailOutboxRepository,
+}
+
+#[async_trait]
+impl OutboxBackend for ResendOutbox {
+    async fn pending(&self, limit: i64, due_only: bool) -> DbResult<Vec<DbEmailOutboxEntry>> {
+        if due_only {
+            self.repo.claim_due(limit).await
+        } else {
+            self.repo.claim_pending(limit).await
+        }
+    }
+
+    async fn send(&self, entry: &DbEmailOutboxEntry) -> Result<(), String> {
+        self.email_service
+            .send_email(&entry.to_email, &entry.subject, &entry.html, entry.unsubscribe_url.as_deref())
+            .await
+    }
+
+    async fn mark_delivered(&self, id: i64) -> DbResult<()> {
+        self.repo.mark_delivered(id).await
+    }
+
+    async fn mark_attempt_failed(&self, id: i64, error: &str, next_attempt_at: Option<DateTime<Utc>>) -> DbResult<()> {
+        self.repo.mark_attempt_failed(id, error, next_attempt_at).await
+    }
+}
+
+/// Run the outbox worker until `shutdown` flips to true, then flush
+/// (spawned from api-server when email is configured)
+pub async fn run(email_service: Arc<EmailService>, pool: PgPool, shutdown: watch::Receiver<bool>) {
+    let backend = ResendOutbox {
+        email_service,
+        repo: EmailOutboxRepository::new(pool),
+    };
+    run_with(&backend, shutdown, Duration::from_secs(SHUTDOWN_FLUSH_TIMEOUT_SECS)).await;
+}
+
+async fn run_with(backend: &dyn OutboxBackend, mut shutdown: watch::Receiver<bool>, flush_timeout: Duration) {
+    tracing::info!("📮 Email outbox worker started (every {}s)", POLL_INTERVAL_SECS);
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+
+    loop {
+        tokio::select! {
+            _ = interval.tick() => {}
+            // Sender dropped counts as shutdown too
+            _ = shutdown.changed() => break,
+        }
+
+        match backend.pending(BATCH_SIZE, true).await {
+            Ok(due) => {
+                attempt_all(backend, due).await;
+            }
+            Err(e) => tracing::warn!("📮 Failed to load email outbox: {}", e),
+        }
+    }
+
+    flush(backend, flush_timeout).await;
+}
+
+/// Final attempt at every pending email, giving up after `timeout`
+async fn flush(backend: &dyn OutboxBackend, timeout: Duration) {
+    let pending = match backend.pending(SHUTDOWN_FLUSH_LIMIT, false).await {
+        Ok(pending) => pending,
+        Err(e) => {
+            tracing::warn!("📮 Failed to load email outbox for shutdown flush: {}", e);
+            return;
+        }
+    };
+    if pending.is_empty() {
+        return;
+    }
+
+    let total = pending.len();
+    tracing::info!("📮 Flushing {} pending email(s) before shutdown", total);
+    match tokio::time::timeout(timeout, attempt_all(backend, pending)).await {
+        Ok(delivered) => tracing::info!("📮 Shutdown flush delivered {}/{} email(s)", delivered, total),
+        Err(_) => tracing::warn!("📮 Shutdown flush timed out after {:?}, remaining emails stay queued", timeout),
+    }
+}
+
+/// Try each entry once and record the outcome; returns how many were delivered
+async fn attempt_all(backend: &dyn OutboxBackend, entries: Vec<DbEmailOutboxEntry>) -> usize {
+    let mut delivered = 0;
+    for entry in entries {
+        let result = match backend.send(&entry).await {
+            Ok(()) => {
+                tracing::info!("📮 Outbox email {} ({}) delivered after {} attempt(s)", entry.id, entry.event, entry.attempts + 1);
+                delivered += 1;
+                backend.mark_delivered(entry.id).await
+            }
+            Err(e) => {
+                let attempts = entry.attempts + 1;
+                let next_attempt_at = (attempts < MAX_SEND_ATTEMPTS)
+                    .then(|| Utc::now() + chrono::Duration::seconds(retry_delay_secs(attempts)));
+                if next_attempt_at.is_none() {
+                    tracing::error!("📮 Outbox email {} ({}) to {} failed permanently after {} attempts: {}",
+                        entry.id, entry.event, entry.to_email, attempts, e);
+                }
+                backend.mark_attempt_failed(entry.id, &e, next_attempt_at).await
+            }
+        };
+        if let Err(e) = result {
+            tracing::warn!("📮 Failed to update outbox email {}: {}", entry.id, e);
+        }
+    }
+    delivered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_retry_delay_schedule() {
+        assert_eq!(retry_delay_secs(1), 60);
+        assert_eq!(retry_delay_secs(2), 120);
+        assert_eq!(retry_delay_secs(5), 960);
+        assert_eq!(retry_delay_secs(7), RETRY_MAX_SECS);
+        assert_eq!(retry_delay_secs(100), RETRY_MAX_SECS);
+    }
+
+    /// In-memory outbox; `send_delay` simulates a slow mail provider
+    struct FakeOutbox {
+        entries: Mutex<Vec<DbEmailOutboxEntry>>,
+        sent: Mutex<Vec<i64>>,
+        send_delay: Duration,
+    }
+
+    impl FakeOutbox {
+        /// Two pending emails, both still backing off (not due for another minute)
+        fn with_backing_off_entries(send_delay: Duration) -> Self {
+            let entry = |id| DbEmailOutboxEntry {
+                id,
+                to_email: "buyer@example.com".to_string(),
+                subject: "Subject".to_string(),
+                html: "<p>Hi</p>".to_string(),
+                unsubscribe_url: None,
+                event: "TradeCreatedBuyer".to_string(),
+                status: "pending".to_string(),
+                attempts: 1,
+                last_error: Some("timeout".to_string()),
+                next_attempt_at: Utc::now() + chrono::Duration::seconds(60),
+                created_at: Utc::now(),
+            };
+            Self {
+                entries: Mutex::new(vec![entry(1), entry(2)]),
+                sent: Mutex::new(Vec::new()),
+                send_delay,
+            }
+        }
+    }
+
+    #[async_trait]
+    impl OutboxBackend for FakeOutbox {
+        async fn pending(&self, limit: i64, due_only: bool) -> DbResult<Vec<DbEmailOutboxEntry>> {
+            let now = Utc::now();
+            Ok(self.entries.lock().unwrap().iter()
+                .filter(|e| e.status == "pending" && (!due_only || e.next_attempt_at <= now))
+                .take(limit as usize)
+                .cloned()
+                .collect())
+        }
+
+        async fn send(&self, entry: &DbEmailOutboxEntry) -> Result<(), String> {
+            tokio::time::sleep(self.send_delay).await;
+            self.sent.lock().unwrap().push(entry.id);
+            Ok(())
+        }
+
+        async fn mark_delivered(&self, id: i64) -> DbResult<()> {
+            for e in self.entries.lock().unwrap().iter_mut().filter(|e| e.id == id) {
+                e.status = "delivered".to_string();
+            }
+            Ok(())
+        }
+
+        async fn mark_attempt_failed(&self, _id: i64, _error: &str, _next: Option<DateTime<Utc>>) -> DbResult<()> {
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_pending_emails_flushed_on_shutdown() {
+        let outbox = FakeOutbox::with_backing_off_entries(Duration::ZERO);
+        let (shutdown_tx, shutdown_rx) = watch::channel(false);
+        shutdown_tx.send(true).unwrap();
+
+        tokio::time::timeout(Duration::from_secs(1), run_with(&outbox, shutdown_rx, Duration::from_secs(1)))
+            .await
+            .expect("worker stops promptly on shutdown");
+
+        assert_eq!(*outbox.sent.lock().unwrap(), vec![1, 2]);
+        assert!(outbox.entries.lock().unwrap().iter().all(|e| e.status == "delivered"));
+    }
+
+    #[tokio::test]
+    async fn test_shutdown_flush_is_bounded() {
+        let outbox = FakeOutbox::with_backing_off_entries(Duration::from_secs(30));
+        let started = std::time::Instant::now();
+
+        flush(&outbox, Duration::from_millis(50)).await;
+
+        assert!(started.elapsed() < Duration::from_secs(5));
+        assert!(outbox.sent.lock().unwrap().is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+//! Trade expiry reminders
+//!
+//! Emails the buyer once, `TRADE_EXPIRY_REMINDER_LEAD_SECS` before a pending
+//! trade expires, so they can finish paying or upload their receipt in time.
+//! A trade is claimed (`trades.expiry_reminder_sent_at`) before its email goes
+//! out, so restarts and multiple API instances never send the reminder twice.
+//! Buyers without an enabled account email are skipped.
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use super::{format_token_amount, EmailEvent, EmailInfo, EmailService};
+use crate::currency::{Currency, InvalidAmount};
+use crate::db::account_emails::AccountEmailRepository;
+use crate::db::models::DbExpiringTrade;
+use crate::db::Database;
+use crate::tokens;
+
+/// Default reminder lead time (5 minutes before expiry)
+pub const DEFAULT_LEAD_SECS: u64 = 300;
+
+/// How often pending trades are checked
+const POLL_INTERVAL_SECS: u64 = 30;
+
+/// Whether a trade should be reminded now: not yet expired and expiring within
+/// the lead time (the DB claim in `mark_expiry_reminder_sent` makes it once only)
+pub fn should_remind(expires_at: i64, now: i64, lead_secs: i64) -> bool {
+    expires_at > now && expires_at - now <= lead_secs
+}
+
+/// Reminder email content for a trade
+fn reminder_info(trade: &DbExpiringTrade) -> EmailInfo {
+    let (token_symbol, decimals) = tokens::symbol_and_decimals(&trade.token);
+    EmailInfo::TradeExpiringSoon {
+        order_id: trade.order_id.clone(),
+        trade_id: trade.trade_id.clone(),
+        token_amount: format_token_amount(&trade.token_amount, decimals, "").unwrap_or_else(InvalidAmount::into_raw),
+        token_symbol: token_symbol.to_string(),
+        cny_amount: trade.cny_amount.clone(),
+        currency: Currency::from_code_or_default(&trade.currency),
+        expires_at: trade.expires_at.max(0) as u64,
+    }
+}
+
+/// Run the reminder job forever (spawned from api-server when email is configured)
+pub async fn run(email_service: Arc<EmailService>, db: Arc<Database>) {
+    let lead_secs = email_service.expiry_reminder_lead_secs() as i64;
+    if lead_secs == 0 {
+        tracing::info!("⏳ Trade expiry reminders disabled (TRADE_EXPIRY_REMINDER_LEAD_SECS=0)");
+        return;
+    }
+    tracing::info!("⏳ Trade expiry reminder job started ({}s before expiry, every {}s)", lead_secs, POLL_INTERVAL_SECS);
+
+    let email_repo = AccountEmailRepository::new(db.pool().clone());
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL_SECS));
+
+    loop {
+        interval.tick().await;
+
+        let trades = match db.get_trades_near_expiry(lead_secs).await {
+            Ok(trades) => trades,
+            Err(e) => {
+                tracing::warn!("⏳ Failed to load trades near expiry: {}", e);
+                continue;
+            }
+        };
+
+        let now = chrono::Utc::now().timestamp();
+        for trade in trades.iter().filter(|t| should_remind(t.expires_at, now, lead_secs)) {
+            match db.mark_expiry_reminder_sent(&trade.trade_id).await {
+                Ok(true) => {}
+                Ok(false) => continue, // Already reminded by another instance
+                Err(e) => {
+                    tracing::warn!("⏳ Failed to claim expiry reminder for {}: {}", trade.trade_id, e);
+                    continue;
+                }
+            }
+
+            let account = match email_repo.get_if_enabled(&trade.buyer).await {
+                Ok(Some(account)) => account,
+                Ok(None) => {
+                    tracing::debug!("⏳ No email enabled for buyer {} (trade {})", trade.buyer, trade.trade_id);
+                    continue;
+                }
+                Err(e) => {
+                    tracing::warn!("⏳ Failed to fetch account email for {}: {}", trade.buyer, e);
+                    continue;
+                }
+            };
+
+            tracing::info!("⏳ Reminding buyer of trade {} ({}s left)", trade.trade_id, trade.expires_at - now);
+            email_service.send_or_enqueue(
+                db.pool(),
+                &account.wallet,
+                &account.email,
+                &account.language,
+                EmailEvent::TradeExpiringSoon,
+                &reminder_info(trade),
+            ).await;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_trade_in_lead_window_reminded_once(pool: sqlx::PgPool) {
+        use crate::db::models::{fixtures, DbTrade};
+        use crate::db::orders::{OrderRepository, PostgresOrderRepository};
+        use crate::db::trades::{PostgresTradeRepository, TradeRepository};
+
+        PostgresOrderRepository::new(pool.clone()).create(&fixtures::order("0xorder")).await.unwrap();
+        let trades = PostgresTradeRepository::new(pool.clone());
+        let now = chrono::Utc::now().timestamp();
+        trades.create(&DbTrade { expires_at: now + 120, ..fixtures::trade("0xsoon", 0) }).await.unwrap();
+        trades.create(&DbTrade { expires_at: now + 3_600, ..fixtures::trade("0xlater", 0) }).await.unwrap();
+        let db = Database::from_pool(pool);
+
+        let due = db.get_trades_near_expiry(300).await.unwrap();
+        assert_eq!(due.iter().map(|t| t.trade_id.as_str()).collect::<Vec<_>>(), ["0xsoon"]);
+
+        // Only the first claim wins, and a claimed trade isn't offered again
+        assert!(db.mark_expiry_reminder_sent("0xsoon").await.unwrap());
+        assert!(!db.mark_expiry_reminder_sent("0xsoon").await.unwrap());
+        assert!(db.get_trades_near_expiry(300).await.unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_should_remind_window() {
+        assert!(should_remind(1_300, 1_000, 300));
+        assert!(!should_remind(1_301, 1_000, 300)); // Not yet in the window
+        assert!(!should_remind(1_000, 1_000, 300)); // Already expired
+    }
+
+    #[test]
+    fn test_reminder_info_formats_token_amount() {
+        let trade = DbExpiringTrade {
+            trade_id: "0xtrade".to_string(),
+            order_id: "0xorder".to_string(),
+            buyer: "0xbuyer".to_string(),
+            token_amount: "1500000".to_string(),
+            cny_amount: "1080".to_string(),
+            currency: "USD".to_string(),
+            expires_at: 1_700_000_000,
+            token: "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913".to_string(),
+        };
+        match reminder_info(&trade) {
+            EmailInfo::TradeExpiringSoon { token_amount, token_symbol, currency, expires_at, .. } => {
+                assert_eq!(token_amount, format_token_amount("1500000", 6, "").unwrap());
+                assert_eq!(token_symbol, "USDC");
+                assert_eq!(currency, Currency::Usd);
+                assert_eq!(expires_at, 1_700_000_000);
+            }
+            other => panic!("unexpected info {:?}", other),
+        }
+    }
+}
@@ -1,7 +1,25 @@
 //! Email templates in English, Simplified Chinese, and Traditional Chinese
 //! Account-based notifications - any wallet can be buyer or seller
 
-use super::{EmailEvent, EmailInfo, truncate_address, format_cny_amount, format_expires_at};
+use super::{EmailEvent, EmailInfo, truncate_address, format_expires_at};
+use crate::currency::{format_fiat, parse_amount, Currency, InvalidAmount};
+use crate::rail::PaymentRail;
+
+/// Subjects of the catch-all email sent for an event/info pair without a template
+pub(super) const GENERIC_SUBJECTS: [&str; 3] = ["LyncZ Notification", "灵犀支付通知", "靈犀支付通知"];
+
+/// Signature shared by the per-language template functions
+pub(super) type TemplateFn = fn(EmailEvent, &EmailInfo, &str) -> (String, String);
+
+/// Template set for a supported language code (see email::language)
+pub(super) fn template_for(language: &str) -> Option<TemplateFn> {
+    match language {
+        "en" => Some(get_email_en),
+        "zh-CN" => Some(get_email_zh_cn),
+        "zh-TW" => Some(get_email_zh_tw),
+        _ => None,
+    }
+}
 
 /// Get block explorer base URL for a given chain
 fn explorer_url(chain_id: u64) -> &'static str {
@@ -11,19 +29,27 @@ fn explorer_url(chain_id: u64) -> &'static str {
     }
 }
 
+/// Network name shown in emails (same default as explorer_url)
+fn chain_name(chain_id: u64) -> &'static str {
+    match chain_id {
+        1 => "Ethereum",
+        _ => "Base",
+    }
+}
+
 /// Get English email subject and body
 pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (String, String) {
     match (event, info) {
         // Order Created (Seller) - handles both public and unlisted orders
-        (EmailEvent::OrderCreated, EmailInfo::OrderCreated { order_id, token_amount, token_symbol, exchange_rate, account_id, account_name, rail, is_private, private_code: _ }) => {
+        (EmailEvent::OrderCreated, EmailInfo::OrderCreated { order_id, token_amount, token_symbol, exchange_rate, account_id, account_name, rail, is_private, private_code: _, currency }) => {
             let (subject, title, message) = if *is_private {
                 (
                     "📦 Your Sell Order is Ready on LyncZ".to_string(),
                     "Your sell order has been created!",
                     format!(
-                        "You've listed <strong>{} {}</strong> for sale at <strong>{} CNY/{}</strong>. \
+                        "You've listed <strong>{} {}</strong> for sale at <strong>{}/{}</strong>. \
                         This is an unlisted order. Visit My Account to get your sharing code.",
-                        token_amount, token_symbol, format_exchange_rate(exchange_rate), token_symbol
+                        token_amount, token_symbol, format_exchange_rate(exchange_rate, *currency), token_symbol
                     )
                 )
             } else {
@@ -31,25 +57,20 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
                     "📦 Your Sell Order is Live on LyncZ".to_string(),
                     "Your sell order has been created!",
                     format!(
-                        "You've listed <strong>{} {}</strong> for sale at <strong>{} CNY/{}</strong>. \
+                        "You've listed <strong>{} {}</strong> for sale at <strong>{}/{}</strong>. \
                         Buyers can now purchase from your order.",
-                        token_amount, token_symbol, format_exchange_rate(exchange_rate), token_symbol
+                        token_amount, token_symbol, format_exchange_rate(exchange_rate, *currency), token_symbol
                     )
                 )
             };
             
-            // Localize rail name for English
-            let rail_name = match rail {
-                0 => "Alipay",
-                1 => "WeChat",
-                _ => "Unknown",
-            };
+            let rail_name = rail.display_name("en");
             
             // Build details array - no code in email, user must visit site
             let details: Vec<(&str, String)> = vec![
                 ("Order ID", truncate_address(order_id)),
                 ("Amount", format!("{} {}", token_amount, token_symbol)),
-                ("Rate", format!("{} CNY/{}", format_exchange_rate(exchange_rate), token_symbol)),
+                ("Rate", format!("{}/{}", format_exchange_rate(exchange_rate, *currency), token_symbol)),
                 ("Payment Account", format!("{} ({})", account_name, account_id)),
                 ("Payment Rail", rail_name.to_string()),
                 ("Listing", if *is_private { "Unlisted".to_string() } else { "Public".to_string() }),
@@ -93,19 +114,19 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         },
         
         // Exchange Rate Updated (Seller)
-        (EmailEvent::OrderUpdated, EmailInfo::ExchangeRateUpdated { order_id, old_rate, new_rate }) => {
+        (EmailEvent::OrderUpdated, EmailInfo::ExchangeRateUpdated { order_id, old_rate, new_rate, currency }) => {
             let subject = "📊 Exchange Rate Updated on Your LyncZ Order".to_string();
             let html = format_simple_email(
                 "Exchange rate has been updated",
                 &format!(
                     "You've updated the exchange rate on your sell order from \
-                    <strong>{} CNY</strong> to <strong>{} CNY</strong>.",
-                    old_rate, new_rate
+                    <strong>{}</strong> to <strong>{}</strong>.",
+                    format_exchange_rate(old_rate, *currency), format_exchange_rate(new_rate, *currency)
                 ),
                 &[
                     ("Order ID", &truncate_address(order_id)),
-                    ("Old Rate", &format!("{} CNY", old_rate)),
-                    ("New Rate", &format!("{} CNY", new_rate)),
+                    ("Old Rate", &format_exchange_rate(old_rate, *currency)),
+                    ("New Rate", &format_exchange_rate(new_rate, *currency)),
                 ],
                 app_url,
                 "/account",
@@ -117,11 +138,7 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         
         // Payment Info Updated (Seller)
         (EmailEvent::OrderUpdated, EmailInfo::PaymentInfoUpdated { order_id, new_account_id, new_account_name, rail }) => {
-            let rail_name = match rail {
-                0 => "Alipay",
-                1 => "WeChat",
-                _ => "Payment",
-            };
+            let rail_name = rail.display_name("en");
             let subject = "👤 Payment Info Updated on Your LyncZ Order".to_string();
             let html = format_simple_email(
                 "Payment information has been updated",
@@ -143,26 +160,22 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         },
         
         // Trade Created (Seller perspective)
-        (EmailEvent::TradeCreatedSeller, EmailInfo::TradeCreatedSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, account_id, account_name, rail }) => {
-            let rail_name = match rail {
-                0 => "Alipay",
-                1 => "WeChat",
-                _ => "Payment",
-            };
+        (EmailEvent::TradeCreatedSeller, EmailInfo::TradeCreatedSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, account_id, account_name, rail, currency }) => {
+            let rail_name = rail.display_name("en");
             let subject = "🔔 New Trade on Your LyncZ Order".to_string();
             let html = format_simple_email(
                 "A buyer has initiated a trade!",
                 &format!(
                     "A buyer is purchasing <strong>{} {}</strong> for <strong>{}</strong>. \
                     They have 15 minutes to complete payment to your account.",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("Order ID", &truncate_address(order_id)),
                     ("Trade ID", &truncate_address(trade_id)),
                     ("Buyer Receives", &format!("{} {}", token_amount, token_symbol)),
                     ("Platform Fee", &format!("-{} {}", fee_amount, token_symbol)),
-                    ("You Receive", &format_cny_amount(cny_amount)),
+                    ("You Receive", &fiat(cny_amount, *currency)),
                     ("Buyer", &truncate_address(buyer_address)),
                     (&format!("{} Account Name", rail_name), account_name),
                     (&format!("{} Account ID", rail_name), account_id),
@@ -176,25 +189,21 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         },
         
         // Trade Created (Buyer perspective)
-        (EmailEvent::TradeCreatedBuyer, EmailInfo::TradeCreatedBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, seller_account_id, seller_account_name, rail, expires_at }) => {
-            let rail_name = match rail {
-                0 => "Alipay",
-                1 => "WeChat",
-                _ => "Payment",
-            };
+        (EmailEvent::TradeCreatedBuyer, EmailInfo::TradeCreatedBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, seller_account_id, seller_account_name, rail, expires_at, currency }) => {
+            let rail_name = rail.display_name("en");
             let subject = "🛒 Your LyncZ Purchase Has Started".to_string();
             let html = format_simple_email(
                 "Your purchase is in progress!",
                 &format!(
                     "You're buying <strong>{} {}</strong> for <strong>{}</strong>. \
                     Please complete payment to the seller's account within 15 minutes.",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("Order ID", &truncate_address(order_id)),
                     ("Trade ID", &truncate_address(trade_id)),
                     ("You'll Receive", &format!("{} {}", token_amount, token_symbol)),
-                    ("Amount to Pay", &format_cny_amount(cny_amount)),
+                    ("Amount to Pay", &fiat(cny_amount, *currency)),
                     (&format!("{} Account Name", rail_name), seller_account_name),
                     (&format!("{} Account ID", rail_name), seller_account_id),
                     ("Expires", &format_expires_at(*expires_at)),
@@ -208,7 +217,7 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         },
         
         // Trade Settled (Seller perspective)
-        (EmailEvent::TradeSettledSeller, EmailInfo::TradeSettledSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, settlement_tx, chain_id }) => {
+        (EmailEvent::TradeSettledSeller, EmailInfo::TradeSettledSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, settlement_tx, chain_id, currency }) => {
             let explorer = explorer_url(*chain_id);
             let subject = "✅ Trade Settled - Payment Received".to_string();
             let html = format_simple_email(
@@ -216,14 +225,14 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
                 &format!(
                     "The trade for <strong>{} {}</strong> ({}) has been settled. \
                     The buyer's payment has been verified and the crypto has been released.",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("Order ID", &truncate_address(order_id)),
                     ("Trade ID", &truncate_address(trade_id)),
                     ("Sold", &format!("{} {}", token_amount, token_symbol)),
                     ("Platform Fee", &format!("-{} {}", fee_amount, token_symbol)),
-                    ("Received", &format_cny_amount(cny_amount)),
+                    ("Received", &fiat(cny_amount, *currency)),
                     ("Buyer", &truncate_address(buyer_address)),
                     ("Settlement TX", &format!("<a href=\"{}/tx/{}\" style=\"color: #6366f1;\">{}</a>", explorer, settlement_tx, truncate_address(settlement_tx))),
                 ],
@@ -250,6 +259,7 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
                     ("Order ID", &truncate_address(order_id)),
                     ("Trade ID", &truncate_address(trade_id)),
                     ("Received", &format!("{} {}", token_amount, token_symbol)),
+                    ("Network", chain_name(*chain_id)),
                     ("Settlement TX", &format!("<a href=\"{}/tx/{}\" style=\"color: #6366f1;\">{}</a>", explorer, settlement_tx, truncate_address(settlement_tx))),
                 ],
                 app_url,
@@ -261,20 +271,20 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         },
         
         // Trade Expired (Seller)
-        (EmailEvent::TradeExpiredSeller, EmailInfo::TradeExpiredSeller { order_id, trade_id, token_amount, token_symbol, cny_amount }) => {
+        (EmailEvent::TradeExpiredSeller, EmailInfo::TradeExpiredSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, currency }) => {
             let subject = "⏰ Trade Expired".to_string();
             let html = format_simple_email(
                 "Trade expired - funds returned to your order",
                 &format!(
                     "The trade for <strong>{} {}</strong> ({}) has expired because the buyer \
                     did not complete payment in time. The funds have been returned to your order.",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("Order ID", &truncate_address(order_id)),
                     ("Trade ID", &truncate_address(trade_id)),
                     ("Amount", &format!("{} {}", token_amount, token_symbol)),
-                    ("CNY Value", &format_cny_amount(cny_amount)),
+                    ("Value", &fiat(cny_amount, *currency)),
                 ],
                 app_url,
                 "/account",
@@ -285,20 +295,20 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
         },
         
         // Trade Expired (Buyer)
-        (EmailEvent::TradeExpiredBuyer, EmailInfo::TradeExpiredBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount }) => {
+        (EmailEvent::TradeExpiredBuyer, EmailInfo::TradeExpiredBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, currency }) => {
             let subject = "⏰ Your Purchase Has Expired".to_string();
             let html = format_simple_email(
                 "Purchase expired - payment not completed in time",
                 &format!(
                     "Your purchase of <strong>{} {}</strong> ({}) has expired because payment \
                     was not completed within the required time window. You can start a new purchase anytime.",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("Order ID", &truncate_address(order_id)),
                     ("Trade ID", &truncate_address(trade_id)),
                     ("Amount", &format!("{} {}", token_amount, token_symbol)),
-                    ("CNY Value", &format_cny_amount(cny_amount)),
+                    ("Value", &fiat(cny_amount, *currency)),
                 ],
                 app_url,
                 "/buy",
@@ -308,9 +318,71 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
             (subject, html)
         },
         
+        (EmailEvent::TradeExpiringSoon, EmailInfo::TradeExpiringSoon { order_id, trade_id, token_amount, token_symbol, cny_amount, expires_at, currency }) => {
+            let subject = "⏳ Your Purchase Expires Soon".to_string();
+            let html = format_simple_email(
+                "Reminder - complete your payment",
+                &format!(
+                    "Your purchase of <strong>{} {}</strong> ({}) expires soon. \
+                    If you have paid, upload your payment receipt before it expires; otherwise the trade will be cancelled.",
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
+                ),
+                &[
+                    ("Order ID", &truncate_address(order_id)),
+                    ("Trade ID", &truncate_address(trade_id)),
+                    ("You'll Receive", &format!("{} {}", token_amount, token_symbol)),
+                    ("Amount to Pay", &fiat(cny_amount, *currency)),
+                    ("Expires", &format_expires_at(*expires_at)),
+                ],
+                app_url,
+                "/account",
+                "View Purchase",
+                "— LyncZ",
+            );
+            (subject, html)
+        },
+        
+        (EmailEvent::DailyDigest, EmailInfo::DailyDigest { date, new_trades, settled_trades, expired_trades }) => {
+            let subject = format!("📊 Your LyncZ Activity for {}", date);
+            let html = format_simple_email(
+                "Your daily activity summary",
+                &format!(
+                    "Here's what happened on your orders and purchases on <strong>{}</strong> (UTC).",
+                    date
+                ),
+                &[
+                    ("New Trades", &new_trades.to_string()),
+                    ("Settled", &settled_trades.to_string()),
+                    ("Expired", &expired_trades.to_string()),
+                ],
+                app_url,
+                "/account",
+                "View Account",
+                "— LyncZ",
+            );
+            (subject, html)
+        },
+        
+        (EmailEvent::EmailVerification, EmailInfo::EmailVerification { email, confirm_url, valid_hours }) => {
+            let subject = "✉️ Confirm your LyncZ notification email".to_string();
+            let html = format_simple_email(
+                "Confirm your email address",
+                &format!(
+                    "Confirm that <strong>{}</strong> should receive LyncZ notifications for your wallet. If you didn't request this, ignore this email and nothing will be sent.",
+                    email
+                ),
+                &[("Link valid for", &format!("{} hours", valid_hours))],
+                "",
+                confirm_url,
+                "Confirm Email",
+                "— LyncZ",
+            );
+            (subject, html)
+        },
+        
         // Fallback for mismatched event/info combinations
         _ => {
-            ("LyncZ Notification".to_string(), "<p>You have a new notification from LyncZ.</p>".to_string())
+            (GENERIC_SUBJECTS[0].to_string(), "<p>You have a new notification from LyncZ.</p>".to_string())
         }
     }
 }
@@ -319,15 +391,15 @@ pub fn get_email_en(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (Stri
 pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (String, String) {
     match (event, info) {
         // 订单已创建（卖家）- 支持公开和非公开订单
-        (EmailEvent::OrderCreated, EmailInfo::OrderCreated { order_id, token_amount, token_symbol, exchange_rate, account_id, account_name, rail, is_private, private_code: _ }) => {
+        (EmailEvent::OrderCreated, EmailInfo::OrderCreated { order_id, token_amount, token_symbol, exchange_rate, account_id, account_name, rail, is_private, private_code: _, currency }) => {
             let (subject, title, message) = if *is_private {
                 (
                     "📦 您的灵犀支付卖单已就绪".to_string(),
                     "您的卖单已创建成功！",
                     format!(
-                        "您已挂出 <strong>{} {}</strong>，售价 <strong>{} CNY/{}</strong>。\
+                        "您已挂出 <strong>{} {}</strong>，售价 <strong>{}/{}</strong>。\
                         此订单为非公开订单，请访问我的账户页面获取分享码。",
-                        token_amount, token_symbol, format_exchange_rate(exchange_rate), token_symbol
+                        token_amount, token_symbol, format_exchange_rate(exchange_rate, *currency), token_symbol
                     )
                 )
             } else {
@@ -335,25 +407,24 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
                     "📦 您的灵犀支付卖单已上线".to_string(),
                     "您的卖单已创建成功！",
                     format!(
-                        "您已挂出 <strong>{} {}</strong>，售价 <strong>{} CNY/{}</strong>。\
+                        "您已挂出 <strong>{} {}</strong>，售价 <strong>{}/{}</strong>。\
                         买家现在可以从您的订单购买。",
-                        token_amount, token_symbol, format_exchange_rate(exchange_rate), token_symbol
+                        token_amount, token_symbol, format_exchange_rate(exchange_rate, *currency), token_symbol
                     )
                 )
             };
             
             // Localize rail name for Simplified Chinese
             let rail_name = match rail {
-                0 => "支付宝",
-                1 => "微信支付",
-                _ => "未知",
+                PaymentRail::Alipay => "支付宝",
+                PaymentRail::WeChat => "微信支付",
             };
             
             // Build details array - no code in email, user must visit site
             let details: Vec<(&str, String)> = vec![
                 ("订单ID", truncate_address(order_id)),
                 ("数量", format!("{} {}", token_amount, token_symbol)),
-                ("汇率", format!("{} CNY/{}", format_exchange_rate(exchange_rate), token_symbol)),
+                ("汇率", format!("{}/{}", format_exchange_rate(exchange_rate, *currency), token_symbol)),
                 ("收款账户", format!("{} ({})", account_name, account_id)),
                 ("收款方式", rail_name.to_string()),
                 ("展示方式", if *is_private { "非公开".to_string() } else { "公开".to_string() }),
@@ -397,18 +468,18 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 汇率已更新（卖家）
-        (EmailEvent::OrderUpdated, EmailInfo::ExchangeRateUpdated { order_id, old_rate, new_rate }) => {
+        (EmailEvent::OrderUpdated, EmailInfo::ExchangeRateUpdated { order_id, old_rate, new_rate, currency }) => {
             let subject = "📊 您的灵犀支付订单汇率已更新".to_string();
             let html = format_simple_email(
                 "汇率已更新",
                 &format!(
-                    "您已将卖单汇率从 <strong>{} CNY</strong> 更新为 <strong>{} CNY</strong>。",
-                    old_rate, new_rate
+                    "您已将卖单汇率从 <strong>{}</strong> 更新为 <strong>{}</strong>。",
+                    format_exchange_rate(old_rate, *currency), format_exchange_rate(new_rate, *currency)
                 ),
                 &[
                     ("订单ID", &truncate_address(order_id)),
-                    ("原汇率", &format!("{} CNY", old_rate)),
-                    ("新汇率", &format!("{} CNY", new_rate)),
+                    ("原汇率", &format_exchange_rate(old_rate, *currency)),
+                    ("新汇率", &format_exchange_rate(new_rate, *currency)),
                 ],
                 app_url,
                 "/account",
@@ -420,11 +491,7 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         
         // 收款信息已更新（卖家）
         (EmailEvent::OrderUpdated, EmailInfo::PaymentInfoUpdated { order_id, new_account_id, new_account_name, rail }) => {
-            let rail_name = match rail {
-                0 => "支付宝",
-                1 => "微信",
-                _ => "收款",
-            };
+            let rail_name = rail.display_name("zh-CN");
             let subject = "👤 您的灵犀支付订单收款信息已更新".to_string();
             let html = format_simple_email(
                 "收款信息已更新",
@@ -443,26 +510,22 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已创建（卖家视角）
-        (EmailEvent::TradeCreatedSeller, EmailInfo::TradeCreatedSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, account_id, account_name, rail }) => {
-            let rail_name = match rail {
-                0 => "支付宝",
-                1 => "微信",
-                _ => "收款",
-            };
+        (EmailEvent::TradeCreatedSeller, EmailInfo::TradeCreatedSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, account_id, account_name, rail, currency }) => {
+            let rail_name = rail.display_name("zh-CN");
             let subject = "🔔 您的灵犀支付订单有新交易".to_string();
             let html = format_simple_email(
                 "买家已发起交易！",
                 &format!(
                     "买家正在购买 <strong>{} {}</strong>，金额为 <strong>{}</strong>。\
                     买家有15分钟时间完成付款。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("订单ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("买家收到", &format!("{} {}", token_amount, token_symbol)),
                     ("平台手续费", &format!("-{} {}", fee_amount, token_symbol)),
-                    ("您收到", &format_cny_amount(cny_amount)),
+                    ("您收到", &fiat(cny_amount, *currency)),
                     ("买家", &truncate_address(buyer_address)),
                     (&format!("{}账户名", rail_name), account_name),
                     (&format!("{}账号", rail_name), account_id),
@@ -476,25 +539,21 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已创建（买家视角）
-        (EmailEvent::TradeCreatedBuyer, EmailInfo::TradeCreatedBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, seller_account_id, seller_account_name, rail, expires_at }) => {
-            let rail_name = match rail {
-                0 => "支付宝",
-                1 => "微信",
-                _ => "收款",
-            };
+        (EmailEvent::TradeCreatedBuyer, EmailInfo::TradeCreatedBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, seller_account_id, seller_account_name, rail, expires_at, currency }) => {
+            let rail_name = rail.display_name("zh-CN");
             let subject = "🛒 您的灵犀支付购买已开始".to_string();
             let html = format_simple_email(
                 "您的购买正在进行中！",
                 &format!(
                     "您正在购买 <strong>{} {}</strong>，金额为 <strong>{}</strong>。\
                     请在15分钟内向卖家账户完成付款。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("订单ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("您将收到", &format!("{} {}", token_amount, token_symbol)),
-                    ("需支付金额", &format_cny_amount(cny_amount)),
+                    ("需支付金额", &fiat(cny_amount, *currency)),
                     (&format!("{}账户名", rail_name), seller_account_name),
                     (&format!("{}账号", rail_name), seller_account_id),
                     ("过期时间", &format_expires_at(*expires_at)),
@@ -508,7 +567,7 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已结算（卖家视角）
-        (EmailEvent::TradeSettledSeller, EmailInfo::TradeSettledSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, settlement_tx, chain_id }) => {
+        (EmailEvent::TradeSettledSeller, EmailInfo::TradeSettledSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, settlement_tx, chain_id, currency }) => {
             let explorer = explorer_url(*chain_id);
             let subject = "✅ 交易成功结算 - 收款已确认".to_string();
             let html = format_simple_email(
@@ -516,14 +575,14 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
                 &format!(
                     "<strong>{} {}</strong>（{}）的交易已成功结算。\
                     买家的付款已验证，加密货币已释放给买家。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("订单ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("已售出", &format!("{} {}", token_amount, token_symbol)),
                     ("平台手续费", &format!("-{} {}", fee_amount, token_symbol)),
-                    ("已收到", &format_cny_amount(cny_amount)),
+                    ("已收到", &fiat(cny_amount, *currency)),
                     ("买家", &truncate_address(buyer_address)),
                     ("结算交易", &format!("<a href=\"{}/tx/{}\" style=\"color: #6366f1;\">{}</a>", explorer, settlement_tx, truncate_address(settlement_tx))),
                 ],
@@ -549,6 +608,7 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
                     ("订单ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("已收到", &format!("{} {}", token_amount, token_symbol)),
+                    ("网络", chain_name(*chain_id)),
                     ("结算交易", &format!("<a href=\"{}/tx/{}\" style=\"color: #6366f1;\">{}</a>", explorer, settlement_tx, truncate_address(settlement_tx))),
                 ],
                 app_url,
@@ -560,20 +620,20 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已过期（卖家）
-        (EmailEvent::TradeExpiredSeller, EmailInfo::TradeExpiredSeller { order_id, trade_id, token_amount, token_symbol, cny_amount }) => {
+        (EmailEvent::TradeExpiredSeller, EmailInfo::TradeExpiredSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, currency }) => {
             let subject = "⏰ 交易已过期".to_string();
             let html = format_simple_email(
                 "交易过期 - 资金已返还到您的订单",
                 &format!(
                     "<strong>{} {}</strong>（{}）的交易已过期，因为买家未能及时完成付款。\
                     资金已返还到您的订单中。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("订单ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("数量", &format!("{} {}", token_amount, token_symbol)),
-                    ("金额", &format_cny_amount(cny_amount)),
+                    ("金额", &fiat(cny_amount, *currency)),
                 ],
                 app_url,
                 "/account",
@@ -584,20 +644,20 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已过期（买家）
-        (EmailEvent::TradeExpiredBuyer, EmailInfo::TradeExpiredBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount }) => {
+        (EmailEvent::TradeExpiredBuyer, EmailInfo::TradeExpiredBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, currency }) => {
             let subject = "⏰ 您的购买已过期".to_string();
             let html = format_simple_email(
                 "购买过期 - 未在规定时间内完成付款",
                 &format!(
                     "您购买 <strong>{} {}</strong>（{}）的交易已过期，因为未能在规定时间内完成付款。\
                     您可以随时发起新的购买。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("订单ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("数量", &format!("{} {}", token_amount, token_symbol)),
-                    ("金额", &format_cny_amount(cny_amount)),
+                    ("金额", &fiat(cny_amount, *currency)),
                 ],
                 app_url,
                 "/buy",
@@ -607,8 +667,70 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
             (subject, html)
         },
         
+        (EmailEvent::TradeExpiringSoon, EmailInfo::TradeExpiringSoon { order_id, trade_id, token_amount, token_symbol, cny_amount, expires_at, currency }) => {
+            let subject = "⏳ 您的购买即将过期".to_string();
+            let html = format_simple_email(
+                "提醒 - 请尽快完成付款",
+                &format!(
+                    "您购买 <strong>{} {}</strong>（{}）的交易即将过期。\
+                    如已付款，请在过期前上传付款凭证；否则交易将被取消。",
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
+                ),
+                &[
+                    ("订单ID", &truncate_address(order_id)),
+                    ("交易ID", &truncate_address(trade_id)),
+                    ("您将收到", &format!("{} {}", token_amount, token_symbol)),
+                    ("需支付金额", &fiat(cny_amount, *currency)),
+                    ("过期时间", &format_expires_at(*expires_at)),
+                ],
+                app_url,
+                "/account",
+                "查看购买",
+                "— 灵犀支付",
+            );
+            (subject, html)
+        },
+        
+        (EmailEvent::DailyDigest, EmailInfo::DailyDigest { date, new_trades, settled_trades, expired_trades }) => {
+            let subject = format!("📊 您的灵犀支付每日动态（{}）", date);
+            let html = format_simple_email(
+                "每日动态汇总",
+                &format!(
+                    "以下是您的订单和购买在 <strong>{}</strong>（UTC）的动态。",
+                    date
+                ),
+                &[
+                    ("新交易", &new_trades.to_string()),
+                    ("已完成", &settled_trades.to_string()),
+                    ("已过期", &expired_trades.to_string()),
+                ],
+                app_url,
+                "/account",
+                "查看账户",
+                "— 灵犀支付",
+            );
+            (subject, html)
+        },
+        
+        (EmailEvent::EmailVerification, EmailInfo::EmailVerification { email, confirm_url, valid_hours }) => {
+            let subject = "✉️ 请确认您的灵犀支付通知邮箱".to_string();
+            let html = format_simple_email(
+                "确认您的邮箱地址",
+                &format!(
+                    "请确认 <strong>{}</strong> 用于接收您钱包的灵犀支付通知。如果这不是您本人的操作，请忽略此邮件，我们不会再发送任何邮件。",
+                    email
+                ),
+                &[("链接有效期", &format!("{} 小时", valid_hours))],
+                "",
+                confirm_url,
+                "确认邮箱",
+                "— 灵犀支付",
+            );
+            (subject, html)
+        },
+        
         _ => {
-            ("灵犀支付通知".to_string(), "<p>您有一条新的灵犀支付通知。</p>".to_string())
+            (GENERIC_SUBJECTS[1].to_string(), "<p>您有一条新的灵犀支付通知。</p>".to_string())
         }
     }
 }
@@ -617,15 +739,15 @@ pub fn get_email_zh_cn(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
 pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (String, String) {
     match (event, info) {
         // 訂單已創建（賣家）- 支持公開和非公開訂單
-        (EmailEvent::OrderCreated, EmailInfo::OrderCreated { order_id, token_amount, token_symbol, exchange_rate, account_id, account_name, rail, is_private, private_code: _ }) => {
+        (EmailEvent::OrderCreated, EmailInfo::OrderCreated { order_id, token_amount, token_symbol, exchange_rate, account_id, account_name, rail, is_private, private_code: _, currency }) => {
             let (subject, title, message) = if *is_private {
                 (
                     "📦 您的靈犀支付賣單已就緒".to_string(),
                     "您的賣單已創建成功！",
                     format!(
-                        "您已掛出 <strong>{} {}</strong>，售價 <strong>{} CNY/{}</strong>。\
+                        "您已掛出 <strong>{} {}</strong>，售價 <strong>{}/{}</strong>。\
                         此訂單為非公開訂單，請訪問我的帳戶頁面獲取分享碼。",
-                        token_amount, token_symbol, format_exchange_rate(exchange_rate), token_symbol
+                        token_amount, token_symbol, format_exchange_rate(exchange_rate, *currency), token_symbol
                     )
                 )
             } else {
@@ -633,25 +755,24 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
                     "📦 您的靈犀支付賣單已上線".to_string(),
                     "您的賣單已創建成功！",
                     format!(
-                        "您已掛出 <strong>{} {}</strong>，售價 <strong>{} CNY/{}</strong>。\
+                        "您已掛出 <strong>{} {}</strong>，售價 <strong>{}/{}</strong>。\
                         買家現在可以從您的訂單購買。",
-                        token_amount, token_symbol, format_exchange_rate(exchange_rate), token_symbol
+                        token_amount, token_symbol, format_exchange_rate(exchange_rate, *currency), token_symbol
                     )
                 )
             };
             
             // Localize rail name for Traditional Chinese
             let rail_name = match rail {
-                0 => "支付寶",
-                1 => "微信支付",
-                _ => "未知",
+                PaymentRail::Alipay => "支付寶",
+                PaymentRail::WeChat => "微信支付",
             };
             
             // Build details array - no code in email, user must visit site
             let details: Vec<(&str, String)> = vec![
                 ("訂單ID", truncate_address(order_id)),
                 ("數量", format!("{} {}", token_amount, token_symbol)),
-                ("匯率", format!("{} CNY/{}", format_exchange_rate(exchange_rate), token_symbol)),
+                ("匯率", format!("{}/{}", format_exchange_rate(exchange_rate, *currency), token_symbol)),
                 ("收款帳戶", format!("{} ({})", account_name, account_id)),
                 ("收款方式", rail_name.to_string()),
                 ("展示方式", if *is_private { "非公開".to_string() } else { "公開".to_string() }),
@@ -695,18 +816,18 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 匯率已更新（賣家）
-        (EmailEvent::OrderUpdated, EmailInfo::ExchangeRateUpdated { order_id, old_rate, new_rate }) => {
+        (EmailEvent::OrderUpdated, EmailInfo::ExchangeRateUpdated { order_id, old_rate, new_rate, currency }) => {
             let subject = "📊 您的靈犀支付訂單匯率已更新".to_string();
             let html = format_simple_email(
                 "匯率已更新",
                 &format!(
-                    "您已將賣單匯率從 <strong>{} CNY</strong> 更新為 <strong>{} CNY</strong>。",
-                    old_rate, new_rate
+                    "您已將賣單匯率從 <strong>{}</strong> 更新為 <strong>{}</strong>。",
+                    format_exchange_rate(old_rate, *currency), format_exchange_rate(new_rate, *currency)
                 ),
                 &[
                     ("訂單ID", &truncate_address(order_id)),
-                    ("原匯率", &format!("{} CNY", old_rate)),
-                    ("新匯率", &format!("{} CNY", new_rate)),
+                    ("原匯率", &format_exchange_rate(old_rate, *currency)),
+                    ("新匯率", &format_exchange_rate(new_rate, *currency)),
                 ],
                 app_url,
                 "/account",
@@ -718,11 +839,7 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         
         // 收款資訊已更新（賣家）
         (EmailEvent::OrderUpdated, EmailInfo::PaymentInfoUpdated { order_id, new_account_id, new_account_name, rail }) => {
-            let rail_name = match rail {
-                0 => "支付寶",
-                1 => "微信",
-                _ => "收款",
-            };
+            let rail_name = rail.display_name("zh-TW");
             let subject = "👤 您的靈犀支付訂單收款資訊已更新".to_string();
             let html = format_simple_email(
                 "收款資訊已更新",
@@ -741,26 +858,22 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已創建（賣家視角）
-        (EmailEvent::TradeCreatedSeller, EmailInfo::TradeCreatedSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, account_id, account_name, rail }) => {
-            let rail_name = match rail {
-                0 => "支付寶",
-                1 => "微信",
-                _ => "收款",
-            };
+        (EmailEvent::TradeCreatedSeller, EmailInfo::TradeCreatedSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, account_id, account_name, rail, currency }) => {
+            let rail_name = rail.display_name("zh-TW");
             let subject = "🔔 您的靈犀支付訂單有新交易".to_string();
             let html = format_simple_email(
                 "買家已發起交易！",
                 &format!(
                     "買家正在購買 <strong>{} {}</strong>，金額為 <strong>{}</strong>。\
                     買家有15分鐘時間完成付款。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("訂單ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("買家收到", &format!("{} {}", token_amount, token_symbol)),
                     ("平台手續費", &format!("-{} {}", fee_amount, token_symbol)),
-                    ("您收到", &format_cny_amount(cny_amount)),
+                    ("您收到", &fiat(cny_amount, *currency)),
                     ("買家", &truncate_address(buyer_address)),
                     (&format!("{}帳戶名", rail_name), account_name),
                     (&format!("{}帳號", rail_name), account_id),
@@ -774,25 +887,21 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已創建（買家視角）
-        (EmailEvent::TradeCreatedBuyer, EmailInfo::TradeCreatedBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, seller_account_id, seller_account_name, rail, expires_at }) => {
-            let rail_name = match rail {
-                0 => "支付寶",
-                1 => "微信",
-                _ => "收款",
-            };
+        (EmailEvent::TradeCreatedBuyer, EmailInfo::TradeCreatedBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, seller_account_id, seller_account_name, rail, expires_at, currency }) => {
+            let rail_name = rail.display_name("zh-TW");
             let subject = "🛒 您的靈犀支付購買已開始".to_string();
             let html = format_simple_email(
                 "您的購買正在進行中！",
                 &format!(
                     "您正在購買 <strong>{} {}</strong>，金額為 <strong>{}</strong>。\
                     請在15分鐘內向賣家帳戶完成付款。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("訂單ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("您將收到", &format!("{} {}", token_amount, token_symbol)),
-                    ("需支付金額", &format_cny_amount(cny_amount)),
+                    ("需支付金額", &fiat(cny_amount, *currency)),
                     (&format!("{}帳戶名", rail_name), seller_account_name),
                     (&format!("{}帳號", rail_name), seller_account_id),
                     ("過期時間", &format_expires_at(*expires_at)),
@@ -806,7 +915,7 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已結算（賣家視角）
-        (EmailEvent::TradeSettledSeller, EmailInfo::TradeSettledSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, settlement_tx, chain_id }) => {
+        (EmailEvent::TradeSettledSeller, EmailInfo::TradeSettledSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, fee_amount, buyer_address, settlement_tx, chain_id, currency }) => {
             let explorer = explorer_url(*chain_id);
             let subject = "✅ 交易成功結算 - 收款已確認".to_string();
             let html = format_simple_email(
@@ -814,14 +923,14 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
                 &format!(
                     "<strong>{} {}</strong>（{}）的交易已成功結算。\
                     買家的付款已驗證，加密貨幣已釋放給買家。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("訂單ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("已售出", &format!("{} {}", token_amount, token_symbol)),
                     ("平台手續費", &format!("-{} {}", fee_amount, token_symbol)),
-                    ("已收到", &format_cny_amount(cny_amount)),
+                    ("已收到", &fiat(cny_amount, *currency)),
                     ("買家", &truncate_address(buyer_address)),
                     ("結算交易", &format!("<a href=\"{}/tx/{}\" style=\"color: #6366f1;\">{}</a>", explorer, settlement_tx, truncate_address(settlement_tx))),
                 ],
@@ -847,6 +956,7 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
                     ("訂單ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("已收到", &format!("{} {}", token_amount, token_symbol)),
+                    ("網路", chain_name(*chain_id)),
                     ("結算交易", &format!("<a href=\"{}/tx/{}\" style=\"color: #6366f1;\">{}</a>", explorer, settlement_tx, truncate_address(settlement_tx))),
                 ],
                 app_url,
@@ -858,20 +968,20 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已過期（賣家）
-        (EmailEvent::TradeExpiredSeller, EmailInfo::TradeExpiredSeller { order_id, trade_id, token_amount, token_symbol, cny_amount }) => {
+        (EmailEvent::TradeExpiredSeller, EmailInfo::TradeExpiredSeller { order_id, trade_id, token_amount, token_symbol, cny_amount, currency }) => {
             let subject = "⏰ 交易已過期".to_string();
             let html = format_simple_email(
                 "交易過期 - 資金已返還到您的訂單",
                 &format!(
                     "<strong>{} {}</strong>（{}）的交易已過期，因為買家未能及時完成付款。\
                     資金已返還到您的訂單中。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("訂單ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("數量", &format!("{} {}", token_amount, token_symbol)),
-                    ("金額", &format_cny_amount(cny_amount)),
+                    ("金額", &fiat(cny_amount, *currency)),
                 ],
                 app_url,
                 "/account",
@@ -882,20 +992,20 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
         },
         
         // 交易已過期（買家）
-        (EmailEvent::TradeExpiredBuyer, EmailInfo::TradeExpiredBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount }) => {
+        (EmailEvent::TradeExpiredBuyer, EmailInfo::TradeExpiredBuyer { order_id, trade_id, token_amount, token_symbol, cny_amount, currency }) => {
             let subject = "⏰ 您的購買已過期".to_string();
             let html = format_simple_email(
                 "購買過期 - 未在規定時間內完成付款",
                 &format!(
                     "您購買 <strong>{} {}</strong>（{}）的交易已過期，因為未能在規定時間內完成付款。\
                     您可以隨時發起新的購買。",
-                    token_amount, token_symbol, format_cny_amount(cny_amount)
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
                 ),
                 &[
                     ("訂單ID", &truncate_address(order_id)),
                     ("交易ID", &truncate_address(trade_id)),
                     ("數量", &format!("{} {}", token_amount, token_symbol)),
-                    ("金額", &format_cny_amount(cny_amount)),
+                    ("金額", &fiat(cny_amount, *currency)),
                 ],
                 app_url,
                 "/buy",
@@ -905,18 +1015,89 @@ pub fn get_email_zh_tw(event: EmailEvent, info: &EmailInfo, app_url: &str) -> (S
             (subject, html)
         },
         
+        (EmailEvent::TradeExpiringSoon, EmailInfo::TradeExpiringSoon { order_id, trade_id, token_amount, token_symbol, cny_amount, expires_at, currency }) => {
+            let subject = "⏳ 您的購買即將過期".to_string();
+            let html = format_simple_email(
+                "提醒 - 請盡快完成付款",
+                &format!(
+                    "您購買 <strong>{} {}</strong>（{}）的交易即將過期。\
+                    如已付款，請在過期前上傳付款憑證；否則交易將被取消。",
+                    token_amount, token_symbol, fiat(cny_amount, *currency)
+                ),
+                &[
+                    ("訂單ID", &truncate_address(order_id)),
+                    ("交易ID", &truncate_address(trade_id)),
+                    ("您將收到", &format!("{} {}", token_amount, token_symbol)),
+                    ("需支付金額", &fiat(cny_amount, *currency)),
+                    ("過期時間", &format_expires_at(*expires_at)),
+                ],
+                app_url,
+                "/account",
+                "查看購買",
+                "— 靈犀支付",
+            );
+            (subject, html)
+        },
+        
+        (EmailEvent::DailyDigest, EmailInfo::DailyDigest { date, new_trades, settled_trades, expired_trades }) => {
+            let subject = format!("📊 您的靈犀支付每日動態（{}）", date);
+            let html = format_simple_email(
+                "每日動態匯總",
+                &format!(
+                    "以下是您的訂單和購買在 <strong>{}</strong>（UTC）的動態。",
+                    date
+                ),
+                &[
+                    ("新交易", &new_trades.to_string()),
+                    ("已完成", &settled_trades.to_string()),
+                    ("已過期", &expired_trades.to_string()),
+                ],
+                app_url,
+                "/account",
+                "查看帳戶",
+                "— 靈犀支付",
+            );
+            (subject, html)
+        },
+        
+        (EmailEvent::EmailVerification, EmailInfo::EmailVerification { email, confirm_url, valid_hours }) => {
+            let subject = "✉️ 請確認您的靈犀支付通知郵箱".to_string();
+            let html = format_simple_email(
+                "確認您的郵箱地址",
+                &format!(
+                    "請確認 <strong>{}</strong> 用於接收您錢包的靈犀支付通知。如果這不是您本人的操作，請忽略此郵件，我們不會再發送任何郵件。",
+                    email
+                ),
+                &[("連結有效期", &format!("{} 小時", valid_hours))],
+                "",
+                confirm_url,
+                "確認郵箱",
+                "— 靈犀支付",
+            );
+            (subject, html)
+        },
+        
         _ => {
-            ("靈犀支付通知".to_string(), "<p>您有一條新的靈犀支付通知。</p>".to_string())
+            (GENERIC_SUBJECTS[2].to_string(), "<p>您有一條新的靈犀支付通知。</p>".to_string())
         }
     }
 }
 
-/// Format exchange rate from cents to human readable
-fn format_exchange_rate(rate: &str) -> String {
-    let rate_u64: u64 = rate.parse().unwrap_or(0);
-    let yuan = rate_u64 / 100;
-    let fen = rate_u64 % 100;
-    format!("{}.{:02}", yuan, fen)
+/// Fiat amount (currency minor units) for an email body; a malformed amount is shown as stored
+fn fiat(minor_units: &str, currency: Currency) -> String {
+    format_fiat(minor_units, currency).unwrap_or_else(InvalidAmount::into_raw)
+}
+
+/// Format an exchange rate (currency minor units per token) as e.g. "7.20 CNY" (as stored if malformed)
+fn format_exchange_rate(rate: &str, currency: Currency) -> String {
+    match parse_amount::<u64>(rate) {
+        Ok(rate_u64) if currency.decimals() == 0 => format!("{} {}", rate_u64, currency),
+        Ok(rate_u64) => {
+            let divisor = currency.minor_unit_divisor();
+            format!("{}.{:0width$} {}", rate_u64 / divisor, rate_u64 % divisor, currency, width = currency.decimals() as usize)
+        }
+        Err(e) => e.into_raw(),
+    }
 }
 
 /// Format a simple email with key-value details
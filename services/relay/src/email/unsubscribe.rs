@@ -0,0 +1,130 @@
+//! One-click unsubscribe links for notification emails
+//!
+//! Every notification carries a link to `GET /api/account/email/unsubscribe?token=`
+//! (and a matching `List-Unsubscribe` header). The token is
+//! `<wallet>.<hex HMAC-SHA256(secret, PURPOSE || wallet)>`: it names exactly one
+//! wallet, is useless for any other signed action, and the endpoint it unlocks
+//! can only turn notifications off, so replaying it never re-subscribes anyone.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::sync::OnceLock;
+
+/// Domain-separation tag mixed into every unsubscribe MAC
+const PURPOSE: &str = "lyncz:email-unsubscribe:v1:";
+
+/// Cached signing secret - generated once if EMAIL_UNSUBSCRIBE_SECRET is unset
+static UNSUBSCRIBE_SECRET: OnceLock<String> = OnceLock::new();
+
+/// Also signs email confirmation links (under their own purpose tag, see email::verification)
+pub(super) fn unsubscribe_secret() -> &'static str {
+    UNSUBSCRIBE_SECRET.get_or_init(|| {
+        std::env::var("EMAIL_UNSUBSCRIBE_SECRET").unwrap_or_else(|_| {
+            tracing::warn!("EMAIL_UNSUBSCRIBE_SECRET not set, generating random secret (unsubscribe links won't survive restarts)");
+            use rand::Rng;
+            rand::thread_rng()
+                .sample_iter(&rand::distributions::Alphanumeric)
+                .take(64)
+                .map(char::from)
+                .collect()
+        })
+    })
+}
+
+fn mac_for(secret: &str, wallet: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(PURPOSE.as_bytes());
+    mac.update(wallet.as_bytes());
+    mac
+}
+
+fn sign_with(secret: &str, wallet: &str) -> String {
+    let wallet = wallet.to_lowercase();
+    let signature = hex::encode(mac_for(secret, &wallet).finalize().into_bytes());
+    format!("{}.{}", wallet, signature)
+}
+
+fn verify_with(secret: &str, token: &str) -> Result<String, String> {
+    let (wallet, signature) = token.split_once('.').ok_or("Malformed unsubscribe token")?;
+    if !wallet.starts_with("0x") || wallet.len() != 42 || wallet != wallet.to_lowercase() {
+        return Err("Malformed unsubscribe token".to_string());
+    }
+    let signature = hex::decode(signature).map_err(|_| "Malformed unsubscribe token")?;
+    mac_for(secret, wallet)
+        .verify_slice(&signature)
+        .map_err(|_| "Invalid unsubscribe token".to_string())?;
+    Ok(wallet.to_string())
+}
+
+/// Token authorizing notifications to be switched off for `wallet`
+pub fn create_token(wallet: &str) -> String {
+    sign_with(unsubscribe_secret(), wallet)
+}
+
+/// Check a token and return the (lowercase) wallet it was issued for
+pub fn verify_token(token: &str) -> Result<String, String> {
+    verify_with(unsubscribe_secret(), token)
+}
+
+/// Unsubscribe link served by the relay API
+pub fn unsubscribe_url(api_url: &str, wallet: &str) -> String {
+    format!(
+        "{}/api/account/email/unsubscribe?token={}",
+        api_url.trim_end_matches('/'),
+        create_token(wallet)
+    )
+}
+
+/// Append a localized unsubscribe line just before `</body>`
+pub fn with_unsubscribe_footer(html: &str, language: &str, url: &str) -> String {
+    let (text, link) = match language {
+        "zh-CN" => ("不想再收到这些邮件？", "退订通知"),
+        "zh-TW" => ("不想再收到這些郵件？", "退訂通知"),
+        _ => ("Don't want these emails?", "Unsubscribe"),
+    };
+    let footer = format!(
+        r#"<p style="margin: 0 0 30px; text-align: center; color: #9ca3af; font-size: 12px;">{} <a href="{}" style="color: #9ca3af;">{}</a></p>"#,
+        text, url, link
+    );
+    match html.rfind("</body>") {
+        Some(pos) => format!("{}{}\n{}", &html[..pos], footer, &html[pos..]),
+        None => format!("{}{}", html, footer),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WALLET: &str = "0xAbC0000000000000000000000000000000000001";
+
+    #[test]
+    fn test_token_round_trip() {
+        let token = sign_with("secret", WALLET);
+        assert_eq!(verify_with("secret", &token).unwrap(), WALLET.to_lowercase());
+        assert!(verify_with("other-secret", &token).is_err());
+    }
+
+    #[test]
+    fn test_token_cannot_be_retargeted() {
+        let token = sign_with("secret", WALLET);
+        let (_, signature) = token.split_once('.').unwrap();
+        let forged = format!("0x{}.{}", "22".repeat(20), signature);
+        assert!(verify_with("secret", &forged).is_err());
+
+        // A bare HMAC of the address (no purpose tag) is not a valid token either
+        let mut mac = Hmac::<Sha256>::new_from_slice(b"secret").unwrap();
+        mac.update(WALLET.to_lowercase().as_bytes());
+        let untagged = format!("{}.{}", WALLET.to_lowercase(), hex::encode(mac.finalize().into_bytes()));
+        assert!(verify_with("secret", &untagged).is_err());
+    }
+
+    #[test]
+    fn test_footer_inserted_before_body_close() {
+        let html = with_unsubscribe_footer("<html><body><p>Hi</p></body></html>", "zh-CN", "https://x/u");
+        assert!(html.contains(r#"<a href="https://x/u""#));
+        assert!(html.contains("退订通知"));
+        assert!(html.ends_with("</body></html>"));
+    }
+}
@@ -0,0 +1,154 @@
+//! Confirmation links for account email addresses
+//!
+//! Storing a new address sends it a link to `GET /api/account/email/confirm?token=`,
+//! and notifications only go to the address once the link was opened (see
+//! `DbAccountEmail::receives_notifications`). The token is
+//! `<wallet>.<nonce>.<expires_at>.<hex HMAC-SHA256(secret, PURPOSE || wallet.nonce.expires_at)>`,
+//! signed with the unsubscribe secret under its own purpose tag.
+//!
+//! It is time-bounded by `expires_at` (unix seconds, EMAIL_VERIFICATION_TTL_SECS
+//! after sending) and single-use: `nonce` must still be the account's pending
+//! verification nonce, which confirming clears and storing a different address
+//! replaces.
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+use super::unsubscribe::unsubscribe_secret;
+
+/// Default confirmation link lifetime (24 hours)
+pub const DEFAULT_TTL_SECS: u64 = 24 * 3600;
+
+/// Domain-separation tag mixed into every confirmation MAC
+const PURPOSE: &str = "lyncz:email-verify:v1:";
+
+/// What a valid confirmation token names
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfirmationToken {
+    /// Lowercase wallet address
+    pub wallet: String,
+    /// Must match the account's pending verification nonce
+    pub nonce: String,
+}
+
+/// Fresh verification nonce to store with an unverified address
+pub fn new_nonce() -> String {
+    use rand::Rng;
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(32)
+        .map(char::from)
+        .collect()
+}
+
+fn mac_for(secret: &str, payload: &str) -> Hmac<Sha256> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(PURPOSE.as_bytes());
+    mac.update(payload.as_bytes());
+    mac
+}
+
+fn sign_with(secret: &str, wallet: &str, nonce: &str, expires_at: i64) -> String {
+    let payload = format!("{}.{}.{}", wallet.to_lowercase(), nonce, expires_at);
+    let signature = hex::encode(mac_for(secret, &payload).finalize().into_bytes());
+    format!("{}.{}", payload, signature)
+}
+
+fn verify_with(secret: &str, token: &str, now: i64) -> Result<ConfirmationToken, String> {
+    const MALFORMED: &str = "Malformed confirmation token";
+    let (payload, signature) = token.rsplit_once('.').ok_or(MALFORMED)?;
+    let mut parts = payload.split('.');
+    let (Some(wallet), Some(nonce), Some(expires_at), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(MALFORMED.to_string());
+    };
+    if !wallet.starts_with("0x") || wallet.len() != 42 || wallet != wallet.to_lowercase() || nonce.is_empty() {
+        return Err(MALFORMED.to_string());
+    }
+    let expires_at: i64 = expires_at.parse().map_err(|_| MALFORMED)?;
+    let signature = hex::decode(signature).map_err(|_| MALFORMED)?;
+    mac_for(secret, payload)
+        .verify_slice(&signature)
+        .map_err(|_| "Invalid confirmation token".to_string())?;
+    if now >= expires_at {
+        return Err("Confirmation link expired, please save your email again to get a new one".to_string());
+    }
+    Ok(ConfirmationToken { wallet: wallet.to_string(), nonce: nonce.to_string() })
+}
+
+/// Token confirming `wallet`'s pending address (identified by `nonce`) until `expires_at`
+pub fn create_token(wallet: &str, nonce: &str, expires_at: i64) -> String {
+    sign_with(unsubscribe_secret(), wallet, nonce, expires_at)
+}
+
+/// Check a token's signature and expiry (whether it's still pending is up to the DB)
+pub fn verify_token(token: &str, now: i64) -> Result<ConfirmationToken, String> {
+    verify_with(unsubscribe_secret(), token, now)
+}
+
+/// Confirmation link served by the relay API
+pub fn confirm_url(api_url: &str, wallet: &str, nonce: &str, expires_at: i64) -> String {
+    format!(
+        "{}/api/account/email/confirm?token={}",
+        api_url.trim_end_matches('/'),
+        create_token(wallet, nonce, expires_at)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::account_emails::AccountEmailRepository;
+
+    const WALLET: &str = "0xAbC0000000000000000000000000000000000001";
+
+    #[test]
+    fn test_token_round_trip_and_expiry() {
+        let token = sign_with("secret", WALLET, "n0nce", 1_000);
+        assert_eq!(verify_with("secret", &token, 999).unwrap(), ConfirmationToken {
+            wallet: WALLET.to_lowercase(),
+            nonce: "n0nce".to_string(),
+        });
+        assert!(verify_with("secret", &token, 1_000).unwrap_err().contains("expired"));
+        assert!(verify_with("other-secret", &token, 999).is_err());
+
+        // Extending the expiry breaks the signature
+        let extended = token.replacen(".1000.", ".9999.", 1);
+        assert_eq!(verify_with("secret", &extended, 999).unwrap_err(), "Invalid confirmation token");
+
+        // An unsubscribe token for the same wallet is not a confirmation token
+        assert!(verify_with("secret", &crate::email::unsubscribe::create_token(WALLET), 0).is_err());
+    }
+
+    /// Run with `DATABASE_URL=postgres://... cargo test -- --ignored`
+    #[sqlx::test]
+    #[ignore = "needs DATABASE_URL"]
+    async fn test_only_confirmed_accounts_receive_notifications(pool: sqlx::PgPool) {
+        let repo = AccountEmailRepository::new(pool);
+        let pending_nonce = new_nonce();
+        let account = repo.upsert(WALLET, "seller@example.com", "en", &pending_nonce).await.unwrap();
+        assert!(!account.verified);
+        assert!(repo.get_if_enabled(WALLET).await.unwrap().is_none());
+
+        // Opening the link confirms the nonce it names
+        let token = sign_with("secret", WALLET, &pending_nonce, 1_000);
+        let confirmed = verify_with("secret", &token, 500).unwrap();
+        assert!(repo.confirm(&confirmed.wallet, &confirmed.nonce).await.unwrap());
+        assert_eq!(repo.get_if_enabled(WALLET).await.unwrap().unwrap().email, "seller@example.com");
+
+        // The link works once
+        assert!(!repo.confirm(&confirmed.wallet, &confirmed.nonce).await.unwrap());
+
+        // Re-saving the same address keeps it verified; a new address needs its own link
+        repo.upsert(WALLET, "Seller@Example.com", "en", &new_nonce()).await.unwrap();
+        assert!(repo.get_if_enabled(WALLET).await.unwrap().is_some());
+        let changed_nonce = new_nonce();
+        repo.upsert(WALLET, "other@example.com", "en", &changed_nonce).await.unwrap();
+        assert!(repo.get_if_enabled(WALLET).await.unwrap().is_none());
+        assert!(!repo.confirm(WALLET, &pending_nonce).await.unwrap());
+        assert!(repo.confirm(WALLET, &changed_nonce).await.unwrap());
+
+        repo.set_enabled(WALLET, false).await.unwrap();
+        assert!(repo.get_if_enabled(WALLET).await.unwrap().is_none());
+    }
+}
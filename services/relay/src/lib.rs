@@ -9,14 +9,22 @@
 
 pub mod config;
 pub mod crypto;
+pub mod currency;
 pub mod db;
 pub mod api;
 pub mod auth;
 pub mod blockchain;
 pub mod axiom_prover;
 pub mod email;
+pub mod price_oracle;
+pub mod rail;
+pub mod retry;
+pub mod storage;
+pub mod telemetry;
+pub mod tokens;
+pub mod webhooks;
 
-pub use config::{Config, ChainConfig};
+pub use config::{Config, ChainConfig, ApiConfig};
 pub use db::{Database, DbError, DbResult};
 pub use api::{AppState, create_router};
 pub use email::{EmailService, EmailEvent, EmailInfo};
@@ -0,0 +1,330 @@
+//! Reference fiat prices for exchange-rate sanity checks
+//!
+//! Sellers set `exchange_rate` themselves and the relay doesn't create orders,
+//! so nothing stops a fat-fingered (or deliberately misleading) rate from being
+//! listed. When enabled, listings compare each order's rate with a reference
+//! spot price and attach a `warning` to orders that deviate by more than
+//! PRICE_ORACLE_MAX_DEVIATION_PCT - they're flagged, never rejected.
+//!
+//! Disabled unless PRICE_ORACLE_ENABLED=true. Prices come from a `PriceSource`;
+//! the built-in `SpotPriceSource` GETs PRICE_ORACLE_URL (default: Coinbase spot
+//! prices) and is cached per (token, currency) for PRICE_ORACLE_CACHE_SECS.
+//! A source that can't answer simply produces no warning; the failure is cached
+//! too (for FAILURE_CACHE_SECS), so an unreachable source costs one timeout per
+//! window instead of one per listed order.
+
+use async_trait::async_trait;
+use reqwest::Client;
+use rust_decimal::Decimal;
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::currency::Currency;
+use crate::tokens;
+
+/// Default spot price endpoint; `{base}` / `{quote}` are replaced (e.g. USDC / CNY)
+pub const DEFAULT_PRICE_URL: &str = "https://api.coinbase.com/v2/prices/{base}-{quote}/spot";
+
+/// Default allowed deviation from the reference price (percent)
+pub const DEFAULT_MAX_DEVIATION_PCT: u32 = 10;
+
+/// Default reference price cache lifetime (5 minutes)
+pub const DEFAULT_CACHE_SECS: u64 = 300;
+
+/// Per-request timeout for the price source
+const REQUEST_TIMEOUT_SECS: u64 = 5;
+
+/// How long a failed lookup is remembered before the source is asked again
+const FAILURE_CACHE_SECS: u64 = 30;
+
+/// Where reference prices come from
+#[async_trait]
+pub trait PriceSource: Send + Sync {
+    /// Price of one `base` asset (e.g. "USDC", "ETH") in major units of `quote`
+    async fn spot_price(&self, base: &str, quote: Currency) -> Result<Decimal, String>;
+}
+
+/// HTTP spot price API answering `{"data": {"amount": "7.15"}}` (Coinbase format)
+pub struct SpotPriceSource {
+    client: Client,
+    url_template: String,
+}
+
+impl SpotPriceSource {
+    pub fn new(url_template: String) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+        Self { client, url_template }
+    }
+}
+
+#[async_trait]
+impl PriceSource for SpotPriceSource {
+    async fn spot_price(&self, base: &str, quote: Currency) -> Result<Decimal, String> {
+        let url = self.url_template.replace("{base}", base).replace("{quote}", quote.code());
+        let body: serde_json::Value = self.client
+            .get(&url)
+            .send()
+            .await
+            .and_then(|r| r.error_for_status())
+            .map_err(|e| e.to_string())?
+            .json()
+            .await
+            .map_err(|e| e.to_string())?;
+        let amount = body["data"]["amount"]
+            .as_str()
+            .ok_or_else(|| format!("No data.amount in price response from {}", url))?;
+        Decimal::from_str(amount).map_err(|e| format!("Invalid price {:?}: {}", amount, e))
+    }
+}
+
+/// (reference symbol, currency) -> (price, or None if the lookup failed; fetched at)
+type PriceEntries = HashMap<(&'static str, Currency), (Option<Decimal>, Instant)>;
+
+/// Cached reference prices and the deviation threshold
+pub struct PriceOracle {
+    source: Arc<dyn PriceSource>,
+    max_deviation_pct: Decimal,
+    ttl: Duration,
+    prices: Mutex<PriceEntries>,
+}
+
+impl PriceOracle {
+    pub fn new(source: Arc<dyn PriceSource>, max_deviation_pct: Decimal, ttl: Duration) -> Self {
+        Self { source, max_deviation_pct, ttl, prices: Mutex::new(HashMap::new()) }
+    }
+
+    /// Load from env; None (no checks) unless PRICE_ORACLE_ENABLED=true
+    ///
+    ///   PRICE_ORACLE_ENABLED            true/false (default false)
+    ///   PRICE_ORACLE_URL                spot price URL with {base} and {quote} (default Coinbase)
+    ///   PRICE_ORACLE_MAX_DEVIATION_PCT  flag rates further than this from the reference (default 10)
+    ///   PRICE_ORACLE_CACHE_SECS         reference price lifetime (default 300)
+    pub fn from_env() -> Option<Arc<Self>> {
+        let enabled = std::env::var("PRICE_ORACLE_ENABLED")
+            .map(|v| v.to_lowercase() == "true")
+            .unwrap_or(false);
+        if !enabled {
+            return None;
+        }
+
+        let url = std::env::var("PRICE_ORACLE_URL")
+            .ok()
+            .filter(|u| !u.trim().is_empty())
+            .unwrap_or_else(|| DEFAULT_PRICE_URL.to_string());
+        let max_deviation_pct = std::env::var("PRICE_ORACLE_MAX_DEVIATION_PCT")
+            .ok()
+            .and_then(|v| Decimal::from_str(v.trim()).ok())
+            .filter(|pct| *pct > Decimal::ZERO)
+            .unwrap_or_else(|| Decimal::from(DEFAULT_MAX_DEVIATION_PCT));
+        let cache_secs = std::env::var("PRICE_ORACLE_CACHE_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .unwrap_or(DEFAULT_CACHE_SECS);
+
+        tracing::info!("💱 Price oracle enabled ({}, max deviation {}%, cache {}s)", url, max_deviation_pct, cache_secs);
+        Some(Arc::new(Self::new(
+            Arc::new(SpotPriceSource::new(url)),
+            max_deviation_pct,
+            Duration::from_secs(cache_secs),
+        )))
+    }
+
+    /// Warning for an order whose rate is too far from the reference price, if any
+    pub async fn check_rate(&self, token_address: &str, currency: &str, exchange_rate: &str) -> Option<String> {
+        let currency = Currency::from_code_or_default(currency);
+        let reference = self.reference_price(token_address, currency).await?;
+        rate_deviation_warning(exchange_rate, currency, reference, self.max_deviation_pct)
+    }
+
+    /// `check_rate` for a page of orders, given as (token, currency, exchange_rate).
+    /// Each distinct reference price is looked up once for the whole page.
+    pub async fn check_rates(&self, orders: &[(&str, &str, &str)]) -> Vec<Option<String>> {
+        let mut references: HashMap<(String, Currency), Option<Decimal>> = HashMap::new();
+        let mut warnings = Vec::with_capacity(orders.len());
+        for &(token_address, currency, exchange_rate) in orders {
+            let currency = Currency::from_code_or_default(currency);
+            let key = (token_address.to_lowercase(), currency);
+            let reference = match references.get(&key) {
+                Some(reference) => *reference,
+                None => {
+                    let reference = self.reference_price(token_address, currency).await;
+                    references.insert(key, reference);
+                    reference
+                }
+            };
+            warnings.push(reference.and_then(|reference| {
+                rate_deviation_warning(exchange_rate, currency, reference, self.max_deviation_pct)
+            }));
+        }
+        warnings
+    }
+
+    /// Reference price of a registry token in `currency` (None for unknown tokens or source errors)
+    async fn reference_price(&self, token_address: &str, currency: Currency) -> Option<Decimal> {
+        let symbol = reference_symbol(tokens::lookup(token_address)?.symbol);
+        let key = (symbol, currency);
+
+        if let Some((price, fetched_at)) = self.lock().get(&key) {
+            let ttl = match price {
+                Some(_) => self.ttl,
+                None => self.ttl.min(Duration::from_secs(FAILURE_CACHE_SECS)),
+            };
+            if fetched_at.elapsed() < ttl {
+                return *price;
+            }
+        }
+
+        let price = match self.source.spot_price(symbol, currency).await {
+            Ok(price) if price > Decimal::ZERO => Some(price),
+            Ok(price) => {
+                tracing::warn!("💱 Ignoring non-positive reference price {} for {}/{}", price, symbol, currency);
+                None
+            }
+            Err(e) => {
+                tracing::warn!("💱 Reference price for {}/{} unavailable: {}", symbol, currency, e);
+                None
+            }
+        };
+        self.lock().insert(key, (price, Instant::now()));
+        price
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, PriceEntries> {
+        self.prices.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Asset whose spot price stands in for a token (wrapped and bridged variants)
+fn reference_symbol(symbol: &'static str) -> &'static str {
+    match symbol {
+        "USDbC" => "USDC",
+        "WETH" => "ETH",
+        "WBTC" | "cbBTC" => "BTC",
+        other => other,
+    }
+}
+
+/// Warning when `exchange_rate` (minor units of `currency` per token) deviates from
+/// `reference` (major units per token) by more than `max_deviation_pct` percent
+pub fn rate_deviation_warning(
+    exchange_rate: &str,
+    currency: Currency,
+    reference: Decimal,
+    max_deviation_pct: Decimal,
+) -> Option<String> {
+    if reference <= Decimal::ZERO {
+        return None;
+    }
+    let rate = Decimal::from_str(exchange_rate).ok()? / Decimal::from(currency.minor_unit_divisor());
+    let deviation_pct = (rate - reference) / reference * Decimal::ONE_HUNDRED;
+    if deviation_pct.abs() <= max_deviation_pct {
+        return None;
+    }
+
+    let direction = if deviation_pct > Decimal::ZERO { "above" } else { "below" };
+    let digits = currency.decimals() as usize;
+    Some(format!(
+        "Exchange rate {}{:.digits$} is {:.1}% {} the reference price {}{:.digits$}",
+        currency.symbol(),
+        rate,
+        deviation_pct.abs().round_dp(1),
+        direction,
+        currency.symbol(),
+        reference,
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    #[test]
+    fn test_rate_deviation_warning() {
+        let reference = Decimal::from_str("7.20").unwrap();
+        let max = Decimal::from(10);
+
+        // ¥7.15 and ¥7.90 are within 10% of ¥7.20
+        assert_eq!(rate_deviation_warning("715", Currency::Cny, reference, max), None);
+        assert_eq!(rate_deviation_warning("790", Currency::Cny, reference, max), None);
+
+        // ¥72.00 (decimal point slip) is 900% above
+        let warning = rate_deviation_warning("7200", Currency::Cny, reference, max).unwrap();
+        assert_eq!(warning, "Exchange rate ¥72.00 is 900.0% above the reference price ¥7.20");
+
+        // ¥6.00 is 16.7% below
+        let warning = rate_deviation_warning("600", Currency::Cny, reference, max).unwrap();
+        assert!(warning.contains("16.7% below"), "{}", warning);
+
+        // JPY has no minor unit: 150 is ¥150
+        assert_eq!(rate_deviation_warning("150", Currency::Jpy, Decimal::from(149), max), None);
+
+        // Unparseable rates aren't flagged
+        assert_eq!(rate_deviation_warning("abc", Currency::Cny, reference, max), None);
+    }
+
+    struct FixedSource {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PriceSource for FixedSource {
+        async fn spot_price(&self, base: &str, _quote: Currency) -> Result<Decimal, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            assert_eq!(base, "ETH");
+            Ok(Decimal::from(25_000))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_check_rate_caches_reference_price() {
+        let source = Arc::new(FixedSource { calls: AtomicUsize::new(0) });
+        let oracle = PriceOracle::new(source.clone(), Decimal::from(10), Duration::from_secs(60));
+        let weth = "0x4200000000000000000000000000000000000006";
+
+        assert_eq!(oracle.check_rate(weth, "CNY", "2500000").await, None);
+        assert!(oracle.check_rate(weth, "CNY", "250000").await.is_some());
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+
+        // Tokens outside the registry have no reference
+        assert_eq!(oracle.check_rate("0xunknown", "CNY", "1").await, None);
+    }
+
+    struct DownSource {
+        calls: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl PriceSource for DownSource {
+        async fn spot_price(&self, _base: &str, _quote: Currency) -> Result<Decimal, String> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Err("operation timed out".to_string())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_unavailable_source_is_asked_once_per_window() {
+        let source = Arc::new(DownSource { calls: AtomicUsize::new(0) });
+        let oracle = PriceOracle::new(source.clone(), Decimal::from(10), Duration::from_secs(300));
+        let weth = "0x4200000000000000000000000000000000000006";
+
+        // A page of three WETH orders looks the price up once, and gets no warnings
+        let page = [(weth, "CNY", "2500000"), (weth, "CNY", "1"), (weth, "CNY", "99999999")];
+        assert_eq!(oracle.check_rates(&page).await, vec![None, None, None]);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+
+        // The failure is remembered for the next request
+        assert_eq!(oracle.check_rates(&page).await, vec![None, None, None]);
+        assert_eq!(source.calls.load(Ordering::SeqCst), 1);
+
+        // Even with the price cache off, one page is still one lookup
+        let uncached = PriceOracle::new(source.clone(), Decimal::from(10), Duration::ZERO);
+        uncached.check_rates(&page).await;
+        assert_eq!(source.calls.load(Ordering::SeqCst), 2);
+    }
+}
@@ -0,0 +1,124 @@
+//! Payment rails
+//!
+//! The escrow's `PaymentRail` enum is a uint8 on-chain and an INTEGER `rail`
+//! column on orders and trades (0 = Alipay, 1 = WeChat). API responses keep the
+//! integer form; anything else read from the chain or the database is an error,
+//! never silently treated as one of the known rails.
+
+use serde::{Deserialize, Serialize};
+use sqlx::encode::IsNull;
+use sqlx::error::BoxDynError;
+use sqlx::postgres::{PgArgumentBuffer, PgTypeInfo, PgValueRef};
+use sqlx::{Decode, Encode, Postgres, Type};
+use std::fmt;
+
+/// Payment rail of an order (and its trades)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(try_from = "i32", into = "i32")]
+pub enum PaymentRail {
+    #[default]
+    Alipay = 0,
+    WeChat = 1,
+}
+
+impl PaymentRail {
+    /// English name, for logs and the English emails
+    pub fn label(&self) -> &'static str {
+        match self {
+            PaymentRail::Alipay => "Alipay",
+            PaymentRail::WeChat => "WeChat",
+        }
+    }
+
+    /// Name shown to users in `lang` ('en', 'zh-CN', 'zh-TW'; others get English)
+    pub fn display_name(&self, lang: &str) -> &'static str {
+        match (self, lang) {
+            (PaymentRail::Alipay, "zh-CN") => "支付宝",
+            (PaymentRail::Alipay, "zh-TW") => "支付寶",
+            (PaymentRail::WeChat, "zh-CN" | "zh-TW") => "微信",
+            _ => self.label(),
+        }
+    }
+}
+
+impl fmt::Display for PaymentRail {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.label())
+    }
+}
+
+impl TryFrom<i32> for PaymentRail {
+    type Error = String;
+
+    fn try_from(value: i32) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(PaymentRail::Alipay),
+            1 => Ok(PaymentRail::WeChat),
+            other => Err(format!("Unknown payment rail {} (expected 0 = Alipay or 1 = WeChat)", other)),
+        }
+    }
+}
+
+impl TryFrom<u8> for PaymentRail {
+    type Error = String;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        PaymentRail::try_from(i32::from(value))
+    }
+}
+
+impl From<PaymentRail> for i32 {
+    fn from(rail: PaymentRail) -> Self {
+        rail as i32
+    }
+}
+
+// Stored as the INTEGER `rail` column; decoding an unknown value fails the query
+impl Type<Postgres> for PaymentRail {
+    fn type_info() -> PgTypeInfo {
+        <i32 as Type<Postgres>>::type_info()
+    }
+}
+
+impl<'r> Decode<'r, Postgres> for PaymentRail {
+    fn decode(value: PgValueRef<'r>) -> Result<Self, BoxDynError> {
+        let raw = <i32 as Decode<Postgres>>::decode(value)?;
+        Ok(PaymentRail::try_from(raw)?)
+    }
+}
+
+impl Encode<'_, Postgres> for PaymentRail {
+    fn encode_by_ref(&self, buf: &mut PgArgumentBuffer) -> IsNull {
+        <i32 as Encode<Postgres>>::encode_by_ref(&i32::from(*self), buf)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_serde_round_trip_as_integer() {
+        for rail in [PaymentRail::Alipay, PaymentRail::WeChat] {
+            let json = serde_json::to_string(&rail).unwrap();
+            assert_eq!(json, i32::from(rail).to_string());
+            assert_eq!(serde_json::from_str::<PaymentRail>(&json).unwrap(), rail);
+        }
+        assert_eq!(serde_json::to_string(&PaymentRail::WeChat).unwrap(), "1");
+    }
+
+    #[test]
+    fn test_unknown_rail_rejected() {
+        assert!(serde_json::from_str::<PaymentRail>("2").is_err());
+        assert!(serde_json::from_str::<PaymentRail>("-1").is_err());
+        assert!(PaymentRail::try_from(7u8).is_err());
+    }
+
+    #[test]
+    fn test_display_names() {
+        assert_eq!(PaymentRail::WeChat.label(), "WeChat");
+        assert_eq!(PaymentRail::Alipay.display_name("zh-TW"), "支付寶");
+        assert_eq!(PaymentRail::WeChat.display_name("zh-CN"), "微信");
+        assert_eq!(PaymentRail::Alipay.display_name("fr"), "Alipay");
+    }
+}
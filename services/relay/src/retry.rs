@@ -0,0 +1,142 @@
+//! Retry with exponential backoff
+//!
+//! Shared by the blockchain client (contract reads, cancel sends) and the
+//! payment-info hash check. The RPC failover transport already moves a failed
+//! request to the next endpoint; this covers the remaining case of every
+//! endpoint failing for a moment (network blip, provider-wide rate limit).
+//!
+//! Only errors the caller's predicate marks as retryable are retried -
+//! a revert or an invalid argument comes back on the first attempt.
+
+use rand::Rng;
+use std::fmt::Display;
+use std::future::Future;
+use std::time::Duration;
+
+/// How often and how long to retry
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    /// Total attempts, including the first (0 is treated as 1)
+    pub max_attempts: u32,
+    /// Delay after the first failure; doubles after each further failure
+    pub base_delay: Duration,
+    /// Cap on a single delay (before jitter)
+    pub max_delay: Duration,
+    /// Random spread applied to each delay: 0.2 = +/-20% (0 = deterministic)
+    pub jitter: f64,
+}
+
+impl RetryPolicy {
+    pub const fn new(max_attempts: u32, base_delay: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay,
+            max_delay: Duration::from_secs(30),
+            jitter: 0.0,
+        }
+    }
+
+    pub const fn with_max_delay(mut self, max_delay: Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    pub const fn with_jitter(mut self, jitter: f64) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Delay before retry number `retry` (1-based), without jitter:
+    /// base, 2x base, 4x base, ... capped at max_delay
+    pub fn delay(&self, retry: u32) -> Duration {
+        let factor = 1u32 << retry.saturating_sub(1).min(16);
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+
+    /// `delay(retry)` spread by up to +/-jitter
+    fn jittered_delay(&self, retry: u32) -> Duration {
+        let delay = self.delay(retry);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 || delay.is_zero() {
+            return delay;
+        }
+        delay.mul_f64(1.0 + rand::thread_rng().gen_range(-jitter..=jitter))
+    }
+}
+
+/// Run `op` until it succeeds, fails with an error `is_retryable` rejects, or
+/// `policy.max_attempts` is used up; returns the last result
+pub async fn with_backoff<T, E, F, Fut, P>(mut op: F, policy: &RetryPolicy, is_retryable: P) -> Result<T, E>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, E>>,
+    P: Fn(&E) -> bool,
+    E: Display,
+{
+    let max_attempts = policy.max_attempts.max(1);
+    let mut attempt = 1;
+    loop {
+        match op().await {
+            Ok(value) => return Ok(value),
+            Err(e) if attempt < max_attempts && is_retryable(&e) => {
+                let delay = policy.jittered_delay(attempt);
+                tracing::warn!("🔁 Attempt {}/{} failed, retrying in {:?}: {}", attempt, max_attempts, delay, e);
+                tokio::time::sleep(delay).await;
+                attempt += 1;
+            }
+            Err(e) => return Err(e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicU32, Ordering};
+
+    #[test]
+    fn test_backoff_schedule_doubles_and_caps() {
+        let policy = RetryPolicy::new(10, Duration::from_millis(250)).with_max_delay(Duration::from_secs(2));
+        let delays: Vec<u128> = (1..=6).map(|retry| policy.delay(retry).as_millis()).collect();
+        assert_eq!(delays, vec![250, 500, 1000, 2000, 2000, 2000]);
+        assert_eq!(policy.delay(u32::MAX), Duration::from_secs(2));
+
+        let jittered = policy.with_jitter(0.2);
+        for _ in 0..100 {
+            let ms = jittered.jittered_delay(2).as_millis();
+            assert!((400..=600).contains(&ms), "{}ms outside +/-20% of 500ms", ms);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retryable_errors_retried_fatal_errors_returned() {
+        let policy = RetryPolicy::new(4, Duration::ZERO);
+        let calls = AtomicU32::new(0);
+
+        // Transient twice, then success
+        let result = with_backoff(|| async {
+            match calls.fetch_add(1, Ordering::SeqCst) {
+                0 | 1 => Err("timeout"),
+                _ => Ok(7),
+            }
+        }, &policy, |e: &&str| *e == "timeout").await;
+        assert_eq!(result, Ok(7));
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 3);
+
+        // Fatal on the first attempt: not retried
+        let result: Result<(), _> = with_backoff(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err("execution reverted")
+        }, &policy, |e: &&str| *e == "timeout").await;
+        assert_eq!(result, Err("execution reverted"));
+        assert_eq!(calls.swap(0, Ordering::SeqCst), 1);
+
+        // Always transient: gives up after max_attempts with the last error
+        let result: Result<(), _> = with_backoff(|| async {
+            calls.fetch_add(1, Ordering::SeqCst);
+            Err("timeout")
+        }, &policy, |e: &&str| *e == "timeout").await;
+        assert_eq!(result, Err("timeout"));
+        assert_eq!(calls.load(Ordering::SeqCst), 4);
+    }
+}
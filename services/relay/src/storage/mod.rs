@@ -0,0 +1,241 @@
+//! Object storage for trade receipt PDFs
+//!
+//! When PDF_S3_BUCKET is set, uploaded receipts are written to an S3-compatible
+//! bucket under `trades/sha256/{hash}.pdf` and only the key (plus the content
+//! hash and original filename) is kept in Postgres. Identical uploads share one
+//! object, and bytes read back must still match the recorded hash. Receipts
+//! uploaded before content addressing keep their `trades/{chain_id}/{trade_id}.pdf`
+//! key. Without a bucket the bytes stay in `trades.pdf_file`, so local
+//! development needs no extra services.
+//!
+//! Environment:
+//!   PDF_S3_BUCKET          bucket name (unset = store PDFs in the database)
+//!   PDF_S3_REGION          region (default AWS_REGION / us-east-1)
+//!   PDF_S3_ENDPOINT        custom endpoint for MinIO/R2/etc. (path-style addressing)
+//!   PDF_SERVE_MODE         stream | redirect (default stream)
+//!   PDF_PRESIGN_TTL_SECS   lifetime of redirect URLs (default 300)
+//! Credentials come from the standard AWS_ACCESS_KEY_ID / AWS_SECRET_ACCESS_KEY.
+
+mod s3;
+
+use async_trait::async_trait;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+use thiserror::Error;
+
+pub use s3::S3BlobStore;
+
+#[derive(Debug, Error)]
+pub enum StorageError {
+    /// The key does not exist in the bucket
+    #[error("Object not found: {0}")]
+    NotFound(String),
+
+    #[error("Storage backend error: {0}")]
+    Backend(String),
+}
+
+pub type StorageResult<T> = Result<T, StorageError>;
+
+/// Minimal blob store used for receipt PDFs
+#[async_trait]
+pub trait BlobStore: Send + Sync {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> StorageResult<()>;
+
+    /// Whether an object exists under `key`
+    async fn exists(&self, key: &str) -> StorageResult<bool>;
+
+    /// Fetch an object; `StorageError::NotFound` if the key is missing
+    async fn get(&self, key: &str) -> StorageResult<Vec<u8>>;
+
+    /// Time-limited GET URL clients can be redirected to; `StorageError::NotFound` if the key is missing
+    async fn presigned_get_url(&self, key: &str, expires_in: Duration) -> StorageResult<String>;
+}
+
+/// Object key for a trade's receipt (uploads before content addressing)
+pub fn trade_pdf_key(chain_id: i32, trade_id: &str) -> String {
+    format!("trades/{}/{}.pdf", chain_id, trade_id.to_lowercase())
+}
+
+/// Object key for a receipt with the given hex SHA-256
+pub fn content_pdf_key(sha256: &str) -> String {
+    format!("trades/sha256/{}.pdf", sha256.to_lowercase())
+}
+
+/// Store a receipt under its content address, skipping the upload when an
+/// identical receipt is already there; returns the object key
+pub async fn put_pdf_content(store: &dyn BlobStore, data: &[u8], sha256: &str) -> StorageResult<String> {
+    let key = content_pdf_key(sha256);
+    if !store.exists(&key).await? {
+        store.put(&key, data.to_vec(), "application/pdf").await?;
+    }
+    Ok(key)
+}
+
+/// Whether receipt bytes still match the hash recorded at upload (legacy rows have none)
+pub fn matches_content_hash(data: &[u8], sha256: Option<&str>) -> bool {
+    sha256.is_none_or(|expected| crate::crypto::sha256_file(data).eq_ignore_ascii_case(expected))
+}
+
+/// How GET /api/trades/:id/pdf serves objects from the bucket
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PdfServeMode {
+    /// Proxy the bytes through the API
+    #[default]
+    Stream,
+    /// 307 to a presigned bucket URL
+    Redirect,
+}
+
+impl FromStr for PdfServeMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_lowercase().as_str() {
+            "stream" => Ok(PdfServeMode::Stream),
+            "redirect" => Ok(PdfServeMode::Redirect),
+            other => Err(format!("Unknown PDF_SERVE_MODE: {} (expected stream or redirect)", other)),
+        }
+    }
+}
+
+/// Default lifetime of presigned redirect URLs
+const DEFAULT_PRESIGN_TTL_SECS: u64 = 300;
+
+/// Bucket settings (see module docs for the environment variables)
+#[derive(Debug, Clone)]
+pub struct StorageConfig {
+    pub bucket: String,
+    pub region: String,
+    pub endpoint: Option<String>,
+    pub serve_mode: PdfServeMode,
+    pub presign_ttl: Duration,
+}
+
+impl StorageConfig {
+    /// None when PDF_S3_BUCKET is not set (database fallback)
+    pub fn from_env() -> Option<Self> {
+        let bucket = std::env::var("PDF_S3_BUCKET").ok().filter(|b| !b.trim().is_empty())?;
+        let region = std::env::var("PDF_S3_REGION")
+            .or_else(|_| std::env::var("AWS_REGION"))
+            .unwrap_or_else(|_| "us-east-1".to_string());
+        let endpoint = std::env::var("PDF_S3_ENDPOINT").ok().filter(|e| !e.trim().is_empty());
+        let serve_mode = match std::env::var("PDF_SERVE_MODE") {
+            Ok(v) => v.parse().unwrap_or_else(|e| {
+                tracing::warn!("⚠️ {}, falling back to stream", e);
+                PdfServeMode::Stream
+            }),
+            Err(_) => PdfServeMode::Stream,
+        };
+        let presign_ttl_secs = std::env::var("PDF_PRESIGN_TTL_SECS")
+            .ok()
+            .and_then(|v| v.parse().ok())
+            .filter(|&secs: &u64| secs > 0)
+            .unwrap_or(DEFAULT_PRESIGN_TTL_SECS);
+
+        Some(Self {
+            bucket,
+            region,
+            endpoint,
+            serve_mode,
+            presign_ttl: Duration::from_secs(presign_ttl_secs),
+        })
+    }
+}
+
+/// Configured PDF bucket plus how to serve from it (AppState.pdf_storage)
+#[derive(Clone)]
+pub struct PdfStorage {
+    pub store: Arc<dyn BlobStore>,
+    pub serve_mode: PdfServeMode,
+    pub presign_ttl: Duration,
+}
+
+impl PdfStorage {
+    /// Build the S3 store from the environment; None (database fallback) if unset or misconfigured
+    pub fn from_env() -> Option<Self> {
+        let config = StorageConfig::from_env()?;
+        match S3BlobStore::new(&config) {
+            Ok(store) => {
+                tracing::info!("🪣 Trade PDFs stored in bucket {} (serve: {:?})", config.bucket, config.serve_mode);
+                Some(Self {
+                    store: Arc::new(store),
+                    serve_mode: config.serve_mode,
+                    presign_ttl: config.presign_ttl,
+                })
+            }
+            Err(e) => {
+                tracing::error!("❌ PDF bucket misconfigured, storing PDFs in the database: {}", e);
+                None
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Mutex;
+
+    /// In-memory bucket counting uploads
+    #[derive(Default)]
+    struct MemoryBlobStore {
+        objects: Mutex<HashMap<String, Vec<u8>>>,
+        puts: AtomicUsize,
+    }
+
+    #[async_trait]
+    impl BlobStore for MemoryBlobStore {
+        async fn put(&self, key: &str, data: Vec<u8>, _content_type: &str) -> StorageResult<()> {
+            self.puts.fetch_add(1, Ordering::SeqCst);
+            self.objects.lock().unwrap().insert(key.to_string(), data);
+            Ok(())
+        }
+
+        async fn exists(&self, key: &str) -> StorageResult<bool> {
+            Ok(self.objects.lock().unwrap().contains_key(key))
+        }
+
+        async fn get(&self, key: &str) -> StorageResult<Vec<u8>> {
+            self.objects.lock().unwrap().get(key).cloned().ok_or_else(|| StorageError::NotFound(key.to_string()))
+        }
+
+        async fn presigned_get_url(&self, key: &str, _expires_in: Duration) -> StorageResult<String> {
+            Ok(format!("memory://{}", key))
+        }
+    }
+
+    #[test]
+    fn test_trade_pdf_key_layout() {
+        assert_eq!(trade_pdf_key(8453, "0xABCD"), "trades/8453/0xabcd.pdf");
+        assert_eq!(content_pdf_key("BA78"), "trades/sha256/ba78.pdf");
+    }
+
+    #[tokio::test]
+    async fn test_same_receipt_for_two_trades_stored_once() {
+        let store = MemoryBlobStore::default();
+        let pdf = b"%PDF-1.4 receipt %%EOF".to_vec();
+        let sha256 = crate::crypto::sha256_file(&pdf);
+
+        let first = put_pdf_content(&store, &pdf, &sha256).await.unwrap();
+        let second = put_pdf_content(&store, &pdf, &sha256).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(store.objects.lock().unwrap().len(), 1);
+        assert_eq!(store.puts.load(Ordering::SeqCst), 1);
+
+        let stored = store.get(&first).await.unwrap();
+        assert!(matches_content_hash(&stored, Some(&sha256)));
+        assert!(!matches_content_hash(b"%PDF-1.4 tampered %%EOF", Some(&sha256)));
+        assert!(matches_content_hash(&stored, None));
+    }
+
+    #[test]
+    fn test_parse_serve_mode() {
+        assert_eq!("Redirect".parse::<PdfServeMode>().unwrap(), PdfServeMode::Redirect);
+        assert_eq!(" stream ".parse::<PdfServeMode>().unwrap(), PdfServeMode::Stream);
+        assert!("proxy".parse::<PdfServeMode>().is_err());
+    }
+}
@@ -0,0 +1,78 @@
+//! S3-compatible BlobStore (AWS S3, MinIO, R2, ...)
+
+use async_trait::async_trait;
+use axum::http::Method;
+use object_store::aws::{AmazonS3, AmazonS3Builder};
+use object_store::path::Path;
+use object_store::signer::Signer;
+use object_store::{Attribute, Attributes, ObjectStore, PutOptions};
+use std::time::Duration;
+
+use super::{BlobStore, StorageConfig, StorageError, StorageResult};
+
+#[derive(Debug)]
+pub struct S3BlobStore {
+    s3: AmazonS3,
+}
+
+impl S3BlobStore {
+    /// Build a client for the configured bucket (credentials from AWS_* env vars)
+    pub fn new(config: &StorageConfig) -> StorageResult<Self> {
+        let mut builder = AmazonS3Builder::from_env()
+            .with_bucket_name(&config.bucket)
+            .with_region(&config.region);
+        if let Some(endpoint) = &config.endpoint {
+            // Self-hosted endpoints use path-style URLs and are often plain http in dev
+            builder = builder
+                .with_endpoint(endpoint)
+                .with_virtual_hosted_style_request(false)
+                .with_allow_http(endpoint.starts_with("http://"));
+        }
+        let s3 = builder.build().map_err(|e| StorageError::Backend(e.to_string()))?;
+        Ok(Self { s3 })
+    }
+}
+
+fn map_err(key: &str, e: object_store::Error) -> StorageError {
+    match e {
+        object_store::Error::NotFound { .. } => StorageError::NotFound(key.to_string()),
+        other => StorageError::Backend(other.to_string()),
+    }
+}
+
+#[async_trait]
+impl BlobStore for S3BlobStore {
+    async fn put(&self, key: &str, data: Vec<u8>, content_type: &str) -> StorageResult<()> {
+        let attributes = Attributes::from_iter([(Attribute::ContentType, content_type.to_string())]);
+        self.s3
+            .put_opts(&Path::from(key), data.into(), PutOptions::from(attributes))
+            .await
+            .map_err(|e| map_err(key, e))?;
+        Ok(())
+    }
+
+    async fn exists(&self, key: &str) -> StorageResult<bool> {
+        match self.s3.head(&Path::from(key)).await {
+            Ok(_) => Ok(true),
+            Err(object_store::Error::NotFound { .. }) => Ok(false),
+            Err(e) => Err(map_err(key, e)),
+        }
+    }
+
+    async fn get(&self, key: &str) -> StorageResult<Vec<u8>> {
+        let result = self.s3.get(&Path::from(key)).await.map_err(|e| map_err(key, e))?;
+        let bytes = result.bytes().await.map_err(|e| map_err(key, e))?;
+        Ok(bytes.to_vec())
+    }
+
+    async fn presigned_get_url(&self, key: &str, expires_in: Duration) -> StorageResult<String> {
+        let path = Path::from(key);
+        // HEAD first so a missing object surfaces as NotFound instead of a dead link
+        self.s3.head(&path).await.map_err(|e| map_err(key, e))?;
+        let url = self.s3
+            .signed_url(Method::GET, &path, expires_in)
+            .await
+            .map_err(|e| map_err(key, e))?;
+        Ok(url.to_string())
+    }
+}
@@ -0,0 +1,70 @@
+//! Tracing subscriber setup shared by both binaries
+//!
+//! LOG_FORMAT=json switches to one JSON object per line for log aggregators;
+//! anything else (or unset) keeps the human-readable format for local dev.
+//! Filtering still comes from RUST_LOG, defaulting to `info,lyncz_relay=debug`.
+//!
+//! Correlation fields (chain_id, trade_id, order_id) are attached through spans,
+//! so in JSON mode they show up as structured fields on every event inside them.
+
+use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt, EnvFilter};
+
+/// Default filter when RUST_LOG is unset
+const DEFAULT_FILTER: &str = "info,lyncz_relay=debug";
+
+/// Log output format (LOG_FORMAT)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LogFormat {
+    /// Human-readable lines (default)
+    #[default]
+    Pretty,
+    /// Newline-delimited JSON with span fields flattened in
+    Json,
+}
+
+impl LogFormat {
+    /// Parse a LOG_FORMAT value; unknown values fall back to pretty
+    pub fn parse(value: &str) -> Self {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "json" => LogFormat::Json,
+            _ => LogFormat::Pretty,
+        }
+    }
+
+    pub fn from_env() -> Self {
+        std::env::var("LOG_FORMAT").map(|v| Self::parse(&v)).unwrap_or_default()
+    }
+}
+
+/// Install the global subscriber. Call once, first thing in `main`.
+pub fn init() {
+    let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| DEFAULT_FILTER.into());
+    let registry = tracing_subscriber::registry().with(filter);
+
+    match LogFormat::from_env() {
+        LogFormat::Json => registry
+            .with(
+                tracing_subscriber::fmt::layer()
+                    .json()
+                    .flatten_event(true)
+                    .with_current_span(false)
+                    .with_span_list(true),
+            )
+            .init(),
+        LogFormat::Pretty => registry.with(tracing_subscriber::fmt::layer()).init(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_log_format() {
+        assert_eq!(LogFormat::parse("json"), LogFormat::Json);
+        assert_eq!(LogFormat::parse(" JSON "), LogFormat::Json);
+        assert_eq!(LogFormat::parse("pretty"), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse(""), LogFormat::Pretty);
+        assert_eq!(LogFormat::parse("yaml"), LogFormat::Pretty);
+    }
+}
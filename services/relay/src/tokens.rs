@@ -0,0 +1,79 @@
+//! Supported ERC20 tokens per chain
+//!
+//! Single source for token symbols and decimals used when formatting amounts,
+//! also served to frontends by GET /api/tokens so token selectors don't keep
+//! their own copy. Addresses are lowercase.
+
+use serde::Serialize;
+
+/// One supported token on one chain
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, utoipa::ToSchema)]
+pub struct TokenInfo {
+    pub chain_id: u64,
+    pub address: &'static str,
+    pub symbol: &'static str,
+    pub decimals: u8,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub logo_url: Option<&'static str>,
+}
+
+const fn token(chain_id: u64, address: &'static str, symbol: &'static str, decimals: u8) -> TokenInfo {
+    TokenInfo { chain_id, address, symbol, decimals, logo_url: None }
+}
+
+/// Registry of supported tokens (Base Mainnet + Ethereum Mainnet)
+pub const TOKENS: &[TokenInfo] = &[
+    // Base Mainnet
+    token(8453, "0x833589fcd6edb6e08f4c7c32d4f71b54bda02913", "USDC", 6),
+    token(8453, "0xd9aaec86b65d86f6a7b5b1b0c42ffa531710b6ca", "USDbC", 6),
+    token(8453, "0x50c5725949a6f0c72e6c4a641f24049a917db0cb", "DAI", 18),
+    token(8453, "0x4200000000000000000000000000000000000006", "WETH", 18),
+    token(8453, "0xcbb7c0000ab88b473b1f5afd9ef808440eed33bf", "cbBTC", 8),
+    // Ethereum Mainnet
+    token(1, "0xa0b86991c6218b36c1d19d4a2e9eb0ce3606eb48", "USDC", 6),
+    token(1, "0xdac17f958d2ee523a2206206994597c13d831ec7", "USDT", 6),
+    token(1, "0xc02aaa39b223fe8d0a0e5c4f27ead9083c756cc2", "WETH", 18),
+    token(1, "0x2260fac5e5542a773aa44fbcfedf7c193bc2c599", "WBTC", 8),
+];
+
+/// Symbol shown for tokens missing from the registry
+pub const UNKNOWN_SYMBOL: &str = "TOKEN";
+
+/// Decimals assumed for tokens missing from the registry (ERC20 default)
+pub const UNKNOWN_DECIMALS: u8 = 18;
+
+/// Look up a token by address (any case); addresses are unique across chains
+pub fn lookup(address: &str) -> Option<&'static TokenInfo> {
+    let address = address.to_lowercase();
+    TOKENS.iter().find(|t| t.address == address)
+}
+
+/// Registry entries for one chain
+pub fn tokens_for_chain(chain_id: u64) -> impl Iterator<Item = &'static TokenInfo> {
+    TOKENS.iter().filter(move |t| t.chain_id == chain_id)
+}
+
+/// (symbol, decimals) for an address, with ERC20 defaults for unknown tokens
+pub fn symbol_and_decimals(address: &str) -> (&'static str, u8) {
+    lookup(address).map_or((UNKNOWN_SYMBOL, UNKNOWN_DECIMALS), |t| (t.symbol, t.decimals))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lookup_is_case_insensitive() {
+        let usdc = lookup("0x833589fCD6eDb6E08f4c7C32D4f71b54bdA02913").unwrap();
+        assert_eq!((usdc.symbol, usdc.decimals, usdc.chain_id), ("USDC", 6, 8453));
+        assert_eq!(symbol_and_decimals("0xdead"), (UNKNOWN_SYMBOL, UNKNOWN_DECIMALS));
+    }
+
+    #[test]
+    fn test_registry_addresses_are_lowercase_and_unique() {
+        for (i, t) in TOKENS.iter().enumerate() {
+            assert_eq!(t.address, t.address.to_lowercase());
+            assert!(TOKENS[i + 1..].iter().all(|other| other.address != t.address));
+        }
+    }
+}
@@ -0,0 +1,318 @@
+//! Outbound webhooks for trade status transitions and finalized orders
+//!
+//! Integrators register URLs (WEBHOOK_URLS, comma-separated) and a shared secret
+//! (WEBHOOK_SECRET). When a trade is settled or expired, or an order becomes
+//! tradable (payment info and visibility both set), a JSON payload is POSTed
+//! to every URL with an `X-LyncZ-Signature: sha256=<hex>` header containing the
+//! HMAC-SHA256 of the raw body, so receivers can verify authenticity.
+//!
+//! Delivery is fire-and-forget with exponential backoff; payloads that still fail
+//! after the last attempt are written to the dead-letter log.
+
+use hmac::{Hmac, Mac};
+use reqwest::Client;
+use serde::Serialize;
+use sha2::Sha256;
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::db::models::{DbOrder, DbTrade};
+use crate::rail::PaymentRail;
+
+/// Delivery attempts per endpoint before dead-lettering
+const MAX_DELIVERY_ATTEMPTS: u32 = 5;
+
+/// Backoff between attempts: 2s, 4s, 8s, 16s
+const RETRY_BASE_DELAY_SECS: u64 = 2;
+
+/// Per-request timeout
+const REQUEST_TIMEOUT_SECS: u64 = 10;
+
+/// Header carrying the body signature
+pub const SIGNATURE_HEADER: &str = "X-LyncZ-Signature";
+
+/// Trade status reported in webhook payloads
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum WebhookTradeStatus {
+    Settled,
+    Expired,
+}
+
+/// JSON body POSTed to webhook endpoints
+#[derive(Debug, Clone, Serialize)]
+pub struct TradeWebhookPayload {
+    /// "trade.settled" or "trade.expired"
+    pub event: String,
+    pub trade_id: String,
+    pub order_id: String,
+    pub status: WebhookTradeStatus,
+    pub chain_id: i32,
+    pub token_amount: String,
+    pub fee_amount: Option<String>,
+    pub cny_amount: String,
+    /// ISO 4217 code of cny_amount
+    pub currency: String,
+    pub tx_hash: Option<String>,
+    /// Unix timestamp when the webhook was generated
+    pub timestamp: i64,
+}
+
+impl TradeWebhookPayload {
+    pub fn from_trade(trade: &DbTrade, status: WebhookTradeStatus, tx_hash: Option<String>) -> Self {
+        let event = match status {
+            WebhookTradeStatus::Settled => "trade.settled",
+            WebhookTradeStatus::Expired => "trade.expired",
+        };
+        Self {
+            event: event.to_string(),
+            trade_id: trade.trade_id.clone(),
+            order_id: trade.order_id.clone(),
+            status,
+            chain_id: trade.chain_id,
+            token_amount: trade.token_amount.clone(),
+            fee_amount: trade.fee_amount.clone(),
+            cny_amount: trade.cny_amount.clone(),
+            currency: trade.currency.clone(),
+            tx_hash,
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// JSON body of the `order.ready` event: the order is fully populated (payment
+/// info submitted, visibility chosen) and can be traded. Sent once per order,
+/// when the seller first chooses its visibility
+#[derive(Debug, Clone, Serialize)]
+pub struct OrderWebhookPayload {
+    /// Always "order.ready"
+    pub event: String,
+    pub order_id: String,
+    pub chain_id: i32,
+    pub seller: String,
+    pub token: String,
+    pub total_amount: String,
+    pub remaining_amount: String,
+    pub exchange_rate: String,
+    /// 0 = Alipay, 1 = WeChat (same as the API)
+    pub rail: PaymentRail,
+    /// ISO 4217 code the exchange rate is quoted in
+    pub currency: String,
+    pub is_public: bool,
+    /// Unix timestamp when the webhook was generated
+    pub timestamp: i64,
+}
+
+impl OrderWebhookPayload {
+    pub fn ready(order: &DbOrder) -> Self {
+        Self {
+            event: "order.ready".to_string(),
+            order_id: order.order_id.clone(),
+            chain_id: order.chain_id,
+            seller: order.seller.clone(),
+            token: order.token.clone(),
+            total_amount: order.total_amount.clone(),
+            remaining_amount: order.remaining_amount.clone(),
+            exchange_rate: order.exchange_rate.clone(),
+            rail: order.rail,
+            currency: order.currency.clone(),
+            is_public: order.is_public,
+            timestamp: chrono::Utc::now().timestamp(),
+        }
+    }
+}
+
+/// Webhook configuration
+#[derive(Debug, Clone)]
+pub struct WebhookConfig {
+    pub urls: Vec<String>,
+    pub secret: String,
+}
+
+impl WebhookConfig {
+    /// Load from WEBHOOK_URLS (comma-separated) and WEBHOOK_SECRET.
+    /// Returns None (webhooks disabled) unless both are set.
+    pub fn from_env() -> Option<Self> {
+        let urls: Vec<String> = std::env::var("WEBHOOK_URLS")
+            .ok()?
+            .split(',')
+            .map(|u| u.trim().to_string())
+            .filter(|u| !u.is_empty())
+            .collect();
+        if urls.is_empty() {
+            return None;
+        }
+
+        let secret = match std::env::var("WEBHOOK_SECRET") {
+            Ok(secret) if !secret.is_empty() => secret,
+            _ => {
+                tracing::warn!("🪝 WEBHOOK_URLS set but WEBHOOK_SECRET missing - webhooks disabled");
+                return None;
+            }
+        };
+
+        Some(Self { urls, secret })
+    }
+}
+
+/// Sends signed webhook payloads to configured endpoints
+pub struct WebhookSender {
+    client: Client,
+    config: WebhookConfig,
+}
+
+impl WebhookSender {
+    pub fn new(config: WebhookConfig) -> Self {
+        let client = Client::builder()
+            .timeout(Duration::from_secs(REQUEST_TIMEOUT_SECS))
+            .build()
+            .unwrap_or_default();
+        Self { client, config }
+    }
+
+    /// Create from environment variables
+    pub fn from_env() -> Option<Arc<Self>> {
+        let sender = WebhookConfig::from_env().map(|config| Arc::new(Self::new(config)));
+        if let Some(ref sender) = sender {
+            tracing::info!("🪝 Webhooks enabled ({} endpoint(s))", sender.config.urls.len());
+        }
+        sender
+    }
+
+    /// Deliver a payload to every endpoint in the background (doesn't block the caller)
+    pub fn dispatch<P: Serialize>(self: &Arc<Self>, payload: P) {
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!("🪝 Failed to serialize webhook payload: {}", e);
+                return;
+            }
+        };
+        let signature = sign_payload(&self.config.secret, body.as_bytes());
+
+        for url in &self.config.urls {
+            let sender = self.clone();
+            let url = url.clone();
+            let body = body.clone();
+            let signature = signature.clone();
+            tokio::spawn(async move {
+                sender.deliver(&url, &body, &signature).await;
+            });
+        }
+    }
+
+    /// POST with retries; dead-letter the payload if every attempt fails
+    async fn deliver(&self, url: &str, body: &str, signature: &str) {
+        let mut last_error = String::new();
+
+        for attempt in 1..=MAX_DELIVERY_ATTEMPTS {
+            let result = self.client
+                .post(url)
+                .header("Content-Type", "application/json")
+                .header(SIGNATURE_HEADER, signature)
+                .body(body.to_string())
+                .send()
+                .await;
+
+            match result {
+                Ok(response) if response.status().is_success() => {
+                    tracing::info!("🪝 Webhook delivered to {} (attempt {})", url, attempt);
+                    return;
+                }
+                Ok(response) => last_error = format!("HTTP {}", response.status()),
+                Err(e) => last_error = e.to_string(),
+            }
+
+            if attempt < MAX_DELIVERY_ATTEMPTS {
+                let delay = RETRY_BASE_DELAY_SECS << (attempt - 1);
+                tracing::warn!(
+                    "🪝 Webhook to {} failed (attempt {}/{}): {} - retrying in {}s",
+                    url, attempt, MAX_DELIVERY_ATTEMPTS, last_error, delay
+                );
+                tokio::time::sleep(Duration::from_secs(delay)).await;
+            }
+        }
+
+        tracing::error!(
+            target: "lyncz_relay::webhooks::dead_letter",
+            url = url,
+            error = %last_error,
+            payload = body,
+            "🪦 Webhook dead-lettered after {} attempts",
+            MAX_DELIVERY_ATTEMPTS
+        );
+    }
+}
+
+/// HMAC-SHA256 signature of a body, formatted as `sha256=<hex>`
+pub fn sign_payload(secret: &str, body: &[u8]) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .expect("HMAC accepts keys of any length");
+    mac.update(body);
+    format!("sha256={}", hex::encode(mac.finalize().into_bytes()))
+}
+
+/// A sender whose one endpoint is a local server that forwards every body it
+/// receives to the returned channel
+#[cfg(test)]
+pub(crate) async fn test_sender() -> (Arc<WebhookSender>, tokio::sync::mpsc::UnboundedReceiver<serde_json::Value>) {
+    use axum::{routing::post, Router};
+
+    let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
+    let app = Router::new().route(
+        "/hook",
+        post(move |body: String| async move {
+            let _ = tx.send(serde_json::from_str::<serde_json::Value>(&body).unwrap());
+        }),
+    );
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+
+    let sender = Arc::new(WebhookSender::new(WebhookConfig {
+        urls: vec![format!("http://{}/hook", addr)],
+        secret: "secret".to_string(),
+    }));
+    (sender, rx)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sign_payload_rfc4231_vector() {
+        // RFC 4231 test case 2
+        let signature = sign_payload("Jefe", b"what do ya want for nothing?");
+        assert_eq!(
+            signature,
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_order_ready_payload() {
+        let (sender, mut received) = test_sender().await;
+        let order = DbOrder {
+            rail: PaymentRail::WeChat,
+            is_public: false,
+            private_code: Some("123456".to_string()),
+            ..crate::db::models::fixtures::order("0xorder")
+        };
+        sender.dispatch(OrderWebhookPayload::ready(&order));
+
+        let body = received.recv().await.unwrap();
+        assert_eq!(body["event"], "order.ready");
+        assert_eq!(body["order_id"], "0xorder");
+        assert_eq!(body["rail"], 1);
+        assert_eq!(body["is_public"], false);
+        // The private code never leaves the relay
+        assert!(body.get("private_code").is_none());
+    }
+
+    #[test]
+    fn test_status_serializes_lowercase() {
+        assert_eq!(serde_json::to_string(&WebhookTradeStatus::Settled).unwrap(), "\"settled\"");
+        assert_eq!(serde_json::to_string(&WebhookTradeStatus::Expired).unwrap(), "\"expired\"");
+    }
+}